@@ -0,0 +1,466 @@
+//! LLM backends for observation compression
+//!
+//! Each backend turns a prompt into the model's reply. `Compressor` tries the
+//! configured backends in order and falls back to plain truncation when none
+//! of them answers with usable JSON.
+
+use crate::compressor::{CompressResult, build_compression_prompt, fallback_compress};
+use attentive_core::{BackendKind, CompressionBackendConfig, Redactor};
+use std::collections::HashMap;
+use std::future::Future;
+use std::path::Path;
+use std::pin::Pin;
+
+/// Reply text from a backend
+pub type BackendFuture<'a> = Pin<Box<dyn Future<Output = anyhow::Result<String>> + Send + 'a>>;
+
+/// A chat model that can summarize tool output
+pub trait CompressionBackend: Send + Sync {
+    /// Name shown in errors, e.g. "anthropic"
+    fn name(&self) -> &str;
+
+    /// The model's reply to a single user message
+    fn complete<'a>(&'a self, prompt: &'a str) -> BackendFuture<'a>;
+}
+
+const MAX_REPLY_TOKENS: u32 = 1024;
+
+/// API keys by backend name, from `attentive_credentials.json`
+#[derive(Debug, Clone, Default)]
+pub struct Credentials {
+    keys: HashMap<String, String>,
+}
+
+impl Credentials {
+    /// Keys from `path`; a missing or malformed file has none
+    pub fn load(path: &Path) -> Self {
+        let keys = std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default();
+        Self { keys }
+    }
+
+    /// The key for `spec`: its `api_key_env` variable, then the backend's
+    /// usual variable, then the credentials file
+    pub fn api_key(&self, spec: &CompressionBackendConfig) -> Option<String> {
+        self.api_key_from(spec, |var| std::env::var(var).ok())
+    }
+
+    fn api_key_from(
+        &self,
+        spec: &CompressionBackendConfig,
+        env: impl Fn(&str) -> Option<String>,
+    ) -> Option<String> {
+        let default_env = match spec.backend {
+            BackendKind::Anthropic => Some("ANTHROPIC_API_KEY"),
+            BackendKind::Openai => Some("OPENAI_API_KEY"),
+            BackendKind::Ollama => None,
+        };
+        spec.api_key_env
+            .as_deref()
+            .into_iter()
+            .chain(default_env)
+            .find_map(|var| env(var).filter(|k| !k.is_empty()))
+            .or_else(|| self.keys.get(spec.backend.as_str()).cloned())
+    }
+}
+
+/// POST `body` as JSON and return the response JSON, failing on non-2xx
+async fn post_json(
+    request: reqwest::RequestBuilder,
+    body: &serde_json::Value,
+) -> anyhow::Result<serde_json::Value> {
+    let response = request.json(body).send().await?;
+    let status = response.status();
+    if !status.is_success() {
+        let text = response.text().await.unwrap_or_default();
+        anyhow::bail!(
+            "HTTP {}: {}",
+            status,
+            text.chars().take(200).collect::<String>()
+        );
+    }
+    Ok(response.json().await?)
+}
+
+fn reply_text(body: &serde_json::Value, pointer: &str) -> anyhow::Result<String> {
+    body.pointer(pointer)
+        .and_then(|v| v.as_str())
+        .map(String::from)
+        .ok_or_else(|| anyhow::anyhow!("response has no {}", pointer))
+}
+
+/// Anthropic Messages API
+pub struct AnthropicBackend {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl AnthropicBackend {
+    pub const DEFAULT_URL: &str = "https://api.anthropic.com";
+    pub const DEFAULT_MODEL: &str = "claude-3-haiku-20240307";
+
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+
+    fn body(&self, prompt: &str) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "max_tokens": MAX_REPLY_TOKENS,
+            "messages": [{"role": "user", "content": prompt}]
+        })
+    }
+}
+
+impl CompressionBackend for AnthropicBackend {
+    fn name(&self) -> &str {
+        "anthropic"
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let Some(api_key) = &self.api_key else {
+                anyhow::bail!(
+                    "no API key (set ANTHROPIC_API_KEY or add it to the credentials file)"
+                );
+            };
+            let request = self
+                .client
+                .post(format!("{}/v1/messages", self.base_url))
+                .header("x-api-key", api_key)
+                .header("anthropic-version", "2023-06-01");
+            let body = post_json(request, &self.body(prompt)).await?;
+            reply_text(&body, "/content/0/text")
+        })
+    }
+}
+
+/// OpenAI-compatible chat completions (OpenAI, vLLM, LM Studio, ...).
+/// The key is optional since local servers usually don't check one.
+pub struct OpenAiBackend {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+    api_key: Option<String>,
+}
+
+impl OpenAiBackend {
+    pub const DEFAULT_URL: &str = "https://api.openai.com/v1";
+    pub const DEFAULT_MODEL: &str = "gpt-4o-mini";
+
+    pub fn new(base_url: &str, model: &str, api_key: Option<String>) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+            api_key,
+        }
+    }
+
+    fn body(&self, prompt: &str) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "max_tokens": MAX_REPLY_TOKENS,
+            "messages": [{"role": "user", "content": prompt}]
+        })
+    }
+}
+
+impl CompressionBackend for OpenAiBackend {
+    fn name(&self) -> &str {
+        "openai"
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let mut request = self
+                .client
+                .post(format!("{}/chat/completions", self.base_url));
+            if let Some(api_key) = &self.api_key {
+                request = request.bearer_auth(api_key);
+            }
+            let body = post_json(request, &self.body(prompt)).await?;
+            reply_text(&body, "/choices/0/message/content")
+        })
+    }
+}
+
+/// A local Ollama server
+pub struct OllamaBackend {
+    client: reqwest::Client,
+    base_url: String,
+    model: String,
+}
+
+impl OllamaBackend {
+    pub const DEFAULT_URL: &str = "http://localhost:11434";
+    pub const DEFAULT_MODEL: &str = "llama3.2";
+
+    pub fn new(base_url: &str, model: &str) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            base_url: base_url.trim_end_matches('/').to_string(),
+            model: model.to_string(),
+        }
+    }
+
+    fn body(&self, prompt: &str) -> serde_json::Value {
+        serde_json::json!({
+            "model": self.model,
+            "stream": false,
+            "messages": [{"role": "user", "content": prompt}]
+        })
+    }
+}
+
+impl CompressionBackend for OllamaBackend {
+    fn name(&self) -> &str {
+        "ollama"
+    }
+
+    fn complete<'a>(&'a self, prompt: &'a str) -> BackendFuture<'a> {
+        Box::pin(async move {
+            let request = self.client.post(format!("{}/api/chat", self.base_url));
+            let body = post_json(request, &self.body(prompt)).await?;
+            reply_text(&body, "/message/content")
+        })
+    }
+}
+
+/// Backend for one `compression` entry
+pub fn backend_from_config(
+    spec: &CompressionBackendConfig,
+    credentials: &Credentials,
+) -> Box<dyn CompressionBackend> {
+    let url = |default: &str| spec.base_url.clone().unwrap_or_else(|| default.to_string());
+    let model = |default: &str| spec.model.clone().unwrap_or_else(|| default.to_string());
+    match spec.backend {
+        BackendKind::Anthropic => Box::new(AnthropicBackend::new(
+            &url(AnthropicBackend::DEFAULT_URL),
+            &model(AnthropicBackend::DEFAULT_MODEL),
+            credentials.api_key(spec),
+        )),
+        BackendKind::Openai => Box::new(OpenAiBackend::new(
+            &url(OpenAiBackend::DEFAULT_URL),
+            &model(OpenAiBackend::DEFAULT_MODEL),
+            credentials.api_key(spec),
+        )),
+        BackendKind::Ollama => Box::new(OllamaBackend::new(
+            &url(OllamaBackend::DEFAULT_URL),
+            &model(OllamaBackend::DEFAULT_MODEL),
+        )),
+    }
+}
+
+/// Summary and key facts from a model reply, if it is the JSON we asked for
+pub(crate) fn parse_reply(reply: &str, output: &str) -> Option<CompressResult> {
+    // Models often wrap JSON in a code fence
+    let json = reply
+        .trim()
+        .trim_start_matches("```json")
+        .trim_start_matches("```")
+        .trim_end_matches("```");
+    let parsed: serde_json::Value = serde_json::from_str(json.trim()).ok()?;
+    let summary = parsed["summary"].as_str()?.to_string();
+    let key_facts = parsed["key_facts"]
+        .as_array()
+        .map(|a| {
+            a.iter()
+                .filter_map(|v| v.as_str().map(String::from))
+                .collect()
+        })
+        .unwrap_or_default();
+    Some(CompressResult {
+        raw_tokens: output.len() / 4,
+        compressed_tokens: summary.len() / 4,
+        summary,
+        key_facts,
+    })
+}
+
+/// Compresses tool output with the first backend that gives a usable reply
+pub struct Compressor {
+    backends: Vec<Box<dyn CompressionBackend>>,
+    redactor: Redactor,
+}
+
+impl Compressor {
+    pub fn new(backends: Vec<Box<dyn CompressionBackend>>) -> Self {
+        Self {
+            backends,
+            redactor: Redactor::default(),
+        }
+    }
+
+    /// Redact tool output with `redactor` before it is sent to a backend
+    /// (the built-in secret patterns by default)
+    pub fn with_redactor(mut self, redactor: Redactor) -> Self {
+        self.redactor = redactor;
+        self
+    }
+
+    /// Backends from the `compression` config, in the configured order
+    pub fn from_config(specs: &[CompressionBackendConfig], credentials: &Credentials) -> Self {
+        Self::new(
+            specs
+                .iter()
+                .map(|spec| backend_from_config(spec, credentials))
+                .collect(),
+        )
+    }
+
+    /// Summary from the first backend that succeeds, or a truncation of the
+    /// output if none does. Errors from skipped backends are returned with it.
+    pub async fn compress(&self, tool_name: &str, output: &str) -> (CompressResult, Vec<String>) {
+        let (output, _) = self.redactor.redact(output);
+        let output = output.as_ref();
+        let prompt = build_compression_prompt(tool_name, output);
+        let mut errors = Vec::new();
+        for backend in &self.backends {
+            match backend.complete(&prompt).await {
+                Ok(reply) => match parse_reply(&reply, output) {
+                    Some(result) => return (result, errors),
+                    None => errors.push(format!(
+                        "{}: reply was not the expected JSON",
+                        backend.name()
+                    )),
+                },
+                Err(e) => errors.push(format!("{}: {}", backend.name(), e)),
+            }
+        }
+        (fallback_compress(tool_name, output, &self.redactor), errors)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Replies from a fixed list, or fails when given none
+    struct Scripted {
+        name: &'static str,
+        reply: Option<&'static str>,
+    }
+
+    impl CompressionBackend for Scripted {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn complete<'a>(&'a self, _prompt: &'a str) -> BackendFuture<'a> {
+            Box::pin(async move {
+                self.reply
+                    .map(String::from)
+                    .ok_or_else(|| anyhow::anyhow!("connection refused"))
+            })
+        }
+    }
+
+    fn scripted(name: &'static str, reply: Option<&'static str>) -> Box<dyn CompressionBackend> {
+        Box::new(Scripted { name, reply })
+    }
+
+    #[tokio::test]
+    async fn test_compressor_falls_back_in_order() {
+        let compressor = Compressor::new(vec![
+            scripted("down", None),
+            scripted("chatty", Some("Sure! Here is a summary.")),
+            scripted(
+                "good",
+                Some(
+                    "```json\n{\"summary\": \"Reads config\", \"key_facts\": [\"uses serde\"]}\n```",
+                ),
+            ),
+            scripted("unused", Some("{\"summary\": \"never\"}")),
+        ]);
+        let (result, errors) = compressor.compress("Read", "fn load() {}").await;
+        assert_eq!(result.summary, "Reads config");
+        assert_eq!(result.key_facts, ["uses serde"]);
+        assert_eq!(
+            errors,
+            [
+                "down: connection refused",
+                "chatty: reply was not the expected JSON"
+            ]
+        );
+
+        let (result, errors) = Compressor::new(vec![scripted("down", None)])
+            .compress("Read", "fn load() {}")
+            .await;
+        assert_eq!(result.summary, "[Read] fn load() {}");
+        assert_eq!(errors.len(), 1);
+
+        // The fallback keeps to the compressor's redactor
+        let secret = "export API_KEY=sk-abcdefghijklmnopqrstuvwx";
+        let (result, _) = Compressor::new(vec![scripted("down", None)])
+            .with_redactor(Redactor::disabled())
+            .compress("Bash", secret)
+            .await;
+        assert_eq!(result.summary, format!("[Bash] {}", secret));
+    }
+
+    #[test]
+    fn test_backends_from_config() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attentive_credentials.json");
+        std::fs::write(&path, r#"{"openai": "sk-file"}"#).unwrap();
+        let credentials = Credentials::load(&path);
+        let spec = |backend| CompressionBackendConfig {
+            backend,
+            model: None,
+            base_url: None,
+            api_key_env: Some("TEAM_OPENAI_KEY".to_string()),
+        };
+        let openai = spec(BackendKind::Openai);
+        let no_env = |_: &str| None;
+        assert_eq!(
+            credentials.api_key_from(&openai, no_env).as_deref(),
+            Some("sk-file")
+        );
+        let env = |var: &str| (var == "TEAM_OPENAI_KEY").then(|| "sk-env".to_string());
+        assert_eq!(
+            credentials.api_key_from(&openai, env).as_deref(),
+            Some("sk-env")
+        );
+        let env = |var: &str| (var == "OPENAI_API_KEY").then(|| "sk-default".to_string());
+        assert_eq!(
+            credentials.api_key_from(&openai, env).as_deref(),
+            Some("sk-default")
+        );
+        assert_eq!(
+            credentials.api_key_from(&spec(BackendKind::Ollama), no_env),
+            None
+        );
+
+        let compressor = Compressor::from_config(
+            &[
+                spec(BackendKind::Ollama),
+                openai,
+                spec(BackendKind::Anthropic),
+            ],
+            &credentials,
+        );
+        let names: Vec<&str> = compressor.backends.iter().map(|b| b.name()).collect();
+        assert_eq!(names, ["ollama", "openai", "anthropic"]);
+    }
+
+    #[test]
+    fn test_request_bodies() {
+        let ollama = OllamaBackend::new("http://localhost:11434/", "llama3.2");
+        assert_eq!(ollama.base_url, "http://localhost:11434");
+        assert_eq!(ollama.body("hi")["stream"], false);
+        let openai = OpenAiBackend::new(OpenAiBackend::DEFAULT_URL, "gpt-4o-mini", None);
+        assert_eq!(openai.body("hi")["messages"][0]["content"], "hi");
+        let anthropic = AnthropicBackend::new(AnthropicBackend::DEFAULT_URL, "m", None);
+        assert_eq!(anthropic.body("hi")["max_tokens"], MAX_REPLY_TOKENS);
+    }
+}
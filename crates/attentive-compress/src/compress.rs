@@ -1,4 +1,9 @@
-pub fn fallback_compress(content: &str, max_sentences: usize) -> String {
+use attentive_core::Redactor;
+
+/// The first `max_sentences` sentences of `content`, with secrets redacted
+/// by `redactor`
+pub fn fallback_compress(content: &str, max_sentences: usize, redactor: &Redactor) -> String {
+    let (content, _) = redactor.redact(content);
     let sentences: Vec<&str> = content
         .split(['.', '!', '?'])
         .filter(|s| !s.trim().is_empty())
@@ -20,9 +25,23 @@ mod tests {
     #[test]
     fn test_fallback_compress() {
         let text = "First sentence. Second sentence. Third sentence.";
-        let compressed = fallback_compress(text, 2);
+        let compressed = fallback_compress(text, 2, &Redactor::default());
         assert!(compressed.contains("First sentence"));
         assert!(compressed.contains("Second sentence"));
         assert!(!compressed.contains("Third"));
     }
+
+    #[test]
+    fn test_fallback_compress_uses_given_redactor() {
+        let text = "token corp-42. export API_KEY=sk-abcdefghijklmnopqrstuvwx";
+        let custom = Redactor::new(false, &["corp-\\d+".to_string()]);
+        assert_eq!(
+            fallback_compress(text, 2, &custom),
+            "token [REDACTED].  export API_KEY=sk-abcdefghijklmnopqrstuvwx..."
+        );
+        assert_eq!(
+            fallback_compress(text, 2, &Redactor::disabled()),
+            "token corp-42.  export API_KEY=sk-abcdefghijklmnopqrstuvwx..."
+        );
+    }
 }
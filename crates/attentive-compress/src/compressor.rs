@@ -1,3 +1,6 @@
+use crate::backend::{AnthropicBackend, CompressionBackend, parse_reply};
+use attentive_core::Redactor;
+
 const MAX_INPUT_CHARS: usize = 10000;
 
 pub fn build_compression_prompt(tool_name: &str, output: &str) -> String {
@@ -13,7 +16,11 @@ pub fn build_compression_prompt(tool_name: &str, output: &str) -> String {
     )
 }
 
-pub fn fallback_compress(tool_name: &str, output: &str) -> CompressResult {
+/// Truncation of `output` standing in for a summary, with secrets redacted
+/// by `redactor`
+pub fn fallback_compress(tool_name: &str, output: &str, redactor: &Redactor) -> CompressResult {
+    let (redacted, _) = redactor.redact(output);
+    let output = redacted.as_ref();
     let summary = if output.len() > 500 {
         format!("[{}] {}...", tool_name, &output[..497])
     } else {
@@ -37,53 +44,24 @@ pub struct CompressResult {
     pub compressed_tokens: usize,
 }
 
+/// Compress with the Anthropic API alone, redacting the fallback with
+/// `redactor`; see `Compressor` for configurable backends with fallback
 pub async fn compress_via_api(
     tool_name: &str,
     output: &str,
     api_key: &str,
+    redactor: &Redactor,
 ) -> Result<CompressResult, Box<dyn std::error::Error>> {
-    const COMPRESSION_MODEL: &str = "claude-3-haiku-20240307";
-
-    let client = reqwest::Client::new();
-    let prompt = build_compression_prompt(tool_name, output);
-
-    let response = client
-        .post("https://api.anthropic.com/v1/messages")
-        .header("x-api-key", api_key)
-        .header("anthropic-version", "2023-06-01")
-        .header("content-type", "application/json")
-        .json(&serde_json::json!({
-            "model": COMPRESSION_MODEL,
-            "max_tokens": 1024,
-            "messages": [{"role": "user", "content": prompt}]
-        }))
-        .send()
+    let backend = AnthropicBackend::new(
+        AnthropicBackend::DEFAULT_URL,
+        AnthropicBackend::DEFAULT_MODEL,
+        Some(api_key.to_string()),
+    );
+    let reply = backend
+        .complete(&build_compression_prompt(tool_name, output))
         .await?;
-
-    let body: serde_json::Value = response.json().await?;
-    let text = body["content"][0]["text"].as_str().unwrap_or("");
-
-    if let Ok(parsed) = serde_json::from_str::<serde_json::Value>(text) {
-        let summary = parsed["summary"].as_str().unwrap_or(text).to_string();
-        let key_facts: Vec<String> = parsed["key_facts"]
-            .as_array()
-            .map(|a| {
-                a.iter()
-                    .filter_map(|v| v.as_str().map(String::from))
-                    .collect()
-            })
-            .unwrap_or_default();
-        let raw_tokens = output.len() / 4;
-        let compressed_tokens = summary.len() / 4;
-        Ok(CompressResult {
-            summary,
-            key_facts,
-            raw_tokens,
-            compressed_tokens,
-        })
-    } else {
-        Ok(fallback_compress(tool_name, output))
-    }
+    Ok(parse_reply(&reply, output)
+        .unwrap_or_else(|| fallback_compress(tool_name, output, redactor)))
 }
 
 #[cfg(test)]
@@ -92,9 +70,17 @@ mod tests {
 
     #[test]
     fn test_fallback_compress() {
-        let result = fallback_compress("Read", "fn main() { println!(\"hello\"); }");
+        let redactor = Redactor::default();
+        let result = fallback_compress("Read", "fn main() { println!(\"hello\"); }", &redactor);
         assert!(!result.summary.is_empty());
         assert!(result.compressed_tokens < 500);
+
+        let secret = "export API_KEY=sk-abcdefghijklmnopqrstuvwx";
+        let result = fallback_compress("Bash", secret, &redactor);
+        assert_eq!(result.summary, "[Bash] export API_KEY=[REDACTED]");
+        // `redact_secrets: false` leaves the output alone
+        let result = fallback_compress("Bash", secret, &Redactor::disabled());
+        assert_eq!(result.summary, format!("[Bash] {}", secret));
     }
 
     #[test]
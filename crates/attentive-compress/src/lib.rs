@@ -1,11 +1,16 @@
 //! Context compression using LLM-based summarization
 
+mod backend;
 mod compress;
 pub mod compressor;
 mod storage;
 mod types;
 
+pub use backend::{
+    AnthropicBackend, BackendFuture, CompressionBackend, Compressor, Credentials, OllamaBackend,
+    OpenAiBackend, backend_from_config,
+};
 pub use compress::fallback_compress;
 pub use compressor::CompressResult;
 pub use storage::ObservationDb;
-pub use types::{CompressedObservation, ObservationIndex};
+pub use types::{CompressedObservation, MEMORY_TYPE, ObservationIndex, file_content_hash};
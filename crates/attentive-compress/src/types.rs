@@ -1,5 +1,11 @@
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::Path;
+
+/// Observation type for user-authored notes (`attentive remember`)
+pub const MEMORY_TYPE: &str = "memory";
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CompressedObservation {
@@ -15,6 +21,74 @@ pub struct CompressedObservation {
     pub key_facts: Vec<String>,
     pub related_files: Vec<String>,
     pub raw_content_hash: String,
+    /// Content hash of each related file when the observation was made
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub file_hashes: BTreeMap<String, String>,
+    /// Canonical root of the project a memory was written for; `None` for
+    /// observations of a session
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub project: Option<String>,
+}
+
+/// Hash of a related file's content, as kept in `file_hashes`
+pub fn file_content_hash(content: &[u8]) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:016x}", hasher.finish())
+}
+
+impl CompressedObservation {
+    /// A user-authored memory, only recalled in `project` (the canonical root
+    /// of the repo it was written for). It belongs to no session.
+    pub fn memory(project: &str, note: &str, files: Vec<String>) -> Self {
+        let now = attentive_telemetry::now();
+        let mut hasher = DefaultHasher::new();
+        note.hash(&mut hasher);
+        let tokens = attentive_telemetry::estimate_tokens(note) as i64;
+        Self {
+            id: format!("mem_{:x}", now.timestamp_nanos_opt().unwrap_or_default()),
+            session_id: String::new(),
+            timestamp: now,
+            tool_name: "remember".to_string(),
+            observation_type: MEMORY_TYPE.to_string(),
+            concepts: Vec::new(),
+            raw_tokens: tokens,
+            compressed_tokens: tokens,
+            semantic_summary: note.to_string(),
+            key_facts: Vec::new(),
+            related_files: files,
+            raw_content_hash: format!("{:016x}", hasher.finish()),
+            file_hashes: BTreeMap::new(),
+            project: Some(project.to_string()),
+        }
+    }
+
+    /// Record the current content hash of each related file, resolving
+    /// relative paths against `root`. Unreadable files are left out.
+    pub fn with_file_hashes(mut self, root: &Path) -> Self {
+        self.file_hashes = self
+            .related_files
+            .iter()
+            .filter_map(|file| {
+                let content = std::fs::read(root.join(file)).ok()?;
+                Some((file.clone(), file_content_hash(&content)))
+            })
+            .collect();
+        self
+    }
+
+    /// Related files changed or removed since the observation was made.
+    /// Observations stored without hashes never report any.
+    pub fn changed_files(&self, root: &Path) -> Vec<String> {
+        self.file_hashes
+            .iter()
+            .filter(|(file, hash)| {
+                std::fs::read(root.join(file))
+                    .map_or(true, |content| file_content_hash(&content) != **hash)
+            })
+            .map(|(file, _)| file.clone())
+            .collect()
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
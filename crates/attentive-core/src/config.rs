@@ -1,15 +1,113 @@
 //! Configuration for attention routing
 
+use crate::ContextTemplates;
+use crate::profile::{PROFILE_KEYS, Profile, builtin_model_profiles, builtin_profiles};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::path::Path;
 
-/// Decay rates per category
+/// Decay rates per path, directory prefix (`dir/`) or glob
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct DecayRates {
     pub rates: HashMap<String, f64>,
     pub default: f64,
 }
 
+/// How a `decay_rates` key matches paths
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecayRule {
+    /// The key is a file path
+    Exact,
+    /// The key ends in `/` and matches everything under it
+    Prefix,
+    /// The key contains `*`, `?`, `[` or `{`
+    Glob,
+}
+
+impl DecayRule {
+    pub fn of(key: &str) -> Self {
+        if key.contains(['*', '?', '[', '{']) {
+            DecayRule::Glob
+        } else if key.ends_with('/') {
+            DecayRule::Prefix
+        } else {
+            DecayRule::Exact
+        }
+    }
+}
+
+impl std::fmt::Display for DecayRule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            DecayRule::Exact => "exact",
+            DecayRule::Prefix => "prefix",
+            DecayRule::Glob => "glob",
+        };
+        f.pad(name)
+    }
+}
+
+/// Glob matching `key` at the root or, for relative keys, in any directory
+fn decay_glob(key: &str) -> Result<GlobSet, globset::Error> {
+    let mut builder = GlobSetBuilder::new();
+    builder.add(Glob::new(key)?);
+    if !key.starts_with('/') && !key.starts_with("**") {
+        builder.add(Glob::new(&format!("**/{}", key))?);
+    }
+    builder.build()
+}
+
+/// `DecayRates` compiled for lookups. An exact path beats the longest matching
+/// prefix or glob, which beats the default.
+#[derive(Debug, Clone)]
+pub struct DecayMatcher {
+    exact: HashMap<String, f64>,
+    /// (key, glob for glob keys, rate), longest key first
+    patterns: Vec<(String, Option<GlobSet>, f64)>,
+    default: f64,
+}
+
+impl DecayMatcher {
+    /// The key deciding `path`'s rate and that rate, or `None` if only the
+    /// default applies
+    pub fn rule_for(&self, path: &str) -> Option<(&str, f64)> {
+        if let Some((key, &rate)) = self.exact.get_key_value(path) {
+            return Some((key, rate));
+        }
+        self.patterns
+            .iter()
+            .find(|(key, glob, _)| match glob {
+                Some(glob) => glob.is_match(path),
+                None => path.starts_with(key.as_str()),
+            })
+            .map(|(key, _, rate)| (key.as_str(), *rate))
+    }
+
+    pub fn get(&self, path: &str) -> f64 {
+        self.rule_for(path).map_or(self.default, |(_, rate)| rate)
+    }
+
+    pub fn default_rate(&self) -> f64 {
+        self.default
+    }
+
+    /// Every usable key with its rule and rate, in precedence order
+    pub fn rules(&self) -> Vec<(String, DecayRule, f64)> {
+        let mut exact: Vec<_> = self.exact.iter().collect();
+        exact.sort_by(|a, b| a.0.cmp(b.0));
+        exact
+            .into_iter()
+            .map(|(key, &rate)| (key.clone(), DecayRule::Exact, rate))
+            .chain(
+                self.patterns
+                    .iter()
+                    .map(|(key, _, rate)| (key.clone(), DecayRule::of(key), *rate)),
+            )
+            .collect()
+    }
+}
+
 impl DecayRates {
     pub fn new() -> Self {
         let mut rates = HashMap::new();
@@ -24,13 +122,34 @@ impl DecayRates {
         }
     }
 
-    pub fn get_decay(&self, path: &str) -> f64 {
-        for (prefix, &rate) in &self.rates {
-            if path.starts_with(prefix) {
-                return rate;
+    /// Compile the rates for lookups; invalid globs are skipped
+    pub fn matcher(&self) -> DecayMatcher {
+        let mut exact = HashMap::new();
+        let mut patterns = Vec::new();
+        for (key, &rate) in &self.rates {
+            match DecayRule::of(key) {
+                DecayRule::Exact => {
+                    exact.insert(key.clone(), rate);
+                }
+                DecayRule::Prefix => patterns.push((key.clone(), None, rate)),
+                DecayRule::Glob => match decay_glob(key) {
+                    Ok(glob) => patterns.push((key.clone(), Some(glob), rate)),
+                    Err(e) => tracing::warn!("ignoring invalid decay pattern {:?}: {}", key, e),
+                },
             }
         }
-        self.default
+        // Longest (most specific) key first; ties broken by name for stable output
+        patterns.sort_by(|a, b| b.0.len().cmp(&a.0.len()).then_with(|| a.0.cmp(&b.0)));
+        DecayMatcher {
+            exact,
+            patterns,
+            default: self.default,
+        }
+    }
+
+    /// Rate for `path`; use `matcher` for repeated lookups
+    pub fn get_decay(&self, path: &str) -> f64 {
+        self.matcher().get(path)
     }
 }
 
@@ -40,6 +159,224 @@ impl Default for DecayRates {
     }
 }
 
+/// LLM service an observation compression backend talks to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    Anthropic,
+    /// Any OpenAI-compatible chat completions endpoint
+    Openai,
+    Ollama,
+}
+
+impl BackendKind {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            BackendKind::Anthropic => "anthropic",
+            BackendKind::Openai => "openai",
+            BackendKind::Ollama => "ollama",
+        }
+    }
+}
+
+/// One entry of `compression`; unset fields use the backend's defaults
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CompressionBackendConfig {
+    pub backend: BackendKind,
+    #[serde(default)]
+    pub model: Option<String>,
+    #[serde(default)]
+    pub base_url: Option<String>,
+    /// Environment variable holding the API key
+    #[serde(default)]
+    pub api_key_env: Option<String>,
+}
+
+/// Where `attentive sync` keeps a copy of learned state
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "backend", rename_all = "lowercase")]
+pub enum SyncBackend {
+    /// A branch of a git repository, pushed and fetched with `git`
+    Git {
+        /// Any URL or path `git` can push to
+        remote: String,
+        #[serde(default = "default_sync_branch")]
+        branch: String,
+    },
+    /// An S3 bucket, or an S3-compatible store at `endpoint_url`, through
+    /// the `aws` CLI and its credentials
+    S3 {
+        bucket: String,
+        /// Key prefix inside the bucket
+        #[serde(default)]
+        prefix: String,
+        #[serde(default)]
+        endpoint_url: Option<String>,
+    },
+}
+
+fn default_sync_branch() -> String {
+    "attentive-sync".to_string()
+}
+
+/// How `search` combines BM25 and embedding similarity into one ranking
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FusionStrategy {
+    /// Weighted sum of the max-normalized BM25 score and the similarity
+    #[default]
+    Weighted,
+    /// Reciprocal rank fusion: weighted `1 / (rrf_k + rank)` summed over
+    /// both rankings, ignoring score scales
+    Rrf,
+    /// The better of the normalized BM25 score and the similarity
+    Max,
+}
+
+impl FusionStrategy {
+    pub const NAMES: &[&str] = &["weighted", "rrf", "max"];
+
+    pub fn as_str(self) -> &'static str {
+        match self {
+            FusionStrategy::Weighted => "weighted",
+            FusionStrategy::Rrf => "rrf",
+            FusionStrategy::Max => "max",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "weighted" => Some(FusionStrategy::Weighted),
+            "rrf" => Some(FusionStrategy::Rrf),
+            "max" => Some(FusionStrategy::Max),
+            _ => None,
+        }
+    }
+}
+
+/// `search_fusion`; unset fields keep their defaults
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SearchFusion {
+    pub strategy: FusionStrategy,
+    /// Weight of the BM25 ranking (`weighted` and `rrf`)
+    pub lexical_weight: f64,
+    /// Weight of the embedding ranking (`weighted` and `rrf`)
+    pub semantic_weight: f64,
+    /// RRF rank constant; larger values flatten the gap between top ranks
+    pub rrf_k: f64,
+}
+
+impl Default for SearchFusion {
+    fn default() -> Self {
+        Self {
+            strategy: FusionStrategy::Weighted,
+            lexical_weight: 0.6,
+            semantic_weight: 0.4,
+            rrf_k: 60.0,
+        }
+    }
+}
+
+/// Which attention state a project uses across git branches
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BranchState {
+    /// One attention state whatever the branch
+    #[default]
+    Shared,
+    /// Each branch keeps its own; a branch seen for the first time starts empty
+    PerBranch,
+    /// Each branch keeps its own; a branch seen for the first time starts
+    /// from main's (or master's)
+    PerBranchWithFallback,
+}
+
+impl BranchState {
+    pub const NAMES: &[&str] = &["shared", "per-branch", "per-branch-with-fallback"];
+}
+
+/// What recall does with an observation whose related files changed after it
+/// was made
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum StaleObservations {
+    /// Inject it marked as possibly outdated
+    #[default]
+    Annotate,
+    /// Leave it out
+    Exclude,
+}
+
+impl StaleObservations {
+    pub const NAMES: &[&str] = &["annotate", "exclude"];
+}
+
+/// How the router keeps scores within [0, 1] after its boost phases
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum NormalizationStrategy {
+    /// Cap every boost at 1.0, so files pushed past it tie at the top
+    #[default]
+    Clamp,
+    /// Let boosts overflow, then map scores above the WARM threshold linearly
+    /// onto [warm_threshold, 1]
+    Rescale,
+    /// Let boosts overflow, then give each file above the WARM threshold its
+    /// softmax weight relative to the top file, `exp((score - top) / temperature)`,
+    /// spread over [warm_threshold, 1]
+    Softmax,
+}
+
+impl NormalizationStrategy {
+    pub const NAMES: &[&str] = &["clamp", "rescale", "softmax"];
+}
+
+/// `score_normalization`; unset fields keep their defaults
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ScoreNormalization {
+    pub strategy: NormalizationStrategy,
+    /// Softmax temperature; lower values pull files below the top apart faster
+    pub temperature: f64,
+}
+
+impl Default for ScoreNormalization {
+    fn default() -> Self {
+        Self {
+            strategy: NormalizationStrategy::Clamp,
+            temperature: 0.5,
+        }
+    }
+}
+
+/// Demoted ahead of `demoted_files` unless `default_file_rules` is off:
+/// lock files, snapshots, minified bundles, and vendored or built code
+pub const DEFAULT_DEMOTED_FILES: &[&str] = &[
+    "package-lock.json",
+    "yarn.lock",
+    "pnpm-lock.yaml",
+    "Cargo.lock",
+    "poetry.lock",
+    "Pipfile.lock",
+    "Gemfile.lock",
+    "composer.lock",
+    "go.sum",
+    "*.snap",
+    "__snapshots__/",
+    "*.min.js",
+    "*.min.css",
+    "vendor/",
+    "third_party/",
+    "dist/",
+    "build/",
+];
+
+/// Never scanned, ahead of `exclude_files`, unless `default_file_rules` is
+/// off: minified bundles and source maps hold no useful symbols
+pub const DEFAULT_EXCLUDED_FILES: &[&str] = &["*.min.js", "*.min.css", "*.map"];
+
 /// Router configuration
 #[derive(Debug, Clone)]
 pub struct Config {
@@ -73,11 +410,128 @@ pub struct Config {
     /// Co-activation graph (file -> related files)
     pub co_activation: HashMap<String, Vec<String>>,
 
-    /// Pinned files (always at least WARM)
+    /// Strengths of `co_activation` edges given in the `{file: strength}`
+    /// form; edges without one have strength 1.0
+    pub co_activation_weights: HashMap<String, HashMap<String, f64>>,
+
+    /// Pinned files, directories (`dir/`) or globs (always at least WARM)
     pub pinned_files: Vec<String>,
 
-    /// Demoted files (penalty applied)
+    /// Demoted files, directories (`dir/`) or globs (penalty applied)
     pub demoted_files: Vec<String>,
+
+    /// When set, files outside these files, directories or globs are demoted
+    pub focus_files: Vec<String>,
+
+    /// Gitignore-style patterns never scanned, on top of .gitignore and .attentiveignore
+    pub exclude_files: Vec<String>,
+
+    /// Apply `DEFAULT_DEMOTED_FILES` and `DEFAULT_EXCLUDED_FILES` before the
+    /// user's lists; `!pattern` entries in those lists override single rules
+    pub default_file_rules: bool,
+
+    /// Absolute paths (or globs, `~/` for home) outside the repo that may
+    /// still enter attention state
+    pub external_files: Vec<String>,
+
+    /// Routing latency budget; optional phases are skipped once exceeded
+    pub latency_budget_ms: u64,
+
+    /// End-to-end prompt hook deadline; partial context is emitted past it
+    pub hook_deadline_ms: u64,
+
+    /// Tag each injected section with why it was included (costs a few tokens each)
+    pub provenance_tags: bool,
+
+    /// Files edited within this many turns never drop below WARM (0 disables)
+    pub edit_floor_turns: usize,
+
+    /// Share of the usual decay applied after a turn with no tool calls (0
+    /// keeps scores as they were, 1 decays as normal)
+    pub planning_decay_scale: f64,
+
+    /// Name files whose section is unchanged since last turn instead of re-sending it
+    pub delta_context: bool,
+
+    /// Treat a source file and its test file as co-activation neighbors
+    pub pair_tests: bool,
+
+    /// Send files the prompt names (by path or file name) straight to HOT and
+    /// let them warm their co-activation neighbors
+    pub activate_mentioned: bool,
+
+    /// Score floor for files referenced in CLAUDE.md / AGENTS.md (0 disables)
+    pub instruction_floor: f64,
+
+    /// Score added to files an external tool reports as active (e.g. open in
+    /// the editor) through the project's `active_files.json` (0 disables)
+    pub active_file_boost: f64,
+
+    /// Score added to the editor's focused file from `active_files.json`
+    /// (0 disables)
+    pub active_buffer_boost: f64,
+
+    /// How scores pushed past 1.0 by the boost phases are brought back
+    pub score_normalization: ScoreNormalization,
+
+    /// Turns after which a learned observation counts half (0 never forgets)
+    pub learner_half_life: usize,
+
+    /// Multiplier on the injected context budget
+    pub context_scale: f64,
+
+    /// Injected context budget in characters, before scaling
+    pub max_context_chars: usize,
+
+    /// Most outline entries shown per WARM file
+    pub max_toc_entries: usize,
+
+    /// Active model, overriding the one reported by Claude Code
+    pub model: Option<String>,
+
+    /// Model name fragment -> profile applied while that model is active
+    pub model_profiles: BTreeMap<String, String>,
+
+    /// Named profiles: the built-in ones plus those from attentive.json
+    pub profiles: BTreeMap<String, Profile>,
+
+    /// Profile applied on top of the other settings
+    pub profile: Option<String>,
+
+    /// Task type (bug_fix, feature, ...) -> profile applied for such prompts
+    pub task_profiles: BTreeMap<String, String>,
+
+    /// Plugin name -> enabled, set by the applied profile
+    pub plugin_toggles: BTreeMap<String, bool>,
+
+    /// Plugin name -> most estimated tokens its per-prompt context may take
+    pub plugin_budgets: BTreeMap<String, usize>,
+
+    /// Observation compression backends, tried in order
+    pub compression: Vec<CompressionBackendConfig>,
+
+    /// Where `attentive sync` pushes and pulls learned state (unset disables it)
+    pub sync: Option<SyncBackend>,
+
+    /// Whether attention state and the learner's warm-start files are kept
+    /// per git branch
+    pub branch_state: BranchState,
+
+    /// How `search` fuses lexical and semantic rankings
+    pub search_fusion: SearchFusion,
+
+    /// Replace common secret patterns in injected and stored text
+    pub redact_secrets: bool,
+
+    /// Extra regexes redacted like secrets (only a `secret` group, if any)
+    pub redact_patterns: Vec<String>,
+
+    /// Whether recalled observations describing since-changed files are
+    /// annotated or left out
+    pub stale_observations: StaleObservations,
+
+    /// User layouts for HOT/WARM sections and the dashboard
+    pub templates: ContextTemplates,
 }
 
 impl Config {
@@ -93,10 +547,109 @@ impl Config {
             pinned_floor_boost: 0.1,
             demoted_penalty: 0.5,
             co_activation: HashMap::new(),
+            co_activation_weights: HashMap::new(),
             pinned_files: Vec::new(),
             demoted_files: Vec::new(),
+            focus_files: Vec::new(),
+            exclude_files: Vec::new(),
+            default_file_rules: true,
+            external_files: vec!["~/.claude/CLAUDE.md".to_string()],
+            latency_budget_ms: 50,
+            hook_deadline_ms: 300,
+            provenance_tags: false,
+            edit_floor_turns: 3,
+            planning_decay_scale: 0.0,
+            delta_context: false,
+            pair_tests: true,
+            activate_mentioned: false,
+            instruction_floor: 0.3,
+            active_file_boost: 0.5,
+            active_buffer_boost: 0.8,
+            score_normalization: ScoreNormalization::default(),
+            learner_half_life: 500,
+            context_scale: 1.0,
+            max_context_chars: 20_000,
+            max_toc_entries: 12,
+            model: None,
+            model_profiles: builtin_model_profiles(),
+            profiles: builtin_profiles(),
+            profile: None,
+            task_profiles: BTreeMap::new(),
+            plugin_toggles: BTreeMap::new(),
+            plugin_budgets: BTreeMap::new(),
+            compression: vec![CompressionBackendConfig {
+                backend: BackendKind::Anthropic,
+                model: None,
+                base_url: None,
+                api_key_env: None,
+            }],
+            sync: None,
+            branch_state: BranchState::default(),
+            search_fusion: SearchFusion::default(),
+            redact_secrets: true,
+            redact_patterns: Vec::new(),
+            stale_observations: StaleObservations::default(),
+            templates: ContextTemplates::default(),
         }
     }
+
+    /// Apply the profile called `name`; returns false if there is none
+    pub fn apply_profile(&mut self, name: &str) -> bool {
+        let Some(profile) = self.profiles.get(name).cloned() else {
+            return false;
+        };
+        self.apply(&profile);
+        true
+    }
+
+    /// Apply the profile mapped to `task_type` in `task_profiles`, returning
+    /// its name. It is layered over the profile already applied, if any.
+    pub fn apply_task_profile(&mut self, task_type: &str) -> Option<String> {
+        let name = self.task_profiles.get(task_type)?.clone();
+        self.apply_profile(&name).then_some(name)
+    }
+
+    /// Apply the profile mapped to the longest `model_profiles` key found in
+    /// `model` (case-insensitive), returning its name
+    pub fn apply_model_profile(&mut self, model: &str) -> Option<String> {
+        let model = model.to_lowercase();
+        let name = self
+            .model_profiles
+            .iter()
+            .filter(|(fragment, _)| model.contains(&fragment.to_lowercase()))
+            .max_by_key(|(fragment, _)| fragment.len())
+            .map(|(_, name)| name.clone())?;
+        self.apply_profile(&name).then_some(name)
+    }
+
+    fn apply(&mut self, profile: &Profile) {
+        if let Some(v) = profile.hot_threshold {
+            self.hot_threshold = v;
+        }
+        if let Some(v) = profile.warm_threshold {
+            self.warm_threshold = v;
+        }
+        if let Some(v) = profile.max_hot_files {
+            self.max_hot_files = v;
+        }
+        if let Some(v) = profile.max_warm_files {
+            self.max_warm_files = v;
+        }
+        if let Some(v) = profile.context_scale {
+            self.context_scale = v;
+        }
+        if let Some(v) = profile.max_context_chars {
+            self.max_context_chars = v;
+        }
+        if let Some(v) = profile.max_toc_entries {
+            self.max_toc_entries = v;
+        }
+        if let Some(v) = profile.delta_context {
+            self.delta_context = v;
+        }
+        self.plugin_toggles
+            .extend(profile.plugins.iter().map(|(k, v)| (k.clone(), *v)));
+    }
 }
 
 impl Default for Config {
@@ -105,6 +658,938 @@ impl Default for Config {
     }
 }
 
+/// A `co_activation` value: related files, or related files with strengths
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+enum CoActivationTargets {
+    Files(Vec<String>),
+    Weighted(HashMap<String, f64>),
+}
+
+/// On-disk shape of `~/.claude/attentive.json`; every key is optional
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct ConfigFile {
+    co_activation: HashMap<String, CoActivationTargets>,
+    pinned_files: Vec<String>,
+    demoted_files: Vec<String>,
+    focus_files: Vec<String>,
+    exclude_files: Vec<String>,
+    default_file_rules: Option<bool>,
+    external_files: Option<Vec<String>>,
+    hot_threshold: Option<f64>,
+    warm_threshold: Option<f64>,
+    coactivation_boost: Option<f64>,
+    transitive_boost: Option<f64>,
+    max_hot_files: Option<usize>,
+    max_warm_files: Option<usize>,
+    pinned_floor_boost: Option<f64>,
+    demoted_penalty: Option<f64>,
+    decay_rates: HashMap<String, f64>,
+    default_decay: Option<f64>,
+    latency_budget_ms: Option<u64>,
+    hook_deadline_ms: Option<u64>,
+    provenance_tags: Option<bool>,
+    edit_floor_turns: Option<usize>,
+    planning_decay_scale: Option<f64>,
+    delta_context: Option<bool>,
+    pair_tests: Option<bool>,
+    activate_mentioned: Option<bool>,
+    instruction_floor: Option<f64>,
+    active_file_boost: Option<f64>,
+    active_buffer_boost: Option<f64>,
+    score_normalization: ScoreNormalization,
+    learner_half_life: Option<usize>,
+    context_scale: Option<f64>,
+    max_context_chars: Option<usize>,
+    max_toc_entries: Option<usize>,
+    model: Option<String>,
+    model_profiles: BTreeMap<String, String>,
+    profiles: BTreeMap<String, Profile>,
+    profile: Option<String>,
+    task_profiles: BTreeMap<String, String>,
+    plugin_budgets: BTreeMap<String, usize>,
+    compression: Option<Vec<CompressionBackendConfig>>,
+    sync: Option<SyncBackend>,
+    branch_state: Option<BranchState>,
+    search_fusion: SearchFusion,
+    redact_secrets: Option<bool>,
+    redact_patterns: Vec<String>,
+    stale_observations: Option<StaleObservations>,
+    templates: ContextTemplates,
+}
+
+/// Keys accepted in attentive.json
+pub const CONFIG_KEYS: &[&str] = &[
+    "co_activation",
+    "pinned_files",
+    "demoted_files",
+    "focus_files",
+    "exclude_files",
+    "default_file_rules",
+    "external_files",
+    "hot_threshold",
+    "warm_threshold",
+    "coactivation_boost",
+    "transitive_boost",
+    "max_hot_files",
+    "max_warm_files",
+    "pinned_floor_boost",
+    "demoted_penalty",
+    "decay_rates",
+    "default_decay",
+    "latency_budget_ms",
+    "hook_deadline_ms",
+    "provenance_tags",
+    "edit_floor_turns",
+    "planning_decay_scale",
+    "delta_context",
+    "pair_tests",
+    "activate_mentioned",
+    "instruction_floor",
+    "active_file_boost",
+    "active_buffer_boost",
+    "score_normalization",
+    "learner_half_life",
+    "context_scale",
+    "max_context_chars",
+    "max_toc_entries",
+    "model",
+    "model_profiles",
+    "profiles",
+    "profile",
+    "task_profiles",
+    "plugin_budgets",
+    "compression",
+    "sync",
+    "branch_state",
+    "search_fusion",
+    "redact_secrets",
+    "redact_patterns",
+    "stale_observations",
+    "templates",
+];
+
+/// Severity of a config validation finding
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    /// Routing would misbehave; the config should not be used
+    Error,
+    /// Legal but probably unintended
+    Warning,
+}
+
+/// A single validation finding for one config field
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigIssue {
+    pub severity: Severity,
+    pub field: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for ConfigIssue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let label = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", label, self.field, self.message)
+    }
+}
+
+fn issue(severity: Severity, field: &str, message: String) -> ConfigIssue {
+    ConfigIssue {
+        severity,
+        field: field.to_string(),
+        message,
+    }
+}
+
+fn check_unit_range(issues: &mut Vec<ConfigIssue>, field: &str, value: f64) {
+    if !(0.0..=1.0).contains(&value) {
+        issues.push(issue(
+            Severity::Error,
+            field,
+            format!("{} is outside [0.0, 1.0]", value),
+        ));
+    }
+}
+
+impl Config {
+    /// Parse attentive.json content on top of the defaults
+    pub fn from_json(content: &str) -> Result<Self, serde_json::Error> {
+        let cf: ConfigFile = serde_json::from_str(content)?;
+        let mut config = Config::new();
+        for (from, targets) in cf.co_activation {
+            let related = match targets {
+                CoActivationTargets::Files(files) => files,
+                CoActivationTargets::Weighted(weights) => {
+                    let mut related: Vec<String> = weights.keys().cloned().collect();
+                    related.sort();
+                    config.co_activation_weights.insert(from.clone(), weights);
+                    related
+                }
+            };
+            config.co_activation.insert(from, related);
+        }
+        config.pinned_files = cf.pinned_files;
+        config.demoted_files = cf.demoted_files;
+        config.focus_files = cf.focus_files;
+        config.exclude_files = cf.exclude_files;
+        if let Some(v) = cf.default_file_rules {
+            config.default_file_rules = v;
+        }
+        if let Some(v) = cf.external_files {
+            config.external_files = v;
+        }
+        config.decay_rates.rates.extend(cf.decay_rates);
+        if let Some(v) = cf.default_decay {
+            config.decay_rates.default = v;
+        }
+        if let Some(v) = cf.hot_threshold {
+            config.hot_threshold = v;
+        }
+        if let Some(v) = cf.warm_threshold {
+            config.warm_threshold = v;
+        }
+        if let Some(v) = cf.coactivation_boost {
+            config.coactivation_boost = v;
+        }
+        if let Some(v) = cf.transitive_boost {
+            config.transitive_boost = v;
+        }
+        if let Some(v) = cf.max_hot_files {
+            config.max_hot_files = v;
+        }
+        if let Some(v) = cf.max_warm_files {
+            config.max_warm_files = v;
+        }
+        if let Some(v) = cf.pinned_floor_boost {
+            config.pinned_floor_boost = v;
+        }
+        if let Some(v) = cf.demoted_penalty {
+            config.demoted_penalty = v;
+        }
+        if let Some(v) = cf.latency_budget_ms {
+            config.latency_budget_ms = v;
+        }
+        if let Some(v) = cf.hook_deadline_ms {
+            config.hook_deadline_ms = v;
+        }
+        if let Some(v) = cf.provenance_tags {
+            config.provenance_tags = v;
+        }
+        if let Some(v) = cf.edit_floor_turns {
+            config.edit_floor_turns = v;
+        }
+        if let Some(v) = cf.planning_decay_scale {
+            config.planning_decay_scale = v;
+        }
+        if let Some(v) = cf.delta_context {
+            config.delta_context = v;
+        }
+        if let Some(v) = cf.pair_tests {
+            config.pair_tests = v;
+        }
+        if let Some(v) = cf.activate_mentioned {
+            config.activate_mentioned = v;
+        }
+        if let Some(v) = cf.instruction_floor {
+            config.instruction_floor = v;
+        }
+        if let Some(v) = cf.active_file_boost {
+            config.active_file_boost = v;
+        }
+        if let Some(v) = cf.active_buffer_boost {
+            config.active_buffer_boost = v;
+        }
+        config.score_normalization = cf.score_normalization;
+        if let Some(v) = cf.learner_half_life {
+            config.learner_half_life = v;
+        }
+        if let Some(v) = cf.context_scale {
+            config.context_scale = v;
+        }
+        if let Some(v) = cf.max_context_chars {
+            config.max_context_chars = v;
+        }
+        if let Some(v) = cf.max_toc_entries {
+            config.max_toc_entries = v;
+        }
+        config.model = cf.model;
+        config.model_profiles.extend(cf.model_profiles);
+        if let Some(v) = cf.compression {
+            config.compression = v;
+        }
+        config.sync = cf.sync;
+        if let Some(v) = cf.branch_state {
+            config.branch_state = v;
+        }
+        config.search_fusion = cf.search_fusion;
+        if let Some(v) = cf.redact_secrets {
+            config.redact_secrets = v;
+        }
+        config.redact_patterns = cf.redact_patterns;
+        if let Some(v) = cf.stale_observations {
+            config.stale_observations = v;
+        }
+        config.templates = cf.templates;
+        config.profiles.extend(cf.profiles);
+        config.task_profiles = cf.task_profiles;
+        config.plugin_budgets = cf.plugin_budgets;
+        config.profile = cf.profile;
+        if let Some(name) = config.profile.clone() {
+            config.apply_profile(&name);
+        }
+        Ok(config)
+    }
+
+    /// Range checks and cross-field invariants, including those of every profile
+    pub fn validate(&self) -> Vec<ConfigIssue> {
+        let mut issues = self.validate_values();
+
+        let unknown = |name: &String| !self.profiles.contains_key(name);
+        if let Some(name) = self.profile.as_ref().filter(|n| unknown(n)) {
+            issues.push(issue(
+                Severity::Error,
+                "profile",
+                format!("no profile named {}", name),
+            ));
+        }
+        for (task_type, name) in &self.task_profiles {
+            let field = format!("task_profiles.{}", task_type);
+            if attentive_learn::TaskType::from_name(task_type).is_none() {
+                issues.push(issue(
+                    Severity::Warning,
+                    &field,
+                    "unknown task type".to_string(),
+                ));
+            }
+            if unknown(name) {
+                issues.push(issue(
+                    Severity::Error,
+                    &field,
+                    format!("no profile named {}", name),
+                ));
+            }
+        }
+        for (fragment, name) in &self.model_profiles {
+            if unknown(name) {
+                issues.push(issue(
+                    Severity::Error,
+                    &format!("model_profiles.{}", fragment),
+                    format!("no profile named {}", name),
+                ));
+            }
+        }
+        // A profile in use is invalid if applying it makes the config invalid
+        let in_use: BTreeSet<&String> = self
+            .profile
+            .iter()
+            .chain(self.task_profiles.values())
+            .chain(self.model_profiles.values())
+            .collect();
+        for name in in_use {
+            let Some(profile) = self.profiles.get(name) else {
+                continue;
+            };
+            let mut applied = self.clone();
+            applied.apply(profile);
+            for found in applied.validate_values() {
+                let field = found.field.as_str();
+                if PROFILE_KEYS.contains(&field) && !issues.iter().any(|i| i.field == field) {
+                    issues.push(ConfigIssue {
+                        field: format!("profiles.{}.{}", name, found.field),
+                        ..found
+                    });
+                }
+            }
+        }
+        issues
+    }
+
+    fn validate_values(&self) -> Vec<ConfigIssue> {
+        let mut issues = Vec::new();
+
+        check_unit_range(&mut issues, "hot_threshold", self.hot_threshold);
+        check_unit_range(&mut issues, "warm_threshold", self.warm_threshold);
+        check_unit_range(&mut issues, "coactivation_boost", self.coactivation_boost);
+        check_unit_range(&mut issues, "transitive_boost", self.transitive_boost);
+        check_unit_range(&mut issues, "pinned_floor_boost", self.pinned_floor_boost);
+        check_unit_range(&mut issues, "demoted_penalty", self.demoted_penalty);
+        check_unit_range(&mut issues, "instruction_floor", self.instruction_floor);
+        check_unit_range(
+            &mut issues,
+            "planning_decay_scale",
+            self.planning_decay_scale,
+        );
+        check_unit_range(&mut issues, "active_file_boost", self.active_file_boost);
+        check_unit_range(&mut issues, "active_buffer_boost", self.active_buffer_boost);
+        if self.context_scale <= 0.0 || self.context_scale > 1.0 {
+            issues.push(issue(
+                Severity::Error,
+                "context_scale",
+                format!("{} is outside (0.0, 1.0]", self.context_scale),
+            ));
+        }
+
+        if self.hot_threshold <= self.warm_threshold {
+            issues.push(issue(
+                Severity::Error,
+                "hot_threshold",
+                format!(
+                    "must be greater than warm_threshold ({} <= {})",
+                    self.hot_threshold, self.warm_threshold
+                ),
+            ));
+        }
+        if self.transitive_boost > self.coactivation_boost {
+            issues.push(issue(
+                Severity::Warning,
+                "transitive_boost",
+                "is larger than coactivation_boost; 2-hop files outrank direct neighbors"
+                    .to_string(),
+            ));
+        }
+
+        let mut rates: Vec<_> = self.decay_rates.rates.iter().collect();
+        rates.sort_by(|a, b| a.0.cmp(b.0));
+        for (key, &rate) in rates {
+            if rate <= 0.0 || rate > 1.0 {
+                issues.push(issue(
+                    Severity::Error,
+                    &format!("decay_rates.{}", key),
+                    format!("{} is outside (0.0, 1.0]", rate),
+                ));
+            }
+            if DecayRule::of(key) == DecayRule::Glob
+                && let Err(e) = decay_glob(key)
+            {
+                issues.push(issue(
+                    Severity::Error,
+                    &format!("decay_rates.{}", key),
+                    format!("invalid glob: {}", e),
+                ));
+            }
+        }
+        for pattern in &self.external_files {
+            if let Err(e) = Glob::new(pattern) {
+                issues.push(issue(
+                    Severity::Error,
+                    "external_files",
+                    format!("invalid glob {}: {}", pattern, e),
+                ));
+            } else if !pattern.starts_with("~/") && !Path::new(pattern).is_absolute() {
+                issues.push(issue(
+                    Severity::Warning,
+                    "external_files",
+                    format!("{} is relative; repo files are always kept", pattern),
+                ));
+            }
+        }
+        match &self.sync {
+            Some(SyncBackend::Git { remote, branch }) if remote.is_empty() || branch.is_empty() => {
+                issues.push(issue(
+                    Severity::Error,
+                    "sync",
+                    "git backend needs a remote and a branch".to_string(),
+                ));
+            }
+            Some(SyncBackend::S3 { bucket, .. }) if bucket.is_empty() => {
+                issues.push(issue(
+                    Severity::Error,
+                    "sync.bucket",
+                    "is empty".to_string(),
+                ));
+            }
+            _ => {}
+        }
+        let fusion = &self.search_fusion;
+        for (field, weight) in [
+            ("search_fusion.lexical_weight", fusion.lexical_weight),
+            ("search_fusion.semantic_weight", fusion.semantic_weight),
+        ] {
+            if weight < 0.0 {
+                issues.push(issue(
+                    Severity::Error,
+                    field,
+                    format!("{} is negative", weight),
+                ));
+            }
+        }
+        if fusion.lexical_weight + fusion.semantic_weight <= 0.0 {
+            issues.push(issue(
+                Severity::Error,
+                "search_fusion",
+                "lexical_weight and semantic_weight are both 0; every result would tie".to_string(),
+            ));
+        }
+        if fusion.rrf_k <= 0.0 {
+            issues.push(issue(
+                Severity::Error,
+                "search_fusion.rrf_k",
+                format!("{} is not positive", fusion.rrf_k),
+            ));
+        }
+        let temperature = self.score_normalization.temperature;
+        if temperature <= 0.0 {
+            issues.push(issue(
+                Severity::Error,
+                "score_normalization.temperature",
+                format!("{} is not positive", temperature),
+            ));
+        }
+        for pattern in &self.redact_patterns {
+            if let Err(e) = regex::Regex::new(pattern) {
+                issues.push(issue(
+                    Severity::Error,
+                    "redact_patterns",
+                    format!("invalid regex {}: {}", pattern, e),
+                ));
+            }
+        }
+        for (key, error) in self.templates.errors() {
+            issues.push(issue(Severity::Error, &format!("templates.{}", key), error));
+        }
+        let default = self.decay_rates.default;
+        if default <= 0.0 || default > 1.0 {
+            issues.push(issue(
+                Severity::Error,
+                "default_decay",
+                format!("{} is outside (0.0, 1.0]", default),
+            ));
+        }
+
+        if self.max_context_chars == 0 {
+            issues.push(issue(
+                Severity::Error,
+                "max_context_chars",
+                "is 0; nothing would be injected".to_string(),
+            ));
+        }
+        if self.max_hot_files == 0 {
+            issues.push(issue(
+                Severity::Warning,
+                "max_hot_files",
+                "is 0; no file will ever be injected in full".to_string(),
+            ));
+        }
+        if self.hook_deadline_ms == 0 {
+            issues.push(issue(
+                Severity::Error,
+                "hook_deadline_ms",
+                "is 0; every prompt would miss the deadline".to_string(),
+            ));
+        } else if self.latency_budget_ms > self.hook_deadline_ms {
+            issues.push(issue(
+                Severity::Warning,
+                "latency_budget_ms",
+                format!(
+                    "exceeds hook_deadline_ms ({} > {})",
+                    self.latency_budget_ms, self.hook_deadline_ms
+                ),
+            ));
+        }
+
+        for pinned in &self.pinned_files {
+            if self.demoted_files.contains(pinned) {
+                issues.push(issue(
+                    Severity::Warning,
+                    "pinned_files",
+                    format!("{} is both pinned and demoted", pinned),
+                ));
+            }
+        }
+        let mut sources: Vec<_> = self.co_activation.iter().collect();
+        sources.sort_by(|a, b| a.0.cmp(b.0));
+        for (from, related) in sources {
+            if related.contains(from) {
+                issues.push(issue(
+                    Severity::Warning,
+                    "co_activation",
+                    format!("{} lists itself as related", from),
+                ));
+            }
+            for to in related {
+                let weight = self.co_activation_weight(from, to);
+                if weight <= 0.0 || weight > 1.0 {
+                    issues.push(issue(
+                        Severity::Error,
+                        "co_activation",
+                        format!(
+                            "strength of {} -> {} ({}) is outside (0.0, 1.0]",
+                            from, to, weight
+                        ),
+                    ));
+                }
+            }
+        }
+
+        issues
+    }
+
+    /// Canonicalize literal file entries in pinned/demoted lists and co-activation.
+    /// Glob patterns and directory prefixes (`dir/`) are left untouched.
+    pub fn canonicalize_paths(&mut self, canonicalizer: &attentive_telemetry::PathCanonicalizer) {
+        let canon = |p: &String| {
+            if p.ends_with('/') || p.contains(['*', '?', '[', '{']) {
+                p.clone()
+            } else if let Some(negated) = p.strip_prefix('!') {
+                format!("!{}", canonicalizer.canonicalize(negated))
+            } else {
+                canonicalizer.canonicalize(p)
+            }
+        };
+        self.pinned_files = self.pinned_files.iter().map(canon).collect();
+        self.demoted_files = self.demoted_files.iter().map(canon).collect();
+        self.focus_files = self.focus_files.iter().map(canon).collect();
+        self.co_activation = self
+            .co_activation
+            .iter()
+            .map(|(from, related)| (canon(from), related.iter().map(canon).collect()))
+            .collect();
+        self.co_activation_weights = self
+            .co_activation_weights
+            .iter()
+            .map(|(from, weights)| {
+                let weights = weights.iter().map(|(to, w)| (canon(to), *w)).collect();
+                (canon(from), weights)
+            })
+            .collect();
+    }
+
+    /// `demoted_files` preceded by the built-in demotions when they are on
+    pub fn demoted_patterns(&self) -> Vec<String> {
+        self.with_defaults(DEFAULT_DEMOTED_FILES, &self.demoted_files)
+    }
+
+    /// `exclude_files` preceded by the built-in exclusions when they are on
+    pub fn exclude_patterns(&self) -> Vec<String> {
+        self.with_defaults(DEFAULT_EXCLUDED_FILES, &self.exclude_files)
+    }
+
+    fn with_defaults(&self, defaults: &[&str], patterns: &[String]) -> Vec<String> {
+        let defaults = if self.default_file_rules {
+            defaults
+        } else {
+            &[]
+        };
+        defaults
+            .iter()
+            .map(|p| p.to_string())
+            .chain(patterns.iter().cloned())
+            .collect()
+    }
+
+    /// Redactor for `redact_secrets` and `redact_patterns`
+    pub fn redactor(&self) -> crate::Redactor {
+        crate::Redactor::new(self.redact_secrets, &self.redact_patterns)
+    }
+
+    /// Filter keeping repo files and the `external_files` allowlist
+    pub fn path_scope(&self, home: &Path) -> crate::PathScope {
+        crate::PathScope::new(&self.external_files, home)
+    }
+
+    /// Strength of the configured co-activation edge `from -> to`
+    pub fn co_activation_weight(&self, from: &str, to: &str) -> f64 {
+        self.co_activation_weights
+            .get(from)
+            .and_then(|weights| weights.get(to))
+            .copied()
+            .unwrap_or(1.0)
+    }
+
+    /// Whether `validate` reports any errors
+    pub fn has_errors(&self) -> bool {
+        self.validate()
+            .iter()
+            .any(|i| i.severity == Severity::Error)
+    }
+
+    /// Parse and validate attentive.json content, flagging unknown keys too
+    pub fn check_json(content: &str) -> Result<Vec<ConfigIssue>, serde_json::Error> {
+        let raw: serde_json::Value = serde_json::from_str(content)?;
+        let config = Config::from_json(content)?;
+
+        let mut issues = Vec::new();
+        if let Some(map) = raw.as_object() {
+            for key in map.keys() {
+                if !CONFIG_KEYS.contains(&key.as_str()) && key != "$schema" {
+                    issues.push(issue(Severity::Warning, key, "unknown key".to_string()));
+                }
+            }
+            let profiles = map.get("profiles").and_then(|p| p.as_object());
+            for (name, profile) in profiles.into_iter().flatten() {
+                for key in profile.as_object().into_iter().flat_map(|p| p.keys()) {
+                    if !PROFILE_KEYS.contains(&key.as_str()) {
+                        issues.push(issue(
+                            Severity::Warning,
+                            &format!("profiles.{}.{}", name, key),
+                            "unknown profile key".to_string(),
+                        ));
+                    }
+                }
+            }
+        }
+        issues.extend(config.validate());
+        Ok(issues)
+    }
+
+    /// JSON Schema for attentive.json (draft 2020-12), for editor autocomplete
+    pub fn json_schema() -> serde_json::Value {
+        let unit = |description: &str| {
+            serde_json::json!({
+                "type": "number", "minimum": 0.0, "maximum": 1.0, "description": description
+            })
+        };
+        let decay = serde_json::json!({
+            "type": "number", "exclusiveMinimum": 0.0, "maximum": 1.0
+        });
+        let scale = serde_json::json!({
+            "type": "number", "exclusiveMinimum": 0.0, "maximum": 1.0,
+            "description": "Multiplier on the injected context budget"
+        });
+        let paths = |description: &str| {
+            serde_json::json!({
+                "type": "array", "items": {"type": "string"}, "description": description
+            })
+        };
+        let backend = serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "required": ["backend"],
+            "properties": {
+                "backend": {"enum": ["anthropic", "openai", "ollama"]},
+                "model": {"type": "string"},
+                "base_url": {"type": "string"},
+                "api_key_env": {
+                    "type": "string",
+                    "description": "Environment variable holding the API key"
+                }
+            }
+        });
+        let related = serde_json::json!({
+            "oneOf": [
+                {"type": "array", "items": {"type": "string"}},
+                {
+                    "type": "object",
+                    "additionalProperties": {
+                        "type": "number", "exclusiveMinimum": 0.0, "maximum": 1.0
+                    }
+                }
+            ]
+        });
+        let context_chars = serde_json::json!({
+            "type": "integer",
+            "minimum": 1,
+            "description": "Injected context budget in characters, before scaling"
+        });
+        let toc_entries = serde_json::json!({
+            "type": "integer",
+            "minimum": 0,
+            "description": "Most outline entries shown per WARM file"
+        });
+        let profile = serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "hot_threshold": unit("Score at or above which a file is HOT"),
+                "warm_threshold": unit("Score at or above which a file is WARM"),
+                "max_hot_files": {"type": "integer", "minimum": 0},
+                "max_warm_files": {"type": "integer", "minimum": 0},
+                "context_scale": scale.clone(),
+                "max_context_chars": context_chars.clone(),
+                "max_toc_entries": toc_entries.clone(),
+                "delta_context": {"type": "boolean"},
+                "plugins": {
+                    "type": "object",
+                    "additionalProperties": {"type": "boolean"},
+                    "description": "Plugin name -> enabled"
+                }
+            }
+        });
+        let model_profiles = serde_json::json!({
+            "type": "object",
+            "additionalProperties": {"type": "string"},
+            "description": "Model name fragment (haiku, [1m], ...) -> profile while that model is active"
+        });
+        let search_fusion = serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "strategy": {"enum": FusionStrategy::NAMES},
+                "lexical_weight": {"type": "number", "minimum": 0.0},
+                "semantic_weight": {"type": "number", "minimum": 0.0},
+                "rrf_k": {"type": "number", "exclusiveMinimum": 0.0}
+            },
+            "description": "How search combines BM25 and embedding rankings: weighted sum (default 0.6/0.4), reciprocal rank fusion (rrf) or max"
+        });
+        let score_normalization = serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "strategy": {"enum": NormalizationStrategy::NAMES},
+                "temperature": {"type": "number", "exclusiveMinimum": 0.0}
+            },
+            "description": "How scores boosted past 1.0 are brought back: clamp (default) ties them at 1.0; rescale and softmax keep their order"
+        });
+        let templates = serde_json::json!({
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "hot": {"type": "string", "description": "HOT section: path, tier, score, provenance, view (full or digest), content"},
+                "warm": {"type": "string", "description": "WARM section: path, tier, score, provenance, view (toc or summary), content"},
+                "dashboard": {"type": "string", "description": "Session-start dashboard: content, turns, waste, top_waste"}
+            },
+            "description": "minijinja templates replacing the built-in layout of injected sections"
+        });
+        serde_json::json!({
+            "$schema": "https://json-schema.org/draft/2020-12/schema",
+            "title": "attentive.json",
+            "type": "object",
+            "additionalProperties": false,
+            "properties": {
+                "$schema": {"type": "string"},
+                "co_activation": {
+                    "type": "object",
+                    "additionalProperties": related,
+                    "description": "File -> related files boosted alongside it, optionally as {file: strength}"
+                },
+                "pinned_files": paths("Files, directories (dir/) or globs kept at least WARM"),
+                "demoted_files": paths("Files, directories (dir/) or globs penalized each turn; !pattern exempts matches, including from the built-in rules"),
+                "focus_files": paths("When set, files outside these files, directories or globs are penalized like demoted ones"),
+                "exclude_files": paths("Gitignore-style patterns never scanned, on top of .gitignore and .attentiveignore"),
+                "default_file_rules": {
+                    "type": "boolean",
+                    "description": "Demote lock files, snapshots, minified bundles and vendored or built code, and skip minified bundles and source maps when scanning (see `attentive config show-defaults`)"
+                },
+                "external_files": paths("Absolute paths or globs (~/ for home) outside the repo that may enter attention state"),
+                "hot_threshold": unit("Score at or above which a file is HOT (full content)"),
+                "warm_threshold": unit("Score at or above which a file is WARM (TOC)"),
+                "coactivation_boost": unit("Boost for direct co-activation neighbors, scaled by edge strength"),
+                "transitive_boost": unit("Boost for 2-hop co-activation neighbors, scaled by both edge strengths"),
+                "max_hot_files": {"type": "integer", "minimum": 0},
+                "max_warm_files": {"type": "integer", "minimum": 0},
+                "pinned_floor_boost": unit("Added to warm_threshold as the pinned floor"),
+                "demoted_penalty": unit("Multiplier applied to demoted files"),
+                "decay_rates": {
+                    "type": "object",
+                    "additionalProperties": decay.clone(),
+                    "description": "File path, directory prefix (dir/) or glob -> per-turn decay multiplier; an exact path beats the longest matching pattern"
+                },
+                "default_decay": decay,
+                "latency_budget_ms": {"type": "integer", "minimum": 0},
+                "hook_deadline_ms": {"type": "integer", "minimum": 1},
+                "provenance_tags": {
+                    "type": "boolean",
+                    "description": "Annotate injected sections with why each file was included"
+                },
+                "edit_floor_turns": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Turns an edited file is kept at least WARM (0 disables)"
+                },
+                "planning_decay_scale": unit("Share of the usual decay applied after a turn with no tool calls (0 freezes scores)"),
+                "delta_context": {
+                    "type": "boolean",
+                    "description": "List files unchanged since the previous turn by name instead of re-injecting them"
+                },
+                "pair_tests": {
+                    "type": "boolean",
+                    "description": "Warm a file's tests when it is active, and the file when its tests are"
+                },
+                "activate_mentioned": {
+                    "type": "boolean",
+                    "description": "Send files named in the prompt straight to HOT and warm their co-activation neighbors"
+                },
+                "instruction_floor": unit("Score floor for files referenced in CLAUDE.md / AGENTS.md (0 disables)"),
+                "active_file_boost": unit("Score added to files external tools list in active_files.json (0 disables)"),
+                "active_buffer_boost": unit("Score added to the focused editor file named in active_files.json (0 disables)"),
+                "score_normalization": score_normalization,
+                "learner_half_life": {
+                    "type": "integer",
+                    "minimum": 0,
+                    "description": "Turns after which a learned prompt-file association counts half (0 never forgets)"
+                },
+                "context_scale": scale,
+                "max_context_chars": context_chars,
+                "max_toc_entries": toc_entries,
+                "model": {
+                    "type": "string",
+                    "description": "Active model, overriding the one Claude Code reports"
+                },
+                "model_profiles": model_profiles,
+                "profiles": {
+                    "type": "object",
+                    "additionalProperties": profile,
+                    "description": "Named bundles of settings; focused, exploratory, economy, compact and long_context are built in"
+                },
+                "profile": {
+                    "type": "string",
+                    "description": "Profile applied on top of the other settings"
+                },
+                "task_profiles": {
+                    "type": "object",
+                    "additionalProperties": {"type": "string"},
+                    "description": "Task type (bug_fix, feature, refactor, ...) -> profile for such prompts"
+                },
+                "plugin_budgets": {
+                    "type": "object",
+                    "additionalProperties": {"type": "integer", "minimum": 0},
+                    "description": "Plugin name -> most estimated tokens its per-prompt context may take; longer output is shortened the way the plugin declares"
+                },
+                "compression": {
+                    "type": "array",
+                    "items": backend,
+                    "description": "Observation compression backends, tried in order until one succeeds"
+                },
+                "sync": {
+                    "oneOf": [
+                        {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["backend", "remote"],
+                            "properties": {
+                                "backend": {"const": "git"},
+                                "remote": {"type": "string", "description": "Git URL or path of the repository holding synced state"},
+                                "branch": {"type": "string", "description": "Branch synced state is committed to (default attentive-sync)"}
+                            }
+                        },
+                        {
+                            "type": "object",
+                            "additionalProperties": false,
+                            "required": ["backend", "bucket"],
+                            "properties": {
+                                "backend": {"const": "s3"},
+                                "bucket": {"type": "string"},
+                                "prefix": {"type": "string", "description": "Key prefix inside the bucket"},
+                                "endpoint_url": {"type": "string", "description": "S3-compatible endpoint, passed to the aws CLI"}
+                            }
+                        }
+                    ],
+                    "description": "Where `attentive sync push|pull` keeps learned state, attention state and observations"
+                },
+                "branch_state": {
+                    "enum": BranchState::NAMES,
+                    "description": "Attention state across git branches: shared (default), per-branch, or per-branch-with-fallback, where a new branch starts from main's state"
+                },
+                "search_fusion": search_fusion,
+                "redact_secrets": {
+                    "type": "boolean",
+                    "description": "Replace API keys, tokens, private keys and password assignments with [REDACTED] in injected context and stored observations"
+                },
+                "redact_patterns": paths("Extra regexes redacted like secrets; a (?P<secret>...) group limits what is replaced"),
+                "stale_observations": {
+                    "enum": StaleObservations::NAMES,
+                    "description": "Recalled memories and observations whose related files changed since: annotate (default) marks them as possibly outdated, exclude leaves them out"
+                },
+                "templates": templates
+            }
+        })
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -117,6 +1602,48 @@ mod tests {
         assert_eq!(rates.get_decay("unknown/file.md"), 0.70);
     }
 
+    #[test]
+    fn test_decay_precedence() {
+        let mut rates = DecayRates::new();
+        rates.rates.extend([
+            ("docs/**/*.md".to_string(), 0.95),
+            ("*.generated.rs".to_string(), 0.3),
+            ("src/".to_string(), 0.6),
+            ("src/core/".to_string(), 0.8),
+            ("src/core/hot.rs".to_string(), 0.9),
+        ]);
+        let matcher = rates.matcher();
+        assert_eq!(matcher.get("src/core/hot.rs"), 0.9);
+        assert_eq!(matcher.get("src/core/cold.rs"), 0.8);
+        assert_eq!(matcher.get("src/lib.rs"), 0.6);
+        // The glob key is longer than `src/`, so it wins
+        assert_eq!(matcher.get("src/api.generated.rs"), 0.3);
+        assert_eq!(matcher.get("docs/guide/intro.md"), 0.95);
+        // `docs/` (0.75) is shorter than the glob and still covers other files
+        assert_eq!(matcher.get("docs/logo.png"), 0.75);
+        assert_eq!(matcher.rule_for("README.md"), None);
+        assert_eq!(matcher.get("README.md"), 0.70);
+
+        let rules = matcher.rules();
+        assert_eq!(
+            rules[0],
+            ("src/core/hot.rs".to_string(), DecayRule::Exact, 0.9)
+        );
+        assert_eq!(rules[1].1, DecayRule::Glob);
+    }
+
+    #[test]
+    fn test_invalid_decay_glob_is_reported() {
+        let issues = Config::check_json(r#"{"decay_rates": {"src/[oops": 0.5}}"#).unwrap();
+        assert!(
+            issues.iter().any(
+                |i| i.field == "decay_rates.src/[oops" && i.message.starts_with("invalid glob")
+            )
+        );
+        let config = Config::from_json(r#"{"decay_rates": {"src/[oops": 0.5}}"#).unwrap();
+        assert_eq!(config.decay_rates.get_decay("src/[oops"), 0.70);
+    }
+
     #[test]
     fn test_config_defaults() {
         let config = Config::new();
@@ -124,4 +1651,310 @@ mod tests {
         assert_eq!(config.warm_threshold, 0.25);
         assert_eq!(config.max_hot_files, 3);
     }
+
+    #[test]
+    fn test_from_json_overrides_defaults() {
+        let config = Config::from_json(
+            r#"{"hot_threshold": 0.9, "decay_rates": {"src/": 0.6}, "pinned_files": ["a.md"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.hot_threshold, 0.9);
+        assert_eq!(config.warm_threshold, 0.25);
+        assert_eq!(config.decay_rates.get_decay("src/lib.rs"), 0.6);
+        assert_eq!(config.decay_rates.get_decay("systems/core.md"), 0.85);
+        assert_eq!(config.pinned_files, vec!["a.md"]);
+        assert!(config.plugin_budgets.is_empty());
+
+        let config = Config::from_json(r#"{"plugin_budgets": {"verifyfirst": 120}}"#).unwrap();
+        assert_eq!(config.plugin_budgets.get("verifyfirst"), Some(&120));
+        assert!(Config::from_json(r#"{"plugin_budgets": {"verifyfirst": -1}}"#).is_err());
+    }
+
+    #[test]
+    fn test_validate_defaults_clean() {
+        assert!(Config::new().validate().is_empty());
+    }
+
+    #[test]
+    fn test_validate_catches_bad_values() {
+        let config = Config::from_json(
+            r#"{"hot_threshold": 0.2, "warm_threshold": 1.2, "decay_rates": {"docs/": 1.5}}"#,
+        )
+        .unwrap();
+        let issues = config.validate();
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert!(fields.contains(&"warm_threshold"));
+        assert!(fields.contains(&"hot_threshold"));
+        assert!(fields.contains(&"decay_rates.docs/"));
+        assert!(config.has_errors());
+
+        let config =
+            Config::from_json(r#"{"templates": {"hot": "{{ path }}", "dashboard": "{% for %}"}}"#)
+                .unwrap();
+        assert_eq!(config.templates.hot.as_deref(), Some("{{ path }}"));
+        let fields: Vec<String> = config.validate().into_iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["templates.dashboard"]);
+    }
+
+    #[test]
+    fn test_profiles_apply_over_settings() {
+        let config = Config::from_json(
+            r#"{
+                "max_hot_files": 4,
+                "profile": "economy",
+                "profiles": {"economy": {"max_warm_files": 1}, "wide": {"max_hot_files": 9, "plugins": {"recall": false}}},
+                "task_profiles": {"exploration": "wide"}
+            }"#,
+        )
+        .unwrap();
+        // The user profile replaces the built-in one of the same name
+        assert_eq!(config.max_warm_files, 1);
+        assert_eq!(config.max_hot_files, 4);
+        assert!(config.profiles.contains_key("focused"));
+        assert!(config.validate().is_empty());
+
+        let mut config = config;
+        assert_eq!(config.apply_task_profile("bug_fix"), None);
+        assert_eq!(
+            config.apply_task_profile("exploration").as_deref(),
+            Some("wide")
+        );
+        assert_eq!(config.max_hot_files, 9);
+        assert_eq!(config.max_warm_files, 1);
+        assert_eq!(config.plugin_toggles.get("recall"), Some(&false));
+    }
+
+    #[test]
+    fn test_model_profiles_pick_longest_fragment() {
+        let mut config = Config::from_json(
+            r#"{
+                "profiles": {"opus_wide": {"max_context_chars": 40000}},
+                "model_profiles": {"opus": "opus_wide"}
+            }"#,
+        )
+        .unwrap();
+        assert_eq!(config.apply_model_profile("gpt-5"), None);
+        assert_eq!(
+            config.apply_model_profile("Claude-Sonnet-4[1m]").as_deref(),
+            Some("long_context")
+        );
+        assert_eq!(config.max_context_chars, 60_000);
+        assert_eq!(
+            config.apply_model_profile("claude-opus-4").as_deref(),
+            Some("opus_wide")
+        );
+        assert_eq!(config.max_context_chars, 40_000);
+
+        let mut config = Config::new();
+        assert_eq!(
+            config.apply_model_profile("claude-haiku-4-5").as_deref(),
+            Some("compact")
+        );
+        assert_eq!(config.max_toc_entries, 6);
+
+        let config = Config::from_json(r#"{"model_profiles": {"ghost": "missing"}}"#).unwrap();
+        let fields: Vec<String> = config.validate().into_iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["model_profiles.ghost"]);
+    }
+
+    #[test]
+    fn test_validate_checks_profiles_in_use() {
+        let config = Config::from_json(
+            r#"{
+                "profile": "tight",
+                "profiles": {
+                    "tight": {"warm_threshold": 0.95},
+                    "wide": {"context_scale": 2},
+                    "unused": {"context_scale": 0}
+                },
+                "task_profiles": {"bugfix": "missing", "feature": "wide"}
+            }"#,
+        )
+        .unwrap();
+        let issues = config.validate();
+        let fields: Vec<&str> = issues.iter().map(|i| i.field.as_str()).collect();
+        assert!(fields.contains(&"task_profiles.bugfix"));
+        // The selected profile is already applied, so it shows as the plain key
+        assert!(fields.contains(&"hot_threshold"));
+        assert!(fields.contains(&"profiles.wide.context_scale"));
+        assert!(!fields.iter().any(|f| f.starts_with("profiles.unused")));
+        assert!(config.has_errors());
+
+        let issues = Config::check_json(r#"{"profiles": {"p": {"max_hot": 2}}}"#).unwrap();
+        assert_eq!(
+            issues[0].to_string(),
+            "warning: profiles.p.max_hot: unknown profile key"
+        );
+        let config = Config::from_json(r#"{"profile": "nope"}"#).unwrap();
+        assert!(config.has_errors());
+    }
+
+    #[test]
+    fn test_check_json_flags_unknown_keys() {
+        let issues = Config::check_json(r#"{"hot_treshold": 0.9, "pinned_files": ["x"]}"#).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].severity, Severity::Warning);
+        assert_eq!(issues[0].to_string(), "warning: hot_treshold: unknown key");
+    }
+
+    #[test]
+    fn test_json_schema_covers_all_keys() {
+        let schema = Config::json_schema();
+        let props = schema["properties"].as_object().unwrap();
+        for key in CONFIG_KEYS {
+            assert!(props.contains_key(*key), "schema missing {}", key);
+        }
+    }
+
+    #[test]
+    fn test_canonicalize_paths_skips_patterns() {
+        let mut config = Config::new();
+        config.pinned_files = vec!["/repo/src/a.rs".to_string(), "docs/**".to_string()];
+        config.demoted_files = vec!["./vendor/".to_string()];
+        config
+            .co_activation
+            .insert("/repo/a.rs".to_string(), vec!["./b.rs".to_string()]);
+
+        config.canonicalize_paths(&attentive_telemetry::PathCanonicalizer::new("/repo"));
+        assert_eq!(config.pinned_files, vec!["src/a.rs", "docs/**"]);
+        assert_eq!(config.demoted_files, vec!["./vendor/"]);
+        assert_eq!(config.co_activation["a.rs"], vec!["b.rs"]);
+    }
+
+    #[test]
+    fn test_learner_half_life() {
+        assert_eq!(Config::new().learner_half_life, 500);
+        let config = Config::from_json(r#"{"learner_half_life": 0}"#).unwrap();
+        assert_eq!(config.learner_half_life, 0);
+        assert!(Config::from_json(r#"{"learner_half_life": -1}"#).is_err());
+    }
+
+    #[test]
+    fn test_co_activation_strengths() {
+        let mut config = Config::from_json(
+            r#"{"co_activation": {
+                "/repo/a.rs": {"b.rs": 0.4, "c.rs": 1.0},
+                "b.rs": ["a.rs"]
+            }}"#,
+        )
+        .unwrap();
+        assert_eq!(config.co_activation["/repo/a.rs"], vec!["b.rs", "c.rs"]);
+        assert_eq!(config.co_activation_weight("/repo/a.rs", "b.rs"), 0.4);
+        assert_eq!(config.co_activation_weight("b.rs", "a.rs"), 1.0);
+        assert!(!config.has_errors());
+
+        config.canonicalize_paths(&attentive_telemetry::PathCanonicalizer::new("/repo"));
+        assert_eq!(config.co_activation_weight("a.rs", "b.rs"), 0.4);
+
+        let config = Config::from_json(r#"{"co_activation": {"a.rs": {"b.rs": 0}}}"#).unwrap();
+        assert_eq!(
+            config.validate()[0].to_string(),
+            "error: co_activation: strength of a.rs -> b.rs (0) is outside (0.0, 1.0]"
+        );
+    }
+
+    #[test]
+    fn test_sync_backend() {
+        assert_eq!(Config::new().sync, None);
+        let config =
+            Config::from_json(r#"{"sync": {"backend": "git", "remote": "git@host:me/state.git"}}"#)
+                .unwrap();
+        assert_eq!(
+            config.sync,
+            Some(SyncBackend::Git {
+                remote: "git@host:me/state.git".to_string(),
+                branch: "attentive-sync".to_string(),
+            })
+        );
+        let config =
+            Config::from_json(r#"{"sync": {"backend": "s3", "bucket": "b", "prefix": "me/"}}"#)
+                .unwrap();
+        assert!(matches!(config.sync, Some(SyncBackend::S3 { ref prefix, .. }) if prefix == "me/"));
+        assert!(Config::from_json(r#"{"sync": {"backend": "ftp"}}"#).is_err());
+
+        let issues = Config::check_json(r#"{"sync": {"backend": "s3", "bucket": ""}}"#).unwrap();
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].field, "sync.bucket");
+    }
+
+    #[test]
+    fn test_search_fusion() {
+        assert_eq!(Config::new().search_fusion, SearchFusion::default());
+        let config =
+            Config::from_json(r#"{"search_fusion": {"strategy": "rrf", "rrf_k": 20}}"#).unwrap();
+        assert_eq!(config.search_fusion.strategy, FusionStrategy::Rrf);
+        assert_eq!(config.search_fusion.rrf_k, 20.0);
+        assert_eq!(config.search_fusion.lexical_weight, 0.6);
+        assert!(!config.has_errors());
+        assert!(Config::from_json(r#"{"search_fusion": {"strategy": "sum"}}"#).is_err());
+
+        let config = Config::from_json(
+            r#"{"search_fusion": {"lexical_weight": 0, "semantic_weight": 0, "rrf_k": 0}}"#,
+        )
+        .unwrap();
+        let fields: Vec<String> = config.validate().into_iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["search_fusion", "search_fusion.rrf_k"]);
+    }
+
+    #[test]
+    fn test_branch_state() {
+        assert_eq!(Config::new().branch_state, BranchState::Shared);
+        let config = Config::from_json(r#"{"branch_state": "per-branch-with-fallback"}"#).unwrap();
+        assert_eq!(config.branch_state, BranchState::PerBranchWithFallback);
+        let config = Config::from_json(r#"{"branch_state": "per-branch"}"#).unwrap();
+        assert_eq!(config.branch_state, BranchState::PerBranch);
+        assert!(Config::from_json(r#"{"branch_state": "branch"}"#).is_err());
+    }
+
+    #[test]
+    fn test_stale_observations() {
+        assert_eq!(
+            Config::new().stale_observations,
+            StaleObservations::Annotate
+        );
+        let config = Config::from_json(r#"{"stale_observations": "exclude"}"#).unwrap();
+        assert_eq!(config.stale_observations, StaleObservations::Exclude);
+        assert!(Config::from_json(r#"{"stale_observations": "drop"}"#).is_err());
+    }
+
+    #[test]
+    fn test_score_normalization() {
+        assert_eq!(
+            Config::new().score_normalization.strategy,
+            NormalizationStrategy::Clamp
+        );
+        let config =
+            Config::from_json(r#"{"score_normalization": {"strategy": "softmax"}}"#).unwrap();
+        assert_eq!(
+            config.score_normalization.strategy,
+            NormalizationStrategy::Softmax
+        );
+        assert_eq!(config.score_normalization.temperature, 0.5);
+        assert!(!config.has_errors());
+        assert!(Config::from_json(r#"{"score_normalization": {"strategy": "minmax"}}"#).is_err());
+
+        let config = Config::from_json(r#"{"score_normalization": {"temperature": 0}}"#).unwrap();
+        let fields: Vec<String> = config.validate().into_iter().map(|i| i.field).collect();
+        assert_eq!(fields, vec!["score_normalization.temperature"]);
+    }
+
+    #[test]
+    fn test_redaction_settings() {
+        let config = Config::new();
+        assert!(config.redact_secrets);
+        assert!(!config.redactor().is_empty());
+
+        let config = Config::from_json(
+            r#"{"redact_secrets": false, "redact_patterns": ["corp-[0-9]+", "(unclosed"]}"#,
+        )
+        .unwrap();
+        assert_eq!(config.redactor().redact("id corp-42").0, "id [REDACTED]");
+        let issues = config.validate();
+        assert_eq!(issues.len(), 1);
+        assert!(
+            issues[0]
+                .to_string()
+                .starts_with("error: redact_patterns: invalid regex (unclosed")
+        );
+    }
 }
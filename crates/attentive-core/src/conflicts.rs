@@ -0,0 +1,222 @@
+//! Files where config rules and learned behavior disagree
+//!
+//! A demoted file the learner keeps boosting, or a pinned file that is never
+//! used, means either the rule is stale or the learner picked up noise. Both
+//! are worth a look, so they are listed with the evidence behind them.
+
+use crate::config::Config;
+use crate::router::PathPatterns;
+use attentive_learn::Learner;
+use std::fmt;
+
+/// Turns a demoted file must have been used in before its boost counts as
+/// consistent
+const MIN_CONFLICT_USES: usize = 5;
+
+/// Learned boost below which a demoted file is not considered lifted
+const MIN_CONFLICT_BOOST: f64 = 0.05;
+
+/// Learner turns before a pinned file that was never used is called unused
+const MIN_UNUSED_TURNS: usize = 50;
+
+/// How a file's config rule and the learner disagree
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictKind {
+    /// Demoted (or outside `focus_files`), yet used and boosted by the learner
+    DemotedButBoosted,
+    /// Pinned, yet never used in the turns the learner has seen
+    PinnedButUnused,
+}
+
+/// One contradiction between config and learned behavior
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigConflict {
+    pub path: String,
+    pub kind: ConflictKind,
+    /// Turns the file was active in
+    pub uses: usize,
+    /// Turns the learner has observed
+    pub turns: usize,
+    /// Learned boost the file gets
+    pub boost: f64,
+    /// Config change that settles the conflict in the learner's favor
+    pub resolution: String,
+}
+
+impl fmt::Display for ConfigConflict {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self.kind {
+            ConflictKind::DemotedButBoosted => write!(
+                f,
+                "demoted, but used in {} of {} turns with a learned boost of {:.2}",
+                self.uses, self.turns, self.boost
+            ),
+            ConflictKind::PinnedButUnused => {
+                write!(f, "pinned, but unused in {} turns", self.turns)
+            }
+        }
+    }
+}
+
+/// Checks files against the pinned, demoted and focus rules of a config
+pub struct ConflictChecker {
+    pinned_files: Vec<String>,
+    demoted_files: Vec<String>,
+    pinned: PathPatterns,
+    demoted: PathPatterns,
+    focus: PathPatterns,
+}
+
+impl ConflictChecker {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            pinned_files: config.pinned_files.clone(),
+            demoted_files: config.demoted_files.clone(),
+            pinned: PathPatterns::new(&config.pinned_files),
+            demoted: PathPatterns::new(&config.demoted_patterns()),
+            focus: PathPatterns::new(&config.focus_files),
+        }
+    }
+
+    /// The conflict over `path`, if any, given the learned `boost` it gets
+    /// (its `Learner::learned_boost`, or what it gained on one turn)
+    pub fn check(&self, path: &str, learner: &Learner, boost: f64) -> Option<ConfigConflict> {
+        let uses = learner.file_usage(path);
+        let turns = learner.turn_count();
+        let kind = if self.pinned.matches(path) {
+            if uses > 0 || turns < MIN_UNUSED_TURNS {
+                return None;
+            }
+            ConflictKind::PinnedButUnused
+        } else if self.is_demoted(path) {
+            if uses < MIN_CONFLICT_USES || boost < MIN_CONFLICT_BOOST {
+                return None;
+            }
+            ConflictKind::DemotedButBoosted
+        } else {
+            return None;
+        };
+        Some(ConfigConflict {
+            path: path.to_string(),
+            kind,
+            uses,
+            turns,
+            boost,
+            resolution: self.resolution(path, kind),
+        })
+    }
+
+    fn is_demoted(&self, path: &str) -> bool {
+        self.demoted.matches(path) || (!self.focus.is_empty() && !self.focus.matches(path))
+    }
+
+    fn resolution(&self, path: &str, kind: ConflictKind) -> String {
+        let entries = match kind {
+            ConflictKind::DemotedButBoosted => &self.demoted_files,
+            ConflictKind::PinnedButUnused => &self.pinned_files,
+        };
+        let key = match kind {
+            ConflictKind::DemotedButBoosted => "demoted_files",
+            ConflictKind::PinnedButUnused => "pinned_files",
+        };
+        if entries.iter().any(|e| e == path) {
+            return format!("remove \"{}\" from {}", path, key);
+        }
+        match kind {
+            ConflictKind::DemotedButBoosted if self.demoted.matches(path) => {
+                format!("add \"!{}\" to demoted_files", path)
+            }
+            ConflictKind::DemotedButBoosted => format!("add \"{}\" to focus_files", path),
+            ConflictKind::PinnedButUnused => {
+                let entry = entries
+                    .iter()
+                    .find(|e| PathPatterns::new(std::slice::from_ref(e)).matches(path))
+                    .map_or(String::new(), |e| format!(" \"{}\"", e));
+                format!("narrow the pinned_files entry{} that covers it", entry)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn observed(turns: usize, used: &str) -> Learner {
+        let mut learner = Learner::new();
+        for turn in 0..turns {
+            if turn % 2 == 0 {
+                learner.observe_turn("update the parser", &[used.to_string()]);
+            } else {
+                learner.observe_turn("tidy main", &["src/other.rs".to_string()]);
+            }
+            learner.update_maturity();
+        }
+        learner
+    }
+
+    #[test]
+    fn test_demoted_but_boosted() {
+        let config = Config::from_json(
+            r#"{"demoted_files": ["legacy.rs"], "focus_files": ["src/**", "vendor/**", "legacy.rs"]}"#,
+        )
+        .unwrap();
+        let checker = ConflictChecker::new(&config);
+
+        let learner = observed(60, "vendor/parser.rs");
+        let boost = learner.learned_boost("vendor/parser.rs");
+        let conflict = checker.check("vendor/parser.rs", &learner, boost).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::DemotedButBoosted);
+        assert_eq!((conflict.uses, conflict.turns), (30, 60));
+        assert_eq!(
+            conflict.resolution,
+            "add \"!vendor/parser.rs\" to demoted_files"
+        );
+        assert!(
+            conflict
+                .to_string()
+                .starts_with("demoted, but used in 30 of 60 turns")
+        );
+
+        // Too weak a boost, or a file the rules leave alone
+        assert!(checker.check("vendor/parser.rs", &learner, 0.01).is_none());
+        assert!(checker.check("src/other.rs", &learner, boost).is_none());
+
+        let learner = observed(60, "legacy.rs");
+        let conflict = checker.check("legacy.rs", &learner, boost).unwrap();
+        assert_eq!(
+            conflict.resolution,
+            "remove \"legacy.rs\" from demoted_files"
+        );
+
+        let learner = observed(60, "docs/guide.md");
+        let conflict = checker.check("docs/guide.md", &learner, boost).unwrap();
+        assert_eq!(conflict.resolution, "add \"docs/guide.md\" to focus_files");
+    }
+
+    #[test]
+    fn test_pinned_but_unused() {
+        let config = Config::from_json(r#"{"pinned_files": ["README.md", "docs/"]}"#).unwrap();
+        let checker = ConflictChecker::new(&config);
+
+        let learner = observed(60, "src/main.rs");
+        let conflict = checker.check("README.md", &learner, 0.0).unwrap();
+        assert_eq!(conflict.kind, ConflictKind::PinnedButUnused);
+        assert_eq!(conflict.to_string(), "pinned, but unused in 60 turns");
+        assert_eq!(
+            conflict.resolution,
+            "remove \"README.md\" from pinned_files"
+        );
+        let conflict = checker.check("docs/a.md", &learner, 0.0).unwrap();
+        assert_eq!(
+            conflict.resolution,
+            "narrow the pinned_files entry \"docs/\" that covers it"
+        );
+
+        // Used, or too few turns to tell
+        let learner = observed(60, "README.md");
+        assert!(checker.check("README.md", &learner, 0.0).is_none());
+        let learner = observed(10, "src/main.rs");
+        assert!(checker.check("README.md", &learner, 0.0).is_none());
+    }
+}
@@ -0,0 +1,137 @@
+//! Files referenced by project instruction files (CLAUDE.md, AGENTS.md)
+//!
+//! Instruction files usually point at the modules that matter most, so the
+//! router keeps the files they name at `instruction_floor`. References are
+//! re-read whenever an instruction file is added, removed or modified.
+
+use attentive_telemetry::PathCanonicalizer;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Instruction files looked for at the repo root
+pub const INSTRUCTION_FILES: &[&str] = &["CLAUDE.md", "AGENTS.md"];
+
+/// Most files a single instruction file can soft-pin
+const MAX_REFERENCES: usize = 20;
+
+/// Files named by the instruction files, and the mtimes they were read at
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct InstructionRefs {
+    /// Instruction file name -> mtime (ms since epoch) when last read
+    pub mtimes: BTreeMap<String, u64>,
+    /// Canonical paths of referenced files that exist
+    pub files: Vec<String>,
+}
+
+impl InstructionRefs {
+    /// Re-read the instruction files at the canonicalizer's root if any changed
+    /// since the last read. Returns whether the references were re-read.
+    pub fn refresh(&mut self, canonicalizer: &PathCanonicalizer) -> bool {
+        let root = canonicalizer.root();
+        let mtimes: BTreeMap<String, u64> = INSTRUCTION_FILES
+            .iter()
+            .filter_map(|name| Some((name.to_string(), mtime_ms(&root.join(name))?)))
+            .collect();
+        if mtimes == self.mtimes {
+            return false;
+        }
+
+        let mut files: Vec<String> = Vec::new();
+        for name in mtimes.keys() {
+            let Ok(content) = std::fs::read_to_string(root.join(name)) else {
+                continue;
+            };
+            let existing = extract_references(&content)
+                .into_iter()
+                .filter(|reference| root.join(reference).is_file())
+                .map(|reference| canonicalizer.canonicalize(&reference))
+                .filter(|key| !Path::new(key).is_absolute())
+                .take(MAX_REFERENCES);
+            for key in existing {
+                if !files.contains(&key) {
+                    files.push(key);
+                }
+            }
+        }
+        self.mtimes = mtimes;
+        self.files = files;
+        true
+    }
+}
+
+fn mtime_ms(path: &Path) -> Option<u64> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis() as u64)
+}
+
+/// Relative path-like tokens with a file extension, in order of first
+/// appearance. Whether they exist is left to the caller.
+pub fn extract_references(content: &str) -> Vec<String> {
+    let mut refs: Vec<String> = Vec::new();
+    let tokens = content.split(|c: char| !(c.is_alphanumeric() || "/._-".contains(c)));
+    for token in tokens {
+        let token = token.trim_start_matches("./").trim_end_matches('.');
+        // Absolute paths and the remains of URLs (`//host/...`) are skipped
+        if token.is_empty() || token.starts_with('/') {
+            continue;
+        }
+        let name = token.rsplit('/').next().unwrap_or(token);
+        let has_extension = name.rsplit_once('.').is_some_and(|(stem, ext)| {
+            !stem.is_empty()
+                && (1..=8).contains(&ext.len())
+                && ext.chars().all(|c| c.is_ascii_alphanumeric())
+        });
+        if has_extension && !refs.iter().any(|r| r == token) {
+            refs.push(token.to_string());
+        }
+    }
+    refs
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_extract_references() {
+        let content = "# Guide\n\
+            Routing lives in `src/router.rs` (see ./src/config.rs).\n\
+            - [Schema](docs/schema.md), also src/router.rs.\n\
+            Docs: https://example.com/guide.html, /etc/hosts.conf\n\
+            Bump to v1.2 when done.";
+        assert_eq!(
+            extract_references(content),
+            ["src/router.rs", "src/config.rs", "docs/schema.md", "v1.2"]
+        );
+    }
+
+    #[test]
+    fn test_refresh_reads_existing_files_and_tracks_mtime() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/router.rs"), "").unwrap();
+        std::fs::write(root.join("CLAUDE.md"), "See src/router.rs and src/gone.rs").unwrap();
+        let canonicalizer = PathCanonicalizer::new(root);
+
+        let mut refs = InstructionRefs::default();
+        assert!(refs.refresh(&canonicalizer));
+        assert_eq!(refs.files, ["src/router.rs"]);
+        assert!(
+            !refs.refresh(&canonicalizer),
+            "unchanged files aren't re-read"
+        );
+
+        std::fs::write(root.join("AGENTS.md"), "Start from `src/lib.rs`.").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        assert!(refs.refresh(&canonicalizer));
+        assert_eq!(refs.files, ["src/lib.rs", "src/router.rs"]);
+
+        std::fs::remove_file(root.join("AGENTS.md")).unwrap();
+        std::fs::remove_file(root.join("CLAUDE.md")).unwrap();
+        assert!(refs.refresh(&canonicalizer));
+        assert!(refs.files.is_empty());
+    }
+}
@@ -1,9 +1,35 @@
 //! Core context routing algorithms and advisor logic
 
+// The config JSON schema is one large `serde_json::json!` literal
+#![recursion_limit = "256"]
+
 mod config;
+mod conflicts;
+mod instructions;
+mod metrics;
+mod profile;
+mod provenance;
+mod redact;
 mod router;
+mod scope;
+mod template;
+mod test_pairing;
 mod types;
 
-pub use config::{Config, DecayRates};
-pub use router::Router;
+pub use config::{
+    BackendKind, BranchState, CONFIG_KEYS, CompressionBackendConfig, Config, ConfigIssue,
+    DEFAULT_DEMOTED_FILES, DEFAULT_EXCLUDED_FILES, DecayMatcher, DecayRates, DecayRule,
+    FusionStrategy, NormalizationStrategy, ScoreNormalization, SearchFusion, Severity,
+    StaleObservations, SyncBackend,
+};
+pub use conflicts::{ConfigConflict, ConflictChecker, ConflictKind};
+pub use instructions::{INSTRUCTION_FILES, InstructionRefs, extract_references};
+pub use metrics::{PhaseTiming, RouterMetrics, set_score_tracing};
+pub use profile::{PROFILE_KEYS, Profile, builtin_model_profiles, builtin_profiles};
+pub use provenance::{Provenance, format_provenance};
+pub use redact::{REDACTED, Redactor};
+pub use router::{PathPatterns, Router};
+pub use scope::PathScope;
+pub use template::{ContextTemplates, DashboardVars, SectionVars, WastedFile, render_template};
+pub use test_pairing::{is_test_file, is_test_pair, test_pairs};
 pub use types::{AttentionState, Tier};
@@ -0,0 +1,110 @@
+//! Router instrumentation (per-phase timings and sizes)
+
+use crate::provenance::Provenance;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+
+static TRACE_SCORES: AtomicBool = AtomicBool::new(false);
+
+/// Snapshot every score after each router phase for the rest of the process
+/// (used by `repl`; too costly for hooks)
+pub fn set_score_tracing(on: bool) {
+    TRACE_SCORES.store(on, Ordering::SeqCst);
+}
+
+/// Timing for a single router phase
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PhaseTiming {
+    pub phase: String,
+    pub micros: u64,
+}
+
+/// Metrics collected while routing one prompt
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct RouterMetrics {
+    pub phases: Vec<PhaseTiming>,
+    /// Number of entries in the score map when routing started
+    pub score_count: usize,
+    /// Optional phases skipped or cut short because the latency budget ran out
+    pub skipped: Vec<String>,
+    /// Per-file reasons recorded by the phases that raised its score
+    #[serde(skip)]
+    pub provenance: HashMap<String, Vec<Provenance>>,
+    /// Scores after each phase that changes them, oldest first; only
+    /// recorded with `set_score_tracing`
+    #[serde(skip)]
+    pub score_trace: Vec<(String, HashMap<String, f64>)>,
+}
+
+impl RouterMetrics {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record how long a phase took since `start`
+    pub fn record(&mut self, phase: &str, start: Instant) {
+        self.phases.push(PhaseTiming {
+            phase: phase.to_string(),
+            micros: start.elapsed().as_micros() as u64,
+        });
+    }
+
+    /// Snapshot `scores` as they stand after `phase`, if tracing is on
+    pub fn trace(&mut self, phase: &str, scores: &HashMap<String, f64>) {
+        if TRACE_SCORES.load(Ordering::SeqCst) {
+            self.score_trace.push((phase.to_string(), scores.clone()));
+        }
+    }
+
+    pub fn skip(&mut self, phase: &str) {
+        self.skipped.push(phase.to_string());
+    }
+
+    /// Sum of all recorded phase timings
+    pub fn total(&self) -> Duration {
+        Duration::from_micros(self.phases.iter().map(|p| p.micros).sum())
+    }
+
+    /// Record why `path` was raised (duplicates are ignored)
+    pub fn note(&mut self, path: &str, reason: Provenance) {
+        let reasons = self.provenance.entry(path.to_string()).or_default();
+        if !reasons.contains(&reason) {
+            reasons.push(reason);
+        }
+    }
+
+    /// Reasons recorded for `path`
+    pub fn reasons(&self, path: &str) -> &[Provenance] {
+        self.provenance
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+
+    pub fn budget_exceeded(&self) -> bool {
+        !self.skipped.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_record_and_total() {
+        let mut metrics = RouterMetrics::new();
+        metrics.phases.push(PhaseTiming {
+            phase: "decay".to_string(),
+            micros: 40,
+        });
+        metrics.record("boost", Instant::now());
+        assert_eq!(metrics.phases.len(), 2);
+        assert!(metrics.total() >= Duration::from_micros(40));
+        assert!(!metrics.budget_exceeded());
+
+        metrics.skip("learner_boost");
+        assert!(metrics.budget_exceeded());
+    }
+}
@@ -0,0 +1,143 @@
+//! Named config profiles
+//!
+//! A profile bundles thresholds, budgets and plugin toggles under a name in
+//! attentive.json's `profiles`. The one named by `profile` is applied on top
+//! of the other keys, `model_profiles` swaps in another for the active model
+//! (context limits differ a lot between models), and `task_profiles` another
+//! per prompt based on its classified task type.
+
+use serde::Deserialize;
+use std::collections::BTreeMap;
+
+/// Keys a profile may set
+pub const PROFILE_KEYS: &[&str] = &[
+    "hot_threshold",
+    "warm_threshold",
+    "max_hot_files",
+    "max_warm_files",
+    "context_scale",
+    "max_context_chars",
+    "max_toc_entries",
+    "delta_context",
+    "plugins",
+];
+
+/// Settings a profile overrides; unset fields keep their configured value
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    pub hot_threshold: Option<f64>,
+    pub warm_threshold: Option<f64>,
+    pub max_hot_files: Option<usize>,
+    pub max_warm_files: Option<usize>,
+    /// Multiplier on the injected context budget
+    pub context_scale: Option<f64>,
+    /// Injected context budget in characters, before scaling
+    pub max_context_chars: Option<usize>,
+    /// Most outline entries shown per WARM file
+    pub max_toc_entries: Option<usize>,
+    pub delta_context: Option<bool>,
+    /// Plugin name -> enabled, overriding plugins/config.json
+    pub plugins: BTreeMap<String, bool>,
+}
+
+/// Profiles available without any configuration. A user profile with the
+/// same name replaces the built-in one.
+pub fn builtin_profiles() -> BTreeMap<String, Profile> {
+    let focused = Profile {
+        hot_threshold: Some(0.7),
+        max_hot_files: Some(5),
+        max_warm_files: Some(8),
+        context_scale: Some(1.0),
+        ..Profile::default()
+    };
+    let exploratory = Profile {
+        warm_threshold: Some(0.15),
+        max_hot_files: Some(2),
+        max_warm_files: Some(12),
+        ..Profile::default()
+    };
+    let economy = Profile {
+        hot_threshold: Some(0.9),
+        max_hot_files: Some(1),
+        max_warm_files: Some(2),
+        context_scale: Some(0.4),
+        delta_context: Some(true),
+        plugins: [("xref".to_string(), false)].into(),
+        ..Profile::default()
+    };
+    let compact = Profile {
+        max_hot_files: Some(2),
+        max_warm_files: Some(3),
+        max_context_chars: Some(8_000),
+        max_toc_entries: Some(6),
+        ..Profile::default()
+    };
+    let long_context = Profile {
+        max_hot_files: Some(6),
+        max_warm_files: Some(10),
+        max_context_chars: Some(60_000),
+        max_toc_entries: Some(20),
+        ..Profile::default()
+    };
+    [
+        ("focused".to_string(), focused),
+        ("exploratory".to_string(), exploratory),
+        ("economy".to_string(), economy),
+        ("compact".to_string(), compact),
+        ("long_context".to_string(), long_context),
+    ]
+    .into()
+}
+
+/// Model name fragment -> profile applied while that model is active
+pub fn builtin_model_profiles() -> BTreeMap<String, String> {
+    [
+        ("haiku".to_string(), "compact".to_string()),
+        ("[1m]".to_string(), "long_context".to_string()),
+    ]
+    .into()
+}
+
+impl Profile {
+    /// One-line summary of what the profile sets
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some(v) = self.hot_threshold {
+            parts.push(format!("hot_threshold={}", v));
+        }
+        if let Some(v) = self.warm_threshold {
+            parts.push(format!("warm_threshold={}", v));
+        }
+        if let Some(v) = self.max_hot_files {
+            parts.push(format!("max_hot_files={}", v));
+        }
+        if let Some(v) = self.max_warm_files {
+            parts.push(format!("max_warm_files={}", v));
+        }
+        if let Some(v) = self.context_scale {
+            parts.push(format!("context_scale={}", v));
+        }
+        if let Some(v) = self.max_context_chars {
+            parts.push(format!("max_context_chars={}", v));
+        }
+        if let Some(v) = self.max_toc_entries {
+            parts.push(format!("max_toc_entries={}", v));
+        }
+        if let Some(v) = self.delta_context {
+            parts.push(format!("delta_context={}", v));
+        }
+        for (plugin, enabled) in &self.plugins {
+            parts.push(format!(
+                "{}={}",
+                plugin,
+                if *enabled { "on" } else { "off" }
+            ));
+        }
+        if parts.is_empty() {
+            "no overrides".to_string()
+        } else {
+            parts.join(", ")
+        }
+    }
+}
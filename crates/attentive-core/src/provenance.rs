@@ -0,0 +1,99 @@
+//! Why a file ended up in the injected context
+
+use std::fmt;
+
+/// One reason recorded for a file while routing a prompt
+#[derive(Debug, Clone, PartialEq)]
+pub enum Provenance {
+    /// Matches a pinned file, directory or glob
+    Pinned,
+    /// Path or file name appears in the prompt
+    Mentioned,
+    /// A learned keyword rule ties `keyword` in the prompt to the file
+    KeywordRule { keyword: String },
+    /// Boosted as a co-activation neighbor of `with`
+    CoActivated { with: String },
+    /// Boosted by learned prompt-file associations
+    LearnedAffinity(f64),
+    /// Touched during the previous session
+    LastSession,
+    /// Among the most frequently used files
+    Frequent,
+    /// Requested back from the cold index
+    Recalled,
+    /// References a symbol modified last turn
+    Caller,
+    /// Edited within the last few turns
+    RecentlyEdited,
+    /// Source or test counterpart of `with`
+    TestPair { with: String },
+    /// Referenced in CLAUDE.md or AGENTS.md
+    Instructions,
+    /// Search index match for a prompt nothing else routed
+    Searched,
+    /// Usually needed by prompts of this task type
+    TaskPrefetch { task: String },
+    /// Reported active by an external tool, e.g. open in the editor
+    Editor,
+    /// The file focused in the editor
+    ActiveBuffer,
+}
+
+impl fmt::Display for Provenance {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Provenance::Pinned => write!(f, "pinned"),
+            Provenance::Mentioned => write!(f, "mentioned in prompt"),
+            Provenance::KeywordRule { keyword } => write!(f, "keyword rule \"{}\"", keyword),
+            Provenance::CoActivated { with } => {
+                let name = with.rsplit('/').next().unwrap_or(with);
+                write!(f, "co-activated with {}", name)
+            }
+            Provenance::LearnedAffinity(affinity) => write!(f, "learned affinity {:.1}", affinity),
+            Provenance::LastSession => write!(f, "used last session"),
+            Provenance::Frequent => write!(f, "frequently used"),
+            Provenance::Recalled => write!(f, "recalled"),
+            Provenance::Caller => write!(f, "calls modified code"),
+            Provenance::RecentlyEdited => write!(f, "edited recently"),
+            Provenance::TestPair { with } => {
+                let name = with.rsplit('/').next().unwrap_or(with);
+                write!(f, "test pair of {}", name)
+            }
+            Provenance::Instructions => write!(f, "referenced in project instructions"),
+            Provenance::Searched => write!(f, "search match"),
+            Provenance::TaskPrefetch { task } => write!(f, "usual for {} tasks", task),
+            Provenance::Editor => write!(f, "open in editor"),
+            Provenance::ActiveBuffer => write!(f, "active in editor"),
+        }
+    }
+}
+
+/// Comma-separated provenance tags, or `None` when nothing was recorded
+pub fn format_provenance(reasons: &[Provenance]) -> Option<String> {
+    if reasons.is_empty() {
+        return None;
+    }
+    let tags: Vec<String> = reasons.iter().map(|r| r.to_string()).collect();
+    Some(tags.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_provenance() {
+        let reasons = vec![
+            Provenance::Pinned,
+            Provenance::CoActivated {
+                with: "src/router.rs".to_string(),
+            },
+            Provenance::LearnedAffinity(0.72),
+        ];
+        assert_eq!(
+            format_provenance(&reasons).unwrap(),
+            "pinned, co-activated with router.rs, learned affinity 0.7"
+        );
+        assert!(format_provenance(&[]).is_none());
+    }
+}
@@ -1,29 +1,126 @@
 //! 7-phase attention router
 
-use crate::config::Config;
+use crate::config::{Config, DecayMatcher, NormalizationStrategy, ScoreNormalization};
+use crate::metrics::RouterMetrics;
+use crate::provenance::Provenance;
+use crate::test_pairing::test_pairs;
 use crate::types::{AttentionState, Tier};
+use globset::{Glob, GlobSet, GlobSetBuilder};
 use petgraph::graph::{Graph, NodeIndex};
-use petgraph::visit::Bfs;
+use petgraph::visit::EdgeRef;
 use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+/// Learner boosts smaller than this aren't worth a provenance tag
+const MIN_NOTED_AFFINITY: f64 = 0.05;
 
 #[derive(Debug)]
 pub struct Router {
     config: Config,
-    co_activation_graph: Option<Graph<String, ()>>,
+    co_activation_graph: Option<Graph<String, f64>>,
     node_indices: HashMap<String, NodeIndex>,
+    pinned: PathPatterns,
+    demoted: PathPatterns,
+    focus: PathPatterns,
+    decay: DecayMatcher,
+    /// Files external tools report as active, see `with_active_files`
+    active: HashSet<String>,
+    /// The editor's focused file, see `with_active_buffer`
+    active_buffer: Option<String>,
+    /// Learned keyword→file rules, see `with_keyword_rules`
+    keyword_rules: attentive_learn::Predictor,
+}
+
+/// Literal paths, directories (`dir/`) and globs, as in `pinned_files`.
+/// A `!`-prefixed pattern exempts the paths it matches from all the others.
+#[derive(Debug)]
+pub struct PathPatterns {
+    literals: Vec<String>,
+    globs: GlobSet,
+    negated: Vec<String>,
+    negated_globs: GlobSet,
+}
+
+impl PathPatterns {
+    pub fn new(patterns: &[String]) -> Self {
+        let (negated, literals): (Vec<String>, Vec<String>) =
+            patterns.iter().cloned().partition(|p| p.starts_with('!'));
+        let negated: Vec<String> = negated.iter().map(|p| p[1..].to_string()).collect();
+        Self {
+            globs: build_path_globset(&literals),
+            literals,
+            negated_globs: build_path_globset(&negated),
+            negated,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.literals.is_empty()
+    }
+
+    /// Whether `path` matches any of the patterns and none of the negated ones
+    pub fn matches(&self, path: &str) -> bool {
+        let exempt = self.negated.iter().any(|p| p == path) || self.negated_globs.is_match(path);
+        !exempt && (self.literals.iter().any(|p| p == path) || self.globs.is_match(path))
+    }
 }
 
 impl Router {
     pub fn new(config: Config) -> Self {
-        let (graph, indices) = build_co_activation_graph(&config.co_activation);
+        let (graph, indices) = build_co_activation_graph(&config);
+        let pinned = PathPatterns::new(&config.pinned_files);
+        let demoted = PathPatterns::new(&config.demoted_patterns());
+        let focus = PathPatterns::new(&config.focus_files);
+        let decay = config.decay_rates.matcher();
 
         Self {
             config,
             co_activation_graph: Some(graph),
             node_indices: indices,
+            pinned,
+            demoted,
+            focus,
+            decay,
+            active: HashSet::new(),
+            active_buffer: None,
+            keyword_rules: attentive_learn::Predictor::new(),
         }
     }
 
+    /// Treat `files` (canonical paths an external tool reports as active,
+    /// e.g. open in the editor) as activated: each gains `active_file_boost`,
+    /// escapes the demoted penalty and warms its co-activation neighbors, as
+    /// a file mentioned in the prompt would
+    pub fn with_active_files(mut self, files: impl IntoIterator<Item = String>) -> Self {
+        self.active = files.into_iter().collect();
+        self
+    }
+
+    /// Like `with_active_files` for the file focused in the editor, which
+    /// gains `active_buffer_boost` instead
+    pub fn with_active_buffer(mut self, path: Option<String>) -> Self {
+        self.active_buffer = path;
+        self
+    }
+
+    /// Activate the file of each keyword rule (`attentive learn rules`) whose
+    /// keyword is in the prompt, as if the prompt had mentioned it
+    pub fn with_keyword_rules(mut self, rules: attentive_learn::Predictor) -> Self {
+        self.keyword_rules = rules;
+        self
+    }
+
+    /// Whether `path` matches a pinned file, directory or glob
+    pub fn is_pinned(&self, path: &str) -> bool {
+        self.pinned.matches(path)
+    }
+
+    /// Whether `path` matches a demoted file, directory or glob, or falls
+    /// outside `focus_files` when that is set
+    pub fn is_demoted(&self, path: &str) -> bool {
+        self.demoted.matches(path) || (!self.focus.is_empty() && !self.focus.matches(path))
+    }
+
     /// Update attention scores based on prompt (7-phase with optional learner integration)
     pub fn update_attention(
         &self,
@@ -31,108 +128,298 @@ impl Router {
         prompt: &str,
         learner: Option<&attentive_learn::Learner>,
     ) -> HashSet<String> {
-        let directly_activated = HashSet::new();
+        self.update_attention_with_metrics(state, prompt, learner, &mut RouterMetrics::new())
+    }
+
+    /// Same as `update_attention`, recording per-phase timings into `metrics`.
+    ///
+    /// Once `config.latency_budget_ms` is spent, optional phases are cut:
+    /// co-activation stops after 1 hop and the learner boost is skipped.
+    pub fn update_attention_with_metrics(
+        &self,
+        state: &mut AttentionState,
+        prompt: &str,
+        learner: Option<&attentive_learn::Learner>,
+        metrics: &mut RouterMetrics,
+    ) -> HashSet<String> {
+        self.update_attention_adjusted(state, prompt, learner, metrics, &mut |_, _| {})
+    }
+
+    /// Same as `update_attention_with_metrics`, letting `adjust` change the
+    /// scores after the learner boost and before streaks are counted by tier
+    pub fn update_attention_adjusted(
+        &self,
+        state: &mut AttentionState,
+        prompt: &str,
+        learner: Option<&attentive_learn::Learner>,
+        metrics: &mut RouterMetrics,
+        adjust: &mut dyn FnMut(&mut HashMap<String, f64>, &str),
+    ) -> HashSet<String> {
+        let started = Instant::now();
+        let budget = Duration::from_millis(self.config.latency_budget_ms);
+        metrics.score_count = state.scores.len();
+        // Boosts may overflow 1.0 when a normalization step brings them back
+        let cap = match self.config.score_normalization.strategy {
+            NormalizationStrategy::Clamp => 1.0,
+            _ => f64::INFINITY,
+        };
 
         // Ensure consecutive_turns exists
         for path in state.scores.keys() {
             state.consecutive_turns.entry(path.clone()).or_insert(0);
         }
+        metrics.trace("start", &state.scores);
 
-        // Phase 1: Decay with learned rates
+        // Phase 1: Decay. Configured rates win, then learned rates, then the
+        // default. A turn spent planning only takes its share of the decay, so
+        // files don't cool while Claude thinks about them.
+        let phase_start = Instant::now();
+        let decay_scale = if std::mem::take(&mut state.planning_turn) {
+            self.config.planning_decay_scale
+        } else {
+            1.0
+        };
         for (path, score) in &mut state.scores {
-            let decay = if let Some(l) = learner {
-                l.get_file_decay(path)
-            } else {
-                self.config.decay_rates.get_decay(path)
+            let decay = match (self.decay.rule_for(path), learner) {
+                (Some((_, rate)), _) => rate,
+                (None, Some(l)) => l.get_file_decay(path),
+                (None, None) => self.decay.default_rate(),
             };
-            *score *= decay;
+            *score *= 1.0 - (1.0 - decay) * decay_scale;
         }
+        metrics.record("decay", phase_start);
+        metrics.trace("decay", &state.scores);
 
-        // Phase 2: Co-activation (direct neighbors + 2-hop transitive via BFS)
-        if let Some(graph) = &self.co_activation_graph {
-            let mut boosts: HashMap<String, f64> = HashMap::new();
-
-            for activated_path in &directly_activated {
-                if let Some(&node_idx) = self.node_indices.get(activated_path) {
-                    // BFS to find neighbors up to 2 hops
-                    let mut bfs = Bfs::new(graph, node_idx);
-                    let mut visited = HashSet::new();
-                    let mut hop_count = HashMap::new();
-                    hop_count.insert(node_idx, 0);
-
-                    while let Some(current_idx) = bfs.next(graph) {
-                        if visited.contains(&current_idx) {
-                            continue;
-                        }
-                        visited.insert(current_idx);
+        // With `activate_mentioned`, files named in the prompt (full path or
+        // file name) go straight to HOT
+        let prompt_lower = prompt.to_lowercase();
+        let mut directly_activated: HashSet<String> = if self.config.activate_mentioned {
+            state
+                .scores
+                .keys()
+                .filter(|path| is_mentioned(&prompt_lower, path))
+                .cloned()
+                .collect()
+        } else {
+            HashSet::new()
+        };
+        for path in &directly_activated {
+            state.scores.insert(path.clone(), 1.0);
+            metrics.note(path, Provenance::Mentioned);
+        }
+        // So do the files learned keyword rules tie the prompt's words to
+        for (keyword, path) in self.keyword_rules.keyword_matches(prompt) {
+            if directly_activated.insert(path.clone()) {
+                state.scores.insert(path.clone(), 1.0);
+                metrics.note(&path, Provenance::KeywordRule { keyword });
+            }
+        }
 
-                        let current_hop = hop_count.get(&current_idx).copied().unwrap_or(0);
-                        if current_hop > 2 {
-                            continue;
-                        }
+        // Files external tools report as active get a boost instead, the
+        // focused editor buffer the largest
+        let mut boosts: HashMap<&String, (f64, Provenance)> = HashMap::new();
+        if self.config.active_file_boost > 0.0 {
+            for path in &self.active {
+                boosts.insert(path, (self.config.active_file_boost, Provenance::Editor));
+            }
+        }
+        if let Some(path) = &self.active_buffer
+            && self.config.active_buffer_boost > 0.0
+        {
+            boosts.insert(
+                path,
+                (self.config.active_buffer_boost, Provenance::ActiveBuffer),
+            );
+        }
+        let mut externally_activated = HashSet::new();
+        for (path, (boost, reason)) in boosts {
+            if directly_activated.contains(path) {
+                continue;
+            }
+            let score = state.scores.entry(path.clone()).or_insert(0.0);
+            *score = (*score + boost).min(cap);
+            metrics.note(path, reason);
+            externally_activated.insert(path.clone());
+        }
+        let activated: Vec<&String> = directly_activated
+            .iter()
+            .chain(&externally_activated)
+            .collect();
 
-                        // Get path for this node
-                        if let Some(neighbor_path) = graph.node_weight(current_idx) {
-                            if current_idx != node_idx {
-                                // Direct neighbor (1-hop) or transitive (2-hop)
-                                let boost = if current_hop == 1 {
-                                    self.config.coactivation_boost // 0.35
-                                } else {
-                                    self.config.transitive_boost // 0.15
-                                };
-
-                                boosts
-                                    .entry(neighbor_path.clone())
-                                    .and_modify(|b| *b = b.max(boost))
-                                    .or_insert(boost);
-                            }
-
-                            // Track hop count for neighbors
-                            for neighbor_idx in graph.neighbors(current_idx) {
-                                hop_count.entry(neighbor_idx).or_insert(current_hop + 1);
-                            }
+        // Phase 2: Co-activation (direct neighbors + 2-hop transitive, by edge strength)
+        let phase_start = Instant::now();
+        let max_hops = if started.elapsed() > budget {
+            metrics.skip("coactivation_transitive");
+            1
+        } else {
+            2
+        };
+        if let Some(graph) = &self.co_activation_graph {
+            // Configured edges plus learned ones, the stronger of the two
+            let neighbors = |path: &str| -> HashMap<String, f64> {
+                let mut out: HashMap<String, f64> = HashMap::new();
+                let mut offer = |to: &String, weight: f64| {
+                    let entry = out.entry(to.clone()).or_insert(weight);
+                    *entry = entry.max(weight);
+                };
+                if let Some(&idx) = self.node_indices.get(path) {
+                    for edge in graph.edges(idx) {
+                        offer(&graph[edge.target()], *edge.weight());
+                    }
+                }
+                if let Some(learner) = learner {
+                    for (to, weight) in learner.coactivation_neighbors(path) {
+                        offer(&to, weight);
+                    }
+                }
+                out.remove(path);
+                out
+            };
+
+            let mut boosts: HashMap<String, (f64, &String)> = HashMap::new();
+            for &activated_path in &activated {
+                for (hop1, weight1) in neighbors(activated_path) {
+                    // Boosts scale with edge strength, multiplied along 2-hop paths
+                    let boost = self.config.coactivation_boost * weight1;
+                    keep_strongest(&mut boosts, &hop1, boost, activated_path);
+                    if max_hops < 2 {
+                        continue;
+                    }
+                    for (hop2, weight2) in neighbors(&hop1) {
+                        if hop2 != *activated_path {
+                            let boost = self.config.transitive_boost * weight1 * weight2;
+                            keep_strongest(&mut boosts, &hop2, boost, activated_path);
                         }
                     }
                 }
             }
 
             // Apply boosts
-            for (path, boost) in boosts {
+            for (path, (boost, source)) in boosts {
+                if let Some(score) = state.scores.get_mut(&path) {
+                    *score = (*score + boost).min(cap);
+                    metrics.note(
+                        &path,
+                        Provenance::CoActivated {
+                            with: source.clone(),
+                        },
+                    );
+                }
+            }
+        }
+
+        // Implicit edges between a source file and its tests: mentioning or
+        // editing either side warms the other
+        if self.config.pair_tests {
+            let sources: HashSet<&String> = activated
+                .iter()
+                .copied()
+                .chain(state.recently_edited.keys())
+                .collect();
+            let mut pairs: HashMap<String, String> = HashMap::new();
+            for source in sources {
+                for pair in test_pairs(source, state.scores.keys()) {
+                    pairs.entry(pair.clone()).or_insert_with(|| source.clone());
+                }
+            }
+            for (path, source) in pairs {
                 if let Some(score) = state.scores.get_mut(&path) {
-                    *score = (*score + boost).min(1.0);
+                    *score = (*score + self.config.coactivation_boost).min(cap);
+                    metrics.note(&path, Provenance::TestPair { with: source });
                 }
             }
         }
+        metrics.record("coactivation", phase_start);
+        metrics.trace("coactivation", &state.scores);
 
         // Phase 3: Pinned file floor
-        for pinned_path in &self.config.pinned_files {
-            if let Some(score) = state.scores.get_mut(pinned_path) {
-                let floor = self.config.warm_threshold + self.config.pinned_floor_boost;
+        let phase_start = Instant::now();
+        let floor = self.config.warm_threshold + self.config.pinned_floor_boost;
+        for (path, score) in &mut state.scores {
+            if self.is_pinned(path) {
                 *score = score.max(floor);
+                metrics.note(path, Provenance::Pinned);
+            }
+        }
+
+        // Phase 3b: Soft pins from CLAUDE.md / AGENTS.md (demotion still applies)
+        if self.config.instruction_floor > 0.0 {
+            for path in &state.instruction_refs.files {
+                let score = state.scores.entry(path.clone()).or_insert(0.0);
+                *score = score.max(self.config.instruction_floor);
+                metrics.note(path, Provenance::Instructions);
             }
         }
 
         // Phase 4: Demoted file penalty
-        for demoted_path in &self.config.demoted_files {
-            if directly_activated.contains(demoted_path) {
+        for (path, score) in &mut state.scores {
+            if directly_activated.contains(path) || externally_activated.contains(path) {
                 continue;
             }
-            if let Some(score) = state.scores.get_mut(demoted_path) {
+            if self.is_demoted(path) {
                 *score *= self.config.demoted_penalty;
             }
         }
 
+        // Phase 4b: Files edited in the last few turns stay at least WARM
+        let window = self.config.edit_floor_turns;
+        let turn = state.turn_count;
+        state
+            .recently_edited
+            .retain(|_, edited| turn.saturating_sub(*edited) < window);
+        for path in state.recently_edited.keys() {
+            let score = state.scores.entry(path.clone()).or_insert(0.0);
+            *score = score.max(self.config.warm_threshold);
+            metrics.note(path, Provenance::RecentlyEdited);
+        }
+        metrics.record("pin_demote", phase_start);
+        metrics.trace("pin_demote", &state.scores);
+
         // Phase 5: Learner boost (learned prompt-file associations)
+        let phase_start = Instant::now();
         if let Some(l) = learner {
-            let boosts = l.boost_scores(prompt, &state.scores);
-            for (path, boosted_score) in boosts {
-                if let Some(score) = state.scores.get_mut(&path) {
-                    *score = boosted_score;
+            if started.elapsed() > budget {
+                metrics.skip("learner_boost");
+            } else {
+                let boosts = l.boost_scores_with_cap(prompt, &state.scores, cap);
+                for (path, boosted_score) in boosts {
+                    if let Some(score) = state.scores.get_mut(&path) {
+                        let affinity = boosted_score - *score;
+                        *score = boosted_score;
+                        if affinity >= MIN_NOTED_AFFINITY {
+                            metrics.note(&path, Provenance::LearnedAffinity(affinity));
+                        }
+                    }
                 }
             }
         }
+        metrics.record("learner_boost", phase_start);
+        metrics.trace("learner_boost", &state.scores);
+
+        // Phase 5a: Bring overflowing scores back within 1.0, keeping their
+        // order; files named in the prompt stay at the top
+        if cap > 1.0 {
+            let phase_start = Instant::now();
+            normalize_scores(
+                &mut state.scores,
+                &self.config.score_normalization,
+                self.config.warm_threshold,
+            );
+            for path in &directly_activated {
+                state.scores.insert(path.clone(), 1.0);
+            }
+            metrics.record("normalize", phase_start);
+            metrics.trace("normalize", &state.scores);
+        }
+
+        // Phase 5b: Caller adjustments (plugin score hooks)
+        let phase_start = Instant::now();
+        adjust(&mut state.scores, prompt);
+        metrics.record("adjust", phase_start);
+        metrics.trace("adjust", &state.scores);
 
         // Phase 6: Update consecutive_turns for cache stability
+        let phase_start = Instant::now();
         for (path, &score) in &state.scores {
             let tier = Tier::from_score(score);
             if matches!(tier, Tier::Hot | Tier::Warm) {
@@ -141,6 +428,7 @@ impl Router {
                 state.consecutive_turns.insert(path.clone(), 0);
             }
         }
+        metrics.record("streaks", phase_start);
 
         state.turn_count += 1;
         directly_activated
@@ -151,6 +439,16 @@ impl Router {
         &self,
         state: &AttentionState,
     ) -> (Vec<String>, Vec<String>, Vec<String>) {
+        self.build_context_output_with_metrics(state, &mut RouterMetrics::new())
+    }
+
+    /// Same as `build_context_output`, recording its timing into `metrics`
+    pub fn build_context_output_with_metrics(
+        &self,
+        state: &AttentionState,
+        metrics: &mut RouterMetrics,
+    ) -> (Vec<String>, Vec<String>, Vec<String>) {
+        let phase_start = Instant::now();
         let mut hot_files = Vec::new();
         let mut warm_files = Vec::new();
         let mut cold_files = Vec::new();
@@ -167,8 +465,8 @@ impl Router {
 
         // Cache stability sort: pinned first, then by streak, then by score
         let sort_fn = |a: &(String, f64), b: &(String, f64)| {
-            let a_pinned = self.config.pinned_files.contains(&a.0);
-            let b_pinned = self.config.pinned_files.contains(&b.0);
+            let a_pinned = self.is_pinned(&a.0);
+            let b_pinned = self.is_pinned(&b.0);
             let a_streak = state.consecutive_turns.get(&a.0).copied().unwrap_or(0);
             let b_streak = state.consecutive_turns.get(&b.0).copied().unwrap_or(0);
 
@@ -190,6 +488,7 @@ impl Router {
         // Apply limits
         hot_files.truncate(self.config.max_hot_files);
         warm_files.truncate(self.config.max_warm_files);
+        metrics.record("context_output", phase_start);
 
         (
             hot_files.into_iter().map(|(p, _)| p).collect(),
@@ -199,15 +498,101 @@ impl Router {
     }
 }
 
-fn build_co_activation_graph(
-    co_activation: &HashMap<String, Vec<String>>,
-) -> (Graph<String, ()>, HashMap<String, NodeIndex>) {
+/// Map scores above `floor` onto [floor, 1] as `normalization` says, if any
+/// score exceeds 1.0. Order is kept and scores at or below `floor` are left
+/// alone, so files stay at least WARM when `floor` is the WARM threshold.
+fn normalize_scores(
+    scores: &mut HashMap<String, f64>,
+    normalization: &ScoreNormalization,
+    floor: f64,
+) {
+    let top = scores.values().copied().fold(f64::MIN, f64::max);
+    if top <= 1.0 {
+        return;
+    }
+    let span = 1.0 - floor;
+    for score in scores.values_mut().filter(|s| **s > floor) {
+        *score = match normalization.strategy {
+            NormalizationStrategy::Clamp => score.min(1.0),
+            NormalizationStrategy::Rescale => floor + (*score - floor) * span / (top - floor),
+            NormalizationStrategy::Softmax => {
+                floor + span * ((*score - top) / normalization.temperature).exp()
+            }
+        };
+    }
+}
+
+/// Whether the prompt names `path`, either in full or by file name.
+/// File names need an extension so bare words like "router" don't activate.
+fn is_mentioned(prompt_lower: &str, path: &str) -> bool {
+    let path_lower = path.to_lowercase();
+    let name = path_lower.rsplit('/').next().unwrap_or(&path_lower);
+    [path_lower.as_str(), name]
+        .into_iter()
+        .filter(|needle| needle.contains('.') && needle.len() >= 4)
+        .any(|needle| {
+            prompt_lower.match_indices(needle).any(|(start, _)| {
+                let before = prompt_lower[..start].chars().next_back();
+                let after = prompt_lower[start + needle.len()..].chars().next();
+                let boundary = |c: Option<char>| {
+                    c.is_none_or(|c| !(c.is_alphanumeric() || c == '_' || c == '/'))
+                };
+                boundary(before) && boundary(after)
+            })
+        })
+}
+
+/// Compile pinned/demoted entries into a GlobSet.
+///
+/// Entries ending in `/` match everything beneath that directory, and relative
+/// globs also match when the path is absolute (`docs/**` matches
+/// `/repo/docs/a.md`). As in `.gitignore`, a bare file name matches at any
+/// depth, but a path without glob metacharacters names only itself. Invalid
+/// patterns are skipped with a warning.
+fn build_path_globset(patterns: &[String]) -> GlobSet {
+    let mut builder = GlobSetBuilder::new();
+    for pattern in patterns {
+        let pattern = if pattern.ends_with('/') {
+            format!("{}**", pattern)
+        } else {
+            pattern.clone()
+        };
+        let mut variants = vec![pattern.clone()];
+        let anywhere = pattern.contains(['*', '?', '[', '{']) || !pattern.contains('/');
+        if anywhere && !pattern.starts_with('/') && !pattern.starts_with("**") {
+            variants.push(format!("**/{}", pattern));
+        }
+        for variant in variants {
+            match Glob::new(&variant) {
+                Ok(glob) => {
+                    builder.add(glob);
+                }
+                Err(e) => tracing::warn!("ignoring invalid path pattern {:?}: {}", variant, e),
+            }
+        }
+    }
+    builder.build().unwrap_or_else(|_| GlobSet::empty())
+}
+
+/// Record `boost` for `path` unless another source already boosts it more
+fn keep_strongest<'a>(
+    boosts: &mut HashMap<String, (f64, &'a String)>,
+    path: &str,
+    boost: f64,
+    source: &'a String,
+) {
+    if boost > boosts.get(path).map_or(0.0, |b| b.0) {
+        boosts.insert(path.to_string(), (boost, source));
+    }
+}
+
+fn build_co_activation_graph(config: &Config) -> (Graph<String, f64>, HashMap<String, NodeIndex>) {
     let mut graph = Graph::new();
     let mut node_indices = HashMap::new();
 
     // Add all nodes
     let mut all_nodes = HashSet::new();
-    for (from, to_list) in co_activation {
+    for (from, to_list) in &config.co_activation {
         all_nodes.insert(from.clone());
         all_nodes.extend(to_list.iter().cloned());
     }
@@ -217,12 +602,12 @@ fn build_co_activation_graph(
         node_indices.insert(node, idx);
     }
 
-    // Add edges
-    for (from, to_list) in co_activation {
+    // Add edges, weighted by their configured strength
+    for (from, to_list) in &config.co_activation {
         if let Some(&from_idx) = node_indices.get(from) {
             for to in to_list {
                 if let Some(&to_idx) = node_indices.get(to) {
-                    graph.add_edge(from_idx, to_idx, ());
+                    graph.add_edge(from_idx, to_idx, config.co_activation_weight(from, to));
                 }
             }
         }
@@ -249,6 +634,35 @@ mod tests {
         assert!(*state.scores.get("file1.md").unwrap() > 0.6);
     }
 
+    #[test]
+    fn test_planning_turn_skips_decay() {
+        let router = Router::new(Config::new());
+        let mut state = AttentionState::new();
+        state.scores.insert("file1.md".to_string(), 0.5);
+        state.planning_turn = true;
+
+        router.update_attention(&mut state, "other prompt", None);
+        assert_eq!(state.scores["file1.md"], 0.5);
+        assert!(!state.planning_turn);
+
+        // Only the planning turn is spared
+        router.update_attention(&mut state, "other prompt", None);
+        assert!(state.scores["file1.md"] < 0.5);
+
+        // A partial scale takes that share of the usual decay
+        let mut config = Config::new();
+        config.planning_decay_scale = 0.5;
+        let router = Router::new(config);
+        let mut normal = AttentionState::new();
+        normal.scores.insert("file1.md".to_string(), 1.0);
+        let mut planned = normal.clone();
+        planned.planning_turn = true;
+        router.update_attention(&mut normal, "other prompt", None);
+        router.update_attention(&mut planned, "other prompt", None);
+        let lost = 1.0 - normal.scores["file1.md"];
+        assert!((1.0 - planned.scores["file1.md"] - lost / 2.0).abs() < 1e-9);
+    }
+
     #[test]
     fn test_build_context_output() {
         let config = Config::new();
@@ -346,5 +760,523 @@ mod tests {
             score
         );
         assert!(score < 0.9, "Decay should still apply: {}", score);
+
+        // A configured rate beats the learned one
+        let mut config = Config::new();
+        config.decay_rates.rates.insert("freq.md".to_string(), 0.5);
+        let mut state = AttentionState::new();
+        state.scores.insert("freq.md".to_string(), 1.0);
+        Router::new(config).update_attention(&mut state, "unrelated", Some(&learner));
+        assert!((state.scores["freq.md"] - 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_metrics_record_phases() {
+        let router = Router::new(Config::new());
+        let mut state = AttentionState::new();
+        state.scores.insert("a.md".to_string(), 0.9);
+
+        let mut metrics = RouterMetrics::new();
+        router.update_attention_with_metrics(&mut state, "prompt", None, &mut metrics);
+        router.build_context_output_with_metrics(&state, &mut metrics);
+
+        assert_eq!(metrics.score_count, 1);
+        let phases: Vec<&str> = metrics.phases.iter().map(|p| p.phase.as_str()).collect();
+        assert_eq!(
+            phases,
+            vec![
+                "decay",
+                "coactivation",
+                "pin_demote",
+                "learner_boost",
+                "adjust",
+                "streaks",
+                "context_output"
+            ]
+        );
+        assert!(!metrics.budget_exceeded());
+    }
+
+    #[test]
+    fn test_adjusted_scores_count_toward_streaks() {
+        let router = Router::new(Config::new());
+        let mut state = AttentionState::new();
+        state.scores.insert("a.md".to_string(), 0.1);
+
+        let mut seen = String::new();
+        router.update_attention_adjusted(
+            &mut state,
+            "prompt",
+            None,
+            &mut RouterMetrics::new(),
+            &mut |scores, prompt| {
+                seen = prompt.to_string();
+                scores.insert("a.md".to_string(), 0.9);
+            },
+        );
+        assert_eq!(seen, "prompt");
+        assert_eq!(state.get_tier("a.md"), Some(Tier::Hot));
+        assert_eq!(state.consecutive_turns["a.md"], 1);
+    }
+
+    #[test]
+    fn test_zero_budget_skips_learner_boost() {
+        let mut learner = attentive_learn::Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("fix the router bug", &["router.rs".to_string()]);
+        }
+
+        let config = Config {
+            latency_budget_ms: 0,
+            ..Config::new()
+        };
+        let router = Router::new(config);
+        let mut state = AttentionState::new();
+        state.scores.insert("router.rs".to_string(), 0.1);
+
+        let mut metrics = RouterMetrics::new();
+        router.update_attention_with_metrics(
+            &mut state,
+            "fix the router bug",
+            Some(&learner),
+            &mut metrics,
+        );
+        assert!(metrics.skipped.contains(&"learner_boost".to_string()));
+        assert!(
+            metrics
+                .skipped
+                .contains(&"coactivation_transitive".to_string())
+        );
+    }
+
+    #[test]
+    fn test_focus_demotes_files_outside_it() {
+        let config = Config {
+            focus_files: vec!["src/auth/**".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config);
+        assert!(!router.is_demoted("src/auth/login.rs"));
+        assert!(router.is_demoted("src/router.rs"));
+        assert!(!Router::new(Config::new()).is_demoted("src/router.rs"));
+    }
+
+    #[test]
+    fn test_default_demotions_and_negated_patterns() {
+        let router = Router::new(Config::new());
+        assert!(router.is_demoted("package-lock.json"));
+        assert!(router.is_demoted("web/dist/app.js"));
+        assert!(router.is_demoted("assets/app.min.js"));
+        assert!(router.is_demoted("vendor/github.com/x/y.go"));
+        assert!(!router.is_demoted("src/distance.rs"));
+
+        let config = Config {
+            demoted_files: vec!["!vendor/ours/**".to_string(), "old/".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config);
+        assert!(router.is_demoted("vendor/theirs/a.go"));
+        assert!(!router.is_demoted("vendor/ours/a.go"));
+        assert!(router.is_demoted("old/a.rs"));
+
+        let config = Config {
+            default_file_rules: false,
+            ..Config::new()
+        };
+        assert!(!Router::new(config).is_demoted("package-lock.json"));
+    }
+
+    #[test]
+    fn test_glob_pinning_and_directory_prefix() {
+        let config = Config {
+            pinned_files: vec!["docs/architecture/**".to_string(), "notes/".to_string()],
+            demoted_files: vec!["**/__snapshots__/**".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config);
+
+        assert!(router.is_pinned("docs/architecture/overview.md"));
+        assert!(router.is_pinned("/repo/docs/architecture/deep/adr.md"));
+        assert!(router.is_pinned("notes/todo.md"));
+        assert!(!router.is_pinned("docs/guide.md"));
+        assert!(router.is_demoted("src/__snapshots__/router.snap"));
+        assert!(!router.is_demoted("src/router.rs"));
+
+        let mut state = AttentionState::new();
+        state
+            .scores
+            .insert("docs/architecture/overview.md".to_string(), 0.0);
+        state
+            .scores
+            .insert("src/__snapshots__/router.snap".to_string(), 1.0);
+        router.update_attention(&mut state, "prompt", None);
+
+        assert!(state.scores["docs/architecture/overview.md"] >= 0.35);
+        assert!(state.scores["src/__snapshots__/router.snap"] < 0.5);
+    }
+
+    #[test]
+    fn test_invalid_glob_is_ignored() {
+        let config = Config {
+            pinned_files: vec!["src/[".to_string(), "exact.md".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config);
+        assert!(router.is_pinned("exact.md"));
+        assert!(!router.is_pinned("src/lib.rs"));
+    }
+
+    #[test]
+    fn test_exact_path_matches_only_itself() {
+        let patterns = PathPatterns::new(&["src/main.rs".to_string()]);
+        assert!(patterns.matches("src/main.rs"));
+        assert!(!patterns.matches("examples/src/main.rs"));
+        assert!(!patterns.matches("/repo/src/main.rs"));
+
+        let patterns = PathPatterns::new(&["src/*.rs".to_string(), "Cargo.lock".to_string()]);
+        assert!(patterns.matches("crates/core/src/lib.rs"));
+        assert!(patterns.matches("crates/core/Cargo.lock"));
+    }
+
+    #[test]
+    fn test_mentioned_files_activate_and_coactivate() {
+        let mut config = Config {
+            activate_mentioned: true,
+            ..Config::new()
+        };
+        config.co_activation.insert(
+            "src/router.rs".to_string(),
+            vec!["src/config.rs".to_string()],
+        );
+        let router = Router::new(config);
+
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.1);
+        state.scores.insert("src/config.rs".to_string(), 0.1);
+        state.scores.insert("src/main.rs".to_string(), 0.1);
+
+        let mut metrics = RouterMetrics::new();
+        let activated = router.update_attention_with_metrics(
+            &mut state,
+            "why does router.rs decay so fast?",
+            None,
+            &mut metrics,
+        );
+        assert!(activated.contains("src/router.rs"));
+        assert_eq!(state.scores["src/router.rs"], 1.0);
+        assert_eq!(metrics.reasons("src/router.rs"), [Provenance::Mentioned]);
+        assert_eq!(
+            metrics.reasons("src/config.rs"),
+            [Provenance::CoActivated {
+                with: "src/router.rs".to_string()
+            }]
+        );
+        assert!(metrics.reasons("src/main.rs").is_empty());
+
+        // Off by default: naming a file changes nothing
+        let router = Router::new(Config::new());
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.1);
+        let activated =
+            router.update_attention(&mut state, "why does router.rs decay so fast?", None);
+        assert!(activated.is_empty());
+        assert!(state.scores["src/router.rs"] < 0.1);
+    }
+
+    #[test]
+    fn test_coactivation_boosts_scale_with_edge_strength() {
+        let config = Config::from_json(
+            r#"{"activate_mentioned": true, "co_activation": {
+                "router.rs": {"config.rs": 0.5, "types.rs": 1.0},
+                "config.rs": {"schema.rs": 0.4}
+            }}"#,
+        )
+        .unwrap();
+        let router = Router::new(config);
+        let mut learner = attentive_learn::Learner::new();
+        for _ in 0..4 {
+            learner.observe_turn(
+                "routing",
+                &["router.rs".to_string(), "metrics.rs".to_string()],
+            );
+        }
+        learner.observe_turn("metrics", &["metrics.rs".to_string()]);
+
+        let mut state = AttentionState::new();
+        for path in [
+            "router.rs",
+            "config.rs",
+            "types.rs",
+            "schema.rs",
+            "metrics.rs",
+        ] {
+            state.scores.insert(path.to_string(), 0.0);
+        }
+        router.update_attention(&mut state, "look at router.rs", Some(&learner));
+
+        let close = |path: &str, expected: f64| {
+            let score = state.scores[path];
+            assert!((score - expected).abs() < 1e-9, "{}: {}", path, score);
+        };
+        close("types.rs", 0.35);
+        close("config.rs", 0.35 * 0.5);
+        // 2-hop: transitive_boost times both edge strengths
+        close("schema.rs", 0.15 * 0.5 * 0.4);
+        // Learned edge: Jaccard 4/5
+        close("metrics.rs", 0.35 * 0.8);
+    }
+
+    #[test]
+    fn test_mentioned_or_edited_files_warm_their_tests() {
+        let router = Router::new(Config {
+            activate_mentioned: true,
+            ..Config::new()
+        });
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.0);
+        state.scores.insert("tests/router.rs".to_string(), 0.0);
+        state.scores.insert("src/config.rs".to_string(), 0.0);
+        state.scores.insert("src/config_test.rs".to_string(), 0.0);
+        state.mark_edited(&["src/config.rs".to_string()]);
+
+        let mut metrics = RouterMetrics::new();
+        router.update_attention_with_metrics(&mut state, "fix src/router.rs", None, &mut metrics);
+        assert_eq!(state.get_tier("tests/router.rs"), Some(Tier::Warm));
+        assert_eq!(
+            metrics.reasons("tests/router.rs"),
+            [Provenance::TestPair {
+                with: "src/router.rs".to_string()
+            }]
+        );
+        assert!(state.scores["src/config_test.rs"] >= 0.35);
+
+        let config = Config {
+            pair_tests: false,
+            activate_mentioned: true,
+            ..Config::new()
+        };
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.0);
+        state.scores.insert("tests/router.rs".to_string(), 0.0);
+        Router::new(config).update_attention(&mut state, "fix src/router.rs", None);
+        assert_eq!(state.scores["tests/router.rs"], 0.0);
+    }
+
+    #[test]
+    fn test_instruction_refs_are_soft_pinned() {
+        let config = Config {
+            demoted_files: vec!["docs/".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config);
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.0);
+        state.instruction_refs.files =
+            vec!["src/router.rs".to_string(), "docs/guide.md".to_string()];
+
+        let mut metrics = RouterMetrics::new();
+        router.update_attention_with_metrics(&mut state, "next step", None, &mut metrics);
+        assert_eq!(state.get_tier("src/router.rs"), Some(Tier::Warm));
+        assert_eq!(metrics.reasons("src/router.rs"), [Provenance::Instructions]);
+        // Unlike pinned files, demotion still wins
+        assert_eq!(state.get_tier("docs/guide.md"), Some(Tier::Cold));
+
+        let config = Config {
+            instruction_floor: 0.0,
+            ..Config::new()
+        };
+        let mut state = AttentionState::new();
+        state.instruction_refs.files = vec!["src/router.rs".to_string()];
+        Router::new(config).update_attention(&mut state, "next step", None);
+        assert!(!state.scores.contains_key("src/router.rs"));
+    }
+
+    #[test]
+    fn test_normalize_scores_keeps_order() {
+        let scores: HashMap<String, f64> = [
+            ("top.rs".to_string(), 1.75),
+            ("next.rs".to_string(), 1.0),
+            ("warm.rs".to_string(), 0.5),
+            ("cold.rs".to_string(), 0.1),
+        ]
+        .into();
+        let normalized = |strategy, temperature| {
+            let mut scores = scores.clone();
+            let normalization = ScoreNormalization {
+                strategy,
+                temperature,
+            };
+            normalize_scores(&mut scores, &normalization, 0.25);
+            scores
+        };
+
+        let clamped = normalized(NormalizationStrategy::Clamp, 0.5);
+        assert_eq!((clamped["top.rs"], clamped["next.rs"]), (1.0, 1.0));
+        assert_eq!(clamped["warm.rs"], 0.5);
+
+        // [0.25, 1.75] onto [0.25, 1]: halves the distance above the floor
+        let rescaled = normalized(NormalizationStrategy::Rescale, 0.5);
+        assert_eq!(rescaled["top.rs"], 1.0);
+        assert!((rescaled["next.rs"] - 0.625).abs() < 1e-9);
+        assert!((rescaled["warm.rs"] - 0.375).abs() < 1e-9);
+        assert_eq!(rescaled["cold.rs"], 0.1);
+
+        let softmax = normalized(NormalizationStrategy::Softmax, 0.5);
+        assert_eq!(softmax["top.rs"], 1.0);
+        assert!((softmax["next.rs"] - (0.25 + 0.75 * (-1.5f64).exp())).abs() < 1e-9);
+        assert!(softmax["next.rs"] > softmax["warm.rs"] && softmax["warm.rs"] > 0.25);
+        assert_eq!(softmax["cold.rs"], 0.1);
+
+        // Nothing overflows: left alone
+        let mut within: HashMap<String, f64> = [("a.rs".to_string(), 0.9)].into();
+        let rescale = ScoreNormalization {
+            strategy: NormalizationStrategy::Rescale,
+            ..Default::default()
+        };
+        normalize_scores(&mut within, &rescale, 0.25);
+        assert_eq!(within["a.rs"], 0.9);
+    }
+
+    #[test]
+    fn test_score_normalization_separates_boosted_files() {
+        let route = |strategy| {
+            let config = Config {
+                active_file_boost: 0.6,
+                activate_mentioned: true,
+                score_normalization: ScoreNormalization {
+                    strategy,
+                    ..Default::default()
+                },
+                ..Config::new()
+            };
+            let router = Router::new(config)
+                .with_active_files(["src/a.rs".to_string(), "src/b.rs".to_string()]);
+            let mut state = AttentionState::new();
+            state.scores.insert("src/a.rs".to_string(), 1.0);
+            state.scores.insert("src/b.rs".to_string(), 0.6);
+            state.scores.insert("src/named.rs".to_string(), 0.0);
+            let mut metrics = RouterMetrics::new();
+            router.update_attention_with_metrics(
+                &mut state,
+                "look at named.rs",
+                None,
+                &mut metrics,
+            );
+            (state.scores, metrics)
+        };
+
+        let (scores, metrics) = route(NormalizationStrategy::Clamp);
+        assert_eq!((scores["src/a.rs"], scores["src/b.rs"]), (1.0, 1.0));
+        assert!(metrics.phases.iter().all(|p| p.phase != "normalize"));
+
+        let (scores, metrics) = route(NormalizationStrategy::Rescale);
+        assert_eq!(scores["src/a.rs"], 1.0);
+        assert!(scores["src/b.rs"] < 1.0 && scores["src/b.rs"] >= 0.25);
+        assert_eq!(scores["src/named.rs"], 1.0);
+        assert!(metrics.phases.iter().any(|p| p.phase == "normalize"));
+    }
+
+    #[test]
+    fn test_keyword_rules_activate_their_files() {
+        let mut state = AttentionState::new();
+        state.scores.insert("src/decay.rs".to_string(), 0.3);
+        let route = |router: &Router, state: &mut AttentionState| {
+            let mut metrics = RouterMetrics::new();
+            router.update_attention_with_metrics(state, "tweak the falloff", None, &mut metrics);
+            metrics
+        };
+
+        let mut without = state.clone();
+        route(&Router::new(Config::new()), &mut without);
+        assert!(without.get_hot_files().is_empty());
+
+        let mut rules = attentive_learn::Predictor::new();
+        rules.set_strong_keyword("falloff", "src/decay.rs");
+        rules.set_strong_keyword("unrelated", "src/other.rs");
+        let metrics = route(
+            &Router::new(Config::new()).with_keyword_rules(rules),
+            &mut state,
+        );
+        assert_eq!(state.get_hot_files(), ["src/decay.rs"]);
+        assert_eq!(
+            metrics.reasons("src/decay.rs"),
+            [Provenance::KeywordRule {
+                keyword: "falloff".to_string()
+            }]
+        );
+    }
+
+    #[test]
+    fn test_active_files_boosted() {
+        let config = Config {
+            demoted_files: vec!["docs/".to_string()],
+            ..Config::new()
+        };
+        let router = Router::new(config)
+            .with_active_files(["src/open.rs".to_string(), "docs/open.md".to_string()]);
+        let mut state = AttentionState::new();
+        state.scores.insert("docs/open.md".to_string(), 0.5);
+
+        let mut metrics = RouterMetrics::new();
+        router.update_attention_with_metrics(&mut state, "next step", None, &mut metrics);
+        // New files start from 0; known ones decay first (docs/ at 0.75: 0.5 * 0.75 + 0.5)
+        assert_eq!(state.scores["src/open.rs"], 0.5);
+        assert!((state.scores["docs/open.md"] - 0.875).abs() < 1e-9);
+        assert_eq!(metrics.reasons("src/open.rs"), [Provenance::Editor]);
+        // Escapes the demoted penalty like a mentioned file
+        assert_eq!(state.get_tier("docs/open.md"), Some(Tier::Hot));
+
+        // The focused buffer gets the larger boost
+        let mut state = AttentionState::new();
+        let mut metrics = RouterMetrics::new();
+        Router::new(Config::new())
+            .with_active_files(["src/open.rs".to_string(), "src/focused.rs".to_string()])
+            .with_active_buffer(Some("src/focused.rs".to_string()))
+            .update_attention_with_metrics(&mut state, "next step", None, &mut metrics);
+        assert_eq!(state.get_tier("src/focused.rs"), Some(Tier::Hot));
+        assert_eq!(state.get_tier("src/open.rs"), Some(Tier::Warm));
+        assert_eq!(
+            metrics.reasons("src/focused.rs"),
+            [Provenance::ActiveBuffer]
+        );
+
+        let config = Config {
+            active_file_boost: 0.0,
+            ..Config::new()
+        };
+        let mut state = AttentionState::new();
+        Router::new(config)
+            .with_active_files(["src/open.rs".to_string()])
+            .update_attention(&mut state, "next step", None);
+        assert!(!state.scores.contains_key("src/open.rs"));
+    }
+
+    #[test]
+    fn test_recently_edited_floor_expires() {
+        let router = Router::new(Config::new()); // edit_floor_turns = 3
+        let mut state = AttentionState::new();
+        state.scores.insert("src/lib.rs".to_string(), 0.05);
+        state.mark_edited(&["src/lib.rs".to_string()]);
+
+        for _ in 0..3 {
+            let mut metrics = RouterMetrics::new();
+            router.update_attention_with_metrics(&mut state, "next step", None, &mut metrics);
+            assert_eq!(state.get_tier("src/lib.rs"), Some(Tier::Warm));
+            assert_eq!(metrics.reasons("src/lib.rs"), [Provenance::RecentlyEdited]);
+        }
+
+        // Past the window the floor lifts and decay takes over
+        router.update_attention(&mut state, "next step", None);
+        assert!(state.recently_edited.is_empty());
+        assert_eq!(state.get_tier("src/lib.rs"), Some(Tier::Cold));
+    }
+
+    #[test]
+    fn test_is_mentioned_boundaries() {
+        assert!(is_mentioned("look at router.rs please", "src/router.rs"));
+        assert!(is_mentioned("see `src/router.rs`", "src/router.rs"));
+        assert!(!is_mentioned("fix the router", "src/router.rs"));
+        assert!(!is_mentioned("check myrouter.rs", "src/router.rs"));
+        assert!(!is_mentioned("edit lib/router.rs", "src/router.rs"));
     }
 }
@@ -0,0 +1,81 @@
+//! Which files may enter attention state
+//!
+//! Tool calls also touch files outside the project (global configs, /tmp,
+//! other checkouts). Scores and learned associations only keep canonical
+//! repo-relative paths, plus absolute paths allowed by `external_files`.
+
+use globset::{Glob, GlobSet, GlobSetBuilder};
+use std::path::Path;
+
+/// Expand a leading `~/` to `home`
+fn expand_home(pattern: &str, home: &Path) -> String {
+    match pattern.strip_prefix("~/") {
+        Some(rest) => home.join(rest).to_string_lossy().replace('\\', "/"),
+        None => pattern.to_string(),
+    }
+}
+
+/// Filter for canonical paths (see `PathCanonicalizer::canonicalize`)
+#[derive(Debug, Clone)]
+pub struct PathScope {
+    external: GlobSet,
+}
+
+impl PathScope {
+    /// Scope keeping repo files and absolute paths matching `external` (exact
+    /// paths or globs, `~/` expanded to `home`). Invalid globs are skipped.
+    pub fn new(external: &[String], home: &Path) -> Self {
+        let mut homes = vec![home.to_path_buf()];
+        if let Ok(resolved) = std::fs::canonicalize(home)
+            && resolved != home
+        {
+            homes.push(resolved);
+        }
+        let mut builder = GlobSetBuilder::new();
+        for pattern in external {
+            for home in &homes {
+                if let Ok(glob) = Glob::new(&expand_home(pattern, home)) {
+                    builder.add(glob);
+                }
+            }
+        }
+        Self {
+            external: builder.build().unwrap_or_else(|_| GlobSet::empty()),
+        }
+    }
+
+    /// Whether `canonical` is inside the repo or explicitly allowed
+    pub fn allows(&self, canonical: &str) -> bool {
+        !Path::new(canonical).is_absolute() || self.external.is_match(canonical)
+    }
+
+    /// `files` without the ones out of scope
+    pub fn retain(&self, files: Vec<String>) -> Vec<String> {
+        files.into_iter().filter(|f| self.allows(f)).collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scope_keeps_repo_and_allowlisted_files() {
+        let scope = PathScope::new(
+            &[
+                "~/.claude/CLAUDE.md".to_string(),
+                "/opt/shared/**".to_string(),
+            ],
+            Path::new("/home/dev"),
+        );
+        assert!(scope.allows("src/lib.rs"));
+        assert!(scope.allows("/home/dev/.claude/CLAUDE.md"));
+        assert!(scope.allows("/opt/shared/notes/a.md"));
+        assert!(!scope.allows("/tmp/scratch.rs"));
+        assert!(!scope.allows("/home/dev/other-repo/src/lib.rs"));
+        assert_eq!(
+            scope.retain(vec!["a.rs".to_string(), "/etc/hosts".to_string()]),
+            vec!["a.rs"]
+        );
+    }
+}
@@ -0,0 +1,124 @@
+//! User templates for injected context sections
+//!
+//! `templates` in attentive.json replaces the built-in layout of HOT and WARM
+//! sections and of the session-start dashboard with minijinja templates, so
+//! the injected context can follow a team's prompt conventions:
+//!
+//! ```json
+//! {"templates": {"hot": "<file path=\"{{ path }}\" score=\"{{ score }}\">\n{{ content }}\n</file>"}}
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+/// Templates from `templates`; unset ones keep the built-in layout
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ContextTemplates {
+    /// HOT section, rendered with `SectionVars`
+    pub hot: Option<String>,
+    /// WARM section, rendered with `SectionVars`
+    pub warm: Option<String>,
+    /// Session-start dashboard, rendered with `DashboardVars`
+    pub dashboard: Option<String>,
+}
+
+/// Variables of a HOT or WARM section template
+#[derive(Debug, Serialize)]
+pub struct SectionVars<'a> {
+    pub path: &'a str,
+    /// `HOT` or `WARM`
+    pub tier: &'a str,
+    /// Attention score after routing
+    pub score: f64,
+    /// Why the file was included; empty unless `provenance_tags` is on
+    pub provenance: &'a str,
+    /// `full`, `digest`, `toc` or `summary`
+    pub view: &'a str,
+    /// File content, digest, outline or observation summary
+    pub content: &'a str,
+}
+
+/// Most wasted file listed on the dashboard
+#[derive(Debug, Serialize)]
+pub struct WastedFile {
+    pub path: String,
+    pub injected: usize,
+    pub used: usize,
+}
+
+/// Variables of the dashboard template
+#[derive(Debug, Serialize)]
+pub struct DashboardVars<'a> {
+    /// The built-in dashboard text
+    pub content: &'a str,
+    /// Turns the dashboard covers
+    pub turns: usize,
+    /// Average share of injected context left unused, in percent
+    pub waste: Option<f64>,
+    pub top_waste: &'a [WastedFile],
+}
+
+impl ContextTemplates {
+    fn entries(&self) -> [(&'static str, Option<&String>); 3] {
+        [
+            ("hot", self.hot.as_ref()),
+            ("warm", self.warm.as_ref()),
+            ("dashboard", self.dashboard.as_ref()),
+        ]
+    }
+
+    /// Syntax errors as (key, message) for each template that fails to parse
+    pub fn errors(&self) -> Vec<(&'static str, String)> {
+        let env = minijinja::Environment::new();
+        self.entries()
+            .into_iter()
+            .filter_map(|(key, template)| {
+                let error = env.template_from_str(template?).err()?;
+                Some((key, error.to_string()))
+            })
+            .collect()
+    }
+}
+
+/// Render `template` with `vars` (no autoescaping)
+pub fn render_template(template: &str, vars: impl Serialize) -> Result<String, minijinja::Error> {
+    minijinja::Environment::new().render_str(template, vars)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_render_section_template() {
+        let vars = SectionVars {
+            path: "src/lib.rs",
+            tier: "HOT",
+            score: 0.9,
+            provenance: "",
+            view: "full",
+            content: "fn a() {}",
+        };
+        let rendered = render_template(
+            "<file path=\"{{ path }}\" tier=\"{{ tier | lower }}\">{% if provenance %} ({{ provenance }}){% endif %}\n{{ content }}\n</file>",
+            &vars,
+        )
+        .unwrap();
+        assert_eq!(
+            rendered,
+            "<file path=\"src/lib.rs\" tier=\"hot\">\nfn a() {}\n</file>"
+        );
+    }
+
+    #[test]
+    fn test_errors_name_broken_templates() {
+        let templates = ContextTemplates {
+            hot: Some("{{ path }}".to_string()),
+            warm: Some("{% if %}".to_string()),
+            dashboard: None,
+        };
+        let errors = templates.errors();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].0, "warm");
+    }
+}
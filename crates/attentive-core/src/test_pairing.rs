@@ -0,0 +1,162 @@
+//! Source ↔ test file pairing by naming convention
+//!
+//! A source file and its tests are almost always needed together, so the
+//! router treats each pair as an implicit co-activation edge. Recognized
+//! conventions:
+//!
+//! - Rust: `foo.rs` ↔ `foo_test.rs`, `tests/foo.rs`
+//! - Go: `foo.go` ↔ `foo_test.go`
+//! - Python: `foo.py` ↔ `test_foo.py`, `foo_test.py`, `tests/test_foo.py`
+//! - JS/TS: `foo.ts` ↔ `foo.spec.ts`, `foo.test.ts`, `__tests__/foo.ts`
+
+/// File stems too generic to pair on (`tests/common/mod.rs` is not the test of
+/// every `mod.rs`)
+const GENERIC_STEMS: &[&str] = &[
+    "mod", "lib", "main", "index", "__init__", "conftest", "common", "utils", "setup",
+];
+
+/// Directory names stripped from the end of a directory to find where a test
+/// and its source meet
+const LAYOUT_DIRS: &[&str] = &["src", "tests", "test", "__tests__", "spec", "lib"];
+
+#[derive(Debug, PartialEq)]
+struct Classified<'a> {
+    lang: &'static str,
+    subject: &'a str,
+    is_test: bool,
+    anchor: &'a str,
+}
+
+fn classify(path: &str) -> Option<Classified<'_>> {
+    let (dir, name) = path.rsplit_once('/').unwrap_or(("", path));
+    let (stem, ext) = name.rsplit_once('.')?;
+    let lang = match ext {
+        "rs" => "rust",
+        "go" => "go",
+        "py" => "python",
+        "ts" | "tsx" | "js" | "jsx" | "mjs" | "cjs" => "js",
+        _ => return None,
+    };
+    let in_dir = |names: &[&str]| dir.split('/').any(|c| names.contains(&c));
+
+    let (subject, is_test) = match lang {
+        "rust" => match stem.strip_suffix("_test") {
+            Some(base) => (base, true),
+            None => (stem, in_dir(&["tests"])),
+        },
+        "go" => match stem.strip_suffix("_test") {
+            Some(base) => (base, true),
+            None => (stem, false),
+        },
+        "python" => match stem.strip_prefix("test_").or(stem.strip_suffix("_test")) {
+            Some(base) => (base, true),
+            None => (stem, false),
+        },
+        _ => match stem.strip_suffix(".spec").or(stem.strip_suffix(".test")) {
+            Some(base) => (base, true),
+            None => (stem, in_dir(&["__tests__"])),
+        },
+    };
+    if subject.is_empty() || GENERIC_STEMS.contains(&subject) {
+        return None;
+    }
+
+    let mut anchor = dir;
+    loop {
+        let (parent, last) = anchor.rsplit_once('/').unwrap_or(("", anchor));
+        if anchor.is_empty() || !LAYOUT_DIRS.contains(&last) {
+            break;
+        }
+        anchor = parent;
+    }
+
+    Some(Classified {
+        lang,
+        subject,
+        is_test,
+        anchor,
+    })
+}
+
+/// Whether `path` is a test file by the conventions above
+pub fn is_test_file(path: &str) -> bool {
+    classify(path).is_some_and(|c| c.is_test)
+}
+
+/// Whether `a` and `b` are a source file and its test (in either order)
+pub fn is_test_pair(a: &str, b: &str) -> bool {
+    let (Some(a), Some(b)) = (classify(a), classify(b)) else {
+        return false;
+    };
+    if a.lang != b.lang || a.subject != b.subject || a.is_test == b.is_test {
+        return false;
+    }
+    let (test, source) = if a.is_test { (&a, &b) } else { (&b, &a) };
+    // A test sits beside its source or in a tests/ directory at or above it
+    test.anchor.is_empty()
+        || source.anchor == test.anchor
+        || source
+            .anchor
+            .strip_prefix(test.anchor)
+            .is_some_and(|rest| rest.starts_with('/'))
+}
+
+/// Files among `known` that pair with `path` as source and test
+pub fn test_pairs<'a>(path: &str, known: impl IntoIterator<Item = &'a String>) -> Vec<&'a String> {
+    if classify(path).is_none() {
+        return Vec::new();
+    }
+    known
+        .into_iter()
+        .filter(|other| other.as_str() != path && is_test_pair(path, other))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pairs_by_language_convention() {
+        let pairs = [
+            ("src/router.rs", "src/router_test.rs"),
+            ("crates/core/src/router.rs", "crates/core/tests/router.rs"),
+            ("pkg/server.go", "pkg/server_test.go"),
+            ("app/models.py", "app/test_models.py"),
+            ("app/models.py", "tests/test_models.py"),
+            ("src/api/client.ts", "src/api/client.spec.ts"),
+            ("src/api/client.tsx", "src/api/client.test.tsx"),
+            ("src/api/client.js", "src/api/__tests__/client.js"),
+        ];
+        for (source, test) in pairs {
+            assert!(is_test_pair(source, test), "{source} <-> {test}");
+            assert!(is_test_pair(test, source), "{test} <-> {source}");
+            assert!(is_test_file(test) && !is_test_file(source));
+        }
+    }
+
+    #[test]
+    fn test_rejects_non_pairs() {
+        // Two sources, other language, other crate, generic stems
+        assert!(!is_test_pair("src/router.rs", "src/router.rs"));
+        assert!(!is_test_pair("src/router.rs", "src/router_test.go"));
+        assert!(!is_test_pair(
+            "crates/a/src/router.rs",
+            "crates/b/tests/router.rs"
+        ));
+        assert!(!is_test_pair("src/foo/mod.rs", "tests/common/mod.rs"));
+        assert!(!is_test_pair("README.md", "README_test.md"));
+    }
+
+    #[test]
+    fn test_pairs_among_known_files() {
+        let known: Vec<String> = ["src/a.rs", "src/a_test.rs", "tests/a.rs", "src/b.rs"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+        let mut pairs = test_pairs("src/a.rs", &known);
+        pairs.sort();
+        assert_eq!(pairs, ["src/a_test.rs", "tests/a.rs"]);
+        assert_eq!(test_pairs("tests/a.rs", &known), ["src/a.rs"]);
+    }
+}
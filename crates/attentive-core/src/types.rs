@@ -1,5 +1,6 @@
 //! Core types for attention routing
 
+use crate::instructions::InstructionRefs;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -39,6 +40,21 @@ pub struct AttentionState {
     /// Total turn count
     #[serde(default)]
     pub turn_count: usize,
+    /// Files edited this session -> turn_count when last edited
+    #[serde(default)]
+    pub recently_edited: HashMap<String, usize>,
+    /// Files referenced in CLAUDE.md / AGENTS.md (soft-pinned)
+    #[serde(default)]
+    pub instruction_refs: InstructionRefs,
+    /// The last turn ran no tools and touched no files, so the next update
+    /// decays scores by `planning_decay_scale` of the usual amount
+    #[serde(default)]
+    pub planning_turn: bool,
+}
+
+impl attentive_telemetry::Versioned for AttentionState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "attention state";
 }
 
 impl AttentionState {
@@ -47,6 +63,99 @@ impl AttentionState {
             scores: HashMap::new(),
             consecutive_turns: HashMap::new(),
             turn_count: 0,
+            recently_edited: HashMap::new(),
+            instruction_refs: InstructionRefs::default(),
+            planning_turn: false,
+        }
+    }
+
+    /// Record files edited during the current turn
+    pub fn mark_edited(&mut self, files: &[String]) {
+        for file in files {
+            self.recently_edited.insert(file.clone(), self.turn_count);
+        }
+    }
+
+    /// Move scores and streaks from `old` to `new`, keeping the higher score.
+    /// Returns the number of entries moved.
+    pub fn remap_file(&mut self, old: &str, new: &str) -> usize {
+        self.rekey_files(|key| attentive_telemetry::remap_path(key, old, new))
+    }
+
+    /// Rewrite every path key into canonical form, merging keys that collapse
+    /// together. Returns the number of entries changed.
+    pub fn canonicalize_files(
+        &mut self,
+        canonicalizer: &attentive_telemetry::PathCanonicalizer,
+    ) -> usize {
+        self.rekey_files(|key| {
+            let canonical = canonicalizer.canonicalize(key);
+            (canonical != key).then_some(canonical)
+        })
+    }
+
+    /// Drop every file `keep` rejects (e.g. paths outside the project).
+    /// Returns the number of scored files removed.
+    pub fn prune_files(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let before = self.scores.len();
+        self.scores.retain(|f, _| keep(f));
+        self.consecutive_turns.retain(|f, _| keep(f));
+        self.recently_edited.retain(|f, _| keep(f));
+        self.instruction_refs.files.retain(|f| keep(f));
+        before - self.scores.len()
+    }
+
+    fn rekey_files(&mut self, target: impl Fn(&str) -> Option<String>) -> usize {
+        for file in &mut self.instruction_refs.files {
+            if let Some(target) = target(file) {
+                *file = target;
+            }
+        }
+
+        let edited: Vec<String> = self.recently_edited.keys().cloned().collect();
+        for key in edited {
+            if let Some(target) = target(&key)
+                && let Some(turn) = self.recently_edited.remove(&key)
+            {
+                let entry = self.recently_edited.entry(target).or_insert(turn);
+                *entry = (*entry).max(turn);
+            }
+        }
+
+        let mut moved = 0;
+        let keys: Vec<String> = self.scores.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = target(&key)
+                && let Some(score) = self.scores.remove(&key)
+            {
+                let entry = self.scores.entry(target.clone()).or_insert(score);
+                *entry = entry.max(score);
+                if let Some(streak) = self.consecutive_turns.remove(&key) {
+                    let entry = self.consecutive_turns.entry(target).or_insert(streak);
+                    *entry = (*entry).max(streak);
+                }
+                moved += 1;
+            }
+        }
+        moved
+    }
+
+    /// Fold in `other`'s scores, streaks and edits (e.g. from another
+    /// machine), keeping the higher of each. Instruction references are
+    /// re-read locally, so they stay as they are.
+    pub fn merge(&mut self, other: &AttentionState) {
+        self.turn_count = self.turn_count.max(other.turn_count);
+        for (file, &score) in &other.scores {
+            let entry = self.scores.entry(file.clone()).or_insert(score);
+            *entry = entry.max(score);
+        }
+        for (file, &streak) in &other.consecutive_turns {
+            let entry = self.consecutive_turns.entry(file.clone()).or_insert(streak);
+            *entry = (*entry).max(streak);
+        }
+        for (file, &turn) in &other.recently_edited {
+            let entry = self.recently_edited.entry(file.clone()).or_insert(turn);
+            *entry = (*entry).max(turn);
         }
     }
 
@@ -100,4 +209,63 @@ mod tests {
         assert_eq!(parsed.scores.get("file1.md"), Some(&0.9));
         assert_eq!(parsed.consecutive_turns.get("file1.md"), Some(&3));
     }
+
+    #[test]
+    fn test_remap_file_keeps_higher_score() {
+        let mut state = AttentionState::new();
+        state.scores.insert("src/old.rs".to_string(), 0.9);
+        state.scores.insert("src/new.rs".to_string(), 0.3);
+        state.scores.insert("crates/x/src/old.rs".to_string(), 0.5);
+        state.consecutive_turns.insert("src/old.rs".to_string(), 4);
+
+        assert_eq!(state.remap_file("src/old.rs", "src/new.rs"), 1);
+        assert!(!state.scores.contains_key("src/old.rs"));
+        assert_eq!(state.scores["src/new.rs"], 0.9);
+        assert_eq!(state.consecutive_turns["src/new.rs"], 4);
+        assert_eq!(state.scores["crates/x/src/old.rs"], 0.5);
+    }
+
+    #[test]
+    fn test_merge_keeps_higher_scores() {
+        let mut state = AttentionState::new();
+        state.scores.insert("a.rs".to_string(), 0.9);
+        state.scores.insert("b.rs".to_string(), 0.2);
+        state.turn_count = 3;
+        let mut other = AttentionState::new();
+        other.scores.insert("b.rs".to_string(), 0.6);
+        other.scores.insert("c.rs".to_string(), 0.4);
+        other.turn_count = 7;
+
+        state.merge(&other);
+        assert_eq!(state.scores["a.rs"], 0.9);
+        assert_eq!(state.scores["b.rs"], 0.6);
+        assert_eq!(state.scores["c.rs"], 0.4);
+        assert_eq!(state.turn_count, 7);
+    }
+
+    #[test]
+    fn test_prune_files() {
+        let mut state = AttentionState::new();
+        state.scores.insert("src/a.rs".to_string(), 0.9);
+        state.scores.insert("/tmp/b.rs".to_string(), 0.9);
+        state.consecutive_turns.insert("/tmp/b.rs".to_string(), 2);
+        state.mark_edited(&["/tmp/b.rs".to_string()]);
+
+        assert_eq!(state.prune_files(|f| !f.starts_with('/')), 1);
+        assert_eq!(state.scores.len(), 1);
+        assert!(state.consecutive_turns.is_empty());
+        assert!(state.recently_edited.is_empty());
+    }
+
+    #[test]
+    fn test_canonicalize_files_merges_absolute_and_relative() {
+        let mut state = AttentionState::new();
+        state.scores.insert("/repo/src/a.rs".to_string(), 0.4);
+        state.scores.insert("src/a.rs".to_string(), 0.7);
+
+        let canonicalizer = attentive_telemetry::PathCanonicalizer::new("/repo");
+        assert_eq!(state.canonicalize_files(&canonicalizer), 1);
+        assert_eq!(state.scores.len(), 1);
+        assert_eq!(state.scores["src/a.rs"], 0.7);
+    }
 }
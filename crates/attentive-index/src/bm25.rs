@@ -1,16 +1,19 @@
 //! Hand-rolled BM25 implementation
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 const K1: f64 = 1.5;
 const B: f64 = 0.75;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BM25 {
     doc_count: usize,
     avg_doc_len: f64,
     doc_lens: Vec<usize>,
     doc_ids: Vec<String>,
+    /// Term -> occurrences, per document
+    term_freqs: Vec<HashMap<String, usize>>,
     idf: HashMap<String, f64>,
 }
 
@@ -21,6 +24,7 @@ impl BM25 {
             avg_doc_len: 0.0,
             doc_lens: Vec::new(),
             doc_ids: Vec::new(),
+            term_freqs: Vec::new(),
             idf: HashMap::new(),
         }
     }
@@ -38,6 +42,11 @@ impl BM25 {
             let len = tokens.len();
             self.doc_lens.push(len);
             total_len += len;
+            let mut freqs: HashMap<String, usize> = HashMap::new();
+            for token in tokens {
+                *freqs.entry(token.clone()).or_insert(0) += 1;
+            }
+            self.term_freqs.push(freqs);
         }
 
         self.avg_doc_len = total_len as f64 / self.doc_count as f64;
@@ -58,18 +67,28 @@ impl BM25 {
     }
 
     pub fn search(&self, query_tokens: &[String], k: usize) -> Vec<(String, f64)> {
+        self.search_where(query_tokens, k, |_| true)
+    }
+
+    /// Like `search`, over only the documents `keep` accepts
+    pub fn search_where(
+        &self,
+        query_tokens: &[String],
+        k: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<(String, f64)> {
         if self.doc_count == 0 {
             return Vec::new();
         }
 
+        // Documents sharing no term with the query aren't matches
         let mut scores: Vec<(String, f64)> = self
             .doc_ids
             .iter()
             .enumerate()
-            .map(|(idx, doc_id)| {
-                let score = self.compute_score(idx, query_tokens);
-                (doc_id.clone(), score)
-            })
+            .filter(|(_, doc_id)| keep(doc_id))
+            .map(|(idx, doc_id)| (doc_id.clone(), self.compute_score(idx, query_tokens)))
+            .filter(|(_, score)| *score > 0.0)
             .collect();
 
         scores.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
@@ -81,12 +100,13 @@ impl BM25 {
         let doc_len = self.doc_lens[doc_idx] as f64;
         let mut score = 0.0;
 
+        let norm = K1 * (1.0 - B + B * doc_len / self.avg_doc_len);
         for term in query_tokens {
-            if let Some(&idf) = self.idf.get(term) {
-                // For simplicity, assume tf = 1 if term present
-                // In full implementation, would count term frequency
-                let norm = 1.0 + K1 * (1.0 - B + B * doc_len / self.avg_doc_len);
-                score += idf * K1 / norm;
+            let tf = self.term_freqs[doc_idx].get(term).copied().unwrap_or(0) as f64;
+            if let Some(&idf) = self.idf.get(term)
+                && tf > 0.0
+            {
+                score += idf * tf * (K1 + 1.0) / (tf + norm);
             }
         }
 
@@ -135,5 +155,9 @@ mod tests {
             .filter(|(id, _)| id.contains("doc1") || id.contains("doc3"))
             .collect();
         assert!(rust_docs.len() >= 2);
+        assert!(
+            results.iter().all(|(id, _)| id != "doc2"),
+            "doc2 has no 'rust'"
+        );
     }
 }
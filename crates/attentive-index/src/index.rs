@@ -3,15 +3,21 @@
 use crate::bm25::BM25;
 use crate::tfidf::SimpleTFIDF;
 use anyhow::Result;
-use chrono::Utc;
+use attentive_core::{FusionStrategy, SearchFusion};
+use attentive_repo::{Symbol, SymbolKind};
+use attentive_telemetry::SqlitePool;
+use chrono::{DateTime, Utc};
 use regex::Regex;
-use rusqlite::{Connection, params};
-use std::collections::HashMap;
+use rusqlite::{Connection, OptionalExtension, params, params_from_iter};
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
 use std::sync::OnceLock;
 
 static TOKENIZE_RE: OnceLock<Regex> = OnceLock::new();
 
+/// Bumped whenever the serialized BM25/TF-IDF layout or the tokenizer changes
+const MEMORY_INDEX_FORMAT: u32 = 2;
+
 #[derive(Debug, Clone)]
 pub struct Document {
     pub path: String,
@@ -20,10 +26,83 @@ pub struct Document {
     pub doc_type: String,
 }
 
+/// Lines shown before and after the line a snippet is anchored on
+const SNIPPET_LINES_BEFORE: usize = 1;
+const SNIPPET_LINES_AFTER: usize = 3;
+
+/// Longest snippet line kept, in characters; minified files have huge lines
+const SNIPPET_LINE_CHARS: usize = 160;
+
+/// A few lines of a document around where it best matches a query
+#[derive(Debug, Clone, PartialEq)]
+pub struct Snippet {
+    /// Symbol whose signature the snippet is anchored on, None when it's
+    /// anchored on a plain line match
+    pub symbol: Option<String>,
+    /// 1-based line number of the first line in `text`
+    pub line: usize,
+    pub text: String,
+}
+
+impl Snippet {
+    /// `text` with each line prefixed by its line number
+    pub fn numbered(&self) -> String {
+        let width = (self.line + self.text.lines().count()).to_string().len();
+        self.text
+            .lines()
+            .enumerate()
+            .map(|(i, line)| format!("{:>width$}  {}", self.line + i, line, width = width))
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+}
+
+/// Restricts a search to some documents. Within each list any entry may
+/// match; the lists and `modified_after` must all match.
+#[derive(Debug, Clone, Default)]
+pub struct DocFilter {
+    /// `doc_type` values (`code`, `doc`) or file extensions (`md`, `rs`)
+    pub types: Vec<String>,
+    /// SQLite GLOB patterns on the indexed path, where `*` also matches `/`
+    pub path_globs: Vec<String>,
+    /// Only documents whose file changed after this time
+    pub modified_after: Option<DateTime<Utc>>,
+}
+
+impl DocFilter {
+    /// SQL condition and its parameters, or None when nothing is filtered
+    fn to_sql(&self) -> Option<(String, Vec<rusqlite::types::Value>)> {
+        let mut clauses = Vec::new();
+        let mut values: Vec<rusqlite::types::Value> = Vec::new();
+        if !self.types.is_empty() {
+            let mut any = Vec::new();
+            for doc_type in &self.types {
+                let extension = doc_type.trim_start_matches('.');
+                any.push("doc_type = ? OR path LIKE ?");
+                values.push(extension.to_string().into());
+                values.push(format!("%.{}", extension).into());
+            }
+            clauses.push(format!("({})", any.join(" OR ")));
+        }
+        if !self.path_globs.is_empty() {
+            let any = vec!["path GLOB ?"; self.path_globs.len()];
+            clauses.push(format!("({})", any.join(" OR ")));
+            values.extend(self.path_globs.iter().map(|g| g.clone().into()));
+        }
+        if let Some(after) = self.modified_after {
+            clauses.push("mtime > ?".to_string());
+            values.push((after.timestamp_millis() as f64 / 1000.0).into());
+        }
+        (!clauses.is_empty()).then(|| (clauses.join(" AND "), values))
+    }
+}
+
 pub struct SearchIndex {
-    db_path: PathBuf,
+    pool: SqlitePool,
     bm25: Option<BM25>,
     tfidf: Option<SimpleTFIDF>,
+    fusion: SearchFusion,
+    expansions: HashMap<String, Vec<String>>,
 }
 
 impl SearchIndex {
@@ -33,18 +112,51 @@ impl SearchIndex {
             std::fs::create_dir_all(parent)?;
         }
 
-        let index = Self {
-            db_path,
+        let mut index = Self {
+            pool: SqlitePool::open(&db_path)?,
             bm25: None,
             tfidf: None,
+            fusion: SearchFusion::default(),
+            expansions: HashMap::new(),
         };
 
         index.init_db()?;
+        index.load_memory_index()?;
         Ok(index)
     }
 
+    /// Combine lexical and semantic rankings with `fusion` in `query`
+    pub fn with_fusion(mut self, fusion: SearchFusion) -> Self {
+        self.fusion = fusion;
+        self
+    }
+
+    /// Also match the terms `expansions` maps query words to, e.g. a
+    /// project's learned synonyms
+    pub fn with_expansions(mut self, expansions: HashMap<String, Vec<String>>) -> Self {
+        self.expansions = expansions;
+        self
+    }
+
+    /// Query tokens followed by the expansions of those not already in it
+    fn query_tokens(&self, prompt: &str) -> Vec<String> {
+        let mut tokens = tokenize(prompt);
+        let added: Vec<String> = tokens
+            .iter()
+            .filter_map(|t| self.expansions.get(t))
+            .flatten()
+            .flat_map(|term| tokenize(term))
+            .collect();
+        for token in added {
+            if !tokens.contains(&token) {
+                tokens.push(token);
+            }
+        }
+        tokens
+    }
+
     fn init_db(&self) -> Result<()> {
-        let conn = Connection::open(&self.db_path)?;
+        let conn = self.pool.get()?;
         conn.execute(
             "CREATE TABLE IF NOT EXISTS documents (
                 path TEXT PRIMARY KEY,
@@ -60,29 +172,88 @@ impl SearchIndex {
             "CREATE INDEX IF NOT EXISTS idx_mtime ON documents(mtime)",
             [],
         )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS memory_index (
+                id INTEGER PRIMARY KEY CHECK (id = 1),
+                cache_key TEXT NOT NULL,
+                data TEXT NOT NULL
+            )",
+            [],
+        )?;
+        Ok(())
+    }
+
+    /// Identifies the document set the in-memory index was built from. The
+    /// newest mtime catches added or touched files, the count catches
+    /// deletions and the mtime total catches updates to older files.
+    fn cache_key(conn: &Connection) -> Result<String> {
+        let (count, newest, total): (i64, Option<f64>, f64) = conn.query_row(
+            "SELECT COUNT(*), MAX(mtime), TOTAL(mtime) FROM documents",
+            [],
+            |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?)),
+        )?;
+        Ok(format!(
+            "v{}:{}:{}:{}",
+            MEMORY_INDEX_FORMAT,
+            count,
+            newest.unwrap_or(0.0),
+            total
+        ))
+    }
+
+    /// Load the persisted BM25/TF-IDF tables if they match the documents,
+    /// rebuilding (and persisting) them otherwise
+    fn load_memory_index(&mut self) -> Result<()> {
+        let conn = self.pool.get()?;
+        let key = Self::cache_key(&conn)?;
+        let cached: Option<String> = conn
+            .query_row(
+                "SELECT data FROM memory_index WHERE id = 1 AND cache_key = ?1",
+                params![key],
+                |row| row.get(0),
+            )
+            .optional()?;
+        // An unreadable cache is rebuilt like a stale one
+        if let Some((bm25, tfidf)) = cached.and_then(|data| serde_json::from_str(&data).ok()) {
+            self.bm25 = bm25;
+            self.tfidf = tfidf;
+            return Ok(());
+        }
+        drop(conn);
+        self.rebuild_memory_index()
+    }
+
+    fn save_memory_index(&self, conn: &Connection) -> Result<()> {
+        let data = serde_json::to_string(&(&self.bm25, &self.tfidf))?;
+        conn.execute(
+            "INSERT OR REPLACE INTO memory_index (id, cache_key, data) VALUES (1, ?1, ?2)",
+            params![Self::cache_key(conn)?, data],
+        )?;
         Ok(())
     }
 
     pub fn build(&mut self, documents: Vec<Document>) -> Result<()> {
-        let conn = Connection::open(&self.db_path)?;
+        {
+            let conn = self.pool.get()?;
 
-        // Clear existing data
-        conn.execute("DELETE FROM documents", [])?;
+            // Clear existing data
+            conn.execute("DELETE FROM documents", [])?;
 
-        // Insert documents
-        for doc in &documents {
-            conn.execute(
+            // Insert documents
+            let mut insert = conn.prepare_cached(
                 "INSERT INTO documents (path, content, outline, mtime, doc_type, indexed_at)
                  VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                params![
+            )?;
+            for doc in &documents {
+                insert.execute(params![
                     &doc.path,
                     &doc.content,
-                    "",
+                    outline(&doc.path, &doc.content),
                     doc.mtime,
                     &doc.doc_type,
-                    Utc::now().to_rfc3339()
-                ],
-            )?;
+                    attentive_telemetry::now().to_rfc3339()
+                ])?;
+            }
         }
 
         // Rebuild in-memory index
@@ -92,39 +263,41 @@ impl SearchIndex {
     }
 
     pub fn update_incremental(&mut self, documents: Vec<Document>) -> Result<usize> {
-        let conn = Connection::open(&self.db_path)?;
+        let mut updated = 0;
+        {
+            let conn = self.pool.get()?;
 
-        // Get existing mtimes
-        let mut existing: HashMap<String, f64> = HashMap::new();
-        let mut stmt = conn.prepare("SELECT path, mtime FROM documents")?;
-        let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
-        for row in rows {
-            let (path, mtime): (String, f64) = row?;
-            existing.insert(path, mtime);
-        }
+            // Get existing mtimes
+            let mut existing: HashMap<String, f64> = HashMap::new();
+            let mut stmt = conn.prepare_cached("SELECT path, mtime FROM documents")?;
+            let rows = stmt.query_map([], |row| Ok((row.get(0)?, row.get(1)?)))?;
+            for row in rows {
+                let (path, mtime): (String, f64) = row?;
+                existing.insert(path, mtime);
+            }
 
-        // Update only changed documents
-        let mut updated = 0;
-        for doc in documents {
-            let should_update = existing
-                .get(&doc.path)
-                .map(|&old_mtime| old_mtime < doc.mtime)
-                .unwrap_or(true);
-
-            if should_update {
-                conn.execute(
-                    "INSERT OR REPLACE INTO documents (path, content, outline, mtime, doc_type, indexed_at)
-                     VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
-                    params![
+            // Update only changed documents
+            let mut upsert = conn.prepare_cached(
+                "INSERT OR REPLACE INTO documents (path, content, outline, mtime, doc_type, indexed_at)
+                 VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+            )?;
+            for doc in documents {
+                let should_update = existing
+                    .get(&doc.path)
+                    .map(|&old_mtime| old_mtime < doc.mtime)
+                    .unwrap_or(true);
+
+                if should_update {
+                    upsert.execute(params![
                         &doc.path,
                         &doc.content,
-                        "",
+                        outline(&doc.path, &doc.content),
                         doc.mtime,
                         &doc.doc_type,
-                        Utc::now().to_rfc3339()
-                    ],
-                )?;
-                updated += 1;
+                        attentive_telemetry::now().to_rfc3339()
+                    ])?;
+                    updated += 1;
+                }
             }
         }
 
@@ -136,8 +309,8 @@ impl SearchIndex {
     }
 
     fn rebuild_memory_index(&mut self) -> Result<()> {
-        let conn = Connection::open(&self.db_path)?;
-        let mut stmt = conn.prepare("SELECT path, content FROM documents")?;
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("SELECT path, content FROM documents")?;
         let rows = stmt.query_map([], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
         })?;
@@ -151,7 +324,7 @@ impl SearchIndex {
         if documents.is_empty() {
             self.bm25 = None;
             self.tfidf = None;
-            return Ok(());
+            return self.save_memory_index(&conn);
         }
 
         // Tokenize documents
@@ -170,12 +343,12 @@ impl SearchIndex {
         tfidf.index(tokenized);
         self.tfidf = Some(tfidf);
 
-        Ok(())
+        self.save_memory_index(&conn)
     }
 
     fn get_document_contents(&self) -> Result<HashMap<String, String>> {
-        let conn = Connection::open(&self.db_path)?;
-        let mut stmt = conn.prepare("SELECT path, content FROM documents")?;
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached("SELECT path, content FROM documents")?;
         let rows = stmt.query_map([], |row| {
             Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
         })?;
@@ -194,25 +367,109 @@ impl SearchIndex {
             return Ok(Vec::new());
         }
 
-        let query_tokens = tokenize(prompt);
+        // Get more candidates for reranking
+        let results = self.query_lexical(prompt, top_k * 3);
+
+        // Apply semantic reranking
+        let contents = self.get_document_contents()?;
+        let reranked = semantic_rerank(prompt, results, &contents, top_k, &self.fusion);
+        Ok(reranked)
+    }
+
+    /// `query` over only the documents matching `filter`. The filter runs in
+    /// SQL first, so `top_k` results are found even when few documents match.
+    pub fn query_filtered(
+        &self,
+        prompt: &str,
+        top_k: usize,
+        filter: &DocFilter,
+    ) -> Result<Vec<(String, f64)>> {
+        let Some((condition, values)) = filter.to_sql() else {
+            return self.query(prompt, top_k);
+        };
+        if self.bm25.is_none() && self.tfidf.is_none() {
+            return Ok(Vec::new());
+        }
+
+        let conn = self.pool.get()?;
+        let mut stmt = conn.prepare_cached(&format!(
+            "SELECT path, content FROM documents WHERE {}",
+            condition
+        ))?;
+        let rows = stmt.query_map(params_from_iter(values), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, String>(1)?))
+        })?;
+        let contents = rows.collect::<rusqlite::Result<HashMap<String, String>>>()?;
+        if contents.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let paths: HashSet<&str> = contents.keys().map(|p| p.as_str()).collect();
+        let results = self.query_lexical_where(prompt, top_k * 3, |p| paths.contains(p));
+        Ok(semantic_rerank(
+            prompt,
+            results,
+            &contents,
+            top_k,
+            &self.fusion,
+        ))
+    }
 
-        // Try BM25 first, fallback to TF-IDF
-        let results = if let Some(bm25) = &self.bm25 {
-            bm25.search(&query_tokens, top_k * 3) // Get more candidates for reranking
+    /// BM25 (or TF-IDF) matches only, without loading the embedding model.
+    /// Cheap enough to run inside a hook.
+    pub fn query_lexical(&self, prompt: &str, top_k: usize) -> Vec<(String, f64)> {
+        let query_tokens = self.query_tokens(prompt);
+        if let Some(bm25) = &self.bm25 {
+            bm25.search(&query_tokens, top_k)
         } else if let Some(tfidf) = &self.tfidf {
-            tfidf.search(&query_tokens, top_k * 3)
+            tfidf.search(&query_tokens, top_k)
         } else {
             Vec::new()
-        };
+        }
+    }
 
-        // Apply semantic reranking
-        let contents = self.get_document_contents()?;
-        let reranked = semantic_rerank(prompt, results, &contents, top_k);
-        Ok(reranked)
+    fn query_lexical_where(
+        &self,
+        prompt: &str,
+        top_k: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<(String, f64)> {
+        let query_tokens = self.query_tokens(prompt);
+        if let Some(bm25) = &self.bm25 {
+            bm25.search_where(&query_tokens, top_k, keep)
+        } else if let Some(tfidf) = &self.tfidf {
+            tfidf.search_where(&query_tokens, top_k, keep)
+        } else {
+            Vec::new()
+        }
+    }
+
+    /// Where the indexed `path` best matches `query`: the symbol from its
+    /// outline sharing the most terms with the query (signature terms count
+    /// double over body terms), else the best-matching line. None when the
+    /// path isn't indexed or nothing matches.
+    pub fn snippet(&self, path: &str, query: &str) -> Result<Option<Snippet>> {
+        let conn = self.pool.get()?;
+        let row: Option<(String, Option<String>)> = conn
+            .query_row(
+                "SELECT content, outline FROM documents WHERE path = ?1",
+                params![path],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .optional()?;
+        let Some((content, stored)) = row else {
+            return Ok(None);
+        };
+        let symbols = match stored.filter(|o| !o.is_empty()) {
+            Some(stored) => serde_json::from_str(&stored).unwrap_or_default(),
+            // Indexes built before outlines were stored
+            None => outline_symbols(path, &content),
+        };
+        Ok(find_snippet(&content, &symbols, query))
     }
 
     pub fn get_stats(&self) -> Result<HashMap<String, serde_json::Value>> {
-        let conn = Connection::open(&self.db_path)?;
+        let conn = self.pool.get()?;
         let count: i64 = conn.query_row("SELECT COUNT(*) FROM documents", [], |row| row.get(0))?;
 
         let mut stats = HashMap::new();
@@ -229,11 +486,143 @@ impl SearchIndex {
     }
 }
 
+/// Two-letter terms common enough in code to be worth indexing
+const SHORT_TERMS: &[&str] = &[
+    "ai", "ci", "db", "fd", "fs", "gc", "go", "id", "io", "ip", "js", "ml", "os", "py", "rs", "rx",
+    "sh", "ts", "tx", "ui", "ux", "vm", "ws",
+];
+
+/// English filler and keywords shared by most languages; they match everything
+const STOPWORDS: &[&str] = &[
+    "the", "and", "for", "with", "this", "that", "from", "are", "was", "but", "not", "you", "your",
+    "have", "has", "into", "then", "than", "its", "can", "will", "pub", "let", "mut", "def", "var",
+    "const", "self", "return", "import", "function",
+];
+
+fn keep_token(token: &str) -> bool {
+    let len = token.len();
+    (len >= 3 || (len == 2 && SHORT_TERMS.contains(&token))) && !STOPWORDS.contains(&token)
+}
+
+/// Split an identifier on underscores and case boundaries:
+/// `fooBarBaz` → foo, bar, baz; `HTTPServer2` → http, server2
+fn split_identifier(word: &str) -> Vec<String> {
+    let mut parts = Vec::new();
+    for segment in word.split('_').filter(|s| !s.is_empty()) {
+        let chars: Vec<char> = segment.chars().collect();
+        let mut start = 0;
+        for i in 1..chars.len() {
+            let (prev, cur) = (chars[i - 1], chars[i]);
+            let next_lower = chars.get(i + 1).is_some_and(|c| c.is_lowercase());
+            let boundary = cur.is_uppercase()
+                && (prev.is_lowercase()
+                    || prev.is_ascii_digit()
+                    || (prev.is_uppercase() && next_lower));
+            if boundary {
+                parts.push(chars[start..i].iter().collect::<String>().to_lowercase());
+                start = i;
+            }
+        }
+        parts.push(chars[start..].iter().collect::<String>().to_lowercase());
+    }
+    parts
+}
+
+/// Code-aware tokens: each identifier as a whole plus its camelCase/snake_case
+/// parts, so `fooBarBaz` matches queries for `foobarbaz`, `foo` or `baz`
 fn tokenize(text: &str) -> Vec<String> {
-    let re = TOKENIZE_RE.get_or_init(|| Regex::new(r"[a-z][a-z0-9_]{2,}").unwrap());
-    re.find_iter(&text.to_lowercase())
-        .map(|m| m.as_str().to_string())
-        .collect()
+    let re = TOKENIZE_RE.get_or_init(|| Regex::new(r"[A-Za-z][A-Za-z0-9_]*").unwrap());
+    let mut tokens = Vec::new();
+    for word in re.find_iter(text).map(|m| m.as_str()) {
+        let compound = word.trim_end_matches('_').to_lowercase();
+        let parts = split_identifier(word);
+        if keep_token(&compound) {
+            tokens.push(compound.clone());
+        }
+        if parts.len() > 1 {
+            tokens.extend(
+                parts
+                    .into_iter()
+                    .filter(|p| *p != compound && keep_token(p)),
+            );
+        }
+    }
+    tokens
+}
+
+/// Functions and types declared in a source file, in line order
+fn outline_symbols(path: &str, content: &str) -> Vec<Symbol> {
+    attentive_repo::extract_symbols(content, path)
+        .map(|file| {
+            file.symbols
+                .into_iter()
+                .filter(|s| s.kind != SymbolKind::Import)
+                .collect()
+        })
+        .unwrap_or_default()
+}
+
+/// The `outline` column: JSON symbols, or empty for files without any
+fn outline(path: &str, content: &str) -> String {
+    let symbols = outline_symbols(path, content);
+    if symbols.is_empty() {
+        return String::new();
+    }
+    serde_json::to_string(&symbols).unwrap_or_default()
+}
+
+fn find_snippet(content: &str, symbols: &[Symbol], query: &str) -> Option<Snippet> {
+    let terms: HashSet<String> = tokenize(query).into_iter().collect();
+    let lines: Vec<&str> = content.lines().collect();
+    let matches = |text: &str| {
+        tokenize(text)
+            .into_iter()
+            .filter(|t| terms.contains(t))
+            .collect::<HashSet<_>>()
+            .len()
+    };
+
+    let mut best: Option<(usize, &Symbol)> = None;
+    for (i, symbol) in symbols.iter().enumerate() {
+        let start = symbol.line.saturating_sub(1).min(lines.len());
+        let end = symbols
+            .get(i + 1)
+            .map_or(lines.len(), |next| next.line.saturating_sub(1))
+            .clamp(start, lines.len());
+        let score = 2 * matches(&symbol.signature) + matches(&lines[start..end].join("\n"));
+        if score > 0 && best.is_none_or(|(top, _)| score > top) {
+            best = Some((score, symbol));
+        }
+    }
+    let (anchor, symbol) = match best {
+        Some((_, symbol)) => (symbol.line, Some(symbol.name.clone())),
+        None => {
+            let (score, index) = lines
+                .iter()
+                .enumerate()
+                .map(|(i, line)| (matches(line), std::cmp::Reverse(i)))
+                .max()?;
+            if score == 0 {
+                return None;
+            }
+            (index.0 + 1, None)
+        }
+    };
+    if anchor == 0 || anchor > lines.len() {
+        return None;
+    }
+    let first = anchor.saturating_sub(SNIPPET_LINES_BEFORE).max(1);
+    let last = (anchor + SNIPPET_LINES_AFTER).min(lines.len());
+    let text = lines[first - 1..last]
+        .iter()
+        .map(|line| line.chars().take(SNIPPET_LINE_CHARS).collect::<String>())
+        .collect::<Vec<_>>()
+        .join("\n");
+    Some(Snippet {
+        symbol,
+        line: first,
+        text,
+    })
 }
 
 fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
@@ -252,6 +641,7 @@ fn semantic_rerank(
     candidates: Vec<(String, f64)>,
     contents: &std::collections::HashMap<String, String>,
     top_k: usize,
+    fusion: &SearchFusion,
 ) -> Vec<(String, f64)> {
     use fastembed::TextEmbedding;
 
@@ -265,12 +655,10 @@ fn semantic_rerank(
         _ => return candidates.into_iter().take(top_k).collect(),
     };
 
-    let bm25_max = candidates.iter().map(|(_, s)| *s).fold(0.0f64, f64::max);
-
-    let mut scored: Vec<(String, f64)> = candidates
-        .iter()
+    let scored: Vec<(String, f64, f64)> = candidates
+        .into_iter()
         .filter_map(|(path, bm25_score)| {
-            let content = contents.get(path)?;
+            let content = contents.get(&path)?;
             let truncated = if content.len() > 2000 {
                 &content[..2000]
             } else {
@@ -282,24 +670,80 @@ fn semantic_rerank(
                 .into_iter()
                 .next()?;
             let sim = cosine_similarity(&query_emb, &doc_emb) as f64;
-            let norm_bm25 = if bm25_max > 0.0 {
-                bm25_score / bm25_max
-            } else {
-                0.0
-            };
-            let combined = 0.6 * norm_bm25 + 0.4 * sim;
-            Some((path.clone(), combined))
+            Some((path, bm25_score, sim))
         })
         .collect();
 
-    scored.sort_by(|a, b| b.1.partial_cmp(&a.1).unwrap_or(std::cmp::Ordering::Equal));
-    scored.into_iter().take(top_k).collect()
+    fuse(scored, fusion).into_iter().take(top_k).collect()
+}
+
+/// Rank `(path, bm25, similarity)` candidates by their fused score, best first
+fn fuse(candidates: Vec<(String, f64, f64)>, fusion: &SearchFusion) -> Vec<(String, f64)> {
+    let bm25_max = candidates.iter().map(|c| c.1).fold(0.0f64, f64::max);
+    let norm_bm25 = |score: f64| {
+        if bm25_max > 0.0 {
+            score / bm25_max
+        } else {
+            0.0
+        }
+    };
+    // 1-based rank of each candidate in one ranking
+    let ranks = |key: fn(&(String, f64, f64)) -> f64| {
+        let mut order: Vec<usize> = (0..candidates.len()).collect();
+        order.sort_by(|&a, &b| key(&candidates[b]).total_cmp(&key(&candidates[a])));
+        let mut rank = vec![0; candidates.len()];
+        for (position, i) in order.into_iter().enumerate() {
+            rank[i] = position + 1;
+        }
+        rank
+    };
+    let lexical_ranks = ranks(|c| c.1);
+    let semantic_ranks = ranks(|c| c.2);
+
+    let mut fused: Vec<(String, f64)> = candidates
+        .iter()
+        .enumerate()
+        .map(|(i, (path, bm25, sim))| {
+            let score = match fusion.strategy {
+                FusionStrategy::Weighted => {
+                    fusion.lexical_weight * norm_bm25(*bm25) + fusion.semantic_weight * sim
+                }
+                FusionStrategy::Rrf => {
+                    fusion.lexical_weight / (fusion.rrf_k + lexical_ranks[i] as f64)
+                        + fusion.semantic_weight / (fusion.rrf_k + semantic_ranks[i] as f64)
+                }
+                FusionStrategy::Max => norm_bm25(*bm25).max(*sim),
+            };
+            (path.clone(), score)
+        })
+        .collect();
+    fused.sort_by(|a, b| b.1.total_cmp(&a.1));
+    fused
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_tokenize_identifiers() {
+        assert_eq!(
+            tokenize("fooBarBaz"),
+            vec!["foobarbaz", "foo", "bar", "baz"]
+        );
+        assert_eq!(
+            tokenize("read_file_content"),
+            vec!["read_file_content", "read", "file", "content"]
+        );
+        assert_eq!(
+            tokenize("HTTPServer2"),
+            vec!["httpserver2", "http", "server2"]
+        );
+        // Allowlisted short terms survive, stopwords and other short words don't
+        assert_eq!(tokenize("the io of a db"), vec!["io", "db"]);
+        assert_eq!(tokenize("std::io::Read"), vec!["std", "io", "read"]);
+    }
+
     #[test]
     fn test_cosine_similarity() {
         let a = vec![1.0, 0.0, 0.0];
@@ -310,6 +754,44 @@ mod tests {
         assert!(cosine_similarity(&a, &c).abs() < 1e-6); // orthogonal
     }
 
+    #[test]
+    fn test_fusion_strategies() {
+        // a's outsized BM25 score dominates a weighted sum; b is near the top
+        // of both rankings
+        let candidates = || {
+            vec![
+                ("a".to_string(), 100.0, 0.1),
+                ("b".to_string(), 9.0, 0.8),
+                ("c".to_string(), 8.0, 0.7),
+                ("d".to_string(), 1.0, 0.2),
+            ]
+        };
+        let order = |fusion: SearchFusion| -> Vec<String> {
+            fuse(candidates(), &fusion)
+                .into_iter()
+                .map(|(p, _)| p)
+                .collect()
+        };
+
+        let weighted = SearchFusion::default();
+        assert_eq!(order(weighted), vec!["a", "b", "c", "d"]);
+        assert!((fuse(candidates(), &weighted)[0].1 - 0.64).abs() < 1e-9);
+
+        let rrf = SearchFusion {
+            strategy: FusionStrategy::Rrf,
+            ..SearchFusion::default()
+        };
+        assert_eq!(order(rrf), vec!["b", "a", "c", "d"]);
+
+        let max = SearchFusion {
+            strategy: FusionStrategy::Max,
+            ..SearchFusion::default()
+        };
+        let fused = fuse(candidates(), &max);
+        assert_eq!(fused[0], ("a".to_string(), 1.0));
+        assert_eq!(fused[1], ("b".to_string(), 0.8));
+    }
+
     #[test]
     fn test_semantic_rerank_basic() {
         // This test only runs when embeddings feature is enabled
@@ -360,10 +842,203 @@ mod tests {
         let results = index.query("rust programming", 5).unwrap();
         assert!(!results.is_empty());
         assert_eq!(results[0].0, "rust_guide.md");
+        let lexical = index.query_lexical("rust programming", 1);
+        assert_eq!(lexical.len(), 1);
+        assert_eq!(lexical[0].0, "rust_guide.md");
 
         std::fs::remove_file(&db_path).unwrap();
     }
 
+    #[test]
+    fn test_memory_index_persists_until_documents_change() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("index.db");
+        let mut index = SearchIndex::new(&db_path).unwrap();
+        index
+            .build(vec![Document {
+                path: "rust_guide.md".to_string(),
+                content: "Rust is a systems programming language".to_string(),
+                mtime: 1.0,
+                doc_type: "markdown".to_string(),
+            }])
+            .unwrap();
+        let doc_ids = |index: &SearchIndex| -> Vec<String> {
+            let tokens = tokenize("rust");
+            let results = index.bm25.as_ref().unwrap().search(&tokens, 10);
+            results.into_iter().map(|(path, _)| path).collect()
+        };
+
+        // Rename behind the index's back without touching mtime: a new
+        // process loads the persisted tables instead of rebuilding
+        let conn = Connection::open(&db_path).unwrap();
+        conn.execute("UPDATE documents SET path = 'renamed.md'", [])
+            .unwrap();
+        assert_eq!(
+            doc_ids(&SearchIndex::new(&db_path).unwrap()),
+            ["rust_guide.md"]
+        );
+
+        // A newer mtime invalidates the cache
+        conn.execute("UPDATE documents SET mtime = 2.0", [])
+            .unwrap();
+        assert_eq!(
+            doc_ids(&SearchIndex::new(&db_path).unwrap()),
+            ["renamed.md"]
+        );
+    }
+
+    #[test]
+    fn test_query_filtered_by_type_path_and_age() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = SearchIndex::new(dir.path().join("index.db")).unwrap();
+        let doc = |path: &str, doc_type: &str, mtime: f64| Document {
+            path: path.to_string(),
+            content: "router decay and attention scores".to_string(),
+            mtime,
+            doc_type: doc_type.to_string(),
+        };
+        let now = attentive_telemetry::now().timestamp() as f64;
+        index
+            .build(vec![
+                doc("src/router.rs", "code", now),
+                doc("src/deep/decay.rs", "code", now - 30.0 * 86400.0),
+                doc("docs/router.md", "doc", now),
+                doc("README.md", "doc", now - 30.0 * 86400.0),
+            ])
+            .unwrap();
+        let paths = |filter: DocFilter| -> Vec<String> {
+            let mut paths: Vec<String> = index
+                .query_filtered("router decay", 10, &filter)
+                .unwrap()
+                .into_iter()
+                .map(|(p, _)| p)
+                .collect();
+            paths.sort();
+            paths
+        };
+
+        assert_eq!(paths(DocFilter::default()).len(), 4);
+        let md = DocFilter {
+            types: vec!["md".to_string()],
+            ..DocFilter::default()
+        };
+        assert_eq!(paths(md), ["README.md", "docs/router.md"]);
+        let code_in_src = DocFilter {
+            types: vec!["code".to_string()],
+            path_globs: vec!["src/**".to_string()],
+            ..DocFilter::default()
+        };
+        assert_eq!(paths(code_in_src), ["src/deep/decay.rs", "src/router.rs"]);
+        let recent = DocFilter {
+            modified_after: Some(attentive_telemetry::now() - chrono::Duration::days(7)),
+            path_globs: vec!["src/*".to_string(), "README.md".to_string()],
+            ..DocFilter::default()
+        };
+        assert_eq!(paths(recent), ["src/router.rs"]);
+        let none = DocFilter {
+            types: vec!["py".to_string()],
+            ..DocFilter::default()
+        };
+        assert!(paths(none).is_empty());
+    }
+
+    #[test]
+    fn test_query_expansions() {
+        let dir = tempfile::tempdir().unwrap();
+        let mut index = SearchIndex::new(dir.path().join("index.db")).unwrap();
+        let doc = |path: &str, content: &str| Document {
+            path: path.to_string(),
+            content: content.to_string(),
+            mtime: 1.0,
+            doc_type: "code".to_string(),
+        };
+        index
+            .build(vec![
+                doc("src/login.rs", "fn create_session() { check_password() }"),
+                doc("src/chart.rs", "fn render_axis() { draw_labels() }"),
+            ])
+            .unwrap();
+        assert!(index.query_lexical("auth redirect", 5).is_empty());
+
+        let expansions = [(
+            "auth".to_string(),
+            vec!["login".to_string(), "session".to_string()],
+        )];
+        let index = index.with_expansions(expansions.into());
+        assert_eq!(
+            index.query_tokens("auth session"),
+            ["auth", "session", "login"]
+        );
+        let results = index.query_lexical("auth redirect", 5);
+        assert_eq!(results[0].0, "src/login.rs");
+        assert_eq!(results.len(), 1);
+    }
+
+    #[test]
+    fn test_outline_snippets() {
+        let dir = tempfile::tempdir().unwrap();
+        let db_path = dir.path().join("index.db");
+        let mut index = SearchIndex::new(&db_path).unwrap();
+        let doc = |path: &str, content: &str| Document {
+            path: path.to_string(),
+            content: content.to_string(),
+            mtime: 1.0,
+            doc_type: "code".to_string(),
+        };
+        let source = "use std::fs;\n\npub fn open_socket() {}\n\n\
+                      /// Parses the grammar\n\
+                      pub fn parse_grammar(input: &str) {\n    let rules = load();\n    apply(rules);\n    done();\n}\n";
+        index
+            .build(vec![
+                doc("src/parser.rs", source),
+                doc(
+                    "notes.md",
+                    "# Notes\n\nintro\nthe grammar is ambiguous\nend",
+                ),
+            ])
+            .unwrap();
+
+        let conn = Connection::open(&db_path).unwrap();
+        let stored: String = conn
+            .query_row(
+                "SELECT outline FROM documents WHERE path = 'src/parser.rs'",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        let symbols: Vec<Symbol> = serde_json::from_str(&stored).unwrap();
+        let names: Vec<&str> = symbols.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["open_socket", "parse_grammar"]);
+
+        let snippet = index
+            .snippet("src/parser.rs", "grammar rules")
+            .unwrap()
+            .unwrap();
+        assert_eq!(snippet.symbol.as_deref(), Some("parse_grammar"));
+        assert_eq!(snippet.line, 5);
+        assert_eq!(
+            snippet.numbered(),
+            " 5  /// Parses the grammar\n 6  pub fn parse_grammar(input: &str) {\n 7      let rules = load();\n 8      apply(rules);\n 9      done();"
+        );
+
+        // Documents without an outline match on lines
+        let snippet = index
+            .snippet("notes.md", "ambiguous grammar")
+            .unwrap()
+            .unwrap();
+        assert_eq!((snippet.symbol, snippet.line), (None, 3));
+        assert!(snippet.text.starts_with("intro\nthe grammar is ambiguous"));
+
+        assert!(index.snippet("notes.md", "socket").unwrap().is_none());
+        assert!(index.snippet("missing.rs", "grammar").unwrap().is_none());
+
+        // Older indexes without outlines compute them on the fly
+        conn.execute("UPDATE documents SET outline = ''", [])
+            .unwrap();
+        let snippet = index.snippet("src/parser.rs", "socket").unwrap().unwrap();
+        assert_eq!(snippet.symbol.as_deref(), Some("open_socket"));
+    }
+
     #[test]
     fn test_incremental_update() {
         let temp_dir = std::env::temp_dir();
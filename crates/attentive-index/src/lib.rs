@@ -4,4 +4,4 @@ mod bm25;
 mod index;
 mod tfidf;
 
-pub use index::{Document, SearchIndex};
+pub use index::{DocFilter, Document, SearchIndex, Snippet};
@@ -1,8 +1,9 @@
 //! SimpleTFIDF fallback implementation
 
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SimpleTFIDF {
     vocab: HashMap<String, usize>,
     idf: HashMap<String, f64>,
@@ -74,6 +75,16 @@ impl SimpleTFIDF {
     }
 
     pub fn search(&self, query_tokens: &[String], top_k: usize) -> Vec<(String, f64)> {
+        self.search_where(query_tokens, top_k, |_| true)
+    }
+
+    /// Like `search`, over only the documents `keep` accepts
+    pub fn search_where(
+        &self,
+        query_tokens: &[String],
+        top_k: usize,
+        keep: impl Fn(&str) -> bool,
+    ) -> Vec<(String, f64)> {
         if self.doc_vecs.is_empty() {
             return Vec::new();
         }
@@ -92,6 +103,9 @@ impl SimpleTFIDF {
         let mut results = Vec::new();
 
         for (i, doc_vec) in self.doc_vecs.iter().enumerate() {
+            if !keep(&self.doc_paths[i]) {
+                continue;
+            }
             let dot = dot_product(&query_vec, doc_vec);
             let doc_norm = norm(doc_vec).max(1.0);
             let score = dot / (query_norm * doc_norm);
@@ -1,12 +1,41 @@
 //! Learner for prompt-file affinity and co-activation patterns
 
+use crate::synonyms::Synonyms;
+use attentive_telemetry::StateError;
 use serde::{Deserialize, Serialize};
 use std::collections::{HashMap, HashSet};
 
 const MATURITY_THRESHOLD: usize = 25;
 const ACTIVE_BOOST_WEIGHT: f64 = 0.35;
 const COACTIVATION_JACCARD_THRESHOLD: f64 = 0.25;
+/// Turns two files must share to count as co-activating
+const MIN_COACTIVATION_TURNS: usize = 3;
+/// Co-activation partners kept per file, most shared turns first
+const MAX_COACTIVATION_PARTNERS: usize = 32;
 const DEFAULT_DECAY: f64 = 0.70;
+/// Turns after an anchor turn in which a file counts as "needed next"
+pub const LOOKAHEAD_TURNS: usize = 2;
+/// Anchor turns a prediction must be seen to follow
+const MIN_PREDICTION_SUPPORT: usize = 3;
+/// Share of anchor turns a prediction must follow
+const MIN_PREDICTION_CONFIDENCE: f64 = 0.3;
+/// A file edited again within this many turns of its last edit is churning
+const CHURN_WINDOW: usize = 2;
+/// Credit for a file whose edit redoes one from the last `CHURN_WINDOW` turns
+const CHURN_WEIGHT: f64 = 0.5;
+/// Credit for an edited file when tests ran after the edits
+const VERIFIED_WEIGHT: f64 = 1.5;
+/// Turns seen before words are judged too common to associate
+const MIN_TURNS_FOR_IDF_FLOOR: usize = 100;
+/// Words whose IDF falls below this (in over ~60% of turns) say nothing
+/// about which files a prompt needs; their associations are dropped
+const IDF_FLOOR: f64 = 0.5;
+/// Word-file pairs seen once and not since in this many turns are dropped
+const STALE_SINGLETON_TURNS: usize = 200;
+/// Turns between cleanup passes over the word-file associations
+const PRUNE_INTERVAL: usize = 100;
+/// Share of a word's affinity its learned synonyms lend, times their strength
+const SYNONYM_WEIGHT: f64 = 0.5;
 
 static STOP_WORDS: &[&str] = &[
     "the", "a", "an", "is", "are", "was", "were", "be", "been", "being", "have", "has", "had",
@@ -30,16 +59,25 @@ pub enum MaturityLevel {
     Active,    // 25+ turns, 0.35 boost
 }
 
+/// What came of a turn's edits, weighting the credit its files get
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct TurnOutcome {
+    /// Files edited during the turn
+    pub edited: Vec<String>,
+    /// Whether tests were run after the turn's last edit
+    pub tests_run: bool,
+}
+
 /// Learner state
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Learner {
     turn_count: usize,
     maturity: MaturityLevel,
     // prompt word -> file -> co-occurrence count
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     word_file_counts: HashMap<String, HashMap<String, usize>>,
     // word -> total document frequency (how many turns it appeared in)
-    #[serde(default)]
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
     word_doc_freq: HashMap<String, usize>,
     // file -> set of turn indices where it was active
     #[serde(default)]
@@ -52,6 +90,62 @@ pub struct Learner {
     // last session state for warm-start
     #[serde(default)]
     last_session_files: Vec<String>,
+    // prompt word -> file -> last turn they co-occurred in
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    word_file_last_turn: HashMap<String, HashMap<String, usize>>,
+    // prompt word -> file -> co-occurrences weighted by turn outcome. Missing
+    // for pairs last seen before outcomes were tracked; the count stands in.
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    word_file_credit: HashMap<String, HashMap<String, f64>>,
+    // file -> last turn it was edited in, for churn detection
+    #[serde(default)]
+    file_last_edited: HashMap<String, usize>,
+    // file -> files it shared at least `MIN_COACTIVATION_TURNS` turns with,
+    // and how many, so routing doesn't scan every file per lookup. `None` in
+    // state saved before it was kept; built on the next observed turn.
+    #[serde(default)]
+    coactivation_partners: Option<HashMap<String, Vec<(String, usize)>>>,
+    // turns after which an observation counts half; 0 never forgets.
+    // Comes from config, so it isn't persisted.
+    #[serde(skip)]
+    half_life: usize,
+    // prompt word -> terms the files say instead; mined at ingest and kept
+    // in its own file, so it isn't persisted here
+    #[serde(skip)]
+    synonyms: Synonyms,
+}
+
+impl attentive_telemetry::Versioned for Learner {
+    const VERSION: u32 = 2;
+    const KIND: &'static str = "learned state";
+
+    /// Version 2 moved the word tables to a `WordIndex`. Older files keep
+    /// them inline, which still loads as is.
+    fn migrate(from: u32, data: serde_json::Value) -> Result<serde_json::Value, StateError> {
+        match from {
+            0 | 1 => Ok(data),
+            _ => Err(StateError::NoMigration {
+                kind: Self::KIND,
+                from,
+            }),
+        }
+    }
+}
+
+/// A learner's per-word tables, persisted apart from the rest of its state
+/// so the prompt hook can read only the prompt's words (see `WordIndex`)
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WordTables {
+    pub(crate) counts: HashMap<String, HashMap<String, usize>>,
+    pub(crate) doc_freq: HashMap<String, usize>,
+    pub(crate) last_turn: HashMap<String, HashMap<String, usize>>,
+    pub(crate) credit: HashMap<String, HashMap<String, f64>>,
+}
+
+impl WordTables {
+    pub fn is_empty(&self) -> bool {
+        self.counts.is_empty() && self.doc_freq.is_empty()
+    }
 }
 
 impl Learner {
@@ -65,13 +159,81 @@ impl Learner {
             file_last_seen: HashMap::new(),
             file_gaps: HashMap::new(),
             last_session_files: Vec::new(),
+            word_file_last_turn: HashMap::new(),
+            word_file_credit: HashMap::new(),
+            file_last_edited: HashMap::new(),
+            coactivation_partners: Some(HashMap::new()),
+            half_life: 0,
+            synonyms: Synonyms::new(),
+        }
+    }
+
+    /// Downweight observations exponentially by age: one `half_life` turns old
+    /// counts half as much as the latest. 0 weights all turns equally.
+    pub fn with_half_life(mut self, half_life: usize) -> Self {
+        self.half_life = half_life;
+        self
+    }
+
+    /// Also credit files through the project's learned synonyms of prompt
+    /// words, at half the weight of the words themselves
+    pub fn with_synonyms(mut self, synonyms: Synonyms) -> Self {
+        self.synonyms = synonyms;
+        self
+    }
+
+    pub fn synonyms(&self) -> &Synonyms {
+        &self.synonyms
+    }
+
+    /// Weight of what was observed on `turn`, 1.0 for the latest turn
+    fn turn_weight(&self, turn: usize) -> f64 {
+        if self.half_life == 0 {
+            return 1.0;
         }
+        let age = self.turn_count.saturating_sub(turn + 1);
+        0.5f64.powf(age as f64 / self.half_life as f64)
+    }
+
+    /// Summed weight of `turns`
+    fn weighted_len<'a>(&self, turns: impl IntoIterator<Item = &'a usize>) -> f64 {
+        turns.into_iter().map(|&t| self.turn_weight(t)).sum()
+    }
+
+    /// Move the per-word tables out, e.g. to store them in a `WordIndex`
+    pub fn take_word_tables(&mut self) -> WordTables {
+        WordTables {
+            counts: std::mem::take(&mut self.word_file_counts),
+            doc_freq: std::mem::take(&mut self.word_doc_freq),
+            last_turn: std::mem::take(&mut self.word_file_last_turn),
+            credit: std::mem::take(&mut self.word_file_credit),
+        }
+    }
+
+    /// Replace the per-word tables with `tables`, all of them or just the
+    /// rows for one prompt's words
+    pub fn set_word_tables(&mut self, tables: WordTables) {
+        self.word_file_counts = tables.counts;
+        self.word_doc_freq = tables.doc_freq;
+        self.word_file_last_turn = tables.last_turn;
+        self.word_file_credit = tables.credit;
+    }
+
+    /// Whether the per-word tables are in memory: loaded, or inline in a
+    /// state file written before they moved out
+    pub fn has_word_tables(&self) -> bool {
+        !self.word_file_counts.is_empty() || !self.word_doc_freq.is_empty()
     }
 
     pub fn maturity(&self) -> MaturityLevel {
         self.maturity
     }
 
+    /// Turns observed so far
+    pub fn turn_count(&self) -> usize {
+        self.turn_count
+    }
+
     pub fn boost_weight(&self) -> f64 {
         match self.maturity {
             MaturityLevel::Observing => 0.0,
@@ -87,23 +249,121 @@ impl Learner {
         };
     }
 
-    /// Extract significant words from a prompt, filtering stop words
-    fn extract_words(prompt: &str) -> Vec<String> {
+    /// Extract significant words from a prompt, filtering stop words,
+    /// numbers and hex fragments
+    pub(crate) fn extract_words(prompt: &str) -> Vec<String> {
         let stop_set: HashSet<&str> = STOP_WORDS.iter().copied().collect();
         prompt
             .to_lowercase()
             .split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-')
-            .filter(|w| w.len() >= 3 && !stop_set.contains(w))
+            .filter(|w| w.len() >= 3 && !stop_set.contains(w) && !is_noise_word(w))
             .map(|w| w.to_string())
             .collect()
     }
 
+    /// Whether `word` appears in so many turns that it no longer tells files
+    /// apart. Its document frequency is still tracked.
+    fn below_idf_floor(&self, word: &str) -> bool {
+        if self.turn_count < MIN_TURNS_FOR_IDF_FLOOR {
+            return false;
+        }
+        let doc_freq = self.word_doc_freq.get(word).copied().unwrap_or(0);
+        (self.turn_count as f64 / (1.0 + doc_freq as f64)).ln() < IDF_FLOOR
+    }
+
+    /// Drop word-file associations that only add noise: those of words below
+    /// the IDF floor, and pairs seen once more than `STALE_SINGLETON_TURNS`
+    /// turns ago. Runs every `PRUNE_INTERVAL` turns; returns the number of
+    /// associations removed.
+    pub fn prune_associations(&mut self) -> usize {
+        let before = self.total_associations();
+        let common: HashSet<String> = self
+            .word_file_counts
+            .keys()
+            .filter(|w| self.below_idf_floor(w))
+            .cloned()
+            .collect();
+        let turn_count = self.turn_count;
+        let last_turns = &self.word_file_last_turn;
+        let mut stale: Vec<(String, String)> = Vec::new();
+        for (word, files) in &self.word_file_counts {
+            for (file, &count) in files {
+                let last = last_turns
+                    .get(word)
+                    .and_then(|f| f.get(file))
+                    .or_else(|| self.file_last_seen.get(file))
+                    .copied()
+                    .unwrap_or(0);
+                if count <= 1 && turn_count.saturating_sub(last) > STALE_SINGLETON_TURNS {
+                    stale.push((word.clone(), file.clone()));
+                }
+            }
+        }
+
+        self.word_file_counts
+            .retain(|word, _| !common.contains(word));
+        self.word_file_last_turn
+            .retain(|word, _| !common.contains(word));
+        self.word_file_credit
+            .retain(|word, _| !common.contains(word));
+        for (word, file) in &stale {
+            if let Some(files) = self.word_file_counts.get_mut(word) {
+                files.remove(file);
+            }
+            if let Some(files) = self.word_file_last_turn.get_mut(word) {
+                files.remove(file);
+            }
+            if let Some(files) = self.word_file_credit.get_mut(word) {
+                files.remove(file);
+            }
+        }
+        self.word_file_counts.retain(|_, files| !files.is_empty());
+        self.word_file_last_turn
+            .retain(|_, files| !files.is_empty());
+        self.word_file_credit.retain(|_, files| !files.is_empty());
+        before - self.total_associations()
+    }
+
     /// Observe a turn: record prompt words and active files
     pub fn observe_turn(&mut self, prompt: &str, active_files: &[String]) {
+        self.observe_turn_with_outcome(prompt, active_files, &TurnOutcome::default());
+    }
+
+    /// Credit for `file` this turn: less when its edit redoes a recent one,
+    /// more when tests ran after it was edited
+    fn outcome_weight(&self, file: &str, outcome: &TurnOutcome) -> f64 {
+        if !outcome.edited.iter().any(|f| f == file) {
+            return 1.0;
+        }
+        let churned = self
+            .file_last_edited
+            .get(file)
+            .is_some_and(|&last| self.turn_count.saturating_sub(last) <= CHURN_WINDOW);
+        if churned {
+            CHURN_WEIGHT
+        } else if outcome.tests_run {
+            VERIFIED_WEIGHT
+        } else {
+            1.0
+        }
+    }
+
+    /// `observe_turn`, crediting each file's word associations by how its
+    /// edits turned out (see `TurnOutcome`)
+    pub fn observe_turn_with_outcome(
+        &mut self,
+        prompt: &str,
+        active_files: &[String],
+        outcome: &TurnOutcome,
+    ) {
         let words = Self::extract_words(prompt);
         if words.is_empty() || active_files.is_empty() {
             return;
         }
+        let weights: Vec<f64> = active_files
+            .iter()
+            .map(|file| self.outcome_weight(file, outcome))
+            .collect();
 
         // Track unique words in this turn for document frequency
         let unique_words: HashSet<String> = words.iter().cloned().collect();
@@ -111,13 +371,25 @@ impl Learner {
             *self.word_doc_freq.entry(word.clone()).or_insert(0) += 1;
         }
 
-        // Track word-file co-occurrences
+        // Track word-file co-occurrences, except for words too common to
+        // tell files apart
         for word in &words {
+            if self.below_idf_floor(word) {
+                continue;
+            }
             let file_counts = self.word_file_counts.entry(word.clone()).or_default();
-            for file in active_files {
-                *file_counts.entry(file.clone()).or_insert(0) += 1;
+            let last_turns = self.word_file_last_turn.entry(word.clone()).or_default();
+            let credits = self.word_file_credit.entry(word.clone()).or_default();
+            for (file, &weight) in active_files.iter().zip(&weights) {
+                let count = file_counts.entry(file.clone()).or_insert(0);
+                *credits.entry(file.clone()).or_insert(*count as f64) += weight;
+                *count += 1;
+                last_turns.insert(file.clone(), self.turn_count);
             }
         }
+        for file in &outcome.edited {
+            self.file_last_edited.insert(file.clone(), self.turn_count);
+        }
 
         // Track file turns and gaps for rhythm detection
         for file in active_files {
@@ -131,9 +403,13 @@ impl Learner {
             }
             self.file_last_seen.insert(file.clone(), self.turn_count);
         }
+        self.record_coactivation(active_files);
 
         self.turn_count += 1;
         self.update_maturity();
+        if self.turn_count.is_multiple_of(PRUNE_INTERVAL) {
+            self.prune_associations();
+        }
     }
 
     /// Calculate IDF for a word
@@ -151,6 +427,16 @@ impl Learner {
         &self,
         prompt: &str,
         current_scores: &HashMap<String, f64>,
+    ) -> HashMap<String, f64> {
+        self.boost_scores_with_cap(prompt, current_scores, 1.0)
+    }
+
+    /// Same as `boost_scores`, capping boosted scores at `cap` instead of 1.0
+    pub fn boost_scores_with_cap(
+        &self,
+        prompt: &str,
+        current_scores: &HashMap<String, f64>,
+        cap: f64,
     ) -> HashMap<String, f64> {
         if self.boost_weight() == 0.0 {
             return current_scores.clone();
@@ -163,38 +449,234 @@ impl Learner {
             return current_scores.clone();
         }
 
-        let mut boosted = current_scores.clone();
+        // Walk each prompt word's associations rather than every scored file,
+        // so only the files a word has been seen with are visited. Synonyms
+        // add to the affinity but not to the word count.
+        let expanded = self
+            .synonyms
+            .expand(&words)
+            .into_iter()
+            .map(|(term, strength)| (term, strength * SYNONYM_WEIGHT));
+        let mut affinity: HashMap<&str, f64> = HashMap::new();
+        for (word, weight) in words.iter().map(|w| (w.clone(), 1.0)).chain(expanded) {
+            let Some(file_counts) = self.word_file_counts.get(&word) else {
+                continue;
+            };
+            let idf = self.calculate_idf(&word);
+            for (file, &count) in file_counts {
+                if !current_scores.contains_key(file) {
+                    continue;
+                }
+                *affinity.entry(file).or_insert(0.0) +=
+                    weight * self.word_affinity(&word, file, count, idf);
+            }
+        }
 
-        // Calculate total words for normalization
+        // Normalize by word count and apply maturity weight
         let total_words = words.len() as f64;
+        current_scores
+            .iter()
+            .map(|(file, base_score)| {
+                let affinity_sum = affinity.get(file.as_str()).copied().unwrap_or(0.0);
+                let boost = affinity_sum / total_words.max(1.0) * self.boost_weight();
+                (file.clone(), (base_score + boost).min(cap))
+            })
+            .collect()
+    }
 
-        // For each file in current scores, calculate learned boost
-        for (file, base_score) in current_scores {
-            let mut affinity_sum = 0.0;
+    /// Affinity `word` (with inverse document frequency `idf`) lends `file`,
+    /// having co-occurred with it `count` times
+    fn word_affinity(&self, word: &str, file: &str, count: usize, idf: f64) -> f64 {
+        if self.turn_count == 0 {
+            return 0.0;
+        }
+        let credit = self
+            .word_file_credit
+            .get(word)
+            .and_then(|files| files.get(file))
+            .copied()
+            .unwrap_or(count as f64);
+        // Associations fade with the turns since they last held. States saved
+        // before this was tracked fall back to when the file was last seen.
+        let last_turn = self
+            .word_file_last_turn
+            .get(word)
+            .and_then(|files| files.get(file))
+            .or_else(|| self.file_last_seen.get(file))
+            .copied()
+            .unwrap_or(0);
+        idf * credit * self.turn_weight(last_turn) / self.turn_count as f64
+    }
 
-            for word in &words {
-                let idf = self.calculate_idf(word);
-                if let Some(file_counts) = self.word_file_counts.get(word)
-                    && let Some(&count) = file_counts.get(file)
-                {
-                    let frequency = if self.turn_count > 0 {
-                        count as f64 / self.turn_count as f64
-                    } else {
-                        0.0
-                    };
-                    affinity_sum += idf * frequency;
+    /// Boost a one-word prompt gives `file` with the word most associated
+    /// with it: the most the learner lifts it on any prompt word. Needs the
+    /// full word tables.
+    pub fn learned_boost(&self, file: &str) -> f64 {
+        let strongest = self
+            .word_file_counts
+            .iter()
+            .filter_map(|(word, files)| {
+                let &count = files.get(file)?;
+                Some(self.word_affinity(word, file, count, self.calculate_idf(word)))
+            })
+            .fold(0.0, f64::max);
+        strongest * self.boost_weight()
+    }
+
+    /// Number of turns `file` was active in
+    pub fn file_usage(&self, file: &str) -> usize {
+        self.file_turns.get(file).map_or(0, |turns| turns.len())
+    }
+
+    /// Jaccard similarity of the turns two files were active in, if it is
+    /// strong enough to count as a learned co-activation
+    pub fn coactivation_strength(&self, file_a: &str, file_b: &str) -> Option<f64> {
+        let turns_a = self.file_turns.get(file_a)?;
+        let turns_b = self.file_turns.get(file_b)?;
+        let intersection = turns_a.intersection(turns_b).count();
+        if intersection == 0 {
+            return None;
+        }
+
+        // Recent turns count more, so pairings that stopped holding fade
+        let shared = self.weighted_len(turns_a.intersection(turns_b));
+        let union = self.weighted_len(turns_a) + self.weighted_len(turns_b) - shared;
+        let jaccard = shared / union;
+        // Threshold: Jaccard >= 0.25 and at least 3 co-occurrences
+        (jaccard >= COACTIVATION_JACCARD_THRESHOLD && intersection >= MIN_COACTIVATION_TURNS)
+            .then_some(jaccard)
+    }
+
+    /// Turns in which both files were active
+    pub fn co_occurrences(&self, file_a: &str, file_b: &str) -> usize {
+        match (self.file_turns.get(file_a), self.file_turns.get(file_b)) {
+            (Some(turns_a), Some(turns_b)) => turns_a.intersection(turns_b).count(),
+            _ => 0,
+        }
+    }
+
+    /// Files that learned to co-activate with `file`, with their strength
+    pub fn coactivation_neighbors(&self, file: &str) -> Vec<(String, f64)> {
+        let strength = |other: &String| {
+            self.coactivation_strength(file, other)
+                .map(|strength| (other.clone(), strength))
+        };
+        match &self.coactivation_partners {
+            Some(partners) => partners
+                .get(file)
+                .into_iter()
+                .flatten()
+                .filter_map(|(other, _)| strength(other))
+                .collect(),
+            None => self
+                .file_turns
+                .keys()
+                .filter(|other| other.as_str() != file)
+                .filter_map(strength)
+                .collect(),
+        }
+    }
+
+    /// Co-activation partners of every file, from scratch
+    fn build_coactivation_partners(&self) -> HashMap<String, Vec<(String, usize)>> {
+        let mut turn_files: HashMap<usize, Vec<&String>> = HashMap::new();
+        for (file, turns) in &self.file_turns {
+            for &turn in turns {
+                turn_files.entry(turn).or_default().push(file);
+            }
+        }
+        let mut shared: HashMap<&String, HashMap<&String, usize>> = HashMap::new();
+        for files in turn_files.values() {
+            for &a in files {
+                for &b in files {
+                    if a != b {
+                        *shared.entry(a).or_default().entry(b).or_insert(0) += 1;
+                    }
                 }
             }
+        }
+        shared
+            .into_iter()
+            .filter_map(|(file, others)| {
+                let mut partners: Vec<(String, usize)> = others
+                    .into_iter()
+                    .filter(|&(_, count)| count >= MIN_COACTIVATION_TURNS)
+                    .map(|(other, count)| (other.clone(), count))
+                    .collect();
+                sort_partners(&mut partners);
+                (!partners.is_empty()).then(|| (file.clone(), partners))
+            })
+            .collect()
+    }
 
-            // Normalize by word count and apply maturity weight
-            let normalized_affinity = affinity_sum / total_words.max(1.0);
-            let boost = normalized_affinity * self.boost_weight();
+    /// Count this turn's shared activation towards each active pair's
+    /// partnership. Only pairs active together change, so only they are
+    /// looked at.
+    fn record_coactivation(&mut self, active_files: &[String]) {
+        if self.coactivation_partners.is_none() {
+            self.coactivation_partners = Some(self.build_coactivation_partners());
+            return;
+        }
+        let mut counts = Vec::new();
+        for (i, a) in active_files.iter().enumerate() {
+            for b in &active_files[i + 1..] {
+                let count = self.co_occurrences(a, b);
+                if a != b && count >= MIN_COACTIVATION_TURNS {
+                    counts.push((a, b, count));
+                }
+            }
+        }
+        let Some(partners) = &mut self.coactivation_partners else {
+            return;
+        };
+        for (a, b, count) in counts {
+            for (file, other) in [(a, b), (b, a)] {
+                let list = partners.entry(file.clone()).or_default();
+                match list.iter_mut().find(|(f, _)| f == other) {
+                    Some(entry) => entry.1 = count,
+                    None => list.push((other.clone(), count)),
+                }
+                sort_partners(list);
+            }
+        }
+    }
 
-            // Add boost, capped at 1.0
-            boosted.insert(file.clone(), (base_score + boost).min(1.0));
+    /// Files likely needed within the next `LOOKAHEAD_TURNS` turns, given the
+    /// files active now: of the past turns where any of `current` was active,
+    /// the share followed by each other file shortly after. Most likely first.
+    pub fn predict_next(&self, current: &[String], limit: usize) -> Vec<(String, f64)> {
+        if self.maturity != MaturityLevel::Active {
+            return Vec::new();
+        }
+        let anchors: HashSet<usize> = current
+            .iter()
+            .filter_map(|f| self.file_turns.get(f))
+            .flatten()
+            .copied()
+            .collect();
+        if anchors.is_empty() {
+            return Vec::new();
         }
+        let anchor_weight = self.weighted_len(&anchors);
 
-        boosted
+        let mut predictions: Vec<(String, f64)> = self
+            .file_turns
+            .iter()
+            .filter(|(file, _)| !current.contains(file))
+            .filter_map(|(file, turns)| {
+                let followed: Vec<&usize> = anchors
+                    .iter()
+                    .filter(|&&t| (1..=LOOKAHEAD_TURNS).any(|ahead| turns.contains(&(t + ahead))))
+                    .collect();
+                let support = followed.len();
+                let confidence = self.weighted_len(followed) / anchor_weight;
+                (support >= MIN_PREDICTION_SUPPORT && confidence >= MIN_PREDICTION_CONFIDENCE)
+                    .then(|| (file.clone(), confidence))
+            })
+            .collect();
+        predictions.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+        predictions.truncate(limit);
+        predictions
     }
 
     /// Get learned co-activation patterns (files that appear together frequently)
@@ -205,21 +687,7 @@ impl Learner {
 
         for (i, file_a) in files.iter().enumerate() {
             for file_b in files.iter().skip(i + 1) {
-                let turns_a = &self.file_turns[*file_a];
-                let turns_b = &self.file_turns[*file_b];
-
-                // Calculate Jaccard similarity
-                let intersection: HashSet<_> = turns_a.intersection(turns_b).collect();
-                let union: HashSet<_> = turns_a.union(turns_b).collect();
-
-                if union.is_empty() {
-                    continue;
-                }
-
-                let jaccard = intersection.len() as f64 / union.len() as f64;
-
-                // Threshold: Jaccard >= 0.25 and at least 3 co-occurrences
-                if jaccard >= COACTIVATION_JACCARD_THRESHOLD && intersection.len() >= 3 {
+                if self.coactivation_strength(file_a, file_b).is_some() {
                     coactivation
                         .entry((*file_a).clone())
                         .or_default()
@@ -274,17 +742,21 @@ impl Learner {
         self.last_session_files = active_files.to_vec();
     }
 
-    /// Get top N files by frequency (number of turns they appeared in)
+    /// Get top N files by frequency (number of turns they appeared in),
+    /// ranked with older turns downweighted
     pub fn top_files_by_frequency(&self, limit: usize) -> Vec<(String, usize)> {
-        let mut file_freq: Vec<(String, usize)> = self
+        let mut file_freq: Vec<(String, usize, f64)> = self
             .file_turns
             .iter()
-            .map(|(file, turns)| (file.clone(), turns.len()))
+            .map(|(file, turns)| (file.clone(), turns.len(), self.weighted_len(turns)))
             .collect();
 
-        file_freq.sort_by_key(|b| std::cmp::Reverse(b.1));
+        file_freq.sort_by(|a, b| b.2.total_cmp(&a.2));
         file_freq.truncate(limit);
         file_freq
+            .into_iter()
+            .map(|(file, count, _)| (file, count))
+            .collect()
     }
 
     /// Count total unique word-file associations
@@ -294,6 +766,218 @@ impl Learner {
             .map(|file_map| file_map.len())
             .sum()
     }
+
+    /// Transfer everything learned about `old` to `new` (e.g. after `git mv`).
+    ///
+    /// Keys must match `old` exactly (see `attentive_telemetry::remap_path`);
+    /// stats already recorded under the new path are merged. Returns the number
+    /// of file keys moved.
+    pub fn remap_file(&mut self, old: &str, new: &str) -> usize {
+        self.rekey_files(|key| attentive_telemetry::remap_path(key, old, new))
+    }
+
+    /// Rewrite every file key into canonical form, merging keys that collapse
+    /// together. Returns the number of file keys changed.
+    pub fn canonicalize_files(
+        &mut self,
+        canonicalizer: &attentive_telemetry::PathCanonicalizer,
+    ) -> usize {
+        let mut cache: HashMap<String, Option<String>> = HashMap::new();
+        self.rekey_files(|key| {
+            cache
+                .entry(key.to_string())
+                .or_insert_with(|| {
+                    let canonical = canonicalizer.canonicalize(key);
+                    (canonical != key).then_some(canonical)
+                })
+                .clone()
+        })
+    }
+
+    /// Forget every file `keep` rejects (e.g. paths outside the project).
+    /// Returns the number of file keys removed.
+    pub fn prune_files(&mut self, keep: impl Fn(&str) -> bool) -> usize {
+        let before = self.file_turns.len();
+        self.file_turns.retain(|f, _| keep(f));
+        self.file_last_seen.retain(|f, _| keep(f));
+        self.file_gaps.retain(|f, _| keep(f));
+        self.file_last_edited.retain(|f, _| keep(f));
+        self.last_session_files.retain(|f| keep(f));
+        for files in self.word_file_counts.values_mut() {
+            files.retain(|f, _| keep(f));
+        }
+        for files in self.word_file_last_turn.values_mut() {
+            files.retain(|f, _| keep(f));
+        }
+        for files in self.word_file_credit.values_mut() {
+            files.retain(|f, _| keep(f));
+        }
+        self.word_file_counts.retain(|_, files| !files.is_empty());
+        self.word_file_last_turn
+            .retain(|_, files| !files.is_empty());
+        self.word_file_credit.retain(|_, files| !files.is_empty());
+        self.coactivation_partners = Some(self.build_coactivation_partners());
+        before - self.file_turns.len()
+    }
+
+    /// Move stats from each key for which `target` returns a new name
+    fn rekey_files(&mut self, mut target: impl FnMut(&str) -> Option<String>) -> usize {
+        let mut moved = 0;
+
+        let keys: Vec<String> = self.file_turns.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = target(&key)
+                && let Some(turns) = self.file_turns.remove(&key)
+            {
+                self.file_turns.entry(target).or_default().extend(turns);
+                moved += 1;
+            }
+        }
+
+        let keys: Vec<String> = self.file_last_seen.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = target(&key)
+                && let Some(seen) = self.file_last_seen.remove(&key)
+            {
+                let entry = self.file_last_seen.entry(target).or_insert(seen);
+                *entry = (*entry).max(seen);
+            }
+        }
+
+        let keys: Vec<String> = self.file_gaps.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = target(&key)
+                && let Some(gaps) = self.file_gaps.remove(&key)
+            {
+                self.file_gaps.entry(target).or_default().extend(gaps);
+            }
+        }
+
+        // Before counts merge, while a target's own count can still stand in
+        // for its missing credit
+        for (word, credits) in &mut self.word_file_credit {
+            let counts = self.word_file_counts.get(word);
+            let keys: Vec<String> = credits.keys().cloned().collect();
+            for key in keys {
+                if let Some(target) = target(&key)
+                    && let Some(credit) = credits.remove(&key)
+                {
+                    let own = counts.and_then(|c| c.get(&target)).copied().unwrap_or(0);
+                    *credits.entry(target).or_insert(own as f64) += credit;
+                }
+            }
+        }
+
+        for file_counts in self.word_file_counts.values_mut() {
+            let keys: Vec<String> = file_counts.keys().cloned().collect();
+            for key in keys {
+                if let Some(target) = target(&key)
+                    && let Some(count) = file_counts.remove(&key)
+                {
+                    *file_counts.entry(target).or_insert(0) += count;
+                }
+            }
+        }
+
+        for last_turns in self.word_file_last_turn.values_mut() {
+            let keys: Vec<String> = last_turns.keys().cloned().collect();
+            for key in keys {
+                if let Some(target) = target(&key)
+                    && let Some(turn) = last_turns.remove(&key)
+                {
+                    let entry = last_turns.entry(target).or_insert(turn);
+                    *entry = (*entry).max(turn);
+                }
+            }
+        }
+
+        let keys: Vec<String> = self.file_last_edited.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = target(&key)
+                && let Some(edited) = self.file_last_edited.remove(&key)
+            {
+                let entry = self.file_last_edited.entry(target).or_insert(edited);
+                *entry = (*entry).max(edited);
+            }
+        }
+
+        for file in &mut self.last_session_files {
+            if let Some(target) = target(file) {
+                *file = target;
+            }
+        }
+        self.coactivation_partners = Some(self.build_coactivation_partners());
+
+        moved
+    }
+
+    /// Fold in what `other` learned about the same project (e.g. on another
+    /// machine), keeping the larger of each count, turn and credit and the
+    /// union of each file's turns. Merging a learner that was itself merged
+    /// from this one changes nothing, so repeated syncs don't inflate counts.
+    pub fn merge(&mut self, other: &Learner) {
+        self.turn_count = self.turn_count.max(other.turn_count);
+        merge_max_nested(&mut self.word_file_counts, &other.word_file_counts);
+        merge_max(&mut self.word_doc_freq, &other.word_doc_freq);
+        merge_max_nested(&mut self.word_file_last_turn, &other.word_file_last_turn);
+        merge_max_nested(&mut self.word_file_credit, &other.word_file_credit);
+        for (file, turns) in &other.file_turns {
+            self.file_turns
+                .entry(file.clone())
+                .or_default()
+                .extend(turns);
+        }
+        merge_max(&mut self.file_last_seen, &other.file_last_seen);
+        merge_max(&mut self.file_last_edited, &other.file_last_edited);
+        for (file, gaps) in &other.file_gaps {
+            let own = self.file_gaps.entry(file.clone()).or_default();
+            if gaps.len() > own.len() {
+                *own = gaps.clone();
+            }
+        }
+        if self.last_session_files.is_empty() {
+            self.last_session_files = other.last_session_files.clone();
+        }
+        self.update_maturity();
+        self.coactivation_partners = Some(self.build_coactivation_partners());
+    }
+}
+
+/// Raise each of `into`'s values to `from`'s where it's larger or missing
+fn merge_max<V: Copy + PartialOrd>(into: &mut HashMap<String, V>, from: &HashMap<String, V>) {
+    for (key, &value) in from {
+        let entry = into.entry(key.clone()).or_insert(value);
+        if value > *entry {
+            *entry = value;
+        }
+    }
+}
+
+/// `merge_max` for each row of a word -> file table
+fn merge_max_nested<V: Copy + PartialOrd>(
+    into: &mut HashMap<String, HashMap<String, V>>,
+    from: &HashMap<String, HashMap<String, V>>,
+) {
+    for (word, files) in from {
+        merge_max(into.entry(word.clone()).or_default(), files);
+    }
+}
+
+/// Most shared turns first, capped at `MAX_COACTIVATION_PARTNERS`
+fn sort_partners(partners: &mut Vec<(String, usize)>) {
+    partners.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    partners.truncate(MAX_COACTIVATION_PARTNERS);
+}
+
+/// Pure numbers and hex fragments (hashes, addresses) that prompts mention
+/// in passing
+fn is_noise_word(word: &str) -> bool {
+    let digits = word.chars().filter(char::is_ascii_digit).count();
+    let numeric = word
+        .chars()
+        .all(|c| c.is_ascii_digit() || c == '_' || c == '-');
+    let hex = word.len() >= 6 && digits > 0 && word.chars().all(|c| c.is_ascii_hexdigit());
+    numeric || hex
 }
 
 impl Default for Learner {
@@ -336,6 +1020,48 @@ mod tests {
             "Boost should not exceed 1.0 (capped): {}",
             boost
         );
+
+        let scores: HashMap<String, f64> = [("router.rs".to_string(), 0.95)].into();
+        let uncapped = learner.boost_scores_with_cap("router", &scores, f64::INFINITY);
+        assert!((uncapped["router.rs"] - (0.95 + boost - 0.5)).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_learned_boost_matches_strongest_word() {
+        let mut learner = Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("router config", &["router.rs".to_string()]);
+        }
+        let scores: HashMap<String, f64> = [("router.rs".to_string(), 0.0)].into();
+        let strongest = ["router", "config"]
+            .iter()
+            .map(|word| learner.boost_scores_with_cap(word, &scores, f64::INFINITY)["router.rs"])
+            .fold(0.0, f64::max);
+        assert!(strongest > 0.0);
+        assert!((learner.learned_boost("router.rs") - strongest).abs() < 1e-9);
+        assert_eq!(learner.learned_boost("other.rs"), 0.0);
+        assert_eq!(learner.file_usage("router.rs"), 30);
+        assert_eq!(learner.file_usage("other.rs"), 0);
+    }
+
+    #[test]
+    fn test_synonyms_lend_half_their_affinity() {
+        let mut learner = Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("login redirect", &["login.rs".to_string()]);
+            learner.observe_turn("chart axis", &["chart.rs".to_string()]);
+        }
+        let scores: HashMap<String, f64> = [("login.rs".to_string(), 0.0)].into();
+        let direct = learner.boost_scores_with_cap("login", &scores, f64::INFINITY)["login.rs"];
+        assert_eq!(learner.boost_scores("auth", &scores)["login.rs"], 0.0);
+
+        let synonyms: Synonyms = serde_json::from_str(r#"{"auth": {"login": 0.8}}"#).unwrap();
+        let learner = learner.with_synonyms(synonyms);
+        let via_synonym = learner.boost_scores_with_cap("auth", &scores, f64::INFINITY)["login.rs"];
+        assert!((via_synonym - direct * 0.8 * SYNONYM_WEIGHT).abs() < 1e-9);
+        // The word itself still counts in full
+        let both = learner.boost_scores_with_cap("login auth", &scores, f64::INFINITY)["login.rs"];
+        assert!((both - direct / 2.0).abs() < 1e-9);
     }
 
     #[test]
@@ -393,6 +1119,214 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_half_life_fades_old_associations() {
+        let mut learner = Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("router bug", &["old_router.rs".to_string()]);
+        }
+        for _ in 0..30 {
+            learner.observe_turn("router bug", &["new_router.rs".to_string()]);
+        }
+        let scores: HashMap<String, f64> = [
+            ("old_router.rs".to_string(), 0.0),
+            ("new_router.rs".to_string(), 0.0),
+        ]
+        .into();
+
+        // Without forgetting both histories weigh the same
+        let boosts = learner.boost_scores("router", &scores);
+        assert_eq!(boosts["old_router.rs"], boosts["new_router.rs"]);
+
+        let learner = learner.with_half_life(10);
+        let boosts = learner.boost_scores("router", &scores);
+        // The old association last held 30 turns ago: 3 half-lives
+        let ratio = boosts["old_router.rs"] / boosts["new_router.rs"];
+        assert!((ratio - 0.125).abs() < 1e-9, "ratio {}", ratio);
+        let top = learner.top_files_by_frequency(1);
+        assert_eq!(top, [("new_router.rs".to_string(), 30)]);
+    }
+
+    #[test]
+    fn test_extract_words_skips_numbers_and_hex() {
+        let words = Learner::extract_words("revert 4f9c2ab1 from pr 1234 in 2024-06-01 parser");
+        assert_eq!(words, ["revert", "parser"]);
+        // Hex-looking words without digits are ordinary words
+        assert_eq!(
+            Learner::extract_words("decade facade"),
+            ["decade", "facade"]
+        );
+    }
+
+    #[test]
+    fn test_prune_associations() {
+        let files = |name: &str| vec![name.to_string()];
+        let mut learner = Learner::new();
+        learner.observe_turn("tokenizer typo", &files("old.rs"));
+        for i in 0..(STALE_SINGLETON_TURNS + 1) {
+            let prompt = if i % 2 == 0 {
+                "session parser"
+            } else {
+                "session lexer"
+            };
+            learner.observe_turn(prompt, &files("src/parser.rs"));
+        }
+        // "session" is in almost every turn: its associations went in the
+        // cleanup pass at turn 100 and it hasn't been associated since
+        assert!(!learner.word_file_counts.contains_key("session"));
+        assert!(learner.word_doc_freq["session"] > 100);
+        assert!(learner.word_file_counts["parser"]["src/parser.rs"] > 1);
+
+        // The one-off "tokenizer typo" pairs are stale by now
+        assert!(learner.word_file_counts.contains_key("tokenizer"));
+        assert_eq!(learner.prune_associations(), 2);
+        assert!(!learner.word_file_counts.contains_key("tokenizer"));
+        assert!(!learner.word_file_credit.contains_key("typo"));
+        assert_eq!(learner.prune_associations(), 0);
+    }
+
+    #[test]
+    fn test_outcome_weights_credit() {
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        let edited = |names: &[&str], tests_run| TurnOutcome {
+            edited: files(names),
+            tests_run,
+        };
+        let mut learner = Learner::new();
+        // parser.rs keeps getting re-edited; lexer.rs's edits are tested
+        for turn in 0..30 {
+            let outcome = if turn % 2 == 0 {
+                edited(&["parser.rs", "lexer.rs"], true)
+            } else {
+                edited(&["parser.rs"], false)
+            };
+            learner.observe_turn_with_outcome(
+                "tokenizer grammar",
+                &files(&["parser.rs", "lexer.rs"]),
+                &outcome,
+            );
+        }
+        let credit = |file: &str| learner.word_file_credit["grammar"][file];
+        // First parser.rs edit had no earlier one, the other 29 churned
+        assert!((credit("parser.rs") - (1.5 + 29.0 * CHURN_WEIGHT)).abs() < 1e-9);
+        // lexer.rs: edited every other turn (gap 2 counts as churn) or just read
+        assert!((credit("lexer.rs") - (VERIFIED_WEIGHT + 14.0 * CHURN_WEIGHT + 15.0)).abs() < 1e-9);
+
+        let scores: HashMap<String, f64> = [
+            ("parser.rs".to_string(), 0.1),
+            ("lexer.rs".to_string(), 0.1),
+        ]
+        .into();
+        let boosts = learner.boost_scores("grammar", &scores);
+        assert!(boosts["lexer.rs"] > boosts["parser.rs"]);
+
+        // Plain observations credit 1 each, so counts stand in for old states
+        let mut plain = Learner::new();
+        plain.observe_turn("tokenizer grammar", &files(&["parser.rs"]));
+        assert_eq!(plain.word_file_credit["grammar"]["parser.rs"], 1.0);
+    }
+
+    #[test]
+    fn test_predict_next_follows_sequences() {
+        let mut learner = Learner::new();
+        let files = |names: &[&str]| names.iter().map(|n| n.to_string()).collect::<Vec<_>>();
+        // Editing the schema is followed by its migration, then its tests
+        for _ in 0..10 {
+            learner.observe_turn("schema change", &files(&["schema.rs"]));
+            learner.observe_turn("write migration", &files(&["migrate.rs"]));
+            learner.observe_turn("run tests", &files(&["schema_test.rs"]));
+        }
+        assert_eq!(learner.maturity(), MaturityLevel::Active);
+
+        let predicted = learner.predict_next(&files(&["schema.rs"]), 5);
+        let names: Vec<&str> = predicted.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(names, ["migrate.rs", "schema_test.rs"]);
+        assert_eq!(predicted[0].1, 1.0);
+        assert_eq!(learner.predict_next(&files(&["schema.rs"]), 1).len(), 1);
+        assert!(learner.predict_next(&files(&["unknown.rs"]), 5).is_empty());
+    }
+
+    #[test]
+    fn test_coactivation_strength_is_jaccard() {
+        let mut learner = Learner::new();
+        let pair = ["a.rs".to_string(), "b.rs".to_string()];
+        for _ in 0..3 {
+            learner.observe_turn("together", &pair);
+        }
+        learner.observe_turn("alone", &["a.rs".to_string()]);
+        // 3 shared turns out of 4
+        assert_eq!(learner.coactivation_strength("a.rs", "b.rs"), Some(0.75));
+        assert_eq!(
+            learner.coactivation_neighbors("b.rs"),
+            [("a.rs".to_string(), 0.75)]
+        );
+        assert_eq!(learner.coactivation_strength("a.rs", "missing.rs"), None);
+    }
+
+    #[test]
+    fn test_coactivation_partners_kept_as_turns_are_observed() {
+        let mut learner = Learner::new();
+        let trio = ["a.rs", "b.rs", "c.rs"].map(String::from);
+        for _ in 0..2 {
+            learner.observe_turn("together", &trio);
+        }
+        // Two shared turns aren't a partnership yet
+        assert!(learner.coactivation_neighbors("a.rs").is_empty());
+        learner.observe_turn("together", &trio[..2]);
+        assert_eq!(
+            learner.coactivation_neighbors("a.rs"),
+            [("b.rs".to_string(), 1.0)]
+        );
+
+        // State saved before partners were kept scans, then builds them
+        let mut legacy = learner.clone();
+        legacy.coactivation_partners = None;
+        assert_eq!(legacy.coactivation_neighbors("a.rs").len(), 1);
+        legacy.observe_turn("together", &trio);
+        assert_eq!(
+            legacy.coactivation_partners.as_ref().unwrap()["a.rs"],
+            [("b.rs".to_string(), 4), ("c.rs".to_string(), 3)]
+        );
+        let json = serde_json::to_string(&legacy).unwrap();
+        let reloaded: Learner = serde_json::from_str(&json).unwrap();
+        assert_eq!(
+            reloaded.coactivation_neighbors("c.rs").len(),
+            2,
+            "partners survive a save"
+        );
+    }
+
+    #[test]
+    fn test_merge_combines_and_is_idempotent() {
+        let mut laptop = Learner::new();
+        for _ in 0..4 {
+            laptop.observe_turn("router decay", &["src/router.rs".to_string()]);
+        }
+        let mut desktop = Learner::new();
+        for _ in 0..2 {
+            desktop.observe_turn("parser tokens", &["src/parser.rs".to_string()]);
+        }
+
+        let mut merged = laptop.clone();
+        merged.merge(&desktop);
+        assert_eq!(merged.turn_count(), 4);
+        assert_eq!(merged.file_usage("src/router.rs"), 4);
+        assert_eq!(merged.file_usage("src/parser.rs"), 2);
+        assert_eq!(merged.word_file_counts["parser"]["src/parser.rs"], 2);
+
+        // Merging back and forth again leaves the counts where they were
+        let before = merged.clone();
+        merged.merge(&desktop);
+        merged.merge(&laptop);
+        let mut back = desktop.clone();
+        back.merge(&merged);
+        for learner in [&merged, &back] {
+            assert_eq!(learner.turn_count, before.turn_count);
+            assert_eq!(learner.word_file_counts, before.word_file_counts);
+            assert_eq!(learner.file_turns, before.file_turns);
+        }
+    }
+
     #[test]
     fn test_json_roundtrip() {
         let mut learner = Learner::new();
@@ -468,4 +1402,57 @@ mod tests {
             "Stop-word-only prompt should return scores unchanged"
         );
     }
+
+    #[test]
+    fn test_remap_file_transfers_stats() {
+        let mut learner = Learner::new();
+        for _ in 0..MATURITY_THRESHOLD {
+            learner.observe_turn("tokenizer tweak", &["src/tokens.rs".to_string()]);
+        }
+        learner.save_session(&["src/tokens.rs".to_string()]);
+
+        let moved = learner.remap_file("src/tokens.rs", "src/tokenize.rs");
+        assert_eq!(moved, 1);
+        assert!(!learner.file_turns.contains_key("src/tokens.rs"));
+        assert_eq!(
+            learner.file_turns["src/tokenize.rs"].len(),
+            MATURITY_THRESHOLD
+        );
+        assert_eq!(learner.get_warmup(), vec!["src/tokenize.rs".to_string()]);
+
+        let mut scores = HashMap::new();
+        scores.insert("src/tokenize.rs".to_string(), 0.1);
+        let boosted = learner.boost_scores("tokenizer", &scores);
+        assert!(boosted["src/tokenize.rs"] > 0.1);
+
+        assert_eq!(learner.remap_file("src/missing.rs", "src/other.rs"), 0);
+    }
+
+    #[test]
+    fn test_prune_files_drops_foreign_paths() {
+        let mut learner = Learner::new();
+        learner.observe_turn(
+            "router change",
+            &["src/router.rs".to_string(), "/tmp/scratch.rs".to_string()],
+        );
+        learner.save_session(&["/tmp/scratch.rs".to_string(), "src/router.rs".to_string()]);
+
+        assert_eq!(learner.prune_files(|f| !f.starts_with('/')), 1);
+        assert_eq!(learner.file_turns.len(), 1);
+        assert!(!learner.word_file_counts["router"].contains_key("/tmp/scratch.rs"));
+        assert_eq!(learner.get_warmup(), vec!["src/router.rs"]);
+    }
+
+    #[test]
+    fn test_canonicalize_files_merges_forms() {
+        let mut learner = Learner::new();
+        learner.observe_turn("router change", &["/repo/src/router.rs".to_string()]);
+        learner.observe_turn("router change", &["./src/router.rs".to_string()]);
+
+        let canonicalizer = attentive_telemetry::PathCanonicalizer::new("/repo");
+        learner.canonicalize_files(&canonicalizer);
+        assert_eq!(learner.file_turns.len(), 1);
+        assert_eq!(learner.file_turns["src/router.rs"].len(), 2);
+        assert_eq!(learner.word_file_counts["router"]["src/router.rs"], 2);
+    }
 }
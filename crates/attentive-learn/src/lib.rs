@@ -3,7 +3,11 @@
 mod learner;
 mod oracle;
 mod predictor;
+mod synonyms;
+mod word_index;
 
-pub use learner::Learner;
+pub use learner::{LOOKAHEAD_TURNS, Learner, TurnOutcome, WordTables};
 pub use oracle::{Oracle, TaskType};
 pub use predictor::Predictor;
+pub use synonyms::Synonyms;
+pub use word_index::{WORD_INDEX_FILE, WordIndex};
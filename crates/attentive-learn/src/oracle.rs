@@ -14,6 +14,33 @@ pub enum TaskType {
     Config,
 }
 
+impl TaskType {
+    pub const ALL: [TaskType; 6] = [
+        TaskType::Refactor,
+        TaskType::BugFix,
+        TaskType::Feature,
+        TaskType::Review,
+        TaskType::Exploration,
+        TaskType::Config,
+    ];
+
+    /// Snake-case name, as serialized
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TaskType::Refactor => "refactor",
+            TaskType::BugFix => "bug_fix",
+            TaskType::Feature => "feature",
+            TaskType::Review => "review",
+            TaskType::Exploration => "exploration",
+            TaskType::Config => "config",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<TaskType> {
+        Self::ALL.into_iter().find(|t| t.as_str() == name)
+    }
+}
+
 struct TaskKeywords {
     task_type: TaskType,
     keywords: &'static [&'static str],
@@ -74,27 +101,57 @@ const TASK_KEYWORD_MAP: &[TaskKeywords] = &[
     },
 ];
 
+/// Turns of a task type needed before its files are prefetched
+const MIN_PREFETCH_TURNS: usize = 5;
+/// Share of a task type's turns a file must be used in to be prefetched
+const MIN_PREFETCH_SHARE: f64 = 0.5;
+/// Most files prefetched for one task type
+const MAX_PREFETCH_FILES: usize = 3;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CostEntry {
     pub tokens: usize,
     pub count: usize,
 }
 
+/// Files used by the turns of one task type
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct TaskFiles {
+    turns: usize,
+    /// File -> turns it was used in
+    files: HashMap<String, usize>,
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Oracle {
     task_costs: HashMap<String, CostEntry>,
+    #[serde(default)]
+    task_files: HashMap<String, TaskFiles>,
+}
+
+impl attentive_telemetry::Versioned for Oracle {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "oracle state";
 }
 
 impl Oracle {
     pub fn new() -> Self {
         Self {
             task_costs: HashMap::new(),
+            task_files: HashMap::new(),
         }
     }
 
     pub fn classify_task(&self, prompt: &str) -> TaskType {
+        self.classify_with_confidence(prompt).0
+    }
+
+    /// Task type with the share of matched keywords that point to it; a
+    /// prompt matching no keyword is a `Feature` with confidence 0
+    pub fn classify_with_confidence(&self, prompt: &str) -> (TaskType, f64) {
         let prompt_lower = prompt.to_lowercase();
         let mut best_match: Option<(TaskType, usize)> = None;
+        let mut total = 0;
 
         for entry in TASK_KEYWORD_MAP {
             let count = entry
@@ -102,16 +159,20 @@ impl Oracle {
                 .iter()
                 .filter(|kw| prompt_lower.contains(*kw))
                 .count();
+            total += count;
             if count > 0 && (best_match.is_none() || count > best_match.unwrap().1) {
                 best_match = Some((entry.task_type, count));
             }
         }
 
-        best_match.map(|(t, _)| t).unwrap_or(TaskType::Feature)
+        match best_match {
+            Some((task_type, count)) => (task_type, count as f64 / total as f64),
+            None => (TaskType::Feature, 0.0),
+        }
     }
 
     pub fn record_cost(&mut self, task_type: TaskType, tokens: usize) {
-        let key = format!("{:?}", task_type).to_lowercase();
+        let key = task_type.as_str().to_string();
         let entry = self.task_costs.entry(key).or_insert(CostEntry {
             tokens: 0,
             count: 0,
@@ -121,11 +182,53 @@ impl Oracle {
     }
 
     pub fn estimate_cost(&self, task_type: TaskType) -> Option<usize> {
-        let key = format!("{:?}", task_type).to_lowercase();
         self.task_costs
-            .get(&key)
+            .get(task_type.as_str())
             .map(|e| e.tokens.checked_div(e.count).unwrap_or(0))
     }
+
+    /// Record the files a turn of `task_type` used
+    pub fn record_files(&mut self, task_type: TaskType, files: &[String]) {
+        let entry = self
+            .task_files
+            .entry(task_type.as_str().to_string())
+            .or_default();
+        entry.turns += 1;
+        let unique: std::collections::HashSet<&String> = files.iter().collect();
+        for file in unique {
+            *entry.files.entry(file.clone()).or_insert(0) += 1;
+        }
+    }
+
+    /// Files most turns of `task_type` used, most common first, once enough
+    /// turns of that type were seen
+    pub fn prefetch_files(&self, task_type: TaskType) -> Vec<String> {
+        let Some(entry) = self.task_files.get(task_type.as_str()) else {
+            return Vec::new();
+        };
+        if entry.turns < MIN_PREFETCH_TURNS {
+            return Vec::new();
+        }
+        let mut files: Vec<(&String, usize)> = entry
+            .files
+            .iter()
+            .filter(|&(_, &n)| n as f64 / entry.turns as f64 >= MIN_PREFETCH_SHARE)
+            .map(|(f, &n)| (f, n))
+            .collect();
+        files.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        files
+            .into_iter()
+            .take(MAX_PREFETCH_FILES)
+            .map(|(f, _)| f.clone())
+            .collect()
+    }
+
+    /// Turns recorded for `task_type`
+    pub fn sample_count(&self, task_type: TaskType) -> usize {
+        self.task_costs
+            .get(task_type.as_str())
+            .map_or(0, |e| e.count)
+    }
 }
 
 impl Default for Oracle {
@@ -138,6 +241,41 @@ impl Default for Oracle {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_classify_confidence() {
+        let oracle = Oracle::new();
+        let (task_type, confidence) = oracle.classify_with_confidence("update the deploy config");
+        assert_eq!(task_type, TaskType::Config);
+        assert_eq!(confidence, 1.0);
+        let (_, mixed) = oracle.classify_with_confidence("fix the config");
+        assert!(mixed <= 0.5);
+        assert_eq!(oracle.classify_with_confidence("hello").1, 0.0);
+    }
+
+    #[test]
+    fn test_prefetch_files_per_task_type() {
+        let mut oracle = Oracle::new();
+        let files =
+            |names: &[&str]| -> Vec<String> { names.iter().map(|n| n.to_string()).collect() };
+        for i in 0..6 {
+            let mut used = files(&["src/settings.rs", "deploy.yaml"]);
+            if i % 3 == 0 {
+                used.push("src/main.rs".to_string());
+            }
+            oracle.record_files(TaskType::Config, &used);
+            oracle.record_files(TaskType::BugFix, &files(&["src/parser.rs"]));
+        }
+        assert_eq!(
+            oracle.prefetch_files(TaskType::Config),
+            vec!["deploy.yaml", "src/settings.rs"]
+        );
+        assert!(oracle.prefetch_files(TaskType::Feature).is_empty());
+
+        let mut young = Oracle::new();
+        young.record_files(TaskType::Config, &files(&["deploy.yaml"]));
+        assert!(young.prefetch_files(TaskType::Config).is_empty());
+    }
+
     #[test]
     fn test_classify_bugfix() {
         let oracle = Oracle::new();
@@ -185,6 +323,16 @@ mod tests {
         assert_eq!(oracle.estimate_cost(TaskType::BugFix), Some(1500));
     }
 
+    #[test]
+    fn test_task_type_names_match_serde() {
+        for task_type in TaskType::ALL {
+            let json = serde_json::to_string(&task_type).unwrap();
+            assert_eq!(json, format!("\"{}\"", task_type.as_str()));
+            assert_eq!(TaskType::from_name(task_type.as_str()), Some(task_type));
+        }
+        assert_eq!(TaskType::from_name("unknown"), None);
+    }
+
     #[test]
     fn test_json_roundtrip() {
         let mut oracle = Oracle::new();
@@ -1,12 +1,18 @@
 //! File predictor with dual-mode prediction
 
+use crate::learner::Learner;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::OnceLock;
 
 static FILE_MENTION_RE: OnceLock<Regex> = OnceLock::new();
 
+/// Minimum prompts a keyword must appear in before it can become a rule
+const STRONG_KEYWORD_MIN_OCCURRENCES: usize = 5;
+/// Fraction of a keyword's prompts that must touch the same file
+const STRONG_KEYWORD_MIN_PRECISION: f64 = 0.9;
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Predictor {
     file_popularity: HashMap<String, usize>,
@@ -64,6 +70,89 @@ impl Predictor {
         }
     }
 
+    /// Mine high-precision keyword→file rules from (prompt, files) pairs.
+    ///
+    /// A keyword becomes a rule when it appears in at least
+    /// `STRONG_KEYWORD_MIN_OCCURRENCES` prompts and exactly one file is touched
+    /// in more than `STRONG_KEYWORD_MIN_PRECISION` of them. Existing rules are
+    /// kept unless re-mined. Returns the number of rules mined.
+    pub fn mine_strong_keywords(&mut self, pairs: &[(String, Vec<String>)]) -> usize {
+        let mut keyword_counts: HashMap<String, usize> = HashMap::new();
+        let mut keyword_file_counts: HashMap<String, HashMap<String, usize>> = HashMap::new();
+
+        for (prompt, files) in pairs {
+            let words: HashSet<String> = Learner::extract_words(prompt).into_iter().collect();
+            let files: HashSet<&String> = files.iter().collect();
+            for word in words {
+                *keyword_counts.entry(word.clone()).or_insert(0) += 1;
+                let per_file = keyword_file_counts.entry(word).or_default();
+                for file in &files {
+                    *per_file.entry((*file).clone()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut mined = 0;
+        for (keyword, occurrences) in keyword_counts {
+            if occurrences < STRONG_KEYWORD_MIN_OCCURRENCES {
+                continue;
+            }
+            let Some(per_file) = keyword_file_counts.get(&keyword) else {
+                continue;
+            };
+            let precise: Vec<&String> = per_file
+                .iter()
+                .filter(|(_, count)| {
+                    **count as f64 / occurrences as f64 > STRONG_KEYWORD_MIN_PRECISION
+                })
+                .map(|(file, _)| file)
+                .collect();
+            if let [file] = precise.as_slice() {
+                self.strong_keywords.insert(keyword, (*file).clone());
+                mined += 1;
+            }
+        }
+        mined
+    }
+
+    /// Current keyword→file rules
+    pub fn strong_keywords(&self) -> &HashMap<String, String> {
+        &self.strong_keywords
+    }
+
+    /// Add or replace a keyword→file rule
+    pub fn set_strong_keyword(&mut self, keyword: &str, file: &str) {
+        self.strong_keywords
+            .insert(keyword.to_lowercase(), file.to_string());
+    }
+
+    /// Remove a keyword rule, returning the file it pointed to
+    pub fn remove_strong_keyword(&mut self, keyword: &str) -> Option<String> {
+        self.strong_keywords.remove(&keyword.to_lowercase())
+    }
+
+    /// Drop every rule whose file `keep` rejects
+    pub fn retain_strong_keywords(&mut self, keep: impl Fn(&str) -> bool) {
+        self.strong_keywords.retain(|_, file| keep(file));
+    }
+
+    /// `(keyword, file)` for each rule a word of `prompt` triggers, by keyword
+    pub fn keyword_matches(&self, prompt: &str) -> Vec<(String, String)> {
+        if self.strong_keywords.is_empty() {
+            return Vec::new();
+        }
+        let mut matches: Vec<(String, String)> = Learner::extract_words(prompt)
+            .into_iter()
+            .filter_map(|word| {
+                let file = self.strong_keywords.get(&word)?.clone();
+                Some((word, file))
+            })
+            .collect();
+        matches.sort();
+        matches.dedup();
+        matches
+    }
+
     pub fn predict(
         &self,
         prompt: &str,
@@ -186,6 +275,57 @@ mod tests {
         assert!(results.is_empty());
     }
 
+    #[test]
+    fn test_mine_strong_keywords() {
+        let mut pairs = Vec::new();
+        for _ in 0..9 {
+            pairs.push((
+                "tweak the tokenizer".to_string(),
+                vec!["tokens.rs".to_string()],
+            ));
+        }
+        pairs.push(("tokenizer docs".to_string(), vec!["tokens.rs".to_string()]));
+        // "router" appears 5 times but splits across two files
+        for i in 0..5 {
+            let file = if i % 2 == 0 { "router.rs" } else { "config.rs" };
+            pairs.push(("router issue".to_string(), vec![file.to_string()]));
+        }
+
+        let mut predictor = Predictor::new();
+        let mined = predictor.mine_strong_keywords(&pairs);
+        assert_eq!(mined, 2);
+        assert_eq!(
+            predictor.strong_keywords().get("tokenizer"),
+            Some(&"tokens.rs".to_string())
+        );
+        assert_eq!(
+            predictor.strong_keywords().get("tweak"),
+            Some(&"tokens.rs".to_string())
+        );
+        assert!(!predictor.strong_keywords().contains_key("router"));
+    }
+
+    #[test]
+    fn test_mine_strong_keywords_requires_min_occurrences() {
+        let pairs: Vec<_> = (0..4)
+            .map(|_| ("parser".to_string(), vec!["parse.rs".to_string()]))
+            .collect();
+        let mut predictor = Predictor::new();
+        assert_eq!(predictor.mine_strong_keywords(&pairs), 0);
+    }
+
+    #[test]
+    fn test_manual_strong_keyword_predicts() {
+        let mut predictor = Predictor::new();
+        predictor.set_strong_keyword("Tokenizer", "tokens.rs");
+        let results = predictor.predict("the tokenizer is slow", &[], 5);
+        assert_eq!(results[0].0, "tokens.rs");
+        assert_eq!(
+            predictor.remove_strong_keyword("tokenizer"),
+            Some("tokens.rs".to_string())
+        );
+    }
+
     #[test]
     fn test_json_roundtrip() {
         let mut predictor = Predictor::new();
@@ -0,0 +1,284 @@
+//! Project-specific query expansion mined from prompts and the files they led to
+//!
+//! Users write "auth" where the code says "login" or "session". A prompt word
+//! is expanded with a file term when most turns whose prompt holds the word
+//! touched files containing the term, without the prompt saying it, and the
+//! term is much more common in those turns than in turns overall.
+
+use crate::learner::Learner;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::Path;
+
+/// Turns a prompt word must appear in before it is expanded
+const MIN_WORD_TURNS: usize = 3;
+/// Fraction of a word's turns whose files hold the term but whose prompt doesn't
+const MIN_PRECISION: f64 = 0.5;
+/// How much likelier the term is in the word's turns than in any turn
+const MIN_LIFT: f64 = 1.5;
+/// Expansions kept per word, strongest first
+const MAX_EXPANSIONS: usize = 3;
+
+/// Prompt word -> file term -> strength (the precision it was mined with)
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Synonyms {
+    expansions: BTreeMap<String, BTreeMap<String, f64>>,
+}
+
+impl Synonyms {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The table at `path`; empty if it is missing or unreadable
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default()
+    }
+
+    /// Lowercase words of `text` as the learner sees them, with identifiers
+    /// also split at `_`, `-` and camelCase: `createSession` gives
+    /// createsession, create and session
+    pub fn terms(text: &str) -> Vec<String> {
+        let mut terms = Vec::new();
+        for word in text.split(|c: char| !c.is_alphanumeric() && c != '_' && c != '-') {
+            terms.extend(Learner::extract_words(word));
+            let mut part = String::new();
+            let mut parts = Vec::new();
+            let mut prev_lower = false;
+            for c in word.chars() {
+                if (c == '_' || c == '-' || (c.is_uppercase() && prev_lower)) && !part.is_empty() {
+                    parts.push(std::mem::take(&mut part));
+                }
+                if c != '_' && c != '-' {
+                    part.extend(c.to_lowercase());
+                }
+                prev_lower = c.is_lowercase();
+            }
+            if !parts.is_empty() {
+                parts.push(part);
+                terms.extend(parts.iter().flat_map(|p| Learner::extract_words(p)));
+            }
+        }
+        terms
+    }
+
+    /// Mine expansions from `pairs` of prompt and files used, given each
+    /// file's terms (e.g. from its path and declared symbols). Existing
+    /// expansions are kept unless their word is re-mined. Returns the number
+    /// of words mined.
+    pub fn mine(
+        &mut self,
+        pairs: &[(String, Vec<String>)],
+        file_terms: impl Fn(&str) -> Vec<String>,
+    ) -> usize {
+        let mut cached: HashMap<&str, Vec<String>> = HashMap::new();
+        let mut turns = 0;
+        let mut word_turns: HashMap<String, usize> = HashMap::new();
+        let mut term_turns: HashMap<String, usize> = HashMap::new();
+        // word -> term -> turns whose files hold the term and prompt doesn't
+        let mut co: HashMap<String, HashMap<String, usize>> = HashMap::new();
+
+        for (prompt, files) in pairs {
+            if files.is_empty() {
+                continue;
+            }
+            turns += 1;
+            let words: HashSet<String> = Learner::extract_words(prompt).into_iter().collect();
+            for file in files {
+                cached.entry(file).or_insert_with(|| file_terms(file));
+            }
+            let terms: HashSet<&String> = files.iter().flat_map(|f| &cached[f.as_str()]).collect();
+            for term in &terms {
+                *term_turns.entry((*term).clone()).or_insert(0) += 1;
+            }
+            for word in words.iter() {
+                *word_turns.entry(word.clone()).or_insert(0) += 1;
+                let per_term = co.entry(word.clone()).or_default();
+                for term in terms.iter().filter(|t| !words.contains(**t)) {
+                    *per_term.entry((*term).clone()).or_insert(0) += 1;
+                }
+            }
+        }
+
+        let mut mined = 0;
+        for (word, per_term) in co {
+            let occurrences = word_turns[&word];
+            if occurrences < MIN_WORD_TURNS {
+                continue;
+            }
+            let mut kept: Vec<(String, f64, f64)> = per_term
+                .into_iter()
+                .filter_map(|(term, count)| {
+                    let precision = count as f64 / occurrences as f64;
+                    let base = term_turns[&term] as f64 / turns as f64;
+                    let lift = precision / base;
+                    (precision >= MIN_PRECISION && lift >= MIN_LIFT)
+                        .then_some((term, precision, lift))
+                })
+                .collect();
+            kept.sort_by(|a, b| {
+                b.1.total_cmp(&a.1)
+                    .then(b.2.total_cmp(&a.2))
+                    .then_with(|| a.0.cmp(&b.0))
+            });
+            kept.truncate(MAX_EXPANSIONS);
+            if !kept.is_empty() {
+                let expansions = kept.into_iter().map(|(term, p, _)| (term, p)).collect();
+                self.expansions.insert(word, expansions);
+                mined += 1;
+            }
+        }
+        mined
+    }
+
+    /// Write the table as sorted, hand-editable JSON
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let json = serde_json::to_string_pretty(self)?;
+        attentive_telemetry::atomic_write(path, json.as_bytes())?;
+        Ok(())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.expansions.is_empty()
+    }
+
+    pub fn len(&self) -> usize {
+        self.expansions.len()
+    }
+
+    /// Every word with its expansions, strongest first
+    pub fn entries(&self) -> Vec<(&str, Vec<(&str, f64)>)> {
+        self.expansions
+            .iter()
+            .map(|(word, terms)| {
+                let mut terms: Vec<(&str, f64)> =
+                    terms.iter().map(|(t, &s)| (t.as_str(), s)).collect();
+                terms.sort_by(|a, b| b.1.total_cmp(&a.1).then(a.0.cmp(b.0)));
+                (word.as_str(), terms)
+            })
+            .collect()
+    }
+
+    /// Terms to add for `words`, with their strength; terms already among
+    /// `words` are left out, and one reached from several words keeps its
+    /// strongest
+    pub fn expand(&self, words: &[String]) -> Vec<(String, f64)> {
+        let mut added: BTreeMap<&str, f64> = BTreeMap::new();
+        for word in words {
+            for (term, &strength) in self.expansions.get(word).into_iter().flatten() {
+                if words.contains(term) {
+                    continue;
+                }
+                let entry = added.entry(term).or_insert(0.0);
+                *entry = entry.max(strength);
+            }
+        }
+        added
+            .into_iter()
+            .map(|(term, strength)| (term.to_string(), strength))
+            .collect()
+    }
+
+    /// `text` followed by the expansions of its words, for lookups that take
+    /// plain text
+    pub fn expand_text(&self, text: &str) -> String {
+        let added = self.expand(&Learner::extract_words(text));
+        if added.is_empty() {
+            return text.to_string();
+        }
+        let terms: Vec<String> = added.into_iter().map(|(term, _)| term).collect();
+        format!("{} {}", text, terms.join(" "))
+    }
+
+    /// Word -> expansion terms, for the search index
+    pub fn term_map(&self) -> HashMap<String, Vec<String>> {
+        self.expansions
+            .iter()
+            .map(|(word, terms)| (word.clone(), terms.keys().cloned().collect()))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pairs() -> Vec<(String, Vec<String>)> {
+        let turn = |prompt: &str, file: &str| (prompt.to_string(), vec![file.to_string()]);
+        vec![
+            turn("fix the auth bug", "src/login.rs"),
+            turn("auth fails on expiry", "src/login.rs"),
+            turn("tidy auth errors", "src/session_store.rs"),
+            turn("auth redirect loops", "src/login.rs"),
+            turn("render the chart", "src/chart.rs"),
+            turn("chart axis labels", "src/chart.rs"),
+            turn("speed up chart render", "src/chart.rs"),
+            turn("cache the chart data", "src/chart.rs"),
+        ]
+    }
+
+    fn mined() -> Synonyms {
+        let mut synonyms = Synonyms::new();
+        synonyms.mine(&pairs(), Synonyms::terms);
+        synonyms
+    }
+
+    #[test]
+    fn test_terms_split_identifiers() {
+        assert_eq!(
+            Synonyms::terms("src/createSession.rs"),
+            ["src", "createsession", "create", "session"]
+        );
+        assert_eq!(
+            Synonyms::terms("parse_token_stream"),
+            ["parse_token_stream", "parse", "token", "stream"]
+        );
+    }
+
+    #[test]
+    fn test_mine_expands_words_with_file_terms() {
+        let mut synonyms = Synonyms::new();
+        assert_eq!(synonyms.mine(&pairs(), Synonyms::terms), 1);
+        let entries = synonyms.entries();
+        let auth = entries.iter().find(|(w, _)| *w == "auth").unwrap();
+        assert_eq!(auth.1[0], ("login", 0.75));
+        // In every turn's files, so it tells nothing apart
+        assert!(auth.1.iter().all(|(t, _)| *t != "src"));
+        // Only one of four auth turns touched the session store
+        assert!(auth.1.iter().all(|(t, _)| *t != "session"));
+        // "chart" is in the prompts already
+        assert!(entries.iter().find(|(w, _)| *w == "chart").is_none());
+
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("synonyms.json");
+        synonyms.save(&path).unwrap();
+        assert_eq!(Synonyms::load(&path), synonyms);
+        assert!(Synonyms::load(&dir.path().join("missing.json")).is_empty());
+
+        // Mining pairs without "auth" leaves its expansions alone
+        let mut synonyms = mined();
+        assert_eq!(synonyms.mine(&pairs()[4..], Synonyms::terms), 0);
+        assert_eq!(synonyms, mined());
+    }
+
+    #[test]
+    fn test_expand() {
+        let synonyms = mined();
+        let words = ["auth".to_string(), "fix".to_string()];
+        assert_eq!(synonyms.expand(&words), [("login".to_string(), 0.75)]);
+        let words = ["auth".to_string(), "login".to_string()];
+        assert!(synonyms.expand(&words).is_empty());
+        assert_eq!(
+            synonyms.expand_text("auth is broken"),
+            "auth is broken login"
+        );
+        assert_eq!(synonyms.term_map()["auth"], ["login"]);
+    }
+}
@@ -0,0 +1,193 @@
+//! On-disk word tables for the learner
+//!
+//! A mature learner has seen thousands of prompt words, and deserializing all
+//! of their file counts dominated the prompt hook. The tables live in SQLite
+//! next to learned_state.json instead: the prompt hook reads the rows for its
+//! prompt's words, while commands that update the learner load them all.
+
+use crate::learner::{Learner, WordTables};
+use anyhow::Result;
+use attentive_telemetry::SqlitePool;
+use rusqlite::{params, params_from_iter};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// File name of the word index, beside learned_state.json
+pub const WORD_INDEX_FILE: &str = "learned_words.db";
+
+pub struct WordIndex {
+    pool: SqlitePool,
+}
+
+impl WordIndex {
+    pub fn open(path: &Path) -> Result<Self> {
+        let index = Self {
+            pool: SqlitePool::open(path)?,
+        };
+        let conn = index.pool.get()?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS word_freq (
+                word TEXT PRIMARY KEY,
+                doc_freq INTEGER NOT NULL
+            ) WITHOUT ROWID",
+            [],
+        )?;
+        conn.execute(
+            "CREATE TABLE IF NOT EXISTS word_files (
+                word TEXT NOT NULL,
+                file TEXT NOT NULL,
+                count INTEGER NOT NULL,
+                credit REAL,
+                last_turn INTEGER,
+                PRIMARY KEY (word, file)
+            ) WITHOUT ROWID",
+            [],
+        )?;
+        drop(conn);
+        Ok(index)
+    }
+
+    /// Every word's rows, for commands that update and save the learner
+    pub fn load_all(&self) -> Result<WordTables> {
+        self.load("", Vec::new())
+    }
+
+    /// Only the rows for the words `prompt` would be scored on
+    pub fn load_for_prompt(&self, prompt: &str) -> Result<WordTables> {
+        let words: Vec<String> = Learner::extract_words(prompt)
+            .into_iter()
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        if words.is_empty() {
+            return Ok(WordTables::default());
+        }
+        let placeholders = vec!["?"; words.len()].join(", ");
+        self.load(&format!(" WHERE word IN ({})", placeholders), words)
+    }
+
+    fn load(&self, filter: &str, words: Vec<String>) -> Result<WordTables> {
+        let conn = self.pool.get()?;
+        let mut tables = WordTables::default();
+
+        let mut stmt = conn.prepare(&format!("SELECT word, doc_freq FROM word_freq{}", filter))?;
+        let rows = stmt.query_map(params_from_iter(&words), |row| {
+            Ok((row.get::<_, String>(0)?, row.get::<_, i64>(1)?))
+        })?;
+        for row in rows {
+            let (word, doc_freq) = row?;
+            tables.doc_freq.insert(word, doc_freq as usize);
+        }
+
+        let mut stmt = conn.prepare(&format!(
+            "SELECT word, file, count, credit, last_turn FROM word_files{}",
+            filter
+        ))?;
+        let rows = stmt.query_map(params_from_iter(&words), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, i64>(2)?,
+                row.get::<_, Option<f64>>(3)?,
+                row.get::<_, Option<i64>>(4)?,
+            ))
+        })?;
+        for row in rows {
+            let (word, file, count, credit, last_turn) = row?;
+            if let Some(credit) = credit {
+                tables
+                    .credit
+                    .entry(word.clone())
+                    .or_default()
+                    .insert(file.clone(), credit);
+            }
+            if let Some(last_turn) = last_turn {
+                tables
+                    .last_turn
+                    .entry(word.clone())
+                    .or_default()
+                    .insert(file.clone(), last_turn as usize);
+            }
+            tables
+                .counts
+                .entry(word)
+                .or_default()
+                .insert(file, count as usize);
+        }
+        Ok(tables)
+    }
+
+    /// Replace the stored tables with `tables` in one transaction, so a
+    /// concurrent prompt hook sees either the old rows or the new ones.
+    /// Credits and last turns are kept for pairs with a count; the learner
+    /// ignores the others.
+    pub fn store(&self, tables: &WordTables) -> Result<()> {
+        let conn = self.pool.get()?;
+        let tx = conn.unchecked_transaction()?;
+        tx.execute("DELETE FROM word_freq", [])?;
+        tx.execute("DELETE FROM word_files", [])?;
+        {
+            let mut insert =
+                tx.prepare_cached("INSERT INTO word_freq (word, doc_freq) VALUES (?1, ?2)")?;
+            for (word, doc_freq) in &tables.doc_freq {
+                insert.execute(params![word, *doc_freq as i64])?;
+            }
+            let mut insert = tx.prepare_cached(
+                "INSERT INTO word_files (word, file, count, credit, last_turn)
+                 VALUES (?1, ?2, ?3, ?4, ?5)",
+            )?;
+            for (word, files) in &tables.counts {
+                let credits = tables.credit.get(word);
+                let last_turns = tables.last_turn.get(word);
+                for (file, count) in files {
+                    insert.execute(params![
+                        word,
+                        file,
+                        *count as i64,
+                        credits.and_then(|c| c.get(file)),
+                        last_turns.and_then(|t| t.get(file)).map(|&t| t as i64),
+                    ])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn test_word_index_roundtrip_and_prompt_subset() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let index = WordIndex::open(&dir.path().join(WORD_INDEX_FILE)).unwrap();
+
+        let mut learner = Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("router decay tuning", &["src/router.rs".to_string()]);
+            learner.observe_turn("config schema keys", &["src/config.rs".to_string()]);
+        }
+        let full = learner.clone();
+        let tables = learner.take_word_tables();
+        assert!(!learner.has_word_tables());
+        index.store(&tables).unwrap();
+        assert_eq!(index.load_all().unwrap(), tables);
+
+        // Scoring with just the prompt's rows matches scoring with all of them
+        let subset = index.load_for_prompt("tune the router decay").unwrap();
+        assert!(subset.counts.contains_key("router"));
+        assert!(!subset.counts.contains_key("config"));
+        learner.set_word_tables(subset);
+        let scores = HashMap::from([
+            ("src/router.rs".to_string(), 0.2),
+            ("src/config.rs".to_string(), 0.2),
+        ]);
+        assert_eq!(
+            learner.boost_scores("tune the router decay", &scores),
+            full.boost_scores("tune the router decay", &scores)
+        );
+    }
+}
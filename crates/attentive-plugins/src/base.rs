@@ -1,17 +1,135 @@
 //! Base plugin trait and utilities
 
+use attentive_telemetry::{TurnRecord, Versioned};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeMap, HashMap};
 use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static READ_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// State saved while read-only, by state file, read back in its place
+static READ_ONLY_STATE: Mutex<BTreeMap<PathBuf, serde_json::Value>> = Mutex::new(BTreeMap::new());
+
+/// Keep `save_state` off disk for the rest of the process (used by dry runs).
+/// Saved state is held in memory instead, so a replay of several prompts
+/// sees its own changes, e.g. queued files drained once rather than every
+/// prompt.
+pub fn set_read_only(read_only: bool) {
+    READ_ONLY.store(read_only, Ordering::SeqCst);
+}
+
+/// What a tool call does, from its tool name (case-insensitive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToolKind {
+    Read,
+    Edit,
+    MultiEdit,
+    Write,
+    NotebookEdit,
+    Bash,
+    Grep,
+    Glob,
+    Other,
+}
+
+impl ToolKind {
+    pub fn from_tool(tool: &str) -> Self {
+        match tool.to_ascii_lowercase().as_str() {
+            "read" => Self::Read,
+            "edit" => Self::Edit,
+            "multiedit" => Self::MultiEdit,
+            "write" => Self::Write,
+            "notebookedit" => Self::NotebookEdit,
+            "bash" => Self::Bash,
+            "grep" => Self::Grep,
+            "glob" => Self::Glob,
+            _ => Self::Other,
+        }
+    }
+
+    /// Whether the call modifies its target file
+    pub fn is_write(self) -> bool {
+        matches!(
+            self,
+            Self::Edit | Self::MultiEdit | Self::Write | Self::NotebookEdit
+        )
+    }
+}
+
+/// One string replacement made by Edit or MultiEdit
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct EditOp {
+    pub old_string: String,
+    #[serde(default)]
+    pub new_string: String,
+}
 
 /// Tool call representation
-#[derive(Debug, Clone, Serialize, Deserialize)]
+///
+/// `target`, `content`, `old_string` and `command` keep their original
+/// meaning; the structured fields default to empty so older JSON still parses.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ToolCall {
     pub tool: String,
     pub target: Option<String>,
     pub content: Option<String>,
     pub old_string: Option<String>,
     pub command: Option<String>,
+    /// Replacements in order: the single edit of Edit, every edit of MultiEdit
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub edits: Vec<EditOp>,
+    /// Search pattern of Grep or Glob
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pattern: Option<String>,
+    /// File filter glob of Grep
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub glob: Option<String>,
+}
+
+impl ToolCall {
+    /// A tool call from its name and `input` object (transcript or hook JSON)
+    pub fn from_input(tool: &str, input: &serde_json::Value) -> Self {
+        let field = |input: &serde_json::Value, name: &str| {
+            input.get(name).and_then(|v| v.as_str()).map(String::from)
+        };
+        let edit = |input: &serde_json::Value| {
+            Some(EditOp {
+                old_string: field(input, "old_string")?,
+                new_string: field(input, "new_string").unwrap_or_default(),
+            })
+        };
+        let edits = match input.get("edits").and_then(|e| e.as_array()) {
+            Some(edits) => edits.iter().filter_map(edit).collect(),
+            None => edit(input).into_iter().collect(),
+        };
+        Self {
+            tool: tool.to_string(),
+            target: field(input, "file_path")
+                .or_else(|| field(input, "path"))
+                .or_else(|| field(input, "notebook_path")),
+            content: field(input, "content").or_else(|| field(input, "new_source")),
+            old_string: field(input, "old_string"),
+            command: field(input, "command"),
+            edits,
+            pattern: field(input, "pattern"),
+            glob: field(input, "glob"),
+        }
+    }
+
+    pub fn kind(&self) -> ToolKind {
+        ToolKind::from_tool(&self.tool)
+    }
+
+    /// Text this call replaces: every edit's old string, else `old_string`
+    pub fn replaced(&self) -> Vec<&str> {
+        if self.edits.is_empty() {
+            self.old_string.as_deref().into_iter().collect()
+        } else {
+            self.edits.iter().map(|e| e.old_string.as_str()).collect()
+        }
+    }
 }
 
 /// Session state shared across plugins
@@ -28,36 +146,56 @@ pub fn state_file(plugin_name: &str) -> anyhow::Result<PathBuf> {
     Ok(plugins_dir()?.join(format!("{}_state.json", plugin_name)))
 }
 
-/// Load plugin state from disk
+/// Load plugin state from disk, migrating older schema versions
 pub fn load_state<T>(plugin_name: &str) -> anyhow::Result<T>
 where
-    T: for<'de> Deserialize<'de> + Default,
+    T: Versioned + Default,
 {
     let state_path = state_file(plugin_name)?;
+    if READ_ONLY.load(Ordering::SeqCst)
+        && let Some(saved) = READ_ONLY_STATE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&state_path)
+    {
+        return Ok(serde_json::from_value(saved.clone())?);
+    }
     if !state_path.exists() {
         return Ok(T::default());
     }
 
-    let contents = std::fs::read_to_string(&state_path)?;
-    let state: T = serde_json::from_str(&contents)?;
-    Ok(state)
+    Ok(attentive_telemetry::read_versioned(&state_path)?)
 }
 
 /// Save plugin state to disk
 pub fn save_state<T>(plugin_name: &str, state: &T) -> anyhow::Result<()>
 where
-    T: Serialize,
+    T: Versioned,
 {
     let state_path = state_file(plugin_name)?;
+    if READ_ONLY.load(Ordering::SeqCst) {
+        READ_ONLY_STATE
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(state_path, serde_json::to_value(state)?);
+        return Ok(());
+    }
     if let Some(parent) = state_path.parent() {
         std::fs::create_dir_all(parent)?;
     }
 
-    let json = serde_json::to_string_pretty(state)?;
-    attentive_telemetry::atomic_write(&state_path, json.as_bytes())?;
+    attentive_telemetry::write_versioned(&state_path, state)?;
     Ok(())
 }
 
+/// Upgrade a plugin's state file to the current schema; returns the version
+/// it was migrated from, if it needed migrating
+pub fn migrate_state<T: Versioned>(plugin_name: &str) -> anyhow::Result<Option<u32>> {
+    Ok(attentive_telemetry::migrate_file::<T>(&state_file(
+        plugin_name,
+    )?)?)
+}
+
 /// Check if a plugin is enabled in config
 pub fn is_plugin_enabled(plugin_name: &str) -> bool {
     let plugins_directory = match plugins_dir() {
@@ -84,6 +222,30 @@ pub fn is_plugin_enabled(plugin_name: &str) -> bool {
     }
 }
 
+/// A plugin's own settings: the `plugin_name` object in plugins/config.json
+pub fn plugin_settings(plugin_name: &str) -> Option<serde_json::Value> {
+    let config_file = plugins_dir().ok()?.join("config.json");
+    let contents = std::fs::read_to_string(config_file).ok()?;
+    let mut config: serde_json::Value = serde_json::from_str(&contents).ok()?;
+    config.get_mut(plugin_name).map(serde_json::Value::take)
+}
+
+/// Most a file's routing score may move in `on_scores`, across all plugins
+pub const MAX_SCORE_DELTA: f64 = 0.3;
+
+/// How the registry shortens a plugin's `on_prompt_post` output that
+/// exceeds the plugin's token budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Truncation {
+    /// Keep the leading lines and note how many were cut
+    #[default]
+    KeepStart,
+    /// Keep the trailing lines and note how many were cut
+    KeepEnd,
+    /// Inject nothing rather than part of the output
+    Drop,
+}
+
 /// Base trait for attentive plugins
 pub trait Plugin: Send + Sync {
     /// Plugin name (unique identifier)
@@ -111,11 +273,25 @@ pub trait Plugin: Send + Sync {
         None
     }
 
+    /// Publish values into the shared session state before routing starts
+    fn share_session_state(&self, _session_state: &mut SessionState) {}
+
     /// Called before context routing
     fn on_prompt_pre(&mut self, prompt: String, _session_state: &SessionState) -> (String, bool) {
         (prompt, true)
     }
 
+    /// Stable context for the prefix slot, placed ahead of the per-prompt file
+    /// content. Keep it identical from turn to turn so it stays in the prompt
+    /// cache; anything that changes per turn belongs in `on_prompt_post`.
+    fn on_prompt_prefix(&mut self, _session_state: &SessionState) -> String {
+        String::new()
+    }
+
+    /// Adjust routing scores after the router's learner boost and before
+    /// files are tiered. Changes beyond `MAX_SCORE_DELTA` are clamped.
+    fn on_scores(&mut self, _scores: &mut HashMap<String, f64>, _prompt: &str) {}
+
     /// Called after context routing
     fn on_prompt_post(
         &mut self,
@@ -126,7 +302,13 @@ pub trait Plugin: Send + Sync {
         String::new()
     }
 
-    /// Called after Claude finishes (Stop hook)
+    /// How `on_prompt_post` output is cut down to a configured budget
+    fn truncation(&self) -> Truncation {
+        Truncation::KeepStart
+    }
+
+    /// Called after Claude finishes (Stop hook) with the tool calls of the
+    /// turn that just ended
     fn on_stop(
         &mut self,
         _tool_calls: &[ToolCall],
@@ -134,6 +316,20 @@ pub trait Plugin: Send + Sync {
     ) -> Option<String> {
         None
     }
+
+    /// Called before a tool runs (PreToolUse hook); a returned message asks the
+    /// user to confirm the call
+    fn on_pre_tool_use(&mut self, _tool_call: &ToolCall) -> Option<String> {
+        None
+    }
+
+    /// Attach per-turn signals to the telemetry record (after on_stop)
+    fn on_turn_record(&mut self, _record: &mut TurnRecord) {}
+
+    /// Upgrade this plugin's state file to the current schema (see `migrate_state`)
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        Ok(None)
+    }
 }
 
 #[cfg(test)]
@@ -160,6 +356,7 @@ mod tests {
         assert_eq!(plugin.version(), "0.1.0");
         assert_eq!(plugin.description(), "");
         assert!(plugin.is_enabled()); // Default is enabled
+        assert_eq!(plugin.truncation(), Truncation::KeepStart);
     }
 
     #[test]
@@ -177,6 +374,11 @@ mod tests {
             message: String,
         }
 
+        impl Versioned for TestState {
+            const VERSION: u32 = 1;
+            const KIND: &'static str = "test plugin state";
+        }
+
         let plugin_name = "test-state-plugin";
 
         // Save state
@@ -210,11 +412,51 @@ mod tests {
         assert_eq!(prompt, "test prompt");
         assert!(cont);
 
-        // on_prompt_post returns empty string by default
+        // on_prompt_prefix and on_prompt_post return empty strings by default
+        assert_eq!(plugin.on_prompt_prefix(&session_state), "");
         let result = plugin.on_prompt_post("prompt", "context", &session_state);
         assert_eq!(result, "");
 
         // on_stop returns None by default
         assert_eq!(plugin.on_stop(&[], &session_state), None);
     }
+
+    #[test]
+    fn test_tool_call_from_multiedit_and_grep_input() {
+        let multi = ToolCall::from_input(
+            "MultiEdit",
+            &serde_json::json!({
+                "file_path": "/repo/src/lib.rs",
+                "edits": [
+                    {"old_string": "fn a()", "new_string": "fn a2()"},
+                    {"old_string": "fn b()", "new_string": "fn b2()"}
+                ]
+            }),
+        );
+        assert_eq!(multi.kind(), ToolKind::MultiEdit);
+        assert!(multi.kind().is_write());
+        assert_eq!(multi.target.as_deref(), Some("/repo/src/lib.rs"));
+        assert_eq!(multi.replaced(), vec!["fn a()", "fn b()"]);
+        assert_eq!(multi.edits[1].new_string, "fn b2()");
+
+        let grep = ToolCall::from_input(
+            "Grep",
+            &serde_json::json!({"pattern": "fn route", "path": "src", "glob": "*.rs"}),
+        );
+        assert_eq!(grep.kind(), ToolKind::Grep);
+        assert_eq!(grep.pattern.as_deref(), Some("fn route"));
+        assert_eq!(grep.glob.as_deref(), Some("*.rs"));
+        assert!(grep.replaced().is_empty());
+    }
+
+    #[test]
+    fn test_tool_call_deserializes_flat_json() {
+        let call: ToolCall = serde_json::from_str(
+            r#"{"tool":"edit","target":"a.rs","content":null,"old_string":"x","command":null}"#,
+        )
+        .unwrap();
+        assert_eq!(call.kind(), ToolKind::Edit);
+        assert!(call.edits.is_empty());
+        assert_eq!(call.replaced(), vec!["x"]);
+    }
 }
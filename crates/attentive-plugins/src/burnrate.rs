@@ -1,6 +1,9 @@
 //! BurnRate Plugin - Predicts and warns about rate limit consumption
 
-use crate::base::{Plugin, SessionState, ToolCall, load_state, save_state};
+use crate::base::{
+    Plugin, SessionState, ToolCall, Truncation, load_state, migrate_state, save_state,
+};
+use attentive_telemetry::{TurnRecord, Versioned};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
@@ -8,6 +11,11 @@ const SAMPLE_WINDOW: usize = 20;
 const WARNING_THRESHOLD_MINUTES: f64 = 30.0;
 const CRITICAL_THRESHOLD_MINUTES: f64 = 10.0;
 
+/// Session-state key holding the context budget multiplier (0.0–1.0]
+pub const CONTEXT_SCALE_KEY: &str = "context_budget_scale";
+/// Never shrink the context budget below this fraction
+pub const MIN_CONTEXT_SCALE: f64 = 0.25;
+
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct BurnRateState {
     samples: VecDeque<Sample>,
@@ -15,6 +23,11 @@ struct BurnRateState {
     warnings_issued: usize,
 }
 
+impl Versioned for BurnRateState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "burnrate plugin state";
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct Sample {
     timestamp: String,
@@ -93,7 +106,7 @@ impl BurnRatePlugin {
             .unwrap_or(0);
 
         let sample = Sample {
-            timestamp: chrono::Utc::now().to_rfc3339(),
+            timestamp: attentive_telemetry::now().to_rfc3339(),
             session_tokens,
         };
 
@@ -150,6 +163,21 @@ impl BurnRatePlugin {
             minutes_remaining,
         })
     }
+
+    /// Budget multiplier: 1.0 normally, shrinking with the remaining window once
+    /// the limit is within the warning horizon. A reset window restores 1.0.
+    fn context_scale(state: &BurnRateState, stats: &serde_json::Value) -> f64 {
+        let Some(rate_info) = Self::calculate_burn_rate(state, stats) else {
+            return 1.0;
+        };
+        match rate_info.minutes_remaining {
+            Some(m) if m.is_finite() && m <= WARNING_THRESHOLD_MINUTES => {}
+            _ => return 1.0,
+        }
+        let remaining = rate_info.limit.saturating_sub(rate_info.tokens_used) as f64
+            / rate_info.limit.max(1) as f64;
+        remaining.clamp(MIN_CONTEXT_SCALE, 1.0)
+    }
 }
 
 impl Default for BurnRatePlugin {
@@ -163,6 +191,10 @@ impl Plugin for BurnRatePlugin {
         &self.name
     }
 
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<BurnRateState>(self.name())
+    }
+
     fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
         let stats = Self::read_stats_cache()?;
         let plan_type = Self::detect_plan_type(&stats);
@@ -192,6 +224,17 @@ impl Plugin for BurnRatePlugin {
         }
     }
 
+    fn share_session_state(&self, session_state: &mut SessionState) {
+        let Some(stats) = Self::read_stats_cache() else {
+            return;
+        };
+        let state: BurnRateState = load_state(self.name()).unwrap_or_default();
+        session_state.insert(
+            CONTEXT_SCALE_KEY.to_string(),
+            serde_json::json!(Self::context_scale(&state, &stats)),
+        );
+    }
+
     fn on_prompt_post(
         &mut self,
         _prompt: &str,
@@ -259,6 +302,11 @@ impl Plugin for BurnRatePlugin {
         )
     }
 
+    /// A warning missing its figures or advice misleads more than it helps
+    fn truncation(&self) -> Truncation {
+        Truncation::Drop
+    }
+
     fn on_stop(
         &mut self,
         _tool_calls: &[ToolCall],
@@ -272,6 +320,24 @@ impl Plugin for BurnRatePlugin {
 
         None
     }
+
+    fn on_turn_record(&mut self, record: &mut TurnRecord) {
+        let state: BurnRateState = load_state(self.name()).unwrap_or_default();
+        let Some(stats) = Self::read_stats_cache() else {
+            return;
+        };
+        let Some(rate_info) = Self::calculate_burn_rate(&state, &stats) else {
+            return;
+        };
+        record.extensions.insert(
+            self.name().to_string(),
+            serde_json::json!({
+                "tokens_per_minute": rate_info.tokens_per_minute.round(),
+                "minutes_remaining": rate_info.minutes_remaining.map(f64::round),
+                "warnings_issued": state.warnings_issued,
+            }),
+        );
+    }
 }
 
 #[cfg(test)]
@@ -306,4 +372,45 @@ mod tests {
         });
         assert_eq!(BurnRatePlugin::detect_plan_type(&stats_max20), "max_20x");
     }
+
+    fn sampled_state(plan_type: &str, tokens: &[(i64, u64)]) -> BurnRateState {
+        let now = chrono::Utc::now();
+        BurnRateState {
+            samples: tokens
+                .iter()
+                .map(|&(minutes_ago, session_tokens)| Sample {
+                    timestamp: (now - chrono::Duration::minutes(minutes_ago)).to_rfc3339(),
+                    session_tokens,
+                })
+                .collect(),
+            plan_type: plan_type.to_string(),
+            warnings_issued: 0,
+        }
+    }
+
+    #[test]
+    fn test_context_scale_shrinks_near_limit() {
+        // 60k tokens in 10 minutes, 30k left of 150k: ~5 minutes remaining
+        let state = sampled_state("pro", &[(10, 60_000), (0, 120_000)]);
+        let stats = serde_json::json!({"sessionTokens": 120_000});
+        let scale = BurnRatePlugin::context_scale(&state, &stats);
+        assert!((scale - 0.25).abs() < 1e-9);
+
+        let stats = serde_json::json!({"sessionTokens": 100_000});
+        let scale = BurnRatePlugin::context_scale(&state, &stats);
+        assert!(scale > 0.25 && scale < 1.0);
+    }
+
+    #[test]
+    fn test_context_scale_restored_when_idle_or_reset() {
+        // Slow burn: far from the limit
+        let state = sampled_state("pro", &[(60, 1_000), (0, 2_000)]);
+        let stats = serde_json::json!({"sessionTokens": 2_000});
+        assert_eq!(BurnRatePlugin::context_scale(&state, &stats), 1.0);
+
+        // Window reset: session tokens dropped, no consumption measured
+        let state = sampled_state("pro", &[(10, 140_000), (0, 500)]);
+        let stats = serde_json::json!({"sessionTokens": 500});
+        assert_eq!(BurnRatePlugin::context_scale(&state, &stats), 1.0);
+    }
 }
@@ -0,0 +1,325 @@
+//! Guardrail Plugin - Warns about destructive shell commands
+//!
+//! Built-in checks cover `rm -rf` outside the repo, force pushes, dropped
+//! tables and world-writable chmods. More patterns can be added in
+//! plugins/config.json:
+//!
+//! ```json
+//! {"guardrail": {"patterns": ["\\bgit\\s+reset\\s+--hard\\b"], "builtin": true}}
+//! ```
+
+use crate::base::{
+    Plugin, SessionState, ToolCall, ToolKind, load_state, migrate_state, plugin_settings,
+    save_state,
+};
+use attentive_telemetry::{TurnRecord, Versioned};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::path::{Component, Path, PathBuf};
+
+/// Built-in (label, pattern) checks; `rm -rf` is checked separately because it
+/// depends on where the targets are
+const BUILTIN_PATTERNS: &[(&str, &str)] = &[
+    ("force push", r"\bgit\s+push\b[^;&|\n]*\s(--force|-f)(\s|$)"),
+    ("DROP TABLE", r"(?i)\bdrop\s+(table|database|schema)\b"),
+    ("chmod 777", r"\bchmod\s+(-[a-zA-Z]+\s+)*0?777\b"),
+];
+
+const RM_OUTSIDE_REPO: &str = "rm -rf outside the repo";
+
+/// Longest command shown in a warning
+const MAX_COMMAND_CHARS: usize = 80;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct GuardState {
+    /// Destructive commands run this session (already reported)
+    session_count: usize,
+    /// Destructive commands from the most recent on_stop
+    #[serde(default)]
+    last_turn: Vec<Hit>,
+}
+
+impl Versioned for GuardState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "guardrail plugin state";
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct Hit {
+    command: String,
+    label: String,
+}
+
+pub struct GuardrailPlugin {
+    name: String,
+    /// Whether the built-in checks (including `rm -rf`) are on
+    builtin: bool,
+    patterns: Vec<(String, Regex)>,
+    repo_root: PathBuf,
+}
+
+impl GuardrailPlugin {
+    pub fn new() -> Self {
+        let settings = plugin_settings("guardrail").unwrap_or_default();
+        let builtin = settings
+            .get("builtin")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let mut patterns: Vec<(String, Regex)> = Vec::new();
+        if builtin {
+            for (label, pattern) in BUILTIN_PATTERNS {
+                if let Ok(regex) = Regex::new(pattern) {
+                    patterns.push((label.to_string(), regex));
+                }
+            }
+        }
+        // Invalid user patterns are skipped rather than disabling the plugin
+        let extra = settings.get("patterns").and_then(|v| v.as_array());
+        for pattern in extra.into_iter().flatten().filter_map(|v| v.as_str()) {
+            if let Ok(regex) = Regex::new(pattern) {
+                patterns.push((pattern.to_string(), regex));
+            }
+        }
+
+        Self {
+            name: "guardrail".to_string(),
+            builtin,
+            patterns,
+            repo_root: std::env::current_dir().unwrap_or_default(),
+        }
+    }
+
+    /// Treat `rm -rf` outside `root` as destructive, rather than outside the
+    /// working directory
+    pub fn with_repo_root(mut self, root: PathBuf) -> Self {
+        self.repo_root = root;
+        self
+    }
+
+    /// Why `command` is destructive, if it is
+    fn check(&self, command: &str) -> Option<String> {
+        if self.builtin && rm_outside(command, &self.repo_root) {
+            return Some(RM_OUTSIDE_REPO.to_string());
+        }
+        self.patterns
+            .iter()
+            .find(|(_, regex)| regex.is_match(command))
+            .map(|(label, _)| label.clone())
+    }
+
+    fn bash_hits(&self, tool_calls: &[ToolCall]) -> Vec<Hit> {
+        tool_calls
+            .iter()
+            .filter(|tc| tc.kind() == ToolKind::Bash)
+            .filter_map(|tc| {
+                let command = tc.command.as_deref()?;
+                let label = self.check(command)?;
+                Some(Hit {
+                    command: command.to_string(),
+                    label,
+                })
+            })
+            .collect()
+    }
+}
+
+impl Default for GuardrailPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn shorten(command: &str) -> String {
+    let line = command.lines().next().unwrap_or("");
+    if line.chars().count() <= MAX_COMMAND_CHARS && !command.contains('\n') {
+        return line.to_string();
+    }
+    let short: String = line.chars().take(MAX_COMMAND_CHARS).collect();
+    format!("{}…", short)
+}
+
+/// Lexically resolve `target` against `root` (no filesystem access)
+fn resolve(root: &Path, target: &str) -> PathBuf {
+    let mut resolved = PathBuf::new();
+    for component in root.join(target).components() {
+        match component {
+            Component::ParentDir => {
+                resolved.pop();
+            }
+            Component::CurDir => {}
+            other => resolved.push(other),
+        }
+    }
+    resolved
+}
+
+/// Whether `command` runs a recursive forced `rm` on anything outside `root`
+fn rm_outside(command: &str, root: &Path) -> bool {
+    for segment in command.split(['\n', ';', '|', '&']) {
+        let mut words = segment
+            .split_whitespace()
+            .map(|w| w.trim_matches(|c| c == '"' || c == '\''))
+            .skip_while(|w| *w == "sudo" || w.contains('='));
+        let Some(program) = words.next() else {
+            continue;
+        };
+        if program != "rm" && !program.ends_with("/rm") {
+            continue;
+        }
+
+        let (mut recursive, mut force) = (false, false);
+        let mut targets = Vec::new();
+        for word in words {
+            match word {
+                "--recursive" => recursive = true,
+                "--force" => force = true,
+                flag if flag.starts_with("--") => {}
+                flag if flag.starts_with('-') => {
+                    recursive |= flag.contains(['r', 'R']);
+                    force |= flag.contains('f');
+                }
+                target => targets.push(target),
+            }
+        }
+        if !(recursive && force) {
+            continue;
+        }
+        let outside = targets.iter().any(|target| {
+            target.starts_with('~')
+                || target.starts_with("$HOME")
+                || target.starts_with("${HOME}")
+                || !resolve(root, target).starts_with(root)
+        });
+        if outside {
+            return true;
+        }
+    }
+    false
+}
+
+impl Plugin for GuardrailPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn description(&self) -> &str {
+        "Warns about destructive shell commands"
+    }
+
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<GuardState>(self.name())
+    }
+
+    fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
+        save_state(self.name(), &GuardState::default()).ok();
+        Some("Guardrail: Active (destructive command warnings)".to_string())
+    }
+
+    fn on_pre_tool_use(&mut self, tool_call: &ToolCall) -> Option<String> {
+        let hit = self.bash_hits(std::slice::from_ref(tool_call)).pop()?;
+        Some(format!(
+            "Guardrail: `{}` looks destructive ({}). Confirm before running it.",
+            shorten(&hit.command),
+            hit.label
+        ))
+    }
+
+    fn on_stop(
+        &mut self,
+        tool_calls: &[ToolCall],
+        _session_state: &SessionState,
+    ) -> Option<String> {
+        let mut state: GuardState = load_state(self.name()).unwrap_or_default();
+        let hits = self.bash_hits(tool_calls);
+        if hits.is_empty() && state.last_turn.is_empty() {
+            return None;
+        }
+        state.session_count += hits.len();
+        state.last_turn = hits;
+        save_state(self.name(), &state).ok();
+        if state.last_turn.is_empty() {
+            return None;
+        }
+
+        let mut lines: Vec<String> = state
+            .last_turn
+            .iter()
+            .map(|hit| {
+                format!(
+                    "[Guardrail] WARNING: destructive command ran: `{}` ({})",
+                    shorten(&hit.command),
+                    hit.label
+                )
+            })
+            .collect();
+        lines.push(format!(
+            "[Guardrail] {} destructive command(s) this session",
+            state.session_count
+        ));
+        Some(lines.join("\n"))
+    }
+
+    fn on_turn_record(&mut self, record: &mut TurnRecord) {
+        let state: GuardState = load_state(self.name()).unwrap_or_default();
+        if !state.last_turn.is_empty() {
+            record.extensions.insert(
+                self.name().to_string(),
+                serde_json::json!({
+                    "destructive_commands": state.last_turn.len(),
+                    "session_count": state.session_count,
+                }),
+            );
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rm_outside_repo() {
+        let root = Path::new("/work/repo");
+        assert!(rm_outside("rm -rf /tmp/build", root));
+        assert!(rm_outside("cd x && rm -fr ../../other", root));
+        assert!(rm_outside("sudo rm -r -f ~/projects", root));
+        assert!(rm_outside("rm --recursive --force \"$HOME/.cache\"", root));
+        assert!(!rm_outside("rm -rf target node_modules", root));
+        assert!(!rm_outside("rm -rf /work/repo/dist", root));
+        assert!(!rm_outside("rm /tmp/file.txt", root));
+        assert!(!rm_outside("echo rm -rf /", root));
+    }
+
+    #[test]
+    fn test_builtin_patterns() {
+        let plugin = GuardrailPlugin {
+            repo_root: PathBuf::from("/work/repo"),
+            ..GuardrailPlugin::new()
+        };
+        let check = |c: &str| plugin.check(c);
+        assert_eq!(
+            check("git push --force origin main").as_deref(),
+            Some("force push")
+        );
+        assert_eq!(check("git push -f").as_deref(), Some("force push"));
+        assert_eq!(check("git push --force-with-lease"), None);
+        assert_eq!(check("git push origin main"), None);
+        assert_eq!(
+            check("psql -c 'drop table users'").as_deref(),
+            Some("DROP TABLE")
+        );
+        assert_eq!(check("chmod -R 777 /var/www").as_deref(), Some("chmod 777"));
+        assert_eq!(check("chmod 755 script.sh"), None);
+        assert_eq!(check("rm -rf /").as_deref(), Some(RM_OUTSIDE_REPO));
+    }
+
+    #[test]
+    fn test_shorten() {
+        assert_eq!(shorten("ls"), "ls");
+        assert_eq!(shorten("a\nb"), "a…");
+        assert_eq!(
+            shorten(&"x".repeat(100)).chars().count(),
+            MAX_COMMAND_CHARS + 1
+        );
+    }
+}
@@ -2,12 +2,18 @@
 
 pub mod base;
 pub mod burnrate;
+pub mod guardrail;
 pub mod loopbreaker;
+pub mod recall;
 pub mod registry;
 pub mod verifyfirst;
+pub mod xref;
 
-pub use base::{Plugin, SessionState, ToolCall};
+pub use base::{EditOp, Plugin, SessionState, ToolCall, ToolKind, Truncation};
 pub use burnrate::BurnRatePlugin;
+pub use guardrail::GuardrailPlugin;
 pub use loopbreaker::LoopBreakerPlugin;
+pub use recall::RecallPlugin;
 pub use registry::PluginRegistry;
 pub use verifyfirst::VerifyFirstPlugin;
+pub use xref::XrefPlugin;
@@ -1,20 +1,30 @@
 //! LoopBreaker Plugin - Detects and breaks repetitive failure loops
-
-use crate::base::{Plugin, SessionState, ToolCall, load_state, save_state};
+//!
+//! Warnings escalate while the loop goes on: a nudge at 3 attempts, a demand
+//! for a different file or approach at 5, and an instruction to stop and
+//! report to the user at 7. Trying something not attempted before on the file
+//! resets the escalation.
+//!
+//! Bash commands are tracked separately: running the same command (after
+//! normalization) 3 times with no edit in between is a loop too, typically a
+//! failing test rerun in the hope it passes.
+
+use crate::base::{
+    Plugin, SessionState, ToolCall, ToolKind, load_state, migrate_state, save_state,
+};
+use attentive_telemetry::{TurnRecord, Versioned};
 use serde::{Deserialize, Serialize};
 use std::collections::VecDeque;
 
 const LOOP_THRESHOLD: usize = 3;
+/// Attempts at which the warning escalates to each level after the first
+const DEMAND_CHANGE_AT: usize = 5;
+const STOP_AT: usize = 7;
+/// Most attempted approaches listed in a warning
+const MAX_LISTED_ATTEMPTS: usize = 5;
 const HISTORY_SIZE: usize = 20;
-const WORK_TOOLS: &[&str] = &[
-    "Edit",
-    "Write",
-    "edit",
-    "write",
-    "MultiEdit",
-    "Bash",
-    "bash",
-];
+/// Pipes that only trim a command's output, ignored when comparing commands
+const OUTPUT_FILTERS: &[&str] = &["head", "tail", "grep", "less", "sort", "wc"];
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct LoopState {
@@ -22,6 +32,22 @@ struct LoopState {
     active_loop: Option<LoopInfo>,
     loops_detected: usize,
     loops_broken: usize,
+    /// Normalized Bash commands run since the last edit, oldest first
+    #[serde(default)]
+    recent_commands: VecDeque<String>,
+    #[serde(default)]
+    command_loop: Option<CommandLoop>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CommandLoop {
+    command: String,
+    count: usize,
+}
+
+impl Versioned for LoopState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "loopbreaker plugin state";
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -35,6 +61,145 @@ struct Attempt {
 struct LoopInfo {
     file: String,
     count: usize,
+    /// Distinct signatures attempted on the file, oldest first
+    #[serde(default)]
+    attempted: Vec<String>,
+}
+
+impl LoopInfo {
+    /// 1 = nudge, 2 = demand a different approach, 3 = stop and report
+    fn level(&self) -> usize {
+        match self.count {
+            n if n >= STOP_AT => 3,
+            n if n >= DEMAND_CHANGE_AT => 2,
+            _ => 1,
+        }
+    }
+}
+
+/// A command with what doesn't change its outcome stripped: `cd dir &&`
+/// prefixes, leading `VAR=value` assignments, `2>&1` and trailing pipes
+/// through output filters like `tail`
+fn normalize_command(command: &str) -> String {
+    let mut command = command.trim();
+    while let Some((first, rest)) = command.split_once("&&")
+        && first.trim_start().starts_with("cd ")
+    {
+        command = rest.trim_start();
+    }
+    let mut segments: Vec<&str> = command.split('|').collect();
+    while segments.len() > 1
+        && segments
+            .last()
+            .and_then(|s| s.split_whitespace().next())
+            .is_some_and(|program| OUTPUT_FILTERS.contains(&program))
+    {
+        segments.pop();
+    }
+    segments
+        .join("|")
+        .split_whitespace()
+        .skip_while(|word| word.contains('=') && !word.starts_with('-'))
+        .filter(|word| *word != "2>&1")
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Human-readable form of a `tool|path|identifiers|command` signature
+fn describe_signature(signature: &str) -> String {
+    let mut parts = signature.splitn(4, '|');
+    let tool = parts.next().unwrap_or("");
+    let _target = parts.next();
+    let identifiers = parts.next().unwrap_or("");
+    let command = parts.next().unwrap_or("");
+    if !command.is_empty() {
+        format!("{} `{}`", tool, command)
+    } else if !identifiers.is_empty() {
+        format!(
+            "{} replacing `{}`",
+            tool,
+            identifiers.replace(':', " ").replace(',', "`, `")
+        )
+    } else {
+        tool.to_string()
+    }
+}
+
+fn file_name_of(path: &str) -> &str {
+    std::path::Path::new(path)
+        .file_name()
+        .and_then(|n| n.to_str())
+        .unwrap_or("unknown")
+}
+
+fn format_alert(loop_info: &LoopInfo) -> String {
+    let file_name = file_name_of(&loop_info.file);
+    let skip = loop_info
+        .attempted
+        .len()
+        .saturating_sub(MAX_LISTED_ATTEMPTS);
+    let attempted: Vec<String> = loop_info.attempted[skip..]
+        .iter()
+        .map(|sig| format!("- {}", describe_signature(sig)))
+        .collect();
+
+    let mut alert = match loop_info.level() {
+        1 => format!(
+            "\n## LoopBreaker Alert\n\
+            **WARNING:** You've attempted to modify `{}` {} times with similar approach.\n\
+            \n\
+            **Reconsider your approach:**\n\
+            1. Re-read the file to verify your understanding\n\
+            2. Check if you're solving the RIGHT problem\n\
+            3. Consider a completely different approach\n",
+            file_name, loop_info.count
+        ),
+        2 => format!(
+            "\n## LoopBreaker Alert (escalated)\n\
+            **You've now attempted to modify `{}` {} times without progress.**\n\
+            \n\
+            Do NOT make another attempt like the ones below. Either work on a \
+            different file (the bug may not be where you think it is) or take a \
+            fundamentally different approach.\n",
+            file_name, loop_info.count
+        ),
+        _ => format!(
+            "\n## LoopBreaker Alert (stop)\n\
+            **STOP.** {} attempts on `{}` have not worked.\n\
+            \n\
+            Do not edit `{}` again. Summarize for the user what you tried, what \
+            you found and where you're stuck, then ask how to proceed.\n",
+            loop_info.count, file_name, file_name
+        ),
+    };
+    if !attempted.is_empty() {
+        alert.push_str(&format!("\nAlready tried:\n{}\n", attempted.join("\n")));
+    }
+    alert
+}
+
+fn format_command_alert(command_loop: &CommandLoop) -> String {
+    if command_loop.count >= STOP_AT {
+        return format!(
+            "\n## LoopBreaker Alert (stop)\n\
+            **STOP.** You've run `{}` {} times without changing anything.\n\
+            \n\
+            Do not run it again. Tell the user what is failing, quoting the \
+            error, and what you've tried, then ask how to proceed.\n",
+            command_loop.command, command_loop.count
+        );
+    }
+    format!(
+        "\n## LoopBreaker Alert (command)\n\
+        **WARNING:** You've run `{}` {} times without editing any file. \
+        Running it again will fail the same way.\n\
+        \n\
+        **Before running it again:**\n\
+        1. Read the error output carefully, starting from the first error\n\
+        2. Change the code or configuration it points at\n\
+        3. Or change approach: a narrower command, a different test, a debug print\n",
+        command_loop.command, command_loop.count
+    )
 }
 
 pub struct LoopBreakerPlugin {
@@ -55,18 +220,19 @@ impl LoopBreakerPlugin {
         // Normalize path
         let normalized_target = target.replace('\\', "/");
 
-        // Extract identifiers from old_string for signature
-        let old_sig = if let Some(old_string) = &tool_call.old_string {
-            // Simple identifier extraction
-            old_string
-                .split(|c: char| !c.is_alphanumeric() && c != '_')
-                .filter(|s| !s.is_empty())
-                .take(5)
-                .collect::<Vec<_>>()
-                .join(":")
-        } else {
-            String::new()
-        };
+        // Identifiers from the replaced text, per edit (MultiEdit makes several)
+        let old_sig = tool_call
+            .replaced()
+            .iter()
+            .map(|old| {
+                old.split(|c: char| !c.is_alphanumeric() && c != '_')
+                    .filter(|s| !s.is_empty())
+                    .take(5)
+                    .collect::<Vec<_>>()
+                    .join(":")
+            })
+            .collect::<Vec<_>>()
+            .join(",");
 
         // Extract command for bash tools
         let cmd_sig = if let Some(command) = &tool_call.command {
@@ -79,7 +245,8 @@ impl LoopBreakerPlugin {
     }
 
     fn is_work_tool(tool: &str) -> bool {
-        WORK_TOOLS.contains(&tool)
+        let kind = ToolKind::from_tool(tool);
+        kind.is_write() || kind == ToolKind::Bash
     }
 
     fn extract_work_attempts(tool_calls: &[ToolCall]) -> Vec<Attempt> {
@@ -129,79 +296,30 @@ impl LoopBreakerPlugin {
             let max_count = sig_counts.values().max().copied().unwrap_or(0);
 
             if max_count >= LOOP_THRESHOLD {
+                let mut attempted: Vec<String> = Vec::new();
+                for attempt in &attempts {
+                    if !attempted.contains(&attempt.signature) {
+                        attempted.push(attempt.signature.clone());
+                    }
+                }
                 return Some(LoopInfo {
                     file,
                     count: max_count,
+                    attempted,
                 });
             }
         }
 
         None
     }
-}
-
-impl Default for LoopBreakerPlugin {
-    fn default() -> Self {
-        Self::new()
-    }
-}
-
-impl Plugin for LoopBreakerPlugin {
-    fn name(&self) -> &str {
-        &self.name
-    }
-
-    fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
-        let state = LoopState::default();
-        save_state(self.name(), &state).ok();
-        Some("LoopBreaker: Active (repetitive failure detection)".to_string())
-    }
-
-    fn on_prompt_post(
-        &mut self,
-        _prompt: &str,
-        _context_output: &str,
-        _session_state: &SessionState,
-    ) -> String {
-        let state: LoopState = load_state(self.name()).unwrap_or_default();
-
-        if let Some(loop_info) = &state.active_loop {
-            let file_name = std::path::Path::new(&loop_info.file)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-
-            format!(
-                "\n## LoopBreaker Alert\n\
-                **WARNING:** You've attempted to modify `{}` {} times with similar approach.\n\
-                \n\
-                **STOP and reconsider your approach:**\n\
-                1. Re-read the file to verify your understanding\n\
-                2. Check if you're solving the RIGHT problem\n\
-                3. Consider a completely different approach\n\
-                4. If stuck, ask the user for clarification\n\
-                \n\
-                **Do NOT repeat the same fix.** Try something fundamentally different.\n",
-                file_name, loop_info.count
-            )
-        } else {
-            String::new()
-        }
-    }
-
-    fn on_stop(
-        &mut self,
-        tool_calls: &[ToolCall],
-        _session_state: &SessionState,
-    ) -> Option<String> {
-        let mut state: LoopState = load_state(self.name()).unwrap_or_default();
 
+    /// Record file attempts, continuing or breaking the active loop
+    fn track_files(state: &mut LoopState, tool_calls: &[ToolCall]) -> Option<String> {
         if tool_calls.is_empty() {
             // No tool calls - clear active loop
             if state.active_loop.is_some() {
                 state.active_loop = None;
                 state.loops_broken += 1;
-                save_state(self.name(), &state).ok();
             }
             return None;
         }
@@ -214,7 +332,6 @@ impl Plugin for LoopBreakerPlugin {
             if state.active_loop.is_some() {
                 state.active_loop = None;
                 state.loops_broken += 1;
-                save_state(self.name(), &state).ok();
             }
             return None;
         }
@@ -227,11 +344,30 @@ impl Plugin for LoopBreakerPlugin {
                 // Working on different file - break the loop
                 state.active_loop = None;
                 state.loops_broken += 1;
-                save_state(self.name(), &state).ok();
                 return None;
             }
         }
 
+        // Repeating only what was already tried keeps the loop going and escalates
+        // it; anything new on the file is a genuine change of approach
+        let continued = state.active_loop.take().and_then(|mut active| {
+            let on_file: Vec<&Attempt> = work_attempts
+                .iter()
+                .filter(|a| a.file == active.file)
+                .collect();
+            if on_file
+                .iter()
+                .all(|a| active.attempted.contains(&a.signature))
+            {
+                // `tool_calls` is this turn's, so earlier attempts aren't recounted
+                active.count += on_file.len();
+                Some(active)
+            } else {
+                state.loops_broken += 1;
+                None
+            }
+        });
+
         // Add new attempts to history
         for attempt in work_attempts {
             state.recent_attempts.push_back(attempt);
@@ -242,38 +378,165 @@ impl Plugin for LoopBreakerPlugin {
             state.recent_attempts.pop_front();
         }
 
+        if let Some(loop_info) = continued {
+            let message = format!(
+                "[LoopBreaker] Detected {} similar attempts on {} (escalation {}/3)",
+                loop_info.count,
+                file_name_of(&loop_info.file),
+                loop_info.level()
+            );
+            state.active_loop = Some(loop_info);
+            return Some(message);
+        }
+
         // Detect loops
         if let Some(loop_info) = Self::detect_loop(&state.recent_attempts) {
             // Loop detected
-            let is_new_loop = state
-                .active_loop
-                .as_ref()
-                .is_none_or(|l| l.file != loop_info.file);
+            state.loops_detected += 1;
+            let message = format!(
+                "[LoopBreaker] Detected {} similar attempts on {}",
+                loop_info.count,
+                file_name_of(&loop_info.file)
+            );
+            state.active_loop = Some(loop_info);
+            Some(message)
+        } else {
+            None
+        }
+    }
 
-            if is_new_loop {
-                state.loops_detected += 1;
+    /// Record Bash commands run since the last edit; a command repeated
+    /// `LOOP_THRESHOLD` times makes (or escalates) the command loop
+    fn track_commands(state: &mut LoopState, tool_calls: &[ToolCall]) -> Option<String> {
+        if tool_calls.is_empty() {
+            state.recent_commands.clear();
+            if state.command_loop.take().is_some() {
+                state.loops_broken += 1;
             }
+            return None;
+        }
+        for tc in tool_calls {
+            if tc.kind().is_write() {
+                state.recent_commands.clear();
+            } else if tc.kind() == ToolKind::Bash
+                && let Some(command) = tc.command.as_deref().map(normalize_command)
+                && !command.is_empty()
+            {
+                state.recent_commands.push_back(command);
+            }
+        }
+        while state.recent_commands.len() > HISTORY_SIZE {
+            state.recent_commands.pop_front();
+        }
 
-            let file_name = std::path::Path::new(&loop_info.file)
-                .file_name()
-                .and_then(|n| n.to_str())
-                .unwrap_or("unknown");
-
-            state.active_loop = Some(loop_info.clone());
-            save_state(self.name(), &state).ok();
-
-            Some(format!(
-                "[LoopBreaker] Detected {} similar attempts on {}",
-                loop_info.count, file_name
-            ))
-        } else {
-            // No loop - clear active loop if we had one
-            if state.active_loop.is_some() {
+        // Most repeated command; ties go to the one run last
+        let mut counts: Vec<(&String, usize)> = Vec::new();
+        for command in state.recent_commands.iter().rev() {
+            match counts.iter_mut().find(|(c, _)| *c == command) {
+                Some((_, count)) => *count += 1,
+                None => counts.push((command, 1)),
+            }
+        }
+        let Some((command, count)) = counts
+            .into_iter()
+            .rev()
+            .max_by_key(|(_, count)| *count)
+            .filter(|(_, count)| *count >= LOOP_THRESHOLD)
+        else {
+            if state.command_loop.take().is_some() {
                 state.loops_broken += 1;
-                state.active_loop = None;
             }
-            save_state(self.name(), &state).ok();
-            None
+            return None;
+        };
+        let command = command.clone();
+        if state
+            .command_loop
+            .as_ref()
+            .is_none_or(|active| active.command != command)
+        {
+            state.loops_detected += 1;
+        }
+        let message = format!(
+            "[LoopBreaker] Ran `{}` {} times without an edit",
+            command, count
+        );
+        state.command_loop = Some(CommandLoop { command, count });
+        Some(message)
+    }
+}
+
+impl Default for LoopBreakerPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plugin for LoopBreakerPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<LoopState>(self.name())
+    }
+
+    fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
+        let state = LoopState::default();
+        save_state(self.name(), &state).ok();
+        Some("LoopBreaker: Active (repetitive failure detection)".to_string())
+    }
+
+    fn on_prompt_post(
+        &mut self,
+        _prompt: &str,
+        _context_output: &str,
+        _session_state: &SessionState,
+    ) -> String {
+        let state: LoopState = load_state(self.name()).unwrap_or_default();
+        let mut alert = state
+            .active_loop
+            .as_ref()
+            .map(format_alert)
+            .unwrap_or_default();
+        if let Some(command_loop) = &state.command_loop {
+            alert.push_str(&format_command_alert(command_loop));
+        }
+        alert
+    }
+
+    fn on_stop(
+        &mut self,
+        tool_calls: &[ToolCall],
+        _session_state: &SessionState,
+    ) -> Option<String> {
+        let mut state: LoopState = load_state(self.name()).unwrap_or_default();
+        let file_message = Self::track_files(&mut state, tool_calls);
+        let command_message = Self::track_commands(&mut state, tool_calls);
+        save_state(self.name(), &state).ok();
+        match (file_message, command_message) {
+            (Some(file), Some(command)) => Some(format!("{}\n{}", file, command)),
+            (file, command) => file.or(command),
+        }
+    }
+
+    fn on_turn_record(&mut self, record: &mut TurnRecord) {
+        let state: LoopState = load_state(self.name()).unwrap_or_default();
+        let mut extension = serde_json::Map::new();
+        if let Some(active) = &state.active_loop {
+            extension.insert("loop_file".into(), active.file.clone().into());
+            extension.insert("loop_count".into(), active.count.into());
+            extension.insert("loop_level".into(), active.level().into());
+        }
+        if let Some(command_loop) = &state.command_loop {
+            extension.insert("loop_command".into(), command_loop.command.clone().into());
+            extension.insert("loop_command_count".into(), command_loop.count.into());
+        }
+        if !extension.is_empty() {
+            extension.insert("loops_detected".into(), state.loops_detected.into());
+            record.extensions.insert(
+                self.name().to_string(),
+                serde_json::Value::Object(extension),
+            );
         }
     }
 }
@@ -290,6 +553,7 @@ mod tests {
             content: None,
             old_string: Some("fn test_function".to_string()),
             command: None,
+            ..Default::default()
         };
 
         let sig = LoopBreakerPlugin::create_signature(&tool_call);
@@ -302,6 +566,98 @@ mod tests {
         assert!(sig.contains("function"), "Signature: {}", sig);
     }
 
+    #[test]
+    fn test_multiedit_signature_covers_every_edit() {
+        let tool_call = ToolCall::from_input(
+            "MultiEdit",
+            &serde_json::json!({
+                "file_path": "/a.rs",
+                "edits": [
+                    {"old_string": "fn parse(", "new_string": "fn parse2("},
+                    {"old_string": "let total", "new_string": "let sum"}
+                ]
+            }),
+        );
+        let sig = LoopBreakerPlugin::create_signature(&tool_call);
+        assert_eq!(sig, "MultiEdit|/a.rs|fn:parse,let:total|");
+        assert_eq!(
+            describe_signature(&sig),
+            "MultiEdit replacing `fn parse`, `let total`"
+        );
+    }
+
+    #[test]
+    fn test_describe_signature() {
+        assert_eq!(
+            describe_signature("Edit|/a.rs|fn:parse|"),
+            "Edit replacing `fn parse`"
+        );
+        assert_eq!(describe_signature("Bash|/a.rs||cargo"), "Bash `cargo`");
+        assert_eq!(describe_signature("Write|/a.rs||"), "Write");
+    }
+
+    #[test]
+    fn test_level_thresholds() {
+        let level = |count| {
+            LoopInfo {
+                file: "a.rs".to_string(),
+                count,
+                attempted: vec![],
+            }
+            .level()
+        };
+        assert_eq!(level(3), 1);
+        assert_eq!(level(5), 2);
+        assert_eq!(level(6), 2);
+        assert_eq!(level(9), 3);
+    }
+
+    #[test]
+    fn test_normalize_command() {
+        assert_eq!(normalize_command("cargo   test\n"), "cargo test");
+        assert_eq!(
+            normalize_command("cd /repo && RUST_LOG=debug cargo test -p core 2>&1 | tail -40"),
+            "cargo test -p core"
+        );
+        assert_eq!(
+            normalize_command("cargo test | tee out.log"),
+            "cargo test | tee out.log"
+        );
+    }
+
+    #[test]
+    fn test_repeated_command_without_edits_is_a_loop() {
+        let bash = |command: &str| ToolCall {
+            tool: "Bash".to_string(),
+            command: Some(command.to_string()),
+            ..Default::default()
+        };
+        let edit = ToolCall {
+            tool: "Edit".to_string(),
+            target: Some("/src/lib.rs".to_string()),
+            old_string: Some("a".to_string()),
+            ..Default::default()
+        };
+        let mut state = LoopState::default();
+        let calls = [bash("cargo test"), bash("cargo test 2>&1 | tail -20")];
+        assert!(LoopBreakerPlugin::track_commands(&mut state, &calls).is_none());
+
+        // An edit in between resets the count
+        let calls = [edit.clone(), bash("cargo test"), bash("cargo test")];
+        assert!(LoopBreakerPlugin::track_commands(&mut state, &calls).is_none());
+
+        let calls = [bash("git status"), bash("cd /src && cargo test")];
+        let message = LoopBreakerPlugin::track_commands(&mut state, &calls).unwrap();
+        assert!(message.contains("`cargo test` 3 times"));
+        let command_loop = state.command_loop.clone().unwrap();
+        assert!(format_command_alert(&command_loop).contains("Read the error output"));
+        assert_eq!(state.loops_detected, 1);
+
+        LoopBreakerPlugin::track_commands(&mut state, &[edit]);
+        assert!(state.command_loop.is_none());
+        assert_eq!(state.loops_broken, 1);
+    }
+
     #[test]
     fn test_is_work_tool() {
         assert!(LoopBreakerPlugin::is_work_tool("Edit"));
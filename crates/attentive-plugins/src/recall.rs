@@ -0,0 +1,251 @@
+//! Recall Plugin - Re-heats evicted (COLD) files on request
+
+use crate::base::{Plugin, SessionState, ToolCall, load_state, migrate_state, save_state};
+use attentive_telemetry::{PathCanonicalizer, Versioned};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Prompt directive that asks for a cold file to be recalled
+pub const RECALL_DIRECTIVE: &str = "@recall";
+
+/// One evicted file in the per-session cold index
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColdEntry {
+    pub path: String,
+    pub summary: String,
+    pub score: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct RecallState {
+    pending: Vec<String>,
+}
+
+impl Versioned for RecallState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "recall plugin state";
+}
+
+/// Load the cold index, returning an empty one when missing or unreadable
+pub fn load_cold_index(path: &Path) -> Vec<ColdEntry> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Write the cold index as compact JSON
+pub fn write_cold_index(path: &Path, entries: &[ColdEntry]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string(entries)?;
+    attentive_telemetry::atomic_write(path, json.as_bytes())?;
+    Ok(())
+}
+
+/// Five-line context pointer telling Claude where cold files went and how to recall them
+pub fn format_cold_pointer(entries: &[ColdEntry], index_path: &Path) -> String {
+    if entries.is_empty() {
+        return String::new();
+    }
+    let top: Vec<&str> = entries.iter().take(5).map(|e| e.path.as_str()).collect();
+    [
+        "## Cold Index".to_string(),
+        format!(
+            "{} evicted files are listed in {} (path, symbols, last score).",
+            entries.len(),
+            index_path.display()
+        ),
+        format!(
+            "To recall one, Read it or mention `{} <path>` in a prompt.",
+            RECALL_DIRECTIVE
+        ),
+        "Recalled files are re-heated to HOT on the next turn.".to_string(),
+        format!("Top cold: {}", top.join(", ")),
+    ]
+    .join("\n")
+}
+
+/// Parse `@recall <path>` directives out of a prompt
+pub fn parse_recall_directives(prompt: &str) -> Vec<String> {
+    let mut recalls = Vec::new();
+    let mut tokens = prompt.split_whitespace();
+    while let Some(token) = tokens.next() {
+        if token == RECALL_DIRECTIVE
+            && let Some(path) = tokens.next()
+        {
+            recalls.push(path.trim_matches(|c| c == '`' || c == ',').to_string());
+        }
+    }
+    recalls
+}
+
+pub struct RecallPlugin {
+    name: String,
+    /// The project's cold index; the working directory's when unset
+    cold_index: Option<PathBuf>,
+    /// Turns tool call targets into cold index keys; the working
+    /// directory's when unset
+    canonicalizer: Option<PathCanonicalizer>,
+}
+
+impl RecallPlugin {
+    pub fn new() -> Self {
+        Self {
+            name: "recall".to_string(),
+            cold_index: None,
+            canonicalizer: None,
+        }
+    }
+
+    /// Match tool call targets against the cold index in `canonicalizer`'s
+    /// repo-relative form
+    pub fn with_canonicalizer(mut self, canonicalizer: PathCanonicalizer) -> Self {
+        self.canonicalizer = Some(canonicalizer);
+        self
+    }
+
+    /// Read the cold index at `path` (see `Paths::cold_index_path`)
+    pub fn with_cold_index(mut self, path: PathBuf) -> Self {
+        self.cold_index = Some(path);
+        self
+    }
+
+    fn cold_index_path(&self) -> Option<PathBuf> {
+        match &self.cold_index {
+            Some(path) => Some(path.clone()),
+            None => attentive_telemetry::Paths::new()
+                .and_then(|p| p.cold_index_path())
+                .ok(),
+        }
+    }
+
+    fn queue(&self, files: impl IntoIterator<Item = String>) {
+        let mut state: RecallState = load_state(self.name()).unwrap_or_default();
+        for file in files {
+            if !state.pending.contains(&file) {
+                state.pending.push(file);
+            }
+        }
+        save_state(self.name(), &state).ok();
+    }
+
+    /// Drain files queued for re-heating
+    pub fn take_pending(&self) -> Vec<String> {
+        let state: RecallState = load_state(self.name()).unwrap_or_default();
+        if !state.pending.is_empty() {
+            save_state(self.name(), &RecallState::default()).ok();
+        }
+        state.pending
+    }
+}
+
+impl Default for RecallPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plugin for RecallPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<RecallState>(self.name())
+    }
+
+    fn description(&self) -> &str {
+        "Re-heats evicted files requested via the cold index"
+    }
+
+    fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
+        save_state(self.name(), &RecallState::default()).ok();
+        if let Some(path) = self.cold_index_path() {
+            std::fs::remove_file(path).ok();
+        }
+        None
+    }
+
+    fn on_prompt_pre(&mut self, prompt: String, _session_state: &SessionState) -> (String, bool) {
+        let recalls = parse_recall_directives(&prompt);
+        if !recalls.is_empty() {
+            self.queue(recalls);
+        }
+        (prompt, true)
+    }
+
+    fn on_stop(
+        &mut self,
+        tool_calls: &[ToolCall],
+        _session_state: &SessionState,
+    ) -> Option<String> {
+        let index_path = self.cold_index_path()?;
+        let cold = load_cold_index(&index_path);
+        if cold.is_empty() {
+            return None;
+        }
+
+        // Tool calls name files by absolute path, the cold index by canonical key
+        let canonicalizer = match &self.canonicalizer {
+            Some(canonicalizer) => canonicalizer.clone(),
+            None => PathCanonicalizer::for_current_dir().ok()?,
+        };
+        let touched: Vec<String> = tool_calls
+            .iter()
+            .filter_map(|tc| tc.target.as_deref())
+            .filter_map(|target| {
+                let key = canonicalizer.key(target);
+                cold.iter()
+                    .find(|e| canonicalizer.key(&e.path) == key)
+                    .map(|e| e.path.clone())
+            })
+            .collect();
+        if touched.is_empty() {
+            return None;
+        }
+        self.queue(touched);
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recall_directives() {
+        let recalls =
+            parse_recall_directives("please @recall src/router.rs and @recall `src/lib.rs`");
+        assert_eq!(recalls, vec!["src/router.rs", "src/lib.rs"]);
+        assert!(parse_recall_directives("no directives here @recall").is_empty());
+    }
+
+    #[test]
+    fn test_format_cold_pointer_is_five_lines() {
+        let entries = vec![ColdEntry {
+            path: "src/old.rs".to_string(),
+            summary: "fn old".to_string(),
+            score: 0.1,
+        }];
+        let pointer = format_cold_pointer(&entries, Path::new("/tmp/cold_index.json"));
+        assert_eq!(pointer.lines().count(), 5);
+        assert!(pointer.contains("src/old.rs"));
+        assert!(format_cold_pointer(&[], Path::new("/tmp/x")).is_empty());
+    }
+
+    #[test]
+    fn test_cold_index_roundtrip() {
+        let dir = std::env::temp_dir().join(format!("attentive-cold-{}", std::process::id()));
+        let path = dir.join("cold_index.json");
+        let entries = vec![ColdEntry {
+            path: "a.rs".to_string(),
+            summary: "struct A".to_string(),
+            score: 0.2,
+        }];
+        write_cold_index(&path, &entries).unwrap();
+        assert_eq!(load_cold_index(&path), entries);
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -1,10 +1,40 @@
 //! Plugin registry for loading and managing plugins
 
-use crate::base::{Plugin, SessionState, ToolCall};
+use crate::base::{MAX_SCORE_DELTA, Plugin, SessionState, ToolCall, Truncation};
+use attentive_telemetry::{PluginBudgetUsage, TurnRecord, estimate_tokens};
+use std::collections::{BTreeMap, HashMap};
 
 /// Registry for managing multiple plugins
 pub struct PluginRegistry {
     plugins: Vec<Box<dyn Plugin>>,
+    /// Plugin name -> enabled, taking precedence over plugins/config.json
+    toggles: BTreeMap<String, bool>,
+    /// Plugin name -> most estimated tokens its on_prompt_post may add
+    budgets: BTreeMap<String, usize>,
+    /// Budget usage from the last on_prompt_post round
+    budget_usage: BTreeMap<String, PluginBudgetUsage>,
+}
+
+/// Cut `text` down to `budget` estimated tokens as `truncation` says,
+/// replacing the cut lines with a note; empty when nothing fits
+fn fit_budget(text: &str, budget: usize, truncation: Truncation) -> String {
+    if estimate_tokens(text) <= budget {
+        return text.to_string();
+    }
+    let lines: Vec<&str> = text.lines().collect();
+    for keep in (1..lines.len()).rev() {
+        let cut = lines.len() - keep;
+        let note = format!("[... {} line(s) cut to fit the plugin's budget]", cut);
+        let candidate = match truncation {
+            Truncation::KeepStart => format!("{}\n{}", lines[..keep].join("\n"), note),
+            Truncation::KeepEnd => format!("{}\n{}", note, lines[cut..].join("\n")),
+            Truncation::Drop => return String::new(),
+        };
+        if estimate_tokens(&candidate) <= budget {
+            return candidate;
+        }
+    }
+    String::new()
 }
 
 impl PluginRegistry {
@@ -12,12 +42,41 @@ impl PluginRegistry {
     pub fn new() -> Self {
         Self {
             plugins: Vec::new(),
+            toggles: BTreeMap::new(),
+            budgets: BTreeMap::new(),
+            budget_usage: BTreeMap::new(),
+        }
+    }
+
+    /// Enable or disable plugins by name regardless of plugins/config.json,
+    /// e.g. from a config profile. Applies to plugins registered afterwards.
+    pub fn set_toggles(&mut self, toggles: BTreeMap<String, bool>) {
+        self.toggles = toggles;
+    }
+
+    /// Cap each named plugin's on_prompt_post output at a number of
+    /// estimated tokens, shortened by the plugin's `truncation`
+    pub fn set_budgets(&mut self, budgets: BTreeMap<String, usize>) {
+        self.budgets = budgets;
+    }
+
+    /// How each budgeted plugin that added context in the last
+    /// on_prompt_post round fared against its budget
+    pub fn budget_usage(&self) -> &BTreeMap<String, PluginBudgetUsage> {
+        &self.budget_usage
+    }
+
+    /// Whether `plugin` would be registered
+    pub fn allows(&self, plugin: &dyn Plugin) -> bool {
+        match self.toggles.get(plugin.name()) {
+            Some(enabled) => *enabled,
+            None => plugin.is_enabled(),
         }
     }
 
     /// Register a plugin
     pub fn register(&mut self, plugin: Box<dyn Plugin>) {
-        if plugin.is_enabled() {
+        if self.allows(plugin.as_ref()) {
             self.plugins.push(plugin);
         }
     }
@@ -30,6 +89,13 @@ impl PluginRegistry {
             .collect()
     }
 
+    /// Let every plugin publish values into the shared session state
+    pub fn share_session_state(&self, session_state: &mut SessionState) {
+        for plugin in &self.plugins {
+            plugin.share_session_state(session_state);
+        }
+    }
+
     /// Call on_prompt_pre for all plugins
     pub fn on_prompt_pre(
         &mut self,
@@ -46,6 +112,44 @@ impl PluginRegistry {
         (prompt, true)
     }
 
+    /// Call on_prompt_prefix for all plugins, keeping each non-empty block
+    /// with the name of the plugin that made it
+    pub fn on_prompt_prefix_by_plugin(
+        &mut self,
+        session_state: &SessionState,
+    ) -> Vec<(String, String)> {
+        self.plugins
+            .iter_mut()
+            .filter_map(|plugin| {
+                let prefix = plugin.on_prompt_prefix(session_state);
+                (!prefix.is_empty()).then(|| (plugin.name().to_string(), prefix))
+            })
+            .collect()
+    }
+
+    /// Call on_scores for all plugins, then hold each file within
+    /// `MAX_SCORE_DELTA` of its score before them (files they add start from
+    /// 0, files they remove are restored) and within [0, 1]
+    pub fn on_scores(&mut self, scores: &mut HashMap<String, f64>, prompt: &str) {
+        let before = scores.clone();
+        for plugin in &mut self.plugins {
+            plugin.on_scores(scores, prompt);
+        }
+        scores.extend(
+            before
+                .iter()
+                .filter(|(path, _)| !scores.contains_key(*path))
+                .map(|(path, score)| (path.clone(), *score))
+                .collect::<Vec<_>>(),
+        );
+        for (path, score) in scores.iter_mut() {
+            let original = before.get(path).copied().unwrap_or(0.0);
+            *score = score
+                .clamp(original - MAX_SCORE_DELTA, original + MAX_SCORE_DELTA)
+                .clamp(0.0, 1.0);
+        }
+    }
+
     /// Call on_prompt_post for all plugins
     pub fn on_prompt_post(
         &mut self,
@@ -53,17 +157,50 @@ impl PluginRegistry {
         context_output: &str,
         session_state: &SessionState,
     ) -> String {
+        self.on_prompt_post_by_plugin(prompt, context_output, session_state)
+            .into_iter()
+            .map(|(_, context)| context)
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    /// Call on_prompt_post for all plugins, keeping each non-empty addition
+    /// with the name of the plugin that made it. Additions over the plugin's
+    /// budget are shortened first; see `budget_usage`.
+    pub fn on_prompt_post_by_plugin(
+        &mut self,
+        prompt: &str,
+        context_output: &str,
+        session_state: &SessionState,
+    ) -> Vec<(String, String)> {
+        self.budget_usage.clear();
         let mut additional_context = Vec::new();
         for plugin in &mut self.plugins {
-            let context = plugin.on_prompt_post(prompt, context_output, session_state);
+            let mut context = plugin.on_prompt_post(prompt, context_output, session_state);
+            if context.is_empty() {
+                continue;
+            }
+            if let Some(&budget) = self.budgets.get(plugin.name()) {
+                let requested = estimate_tokens(&context);
+                context = fit_budget(&context, budget, plugin.truncation());
+                self.budget_usage.insert(
+                    plugin.name().to_string(),
+                    PluginBudgetUsage {
+                        budget,
+                        requested,
+                        used: estimate_tokens(&context),
+                    },
+                );
+            }
             if !context.is_empty() {
-                additional_context.push(context);
+                additional_context.push((plugin.name().to_string(), context));
             }
         }
-        additional_context.join("\n")
+        additional_context
     }
 
-    /// Call on_stop for all plugins
+    /// Call on_stop for all plugins with the tool calls of the turn that
+    /// just ended
     pub fn on_stop(
         &mut self,
         tool_calls: &[ToolCall],
@@ -75,6 +212,29 @@ impl PluginRegistry {
             .collect()
     }
 
+    /// Call on_pre_tool_use for all plugins
+    pub fn on_pre_tool_use(&mut self, tool_call: &ToolCall) -> Vec<String> {
+        self.plugins
+            .iter_mut()
+            .filter_map(|p| p.on_pre_tool_use(tool_call))
+            .collect()
+    }
+
+    /// Let every plugin attach its signals to this turn's telemetry record
+    pub fn on_turn_record(&mut self, record: &mut TurnRecord) {
+        for plugin in &mut self.plugins {
+            plugin.on_turn_record(record);
+        }
+    }
+
+    /// Upgrade every plugin's state file, by plugin name
+    pub fn migrate_states(&self) -> Vec<(String, anyhow::Result<Option<u32>>)> {
+        self.plugins
+            .iter()
+            .map(|p| (p.name().to_string(), p.migrate_state()))
+            .collect()
+    }
+
     /// Get number of registered plugins
     pub fn len(&self) -> usize {
         self.plugins.len()
@@ -95,7 +255,6 @@ impl Default for PluginRegistry {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use std::collections::HashMap;
 
     struct TestPlugin {
         name: String,
@@ -141,6 +300,12 @@ mod tests {
         ) -> Option<String> {
             self.stop_msg.clone()
         }
+
+        fn on_turn_record(&mut self, record: &mut TurnRecord) {
+            record
+                .extensions
+                .insert(self.name.clone(), serde_json::json!({ "seen": true }));
+        }
     }
 
     #[test]
@@ -174,6 +339,104 @@ mod tests {
         assert_eq!(registry.len(), 0); // Disabled plugin not added
     }
 
+    #[test]
+    fn test_registry_toggles_override_enabled() {
+        let plugin = |name: &str, enabled| {
+            Box::new(TestPlugin {
+                name: name.to_string(),
+                enabled,
+                session_msg: None,
+                stop_msg: None,
+            })
+        };
+        let mut registry = PluginRegistry::new();
+        registry.set_toggles([("on".to_string(), true), ("off".to_string(), false)].into());
+        registry.register(plugin("on", false));
+        registry.register(plugin("off", true));
+        registry.register(plugin("untoggled", true));
+        assert_eq!(registry.len(), 2);
+        assert!(!registry.allows(plugin("off", true).as_ref()));
+    }
+
+    /// Adds `delta` to every score, drops `drop` and adds `add` at 1.0
+    struct ScoringPlugin {
+        delta: f64,
+        drop: Option<&'static str>,
+        add: Option<&'static str>,
+    }
+
+    impl Plugin for ScoringPlugin {
+        fn name(&self) -> &str {
+            "scoring"
+        }
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn on_scores(&mut self, scores: &mut HashMap<String, f64>, prompt: &str) {
+            assert_eq!(prompt, "fix auth");
+            for score in scores.values_mut() {
+                *score += self.delta;
+            }
+            if let Some(path) = self.drop {
+                scores.remove(path);
+            }
+            if let Some(path) = self.add {
+                scores.insert(path.to_string(), 1.0);
+            }
+        }
+    }
+
+    #[test]
+    fn test_registry_on_scores_bounds_changes() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(ScoringPlugin {
+            delta: 0.1,
+            drop: Some("c.rs"),
+            add: Some("new.rs"),
+        }));
+        registry.register(Box::new(ScoringPlugin {
+            delta: 0.5,
+            drop: None,
+            add: None,
+        }));
+
+        let mut scores: HashMap<String, f64> = [
+            ("a.rs".to_string(), 0.2),
+            ("b.rs".to_string(), 0.9),
+            ("c.rs".to_string(), 0.4),
+        ]
+        .into();
+        registry.on_scores(&mut scores, "fix auth");
+        assert!((scores["a.rs"] - (0.2 + MAX_SCORE_DELTA)).abs() < 1e-9);
+        assert_eq!(scores["b.rs"], 1.0);
+        assert_eq!(scores["c.rs"], 0.4);
+        assert_eq!(scores["new.rs"], MAX_SCORE_DELTA);
+        assert_eq!(scores.len(), 4);
+    }
+
+    #[test]
+    fn test_registry_on_turn_record() {
+        let mut registry = PluginRegistry::new();
+        registry.register(Box::new(TestPlugin {
+            name: "test1".to_string(),
+            enabled: true,
+            session_msg: None,
+            stop_msg: None,
+        }));
+
+        let mut record = TurnRecord {
+            turn_id: "t".to_string(),
+            session_id: "s".to_string(),
+            project: "/p".to_string(),
+            timestamp: chrono::Utc::now(),
+            ..Default::default()
+        };
+        registry.on_turn_record(&mut record);
+        assert_eq!(record.extensions["test1"]["seen"], true);
+    }
+
     #[test]
     fn test_registry_on_session_start() {
         let mut registry = PluginRegistry::new();
@@ -248,6 +511,97 @@ mod tests {
 
         assert!(context.contains("Context from plugin1"));
         assert!(context.contains("Context from plugin2"));
+
+        let by_plugin = registry.on_prompt_post_by_plugin("prompt", "context", &session_state);
+        assert_eq!(by_plugin[1].0, "plugin2");
+        assert_eq!(by_plugin[1].1, "Context from plugin2");
+        assert!(
+            registry
+                .on_prompt_prefix_by_plugin(&session_state)
+                .is_empty()
+        );
+    }
+
+    /// Emits `lines` numbered list lines and shortens them as `truncation` says
+    struct ListPlugin {
+        name: &'static str,
+        lines: usize,
+        truncation: Truncation,
+    }
+
+    impl Plugin for ListPlugin {
+        fn name(&self) -> &str {
+            self.name
+        }
+
+        fn is_enabled(&self) -> bool {
+            true
+        }
+
+        fn on_prompt_post(
+            &mut self,
+            _prompt: &str,
+            _context_output: &str,
+            _session_state: &SessionState,
+        ) -> String {
+            (1..=self.lines)
+                .map(|i| format!("- verified file number {}", i))
+                .collect::<Vec<_>>()
+                .join("\n")
+        }
+
+        fn truncation(&self) -> Truncation {
+            self.truncation
+        }
+    }
+
+    #[test]
+    fn test_registry_enforces_plugin_budgets() {
+        let plugin = |name, truncation| {
+            Box::new(ListPlugin {
+                name,
+                lines: 30,
+                truncation,
+            })
+        };
+        let mut registry = PluginRegistry::new();
+        registry.set_budgets(
+            [
+                ("start".to_string(), 40),
+                ("end".to_string(), 40),
+                ("drop".to_string(), 40),
+                ("roomy".to_string(), 10_000),
+            ]
+            .into(),
+        );
+        registry.register(plugin("start", Truncation::KeepStart));
+        registry.register(plugin("end", Truncation::KeepEnd));
+        registry.register(plugin("drop", Truncation::Drop));
+        registry.register(plugin("roomy", Truncation::KeepStart));
+        registry.register(plugin("unbudgeted", Truncation::Drop));
+
+        let by_plugin: HashMap<String, String> = registry
+            .on_prompt_post_by_plugin("prompt", "context", &HashMap::new())
+            .into_iter()
+            .collect();
+        let start = &by_plugin["start"];
+        assert!(start.starts_with("- verified file number 1\n"));
+        assert!(start.ends_with("cut to fit the plugin's budget]"));
+        assert!(estimate_tokens(start) <= 40);
+        let end = &by_plugin["end"];
+        assert!(end.starts_with("[... "));
+        assert!(end.ends_with("- verified file number 30"));
+        assert!(!by_plugin.contains_key("drop"));
+        assert_eq!(by_plugin["roomy"].lines().count(), 30);
+        assert_eq!(by_plugin["unbudgeted"].lines().count(), 30);
+
+        let usage = registry.budget_usage();
+        assert_eq!(usage.len(), 4);
+        assert!(usage["start"].truncated());
+        assert_eq!(usage["start"].used, estimate_tokens(start));
+        assert_eq!(usage["drop"].used, 0);
+        assert!(!usage["roomy"].truncated());
+        assert_eq!(usage["roomy"].budget, 10_000);
     }
 
     #[test]
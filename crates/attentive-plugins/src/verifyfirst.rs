@@ -1,11 +1,12 @@
 //! VerifyFirst Plugin - Ensures files are read before being edited
 
-use crate::base::{Plugin, SessionState, ToolCall, load_state, save_state};
+use crate::base::{
+    Plugin, SessionState, ToolCall, ToolKind, load_state, migrate_state, save_state,
+};
+use attentive_telemetry::{TurnRecord, Versioned};
 use serde::{Deserialize, Serialize};
 use std::collections::HashSet;
 
-const READ_TOOLS: &[&str] = &["Read", "read"];
-const WRITE_TOOLS: &[&str] = &["Edit", "Write", "edit", "write", "MultiEdit"];
 const MAX_DISPLAY_FILES: usize = 30;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -13,6 +14,14 @@ struct VerifyState {
     files_read: HashSet<String>,
     files_written: HashSet<String>,
     violations: Vec<Violation>,
+    /// Violations from the most recent on_stop
+    #[serde(default)]
+    last_turn_violations: usize,
+}
+
+impl Versioned for VerifyState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "verifyfirst plugin state";
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,11 +54,11 @@ impl VerifyFirstPlugin {
     }
 
     fn is_read_tool(tool: &str) -> bool {
-        READ_TOOLS.contains(&tool)
+        ToolKind::from_tool(tool) == ToolKind::Read
     }
 
     fn is_write_tool(tool: &str) -> bool {
-        WRITE_TOOLS.contains(&tool)
+        ToolKind::from_tool(tool).is_write()
     }
 }
 
@@ -64,12 +73,25 @@ impl Plugin for VerifyFirstPlugin {
         &self.name
     }
 
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<VerifyState>(self.name())
+    }
+
     fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
         let state = VerifyState::default();
         save_state(self.name(), &state).ok()?;
         Some("VerifyFirst: Active (read-before-write policy)".to_string())
     }
 
+    fn on_prompt_prefix(&mut self, _session_state: &SessionState) -> String {
+        [
+            "## VerifyFirst Policy",
+            "You MUST read a file before editing it. This ensures you understand the full context.",
+            "For any file NOT listed as verified this session, you MUST use Read before Edit or Write.",
+        ]
+        .join("\n")
+    }
+
     fn on_prompt_post(
         &mut self,
         _prompt: &str,
@@ -78,46 +100,25 @@ impl Plugin for VerifyFirstPlugin {
     ) -> String {
         let state: VerifyState = load_state(self.name()).unwrap_or_default();
 
-        let mut policy_lines = vec![
-            String::new(),
-            "## VerifyFirst Policy".to_string(),
-            "You MUST read a file before editing it. This ensures you understand the full context."
-                .to_string(),
-            String::new(),
-        ];
-
-        if !state.files_read.is_empty() {
-            let files: Vec<_> = state.files_read.iter().take(MAX_DISPLAY_FILES).collect();
-
-            policy_lines.push("**Files verified (safe to edit):**".to_string());
-            for file in &files {
-                let name = std::path::Path::new(file)
-                    .file_name()
-                    .and_then(|n| n.to_str())
-                    .unwrap_or(file);
-                policy_lines.push(format!("- `{}`", name));
-            }
-
-            if state.files_read.len() > MAX_DISPLAY_FILES {
-                policy_lines.push(format!(
-                    "- ... and {} more",
-                    state.files_read.len() - MAX_DISPLAY_FILES
-                ));
-            }
-
-            policy_lines.push(String::new());
-            policy_lines.push(
-                "**IMPORTANT:** For any file NOT in this list, you MUST use Read first."
-                    .to_string(),
-            );
-        } else {
-            policy_lines.push("**No files have been read yet this session.**".to_string());
-            policy_lines
-                .push("You MUST Read any file before attempting to Edit or Write it.".to_string());
+        if state.files_read.is_empty() {
+            return "**VerifyFirst: no files have been read yet this session.**".to_string();
         }
 
-        policy_lines.push(String::new());
-        policy_lines.join("\n")
+        let mut lines = vec!["**VerifyFirst: files verified (safe to edit):**".to_string()];
+        for file in state.files_read.iter().take(MAX_DISPLAY_FILES) {
+            let name = std::path::Path::new(file)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .unwrap_or(file);
+            lines.push(format!("- `{}`", name));
+        }
+        if state.files_read.len() > MAX_DISPLAY_FILES {
+            lines.push(format!(
+                "- ... and {} more",
+                state.files_read.len() - MAX_DISPLAY_FILES
+            ));
+        }
+        lines.join("\n")
     }
 
     fn on_stop(
@@ -156,6 +157,7 @@ impl Plugin for VerifyFirstPlugin {
             }
         }
 
+        state.last_turn_violations = new_violations.len();
         save_state(self.name(), &state).ok();
 
         if !new_violations.is_empty() {
@@ -168,6 +170,19 @@ impl Plugin for VerifyFirstPlugin {
             None
         }
     }
+
+    fn on_turn_record(&mut self, record: &mut TurnRecord) {
+        let state: VerifyState = load_state(self.name()).unwrap_or_default();
+        if state.last_turn_violations > 0 {
+            record.extensions.insert(
+                self.name().to_string(),
+                serde_json::json!({
+                    "violations": state.last_turn_violations,
+                    "session_violations": state.violations.len(),
+                }),
+            );
+        }
+    }
 }
 
 #[cfg(test)]
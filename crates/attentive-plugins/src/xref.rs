@@ -0,0 +1,262 @@
+//! Xref Plugin - Pulls in caller files when a file's symbols are modified
+
+use crate::base::{Plugin, SessionState, ToolCall, load_state, migrate_state, save_state};
+use attentive_repo::XrefIndex;
+use attentive_telemetry::Versioned;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+/// Most caller files queued per turn
+const MAX_CALLERS_PER_TURN: usize = 8;
+
+#[derive(Debug, Serialize, Deserialize, Default)]
+struct XrefState {
+    pending: Vec<String>,
+}
+
+impl Versioned for XrefState {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "xref plugin state";
+}
+
+/// Symbols from `defined` that a tool call modifies: for edits, the ones named
+/// in any replaced text; for whole-file writes, all of them
+pub fn modified_symbols(tool_call: &ToolCall, defined: &[String]) -> Vec<String> {
+    let replaced = tool_call.replaced();
+    if replaced.is_empty() {
+        return match tool_call.content {
+            Some(_) => defined.to_vec(),
+            None => Vec::new(),
+        };
+    }
+    defined
+        .iter()
+        .filter(|name| replaced.iter().any(|old| old.contains(name.as_str())))
+        .cloned()
+        .collect()
+}
+
+/// Caller files for symbols modified by `tool_calls`, excluding the edited files
+pub fn find_callers(index: &XrefIndex, root: &Path, tool_calls: &[ToolCall]) -> Vec<String> {
+    let edited: Vec<(&ToolCall, String)> = tool_calls
+        .iter()
+        .filter(|tc| tc.kind().is_write())
+        .filter_map(|tc| {
+            let target = tc.target.as_deref()?;
+            let rel = Path::new(target)
+                .strip_prefix(root)
+                .map(|p| p.to_string_lossy().to_string())
+                .unwrap_or_else(|_| target.to_string());
+            Some((tc, rel))
+        })
+        .collect();
+
+    let mut callers = Vec::new();
+    for (tc, rel) in &edited {
+        for symbol in modified_symbols(tc, index.definitions_in(rel)) {
+            for caller in index.callers(&symbol) {
+                if !edited.iter().any(|(_, e)| *e == caller) && !callers.contains(&caller) {
+                    callers.push(caller);
+                }
+            }
+        }
+    }
+    callers.truncate(MAX_CALLERS_PER_TURN);
+    callers
+}
+
+pub struct XrefPlugin {
+    name: String,
+    /// Repo root edits are resolved against; the current directory's repo
+    /// root when unset
+    root: Option<PathBuf>,
+    /// The router's `exclude_files`, which plugins don't otherwise see
+    excludes: Vec<String>,
+    /// Where the scan is cached; the working directory's project when unset
+    cache: Option<PathBuf>,
+}
+
+impl XrefPlugin {
+    pub fn new() -> Self {
+        Self {
+            name: "xref".to_string(),
+            root: None,
+            excludes: Vec::new(),
+            cache: None,
+        }
+    }
+
+    /// Index the repo at `root`, skipping files matched by `excludes` on top
+    /// of .gitignore and .attentiveignore, and caching the scan at `cache`
+    /// (see `Paths::xref_index_path`)
+    pub fn with_repo(mut self, root: PathBuf, excludes: Vec<String>, cache: PathBuf) -> Self {
+        self.root = Some(root);
+        self.excludes = excludes;
+        self.cache = Some(cache);
+        self
+    }
+
+    /// Drain caller files queued for inclusion
+    pub fn take_pending(&self) -> Vec<String> {
+        let state: XrefState = load_state(self.name()).unwrap_or_default();
+        if !state.pending.is_empty() {
+            save_state(self.name(), &XrefState::default()).ok();
+        }
+        state.pending
+    }
+}
+
+impl Default for XrefPlugin {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Plugin for XrefPlugin {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn migrate_state(&self) -> anyhow::Result<Option<u32>> {
+        migrate_state::<XrefState>(self.name())
+    }
+
+    fn description(&self) -> &str {
+        "Includes caller files when symbols they use are modified"
+    }
+
+    fn on_session_start(&mut self, _session_state: &SessionState) -> Option<String> {
+        save_state(self.name(), &XrefState::default()).ok();
+        None
+    }
+
+    fn on_stop(
+        &mut self,
+        tool_calls: &[ToolCall],
+        _session_state: &SessionState,
+    ) -> Option<String> {
+        // Only this turn's calls arrive, so callers of older edits aren't
+        // queued again
+        if !tool_calls.iter().any(|tc| tc.kind().is_write()) {
+            return None;
+        }
+
+        let root = match &self.root {
+            Some(root) => root.clone(),
+            None => attentive_telemetry::PathCanonicalizer::for_current_dir()
+                .ok()?
+                .root()
+                .to_path_buf(),
+        };
+        let index = match &self.cache {
+            Some(cache) => XrefIndex::load_cached_at(&root, &self.excludes, cache),
+            None => XrefIndex::load_cached(&root, &self.excludes),
+        }
+        .ok()?;
+        let callers = find_callers(&index, &root, tool_calls);
+        if callers.is_empty() {
+            return None;
+        }
+
+        let mut state: XrefState = load_state(self.name()).unwrap_or_default();
+        for caller in callers {
+            if !state.pending.contains(&caller) {
+                state.pending.push(caller);
+            }
+        }
+        save_state(self.name(), &state).ok();
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn edit(target: &str, old: &str) -> ToolCall {
+        ToolCall {
+            tool: "Edit".to_string(),
+            target: Some(target.to_string()),
+            content: None,
+            old_string: Some(old.to_string()),
+            command: None,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_modified_symbols() {
+        let defined = vec!["route".to_string(), "decay".to_string()];
+        assert_eq!(
+            modified_symbols(&edit("a.rs", "pub fn route() {"), &defined),
+            vec!["route"]
+        );
+
+        let write = ToolCall {
+            tool: "Write".to_string(),
+            target: Some("a.rs".to_string()),
+            content: Some(String::new()),
+            old_string: None,
+            command: None,
+            ..Default::default()
+        };
+        assert_eq!(modified_symbols(&write, &defined), defined);
+
+        let multi = ToolCall::from_input(
+            "MultiEdit",
+            &serde_json::json!({"file_path": "a.rs", "edits": [
+                {"old_string": "fn helper()", "new_string": ""},
+                {"old_string": "decay(x)", "new_string": "decay(y)"}
+            ]}),
+        );
+        assert_eq!(modified_symbols(&multi, &defined), vec!["decay"]);
+    }
+
+    #[test]
+    fn test_find_callers() {
+        let mut index = XrefIndex::new();
+        index.add_file("src/router.rs", "pub fn route() {}\npub fn decay() {}\n");
+        index.add_file("src/hooks.rs", "fn run() { route(); }\n");
+        index.add_file("src/bench.rs", "fn bench() { decay(); }\n");
+
+        let root = Path::new("/repo");
+        let callers = find_callers(&index, root, &[edit("/repo/src/router.rs", "fn route()")]);
+        assert_eq!(callers, vec!["src/hooks.rs"]);
+
+        let read = ToolCall {
+            tool: "Read".to_string(),
+            ..edit("/repo/src/router.rs", "fn route()")
+        };
+        assert!(find_callers(&index, root, &[read]).is_empty());
+    }
+
+    #[test]
+    fn test_on_stop_queues_callers_of_this_turns_edits() {
+        let env = attentive_telemetry::TestEnv::new();
+        let repo = env.paths().home_claude.join("repo");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        std::fs::write(repo.join("src/router.rs"), "pub fn route() {}\n").unwrap();
+        std::fs::write(repo.join("src/hooks.rs"), "fn run() { route(); }\n").unwrap();
+        let mut plugin = XrefPlugin::new().with_repo(
+            repo.clone(),
+            Vec::new(),
+            env.paths().home_claude.join("xref.json"),
+        );
+        let router = repo.join("src/router.rs").to_string_lossy().into_owned();
+
+        // Tool names are matched by kind, whatever their case
+        let lowercase = ToolCall {
+            tool: "edit".to_string(),
+            ..edit(&router, "pub fn route() {")
+        };
+        plugin.on_stop(&[lowercase], &SessionState::new());
+        assert_eq!(plugin.take_pending(), ["src/hooks.rs"]);
+
+        let read = ToolCall {
+            tool: "Read".to_string(),
+            ..edit(&router, "pub fn route() {")
+        };
+        plugin.on_stop(&[read], &SessionState::new());
+        assert!(plugin.take_pending().is_empty());
+    }
+}
@@ -0,0 +1,94 @@
+use attentive_plugins::{GuardrailPlugin, Plugin, SessionState, ToolCall};
+use attentive_telemetry::{TestEnv, TurnRecord};
+
+fn bash(command: &str) -> ToolCall {
+    ToolCall {
+        tool: "Bash".to_string(),
+        target: None,
+        content: None,
+        old_string: None,
+        command: Some(command.to_string()),
+        ..Default::default()
+    }
+}
+
+fn turn_record() -> TurnRecord {
+    TurnRecord {
+        turn_id: "t1".to_string(),
+        session_id: "s1".to_string(),
+        project: "/tmp".to_string(),
+        timestamp: attentive_telemetry::now(),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_counts_destructive_commands_per_session() {
+    let _env = TestEnv::new();
+    let mut plugin = GuardrailPlugin::new();
+    let session_state = SessionState::new();
+    plugin.on_session_start(&session_state);
+
+    let calls = vec![bash("cargo test"), bash("git push --force origin main")];
+    let message = plugin.on_stop(&calls, &session_state).unwrap();
+    assert!(message.contains("destructive command ran: `git push --force origin main`"));
+    assert!(message.contains("1 destructive command(s) this session"));
+
+    // Each Stop gets only its own turn's calls
+    let message = plugin
+        .on_stop(&[bash("chmod 777 deploy.sh")], &session_state)
+        .unwrap();
+    assert!(!message.contains("git push"));
+    assert!(message.contains("2 destructive command(s) this session"));
+
+    let mut record = turn_record();
+    plugin.on_turn_record(&mut record);
+    assert_eq!(record.extensions["guardrail"]["session_count"], 2);
+
+    assert!(plugin.on_stop(&[bash("ls")], &session_state).is_none());
+    let mut record = turn_record();
+    plugin.on_turn_record(&mut record);
+    assert!(record.extensions.is_empty());
+
+    // A new session starts the count over
+    plugin.on_session_start(&session_state);
+    let message = plugin
+        .on_stop(&[bash("git push -f")], &session_state)
+        .unwrap();
+    assert!(message.contains("1 destructive command(s) this session"));
+}
+
+#[test]
+fn test_pre_tool_use_warns_without_counting() {
+    let _env = TestEnv::new();
+    let mut plugin = GuardrailPlugin::new();
+    let warning = plugin
+        .on_pre_tool_use(&bash("mysql -e 'DROP DATABASE prod'"))
+        .unwrap();
+    assert!(warning.contains("looks destructive (DROP TABLE)"));
+    assert!(plugin.on_pre_tool_use(&bash("git status")).is_none());
+
+    let mut record = turn_record();
+    plugin.on_turn_record(&mut record);
+    assert!(record.extensions.is_empty());
+}
+
+#[test]
+fn test_user_patterns_from_plugin_config() {
+    let env = TestEnv::new();
+    let plugins_dir = env.paths().home_claude.join("plugins");
+    std::fs::create_dir_all(&plugins_dir).unwrap();
+    std::fs::write(
+        plugins_dir.join("config.json"),
+        r#"{"guardrail": {"builtin": false, "patterns": ["\\bterraform\\s+destroy\\b", "("]}}"#,
+    )
+    .unwrap();
+
+    let mut plugin = GuardrailPlugin::new();
+    let warning = plugin
+        .on_pre_tool_use(&bash("terraform destroy -auto-approve"))
+        .unwrap();
+    assert!(warning.contains(r"(\bterraform\s+destroy\b)"));
+    // Built-ins are off
+    assert!(plugin.on_pre_tool_use(&bash("git push --force")).is_none());
+}
@@ -1,19 +1,9 @@
 use attentive_plugins::{LoopBreakerPlugin, Plugin, SessionState, ToolCall};
-use serial_test::serial;
-
-fn cleanup_state() {
-    let paths = attentive_telemetry::Paths::new().unwrap();
-    let state_file = paths
-        .home_claude
-        .join("plugins")
-        .join("loopbreaker_state.json");
-    std::fs::remove_file(state_file).ok();
-}
+use attentive_telemetry::TestEnv;
 
 #[test]
-#[serial]
 fn test_three_identical_signatures_detects_loop() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = LoopBreakerPlugin::new();
 
     // Initialize session to clear any stale state
@@ -27,6 +17,7 @@ fn test_three_identical_signatures_detects_loop() {
         content: Some("new content".to_string()),
         old_string: Some("old content".to_string()),
         command: None,
+        ..Default::default()
     }];
 
     let result1 = plugin.on_stop(&tool_calls, &session_state);
@@ -46,9 +37,8 @@ fn test_three_identical_signatures_detects_loop() {
 }
 
 #[test]
-#[serial]
 fn test_different_files_no_loop() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = LoopBreakerPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -60,6 +50,7 @@ fn test_different_files_no_loop() {
         content: None,
         old_string: Some("content".to_string()),
         command: None,
+        ..Default::default()
     }];
     plugin.on_stop(&tool_calls_1, &session_state);
 
@@ -70,6 +61,7 @@ fn test_different_files_no_loop() {
         content: None,
         old_string: Some("content".to_string()),
         command: None,
+        ..Default::default()
     }];
     plugin.on_stop(&tool_calls_2, &session_state);
 
@@ -80,6 +72,7 @@ fn test_different_files_no_loop() {
         content: None,
         old_string: Some("content".to_string()),
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&tool_calls_3, &session_state);
 
@@ -87,9 +80,8 @@ fn test_different_files_no_loop() {
 }
 
 #[test]
-#[serial]
 fn test_read_tools_dont_count_as_work() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = LoopBreakerPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -101,6 +93,7 @@ fn test_read_tools_dont_count_as_work() {
         content: None,
         old_string: None,
         command: None,
+        ..Default::default()
     }];
 
     plugin.on_stop(&read_calls, &session_state);
@@ -111,9 +104,8 @@ fn test_read_tools_dont_count_as_work() {
 }
 
 #[test]
-#[serial]
 fn test_loop_broken_by_different_file() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = LoopBreakerPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -124,6 +116,7 @@ fn test_loop_broken_by_different_file() {
         content: None,
         old_string: Some("content".to_string()),
         command: None,
+        ..Default::default()
     }];
 
     let file2_calls = vec![ToolCall {
@@ -132,6 +125,7 @@ fn test_loop_broken_by_different_file() {
         content: None,
         old_string: Some("content".to_string()),
         command: None,
+        ..Default::default()
     }];
 
     // Build up a loop on file1
@@ -154,3 +148,66 @@ fn test_loop_broken_by_different_file() {
         "Loop should be detected again after reset"
     );
 }
+
+fn edit(file: &str, old_string: &str) -> Vec<ToolCall> {
+    vec![ToolCall {
+        tool: "Edit".to_string(),
+        target: Some(file.to_string()),
+        content: None,
+        old_string: Some(old_string.to_string()),
+        command: None,
+        ..Default::default()
+    }]
+}
+
+#[test]
+fn test_alert_escalates_and_lists_attempts() {
+    let _env = TestEnv::new();
+    let mut plugin = LoopBreakerPlugin::new();
+    let session_state = SessionState::new();
+    plugin.on_session_start(&session_state);
+
+    plugin.on_stop(&edit("/src/parser.rs", "fn parse_header"), &session_state);
+    for _ in 0..3 {
+        plugin.on_stop(&edit("/src/parser.rs", "let offset"), &session_state);
+    }
+    let alert = plugin.on_prompt_post("", "", &session_state);
+    assert!(alert.contains("`parser.rs` 3 times"), "{}", alert);
+    assert!(alert.contains("- Edit replacing `fn parse_header`"));
+    assert!(alert.contains("- Edit replacing `let offset`"));
+
+    // Going back to an approach already tried doesn't reset the escalation
+    plugin.on_stop(&edit("/src/parser.rs", "fn parse_header"), &session_state);
+    let result = plugin.on_stop(&edit("/src/parser.rs", "let offset"), &session_state);
+    assert!(result.unwrap().contains("(escalation 2/3)"));
+    let alert = plugin.on_prompt_post("", "", &session_state);
+    assert!(alert.contains("(escalated)"));
+    assert!(alert.contains("different file"));
+
+    plugin.on_stop(&edit("/src/parser.rs", "let offset"), &session_state);
+    plugin.on_stop(&edit("/src/parser.rs", "let offset"), &session_state);
+    let alert = plugin.on_prompt_post("", "", &session_state);
+    assert!(alert.contains("**STOP.** 7 attempts"), "{}", alert);
+    assert!(alert.contains("Summarize for the user"));
+}
+
+#[test]
+fn test_new_approach_resets_escalation() {
+    let _env = TestEnv::new();
+    let mut plugin = LoopBreakerPlugin::new();
+    let session_state = SessionState::new();
+    plugin.on_session_start(&session_state);
+
+    for _ in 0..5 {
+        plugin.on_stop(&edit("/src/parser.rs", "let offset"), &session_state);
+    }
+    assert!(
+        plugin
+            .on_prompt_post("", "", &session_state)
+            .contains("(escalated)")
+    );
+
+    let result = plugin.on_stop(&edit("/src/parser.rs", "struct Header"), &session_state);
+    assert!(result.is_none(), "a new approach clears the loop");
+    assert!(plugin.on_prompt_post("", "", &session_state).is_empty());
+}
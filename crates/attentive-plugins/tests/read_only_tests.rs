@@ -0,0 +1,21 @@
+//! Read-only mode is process-wide, so it gets a test binary of its own
+
+use attentive_plugins::{Plugin, RecallPlugin, SessionState};
+use attentive_telemetry::TestEnv;
+
+#[test]
+fn test_read_only_drains_pending_in_memory() {
+    let _env = TestEnv::new();
+    let mut plugin = RecallPlugin::new();
+    plugin.on_prompt_pre(
+        "look at @recall src/old.rs".to_string(),
+        &SessionState::new(),
+    );
+    let state_path = attentive_plugins::base::state_file(plugin.name()).unwrap();
+    let on_disk = std::fs::read_to_string(&state_path).unwrap();
+
+    attentive_plugins::base::set_read_only(true);
+    assert_eq!(plugin.take_pending(), ["src/old.rs"]);
+    assert!(plugin.take_pending().is_empty(), "drained for this process");
+    assert_eq!(std::fs::read_to_string(&state_path).unwrap(), on_disk);
+}
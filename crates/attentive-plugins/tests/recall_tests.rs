@@ -0,0 +1,60 @@
+use attentive_plugins::recall::{ColdEntry, write_cold_index};
+use attentive_plugins::{Plugin, RecallPlugin, SessionState, ToolCall};
+use attentive_telemetry::{PathCanonicalizer, TestEnv};
+
+#[test]
+fn test_prompt_directive_queues_recall() {
+    let _env = TestEnv::new();
+    let mut plugin = RecallPlugin::new();
+    let session_state = SessionState::new();
+
+    let (prompt, cont) =
+        plugin.on_prompt_pre("look at @recall src/old.rs".to_string(), &session_state);
+    assert!(cont);
+    assert_eq!(prompt, "look at @recall src/old.rs");
+
+    assert_eq!(plugin.take_pending(), vec!["src/old.rs".to_string()]);
+    assert!(plugin.take_pending().is_empty(), "pending should drain");
+}
+
+#[test]
+fn test_reading_cold_file_queues_recall() {
+    let env = TestEnv::new();
+    let index_path = env.paths().cold_index_path().unwrap();
+    let repo = env.paths().home_claude.join("repo");
+    std::fs::create_dir_all(&repo).unwrap();
+    let absolute = |rel: &str| repo.join(rel).to_string_lossy().into_owned();
+
+    // Cold entries are canonical repo-relative keys; Read targets are absolute
+    write_cold_index(
+        &index_path,
+        &[ColdEntry {
+            path: "src/evicted.rs".to_string(),
+            summary: "fn evicted".to_string(),
+            score: 0.1,
+        }],
+    )
+    .unwrap();
+
+    let mut plugin = RecallPlugin::new().with_canonicalizer(PathCanonicalizer::new(&repo));
+    let calls = vec![
+        ToolCall {
+            tool: "Read".to_string(),
+            target: Some(absolute("src/evicted.rs")),
+            content: None,
+            old_string: None,
+            command: None,
+            ..Default::default()
+        },
+        ToolCall {
+            tool: "Read".to_string(),
+            target: Some(absolute("src/not_cold.rs")),
+            content: None,
+            old_string: None,
+            command: None,
+            ..Default::default()
+        },
+    ];
+    plugin.on_stop(&calls, &SessionState::new());
+    assert_eq!(plugin.take_pending(), vec!["src/evicted.rs".to_string()]);
+}
@@ -1,19 +1,9 @@
 use attentive_plugins::{Plugin, SessionState, ToolCall, VerifyFirstPlugin};
-use serial_test::serial;
-
-fn cleanup_state() {
-    let paths = attentive_telemetry::Paths::new().unwrap();
-    let state_file = paths
-        .home_claude
-        .join("plugins")
-        .join("verifyfirst_state.json");
-    std::fs::remove_file(state_file).ok();
-}
+use attentive_telemetry::TestEnv;
 
 #[test]
-#[serial]
 fn test_read_then_edit_no_violation() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -25,6 +15,7 @@ fn test_read_then_edit_no_violation() {
         content: None,
         old_string: None,
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&read_call, &session_state);
     assert!(result.is_none(), "Read should not trigger violation");
@@ -36,15 +27,15 @@ fn test_read_then_edit_no_violation() {
         content: Some("new content".to_string()),
         old_string: Some("old content".to_string()),
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&edit_call, &session_state);
     assert!(result.is_none(), "Edit after Read should not violate");
 }
 
 #[test]
-#[serial]
 fn test_edit_without_read_violates() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -56,6 +47,7 @@ fn test_edit_without_read_violates() {
         content: Some("new content".to_string()),
         old_string: Some("old content".to_string()),
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&edit_call, &session_state);
     assert!(result.is_some(), "Edit without Read should violate");
@@ -63,9 +55,8 @@ fn test_edit_without_read_violates() {
 }
 
 #[test]
-#[serial]
 fn test_write_without_read_violates() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -77,6 +68,7 @@ fn test_write_without_read_violates() {
         content: Some("file content".to_string()),
         old_string: None,
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&write_call, &session_state);
     assert!(result.is_some(), "Write without Read should violate");
@@ -84,9 +76,8 @@ fn test_write_without_read_violates() {
 }
 
 #[test]
-#[serial]
 fn test_path_normalization() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -98,6 +89,7 @@ fn test_path_normalization() {
         content: None,
         old_string: None,
         command: None,
+        ..Default::default()
     }];
     plugin.on_stop(&read_call, &session_state);
 
@@ -108,6 +100,7 @@ fn test_path_normalization() {
         content: Some("new".to_string()),
         old_string: None,
         command: None,
+        ..Default::default()
     }];
     let result = plugin.on_stop(&edit_call, &session_state);
     assert!(
@@ -117,9 +110,8 @@ fn test_path_normalization() {
 }
 
 #[test]
-#[serial]
 fn test_policy_context_injection() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -131,6 +123,7 @@ fn test_policy_context_injection() {
         content: None,
         old_string: None,
         command: None,
+        ..Default::default()
     }];
     plugin.on_stop(&read_call, &session_state);
 
@@ -138,12 +131,28 @@ fn test_policy_context_injection() {
     let context = plugin.on_prompt_post("test prompt", "test context", &session_state);
     assert!(context.contains("VerifyFirst"), "Should inject policy");
     assert!(context.contains("file.rs"), "Should list verified files");
+
+    // The policy itself is stable, so it goes in the prefix slot
+    let prefix = plugin.on_prompt_prefix(&session_state);
+    assert!(prefix.contains("## VerifyFirst Policy"));
+    assert!(!prefix.contains("file.rs"));
+    plugin.on_stop(
+        &[ToolCall {
+            tool: "Read".to_string(),
+            target: Some("/path/to/other.rs".to_string()),
+            content: None,
+            old_string: None,
+            command: None,
+            ..Default::default()
+        }],
+        &session_state,
+    );
+    assert_eq!(plugin.on_prompt_prefix(&session_state), prefix);
 }
 
 #[test]
-#[serial]
 fn test_tool_without_target_doesnt_skip_remaining() {
-    cleanup_state();
+    let _env = TestEnv::new();
     let mut plugin = VerifyFirstPlugin::new();
     let session_state = SessionState::new();
     plugin.on_session_start(&session_state);
@@ -156,6 +165,7 @@ fn test_tool_without_target_doesnt_skip_remaining() {
             content: None,
             old_string: None,
             command: Some("ls".to_string()),
+            ..Default::default()
         },
         ToolCall {
             tool: "Edit".to_string(),
@@ -163,6 +173,7 @@ fn test_tool_without_target_doesnt_skip_remaining() {
             content: Some("new".to_string()),
             old_string: Some("old".to_string()),
             command: None,
+            ..Default::default()
         },
     ];
     let result = plugin.on_stop(&calls, &session_state);
@@ -0,0 +1,179 @@
+//! Extractive digests of files far over their context budget
+//!
+//! Truncation keeps a file's head and drops what doesn't fit, which for a file
+//! several times its budget loses most of it. A digest keeps the lines that say
+//! the most about the whole file instead: recently edited regions first, then
+//! symbol signatures, TODO-style notes and the leading lines, shown in file
+//! order with elisions.
+
+use crate::symbols::{SymbolKind, extract_symbols};
+use std::ops::RangeInclusive;
+use std::path::Path;
+use std::process::Command;
+
+/// Leading lines kept after everything else that fits
+const HEAD_LINES: usize = 10;
+
+/// Lines of context kept around each edited region
+const EDIT_CONTEXT: usize = 2;
+
+/// Elision between kept lines, charged to each kept line's budget
+const ELISION: &str = "...\n";
+
+/// Markers of notes worth keeping wherever they are
+const NOTE_MARKERS: &[&str] = &["TODO", "FIXME", "HACK", "XXX"];
+
+/// Digest of `content` in about `max_chars`, or `None` if nothing but leading
+/// lines would be kept (truncation does as well then). `edited` holds 1-based
+/// line ranges changed recently (see `git_changed_lines`), kept with a little
+/// context.
+pub fn digest_source(
+    content: &str,
+    path: &str,
+    max_chars: usize,
+    edited: &[RangeInclusive<usize>],
+) -> Option<String> {
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let line_range = |range: &RangeInclusive<usize>| {
+        let start = range.start().saturating_sub(1 + EDIT_CONTEXT);
+        let end = (*range.end() + EDIT_CONTEXT).min(lines.len());
+        start..end
+    };
+    let edited_lines: Vec<usize> = edited.iter().flat_map(line_range).collect();
+
+    let signature_lines: Vec<usize> = match extract_symbols(content, path) {
+        Some(file) => file
+            .symbols
+            .iter()
+            .filter(|s| s.kind != SymbolKind::Import && s.line > 0)
+            .map(|s| s.line - 1)
+            .collect(),
+        // No parser for the language: headings stand in for signatures
+        None => (0..lines.len())
+            .filter(|&i| lines[i].trim_start().starts_with('#'))
+            .collect(),
+    };
+    let note_lines: Vec<usize> = (0..lines.len())
+        .filter(|&i| NOTE_MARKERS.iter().any(|m| lines[i].contains(m)))
+        .collect();
+    let head_lines: Vec<usize> = (0..HEAD_LINES.min(lines.len())).collect();
+
+    let mut keep = vec![false; lines.len()];
+    let mut used = 0;
+    for tier in [&edited_lines, &signature_lines, &note_lines, &head_lines] {
+        if tier == &head_lines && used == 0 {
+            return None;
+        }
+        for &i in tier {
+            let cost = lines.get(i).map_or(0, |line| line.len() + ELISION.len());
+            if i >= lines.len() || keep[i] || used + cost > max_chars {
+                continue;
+            }
+            keep[i] = true;
+            used += cost;
+        }
+    }
+    let kept = keep.iter().filter(|&&k| k).count();
+
+    let mut out = String::with_capacity(used + 100);
+    let mut gap = false;
+    for (i, line) in lines.iter().enumerate() {
+        if keep[i] {
+            if gap {
+                out.push_str(ELISION);
+                gap = false;
+            }
+            out.push_str(line);
+        } else {
+            gap = true;
+        }
+    }
+    if gap {
+        out.push_str(ELISION);
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+    out.push_str(&format!(
+        "[digest: {} of {} lines kept: recent edits, signatures and TODOs]",
+        kept,
+        lines.len()
+    ));
+    Some(out)
+}
+
+/// 1-based line ranges of `path` that differ from HEAD (empty outside git or
+/// for untracked files). A pure deletion marks the line it follows.
+pub fn git_changed_lines(path: &str) -> Vec<RangeInclusive<usize>> {
+    let file = Path::new(path);
+    let output = Command::new("git")
+        .args(["diff", "-U0", "--no-color", "--no-ext-diff", "HEAD", "--"])
+        .arg(file.file_name().unwrap_or(file.as_os_str()))
+        .current_dir(
+            file.parent()
+                .filter(|p| p.is_dir())
+                .unwrap_or(Path::new(".")),
+        )
+        .output();
+    match output {
+        Ok(out) if out.status.success() => parse_diff_hunks(&String::from_utf8_lossy(&out.stdout)),
+        _ => Vec::new(),
+    }
+}
+
+/// New-side line ranges of the `@@` hunk headers in a unified diff
+pub fn parse_diff_hunks(diff: &str) -> Vec<RangeInclusive<usize>> {
+    diff.lines()
+        .filter_map(|line| {
+            let new_side = line.strip_prefix("@@ ")?.split_whitespace().nth(1)?;
+            let new_side = new_side.strip_prefix('+')?;
+            let (start, count): (usize, usize) = match new_side.split_once(',') {
+                Some((start, count)) => (start.parse().ok()?, count.parse().ok()?),
+                None => (new_side.parse().ok()?, 1),
+            };
+            Some(match count {
+                0 => start.max(1)..=start.max(1),
+                n => start..=start + n - 1,
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_diff_hunks() {
+        let diff = "diff --git a/x.rs b/x.rs\n@@ -3 +3 @@ fn a()\n-old\n+new\n@@ -10,0 +11,3 @@\n@@ -20,2 +22,0 @@\n";
+        assert_eq!(parse_diff_hunks(diff), vec![3..=3, 11..=13, 22..=22]);
+    }
+
+    #[test]
+    fn test_digest_keeps_edits_signatures_and_todos() {
+        let mut src = String::from("//! Module docs\n\nuse std::fmt;\n\n");
+        for i in 0..40 {
+            src.push_str(&format!(
+                "pub fn func_{i}() {{\n    let value = {i};\n    println!(\"{{}}\", value);\n}}\n\n"
+            ));
+        }
+        src.push_str("// TODO: handle overflow\n");
+        let edited_line = src
+            .lines()
+            .position(|l| l.contains("let value = 30;"))
+            .unwrap()
+            + 1;
+
+        let out = digest_source(&src, "lib.rs", 1200, &[edited_line..=edited_line]).unwrap();
+        assert!(out.contains("    let value = 30;\n    println!"));
+        assert!(out.contains("pub fn func_0()"));
+        assert!(out.contains("pub fn func_39()"));
+        assert!(out.contains("// TODO: handle overflow"));
+        assert!(!out.contains("let value = 12;"));
+        assert!(out.contains("...\n"));
+        assert!(out.ends_with("recent edits, signatures and TODOs]"));
+        assert!(out.len() <= 1200 + 100);
+
+        assert_eq!(digest_source(&"x".repeat(500), "a.txt", 100, &[]), None);
+    }
+}
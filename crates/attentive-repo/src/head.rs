@@ -0,0 +1,144 @@
+//! The checked-out branch and commit, read from `.git` without spawning git
+//!
+//! Hooks run on every prompt, so looking up HEAD has to cost a couple of
+//! small file reads rather than a process.
+
+use std::path::{Path, PathBuf};
+
+/// What `HEAD` points at
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GitHead {
+    /// Branch checked out; `None` on a detached HEAD
+    pub branch: Option<String>,
+    /// Commit HEAD resolves to; `None` on a branch with no commits yet
+    pub commit: Option<String>,
+}
+
+/// The git directory of the repo containing `dir`, following the `gitdir:`
+/// file of worktrees and submodules
+fn git_dir(dir: &Path) -> Option<PathBuf> {
+    for ancestor in dir.ancestors() {
+        let dot_git = ancestor.join(".git");
+        if dot_git.is_dir() {
+            return Some(dot_git);
+        }
+        if dot_git.is_file() {
+            let content = std::fs::read_to_string(&dot_git).ok()?;
+            let target = content.trim().strip_prefix("gitdir:")?.trim();
+            return Some(ancestor.join(target));
+        }
+    }
+    None
+}
+
+/// Directory shared refs live in: the main repo's for a worktree
+fn common_dir(git_dir: &Path) -> PathBuf {
+    std::fs::read_to_string(git_dir.join("commondir"))
+        .map(|c| git_dir.join(c.trim()))
+        .unwrap_or_else(|_| git_dir.to_path_buf())
+}
+
+/// Commit `reference` (e.g. `refs/heads/main`) points at, loose or packed
+fn resolve_ref(git_dir: &Path, reference: &str) -> Option<String> {
+    let common = common_dir(git_dir);
+    for dir in [git_dir, common.as_path()] {
+        if let Ok(commit) = std::fs::read_to_string(dir.join(reference)) {
+            return Some(commit.trim().to_string());
+        }
+    }
+    std::fs::read_to_string(common.join("packed-refs"))
+        .ok()?
+        .lines()
+        .filter(|line| !line.starts_with('#') && !line.starts_with('^'))
+        .find_map(|line| {
+            let (commit, name) = line.split_once(' ')?;
+            (name == reference).then(|| commit.to_string())
+        })
+}
+
+/// HEAD of the repo containing `dir`; `None` outside git
+pub fn git_head(dir: &Path) -> Option<GitHead> {
+    let git_dir = git_dir(dir)?;
+    let head = std::fs::read_to_string(git_dir.join("HEAD")).ok()?;
+    let head = head.trim();
+    Some(match head.strip_prefix("ref:") {
+        Some(reference) => {
+            let reference = reference.trim();
+            GitHead {
+                branch: Some(
+                    reference
+                        .strip_prefix("refs/heads/")
+                        .unwrap_or(reference)
+                        .to_string(),
+                ),
+                commit: resolve_ref(&git_dir, reference),
+            }
+        }
+        None => GitHead {
+            branch: None,
+            commit: Some(head.to_string()),
+        },
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_git_head_reads_loose_and_packed_refs() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let git = temp.path().join(".git");
+        std::fs::create_dir_all(git.join("refs/heads/feature")).unwrap();
+        std::fs::write(git.join("HEAD"), "ref: refs/heads/feature/login\n").unwrap();
+        std::fs::write(git.join("refs/heads/feature/login"), "abc123\n").unwrap();
+        let sub = temp.path().join("src");
+        std::fs::create_dir_all(&sub).unwrap();
+
+        let head = git_head(&sub).unwrap();
+        assert_eq!(head.branch.as_deref(), Some("feature/login"));
+        assert_eq!(head.commit.as_deref(), Some("abc123"));
+
+        std::fs::write(git.join("HEAD"), "ref: refs/heads/main\n").unwrap();
+        std::fs::write(
+            git.join("packed-refs"),
+            "# pack-refs with: peeled\ndef456 refs/heads/main\n^0123 \n",
+        )
+        .unwrap();
+        assert_eq!(git_head(&sub).unwrap().commit.as_deref(), Some("def456"));
+
+        // Detached
+        std::fs::write(git.join("HEAD"), "fedcba\n").unwrap();
+        assert_eq!(
+            git_head(temp.path()).unwrap(),
+            GitHead {
+                branch: None,
+                commit: Some("fedcba".to_string()),
+            }
+        );
+    }
+
+    #[test]
+    fn test_git_head_follows_worktree_gitdir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let main_git = temp.path().join("main/.git");
+        let worktree_git = main_git.join("worktrees/wt");
+        std::fs::create_dir_all(main_git.join("refs/heads")).unwrap();
+        std::fs::create_dir_all(&worktree_git).unwrap();
+        std::fs::write(main_git.join("refs/heads/topic"), "aaa111\n").unwrap();
+        std::fs::write(worktree_git.join("HEAD"), "ref: refs/heads/topic\n").unwrap();
+        std::fs::write(worktree_git.join("commondir"), "../..\n").unwrap();
+        let wt = temp.path().join("wt");
+        std::fs::create_dir_all(&wt).unwrap();
+        std::fs::write(
+            wt.join(".git"),
+            format!("gitdir: {}\n", worktree_git.display()),
+        )
+        .unwrap();
+
+        let head = git_head(&wt).unwrap();
+        assert_eq!(head.branch.as_deref(), Some("topic"));
+        assert_eq!(head.commit.as_deref(), Some("aaa111"));
+        assert!(git_head(Path::new("/")).is_none());
+    }
+}
@@ -0,0 +1,151 @@
+//! Which repo files attentive scans
+//!
+//! Walks honor `.gitignore` (and git's own excludes), a project
+//! `.attentiveignore` in the same gitignore syntax, a built-in list of
+//! dependency and build directories, and extra patterns from the
+//! `exclude_files` config key.
+
+use ::ignore::WalkBuilder;
+use ::ignore::gitignore::{Gitignore, GitignoreBuilder};
+use std::path::{Path, PathBuf};
+
+/// Project ignore file, gitignore syntax, read in every directory walked
+pub const ATTENTIVE_IGNORE_FILE: &str = ".attentiveignore";
+
+/// Directories never scanned, even when no ignore file lists them
+const SKIP_DIRS: &[&str] = &[
+    ".git",
+    "node_modules",
+    "target",
+    "__pycache__",
+    ".venv",
+    "dist",
+    "build",
+];
+
+/// Files larger than this are skipped (generated or vendored code)
+const MAX_FILE_BYTES: u64 = 512 * 1024;
+
+/// Ignore rules for one repo root
+pub struct RepoIgnore {
+    root: PathBuf,
+    excludes: Gitignore,
+}
+
+impl RepoIgnore {
+    /// Rules for `root` plus `excludes` (gitignore-style patterns relative to
+    /// it). Invalid patterns are skipped with a warning.
+    pub fn new(root: &Path, excludes: &[String]) -> Self {
+        let mut builder = GitignoreBuilder::new(root);
+        for pattern in excludes {
+            if let Err(e) = builder.add_line(None, pattern) {
+                tracing::warn!("ignoring invalid exclude pattern {:?}: {}", pattern, e);
+            }
+        }
+        Self {
+            root: root.to_path_buf(),
+            excludes: builder.build().unwrap_or_else(|_| Gitignore::empty()),
+        }
+    }
+
+    /// Paths (relative to the root, `/`-separated, sorted) of every file small
+    /// enough to scan that no rule excludes
+    pub fn files(&self) -> Vec<String> {
+        let excludes = self.excludes.clone();
+        let walker = WalkBuilder::new(&self.root)
+            .hidden(false)
+            .require_git(false)
+            .add_custom_ignore_filename(ATTENTIVE_IGNORE_FILE)
+            // Size is checked here: with `max_filesize` set, files bypass the filter
+            .filter_entry(move |entry| {
+                let is_dir = entry.file_type().is_some_and(|t| t.is_dir());
+                let skipped = if is_dir {
+                    SKIP_DIRS.contains(&entry.file_name().to_string_lossy().as_ref())
+                } else {
+                    entry.metadata().is_ok_and(|m| m.len() > MAX_FILE_BYTES)
+                };
+                !skipped
+                    && !excludes
+                        .matched_path_or_any_parents(entry.path(), is_dir)
+                        .is_ignore()
+            })
+            .build();
+
+        let mut files: Vec<String> = walker
+            .flatten()
+            .filter(|entry| entry.file_type().is_some_and(|t| t.is_file()))
+            .filter_map(|entry| {
+                let rel = entry.path().strip_prefix(&self.root).ok()?;
+                Some(rel.to_string_lossy().replace('\\', "/"))
+            })
+            .collect();
+        files.sort();
+        files
+    }
+}
+
+/// Files under `root` that attentive scans; see [`RepoIgnore::files`]
+pub fn repo_files(root: &Path, excludes: &[String]) -> Vec<String> {
+    RepoIgnore::new(root, excludes).files()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn write_all(root: &Path, files: &[(&str, &str)]) {
+        for (file, content) in files {
+            let path = root.join(file);
+            std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+            std::fs::write(path, content).unwrap();
+        }
+    }
+
+    #[test]
+    fn test_repo_files_skips_build_dirs() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_all(
+            root,
+            &[
+                ("src/lib.rs", "x"),
+                ("README.md", "x"),
+                ("target/debug/out.rs", "x"),
+                (".git/HEAD", "x"),
+            ],
+        );
+        std::fs::write(root.join("huge.rs"), vec![b'x'; 600 * 1024]).unwrap();
+
+        assert_eq!(repo_files(root, &[]), ["README.md", "src/lib.rs"]);
+    }
+
+    #[test]
+    fn test_repo_files_honors_ignore_files_and_excludes() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        write_all(
+            root,
+            &[
+                (".gitignore", "*.log\n"),
+                (ATTENTIVE_IGNORE_FILE, "fixtures/\n"),
+                ("src/gen/schema.rs", "x"),
+                ("src/lib.rs", "x"),
+                ("src/debug.log", "x"),
+                ("tests/fixtures/big.json", "x"),
+                ("vendor/.gitignore", "*.c\n"),
+                ("vendor/lib.c", "x"),
+                ("vendor/lib.h", "x"),
+            ],
+        );
+
+        assert_eq!(
+            repo_files(root, &["src/gen/".to_string(), "*.h".to_string()]),
+            [
+                ".attentiveignore",
+                ".gitignore",
+                "src/lib.rs",
+                "vendor/.gitignore"
+            ]
+        );
+    }
+}
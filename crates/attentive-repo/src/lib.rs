@@ -1,7 +1,21 @@
-//! Repository analysis with symbol extraction and dependency ranking
+//! Repository analysis with symbol extraction, cross-references and dependency ranking
 
+mod digest;
+mod head;
+mod ignore;
 mod mapper;
+mod recency;
+mod renames;
 mod symbols;
+mod truncate;
+mod xref;
 
+pub use digest::{digest_source, git_changed_lines, parse_diff_hunks};
+pub use head::{GitHead, git_head};
+pub use ignore::{ATTENTIVE_IGNORE_FILE, RepoIgnore, repo_files};
 pub use mapper::RepoMapper;
-pub use symbols::{FileSymbols, Symbol, SymbolKind};
+pub use recency::{RECENCY_LOOKBACK_COMMITS, git_recent_files, parse_recency_log};
+pub use renames::{RENAME_LOOKBACK_COMMITS, Rename, detect_git_renames, parse_rename_log};
+pub use symbols::{FileSymbols, Symbol, SymbolKind, extract_symbols};
+pub use truncate::truncate_source;
+pub use xref::{XrefHit, XrefIndex};
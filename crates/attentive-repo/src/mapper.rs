@@ -1,15 +1,49 @@
 //! Repository mapper with PageRank-based ranking
 
+use crate::ignore::repo_files;
 use crate::symbols::{FileSymbols, extract_symbols};
+use attentive_telemetry::Versioned;
 use petgraph::algo::page_rank;
 use petgraph::graph::{DiGraph, NodeIndex};
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::path::Path;
+
+/// Symbols extracted from a file, valid while its content hashes to `hash`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedSymbols {
+    hash: u64,
+    symbols: FileSymbols,
+}
+
+/// Persisted symbol extraction results, keyed by path relative to the root
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SymbolCache {
+    files: HashMap<String, CachedSymbols>,
+}
+
+impl Versioned for SymbolCache {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "repo map cache";
+}
+
+fn content_hash(content: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    hasher.finish()
+}
 
 /// Repository mapper for symbol extraction and ranking
 pub struct RepoMapper {
     file_symbols: HashMap<String, FileSymbols>,
     dependency_graph: DiGraph<String, ()>,
     node_indices: HashMap<String, NodeIndex>,
+    /// Last segment of a module stem -> (file, stem) of the files with it
+    modules_by_name: HashMap<String, Vec<(String, String)>>,
+    /// Last segment of an import fragment -> (importing file, fragment)
+    imports_by_name: HashMap<String, Vec<(String, String)>>,
 }
 
 impl RepoMapper {
@@ -18,51 +52,122 @@ impl RepoMapper {
             file_symbols: HashMap::new(),
             dependency_graph: DiGraph::new(),
             node_indices: HashMap::new(),
+            modules_by_name: HashMap::new(),
+            imports_by_name: HashMap::new(),
         }
     }
 
+    /// Map every supported source file under `root` that isn't ignored or
+    /// matched by `excludes`, reusing the symbols cached for the project
+    /// (see `Paths::repo_map_path`) and re-extracting only changed files
+    pub fn load_cached(root: &Path, excludes: &[String]) -> anyhow::Result<Self> {
+        let cache_path = attentive_telemetry::Paths::new()?.repo_map_path()?;
+        Self::load_cached_at(root, excludes, &cache_path)
+    }
+
+    /// `load_cached` with the cache stored at `cache_path`
+    pub fn load_cached_at(
+        root: &Path,
+        excludes: &[String],
+        cache_path: &Path,
+    ) -> anyhow::Result<Self> {
+        let mut cache: SymbolCache = if cache_path.exists() {
+            attentive_telemetry::read_versioned(cache_path).unwrap_or_default()
+        } else {
+            SymbolCache::default()
+        };
+
+        let mut mapper = Self::new();
+        let mut fresh = SymbolCache::default();
+        let mut extracted = 0;
+        // extract_symbols only recognizes supported source extensions
+        for rel in repo_files(root, excludes) {
+            if extract_symbols("", &rel).is_none() {
+                continue;
+            }
+            let Ok(content) = std::fs::read_to_string(root.join(&rel)) else {
+                continue;
+            };
+            let hash = content_hash(&content);
+            let entry = match cache.files.remove(&rel) {
+                Some(cached) if cached.hash == hash => cached,
+                _ => {
+                    extracted += 1;
+                    let Some(symbols) = extract_symbols(&content, &rel) else {
+                        continue;
+                    };
+                    CachedSymbols { hash, symbols }
+                }
+            };
+            mapper.add_symbols(&rel, entry.symbols.clone());
+            fresh.files.insert(rel, entry);
+        }
+
+        // Rewrite when files changed, appeared or were deleted
+        if extracted > 0 || !cache.files.is_empty() {
+            tracing::debug!(
+                extracted,
+                removed = cache.files.len(),
+                "updating repo map cache"
+            );
+            attentive_telemetry::write_versioned(cache_path, &fresh)?;
+        }
+        Ok(mapper)
+    }
+
     /// Add a file's symbols to the mapper
     pub fn add_file(&mut self, path: &str, content: &str) {
-        let symbols = match extract_symbols(content, path) {
-            Some(s) => s,
-            None => return,
-        };
+        if let Some(symbols) = extract_symbols(content, path) {
+            self.add_symbols(path, symbols);
+        }
+    }
 
-        // Add node to graph
+    /// Add symbols already extracted from the file at `path`, e.g. cached
+    /// ones, so it needn't be read again
+    pub fn add_symbols(&mut self, path: &str, symbols: FileSymbols) {
+        if self.node_indices.contains_key(path) {
+            return;
+        }
         let idx = self.dependency_graph.add_node(path.to_string());
         self.node_indices.insert(path.to_string(), idx);
 
-        // Add edges for imports
-        for import in &symbols.imports {
-            if import.is_empty() {
-                continue;
+        // Link imports in both directions so edges don't depend on insertion
+        // order, looking only at files whose name an import could refer to
+        let stem = module_stem(path);
+        let mut fragments: Vec<String> = symbols
+            .imports
+            .iter()
+            .flat_map(|import| import_fragments(import, &symbols.language))
+            .collect();
+        fragments.sort();
+        fragments.dedup();
+        for fragment in &fragments {
+            let candidates = self.modules_by_name.get(last_segment(fragment));
+            for (other, other_stem) in candidates.into_iter().flatten() {
+                if resolves(path, fragment, other_stem) {
+                    self.dependency_graph
+                        .update_edge(idx, self.node_indices[other], ());
+                }
             }
-            // Try direct match first, then with language-specific extension
-            let target_idx = if let Some(&tidx) = self.node_indices.get(import) {
-                Some(tidx)
-            } else {
-                // Try common extensions based on language
-                let extensions = match symbols.language.as_str() {
-                    "python" => vec![".py"],
-                    "javascript" => vec![".js", ".jsx", ".ts", ".tsx"],
-                    "rust" => vec![".rs"],
-                    "go" => vec![".go"],
-                    "java" => vec![".java"],
-                    "c" => vec![".c", ".cpp", ".cc", ".h", ".hpp"],
-                    _ => vec![],
-                };
-
-                extensions.iter().find_map(|ext| {
-                    let with_ext = format!("{}{}", import, ext);
-                    self.node_indices.get(&with_ext).copied()
-                })
-            };
-
-            if let Some(tidx) = target_idx {
-                self.dependency_graph.add_edge(idx, tidx, ());
+        }
+        let importers = self.imports_by_name.get(last_segment(&stem));
+        for (importer, fragment) in importers.into_iter().flatten() {
+            if resolves(importer, fragment, &stem) {
+                self.dependency_graph
+                    .update_edge(self.node_indices[importer], idx, ());
             }
         }
 
+        for fragment in fragments {
+            self.imports_by_name
+                .entry(last_segment(&fragment).to_string())
+                .or_default()
+                .push((path.to_string(), fragment));
+        }
+        self.modules_by_name
+            .entry(last_segment(&stem).to_string())
+            .or_default()
+            .push((path.to_string(), stem));
         self.file_symbols.insert(path.to_string(), symbols);
     }
 
@@ -85,6 +190,30 @@ impl RepoMapper {
         self.file_symbols.get(path)
     }
 
+    /// Files this file imports (among files added to the mapper)
+    pub fn imports_of(&self, path: &str) -> Vec<String> {
+        self.neighbors(path, petgraph::Direction::Outgoing)
+    }
+
+    /// Files that import this file (among files added to the mapper)
+    pub fn imported_by(&self, path: &str) -> Vec<String> {
+        self.neighbors(path, petgraph::Direction::Incoming)
+    }
+
+    fn neighbors(&self, path: &str, direction: petgraph::Direction) -> Vec<String> {
+        let Some(&idx) = self.node_indices.get(path) else {
+            return Vec::new();
+        };
+        let mut files: Vec<String> = self
+            .dependency_graph
+            .neighbors_directed(idx, direction)
+            .filter_map(|n| self.dependency_graph.node_weight(n).cloned())
+            .collect();
+        files.sort();
+        files.dedup();
+        files
+    }
+
     /// Get ranked files respecting token budget
     pub fn get_ranked_files(&self, token_budget: usize) -> Vec<String> {
         let mut ranks: Vec<_> = self.page_rank().into_iter().collect();
@@ -107,6 +236,80 @@ impl RepoMapper {
     }
 }
 
+/// Whether `fragment`, of an import in `importer`, names the module at
+/// `stem`: all of it, or its tail below a directory enclosing the importer.
+/// `router` from `src/cli/main.rs` is `src/router.rs`, never `lib/router.rs`.
+fn resolves(importer: &str, fragment: &str, stem: &str) -> bool {
+    if stem == fragment {
+        return true;
+    }
+    let Some(dir) = stem
+        .strip_suffix(fragment)
+        .and_then(|rest| rest.strip_suffix('/'))
+    else {
+        return false;
+    };
+    let importer_dir = importer.rsplit_once('/').map_or("", |(dir, _)| dir);
+    importer_dir == dir
+        || importer_dir
+            .strip_prefix(dir)
+            .is_some_and(|below| below.starts_with('/'))
+}
+
+/// Part of a module path after its last `/`
+fn last_segment(module: &str) -> &str {
+    module.rsplit('/').next().unwrap_or(module)
+}
+
+/// Path without extension; package entry files (mod.rs, __init__.py, index.js)
+/// stand for their directory
+fn module_stem(path: &str) -> String {
+    let without_ext = match path.rfind('.') {
+        Some(dot) if !path[dot..].contains('/') => &path[..dot],
+        _ => path,
+    };
+    for entry in ["/mod", "/__init__", "/index"] {
+        if let Some(dir) = without_ext.strip_suffix(entry) {
+            return dir.to_string();
+        }
+    }
+    without_ext.to_string()
+}
+
+/// Candidate module paths an import statement could refer to
+fn import_fragments(import: &str, language: &str) -> Vec<String> {
+    let trimmed = import.trim().trim_end_matches(';');
+    if trimmed.is_empty() {
+        return Vec::new();
+    }
+    match language {
+        "rust" => {
+            // `crate::a::b::Item` may be the file a/b.rs or a.rs
+            let segments: Vec<&str> = trimmed
+                .split("::")
+                .filter(|s| !matches!(*s, "crate" | "self" | "super") && !s.starts_with('{'))
+                .collect();
+            (1..=segments.len())
+                .rev()
+                .map(|n| segments[..n].join("/"))
+                .collect()
+        }
+        "python" => vec![trimmed.trim_start_matches('.').replace('.', "/")],
+        "c" => vec![module_stem(trimmed)],
+        _ => {
+            let relative = trimmed
+                .split('/')
+                .filter(|s| !matches!(*s, "." | ".."))
+                .collect::<Vec<_>>()
+                .join("/");
+            vec![module_stem(&relative)]
+        }
+    }
+    .into_iter()
+    .filter(|f| !f.is_empty())
+    .collect()
+}
+
 impl Default for RepoMapper {
     fn default() -> Self {
         Self::new()
@@ -152,4 +355,91 @@ mod tests {
         let ranked = mapper.get_ranked_files(20); // Only 1-2 files fit
         assert!(ranked.len() <= 2);
     }
+
+    #[test]
+    fn test_imports_linked_regardless_of_order() {
+        let mut mapper = RepoMapper::new();
+        mapper.add_file(
+            "src/app.py",
+            "from pkg.utils import helper\ndef run(): pass",
+        );
+        mapper.add_file("src/pkg/utils.py", "def helper(): pass");
+
+        assert_eq!(mapper.imports_of("src/app.py"), vec!["src/pkg/utils.py"]);
+        assert_eq!(mapper.imported_by("src/pkg/utils.py"), vec!["src/app.py"]);
+    }
+
+    #[test]
+    fn test_rust_and_js_imports_resolve() {
+        let mut mapper = RepoMapper::new();
+        mapper.add_file("src/router.rs", "pub fn route() {}");
+        mapper.add_file("src/lib.rs", "use crate::router::route;\npub fn run() {}");
+        mapper.add_file(
+            "web/index.js",
+            "import { x } from './util'\nfunction main() {}",
+        );
+        mapper.add_file("web/util.ts", "export function x() {}");
+
+        assert_eq!(mapper.imported_by("src/router.rs"), vec!["src/lib.rs"]);
+        assert_eq!(mapper.imports_of("web/index.js"), vec!["web/util.ts"]);
+        assert!(mapper.imports_of("src/router.rs").is_empty());
+    }
+
+    #[test]
+    fn test_imports_resolve_only_below_an_enclosing_directory() {
+        let mut mapper = RepoMapper::new();
+        mapper.add_file("crates/core/src/router.rs", "pub fn route() {}");
+        mapper.add_file("crates/cli/src/router.rs", "pub fn route() {}");
+        mapper.add_file(
+            "crates/cli/src/commands/run.rs",
+            "use crate::router::route;\npub fn run() {}",
+        );
+        mapper.add_file("vendor/xrouter.rs", "pub fn route() {}");
+
+        assert_eq!(
+            mapper.imports_of("crates/cli/src/commands/run.rs"),
+            vec!["crates/cli/src/router.rs"]
+        );
+        assert!(mapper.imported_by("crates/core/src/router.rs").is_empty());
+        assert!(mapper.imported_by("vendor/xrouter.rs").is_empty());
+    }
+
+    #[test]
+    fn test_load_cached_reextracts_only_changed_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/router.rs"), "pub fn route() {}").unwrap();
+        std::fs::write(root.join("src/lib.rs"), "use crate::router::route;").unwrap();
+        std::fs::write(root.join("README.md"), "# Readme").unwrap();
+        let cache_path = dir.path().join("repo_map.json");
+
+        let mapper = RepoMapper::load_cached_at(&root, &[], &cache_path).unwrap();
+        assert_eq!(mapper.imported_by("src/router.rs"), vec!["src/lib.rs"]);
+        assert!(mapper.get_symbols("README.md").is_none());
+
+        // A cached entry is trusted while the content hash matches, so a
+        // planted symbol survives; once the file changes it is re-extracted
+        let mut cache: SymbolCache = attentive_telemetry::read_versioned(&cache_path).unwrap();
+        assert_eq!(cache.files.len(), 2);
+        let entry = cache.files.get_mut("src/router.rs").unwrap();
+        entry.symbols.symbols[0].name = "planted".to_string();
+        attentive_telemetry::write_versioned(&cache_path, &cache).unwrap();
+        let mapper = RepoMapper::load_cached_at(&root, &[], &cache_path).unwrap();
+        assert_eq!(
+            mapper.get_symbols("src/router.rs").unwrap().symbols[0].name,
+            "planted"
+        );
+
+        std::fs::write(root.join("src/router.rs"), "pub fn dispatch() {}").unwrap();
+        std::fs::remove_file(root.join("src/lib.rs")).unwrap();
+        let mapper = RepoMapper::load_cached_at(&root, &[], &cache_path).unwrap();
+        assert_eq!(
+            mapper.get_symbols("src/router.rs").unwrap().symbols[0].name,
+            "dispatch"
+        );
+        assert!(mapper.get_symbols("src/lib.rs").is_none());
+        let cache: SymbolCache = attentive_telemetry::read_versioned(&cache_path).unwrap();
+        assert_eq!(cache.files.len(), 1);
+    }
 }
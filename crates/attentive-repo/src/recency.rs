@@ -0,0 +1,92 @@
+//! File activity from recent git history, used to warm-start new projects
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// How many commits back to look when ranking files by activity
+pub const RECENCY_LOOKBACK_COMMITS: usize = 50;
+
+/// Starts each commit in `git log --name-only --format=%x1e` output
+const COMMIT_SEPARATOR: char = '\u{1e}';
+
+/// Rank files in `git log --name-only --format=%x1e` output (newest commit
+/// first) by how often and how recently they changed. Each commit adds
+/// `1 - i / n` to its files, where `i` is its age in commits, so a file in the
+/// newest commit gets 1 and one in the oldest almost nothing. Scores are
+/// scaled so the top file has 1.0; best first, ties by path.
+pub fn parse_recency_log(output: &str) -> Vec<(String, f64)> {
+    let commits: Vec<Vec<&str>> = output
+        .split(COMMIT_SEPARATOR)
+        .map(|commit| {
+            commit
+                .lines()
+                .map(str::trim)
+                .filter(|l| !l.is_empty())
+                .collect()
+        })
+        .filter(|files: &Vec<&str>| !files.is_empty())
+        .collect();
+
+    let mut totals: HashMap<&str, f64> = HashMap::new();
+    for (age, files) in commits.iter().enumerate() {
+        let weight = 1.0 - age as f64 / commits.len() as f64;
+        for file in files {
+            *totals.entry(file).or_insert(0.0) += weight;
+        }
+    }
+    let top = totals.values().copied().fold(0.0f64, f64::max);
+    if top <= 0.0 {
+        return Vec::new();
+    }
+    let mut ranked: Vec<(String, f64)> = totals
+        .into_iter()
+        .map(|(file, total)| (file.to_string(), total / top))
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+}
+
+/// Files changed in the last `max_commits` commits of the repository rooted
+/// at `toplevel`, ranked by `parse_recency_log`, as paths relative to the
+/// root. Files since deleted are skipped; empty outside git.
+pub fn git_recent_files(toplevel: &Path, max_commits: usize) -> Vec<(String, f64)> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--name-only",
+            "--no-renames",
+            "--format=%x1e",
+            &format!("-n{}", max_commits),
+        ])
+        .current_dir(toplevel)
+        .output();
+    match output {
+        Ok(out) if out.status.success() => parse_recency_log(&String::from_utf8_lossy(&out.stdout))
+            .into_iter()
+            .filter(|(file, _)| toplevel.join(file).is_file())
+            .collect(),
+        _ => Vec::new(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_recency_log() {
+        // Newest first: a.rs changed in all three commits, c.rs only in the newest
+        let output =
+            "\u{1e}\n\nsrc/a.rs\nsrc/c.rs\n\u{1e}\n\nsrc/a.rs\nsrc/b.rs\n\u{1e}\n\nsrc/a.rs\n";
+        let ranked = parse_recency_log(output);
+        let files: Vec<&str> = ranked.iter().map(|(f, _)| f.as_str()).collect();
+        assert_eq!(files, ["src/a.rs", "src/c.rs", "src/b.rs"]);
+        assert_eq!(ranked[0].1, 1.0);
+        // c.rs: 1 / (1 + 2/3 + 1/3); b.rs: (2/3) / 2
+        assert!((ranked[1].1 - 0.5).abs() < 1e-9);
+        assert!((ranked[2].1 - 1.0 / 3.0).abs() < 1e-9);
+
+        assert!(parse_recency_log("").is_empty());
+    }
+}
@@ -0,0 +1,135 @@
+//! File rename detection from git history
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::process::Command;
+
+/// How many commits back to look for renames
+pub const RENAME_LOOKBACK_COMMITS: usize = 200;
+
+/// A file moved from `from` to `to` (repo-relative paths)
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rename {
+    pub from: String,
+    pub to: String,
+}
+
+/// Parse `git log --name-status -M --diff-filter=R --format=` output.
+///
+/// git lists commits newest first; chains (a→b, later b→c) resolve every
+/// earlier name to the latest one, and moves back to the original path are dropped.
+pub fn parse_rename_log(output: &str) -> Vec<Rename> {
+    let mut ordered = Vec::new();
+    for line in output.lines() {
+        let mut parts = line.split('\t');
+        let (Some(status), Some(from), Some(to)) = (parts.next(), parts.next(), parts.next())
+        else {
+            continue;
+        };
+        if status.starts_with('R') {
+            ordered.push((from.to_string(), to.to_string()));
+        }
+    }
+
+    // Replay oldest first so each original path resolves to its latest name
+    let mut latest: HashMap<String, String> = HashMap::new();
+    let mut order: Vec<String> = Vec::new();
+    for (from, to) in ordered.into_iter().rev() {
+        let origins: Vec<String> = latest
+            .iter()
+            .filter(|(_, current)| **current == from)
+            .map(|(origin, _)| origin.clone())
+            .collect();
+        for origin in &origins {
+            latest.insert(origin.clone(), to.clone());
+        }
+        if let std::collections::hash_map::Entry::Vacant(slot) = latest.entry(from.clone()) {
+            slot.insert(to);
+            order.push(from);
+        }
+    }
+
+    order
+        .into_iter()
+        .filter_map(|from| {
+            let to = latest.remove(&from)?;
+            (from != to).then_some(Rename { from, to })
+        })
+        .collect()
+}
+
+/// Detect renames in the recent history of the repository rooted at
+/// `toplevel` (empty outside git).
+///
+/// Only renames whose old path is gone and whose new path exists are returned,
+/// so a file later re-created under an old name keeps its own history.
+pub fn detect_git_renames(toplevel: &Path, max_commits: usize) -> Vec<Rename> {
+    let output = Command::new("git")
+        .args([
+            "log",
+            "--name-status",
+            "-M",
+            "--diff-filter=R",
+            "--format=",
+            &format!("-n{}", max_commits),
+        ])
+        .current_dir(toplevel)
+        .output();
+
+    let renames = match output {
+        Ok(out) if out.status.success() => parse_rename_log(&String::from_utf8_lossy(&out.stdout)),
+        _ => return Vec::new(),
+    };
+    renames
+        .into_iter()
+        .filter(|r| !toplevel.join(&r.from).exists() && toplevel.join(&r.to).exists())
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rename_log() {
+        let output = "R100\tsrc/old.rs\tsrc/new.rs\n\nM\tsrc/lib.rs\nR087\tdocs/a.md\tdocs/b.md\n";
+        let renames = parse_rename_log(output);
+        assert_eq!(renames.len(), 2);
+        assert!(renames.contains(&Rename {
+            from: "src/old.rs".to_string(),
+            to: "src/new.rs".to_string(),
+        }));
+    }
+
+    #[test]
+    fn test_parse_rename_log_collapses_chains() {
+        // Newest first: b→c happened after a→b
+        let output = "R100\tb.rs\tc.rs\nR100\ta.rs\tb.rs\n";
+        let renames = parse_rename_log(output);
+        assert_eq!(
+            renames,
+            vec![
+                Rename {
+                    from: "a.rs".to_string(),
+                    to: "c.rs".to_string(),
+                },
+                Rename {
+                    from: "b.rs".to_string(),
+                    to: "c.rs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_rename_log_drops_round_trip() {
+        let output = "R100\tb.rs\ta.rs\nR100\ta.rs\tb.rs\n";
+        assert_eq!(
+            parse_rename_log(output),
+            vec![Rename {
+                from: "b.rs".to_string(),
+                to: "a.rs".to_string(),
+            }]
+        );
+    }
+}
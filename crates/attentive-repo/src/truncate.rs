@@ -0,0 +1,217 @@
+//! Structure-aware truncation of files for context injection
+
+use crate::symbols::{SymbolKind, extract_symbols};
+
+/// Leading lines always kept (module docs, imports, top-level declarations)
+const HEAD_LINES: usize = 10;
+
+/// Omitted symbols named in the truncation note
+const MAX_LISTED_SYMBOLS: usize = 5;
+
+fn is_import_line(line: &str) -> bool {
+    let trimmed = line.trim_start();
+    [
+        "use ", "pub use ", "import ", "from ", "#include", "package ",
+    ]
+    .iter()
+    .any(|prefix| trimmed.starts_with(prefix))
+}
+
+/// Longest prefix of `s` no longer than `max` bytes that ends on a char boundary
+fn prefix_at_boundary(s: &str, max: usize) -> &str {
+    let mut end = max.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// A run of lines kept or dropped together: one preamble line, or a symbol
+/// through to the line before the next symbol
+struct Unit<'a> {
+    start: usize,
+    end: usize,
+    symbol: Option<&'a str>,
+}
+
+/// Cut `content` to about `max_chars`, only at line boundaries. Source files
+/// keep their first lines and imports and drop whole trailing symbols. A note
+/// of what was omitted is appended.
+pub fn truncate_source(content: &str, path: &str, max_chars: usize) -> String {
+    if content.len() <= max_chars {
+        return content.to_string();
+    }
+
+    let lines: Vec<&str> = content.split_inclusive('\n').collect();
+    let symbols = extract_symbols(content, path);
+    let mut starts: Vec<(usize, &str)> = symbols
+        .iter()
+        .flat_map(|fs| &fs.symbols)
+        .filter(|s| s.kind != SymbolKind::Import && s.line > 0)
+        .map(|s| (s.line - 1, s.name.as_str()))
+        .collect();
+    starts.sort_by_key(|&(line, _)| line);
+    starts.dedup_by_key(|&mut (line, _)| line);
+
+    let first_symbol = starts.first().map_or(lines.len(), |&(line, _)| line);
+    let mut units: Vec<Unit> = (0..first_symbol)
+        .map(|i| Unit {
+            start: i,
+            end: i + 1,
+            symbol: None,
+        })
+        .collect();
+    for (i, &(start, name)) in starts.iter().enumerate() {
+        let end = starts.get(i + 1).map_or(lines.len(), |&(next, _)| next);
+        units.push(Unit {
+            start,
+            end,
+            symbol: Some(name),
+        });
+    }
+
+    let mut keep = vec![false; lines.len()];
+    let mut used = 0;
+    for (i, line) in lines.iter().enumerate() {
+        if i < HEAD_LINES || (symbols.is_some() && is_import_line(line)) {
+            keep[i] = true;
+            used += line.len();
+        }
+    }
+    if used > max_chars {
+        // The head alone is over budget: plain line-by-line fill
+        keep.fill(false);
+        used = 0;
+    }
+    let required = used;
+
+    let mut partial = None;
+    for unit in &units {
+        let size: usize = (unit.start..unit.end)
+            .filter(|&i| !keep[i])
+            .map(|i| lines[i].len())
+            .sum();
+        if used + size <= max_chars {
+            keep[unit.start..unit.end].fill(true);
+            used += size;
+            continue;
+        }
+        // Nothing but the head fits whole: keep what lines of this unit do
+        if used == required || unit.symbol.is_none() {
+            for i in unit.start..unit.end {
+                if keep[i] {
+                    continue;
+                }
+                if used + lines[i].len() > max_chars {
+                    if used == 0 {
+                        partial = Some(prefix_at_boundary(lines[i], max_chars));
+                    }
+                    break;
+                }
+                keep[i] = true;
+                used += lines[i].len();
+            }
+        }
+        break;
+    }
+
+    let mut out = String::with_capacity(used + 100);
+    let mut gap = false;
+    for (i, line) in lines.iter().enumerate() {
+        if keep[i] {
+            if gap {
+                out.push_str("...\n");
+                gap = false;
+            }
+            out.push_str(line);
+        } else {
+            gap = true;
+        }
+    }
+    if let Some(prefix) = partial {
+        out.push_str(prefix);
+        out.push_str("...");
+    }
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+
+    let omitted_lines = keep.iter().filter(|&&k| !k).count();
+    let omitted: Vec<&str> = units
+        .iter()
+        .filter(|u| !keep[u.start..u.end].iter().all(|&k| k))
+        .filter_map(|u| u.symbol)
+        .collect();
+    out.push_str(&format!(
+        "[truncated: {} of {} lines omitted",
+        omitted_lines,
+        lines.len()
+    ));
+    if !omitted.is_empty() {
+        out.push_str(&format!(
+            "; omitted symbols: {}",
+            omitted[..omitted.len().min(MAX_LISTED_SYMBOLS)].join(", ")
+        ));
+        if omitted.len() > MAX_LISTED_SYMBOLS {
+            out.push_str(&format!(" (+{} more)", omitted.len() - MAX_LISTED_SYMBOLS));
+        }
+    }
+    out.push(']');
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rust_file(functions: usize) -> String {
+        let mut src = String::from("//! Module docs\n\nuse std::fmt;\nuse std::io;\n\n");
+        for i in 0..functions {
+            src.push_str(&format!(
+                "pub fn func_{i}() {{\n    let value = {i};\n    println!(\"{{}}\", value);\n}}\n\n"
+            ));
+        }
+        src
+    }
+
+    #[test]
+    fn test_drops_whole_trailing_functions() {
+        let src = rust_file(20);
+        let out = truncate_source(&src, "lib.rs", 400);
+
+        assert!(out.contains("use std::io;\n"));
+        assert!(out.contains("pub fn func_0()"));
+        // Every kept function is complete
+        for i in 0..20 {
+            if out.contains(&format!("pub fn func_{i}()")) {
+                let body = format!("let value = {i};\n    println!(\"{{}}\", value);\n}}\n");
+                assert!(out.contains(&body), "func_{i} cut mid-body");
+            }
+        }
+        assert!(!out.contains("pub fn func_19"));
+        assert!(out.contains("omitted symbols: func_"));
+        assert!(out.len() <= 400 + 150);
+    }
+
+    #[test]
+    fn test_plain_text_cut_at_line_boundary() {
+        let src: String = (0..100).map(|i| format!("line number {i}\n")).collect();
+        let out = truncate_source(&src, "notes.md", 200);
+        let body = out.rsplit_once('[').unwrap().0;
+        assert!(body.lines().all(|l| l.starts_with("line number ")));
+        assert!(out.contains("lines omitted]"));
+    }
+
+    #[test]
+    fn test_multibyte_single_line_does_not_panic() {
+        let src = "é".repeat(1000);
+        let out = truncate_source(&src, "data.txt", 101);
+        assert!(out.starts_with("ééé"));
+        assert!(out.contains("[truncated: 1 of 1 lines omitted]"));
+    }
+
+    #[test]
+    fn test_fits_unchanged() {
+        assert_eq!(truncate_source("fn a() {}\n", "a.rs", 100), "fn a() {}\n");
+    }
+}
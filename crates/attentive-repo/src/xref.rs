@@ -0,0 +1,307 @@
+//! Identifier cross-reference index: which files mention a symbol
+
+use crate::ignore::repo_files;
+use crate::symbols::extract_symbols;
+use attentive_telemetry::Versioned;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::Path;
+use std::sync::OnceLock;
+use std::time::UNIX_EPOCH;
+
+static IDENT_RE: OnceLock<Regex> = OnceLock::new();
+
+/// Identifiers shorter than this are too ambiguous to index
+const MIN_IDENT_LEN: usize = 3;
+
+/// Where a symbol appears in one file
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+pub struct XrefHit {
+    pub path: String,
+    /// Whether the file defines the symbol (rather than only referencing it)
+    pub defines: bool,
+}
+
+/// Identifiers a file mentions and the symbols it defines, valid while its
+/// modification time is `modified`
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedXref {
+    /// Milliseconds since the epoch
+    modified: u128,
+    identifiers: Vec<String>,
+    definitions: Vec<String>,
+}
+
+impl CachedXref {
+    fn read(modified: u128, path: &str, content: &str) -> Self {
+        let re = IDENT_RE.get_or_init(|| Regex::new(r"\b[A-Za-z_][A-Za-z0-9_]*\b").unwrap());
+        let identifiers: BTreeSet<&str> = re
+            .find_iter(content)
+            .map(|m| m.as_str())
+            .filter(|ident| ident.len() >= MIN_IDENT_LEN)
+            .collect();
+        Self {
+            modified,
+            identifiers: identifiers.into_iter().map(String::from).collect(),
+            definitions: extract_symbols(content, path)
+                .map(|fs| fs.symbols.into_iter().map(|s| s.name).collect())
+                .unwrap_or_default(),
+        }
+    }
+}
+
+/// Persisted per-file scan results, keyed by path relative to the root
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct XrefCache {
+    files: HashMap<String, CachedXref>,
+}
+
+impl Versioned for XrefCache {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "xref index cache";
+}
+
+fn modified_millis(path: &Path) -> Option<u128> {
+    let modified = std::fs::metadata(path).ok()?.modified().ok()?;
+    Some(modified.duration_since(UNIX_EPOCH).ok()?.as_millis())
+}
+
+/// Maps identifiers to the files that define or reference them
+#[derive(Debug, Default)]
+pub struct XrefIndex {
+    references: HashMap<String, BTreeSet<String>>,
+    definitions: HashMap<String, BTreeSet<String>>,
+    file_definitions: HashMap<String, Vec<String>>,
+}
+
+impl XrefIndex {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Index every supported source file under `root` that isn't ignored or
+    /// matched by `excludes` (paths stored relative to `root`)
+    pub fn build(root: &Path, excludes: &[String]) -> Self {
+        let mut index = Self::new();
+        // extract_symbols only recognizes supported source extensions
+        for rel in repo_files(root, excludes) {
+            if extract_symbols("", &rel).is_some()
+                && let Ok(content) = std::fs::read_to_string(root.join(&rel))
+            {
+                index.add_file(&rel, &content);
+            }
+        }
+        index
+    }
+
+    /// `build`, reusing the scan cached for the project (see
+    /// `Paths::xref_index_path`) and re-reading only files whose
+    /// modification time changed
+    pub fn load_cached(root: &Path, excludes: &[String]) -> anyhow::Result<Self> {
+        let cache_path = attentive_telemetry::Paths::new()?.xref_index_path()?;
+        Self::load_cached_at(root, excludes, &cache_path)
+    }
+
+    /// `load_cached` with the cache stored at `cache_path`
+    pub fn load_cached_at(
+        root: &Path,
+        excludes: &[String],
+        cache_path: &Path,
+    ) -> anyhow::Result<Self> {
+        let mut cache: XrefCache = if cache_path.exists() {
+            attentive_telemetry::read_versioned(cache_path).unwrap_or_default()
+        } else {
+            XrefCache::default()
+        };
+
+        let mut index = Self::new();
+        let mut fresh = XrefCache::default();
+        let mut read = 0;
+        for rel in repo_files(root, excludes) {
+            if extract_symbols("", &rel).is_none() {
+                continue;
+            }
+            let path = root.join(&rel);
+            let Some(modified) = modified_millis(&path) else {
+                continue;
+            };
+            let entry = match cache.files.remove(&rel) {
+                Some(cached) if cached.modified == modified => cached,
+                _ => {
+                    let Ok(content) = std::fs::read_to_string(&path) else {
+                        continue;
+                    };
+                    read += 1;
+                    CachedXref::read(modified, &rel, &content)
+                }
+            };
+            index.add_entry(&rel, &entry);
+            fresh.files.insert(rel, entry);
+        }
+
+        // Rewrite when files changed, appeared or were deleted
+        if read > 0 || !cache.files.is_empty() {
+            tracing::debug!(read, removed = cache.files.len(), "updating xref cache");
+            attentive_telemetry::write_versioned(cache_path, &fresh)?;
+        }
+        Ok(index)
+    }
+
+    /// Index one file's definitions and identifier references
+    pub fn add_file(&mut self, path: &str, content: &str) {
+        self.add_entry(path, &CachedXref::read(0, path, content));
+    }
+
+    fn add_entry(&mut self, path: &str, entry: &CachedXref) {
+        for ident in &entry.identifiers {
+            self.references
+                .entry(ident.clone())
+                .or_default()
+                .insert(path.to_string());
+        }
+        for name in &entry.definitions {
+            self.definitions
+                .entry(name.clone())
+                .or_default()
+                .insert(path.to_string());
+        }
+        self.file_definitions
+            .insert(path.to_string(), entry.definitions.clone());
+    }
+
+    /// Every file mentioning `symbol`, definitions first
+    pub fn lookup(&self, symbol: &str) -> Vec<XrefHit> {
+        let definers = self.definitions.get(symbol);
+        let mut hits: Vec<XrefHit> = self
+            .references
+            .get(symbol)
+            .into_iter()
+            .flatten()
+            .map(|path| XrefHit {
+                path: path.clone(),
+                defines: definers.is_some_and(|d| d.contains(path)),
+            })
+            .collect();
+        hits.sort_by(|a, b| b.defines.cmp(&a.defines).then_with(|| a.path.cmp(&b.path)));
+        hits
+    }
+
+    /// Files that reference `symbol` without defining it
+    pub fn callers(&self, symbol: &str) -> Vec<String> {
+        self.lookup(symbol)
+            .into_iter()
+            .filter(|hit| !hit.defines)
+            .map(|hit| hit.path)
+            .collect()
+    }
+
+    /// Symbols defined in `path`
+    pub fn definitions_in(&self, path: &str) -> &[String] {
+        self.file_definitions
+            .get(path)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample_index() -> XrefIndex {
+        let mut index = XrefIndex::new();
+        index.add_file("src/router.rs", "pub fn update_attention() {}\n");
+        index.add_file(
+            "src/hooks.rs",
+            "use crate::router::update_attention;\nfn run() { update_attention(); }\n",
+        );
+        index.add_file("src/bench.py", "def bench():\n    update_attention()\n");
+        index
+    }
+
+    #[test]
+    fn test_lookup_definitions_first() {
+        let hits = sample_index().lookup("update_attention");
+        assert_eq!(hits.len(), 3);
+        assert_eq!(
+            hits[0],
+            XrefHit {
+                path: "src/router.rs".to_string(),
+                defines: true,
+            }
+        );
+        assert!(hits[1..].iter().all(|h| !h.defines));
+    }
+
+    #[test]
+    fn test_callers_exclude_definers() {
+        let index = sample_index();
+        assert_eq!(
+            index.callers("update_attention"),
+            vec!["src/bench.py", "src/hooks.rs"]
+        );
+        assert!(index.callers("missing_symbol").is_empty());
+        assert_eq!(index.definitions_in("src/router.rs"), ["update_attention"]);
+    }
+
+    #[test]
+    fn test_build_scans_supported_files() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::create_dir_all(dir.path().join("target")).unwrap();
+        std::fs::write(dir.path().join("src/a.rs"), "fn shared_helper() {}").unwrap();
+        std::fs::write(dir.path().join("src/b.rs"), "fn x() { shared_helper() }").unwrap();
+        std::fs::write(dir.path().join("notes.txt"), "shared_helper").unwrap();
+        std::fs::write(dir.path().join("target/gen.rs"), "shared_helper").unwrap();
+
+        let index = XrefIndex::build(dir.path(), &[]);
+        assert_eq!(index.callers("shared_helper"), vec!["src/b.rs"]);
+    }
+
+    #[test]
+    fn test_load_cached_rereads_only_modified_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path().join("repo");
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(root.join("src/a.rs"), "fn shared_helper() {}").unwrap();
+        std::fs::write(root.join("src/b.rs"), "fn x() { shared_helper() }").unwrap();
+        std::fs::write(root.join("src/gen.rs"), "fn y() { shared_helper() }").unwrap();
+        let cache_path = dir.path().join("xref.json");
+        let excludes = vec!["src/gen.rs".to_string()];
+
+        let index = XrefIndex::load_cached_at(&root, &excludes, &cache_path).unwrap();
+        assert_eq!(index.callers("shared_helper"), vec!["src/b.rs"]);
+        let cached = std::fs::read_to_string(&cache_path).unwrap();
+        assert!(cached.contains("src/b.rs") && !cached.contains("src/gen.rs"));
+
+        // An unchanged tree leaves the cache alone
+        let before = std::fs::metadata(&cache_path).unwrap().modified().unwrap();
+        XrefIndex::load_cached_at(&root, &excludes, &cache_path).unwrap();
+        assert_eq!(
+            std::fs::metadata(&cache_path).unwrap().modified().unwrap(),
+            before
+        );
+
+        // A cached entry with a stale mtime is re-read from disk
+        let mut cache: XrefCache = attentive_telemetry::read_versioned(&cache_path).unwrap();
+        cache.files.get_mut("src/b.rs").unwrap().identifiers.clear();
+        attentive_telemetry::write_versioned(&cache_path, &cache).unwrap();
+        let index = XrefIndex::load_cached_at(&root, &excludes, &cache_path).unwrap();
+        assert!(index.callers("shared_helper").is_empty());
+
+        cache.files.get_mut("src/b.rs").unwrap().modified = 0;
+        attentive_telemetry::write_versioned(&cache_path, &cache).unwrap();
+        let index = XrefIndex::load_cached_at(&root, &excludes, &cache_path).unwrap();
+        assert_eq!(index.callers("shared_helper"), vec!["src/b.rs"]);
+
+        std::fs::remove_file(root.join("src/b.rs")).unwrap();
+        let index = XrefIndex::load_cached_at(&root, &excludes, &cache_path).unwrap();
+        assert!(index.callers("shared_helper").is_empty());
+        assert!(
+            !std::fs::read_to_string(&cache_path)
+                .unwrap()
+                .contains("src/b.rs")
+        );
+    }
+}
@@ -0,0 +1,290 @@
+//! Injection records and token-count calibration against transcript usage
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::io::{BufRead, BufReader};
+use std::path::Path;
+
+use crate::io::atomic_write;
+use crate::types::PluginBudgetUsage;
+
+/// Injection records kept per project (one per turn)
+const MAX_INJECTION_RECORDS: usize = 50;
+
+/// Floor for the moving-average weight once enough samples have been seen
+const CALIBRATION_ALPHA: f64 = 0.1;
+
+/// Observed/estimated ratios outside this range are treated as noise
+const MIN_RATIO: f64 = 0.25;
+const MAX_RATIO: f64 = 4.0;
+
+/// What the prompt hook injected on one turn
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InjectionRecord {
+    /// Attention-state turn the context was built for
+    pub turn: usize,
+    pub timestamp: DateTime<Utc>,
+    pub chars: usize,
+    /// Heuristic token estimate of the injected context
+    pub estimated_tokens: usize,
+    /// Heuristic token estimate of the user's prompt
+    pub prompt_tokens: usize,
+    pub files: Vec<String>,
+    /// Heuristic token estimate per context source (hot, warm, plugin:<name>, ...)
+    #[serde(default)]
+    pub sources: BTreeMap<String, usize>,
+    /// HOT/WARM sections truncated or dropped to fit the context budget
+    #[serde(default)]
+    pub overflow: usize,
+    /// Secrets redacted from the injected HOT/WARM sections
+    #[serde(default)]
+    pub redactions: usize,
+    /// Estimated tokens the dedupe pass removed from the context
+    #[serde(default)]
+    pub deduped_tokens: usize,
+    /// Task type the prompt was classified as (bug_fix, feature, ...)
+    #[serde(default)]
+    pub task_type: Option<String>,
+    /// Files listed as likely needed in the next turns
+    #[serde(default)]
+    pub predicted: Vec<String>,
+    /// Injected files boosted by the editor's open files or focused buffer
+    #[serde(default)]
+    pub editor_files: Vec<String>,
+    /// Budget usage of each budgeted plugin that added context
+    #[serde(default)]
+    pub plugin_budgets: BTreeMap<String, PluginBudgetUsage>,
+    /// Injected files whose pinned or demoted rule the learner contradicts
+    #[serde(default)]
+    pub conflicts: Vec<String>,
+}
+
+/// Store `record`, replacing any earlier record for the same turn
+pub fn record_injection(path: &Path, record: InjectionRecord) -> std::io::Result<()> {
+    let mut records = read_injections(path);
+    records.retain(|r| r.turn != record.turn);
+    records.push(record);
+    let skip = records.len().saturating_sub(MAX_INJECTION_RECORDS);
+    let json = serde_json::to_string(&records[skip..])?;
+    atomic_write(path, json.as_bytes())
+}
+
+/// The injection recorded for `turn`, if any
+pub fn find_injection(path: &Path, turn: usize) -> Option<InjectionRecord> {
+    read_injections(path).into_iter().rfind(|r| r.turn == turn)
+}
+
+fn read_injections(path: &Path) -> Vec<InjectionRecord> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default()
+}
+
+/// Correction factor applied to heuristic token estimates, learned from
+/// the usage Claude reports in transcripts
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TokenCalibration {
+    pub factor: f64,
+    pub samples: usize,
+}
+
+impl Default for TokenCalibration {
+    fn default() -> Self {
+        Self {
+            factor: 1.0,
+            samples: 0,
+        }
+    }
+}
+
+impl TokenCalibration {
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|c| serde_json::from_str(&c).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        let json = serde_json::to_string(self)?;
+        atomic_write(path, json.as_bytes())
+    }
+
+    /// Calibrated token count for a heuristic estimate
+    pub fn calibrate(&self, estimated: usize) -> usize {
+        (estimated as f64 * self.factor).round() as usize
+    }
+
+    /// Fold in one observation; returns false when it was discarded as noise
+    pub fn observe(&mut self, estimated: usize, observed: usize) -> bool {
+        if estimated == 0 || observed == 0 {
+            return false;
+        }
+        let ratio = observed as f64 / estimated as f64;
+        if !(MIN_RATIO..=MAX_RATIO).contains(&ratio) {
+            return false;
+        }
+        // Plain average for the first samples, then an exponential moving average
+        let weight = (1.0 / (self.samples + 1) as f64).max(CALIBRATION_ALPHA);
+        self.factor += weight * (ratio - self.factor);
+        self.samples += 1;
+        true
+    }
+}
+
+/// Tokens spent on the last user turn: fresh input (uncached and cache
+/// writes) plus output, summed over every API call since the last prompt.
+/// Cache reads are left out since they're re-reads of context already paid for.
+pub fn last_turn_usage(transcript_path: &Path) -> Option<usize> {
+    let file = std::fs::File::open(transcript_path).ok()?;
+    let mut turn: Option<usize> = None;
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if is_user_prompt(&entry) {
+            turn = Some(0);
+            continue;
+        }
+        if let (Some(total), Some(usage)) = (turn.as_mut(), entry.pointer("/message/usage")) {
+            *total += usage_field(usage, "input_tokens")
+                + usage_field(usage, "cache_creation_input_tokens")
+                + usage_field(usage, "output_tokens");
+        }
+    }
+    turn.filter(|&total| total > 0)
+}
+
+fn usage_field(usage: &serde_json::Value, field: &str) -> usize {
+    usage.get(field).and_then(|v| v.as_u64()).unwrap_or(0) as usize
+}
+
+/// Whether a transcript entry is a typed user prompt rather than a tool result
+pub fn is_user_prompt(entry: &serde_json::Value) -> bool {
+    if entry.get("type").and_then(|t| t.as_str()) != Some("user") {
+        return false;
+    }
+    match entry.pointer("/message/content") {
+        Some(serde_json::Value::String(_)) => true,
+        Some(serde_json::Value::Array(items)) => !items
+            .iter()
+            .any(|i| i.get("type").and_then(|t| t.as_str()) == Some("tool_result")),
+        _ => false,
+    }
+}
+
+/// Tokens the last user turn added to the conversation (prompt plus injected
+/// context), from the growth in reported input usage across that prompt
+pub fn last_prompt_usage(transcript_path: &Path) -> Option<usize> {
+    let file = std::fs::File::open(transcript_path).ok()?;
+
+    let mut context_total: Option<usize> = None;
+    let mut before_prompt: Option<usize> = None;
+    let mut awaiting = false;
+    let mut observed = None;
+
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(entry) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
+        };
+        if is_user_prompt(&entry) {
+            before_prompt = context_total;
+            awaiting = true;
+            continue;
+        }
+        let Some(usage) = entry.pointer("/message/usage") else {
+            continue;
+        };
+        let input = usage_field(usage, "input_tokens")
+            + usage_field(usage, "cache_creation_input_tokens")
+            + usage_field(usage, "cache_read_input_tokens");
+        if awaiting {
+            if let Some(before) = before_prompt {
+                observed = Some(input.saturating_sub(before));
+            }
+            awaiting = false;
+        }
+        context_total = Some(input + usage_field(usage, "output_tokens"));
+    }
+    observed
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(turn: usize) -> InjectionRecord {
+        InjectionRecord {
+            turn,
+            timestamp: Utc::now(),
+            chars: 4000,
+            estimated_tokens: 1000,
+            prompt_tokens: 10,
+            files: vec!["src/lib.rs".to_string()],
+            sources: BTreeMap::new(),
+            overflow: 0,
+            redactions: 0,
+            deduped_tokens: 0,
+            task_type: None,
+            predicted: Vec::new(),
+            editor_files: Vec::new(),
+            plugin_budgets: BTreeMap::new(),
+            conflicts: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_injection_records_keyed_by_turn() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("injections.json");
+        record_injection(&path, record(1)).unwrap();
+        record_injection(&path, record(2)).unwrap();
+        record_injection(
+            &path,
+            InjectionRecord {
+                chars: 8,
+                ..record(2)
+            },
+        )
+        .unwrap();
+
+        assert_eq!(find_injection(&path, 1).unwrap().chars, 4000);
+        assert_eq!(find_injection(&path, 2).unwrap().chars, 8);
+        assert!(find_injection(&path, 3).is_none());
+        assert_eq!(read_injections(&path).len(), 2);
+    }
+
+    #[test]
+    fn test_calibration_converges_and_rejects_noise() {
+        let mut cal = TokenCalibration::default();
+        assert_eq!(cal.calibrate(100), 100);
+        assert!(cal.observe(100, 150));
+        assert!((cal.factor - 1.5).abs() < 1e-9);
+        assert!(!cal.observe(100, 10_000));
+        for _ in 0..50 {
+            cal.observe(100, 120);
+        }
+        assert!((cal.factor - 1.2).abs() < 0.01);
+        assert_eq!(cal.calibrate(1000), 1200);
+    }
+
+    #[test]
+    fn test_last_prompt_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("t.jsonl");
+        let lines = [
+            r#"{"type":"user","message":{"content":"first"}}"#,
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":10,"cache_read_input_tokens":990,"output_tokens":50}}}"#,
+            r#"{"type":"user","message":{"content":[{"type":"tool_result","content":"ok"}]}}"#,
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":5,"cache_read_input_tokens":1195,"output_tokens":20}}}"#,
+            r#"{"type":"user","message":{"content":"second"}}"#,
+            r#"{"type":"assistant","message":{"usage":{"input_tokens":700,"cache_read_input_tokens":1200,"output_tokens":30}}}"#,
+        ];
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        // 1900 input after the prompt vs. 1220 in context before it
+        assert_eq!(last_prompt_usage(&path), Some(680));
+        assert_eq!(last_turn_usage(&path), Some(730));
+    }
+}
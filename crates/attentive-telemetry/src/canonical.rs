@@ -0,0 +1,174 @@
+//! Path canonicalization so injected, used and learned paths compare equal
+
+use std::path::{Component, Path, PathBuf};
+use std::process::Command;
+
+/// Normalizes file paths to a single repo-relative form.
+///
+/// Tool calls report absolute paths while config and attention state may hold
+/// relative ones; both resolve to the same key here. Symlinks are resolved for
+/// files that exist, and paths outside the root stay absolute.
+#[derive(Debug, Clone)]
+pub struct PathCanonicalizer {
+    root: PathBuf,
+    resolved_root: Option<PathBuf>,
+    case_insensitive: bool,
+}
+
+impl PathCanonicalizer {
+    pub fn new(root: impl Into<PathBuf>) -> Self {
+        let root = normalize_lexically(&root.into());
+        let resolved_root = std::fs::canonicalize(&root).ok();
+        Self {
+            root,
+            resolved_root,
+            case_insensitive: cfg!(any(target_os = "macos", target_os = "windows")),
+        }
+    }
+
+    /// Canonicalizer rooted at the git toplevel, or the current directory outside git
+    pub fn for_current_dir() -> std::io::Result<Self> {
+        Ok(Self::for_dir(&std::env::current_dir()?))
+    }
+
+    /// Canonicalizer rooted at the git toplevel containing `dir`, or `dir`
+    /// outside git
+    pub fn for_dir(dir: &Path) -> Self {
+        Self::new(git_toplevel(dir).unwrap_or_else(|| dir.to_path_buf()))
+    }
+
+    /// Root that relative paths are resolved against
+    pub fn root(&self) -> &Path {
+        &self.root
+    }
+
+    /// Override case folding (defaults to on for macOS and Windows)
+    pub fn with_case_insensitive(mut self, case_insensitive: bool) -> Self {
+        self.case_insensitive = case_insensitive;
+        self
+    }
+
+    /// Repo-relative form of `path` with `/` separators
+    pub fn canonicalize(&self, path: &str) -> String {
+        let unified = path.replace('\\', "/");
+        let raw = Path::new(&unified);
+        let absolute = if raw.is_absolute() {
+            normalize_lexically(raw)
+        } else {
+            normalize_lexically(&self.root.join(raw))
+        };
+        let resolved = std::fs::canonicalize(&absolute).unwrap_or(absolute);
+
+        let relative = self
+            .resolved_root
+            .as_ref()
+            .and_then(|root| resolved.strip_prefix(root).ok())
+            .or_else(|| resolved.strip_prefix(&self.root).ok());
+        match relative {
+            Some(rel) if !rel.as_os_str().is_empty() => rel.to_string_lossy().replace('\\', "/"),
+            _ => resolved.to_string_lossy().replace('\\', "/"),
+        }
+    }
+
+    /// Comparison key: canonical form, case-folded on case-insensitive filesystems
+    pub fn key(&self, path: &str) -> String {
+        self.fold(&self.canonicalize(path))
+    }
+
+    /// Canonicalize a list, dropping duplicates while keeping first-seen order
+    pub fn canonicalize_all(&self, paths: &[String]) -> Vec<String> {
+        let mut seen = std::collections::HashSet::new();
+        paths
+            .iter()
+            .map(|p| self.canonicalize(p))
+            .filter(|p| seen.insert(self.fold(p)))
+            .collect()
+    }
+
+    fn fold(&self, canonical: &str) -> String {
+        if self.case_insensitive {
+            canonical.to_lowercase()
+        } else {
+            canonical.to_string()
+        }
+    }
+}
+
+/// Resolve `.` and `..` without touching the filesystem
+fn normalize_lexically(path: &Path) -> PathBuf {
+    let mut out = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                if !out.pop() {
+                    out.push("..");
+                }
+            }
+            other => out.push(other.as_os_str()),
+        }
+    }
+    out
+}
+
+fn git_toplevel(dir: &Path) -> Option<PathBuf> {
+    let output = Command::new("git")
+        .args(["rev-parse", "--show-toplevel"])
+        .current_dir(dir)
+        .output()
+        .ok()?;
+    output
+        .status
+        .success()
+        .then(|| PathBuf::from(String::from_utf8_lossy(&output.stdout).trim()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_absolute_and_relative_agree() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(dir.path().join("src")).unwrap();
+        std::fs::write(dir.path().join("src/lib.rs"), "").unwrap();
+
+        let canon = PathCanonicalizer::new(dir.path()).with_case_insensitive(false);
+        let abs = dir.path().join("src/lib.rs").to_string_lossy().to_string();
+        assert_eq!(canon.canonicalize(&abs), "src/lib.rs");
+        assert_eq!(canon.canonicalize("./src/../src/lib.rs"), "src/lib.rs");
+        assert_eq!(canon.canonicalize("src\\lib.rs"), "src/lib.rs");
+        // Missing files still normalize lexically
+        assert_eq!(canon.canonicalize("docs/./new.md"), "docs/new.md");
+    }
+
+    #[test]
+    fn test_outside_root_stays_absolute() {
+        let dir = tempfile::tempdir().unwrap();
+        let canon = PathCanonicalizer::new(dir.path().join("repo"));
+        assert!(canon.canonicalize("/elsewhere/file.rs").starts_with('/'));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_symlink_resolved() {
+        let dir = tempfile::tempdir().unwrap();
+        std::fs::write(dir.path().join("real.rs"), "").unwrap();
+        std::os::unix::fs::symlink(dir.path().join("real.rs"), dir.path().join("link.rs")).unwrap();
+
+        let canon = PathCanonicalizer::new(dir.path());
+        assert_eq!(canon.canonicalize("link.rs"), "real.rs");
+    }
+
+    #[test]
+    fn test_case_folding_key_and_dedup() {
+        let canon = PathCanonicalizer::new("/repo").with_case_insensitive(true);
+        assert_eq!(canon.key("/repo/Src/Lib.rs"), "src/lib.rs");
+        let all = canon.canonicalize_all(&[
+            "/repo/Src/Lib.rs".to_string(),
+            "src/lib.rs".to_string(),
+            "b.rs".to_string(),
+        ]);
+        assert_eq!(all, vec!["Src/Lib.rs", "b.rs"]);
+    }
+}
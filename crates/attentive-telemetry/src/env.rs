@@ -0,0 +1,173 @@
+//! Injectable clock and home directory, for hermetic tests and embedding
+//!
+//! Production code reads the time through [`now`] and resolves paths through
+//! [`Paths::new`](crate::Paths::new); both consult overrides installed on the
+//! current thread. [`TestEnv`] installs a temporary home and a fixed clock so
+//! tests can run in parallel without touching the real `~/.claude`.
+//!
+//! Overrides are thread-local: work moved onto another thread (e.g. a
+//! `spawn_blocking` task) sees the real clock and home directory.
+
+use chrono::{DateTime, Duration, TimeZone, Utc};
+use std::cell::RefCell;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+
+use crate::Paths;
+
+/// Source of the current time
+pub trait Clock: Send + Sync {
+    fn now(&self) -> DateTime<Utc>;
+}
+
+/// The wall clock
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> DateTime<Utc> {
+        Utc::now()
+    }
+}
+
+/// A clock that only moves when told to
+#[derive(Debug)]
+pub struct FixedClock {
+    now: Mutex<DateTime<Utc>>,
+}
+
+impl FixedClock {
+    pub fn new(now: DateTime<Utc>) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    pub fn set(&self, now: DateTime<Utc>) {
+        *self.now.lock().unwrap() = now;
+    }
+
+    pub fn advance(&self, by: Duration) {
+        *self.now.lock().unwrap() += by;
+    }
+}
+
+impl Clock for FixedClock {
+    fn now(&self) -> DateTime<Utc> {
+        *self.now.lock().unwrap()
+    }
+}
+
+thread_local! {
+    static CLOCK: RefCell<Option<Arc<dyn Clock>>> = const { RefCell::new(None) };
+    static HOME: RefCell<Option<PathBuf>> = const { RefCell::new(None) };
+}
+
+/// Current time from this thread's clock (the wall clock unless overridden)
+pub fn now() -> DateTime<Utc> {
+    CLOCK
+        .with(|c| c.borrow().as_ref().map(|clock| clock.now()))
+        .unwrap_or_else(Utc::now)
+}
+
+/// Use `clock` for [`now`] on this thread; `None` restores the wall clock
+pub fn set_clock(clock: Option<Arc<dyn Clock>>) {
+    CLOCK.with(|c| *c.borrow_mut() = clock);
+}
+
+/// Resolve `~` to `home` for [`Paths::new`] on this thread; `None` restores
+/// the real home directory
+pub fn set_home(home: Option<PathBuf>) {
+    HOME.with(|h| *h.borrow_mut() = home);
+}
+
+pub(crate) fn home_override() -> Option<PathBuf> {
+    HOME.with(|h| h.borrow().clone())
+}
+
+/// Instant the [`TestEnv`] clock starts at
+const TEST_EPOCH_SECS: i64 = 1_735_689_600; // 2025-01-01T00:00:00Z
+
+static TEST_ENV_SEQ: AtomicUsize = AtomicUsize::new(0);
+
+/// A temporary home directory and fixed clock installed on the current thread
+/// until dropped
+pub struct TestEnv {
+    home: PathBuf,
+    clock: Arc<FixedClock>,
+}
+
+impl TestEnv {
+    pub fn new() -> Self {
+        let seq = TEST_ENV_SEQ.fetch_add(1, Ordering::Relaxed);
+        let home =
+            std::env::temp_dir().join(format!("attentive-test-{}-{}", std::process::id(), seq));
+        let _ = std::fs::remove_dir_all(&home);
+        std::fs::create_dir_all(home.join(".claude")).expect("create test home");
+
+        let epoch = Utc.timestamp_opt(TEST_EPOCH_SECS, 0).unwrap();
+        let clock = Arc::new(FixedClock::new(epoch));
+        set_home(Some(home.clone()));
+        set_clock(Some(clock.clone()));
+        Self { home, clock }
+    }
+
+    /// The temporary home directory (contains `.claude`)
+    pub fn home(&self) -> &Path {
+        &self.home
+    }
+
+    pub fn paths(&self) -> Paths {
+        Paths::with_root(&self.home)
+    }
+
+    pub fn clock(&self) -> &FixedClock {
+        &self.clock
+    }
+}
+
+impl Default for TestEnv {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Drop for TestEnv {
+    fn drop(&mut self) {
+        set_home(None);
+        set_clock(None);
+        let _ = std::fs::remove_dir_all(&self.home);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_env_overrides_paths_and_clock() {
+        let real = Paths::new().unwrap().home_claude;
+        {
+            let env = TestEnv::new();
+            let paths = Paths::new().unwrap();
+            assert_eq!(paths.home_claude, env.home().join(".claude"));
+            assert!(paths.home_claude.is_dir());
+
+            let start = now();
+            env.clock().advance(Duration::minutes(5));
+            assert_eq!(now() - start, Duration::minutes(5));
+        }
+        assert_eq!(Paths::new().unwrap().home_claude, real);
+    }
+
+    #[test]
+    fn test_envs_are_isolated() {
+        let a = TestEnv::new();
+        let b = std::thread::spawn(|| TestEnv::new().home().to_path_buf())
+            .join()
+            .unwrap();
+        assert_ne!(a.home(), b);
+        assert!(!b.exists());
+    }
+}
@@ -1,9 +1,35 @@
 //! JSONL I/O and atomic file operations
+//!
+//! Records are written one per line with a CRC-32 of the JSON appended after
+//! a tab, so truncated or garbled lines can be told apart from good ones.
+//! Readers also accept plain JSON lines, as written by earlier versions, and
+//! skip lines that don't parse instead of failing the whole file.
 
-use serde::{Deserialize, Serialize};
+use serde::Serialize;
+use serde::de::DeserializeOwned;
 use std::fs::{File, OpenOptions};
-use std::io::{BufRead, BufReader, Write};
-use std::path::Path;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::marker::PhantomData;
+use std::path::{Path, PathBuf};
+
+/// Separates a record from its checksum
+const CHECKSUM_SEPARATOR: char = '\t';
+
+fn checksum(json: &str) -> String {
+    format!("{:08x}", crc32fast::hash(json.as_bytes()))
+}
+
+/// The JSON of a line, or why it is corrupt
+fn decode_line(bytes: &[u8]) -> Result<&str, &'static str> {
+    let line = std::str::from_utf8(bytes).map_err(|_| "invalid UTF-8")?;
+    let line = line.strip_suffix('\r').unwrap_or(line);
+    // serde_json escapes tabs inside strings, so a raw tab is always ours
+    match line.rsplit_once(CHECKSUM_SEPARATOR) {
+        Some((json, sum)) if checksum(json) == sum => Ok(json),
+        Some(_) => Err("checksum mismatch"),
+        None => Ok(line),
+    }
+}
 
 /// Append a JSON record to a JSONL file
 pub fn append_jsonl<T: Serialize>(path: &Path, record: &T) -> std::io::Result<()> {
@@ -11,35 +37,173 @@ pub fn append_jsonl<T: Serialize>(path: &Path, record: &T) -> std::io::Result<()
         std::fs::create_dir_all(parent)?;
     }
 
-    let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+    let mut file = OpenOptions::new()
+        .create(true)
+        .read(true)
+        .append(true)
+        .open(path)?;
 
     let json = serde_json::to_string(record)?;
-    writeln!(file, "{}", json)?;
+    let mut line = format!("{}{}{}\n", json, CHECKSUM_SEPARATOR, checksum(&json));
+    // Finish a line left torn by an interrupted write so it doesn't swallow this one
+    if file.metadata()?.len() > 0 {
+        let mut last = [0u8];
+        file.seek(SeekFrom::End(-1))?;
+        file.read_exact(&mut last)?;
+        if last[0] != b'\n' {
+            line.insert(0, '\n');
+        }
+    }
+    // One write, so concurrent appends don't interleave
+    file.write_all(line.as_bytes())?;
     Ok(())
 }
 
-/// Read all records from a JSONL file
-pub fn read_jsonl<T: for<'de> Deserialize<'de>>(path: &Path) -> std::io::Result<Vec<T>> {
-    if !path.exists() {
-        return Ok(Vec::new());
+/// Streams the records of a JSONL file, skipping lines that are corrupt or
+/// don't deserialize as `T`. A warning with the count is logged at the end.
+pub struct JsonlReader<T> {
+    lines: Option<std::io::Split<BufReader<File>>>,
+    path: PathBuf,
+    skipped: usize,
+    _record: PhantomData<fn() -> T>,
+}
+
+impl<T: DeserializeOwned> JsonlReader<T> {
+    /// Reader over `path`; a missing file reads as empty
+    pub fn open(path: &Path) -> std::io::Result<Self> {
+        let lines = match File::open(path) {
+            Ok(file) => Some(BufReader::new(file).split(b'\n')),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => None,
+            Err(e) => return Err(e),
+        };
+        Ok(Self {
+            lines,
+            path: path.to_path_buf(),
+            skipped: 0,
+            _record: PhantomData,
+        })
+    }
+
+    /// Lines skipped so far
+    pub fn skipped(&self) -> usize {
+        self.skipped
     }
+}
 
-    let file = File::open(path)?;
-    let reader = BufReader::new(file);
-    let mut records = Vec::new();
+impl<T: DeserializeOwned> Iterator for JsonlReader<T> {
+    type Item = std::io::Result<T>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let lines = self.lines.as_mut()?;
+        for bytes in lines.by_ref() {
+            let bytes = match bytes {
+                Ok(bytes) => bytes,
+                Err(e) => return Some(Err(e)),
+            };
+            if bytes.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let record = decode_line(&bytes)
+                .ok()
+                .and_then(|json| serde_json::from_str(json).ok());
+            match record {
+                Some(record) => return Some(Ok(record)),
+                None => self.skipped += 1,
+            }
+        }
+        self.lines = None;
+        if self.skipped > 0 {
+            tracing::warn!(
+                "skipped {} malformed line(s) in {} (`attentive repair` drops them)",
+                self.skipped,
+                self.path.display()
+            );
+        }
+        None
+    }
+}
 
-    for line in reader.lines() {
-        let line = line?;
-        if line.trim().is_empty() {
+/// Read all records from a JSONL file
+pub fn read_jsonl<T: DeserializeOwned>(path: &Path) -> std::io::Result<Vec<T>> {
+    JsonlReader::open(path)?.collect()
+}
+
+/// Good and corrupt line counts of a JSONL file
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct JsonlHealth {
+    pub valid: usize,
+    pub corrupt: usize,
+}
+
+/// Split the lines of `content` into valid JSON and corrupt ones
+fn sort_lines(content: &[u8]) -> (Vec<&str>, Vec<&[u8]>) {
+    let mut valid = Vec::new();
+    let mut corrupt = Vec::new();
+    for bytes in content.split(|b| *b == b'\n') {
+        if bytes.iter().all(u8::is_ascii_whitespace) {
             continue;
         }
-        match serde_json::from_str(&line) {
-            Ok(record) => records.push(record),
-            Err(_) => continue, // Skip malformed lines
+        match decode_line(bytes) {
+            Ok(json) if serde_json::from_str::<serde::de::IgnoredAny>(json).is_ok() => {
+                valid.push(json)
+            }
+            _ => corrupt.push(bytes),
         }
     }
+    (valid, corrupt)
+}
 
-    Ok(records)
+/// Count good and corrupt lines without deserializing records
+pub fn check_jsonl(path: &Path) -> std::io::Result<JsonlHealth> {
+    if !path.exists() {
+        return Ok(JsonlHealth::default());
+    }
+    let content = std::fs::read(path)?;
+    let (valid, corrupt) = sort_lines(&content);
+    Ok(JsonlHealth {
+        valid: valid.len(),
+        corrupt: corrupt.len(),
+    })
+}
+
+/// Rewrite `path` without its corrupt lines, checksumming every kept line.
+/// Dropped lines are appended to `<path>.corrupt` for inspection.
+pub fn repair_jsonl(path: &Path) -> std::io::Result<JsonlHealth> {
+    if !path.exists() {
+        return Ok(JsonlHealth::default());
+    }
+    let content = std::fs::read(path)?;
+    let (valid, corrupt) = sort_lines(&content);
+    let health = JsonlHealth {
+        valid: valid.len(),
+        corrupt: corrupt.len(),
+    };
+    let repaired: String = valid
+        .iter()
+        .map(|json| format!("{}{}{}\n", json, CHECKSUM_SEPARATOR, checksum(json)))
+        .collect();
+    if repaired.as_bytes() == content.as_slice() {
+        return Ok(health);
+    }
+    if !corrupt.is_empty() {
+        let mut dropped = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(corrupt_path(path))?;
+        for bytes in &corrupt {
+            dropped.write_all(bytes)?;
+            dropped.write_all(b"\n")?;
+        }
+    }
+    atomic_write(path, repaired.as_bytes())?;
+    Ok(health)
+}
+
+/// Where `repair_jsonl` keeps the lines it drops from `path`
+pub fn corrupt_path(path: &Path) -> PathBuf {
+    let mut name = path.as_os_str().to_owned();
+    name.push(".corrupt");
+    PathBuf::from(name)
 }
 
 /// Write data atomically using temp file + rename
@@ -97,6 +261,81 @@ mod tests {
         std::fs::remove_file(&test_file).unwrap();
     }
 
+    fn record(id: u32) -> TestRecord {
+        TestRecord {
+            id,
+            name: format!("r{}", id),
+        }
+    }
+
+    #[test]
+    fn test_reader_skips_corrupt_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("turns.jsonl");
+        append_jsonl(&path, &record(1)).unwrap();
+        // A checksummed line whose JSON was altered still parses but fails the check
+        let tampered = std::fs::read_to_string(&path)
+            .unwrap()
+            .replace("\"id\":1", "\"id\":7");
+        std::fs::write(
+            &path,
+            format!("{}{{\"id\":2,\"name\":\"legacy\"}}\n", tampered),
+        )
+        .unwrap();
+        // Interrupted write: no trailing newline
+        let mut file = OpenOptions::new().append(true).open(&path).unwrap();
+        file.write_all(b"{\"id\":3,\"na").unwrap();
+        append_jsonl(&path, &record(4)).unwrap();
+        std::fs::OpenOptions::new()
+            .append(true)
+            .open(&path)
+            .unwrap()
+            .write_all(&[0xff, 0xfe, b'\n'])
+            .unwrap();
+
+        let mut reader = JsonlReader::<TestRecord>::open(&path).unwrap();
+        let ids: Vec<u32> = reader.by_ref().map(|r| r.unwrap().id).collect();
+        assert_eq!(ids, [2, 4]);
+        assert_eq!(reader.skipped(), 3);
+        assert_eq!(
+            check_jsonl(&path).unwrap(),
+            JsonlHealth {
+                valid: 2,
+                corrupt: 3
+            }
+        );
+    }
+
+    #[test]
+    fn test_repair_drops_corrupt_lines() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("turns.jsonl");
+        std::fs::write(&path, "{\"id\":1,\"name\":\"a\"}\n{\"id\":2,\n").unwrap();
+        append_jsonl(&path, &record(3)).unwrap();
+
+        let health = repair_jsonl(&path).unwrap();
+        assert_eq!(health.corrupt, 1);
+        let records: Vec<TestRecord> = read_jsonl(&path).unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(check_jsonl(&path).unwrap().corrupt, 0);
+        assert_eq!(
+            std::fs::read_to_string(corrupt_path(&path)).unwrap(),
+            "{\"id\":2,\n"
+        );
+        // Every kept line is now checksummed, so a second pass changes nothing
+        let repaired = std::fs::read_to_string(&path).unwrap();
+        assert!(repaired.lines().all(|l| l.contains(CHECKSUM_SEPARATOR)));
+        repair_jsonl(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), repaired);
+    }
+
+    #[test]
+    fn test_read_missing_file_is_empty() {
+        let dir = tempfile::tempdir().unwrap();
+        let records: Vec<TestRecord> = read_jsonl(&dir.path().join("none.jsonl")).unwrap();
+        assert!(records.is_empty());
+    }
+
     #[test]
     fn test_atomic_write() {
         let temp_dir = std::env::temp_dir();
@@ -1,11 +1,32 @@
 //! Telemetry types and utilities for tracking context routing performance
 
+mod calibration;
+mod canonical;
+mod env;
 mod io;
 mod paths;
+mod query;
+mod sqlite;
 mod tokens;
 mod types;
+mod versioned;
 
-pub use io::{append_jsonl, atomic_write, read_jsonl};
-pub use paths::Paths;
+pub use calibration::{
+    InjectionRecord, TokenCalibration, find_injection, is_user_prompt, last_prompt_usage,
+    last_turn_usage, record_injection,
+};
+pub use canonical::PathCanonicalizer;
+pub use env::{Clock, FixedClock, SystemClock, TestEnv, now, set_clock, set_home};
+pub use io::{
+    JsonlHealth, JsonlReader, append_jsonl, atomic_write, check_jsonl, corrupt_path, read_jsonl,
+    repair_jsonl,
+};
+pub use paths::{Paths, remap_path};
+pub use query::{Expr, QueryError, TurnQuery, parse_since};
+pub use sqlite::{PooledConnection, SqlitePool};
 pub use tokens::estimate_tokens;
-pub use types::TurnRecord;
+pub use types::{EvalRecord, HookBenchRecord, PluginBudgetUsage, TurnRecord};
+pub use versioned::{
+    StateError, Versioned, from_versioned_str, migrate_file, read_versioned, stored_version,
+    to_versioned_string, write_versioned, write_versioned_compact,
+};
@@ -1,6 +1,6 @@
 //! Path resolution for telemetry files
 
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::process::Command;
 
 /// Resolves standard paths for telemetry files
@@ -8,24 +8,55 @@ use std::process::Command;
 pub struct Paths {
     pub home_claude: PathBuf,
     pub git_common_dir: Option<PathBuf>,
+    /// Directory project state is resolved for; the working directory when unset
+    cwd: Option<PathBuf>,
 }
 
 impl Paths {
     /// Create a new Paths resolver for the current working directory
     pub fn new() -> std::io::Result<Self> {
-        let home = dirs::home_dir().ok_or_else(|| {
-            std::io::Error::new(std::io::ErrorKind::NotFound, "home directory not found")
-        })?;
+        let home = crate::env::home_override()
+            .or_else(dirs::home_dir)
+            .ok_or_else(|| {
+                std::io::Error::new(std::io::ErrorKind::NotFound, "home directory not found")
+            })?;
+        Ok(Self::with_root(home))
+    }
 
-        let home_claude = home.join(".claude");
+    /// Create a Paths resolver rooted at `home` (state lives in `home/.claude`)
+    pub fn with_root(home: impl AsRef<Path>) -> Self {
+        let home_claude = home.as_ref().join(".claude");
 
         // Detect git worktree common dir
-        let git_common_dir = detect_git_common_dir();
+        let git_common_dir = detect_git_common_dir(None);
 
-        Ok(Self {
+        Self {
             home_claude,
             git_common_dir,
-        })
+            cwd: None,
+        }
+    }
+
+    /// Resolve project state for `dir` rather than the working directory, as
+    /// the state server does for each client
+    pub fn in_dir(mut self, dir: impl Into<PathBuf>) -> Self {
+        let dir = dir.into();
+        self.git_common_dir = detect_git_common_dir(Some(&dir));
+        self.cwd = Some(dir);
+        self
+    }
+
+    /// Directory project state is resolved for
+    pub fn cwd(&self) -> std::io::Result<PathBuf> {
+        match &self.cwd {
+            Some(dir) => Ok(dir.clone()),
+            None => std::env::current_dir(),
+        }
+    }
+
+    /// Canonicalizer for the repo containing `cwd`
+    pub fn canonicalizer(&self) -> std::io::Result<crate::PathCanonicalizer> {
+        Ok(crate::PathCanonicalizer::for_dir(&self.cwd()?))
     }
 
     /// Get telemetry directory path
@@ -38,11 +69,46 @@ impl Paths {
         self.telemetry_dir().join("turns.jsonl")
     }
 
-    /// Get project-scoped directory based on current working directory
+    /// Get hook_bench.jsonl (history of `benchmark --hook` runs) path
+    pub fn hook_bench_file(&self) -> PathBuf {
+        self.telemetry_dir().join("hook_bench.jsonl")
+    }
+
+    /// Get the state server's unix socket path
+    pub fn server_socket_path(&self) -> PathBuf {
+        self.home_claude.join("attentive.sock")
+    }
+
+    /// Get the compressed observations (and memories) database path
+    pub fn observations_db_path(&self) -> PathBuf {
+        self.home_claude.join("observations.db")
+    }
+
+    /// Get the file marking that the project rooted at `root` has memories in
+    /// the observations database, so prompts elsewhere needn't open it
+    pub fn memory_marker_path(&self, root: &Path) -> PathBuf {
+        self.project_dir_for(root).join("has_memories")
+    }
+
+    /// Get the API keys file used by compression backends
+    pub fn credentials_path(&self) -> PathBuf {
+        self.home_claude.join("attentive_credentials.json")
+    }
+
+    /// Get project-scoped directory based on `cwd`
     pub fn project_dir(&self) -> std::io::Result<PathBuf> {
-        let cwd = std::env::current_dir()?;
+        Ok(self.project_dir_for(&self.cwd()?))
+    }
+
+    /// Get the project-scoped directory for a project rooted at `cwd`
+    pub fn project_dir_for(&self, cwd: &Path) -> PathBuf {
         let hash = cwd.to_string_lossy().replace(['/', '.'], "-");
-        Ok(self.home_claude.join("projects").join(hash))
+        self.home_claude.join("projects").join(hash)
+    }
+
+    /// Get oracle_state.json (average token cost per task type) path for current project
+    pub fn oracle_state_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("oracle_state.json"))
     }
 
     /// Get learned_state.json path for current project
@@ -50,11 +116,86 @@ impl Paths {
         Ok(self.project_dir()?.join("learned_state.json"))
     }
 
+    /// Get keyword_rules.json path for current project
+    pub fn keyword_rules_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("keyword_rules.json"))
+    }
+
+    /// Get synonyms.json (learned query expansions) path for current project
+    pub fn synonyms_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("synonyms.json"))
+    }
+
+    /// Get ingest_manifest.json (session files already ingested) path for current project
+    pub fn ingest_manifest_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("ingest_manifest.json"))
+    }
+
+    /// Get search_index.db (full-text index of repo files) path for current project
+    pub fn search_index_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("search_index.db"))
+    }
+
+    /// Get rename_scan_head (the HEAD commit git renames were last scanned at) path for current project
+    pub fn rename_scan_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("rename_scan_head"))
+    }
+
+    /// Get the directory holding `attentive stash` snapshots for current project
+    pub fn stash_dir(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("stash"))
+    }
+
+    /// Get the directory holding each git branch's parked attention state for current project
+    pub fn branch_state_dir(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("branches"))
+    }
+
+    /// Get cold_index.json path for current project
+    pub fn cold_index_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("cold_index.json"))
+    }
+
+    /// Get content_cache.json (file contents reused across hook runs) path for current project
+    pub fn content_cache_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("content_cache.json"))
+    }
+
+    /// Get repo_map.json (symbols extracted per file, keyed by content hash) path for current project
+    pub fn repo_map_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("repo_map.json"))
+    }
+
+    /// Get xref_index.json (identifiers found per file, keyed by modification time) path for current project
+    pub fn xref_index_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("xref_index.json"))
+    }
+
+    /// Get eval_history.jsonl (history of `attentive eval` runs) path for current project
+    pub fn eval_history_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("eval_history.jsonl"))
+    }
+
     /// Get attn_state.json path for current project
     pub fn attn_state_path(&self) -> std::io::Result<PathBuf> {
         Ok(self.project_dir()?.join("attn_state.json"))
     }
 
+    /// Get injections.json (per-turn injected context sizes) path for current project
+    pub fn injections_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("injections.json"))
+    }
+
+    /// Get active_files.json (files external tools report as active) path for current project
+    pub fn active_files_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("active_files.json"))
+    }
+
+    /// Get token_calibration.json path for current project
+    pub fn token_calibration_path(&self) -> std::io::Result<PathBuf> {
+        Ok(self.project_dir()?.join("token_calibration.json"))
+    }
+
     /// Get session_state.json path for current project
     pub fn session_state_path(&self) -> std::io::Result<PathBuf> {
         Ok(self.project_dir()?.join("session_state.json"))
@@ -67,11 +208,21 @@ impl Default for Paths {
     }
 }
 
-fn detect_git_common_dir() -> Option<PathBuf> {
-    let output = Command::new("git")
-        .args(["rev-parse", "--git-common-dir"])
-        .output()
-        .ok()?;
+/// `new` if `path` is the canonical repo-relative key `old`.
+///
+/// Keys are matched whole: `README.md` -> `docs/README.md` leaves
+/// `crates/foo/README.md` alone. Canonicalize state before remapping it.
+pub fn remap_path(path: &str, old: &str, new: &str) -> Option<String> {
+    (path == old).then(|| new.to_string())
+}
+
+fn detect_git_common_dir(dir: Option<&Path>) -> Option<PathBuf> {
+    let mut command = Command::new("git");
+    command.args(["rev-parse", "--git-common-dir"]);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command.output().ok()?;
 
     if output.status.success() {
         let path_str = String::from_utf8_lossy(&output.stdout).trim().to_string();
@@ -105,6 +256,18 @@ mod tests {
         assert!(turns.ends_with("turns.jsonl"));
     }
 
+    #[test]
+    fn test_in_dir_resolves_project_for_that_dir() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let paths = Paths::with_root(temp.path()).in_dir(temp.path());
+        assert_eq!(paths.cwd().unwrap(), temp.path());
+        assert_eq!(
+            paths.project_dir().unwrap(),
+            paths.project_dir_for(temp.path())
+        );
+        assert_eq!(paths.canonicalizer().unwrap().root(), temp.path());
+    }
+
     #[test]
     fn test_project_dir() {
         let paths = Paths::new().unwrap();
@@ -116,6 +279,18 @@ mod tests {
         assert!(project_dir.ends_with(&expected_hash));
     }
 
+    #[test]
+    fn test_remap_path() {
+        assert_eq!(
+            remap_path("src/a.rs", "src/a.rs", "src/b.rs").unwrap(),
+            "src/b.rs"
+        );
+        // Other files sharing the name or a path suffix keep theirs
+        assert!(remap_path("crates/foo/README.md", "README.md", "docs/README.md").is_none());
+        assert!(remap_path("/repo/src/a.rs", "src/a.rs", "lib/b.rs").is_none());
+        assert!(remap_path("src/other.rs", "src/a.rs", "b.rs").is_none());
+    }
+
     #[test]
     fn test_learned_state_path() {
         let paths = Paths::new().unwrap();
@@ -0,0 +1,426 @@
+//! Filters over turn records, shared by `history` and `report`
+//!
+//! Besides the fixed filters (file, age, waste, session), a query can carry an
+//! expression over any `TurnRecord` field:
+//!
+//! ```text
+//! waste > 0.5 && files_injected ~ "auth"
+//! !(task_type == "review") || extensions.loopbreaker.loop_count >= 3
+//! ```
+//!
+//! `~` tests for a substring; on list fields (`files_injected`, ...) every
+//! operator matches if any element does. Dotted names reach into
+//! `extensions` and `context_sources`; `waste` is short for `waste_ratio`.
+
+use crate::TurnRecord;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use serde_json::Value;
+
+#[derive(Debug, thiserror::Error, PartialEq)]
+pub enum QueryError {
+    #[error("invalid duration or date {0:?} (use e.g. 30m, 12h, 7d, 2w or 2024-05-01)")]
+    Since(String),
+    #[error("unexpected `{found}` at position {at} in filter expression")]
+    Unexpected { found: String, at: usize },
+    #[error("unterminated string in filter expression")]
+    UnterminatedString,
+    #[error("filter expression ends early")]
+    UnexpectedEnd,
+}
+
+/// Which turns to keep; every set filter must match
+#[derive(Debug, Default)]
+pub struct TurnQuery {
+    /// File injected or used in the turn
+    pub file: Option<String>,
+    pub since: Option<DateTime<Utc>>,
+    pub min_waste: Option<f64>,
+    /// Session id or id prefix
+    pub session: Option<String>,
+    pub filter: Option<Expr>,
+    /// Keep only the most recent this many matching turns
+    pub limit: Option<usize>,
+}
+
+impl TurnQuery {
+    pub fn matches(&self, turn: &TurnRecord) -> bool {
+        self.since.is_none_or(|since| turn.timestamp >= since)
+            && self.min_waste.is_none_or(|min| turn.waste_ratio >= min)
+            && self
+                .session
+                .as_ref()
+                .is_none_or(|id| turn.session_id.starts_with(id.as_str()))
+            && self.file.as_ref().is_none_or(|file| {
+                turn.files_injected.contains(file) || turn.files_used.contains(file)
+            })
+            && self.filter.as_ref().is_none_or(|expr| expr.matches(turn))
+    }
+
+    /// Matching turns in their original order, cut to the last `limit`
+    pub fn apply<'a>(&self, turns: &'a [TurnRecord]) -> Vec<&'a TurnRecord> {
+        let mut matched: Vec<&TurnRecord> = turns.iter().filter(|t| self.matches(t)).collect();
+        if let Some(limit) = self.limit {
+            matched.drain(..matched.len().saturating_sub(limit));
+        }
+        matched
+    }
+}
+
+/// A point in time from an age (`30m`, `12h`, `7d`, `2w`) or a date
+/// (`2024-05-01`, or RFC 3339)
+pub fn parse_since(text: &str) -> Result<DateTime<Utc>, QueryError> {
+    let error = || QueryError::Since(text.to_string());
+    let text = text.trim();
+    if let Ok(at) = DateTime::parse_from_rfc3339(text) {
+        return Ok(at.with_timezone(&Utc));
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).ok_or_else(error)?.and_utc());
+    }
+    let unit_at = text.len().checked_sub(1).ok_or_else(error)?;
+    let amount: i64 = text[..unit_at].parse().map_err(|_| error())?;
+    let age = match &text[unit_at..] {
+        "m" => Duration::minutes(amount),
+        "h" => Duration::hours(amount),
+        "d" => Duration::days(amount),
+        "w" => Duration::weeks(amount),
+        _ => return Err(error()),
+    };
+    Ok(crate::now() - age)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Op {
+    Gt,
+    Ge,
+    Lt,
+    Le,
+    Eq,
+    Ne,
+    Contains,
+}
+
+/// A filter expression (see the module docs)
+#[derive(Debug, Clone, PartialEq)]
+pub struct Expr(Node);
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    And(Box<Node>, Box<Node>),
+    Or(Box<Node>, Box<Node>),
+    Not(Box<Node>),
+    Compare { field: String, op: Op, value: Value },
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Ident(String),
+    Literal(Value),
+    Op(Op),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+fn tokenize(text: &str) -> Result<Vec<(usize, Token)>, QueryError> {
+    let chars: Vec<(usize, char)> = text.char_indices().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while let Some(&(at, c)) = chars.get(i) {
+        let next = chars.get(i + 1).map(|&(_, c)| c);
+        let (token, width) = match (c, next) {
+            (c, _) if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            ('&', Some('&')) => (Token::And, 2),
+            ('|', Some('|')) => (Token::Or, 2),
+            ('>', Some('=')) => (Token::Op(Op::Ge), 2),
+            ('<', Some('=')) => (Token::Op(Op::Le), 2),
+            ('=', Some('=')) => (Token::Op(Op::Eq), 2),
+            ('!', Some('=')) => (Token::Op(Op::Ne), 2),
+            ('>', _) => (Token::Op(Op::Gt), 1),
+            ('<', _) => (Token::Op(Op::Lt), 1),
+            ('~', _) => (Token::Op(Op::Contains), 1),
+            ('!', _) => (Token::Not, 1),
+            ('(', _) => (Token::Open, 1),
+            (')', _) => (Token::Close, 1),
+            ('"', _) => {
+                let end = chars[i + 1..]
+                    .iter()
+                    .position(|&(_, c)| c == '"')
+                    .ok_or(QueryError::UnterminatedString)?;
+                let content: String = chars[i + 1..i + 1 + end].iter().map(|&(_, c)| c).collect();
+                (Token::Literal(Value::String(content)), end + 2)
+            }
+            (c, _) if c.is_alphanumeric() || matches!(c, '_' | '.' | '-') => {
+                let width = chars[i..]
+                    .iter()
+                    .take_while(|&&(_, c)| c.is_alphanumeric() || matches!(c, '_' | '.' | '-'))
+                    .count();
+                let word: String = chars[i..i + width].iter().map(|&(_, c)| c).collect();
+                let token = match word.as_str() {
+                    "true" => Token::Literal(Value::Bool(true)),
+                    "false" => Token::Literal(Value::Bool(false)),
+                    _ => match word.parse::<f64>() {
+                        Ok(n) => Token::Literal(serde_json::json!(n)),
+                        Err(_) => Token::Ident(word),
+                    },
+                };
+                (token, width)
+            }
+            (c, _) => {
+                return Err(QueryError::Unexpected {
+                    found: c.to_string(),
+                    at,
+                });
+            }
+        };
+        tokens.push((at, token));
+        i += width;
+    }
+    Ok(tokens)
+}
+
+/// Recursive descent over the token list: `||` binds loosest, then `&&`,
+/// then `!`
+struct Parser<'a> {
+    text: &'a str,
+    tokens: Vec<(usize, Token)>,
+    pos: usize,
+}
+
+impl Parser<'_> {
+    /// Error naming the source word at `at`
+    fn unexpected(&self, at: usize) -> QueryError {
+        QueryError::Unexpected {
+            found: self.text[at..]
+                .split_whitespace()
+                .next()
+                .unwrap_or_default()
+                .to_string(),
+            at,
+        }
+    }
+
+    fn next(&mut self) -> Result<(usize, Token), QueryError> {
+        let token = self
+            .tokens
+            .get(self.pos)
+            .cloned()
+            .ok_or(QueryError::UnexpectedEnd)?;
+        self.pos += 1;
+        Ok(token)
+    }
+
+    fn eat(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.pos).is_some_and(|(_, t)| t == token);
+        self.pos += usize::from(found);
+        found
+    }
+
+    fn or(&mut self) -> Result<Node, QueryError> {
+        let mut node = self.and()?;
+        while self.eat(&Token::Or) {
+            node = Node::Or(Box::new(node), Box::new(self.and()?));
+        }
+        Ok(node)
+    }
+
+    fn and(&mut self) -> Result<Node, QueryError> {
+        let mut node = self.unary()?;
+        while self.eat(&Token::And) {
+            node = Node::And(Box::new(node), Box::new(self.unary()?));
+        }
+        Ok(node)
+    }
+
+    fn unary(&mut self) -> Result<Node, QueryError> {
+        match self.next()? {
+            (_, Token::Not) => Ok(Node::Not(Box::new(self.unary()?))),
+            (_, Token::Open) => {
+                let node = self.or()?;
+                match self.next()? {
+                    (_, Token::Close) => Ok(node),
+                    (at, _) => Err(self.unexpected(at)),
+                }
+            }
+            (_, Token::Ident(field)) => {
+                let op = match self.next()? {
+                    (_, Token::Op(op)) => op,
+                    (at, _) => return Err(self.unexpected(at)),
+                };
+                let value = match self.next()? {
+                    (_, Token::Literal(value)) => value,
+                    // Bare words compare as strings: task_type == review
+                    (_, Token::Ident(word)) => Value::String(word),
+                    (at, _) => return Err(self.unexpected(at)),
+                };
+                Ok(Node::Compare { field, op, value })
+            }
+            (at, _) => Err(self.unexpected(at)),
+        }
+    }
+}
+
+impl Expr {
+    pub fn parse(text: &str) -> Result<Self, QueryError> {
+        let mut parser = Parser {
+            text,
+            tokens: tokenize(text)?,
+            pos: 0,
+        };
+        let node = parser.or()?;
+        match parser.tokens.get(parser.pos) {
+            None => Ok(Self(node)),
+            Some((at, _)) => Err(parser.unexpected(*at)),
+        }
+    }
+
+    pub fn matches(&self, turn: &TurnRecord) -> bool {
+        match serde_json::to_value(turn) {
+            Ok(record) => self.0.eval(&record),
+            Err(_) => false,
+        }
+    }
+}
+
+impl Node {
+    fn eval(&self, record: &Value) -> bool {
+        match self {
+            Node::And(a, b) => a.eval(record) && b.eval(record),
+            Node::Or(a, b) => a.eval(record) || b.eval(record),
+            Node::Not(node) => !node.eval(record),
+            Node::Compare { field, op, value } => {
+                let field = if field == "waste" {
+                    "waste_ratio"
+                } else {
+                    field
+                };
+                let found = field
+                    .split('.')
+                    .try_fold(record, |value, key| value.get(key));
+                match found {
+                    Some(Value::Array(items)) => items.iter().any(|item| compare(item, *op, value)),
+                    Some(found) if !found.is_null() => compare(found, *op, value),
+                    _ => *op == Op::Ne,
+                }
+            }
+        }
+    }
+}
+
+fn compare(found: &Value, op: Op, value: &Value) -> bool {
+    if let (Some(a), Some(b)) = (found.as_f64(), value.as_f64()) {
+        return match op {
+            Op::Gt => a > b,
+            Op::Ge => a >= b,
+            Op::Lt => a < b,
+            Op::Le => a <= b,
+            Op::Eq => a == b,
+            Op::Ne => a != b,
+            Op::Contains => false,
+        };
+    }
+    let text = |v: &Value| match v {
+        Value::String(s) => s.clone(),
+        other => other.to_string(),
+    };
+    let (a, b) = (text(found), text(value));
+    match op {
+        Op::Eq => a == b,
+        Op::Ne => a != b,
+        Op::Contains => a.contains(&b),
+        Op::Gt => a > b,
+        Op::Ge => a >= b,
+        Op::Lt => a < b,
+        Op::Le => a <= b,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(session: &str, waste: f64, files: &[&str]) -> TurnRecord {
+        TurnRecord {
+            turn_id: format!("{}-{}", session, waste),
+            session_id: session.to_string(),
+            project: "/test".to_string(),
+            timestamp: Utc::now(),
+            injected_tokens: 1000,
+            used_tokens: 500,
+            waste_ratio: waste,
+            files_injected: files.iter().map(|f| f.to_string()).collect(),
+            injection_chars: 4000,
+            task_type: Some("bug_fix".to_string()),
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_expression_filters() {
+        let mut auth = turn("s1", 0.7, &["src/auth/login.rs", "README.md"]);
+        auth.extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({"loop_count": 4}),
+        );
+        let other = turn("s2", 0.2, &["src/lib.rs"]);
+
+        let expr = Expr::parse(r#"waste>0.5 && files_injected~"auth""#).unwrap();
+        assert!(expr.matches(&auth));
+        assert!(!expr.matches(&other));
+
+        let expr = Expr::parse("!(task_type == bug_fix) || extensions.loopbreaker.loop_count >= 3")
+            .unwrap();
+        assert!(expr.matches(&auth));
+        assert!(!expr.matches(&other));
+
+        assert!(Expr::parse("turn_tokens != 5").unwrap().matches(&auth));
+        assert_eq!(Expr::parse("waste > "), Err(QueryError::UnexpectedEnd));
+        assert_eq!(
+            Expr::parse("waste > 1 1").unwrap_err().to_string(),
+            "unexpected `1` at position 10 in filter expression"
+        );
+        assert_eq!(
+            Expr::parse(r#"project ~ "x"#),
+            Err(QueryError::UnterminatedString)
+        );
+    }
+
+    #[test]
+    fn test_query_combines_filters_and_limit() {
+        let mut old = turn("s1", 0.9, &["a.rs"]);
+        old.timestamp = Utc::now() - Duration::days(3);
+        let turns = vec![
+            old,
+            turn("s1", 0.8, &["a.rs"]),
+            turn("s1", 0.6, &["a.rs"]),
+            turn("s2", 0.9, &["a.rs"]),
+            turn("s1", 0.1, &["a.rs"]),
+        ];
+        let query = TurnQuery {
+            file: Some("a.rs".to_string()),
+            since: Some(parse_since("1d").unwrap()),
+            min_waste: Some(0.5),
+            session: Some("s1".to_string()),
+            limit: Some(1),
+            ..Default::default()
+        };
+        let matched = query.apply(&turns);
+        assert_eq!(matched.len(), 1);
+        assert_eq!(matched[0].waste_ratio, 0.6);
+        assert_eq!(TurnQuery::default().apply(&turns).len(), 5);
+    }
+
+    #[test]
+    fn test_parse_since() {
+        let day = parse_since("2024-05-01").unwrap();
+        assert_eq!(day.to_rfc3339(), "2024-05-01T00:00:00+00:00");
+        assert!(parse_since("12h").unwrap() < Utc::now());
+        assert!(parse_since("12y").is_err());
+        assert!(parse_since("").is_err());
+    }
+}
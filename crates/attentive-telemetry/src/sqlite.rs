@@ -0,0 +1,125 @@
+//! Shared SQLite connections for the search index and observation store
+//!
+//! Hooks, the state server and the compress worker open the same databases
+//! concurrently. Connections run in WAL mode (readers don't block the writer)
+//! with a busy timeout instead of failing on a held lock, and are kept in a
+//! small pool so their prepared statement caches survive between operations.
+
+use rusqlite::Connection;
+use std::ops::Deref;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// How long a statement waits for a lock held by another connection
+const BUSY_TIMEOUT: Duration = Duration::from_secs(5);
+/// Idle connections kept open per database
+const MAX_IDLE: usize = 4;
+/// Prepared statements cached per connection (`prepare_cached`)
+const STATEMENT_CACHE: usize = 32;
+
+/// Pool of configured connections to one database file
+pub struct SqlitePool {
+    path: PathBuf,
+    idle: Mutex<Vec<Connection>>,
+}
+
+impl SqlitePool {
+    /// Open `path`, switching it to WAL mode; the first connection is kept
+    pub fn open(path: &Path) -> rusqlite::Result<Self> {
+        let pool = Self {
+            path: path.to_path_buf(),
+            idle: Mutex::new(Vec::new()),
+        };
+        let conn = pool.connect()?;
+        pool.release(conn);
+        Ok(pool)
+    }
+
+    /// An idle connection, or a new one when all are in use
+    pub fn get(&self) -> rusqlite::Result<PooledConnection<'_>> {
+        let idle = self.idle.lock().ok().and_then(|mut idle| idle.pop());
+        let conn = match idle {
+            Some(conn) => conn,
+            None => self.connect()?,
+        };
+        Ok(PooledConnection {
+            pool: self,
+            conn: Some(conn),
+        })
+    }
+
+    fn connect(&self) -> rusqlite::Result<Connection> {
+        let conn = Connection::open(&self.path)?;
+        conn.busy_timeout(BUSY_TIMEOUT)?;
+        conn.pragma_update_and_check(None, "journal_mode", "WAL", |row| row.get::<_, String>(0))?;
+        conn.pragma_update(None, "synchronous", "NORMAL")?;
+        conn.set_prepared_statement_cache_capacity(STATEMENT_CACHE);
+        Ok(conn)
+    }
+
+    fn release(&self, conn: Connection) {
+        if let Ok(mut idle) = self.idle.lock()
+            && idle.len() < MAX_IDLE
+        {
+            idle.push(conn);
+        }
+    }
+}
+
+/// A connection borrowed from a `SqlitePool`, returned to it on drop
+pub struct PooledConnection<'a> {
+    pool: &'a SqlitePool,
+    conn: Option<Connection>,
+}
+
+impl Deref for PooledConnection<'_> {
+    type Target = Connection;
+
+    fn deref(&self) -> &Connection {
+        self.conn
+            .as_ref()
+            .expect("connection is only taken on drop")
+    }
+}
+
+impl Drop for PooledConnection<'_> {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            self.pool.release(conn);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pool_reuses_wal_connections() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let pool = SqlitePool::open(&dir.path().join("test.db")).unwrap();
+        {
+            let conn = pool.get().unwrap();
+            let mode: String = conn
+                .query_row("PRAGMA journal_mode", [], |row| row.get(0))
+                .unwrap();
+            assert_eq!(mode, "wal");
+            conn.execute("CREATE TABLE t (x INTEGER)", []).unwrap();
+
+            // A second borrower gets its own connection while the first is out
+            let other = pool.get().unwrap();
+            other.execute("INSERT INTO t VALUES (1)", []).unwrap();
+        }
+        assert_eq!(pool.idle.lock().unwrap().len(), 2);
+
+        let conn = pool.get().unwrap();
+        let count: i64 = conn
+            .prepare_cached("SELECT COUNT(*) FROM t")
+            .unwrap()
+            .query_row([], |row| row.get(0))
+            .unwrap();
+        assert_eq!(count, 1);
+        assert_eq!(pool.idle.lock().unwrap().len(), 1);
+    }
+}
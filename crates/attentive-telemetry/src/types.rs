@@ -2,9 +2,10 @@
 
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 
 /// A turn record capturing context routing performance
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct TurnRecord {
     pub turn_id: String,
     pub session_id: String,
@@ -23,6 +24,93 @@ pub struct TurnRecord {
     pub injection_chars: usize,
     #[serde(default)]
     pub context_confidence: Option<f64>,
+    /// Structured per-turn signals contributed by plugins, keyed by plugin name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub extensions: BTreeMap<String, serde_json::Value>,
+    /// Calibrated tokens injected per context source (hot, warm, memory,
+    /// plugin:<name>, dashboard, ...)
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub context_sources: BTreeMap<String, usize>,
+    /// HOT/WARM sections truncated or dropped to fit the context budget
+    #[serde(default)]
+    pub budget_overflow: usize,
+    /// Secrets redacted from the injected context
+    #[serde(default)]
+    pub redactions: usize,
+    /// Estimated tokens dropped from the context as repeats of other sections
+    #[serde(default)]
+    pub deduped_tokens: usize,
+    /// Task type the prompt was classified as (bug_fix, feature, ...)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub task_type: Option<String>,
+    /// Tokens the turn spent, from transcript usage (see `last_turn_usage`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub turn_tokens: Option<usize>,
+    /// Files the prompt hook listed as likely needed in the next turns
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_predicted: Vec<String>,
+    /// Injected files the assistant's reply mentioned by path or name
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_referenced: Vec<String>,
+    /// Injected files boosted because they were open or focused in the editor
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub files_from_editor: Vec<String>,
+    /// Budget usage of each plugin with a `plugin_budgets` entry that added
+    /// context, keyed by plugin name
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub plugin_budgets: BTreeMap<String, PluginBudgetUsage>,
+    /// Injected files whose pinned or demoted rule the learner contradicted
+    /// (see `attentive config conflicts`)
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub config_conflicts: Vec<String>,
+}
+
+/// How one plugin's prompt-time context fared against its token budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PluginBudgetUsage {
+    /// Configured budget, in estimated tokens
+    pub budget: usize,
+    /// Estimated tokens the plugin produced
+    pub requested: usize,
+    /// Estimated tokens injected after truncation
+    pub used: usize,
+}
+
+impl PluginBudgetUsage {
+    /// Whether the output was cut down or dropped to fit
+    pub fn truncated(&self) -> bool {
+        self.used < self.requested
+    }
+}
+
+/// End-to-end prompt hook latency for one `benchmark --hook` scenario
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HookBenchRecord {
+    pub timestamp: DateTime<Utc>,
+    /// attentive version that was measured
+    pub version: String,
+    /// Whether the measured binary was a debug build (not comparable to release)
+    pub debug_build: bool,
+    pub scenario: String,
+    pub repo_files: usize,
+    pub state_entries: usize,
+    pub p50_ms: f64,
+    pub p95_ms: f64,
+}
+
+/// Scores of one `attentive eval` run over a labeled suite
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct EvalRecord {
+    pub timestamp: DateTime<Utc>,
+    /// attentive version that routed the cases
+    pub version: String,
+    pub suite: String,
+    pub cases: usize,
+    pub k: usize,
+    /// Averaged over cases
+    pub precision_at_k: f64,
+    pub recall: f64,
+    pub mrr: f64,
 }
 
 #[cfg(test)]
@@ -39,11 +127,7 @@ mod tests {
             injected_tokens: 1000,
             used_tokens: 600,
             waste_ratio: 0.4,
-            files_injected: vec![],
-            files_used: vec![],
-            was_notification: false,
-            injection_chars: 0,
-            context_confidence: None,
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&record).unwrap();
@@ -65,9 +149,9 @@ mod tests {
             waste_ratio: 0.4,
             files_injected: vec!["router.rs".to_string(), "config.rs".to_string()],
             files_used: vec!["router.rs".to_string()],
-            was_notification: false,
             injection_chars: 5000,
             context_confidence: Some(0.75),
+            ..Default::default()
         };
 
         let json = serde_json::to_string(&record).unwrap();
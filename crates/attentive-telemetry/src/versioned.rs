@@ -0,0 +1,232 @@
+//! Schema-versioned envelope for persisted state
+//!
+//! State files are written as `{"version": N, "data": ...}`. Files without the
+//! envelope predate it and are read as version 0. Older versions are upgraded
+//! one step at a time through [`Versioned::migrate`]; versions newer than this
+//! build understands are refused on read and never overwritten.
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::path::Path;
+
+use crate::io::atomic_write;
+
+/// A persisted state type with a schema version
+pub trait Versioned: Serialize + DeserializeOwned {
+    /// Schema version written by this build
+    const VERSION: u32;
+
+    /// Name used in errors, e.g. "attention state"
+    const KIND: &'static str;
+
+    /// Upgrade `data` from schema version `from` to `from + 1`.
+    ///
+    /// Version 0 (no envelope) has the same shape as version 1, so the default
+    /// only passes it through; types override this when they bump `VERSION`.
+    fn migrate(from: u32, data: Value) -> Result<Value, StateError> {
+        match from {
+            0 => Ok(data),
+            _ => Err(StateError::NoMigration {
+                kind: Self::KIND,
+                from,
+            }),
+        }
+    }
+}
+
+#[derive(Debug, thiserror::Error)]
+pub enum StateError {
+    #[error(
+        "{kind} has schema version {found}, newer than the {supported} this build supports; upgrade attentive"
+    )]
+    FutureVersion {
+        kind: &'static str,
+        found: u32,
+        supported: u32,
+    },
+    #[error("no migration for {kind} from schema version {from}")]
+    NoMigration { kind: &'static str, from: u32 },
+    #[error(transparent)]
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+}
+
+/// Split a stored document into its schema version and payload
+fn unwrap_envelope(value: Value) -> (u32, Value) {
+    if let Value::Object(mut map) = value {
+        let version = map.get("version").and_then(Value::as_u64);
+        if let Some(version) = version
+            && map.len() == 2
+            && let Some(data) = map.remove("data")
+        {
+            return (version as u32, data);
+        }
+        return (0, Value::Object(map));
+    }
+    (0, value)
+}
+
+/// Schema version of a stored document (0 when it has no envelope)
+pub fn stored_version(json: &str) -> Result<u32, StateError> {
+    Ok(unwrap_envelope(serde_json::from_str(json)?).0)
+}
+
+/// Parse a stored document, migrating it to the current schema
+pub fn from_versioned_str<T: Versioned>(json: &str) -> Result<T, StateError> {
+    let (version, mut data) = unwrap_envelope(serde_json::from_str(json)?);
+    if version > T::VERSION {
+        return Err(StateError::FutureVersion {
+            kind: T::KIND,
+            found: version,
+            supported: T::VERSION,
+        });
+    }
+    for from in version..T::VERSION {
+        data = T::migrate(from, data)?;
+    }
+    Ok(serde_json::from_value(data)?)
+}
+
+fn envelope<T: Versioned>(value: &T) -> Value {
+    serde_json::json!({
+        "version": T::VERSION,
+        "data": value,
+    })
+}
+
+/// Serialize `value` inside a current-version envelope
+pub fn to_versioned_string<T: Versioned>(value: &T) -> Result<String, StateError> {
+    Ok(serde_json::to_string_pretty(&envelope(value))?)
+}
+
+/// Read and migrate the state stored at `path`
+pub fn read_versioned<T: Versioned>(path: &Path) -> Result<T, StateError> {
+    from_versioned_str(&std::fs::read_to_string(path)?)
+}
+
+/// Write `value` to `path`, refusing to replace a file from a newer schema
+pub fn write_versioned<T: Versioned>(path: &Path, value: &T) -> Result<(), StateError> {
+    refuse_future_version::<T>(path)?;
+    atomic_write(path, to_versioned_string(value)?.as_bytes())?;
+    Ok(())
+}
+
+/// `write_versioned` without indentation, for large state nobody reads by hand
+pub fn write_versioned_compact<T: Versioned>(path: &Path, value: &T) -> Result<(), StateError> {
+    refuse_future_version::<T>(path)?;
+    atomic_write(path, &serde_json::to_vec(&envelope(value))?)?;
+    Ok(())
+}
+
+fn refuse_future_version<T: Versioned>(path: &Path) -> Result<(), StateError> {
+    if let Ok(existing) = std::fs::read_to_string(path)
+        && let Ok(found) = stored_version(&existing)
+        && found > T::VERSION
+    {
+        return Err(StateError::FutureVersion {
+            kind: T::KIND,
+            found,
+            supported: T::VERSION,
+        });
+    }
+    Ok(())
+}
+
+/// Upgrade the file at `path` in place. Returns the version it was migrated
+/// from, or `None` when it was missing or already current.
+pub fn migrate_file<T: Versioned>(path: &Path) -> Result<Option<u32>, StateError> {
+    let Ok(json) = std::fs::read_to_string(path) else {
+        return Ok(None);
+    };
+    let found = stored_version(&json)?;
+    if found == T::VERSION {
+        return Ok(None);
+    }
+    let value: T = from_versioned_str(&json)?;
+    write_versioned(path, &value)?;
+    Ok(Some(found))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde::Deserialize;
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    struct Counter {
+        count: u32,
+        #[serde(default)]
+        label: String,
+    }
+
+    impl Versioned for Counter {
+        const VERSION: u32 = 2;
+        const KIND: &'static str = "counter";
+
+        fn migrate(from: u32, mut data: Value) -> Result<Value, StateError> {
+            match from {
+                0 => Ok(data),
+                // v2 renamed `n` to `count`
+                1 => {
+                    if let Some(n) = data.as_object_mut().and_then(|m| m.remove("n")) {
+                        data["count"] = n;
+                    }
+                    Ok(data)
+                }
+                _ => Err(StateError::NoMigration {
+                    kind: Self::KIND,
+                    from,
+                }),
+            }
+        }
+    }
+
+    #[test]
+    fn test_reads_legacy_and_migrates() {
+        let legacy: Counter = from_versioned_str(r#"{"count": 3}"#).unwrap();
+        assert_eq!(legacy.count, 3);
+
+        let v1: Counter = from_versioned_str(r#"{"version": 1, "data": {"n": 7}}"#).unwrap();
+        assert_eq!(v1.count, 7);
+
+        let json = to_versioned_string(&v1).unwrap();
+        assert_eq!(stored_version(&json).unwrap(), 2);
+        assert_eq!(from_versioned_str::<Counter>(&json).unwrap(), v1);
+    }
+
+    #[test]
+    fn test_refuses_future_versions() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter.json");
+        let future = r#"{"version": 9, "data": {"count": 1}}"#;
+        std::fs::write(&path, future).unwrap();
+
+        assert!(matches!(
+            read_versioned::<Counter>(&path),
+            Err(StateError::FutureVersion { found: 9, .. })
+        ));
+        let counter = Counter {
+            count: 2,
+            label: String::new(),
+        };
+        assert!(write_versioned(&path, &counter).is_err());
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), future);
+    }
+
+    #[test]
+    fn test_migrate_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("counter.json");
+        std::fs::write(&path, r#"{"version": 1, "data": {"n": 4}}"#).unwrap();
+
+        assert_eq!(migrate_file::<Counter>(&path).unwrap(), Some(1));
+        assert_eq!(migrate_file::<Counter>(&path).unwrap(), None);
+        assert_eq!(read_versioned::<Counter>(&path).unwrap().count, 4);
+        assert_eq!(
+            migrate_file::<Counter>(&dir.path().join("missing.json")).unwrap(),
+            None
+        );
+    }
+}
@@ -1,17 +1,12 @@
 use attentive_core::{AttentionState, Config, Router};
 use attentive_plugins::{BurnRatePlugin, LoopBreakerPlugin, PluginRegistry, SessionState};
 use criterion::{Criterion, criterion_group, criterion_main};
-use std::collections::HashMap;
 use std::hint::black_box;
 
 fn bench_full_pipeline_20_files(c: &mut Criterion) {
     let config = Config::default();
     let router = Router::new(config);
-    let mut state = AttentionState {
-        scores: HashMap::new(),
-        consecutive_turns: HashMap::new(),
-        turn_count: 0,
-    };
+    let mut state = AttentionState::new();
 
     for i in 0..20 {
         state.scores.insert(format!("file{}.rs", i), 0.5);
@@ -1,16 +1,11 @@
 use attentive_core::{AttentionState, Config, Router};
 use criterion::{Criterion, criterion_group, criterion_main};
-use std::collections::HashMap;
 use std::hint::black_box;
 
 fn bench_router_update_10_files(c: &mut Criterion) {
     let config = Config::default();
     let router = Router::new(config);
-    let mut state = AttentionState {
-        scores: HashMap::new(),
-        consecutive_turns: HashMap::new(),
-        turn_count: 0,
-    };
+    let mut state = AttentionState::new();
 
     for i in 0..10 {
         state.scores.insert(format!("file{}.rs", i), 0.5);
@@ -35,11 +30,7 @@ fn bench_router_co_activation_2hop(c: &mut Criterion) {
         .insert("b.rs".to_string(), vec!["d.rs".to_string()]);
 
     let router = Router::new(config);
-    let mut state = AttentionState {
-        scores: HashMap::new(),
-        consecutive_turns: HashMap::new(),
-        turn_count: 0,
-    };
+    let mut state = AttentionState::new();
 
     for f in ["a.rs", "b.rs", "c.rs", "d.rs"] {
         state.scores.insert(f.to_string(), 0.5);
@@ -1,4 +1,8 @@
+use crate::error::ErrorKind;
+use attentive_core::FusionStrategy;
+use clap::builder::PossibleValuesParser;
 use clap::{Parser, Subcommand};
+use clap_complete::Shell;
 
 #[derive(Parser)]
 #[command(name = "attentive")]
@@ -11,14 +15,32 @@ pub struct Cli {
 
 #[derive(Subcommand)]
 pub enum Commands {
-    /// Initialize attentive in current project
-    Init,
+    /// Install attentive hooks in ~/.claude/settings.json
+    Init {
+        /// Bootstrap the current repo instead: .attentive.json with suggested
+        /// pins and the initial search index
+        #[arg(long)]
+        project: bool,
+
+        /// With --project, also ingest this project's Claude Code transcripts
+        #[arg(long, requires = "project")]
+        ingest: bool,
+    },
 
     /// Ingest Claude Code sessions to bootstrap learner
     Ingest {
-        /// Path to session JSONL (auto-discovers if omitted)
+        /// Path to a session transcript: Claude Code, Cursor, Aider or OpenAI-style
+        /// (auto-discovers Claude Code sessions if omitted)
         #[arg(short, long)]
         file: Option<String>,
+
+        /// Re-ingest sessions already ingested (their turns are counted again)
+        #[arg(long)]
+        force: bool,
+
+        /// Discard the learned state and rebuild it from every session
+        #[arg(long)]
+        reset: bool,
     },
 
     /// Output statusline JSON (tiers, hit rate)
@@ -26,11 +48,27 @@ pub enum Commands {
         /// Session ID for live hit rate from transcript
         #[arg(long)]
         session: Option<String>,
+        /// Per-file table of HOT/WARM files: language, symbols, tokens, PageRank, streak
+        #[arg(long)]
+        files: bool,
+        /// Print the current routing decision as versioned JSON for external tools
+        #[arg(long, conflicts_with = "files")]
+        export_context: bool,
     },
 
     /// Print version information
     Version,
 
+    /// Dry-run the prompt pipeline and show the context that would be injected
+    Preview {
+        /// Prompt to route
+        prompt: String,
+    },
+
+    /// Type prompts and see routing, context, plugin messages and per-phase
+    /// scores without saving state
+    Repl,
+
     /// Hook: Process user prompt (stdin/stdout JSON)
     #[command(name = "hook:user-prompt-submit")]
     HookUserPromptSubmit,
@@ -43,18 +81,81 @@ pub enum Commands {
     #[command(name = "hook:stop")]
     HookStop,
 
+    /// Hook: Check a tool call before it runs (stdin/stdout JSON)
+    #[command(name = "hook:pre-tool-use")]
+    HookPreToolUse,
+
+    /// Hook: Save the working set before Claude Code compacts the conversation
+    #[command(name = "hook:pre-compact")]
+    HookPreCompact,
+
     // Stubs for future implementation
     /// Generate token usage report
-    Report,
+    Report {
+        /// Write anonymized turn records (no prompts or absolute paths) for team pooling
+        #[arg(long, value_name = "FILE")]
+        export: Option<String>,
+        /// Aggregate exported records from every .jsonl file in a directory
+        #[arg(long, value_name = "DIR", conflicts_with = "export")]
+        merge: Option<String>,
+        /// Narrative report for one session (id or unique id prefix)
+        #[arg(long, value_name = "ID", conflicts_with_all = ["export", "merge"])]
+        session: Option<String>,
+        /// Only turns since an age (30m, 12h, 7d, 2w) or date (2024-05-01)
+        #[arg(long, value_name = "WHEN", conflicts_with_all = ["merge", "session"])]
+        since: Option<String>,
+        /// Only turns matching an expression (see `history --where`)
+        #[arg(long = "where", value_name = "EXPR", conflicts_with_all = ["merge", "session"])]
+        filter: Option<String>,
+    },
 
     /// Run diagnostic checks
     Diagnostic,
 
     /// Run performance benchmarks
-    Benchmark,
+    #[command(alias = "bench")]
+    Benchmark {
+        /// Replay recorded prompts and report precision/recall of injected files.
+        /// Takes a YAML suite file; without one, replays ingested sessions.
+        #[arg(long, value_name = "FILE")]
+        suite: Option<Option<String>>,
+        /// Print the suite report as JSON (for before/after comparisons)
+        #[arg(long, requires = "suite")]
+        json: bool,
+        /// Also replay the suite through a simple baseline and compare hit
+        /// rate and token cost with the full pipeline
+        #[arg(long, requires = "suite", value_parser = ["bm25", "recent-files", "none"])]
+        against: Option<String>,
+        /// Time the prompt hook end to end across repo and state sizes and flag
+        /// regressions against earlier runs
+        #[arg(long, conflicts_with = "suite")]
+        hook: bool,
+        /// Runs per scenario in --hook mode
+        #[arg(long, default_value_t = 10, requires = "hook")]
+        iterations: usize,
+        /// Fail when a scenario's median is this many percent slower than before
+        #[arg(long, default_value_t = 20.0, requires = "hook")]
+        threshold: f64,
+    },
 
-    /// Compress observations
-    Compress,
+    /// Score routing against labeled prompts (precision@k, recall, MRR) and
+    /// compare with earlier runs
+    Eval {
+        /// YAML suite of {prompt, expected} cases (default: .attentive-eval.yaml)
+        file: Option<String>,
+        /// Rank cutoff for precision
+        #[arg(long, short, default_value_t = 5)]
+        k: usize,
+        /// Print the scores as JSON
+        #[arg(long)]
+        json: bool,
+    },
+
+    /// Summarize the observation store, or query it
+    Compress {
+        #[command(subcommand)]
+        action: Option<CompressAction>,
+    },
 
     /// Analyze dependency graph
     Graph,
@@ -64,6 +165,24 @@ pub enum Commands {
         /// Show statistics summary
         #[arg(long)]
         stats: bool,
+        /// Only turns that injected or used this file
+        #[arg(long)]
+        file: Option<String>,
+        /// Only turns since an age (30m, 12h, 7d, 2w) or date (2024-05-01)
+        #[arg(long, value_name = "WHEN")]
+        since: Option<String>,
+        /// Only turns wasting at least this fraction of injected tokens
+        #[arg(long, value_name = "RATIO")]
+        min_waste: Option<f64>,
+        /// Only turns of one session (id or id prefix)
+        #[arg(long, value_name = "ID")]
+        session: Option<String>,
+        /// Only turns matching an expression, e.g. 'waste>0.5 && files_injected~"auth"'
+        #[arg(long = "where", value_name = "EXPR")]
+        filter: Option<String>,
+        /// Most recent matching turns to show (default 20; stats use all unless set)
+        #[arg(long)]
+        limit: Option<usize>,
     },
 
     /// Manage plugins
@@ -71,6 +190,183 @@ pub enum Commands {
         #[command(subcommand)]
         action: Option<PluginAction>,
     },
+
+    /// Transfer learned state across file renames (detects from git if no paths given)
+    Remap {
+        /// Old path
+        #[arg(requires = "to")]
+        from: Option<String>,
+        /// New path
+        to: Option<String>,
+    },
+
+    /// Upgrade persisted state files to the current schema version
+    Migrate,
+
+    /// Drop corrupt lines from turns.jsonl and hook_bench.jsonl
+    Repair,
+
+    /// Regenerate the learner, keyword rules, synonyms, observation search and
+    /// search index from stored transcripts and the repo
+    Rebuild {
+        /// Report what would be rebuilt without writing anything
+        #[arg(long)]
+        dry_run: bool,
+    },
+
+    /// Inspect and edit learned state
+    Learn {
+        #[command(subcommand)]
+        action: LearnAction,
+    },
+
+    /// Store a note as project memory, recalled when prompts or HOT files match it
+    Remember {
+        /// The fact to remember
+        note: String,
+        /// Files the note is about
+        #[arg(long, num_args = 1..)]
+        files: Vec<String>,
+    },
+
+    /// Search the project index, optionally filtered by type, path and age
+    Search {
+        /// Search terms
+        query: String,
+        /// Document type (code, doc) or file extension (md, rs); repeatable
+        #[arg(long = "type")]
+        types: Vec<String>,
+        /// Path glob (e.g. 'src/**'); repeatable
+        #[arg(long = "path")]
+        paths: Vec<String>,
+        /// Only files modified within this long, e.g. 12h, 7d, 2w
+        #[arg(long)]
+        since: Option<String>,
+        /// Most results to list
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        /// How BM25 and embedding rankings are combined (default: search_fusion in attentive.json)
+        #[arg(long, value_parser = PossibleValuesParser::new(FusionStrategy::NAMES))]
+        fusion: Option<String>,
+    },
+
+    /// Suggest demotions, pins and co-activation edges from how injected files were used
+    Tune {
+        /// Turns of evidence a file needs before anything is suggested for it
+        #[arg(long, default_value_t = 10)]
+        min_turns: usize,
+        /// Apply every suggestion without asking
+        #[arg(long, short)]
+        yes: bool,
+    },
+
+    /// Find files that define or reference a symbol
+    Xref {
+        /// Identifier to look up
+        symbol: String,
+    },
+
+    /// Validate attentive.json or print its JSON Schema
+    Config {
+        #[command(subcommand)]
+        action: ConfigAction,
+    },
+
+    /// Keep learned and attention state in memory and serve hooks over a unix socket
+    Serve,
+
+    /// List config profiles and choose the one in use
+    Profile {
+        #[command(subcommand)]
+        action: ProfileAction,
+    },
+
+    /// Save and restore attention state when switching between tasks
+    Stash {
+        #[command(subcommand)]
+        action: StashAction,
+    },
+
+    /// Push or pull learned state to the backend configured under `sync`
+    Sync {
+        #[command(subcommand)]
+        action: SyncAction,
+    },
+
+    /// Export attention data for visualization
+    Export {
+        #[command(subcommand)]
+        action: ExportAction,
+    },
+
+    /// Print a shell completion script
+    Completions {
+        /// Shell to generate completions for
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+impl Commands {
+    /// Family a failure of this command is filed under, unless the error
+    /// itself says otherwise
+    pub fn error_kind(&self) -> ErrorKind {
+        match self {
+            Commands::HookUserPromptSubmit
+            | Commands::HookSessionStart
+            | Commands::HookStop
+            | Commands::HookPreToolUse
+            | Commands::HookPreCompact => ErrorKind::Hook,
+            Commands::Config { .. }
+            | Commands::Profile { .. }
+            | Commands::Plugins { .. }
+            | Commands::Learn {
+                action: LearnAction::Coactivate { .. },
+            } => ErrorKind::Config,
+            Commands::Search { .. } | Commands::Xref { .. } | Commands::Graph => ErrorKind::Index,
+            Commands::Serve | Commands::Compress { action: None } | Commands::Sync { .. } => {
+                ErrorKind::Network
+            }
+            Commands::Init { .. }
+            | Commands::Ingest { .. }
+            | Commands::Status { .. }
+            | Commands::Preview { .. }
+            | Commands::Repl
+            | Commands::Report { .. }
+            | Commands::History { .. }
+            | Commands::Compress { .. }
+            | Commands::Remap { .. }
+            | Commands::Migrate
+            | Commands::Repair
+            | Commands::Rebuild { .. }
+            | Commands::Learn { .. }
+            | Commands::Remember { .. }
+            | Commands::Tune { .. }
+            | Commands::Stash { .. }
+            | Commands::Export { .. } => ErrorKind::State,
+            Commands::Version
+            | Commands::Diagnostic
+            | Commands::Benchmark { .. }
+            | Commands::Eval { .. }
+            | Commands::Completions { .. } => ErrorKind::Other,
+        }
+    }
+
+    /// Whether `--json` was passed, so errors are reported as JSON too
+    pub fn json(&self) -> bool {
+        match self {
+            Commands::Benchmark { json, .. } | Commands::Eval { json, .. } => *json,
+            Commands::Compress {
+                action:
+                    Some(
+                        CompressAction::Query { json, .. }
+                        | CompressAction::Show { json, .. }
+                        | CompressAction::Timeline { json, .. },
+                    ),
+            } => *json,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Subcommand)]
@@ -83,6 +379,144 @@ pub enum PluginAction {
     Disable { name: String },
 }
 
+#[derive(Subcommand)]
+pub enum CompressAction {
+    /// Full-text search over observation summaries, facts and concepts
+    Query {
+        text: String,
+        /// Most observations to list
+        #[arg(long, default_value_t = 10)]
+        limit: usize,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print one observation in full
+    Show {
+        id: String,
+        #[arg(long)]
+        json: bool,
+    },
+    /// Observations recorded within N days of the given one
+    Timeline {
+        id: String,
+        #[arg(long, default_value_t = 1)]
+        days: usize,
+        #[arg(long)]
+        json: bool,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum ConfigAction {
+    /// Check attentive.json for invalid values and unknown keys
+    Check,
+    /// Print the JSON Schema for attentive.json
+    Schema,
+    /// Open attentive.json in $EDITOR and validate it before saving
+    Edit,
+    /// Show the effective decay rate rules and the tracked files each covers
+    ShowDecay,
+    /// Show the built-in demotion and scan exclusion rules and the tracked
+    /// files each demotion covers
+    ShowDefaults,
+    /// List files whose pinned or demoted rule the learner contradicts, with
+    /// the evidence and a suggested fix
+    Conflicts,
+}
+
+#[derive(Subcommand)]
+pub enum ProfileAction {
+    /// Apply a profile (focused, exploratory, economy or one from attentive.json)
+    Use { name: String },
+    /// List profiles and their settings
+    List,
+    /// Go back to the settings in attentive.json without a profile
+    Clear,
+}
+
+#[derive(Subcommand)]
+pub enum ExportAction {
+    /// Treemap of files by injections, colored by how often injected files were used
+    Heatmap {
+        /// Self-contained HTML page or the raw per-file numbers
+        #[arg(long, default_value = "html", value_parser = ["html", "json"])]
+        format: String,
+        /// Only count turns from the last N days
+        #[arg(long)]
+        days: Option<i64>,
+        /// Write to this file instead of stdout
+        #[arg(short, long)]
+        output: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum StashAction {
+    /// Snapshot the current attention state under a name
+    Save {
+        name: String,
+        /// Also snapshot plugin state (loop history, burn rate, ...)
+        #[arg(long)]
+        plugins: bool,
+    },
+    /// Restore a snapshot, replacing the current attention state, and delete it
+    Pop { name: String },
+    /// List saved snapshots
+    List,
+}
+
+#[derive(Subcommand)]
+pub enum SyncAction {
+    /// Merge this project's learner, attention state and observations into
+    /// the remote's
+    Push {
+        /// Overwrite the remote's learner and attention state instead of
+        /// merging
+        #[arg(long)]
+        force: bool,
+        /// Name the project goes under on the remote (default: from its origin URL)
+        #[arg(long)]
+        project: Option<String>,
+    },
+    /// Merge the remote's learner, attention state and observations into
+    /// this project's
+    Pull {
+        /// Replace the local learner and attention state instead of merging
+        #[arg(long)]
+        force: bool,
+        /// Name the project goes under on the remote (default: from its origin URL)
+        #[arg(long)]
+        project: Option<String>,
+    },
+}
+
+#[derive(Subcommand)]
+pub enum LearnAction {
+    /// Show or edit mined keyword→file rules
+    Rules {
+        #[command(subcommand)]
+        action: Option<RuleAction>,
+    },
+    /// Add learned co-activation pairs (as listed by `report`) to .attentive.json
+    Coactivate {
+        /// Pairs of files that load together, e.g. src/router.rs=src/config.rs
+        #[arg(required = true, value_name = "FILE=FILE")]
+        pairs: Vec<String>,
+    },
+    /// List learned synonyms that expand prompts in search and routing
+    Synonyms,
+}
+
+#[derive(Subcommand)]
+pub enum RuleAction {
+    /// List all keyword rules
+    List,
+    /// Add or replace a rule
+    Set { keyword: String, file: String },
+    /// Remove a rule
+    Remove { keyword: String },
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -98,23 +532,205 @@ mod tests {
     fn test_cli_parse_init() {
         let cli = Cli::try_parse_from(["attentive", "init"]);
         assert!(cli.is_ok());
-        assert!(matches!(cli.unwrap().command, Commands::Init));
+        assert!(matches!(
+            cli.unwrap().command,
+            Commands::Init {
+                project: false,
+                ingest: false
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_init_project() {
+        let cli = Cli::try_parse_from(["attentive", "init", "--project", "--ingest"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Init {
+                project: true,
+                ingest: true
+            }
+        ));
+        assert!(Cli::try_parse_from(["attentive", "init", "--ingest"]).is_err());
     }
 
     #[test]
     fn test_cli_parse_ingest() {
         let cli = Cli::try_parse_from(["attentive", "ingest", "--file", "test.jsonl"]);
         assert!(cli.is_ok());
-        if let Commands::Ingest { file } = cli.unwrap().command {
+        if let Commands::Ingest { file, force, reset } = cli.unwrap().command {
             assert_eq!(file, Some("test.jsonl".to_string()));
+            assert!(!force && !reset);
         } else {
             panic!("Expected Ingest command");
         }
+
+        let cli = Cli::try_parse_from(["attentive", "ingest", "--force", "--reset"]);
+        assert!(matches!(
+            cli.unwrap().command,
+            Commands::Ingest {
+                file: None,
+                force: true,
+                reset: true
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_preview() {
+        let cli = Cli::try_parse_from(["attentive", "preview", "fix the router"]);
+        if let Commands::Preview { prompt } = cli.unwrap().command {
+            assert_eq!(prompt, "fix the router");
+        } else {
+            panic!("Expected Preview command");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_learn_rules_set() {
+        let cli = Cli::try_parse_from(["attentive", "learn", "rules", "set", "bm25", "bm25.rs"]);
+        if let Commands::Learn {
+            action:
+                LearnAction::Rules {
+                    action: Some(RuleAction::Set { keyword, file }),
+                },
+        } = cli.unwrap().command
+        {
+            assert_eq!(keyword, "bm25");
+            assert_eq!(file, "bm25.rs");
+        } else {
+            panic!("Expected Learn Rules Set command");
+        }
+    }
+
+    #[test]
+    fn test_cli_parse_bench_suite() {
+        let cli = Cli::try_parse_from(["attentive", "bench", "--suite"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Benchmark {
+                suite: Some(None),
+                json: false,
+                hook: false,
+                ..
+            }
+        ));
+
+        let cli = Cli::try_parse_from(["attentive", "benchmark", "--suite", "s.yaml", "--json"]);
+        if let Commands::Benchmark { suite, json, .. } = cli.unwrap().command {
+            assert_eq!(suite, Some(Some("s.yaml".to_string())));
+            assert!(json);
+        } else {
+            panic!("Expected Benchmark command");
+        }
+
+        let cli = Cli::try_parse_from(["attentive", "benchmark", "--hook", "--threshold", "10"]);
+        if let Commands::Benchmark {
+            hook,
+            iterations,
+            threshold,
+            ..
+        } = cli.unwrap().command
+        {
+            assert!(hook);
+            assert_eq!(iterations, 10);
+            assert_eq!(threshold, 10.0);
+        } else {
+            panic!("Expected Benchmark command");
+        }
+        assert!(Cli::try_parse_from(["attentive", "benchmark", "--hook", "--suite"]).is_err());
+
+        let cli = Cli::try_parse_from(["attentive", "benchmark", "--suite", "--against", "bm25"]);
+        if let Commands::Benchmark { against, .. } = cli.unwrap().command {
+            assert_eq!(against.as_deref(), Some("bm25"));
+        } else {
+            panic!("Expected Benchmark command");
+        }
+        assert!(Cli::try_parse_from(["attentive", "benchmark", "--against", "none"]).is_err());
+        assert!(
+            Cli::try_parse_from(["attentive", "benchmark", "--suite", "--against", "grep"])
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_eval() {
+        let cli = Cli::try_parse_from(["attentive", "eval", "-k", "3", "cases.yaml"]).unwrap();
+        if let Commands::Eval { file, k, json } = cli.command {
+            assert_eq!(file.as_deref(), Some("cases.yaml"));
+            assert_eq!(k, 3);
+            assert!(!json);
+        } else {
+            panic!("Expected Eval command");
+        }
+    }
+
+    #[test]
+    fn test_cli_error_kind_and_json() {
+        let parse = |args: &[&str]| Cli::try_parse_from(args).unwrap().command;
+        let hook = parse(&["attentive", "hook:stop"]);
+        assert_eq!(hook.error_kind(), ErrorKind::Hook);
+        assert!(!hook.json());
+        assert_eq!(
+            parse(&["attentive", "search", "router"]).error_kind(),
+            ErrorKind::Index
+        );
+        let show = parse(&["attentive", "compress", "show", "obs-1", "--json"]);
+        assert_eq!(show.error_kind(), ErrorKind::State);
+        assert!(show.json());
+        assert_eq!(
+            parse(&["attentive", "compress"]).error_kind(),
+            ErrorKind::Network
+        );
+    }
+
+    #[test]
+    fn test_cli_parse_config_check() {
+        let cli = Cli::try_parse_from(["attentive", "config", "check"]);
+        assert!(matches!(
+            cli.unwrap().command,
+            Commands::Config {
+                action: ConfigAction::Check
+            }
+        ));
+    }
+
+    #[test]
+    fn test_cli_parse_compress_timeline() {
+        let cli = Cli::try_parse_from(["attentive", "compress", "timeline", "obs1", "--days", "3"]);
+        if let Commands::Compress {
+            action: Some(CompressAction::Timeline { id, days, json }),
+        } = cli.unwrap().command
+        {
+            assert_eq!(id, "obs1");
+            assert_eq!(days, 3);
+            assert!(!json);
+        } else {
+            panic!("Expected Compress Timeline command");
+        }
+
+        let cli = Cli::try_parse_from(["attentive", "compress"]).unwrap();
+        assert!(matches!(cli.command, Commands::Compress { action: None }));
+    }
+
+    #[test]
+    fn test_cli_parse_completions() {
+        let cli = Cli::try_parse_from(["attentive", "completions", "zsh"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Commands::Completions { shell: Shell::Zsh }
+        ));
+        assert!(Cli::try_parse_from(["attentive", "completions", "tcsh"]).is_err());
     }
 
     #[test]
     fn test_cli_parse_hook_commands() {
-        let hooks = ["hook:user-prompt-submit", "hook:session-start", "hook:stop"];
+        let hooks = [
+            "hook:user-prompt-submit",
+            "hook:session-start",
+            "hook:stop",
+            "hook:pre-tool-use",
+        ];
 
         for hook in hooks {
             let cli = Cli::try_parse_from(["attentive", hook]);
@@ -0,0 +1,123 @@
+//! Files external tools report as active
+//!
+//! An IDE extension (or any other tool) can write the project's
+//! `active_files.json` to tell the router which files the user is working
+//! with: those open in the editor and, optionally, the focused one:
+//!
+//! ```json
+//! {"version": 1, "files": ["src/router.rs", "/abs/path/to/src/lib.rs"], "active": "src/router.rs"}
+//! ```
+//!
+//! Paths may be repo-relative or absolute. Every prompt, the router boosts
+//! each listed file by `active_file_boost` and the focused one by
+//! `active_buffer_boost`, until the file is rewritten or removed.
+//! `status --export-context` reports where the file lives.
+
+use attentive_core::PathScope;
+use attentive_telemetry::PathCanonicalizer;
+use serde::Deserialize;
+use std::path::Path;
+
+/// Newest `active_files.json` format this build reads
+pub const ACTIVE_FILES_VERSION: u32 = 1;
+
+#[derive(Debug, Deserialize)]
+struct ActiveFilesFile {
+    version: u32,
+    #[serde(default)]
+    files: Vec<String>,
+    #[serde(default)]
+    active: Option<String>,
+}
+
+/// Files an external tool reports as active, as canonical paths
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ActiveFiles {
+    /// Open files, without duplicates
+    pub files: Vec<String>,
+    /// The focused file, if any
+    pub active: Option<String>,
+}
+
+/// Parse `active_files.json` content, keeping paths inside `scope`
+fn parse(
+    content: &str,
+    canonicalizer: &PathCanonicalizer,
+    scope: &PathScope,
+) -> anyhow::Result<ActiveFiles> {
+    let file: ActiveFilesFile = serde_json::from_str(content)?;
+    if file.version > ACTIVE_FILES_VERSION {
+        anyhow::bail!(
+            "version {} is newer than supported ({})",
+            file.version,
+            ACTIVE_FILES_VERSION
+        );
+    }
+    Ok(ActiveFiles {
+        files: scope.retain(canonicalizer.canonicalize_all(&file.files)),
+        active: file
+            .active
+            .map(|path| canonicalizer.canonicalize(&path))
+            .filter(|path| scope.allows(path)),
+    })
+}
+
+/// Files listed in the `active_files.json` at `path`; none when it's missing
+/// or unreadable
+pub(crate) fn load(
+    path: &Path,
+    canonicalizer: &PathCanonicalizer,
+    scope: &PathScope,
+) -> ActiveFiles {
+    let Ok(content) = std::fs::read_to_string(path) else {
+        return ActiveFiles::default();
+    };
+    parse(&content, canonicalizer, scope).unwrap_or_else(|e| {
+        tracing::warn!("ignoring {}: {}", path.display(), e);
+        ActiveFiles::default()
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_active_files() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        let canonicalizer = PathCanonicalizer::new(root);
+        let scope = PathScope::new(&[], Path::new("/home/user"));
+        let content = serde_json::json!({
+            "version": 1,
+            "files": [
+                "src/router.rs",
+                root.join("src/router.rs"),
+                root.join("src/lib.rs"),
+                "/elsewhere/notes.md",
+            ],
+            "active": root.join("src/lib.rs"),
+        });
+        assert_eq!(
+            parse(&content.to_string(), &canonicalizer, &scope).unwrap(),
+            ActiveFiles {
+                files: vec!["src/router.rs".to_string(), "src/lib.rs".to_string()],
+                active: Some("src/lib.rs".to_string()),
+            }
+        );
+        let outside = r#"{"version": 1, "active": "/elsewhere/notes.md"}"#;
+        assert_eq!(
+            parse(outside, &canonicalizer, &scope).unwrap(),
+            ActiveFiles::default()
+        );
+
+        let newer = r#"{"version": 2, "files": ["src/lib.rs"]}"#;
+        let error = parse(newer, &canonicalizer, &scope).unwrap_err();
+        assert!(error.to_string().contains("newer than supported"));
+        assert!(parse(r#"{"files": []}"#, &canonicalizer, &scope).is_err());
+        assert_eq!(
+            load(&root.join("missing.json"), &canonicalizer, &scope),
+            ActiveFiles::default()
+        );
+    }
+}
@@ -1,5 +1,5 @@
+use super::hooks::load_learner;
 use attentive_core::{AttentionState, Config, Router};
-use attentive_learn::Learner;
 use attentive_telemetry::Paths;
 use std::path::Path;
 use std::time::Instant;
@@ -19,44 +19,12 @@ struct BenchmarkResult {
     warm_chars: usize,
 }
 
-fn scan_repo_files(root: &Path) -> Vec<(String, String)> {
-    let skip_dirs = [
-        ".git",
-        "node_modules",
-        "target",
-        "__pycache__",
-        ".venv",
-        "dist",
-        "build",
-    ];
-    let mut files = Vec::new();
-    scan_dir(root, root, &skip_dirs, &mut files);
-    files
-}
-
-fn scan_dir(root: &Path, dir: &Path, skip: &[&str], files: &mut Vec<(String, String)>) {
-    let entries = match std::fs::read_dir(dir) {
-        Ok(e) => e,
-        Err(_) => return,
-    };
-    for entry in entries.flatten() {
-        let path = entry.path();
-        let name = entry.file_name().to_string_lossy().to_string();
-        if path.is_dir() {
-            if !skip.contains(&name.as_str()) {
-                scan_dir(root, &path, skip, files);
-            }
-        } else if path.is_file()
-            && let Ok(content) = std::fs::read_to_string(&path)
-        {
-            let rel = path
-                .strip_prefix(root)
-                .unwrap_or(&path)
-                .to_string_lossy()
-                .to_string();
-            files.push((rel, content));
-        }
-    }
+/// (path, content) of every scannable file under `root`
+fn scan_repo_files(root: &Path, excludes: &[String]) -> Vec<(String, String)> {
+    attentive_repo::repo_files(root, excludes)
+        .into_iter()
+        .filter_map(|rel| Some((rel.clone(), std::fs::read_to_string(root.join(&rel)).ok()?)))
+        .collect()
 }
 
 fn estimate_tokens(text: &str) -> usize {
@@ -97,9 +65,11 @@ fn format_result(r: &BenchmarkResult) -> String {
 
 pub fn run() -> anyhow::Result<()> {
     let cwd = std::env::current_dir()?;
+    let paths = Paths::new()?;
 
     // 1. Scan repo
-    let files = scan_repo_files(&cwd);
+    let excludes = super::hooks::load_config(&paths.home_claude).exclude_patterns();
+    let files = scan_repo_files(&cwd, &excludes);
     if files.is_empty() {
         println!("No files found in {}", cwd.display());
         return Ok(());
@@ -109,16 +79,8 @@ pub fn run() -> anyhow::Result<()> {
     let baseline_tokens: usize = files.iter().map(|(_, c)| estimate_tokens(c)).sum();
 
     // 3. Load learned state
-    let paths = Paths::new()?;
     let learned_state_path = paths.learned_state_path()?;
-    let learner = if learned_state_path.exists() {
-        std::fs::read_to_string(&learned_state_path)
-            .ok()
-            .and_then(|c| serde_json::from_str(&c).ok())
-            .unwrap_or_else(Learner::new)
-    } else {
-        Learner::new()
-    };
+    let learner = load_learner(&learned_state_path).unwrap_or_default();
 
     // 4. Build attention state from file list
     let config = Config::default();
@@ -213,7 +175,7 @@ mod tests {
         std::fs::create_dir_all(temp.path().join(".git")).unwrap();
         std::fs::write(temp.path().join(".git/config"), "gitconfig").unwrap();
 
-        let files = scan_repo_files(temp.path());
+        let files = scan_repo_files(temp.path(), &[]);
         assert_eq!(files.len(), 2); // .git excluded
     }
 
@@ -0,0 +1,221 @@
+//! Attention state kept per git branch (`branch_state`)
+//!
+//! `attn_state.json` always holds the state of the branch named in
+//! `branches/active`. When a hook finds another branch checked out, that state
+//! is parked under `branches/<branch>/` along with the learner's warm-start
+//! files, and the new branch's parked copy takes its place. A branch seen for
+//! the first time starts empty, or from main's state with the fallback.
+
+use super::hooks::{load_attention_state, load_learner, save_learner};
+use attentive_core::{AttentionState, BranchState};
+use attentive_telemetry::{Paths, atomic_write, read_versioned, write_versioned};
+use std::path::{Path, PathBuf};
+
+/// Names the branch `attn_state.json` belongs to
+const ACTIVE_FILE: &str = "active";
+const STATE_FILE: &str = "attn_state.json";
+const WARMUP_FILE: &str = "warmup.json";
+
+/// Branches a new branch's state falls back to, in order
+const FALLBACK_BRANCHES: &[&str] = &["main", "master"];
+
+/// Branch checked out in `dir`, read from `.git` since this runs on every
+/// prompt; `None` outside git or on a detached HEAD
+fn current_branch(dir: &Path) -> Option<String> {
+    attentive_repo::git_head(dir)?.branch
+}
+
+/// Directory a branch's state is parked in. Branch names may hold `/`, which
+/// would nest directories, so it and anything unusual are percent-encoded,
+/// keeping distinct branches in distinct directories.
+fn branch_dir(dir: &Path, branch: &str) -> PathBuf {
+    let mut name = String::with_capacity(branch.len());
+    for (i, byte) in branch.bytes().enumerate() {
+        if byte.is_ascii_alphanumeric() || b"_-".contains(&byte) || (byte == b'.' && i > 0) {
+            name.push(byte as char);
+        } else {
+            name.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    dir.join(name)
+}
+
+/// A parked state and its warm-start files
+fn load_parked(dir: &Path, branch: &str) -> Option<(AttentionState, Vec<String>)> {
+    let parked = branch_dir(dir, branch);
+    let state = read_versioned(&parked.join(STATE_FILE)).ok()?;
+    let warmup = std::fs::read_to_string(parked.join(WARMUP_FILE))
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    Some((state, warmup))
+}
+
+/// Swap in the attention state of the branch checked out in the current
+/// directory, if it isn't the active one. Returns what was done, for the
+/// hook's stderr.
+pub(crate) fn switch(paths: &Paths, strategy: BranchState) -> anyhow::Result<Option<String>> {
+    if strategy == BranchState::Shared {
+        return Ok(None);
+    }
+    match current_branch(&paths.cwd()?) {
+        Some(branch) => swap(paths, strategy, &branch),
+        None => Ok(None),
+    }
+}
+
+fn swap(paths: &Paths, strategy: BranchState, branch: &str) -> anyhow::Result<Option<String>> {
+    let dir = paths.branch_state_dir()?;
+    let active_path = dir.join(ACTIVE_FILE);
+    let active = std::fs::read_to_string(&active_path)
+        .ok()
+        .map(|name| name.trim().to_string());
+    if active.as_deref() == Some(branch) {
+        return Ok(None);
+    }
+    std::fs::create_dir_all(&dir)?;
+    // The first time, the existing state becomes this branch's
+    let Some(previous) = active else {
+        atomic_write(&active_path, branch.as_bytes())?;
+        return Ok(None);
+    };
+
+    let learner_path = paths.learned_state_path()?;
+    let mut learner = load_learner(&learner_path);
+    let parked = branch_dir(&dir, &previous);
+    std::fs::create_dir_all(&parked)?;
+    write_versioned(&parked.join(STATE_FILE), &load_attention_state(paths)?)?;
+    let warmup = learner.as_ref().map(|l| l.get_warmup()).unwrap_or_default();
+    atomic_write(
+        &parked.join(WARMUP_FILE),
+        serde_json::to_string(&warmup)?.as_bytes(),
+    )?;
+
+    let fallback = || {
+        if strategy != BranchState::PerBranchWithFallback {
+            return None;
+        }
+        FALLBACK_BRANCHES
+            .iter()
+            .filter(|name| **name != branch)
+            .find_map(|name| Some((format!("{}'s state", name), load_parked(&dir, name)?)))
+    };
+    let (origin, (state, warmup)) = load_parked(&dir, branch)
+        .map(|restored| ("its saved state".to_string(), restored))
+        .or_else(fallback)
+        .unwrap_or_else(|| ("a fresh state".to_string(), Default::default()));
+
+    let state_path = paths.attn_state_path()?;
+    write_versioned(&state_path, &state)?;
+    super::serve::store_cached(&state_path, &state);
+    if let Some(learner) = learner.as_mut() {
+        learner.save_session(&warmup);
+        if save_learner(&learner_path, learner).is_ok() {
+            super::serve::store_cached(&learner_path, &*learner);
+        }
+    }
+    atomic_write(&active_path, branch.as_bytes())?;
+    Ok(Some(format!(
+        "Switched attention from {} to {}, starting from {} ({} file(s))",
+        previous,
+        branch,
+        origin,
+        state.scores.len()
+    )))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_telemetry::TestEnv;
+
+    fn state_with(file: &str) -> AttentionState {
+        let mut state = AttentionState::new();
+        state.scores.insert(file.to_string(), 0.9);
+        state
+    }
+
+    fn active_files(paths: &Paths) -> Vec<String> {
+        let state: AttentionState = read_versioned(&paths.attn_state_path().unwrap()).unwrap();
+        state.scores.into_keys().collect()
+    }
+
+    #[test]
+    fn test_swap_parks_and_restores_per_branch() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let state_path = paths.attn_state_path().unwrap();
+        std::fs::create_dir_all(state_path.parent().unwrap()).unwrap();
+        write_versioned(&state_path, &state_with("src/main.rs")).unwrap();
+
+        // The state in place on the first run is main's
+        assert_eq!(swap(&paths, BranchState::PerBranch, "main").unwrap(), None);
+        assert_eq!(active_files(&paths), ["src/main.rs"]);
+
+        let message = swap(&paths, BranchState::PerBranch, "feature/auth")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            message,
+            "Switched attention from main to feature/auth, starting from a fresh state (0 file(s))"
+        );
+        assert!(active_files(&paths).is_empty());
+        write_versioned(&state_path, &state_with("src/auth.rs")).unwrap();
+
+        let message = swap(&paths, BranchState::PerBranch, "main")
+            .unwrap()
+            .unwrap();
+        assert!(message.ends_with("starting from its saved state (1 file(s))"));
+        assert_eq!(active_files(&paths), ["src/main.rs"]);
+
+        swap(&paths, BranchState::PerBranch, "feature/auth").unwrap();
+        assert_eq!(active_files(&paths), ["src/auth.rs"]);
+        assert_eq!(
+            swap(&paths, BranchState::PerBranch, "feature/auth").unwrap(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_swap_falls_back_to_main() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let state_path = paths.attn_state_path().unwrap();
+        std::fs::create_dir_all(state_path.parent().unwrap()).unwrap();
+        write_versioned(&state_path, &state_with("src/main.rs")).unwrap();
+
+        let strategy = BranchState::PerBranchWithFallback;
+        swap(&paths, strategy, "main").unwrap();
+        let message = swap(&paths, strategy, "fix-1").unwrap().unwrap();
+        assert!(message.ends_with("starting from main's state (1 file(s))"));
+        assert_eq!(active_files(&paths), ["src/main.rs"]);
+    }
+
+    #[test]
+    fn test_branch_dir_flattens_names() {
+        let dir = Path::new("/b");
+        assert_eq!(branch_dir(dir, "feature/x"), Path::new("/b/feature%2Fx"));
+        assert_eq!(branch_dir(dir, "../up"), Path::new("/b/%2E.%2Fup"));
+        assert_eq!(branch_dir(dir, "main"), Path::new("/b/main"));
+        assert_eq!(branch_dir(dir, "v1.2"), Path::new("/b/v1.2"));
+        // Names that only differ in the characters being encoded stay apart
+        assert_ne!(branch_dir(dir, "feature/x"), branch_dir(dir, "feature_x"));
+        assert_ne!(branch_dir(dir, "a%2Fb"), branch_dir(dir, "a/b"));
+    }
+
+    #[test]
+    fn test_current_branch() {
+        let dir = tempfile::tempdir().unwrap();
+        assert_eq!(current_branch(dir.path()), None);
+        let git = |args: &[&str]| {
+            std::process::Command::new("git")
+                .arg("-C")
+                .arg(dir.path())
+                .args(args)
+                .output()
+                .unwrap()
+        };
+        git(&["init", "-q", "-b", "trunk"]);
+        assert_eq!(current_branch(dir.path()).as_deref(), Some("trunk"));
+    }
+}
@@ -0,0 +1,115 @@
+//! Post-compaction brief
+//!
+//! Compaction drops the context injected earlier in the conversation. The
+//! PreCompact hook saves the working set (HOT and WARM files, recent edits and
+//! the session's latest observations) in the session state, and the next
+//! prompt injects it once so Claude knows what it was working on.
+
+use attentive_core::{AttentionState, Router};
+use serde::{Deserialize, Serialize};
+
+/// Session-state key holding the brief until the next prompt takes it
+pub(crate) const COMPACTION_BRIEF_KEY: &str = "compaction_brief";
+
+/// Most observations listed in a brief
+const MAX_BRIEF_OBSERVATIONS: usize = 5;
+
+/// Working set saved before compaction
+#[derive(Debug, Default, PartialEq, Serialize, Deserialize)]
+pub(crate) struct CompactionBrief {
+    pub hot: Vec<String>,
+    pub warm: Vec<String>,
+    /// Files edited within the `edit_floor_turns` window
+    pub edited: Vec<String>,
+    /// Summaries of the session's latest observations, oldest first
+    pub observations: Vec<String>,
+}
+
+impl CompactionBrief {
+    /// Brief for `state` as `router` tiers it, with the last few of
+    /// `observations` (oldest first)
+    pub fn new(router: &Router, state: &AttentionState, observations: Vec<String>) -> Self {
+        let (hot, warm, _) = router.build_context_output(state);
+        let mut edited: Vec<String> = state.recently_edited.keys().cloned().collect();
+        edited.sort();
+        let skip = observations.len().saturating_sub(MAX_BRIEF_OBSERVATIONS);
+        Self {
+            hot,
+            warm,
+            edited,
+            observations: observations.into_iter().skip(skip).collect(),
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.hot.is_empty()
+            && self.warm.is_empty()
+            && self.edited.is_empty()
+            && self.observations.is_empty()
+    }
+
+    /// Section injected ahead of the next prompt's context
+    pub fn format(&self) -> String {
+        let list = |files: &[String]| {
+            files
+                .iter()
+                .map(|f| format!("`{}`", f))
+                .collect::<Vec<_>>()
+                .join(", ")
+        };
+        let mut lines = vec![
+            "## Post-compaction brief".to_string(),
+            "The conversation was just compacted. Working set before it:".to_string(),
+        ];
+        for (label, files) in [
+            ("HOT", &self.hot),
+            ("WARM", &self.warm),
+            ("Recently edited", &self.edited),
+        ] {
+            if !files.is_empty() {
+                lines.push(format!("- {}: {}", label, list(files)));
+            }
+        }
+        if !self.observations.is_empty() {
+            lines.push("Latest observations:".to_string());
+            lines.extend(self.observations.iter().map(|o| format!("- {}", o)));
+        }
+        lines.join("\n")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_core::Config;
+
+    #[test]
+    fn test_compaction_brief() {
+        let router = Router::new(Config::new());
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.9);
+        state.scores.insert("src/config.rs".to_string(), 0.5);
+        state.scores.insert("README.md".to_string(), 0.1);
+        state.recently_edited.insert("src/router.rs".to_string(), 3);
+        let observations = (1..=7).map(|i| format!("step {}", i)).collect();
+
+        let brief = CompactionBrief::new(&router, &state, observations);
+        assert_eq!(brief.hot, ["src/router.rs"]);
+        assert_eq!(brief.warm, ["src/config.rs"]);
+        assert_eq!(brief.observations.first().unwrap(), "step 3");
+        assert_eq!(brief.observations.len(), MAX_BRIEF_OBSERVATIONS);
+        assert_eq!(
+            brief.format(),
+            "## Post-compaction brief\n\
+             The conversation was just compacted. Working set before it:\n\
+             - HOT: `src/router.rs`\n\
+             - WARM: `src/config.rs`\n\
+             - Recently edited: `src/router.rs`\n\
+             Latest observations:\n\
+             - step 3\n- step 4\n- step 5\n- step 6\n- step 7"
+        );
+
+        let empty = CompactionBrief::new(&router, &AttentionState::new(), Vec::new());
+        assert!(empty.is_empty());
+    }
+}
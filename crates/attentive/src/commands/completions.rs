@@ -0,0 +1,14 @@
+use clap::CommandFactory;
+use clap_complete::Shell;
+use std::io::Write;
+
+use crate::cli::Cli;
+
+/// Print the completion script for `shell` to stdout
+pub fn run(shell: Shell) -> anyhow::Result<()> {
+    // Generate into a buffer: clap_complete panics on a closed stdout
+    let mut script = Vec::new();
+    clap_complete::generate(shell, &mut Cli::command(), "attentive", &mut script);
+    std::io::stdout().write_all(&script)?;
+    Ok(())
+}
@@ -1,13 +1,22 @@
-pub fn run() -> anyhow::Result<()> {
+use attentive_compress::{CompressedObservation, ObservationDb};
+
+/// The observation store, or `None` (after telling the user) when there isn't one
+fn open_db() -> anyhow::Result<Option<ObservationDb>> {
     let paths = attentive_telemetry::Paths::new()?;
-    let db_path = paths.home_claude.join("observations.db");
+    let db_path = paths.observations_db_path();
 
     if !db_path.exists() {
         println!("No observations database found. Run some sessions first.");
-        return Ok(());
+        return Ok(None);
     }
+    let redactor = super::hooks::load_config(&paths.home_claude).redactor();
+    Ok(Some(ObservationDb::new(&db_path)?.with_redactor(redactor)))
+}
 
-    let db = attentive_compress::ObservationDb::new(&db_path)?;
+pub fn run() -> anyhow::Result<()> {
+    let Some(db) = open_db()? else {
+        return Ok(());
+    };
     let index = db.get_index()?;
 
     println!("Compressed Observations: {}", index.len());
@@ -42,3 +51,114 @@ pub fn run() -> anyhow::Result<()> {
     }
     Ok(())
 }
+
+/// One line per observation: date, type, summary and id
+fn format_line(obs: &CompressedObservation) -> String {
+    format!(
+        "  {} [{}] {} ({})",
+        obs.timestamp.format("%Y-%m-%d %H:%M"),
+        obs.observation_type,
+        obs.semantic_summary,
+        obs.id
+    )
+}
+
+fn format_list(observations: &[CompressedObservation]) -> String {
+    observations
+        .iter()
+        .map(format_line)
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_observation(obs: &CompressedObservation) -> String {
+    let mut lines = vec![
+        format!("{} [{}]", obs.id, obs.observation_type),
+        format!("Session: {}", obs.session_id),
+        format!("Time: {}", obs.timestamp.to_rfc3339()),
+        format!("Tool: {}", obs.tool_name),
+        format!(
+            "Tokens: {} compressed ({} raw)",
+            obs.compressed_tokens, obs.raw_tokens
+        ),
+        String::new(),
+        obs.semantic_summary.clone(),
+    ];
+    if !obs.key_facts.is_empty() {
+        lines.push(String::new());
+        lines.push("Key facts:".to_string());
+        lines.extend(obs.key_facts.iter().map(|f| format!("  - {}", f)));
+    }
+    if !obs.concepts.is_empty() {
+        lines.push(format!("Concepts: {}", obs.concepts.join(", ")));
+    }
+    if !obs.related_files.is_empty() {
+        lines.push(format!("Files: {}", obs.related_files.join(", ")));
+    }
+    lines.join("\n")
+}
+
+fn print_list(observations: &[CompressedObservation], json: bool) -> anyhow::Result<()> {
+    if json {
+        println!("{}", serde_json::to_string_pretty(observations)?);
+    } else if observations.is_empty() {
+        println!("No matching observations.");
+    } else {
+        println!("{}", format_list(observations));
+    }
+    Ok(())
+}
+
+pub fn run_query(text: &str, limit: usize, json: bool) -> anyhow::Result<()> {
+    let Some(db) = open_db()? else {
+        return Ok(());
+    };
+    print_list(&db.search(text, limit)?, json)
+}
+
+pub fn run_show(id: &str, json: bool) -> anyhow::Result<()> {
+    let Some(db) = open_db()? else {
+        return Ok(());
+    };
+    let Some(obs) = db.get_by_id(id)? else {
+        anyhow::bail!("No observation with id {}", id);
+    };
+    if json {
+        println!("{}", serde_json::to_string_pretty(&obs)?);
+    } else {
+        println!("{}", format_observation(&obs));
+    }
+    Ok(())
+}
+
+pub fn run_timeline(id: &str, days: usize, json: bool) -> anyhow::Result<()> {
+    let Some(db) = open_db()? else {
+        return Ok(());
+    };
+    if db.get_by_id(id)?.is_none() {
+        anyhow::bail!("No observation with id {}", id);
+    }
+    print_list(&db.get_timeline(id, days)?, json)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_observation() {
+        let mut obs =
+            CompressedObservation::memory("/repo", "Hooks must not block", vec!["a.rs".into()]);
+        obs.key_facts = vec!["deadline is 300ms".to_string()];
+
+        let full = format_observation(&obs);
+        assert!(full.starts_with(&format!("{} [memory]", obs.id)));
+        assert!(full.contains("\nHooks must not block\n"));
+        assert!(full.contains("Key facts:\n  - deadline is 300ms"));
+        assert!(full.ends_with("Files: a.rs"));
+
+        let line = format_line(&obs);
+        assert!(line.contains("[memory] Hooks must not block"));
+        assert!(line.ends_with(&format!("({})", obs.id)));
+    }
+}
@@ -0,0 +1,431 @@
+use super::hooks::{load_attention_state, load_config, load_learner};
+use attentive_core::{
+    Config, ConfigConflict, ConfigIssue, ConflictChecker, DEFAULT_DEMOTED_FILES,
+    DEFAULT_EXCLUDED_FILES, DecayMatcher, PathPatterns, Severity,
+};
+use attentive_telemetry::{Paths, atomic_write};
+use std::collections::{BTreeSet, HashMap};
+
+/// Starting point for `config edit` when attentive.json does not exist yet.
+/// `//` lines are stripped before the file is validated and saved.
+const TEMPLATE: &str = r#"// attentive configuration. Lines starting with // are removed on save.
+// Every key is optional; `attentive config schema` lists them all.
+{
+  // Score at or above which a file is injected in full
+  "hot_threshold": 0.8,
+  // Score at or above which a file's table of contents is injected
+  "warm_threshold": 0.25,
+  "max_hot_files": 3,
+  "max_warm_files": 5,
+  // Files, directories (dir/) or globs that are always at least WARM
+  "pinned_files": [],
+  // Files, directories or globs whose scores are penalized
+  "demoted_files": [],
+  // Files that warm each other: {"src/router.rs": ["src/config.rs"]}
+  "co_activation": {}
+}
+"#;
+
+fn format_check(path: &str, issues: &[ConfigIssue]) -> String {
+    let mut lines = vec![format!("Checking {}", path)];
+    for issue in issues {
+        lines.push(format!("  {}", issue));
+    }
+    let errors = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .count();
+    lines.push(format!(
+        "\n{} errors, {} warnings",
+        errors,
+        issues.len() - errors
+    ));
+    lines.join("\n")
+}
+
+/// Validate ~/.claude/attentive.json; fails if any errors are found
+pub fn run_check() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config_path = paths.home_claude.join("attentive.json");
+    let display = config_path.display().to_string();
+
+    if !config_path.exists() {
+        println!("{} not found; defaults are in use", display);
+        return Ok(());
+    }
+
+    let content = std::fs::read_to_string(&config_path)?;
+    let issues = match Config::check_json(&content) {
+        Ok(issues) => issues,
+        Err(e) => anyhow::bail!("{} is not valid: {}", display, e),
+    };
+
+    println!("{}", format_check(&display, &issues));
+    if issues.iter().any(|i| i.severity == Severity::Error) {
+        anyhow::bail!("attentive.json has invalid values");
+    }
+    Ok(())
+}
+
+/// Drop whole-line `//` comments so the edited text is plain JSON
+fn strip_comments(text: &str) -> String {
+    text.lines()
+        .filter(|line| !line.trim_start().starts_with("//"))
+        .map(|line| format!("{}\n", line))
+        .collect()
+}
+
+/// Strip comments from an edited config and validate it; returns the JSON to
+/// save and any warnings, or an error describing why it was rejected
+fn finish_edit(edited: &str) -> anyhow::Result<(String, Vec<ConfigIssue>)> {
+    let json = strip_comments(edited);
+    let issues = Config::check_json(&json).map_err(|e| anyhow::anyhow!("invalid JSON: {}", e))?;
+    let errors: Vec<String> = issues
+        .iter()
+        .filter(|i| i.severity == Severity::Error)
+        .map(|i| format!("  {}", i))
+        .collect();
+    if !errors.is_empty() {
+        anyhow::bail!("invalid values:\n{}", errors.join("\n"));
+    }
+    Ok((json, issues))
+}
+
+/// Open ~/.claude/attentive.json in $VISUAL or $EDITOR (a commented template if
+/// it doesn't exist) and save it only if the result validates
+pub fn run_edit() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config_path = paths.home_claude.join("attentive.json");
+    let original = std::fs::read_to_string(&config_path).ok();
+
+    // Edit a scratch copy so a rejected edit never replaces the real file
+    let draft_path = paths.home_claude.join("attentive.json.edit");
+    if !draft_path.exists() {
+        std::fs::create_dir_all(&paths.home_claude)?;
+        std::fs::write(&draft_path, original.as_deref().unwrap_or(TEMPLATE))?;
+    }
+
+    let editor = std::env::var("VISUAL")
+        .or_else(|_| std::env::var("EDITOR"))
+        .unwrap_or_else(|_| "vi".to_string());
+    let mut words = editor.split_whitespace();
+    let program = words.next().unwrap_or("vi");
+    let status = std::process::Command::new(program)
+        .args(words)
+        .arg(&draft_path)
+        .status()
+        .map_err(|e| anyhow::anyhow!("could not run editor `{}`: {}", editor, e))?;
+    if !status.success() {
+        anyhow::bail!(
+            "editor exited with {}; draft kept at {}",
+            status,
+            draft_path.display()
+        );
+    }
+
+    let edited = std::fs::read_to_string(&draft_path)?;
+    let (json, issues) = match finish_edit(&edited) {
+        Ok(result) => result,
+        Err(e) => anyhow::bail!(
+            "{}\nattentive.json was not changed; fix the draft at {} and run `attentive config edit` again",
+            e,
+            draft_path.display()
+        ),
+    };
+    for issue in &issues {
+        println!("  {}", issue);
+    }
+    std::fs::remove_file(&draft_path)?;
+
+    if original.as_deref() == Some(json.as_str()) {
+        println!("No changes");
+        return Ok(());
+    }
+    atomic_write(&config_path, json.as_bytes())?;
+    println!("Saved {}", config_path.display());
+    Ok(())
+}
+
+/// Print the JSON Schema for attentive.json
+pub fn run_schema() -> anyhow::Result<()> {
+    println!("{}", serde_json::to_string_pretty(&Config::json_schema())?);
+    Ok(())
+}
+
+/// Effective decay rules in precedence order, with how many of `files` each
+/// one decides
+fn format_decay(matcher: &DecayMatcher, files: &[String]) -> String {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    let mut defaulted = 0;
+    for file in files {
+        match matcher.rule_for(file) {
+            Some((key, _)) => *counts.entry(key).or_insert(0) += 1,
+            None => defaulted += 1,
+        }
+    }
+
+    let mut lines = vec![
+        "Decay rates (exact path > longest prefix or glob > default)".to_string(),
+        format!("  {:<7} {:<32} {:>5} {:>6}", "RULE", "KEY", "RATE", "FILES"),
+    ];
+    for (key, rule, rate) in matcher.rules() {
+        let count = counts.get(key.as_str()).copied().unwrap_or(0);
+        lines.push(format!(
+            "  {:<7} {:<32} {:>5.2} {:>6}",
+            rule, key, rate, count
+        ));
+    }
+    lines.push(format!(
+        "  {:<7} {:<32} {:>5.2} {:>6}",
+        "default",
+        "",
+        matcher.default_rate(),
+        defaulted
+    ));
+    lines.push(
+        "\nFILES counts tracked files. Files under the default use learned rates once the learner has seen them."
+            .to_string(),
+    );
+    lines.join("\n")
+}
+
+/// Print the effective decay rules and which tracked files each one covers
+pub fn run_show_decay() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config = load_config(&paths.home_claude);
+    let state = load_attention_state(&paths)?;
+    let mut files: Vec<String> = state.scores.into_keys().collect();
+    files.sort();
+    println!("{}", format_decay(&config.decay_rates.matcher(), &files));
+    Ok(())
+}
+
+/// Built-in demotion and scan exclusion rules, with how many of `files` each
+/// demotion covers and how many of those `!` entries in `demoted_files` exempt.
+/// Counts are what the rules would cover when they are off.
+fn format_defaults(config: &Config, files: &[String]) -> String {
+    let state = if config.default_file_rules {
+        "on"
+    } else {
+        "off"
+    };
+    let mut lines = vec![
+        format!(
+            "Built-in demotions, applied before demoted_files (default_file_rules: {})",
+            state
+        ),
+        format!("  {:<24} {:>6} {:>6}", "PATTERN", "FILES", "EXEMPT"),
+    ];
+    let with_defaults: Vec<String> = DEFAULT_DEMOTED_FILES
+        .iter()
+        .map(|p| p.to_string())
+        .chain(config.demoted_files.iter().cloned())
+        .collect();
+    let effective = PathPatterns::new(&with_defaults);
+    for rule in DEFAULT_DEMOTED_FILES {
+        let patterns = PathPatterns::new(&[rule.to_string()]);
+        let (covered, exempt) =
+            files
+                .iter()
+                .filter(|f| patterns.matches(f))
+                .fold((0, 0), |(covered, exempt), f| {
+                    if effective.matches(f) {
+                        (covered + 1, exempt)
+                    } else {
+                        (covered, exempt + 1)
+                    }
+                });
+        lines.push(format!("  {:<24} {:>6} {:>6}", rule, covered, exempt));
+    }
+    lines.push(String::new());
+    lines.push("Built-in scan exclusions, applied before exclude_files".to_string());
+    for rule in DEFAULT_EXCLUDED_FILES {
+        lines.push(format!("  {}", rule));
+    }
+    lines.push(
+        "\nFILES counts tracked files. Turn the rules off with \"default_file_rules\": false, or exempt paths with \"!pattern\" in demoted_files or exclude_files."
+            .to_string(),
+    );
+    lines.join("\n")
+}
+
+/// Print the built-in demotion and exclusion rules
+pub fn run_show_defaults() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config = load_config(&paths.home_claude);
+    let state = load_attention_state(&paths)?;
+    let mut files: Vec<String> = state.scores.into_keys().collect();
+    files.sort();
+    println!("{}", format_defaults(&config, &files));
+    Ok(())
+}
+
+/// Conflicts with their evidence and suggested fix, demoted files first
+fn format_conflicts(conflicts: &[ConfigConflict]) -> String {
+    if conflicts.is_empty() {
+        return "No conflicts between config and learned behavior".to_string();
+    }
+    let mut lines = vec![format!("Config conflicts ({})", conflicts.len())];
+    for conflict in conflicts {
+        lines.push(format!("  {}", conflict.path));
+        lines.push(format!("    {}", conflict));
+        lines.push(format!("    fix: {}", conflict.resolution));
+    }
+    lines.push(
+        "\nThe fixes side with the learner; keep the rule instead if the learned usage is noise."
+            .to_string(),
+    );
+    lines.join("\n")
+}
+
+/// Print files whose pinned or demoted rule the learner contradicts
+pub fn run_conflicts() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config = load_config(&paths.home_claude);
+    let Some(learner) = load_learner(&paths.learned_state_path()?) else {
+        println!("No learned state yet; conflicts show up once the learner has seen some turns");
+        return Ok(());
+    };
+    let learner = learner.with_half_life(config.learner_half_life);
+    let state = load_attention_state(&paths)?;
+
+    // Learned and scored files, plus pinned paths that may never have been scored
+    let literal_pins = config
+        .pinned_files
+        .iter()
+        .filter(|p| !p.ends_with('/') && !p.contains(['*', '?', '[', '!']))
+        .cloned();
+    let files: BTreeSet<String> = learner
+        .top_files_by_frequency(usize::MAX)
+        .into_iter()
+        .map(|(file, _)| file)
+        .chain(state.scores.into_keys())
+        .chain(literal_pins)
+        .collect();
+    let checker = ConflictChecker::new(&config);
+    let mut conflicts: Vec<ConfigConflict> = files
+        .iter()
+        .filter_map(|file| checker.check(file, &learner, learner.learned_boost(file)))
+        .collect();
+    conflicts.sort_by(|a, b| {
+        (a.kind as u8)
+            .cmp(&(b.kind as u8))
+            .then(b.uses.cmp(&a.uses))
+            .then(a.path.cmp(&b.path))
+    });
+    println!("{}", format_conflicts(&conflicts));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_check_counts() {
+        let issues = Config::check_json(r#"{"hot_threshold": 1.5, "typo": 1}"#).unwrap();
+        let output = format_check("attentive.json", &issues);
+        assert!(output.starts_with("Checking attentive.json"));
+        assert!(output.contains("  warning: typo: unknown key"));
+        assert!(output.contains("  error: hot_threshold: 1.5 is outside [0.0, 1.0]"));
+        assert!(output.ends_with("1 errors, 1 warnings"));
+    }
+
+    #[test]
+    fn test_finish_edit_strips_comments_and_validates() {
+        let (json, issues) = finish_edit(TEMPLATE).unwrap();
+        assert!(!json.contains("//"));
+        assert!(issues.is_empty());
+        assert_eq!(Config::from_json(&json).unwrap().max_hot_files, 3);
+
+        let err = finish_edit("{\"hot_threshold\": 2.0}").unwrap_err();
+        assert!(err.to_string().contains("hot_threshold"));
+        assert!(finish_edit("{ // inline comments are not supported\n}").is_err());
+    }
+
+    #[test]
+    fn test_format_decay_counts_files_per_rule() {
+        let config =
+            Config::from_json(r#"{"decay_rates": {"gen/**": 0.3, "src/main.rs": 0.9}}"#).unwrap();
+        let files =
+            ["src/main.rs", "gen/a.rs", "gen/b.rs", "docs/x.md", "lib.rs"].map(String::from);
+        let output = format_decay(&config.decay_rates.matcher(), &files);
+        let row = |key: &str| {
+            output
+                .lines()
+                .find(|l| l.split_whitespace().nth(1) == Some(key))
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(row("src/main.rs"), ["exact", "src/main.rs", "0.90", "1"]);
+        assert_eq!(row("gen/**"), ["glob", "gen/**", "0.30", "2"]);
+        assert_eq!(row("docs/"), ["prefix", "docs/", "0.75", "1"]);
+        let default = output
+            .lines()
+            .find(|l| l.trim_start().starts_with("default"));
+        assert_eq!(
+            default.unwrap().split_whitespace().collect::<Vec<_>>(),
+            ["default", "0.70", "1"]
+        );
+    }
+
+    #[test]
+    fn test_format_defaults_counts_covered_and_exempt_files() {
+        let config = Config::from_json(r#"{"demoted_files": ["!vendor/ours/**"]}"#).unwrap();
+        let files = [
+            "package-lock.json",
+            "web/package-lock.json",
+            "vendor/lib/a.go",
+            "vendor/ours/b.go",
+            "src/main.rs",
+        ]
+        .map(String::from);
+        let output = format_defaults(&config, &files);
+        let row = |key: &str| {
+            output
+                .lines()
+                .find(|l| l.split_whitespace().next() == Some(key))
+                .unwrap()
+                .split_whitespace()
+                .collect::<Vec<_>>()
+        };
+        assert!(output.starts_with("Built-in demotions"));
+        assert_eq!(row("package-lock.json"), ["package-lock.json", "2", "0"]);
+        assert_eq!(row("vendor/"), ["vendor/", "1", "1"]);
+        assert_eq!(row("*.map"), ["*.map"]);
+
+        let off = Config::from_json(r#"{"default_file_rules": false}"#).unwrap();
+        assert!(format_defaults(&off, &files).contains("(default_file_rules: off)"));
+        assert!(off.demoted_patterns().is_empty());
+        assert!(off.exclude_patterns().is_empty());
+    }
+
+    #[test]
+    fn test_format_conflicts_lists_evidence_and_fix() {
+        assert_eq!(
+            format_conflicts(&[]),
+            "No conflicts between config and learned behavior"
+        );
+        let conflict = ConfigConflict {
+            path: "vendor/parser.rs".to_string(),
+            kind: attentive_core::ConflictKind::DemotedButBoosted,
+            uses: 30,
+            turns: 60,
+            boost: 0.31,
+            resolution: "add \"!vendor/parser.rs\" to demoted_files".to_string(),
+        };
+        let output = format_conflicts(&[conflict]);
+        assert!(output.starts_with(
+            "Config conflicts (1)\n  vendor/parser.rs\n    \
+             demoted, but used in 30 of 60 turns with a learned boost of 0.31\n    \
+             fix: add \"!vendor/parser.rs\" to demoted_files"
+        ));
+    }
+
+    #[test]
+    fn test_format_check_clean() {
+        let output = format_check("attentive.json", &[]);
+        assert!(output.ends_with("0 errors, 0 warnings"));
+    }
+}
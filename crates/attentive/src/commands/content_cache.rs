@@ -0,0 +1,637 @@
+//! File contents reused across prompt hook runs
+//!
+//! HOT and WARM files are usually the same from one turn to the next, and on
+//! network filesystems reading them dominates hook latency. Contents are kept
+//! in `content_cache.json` in the project dir, keyed by path and checked
+//! against the file's mtime and size before use. The least recently used
+//! entries are evicted once the cache exceeds its size cap.
+//!
+//! Contents are only rewritten when an entry is added, replaced or evicted.
+//! When each entry was last used changes on every hit, so it is kept apart in
+//! the much smaller `content_cache.lru.json`.
+//!
+//! Digests of oversized HOT files are kept alongside, keyed by a hash of the
+//! content and the budget, so one is only rebuilt when the file changes.
+//! WARM outlines are kept the same way, keyed by path and stamp, with the
+//! file's imports so an unchanged WARM file isn't read at all.
+//!
+//! `prefetch` stats and reads a turn's files on a few threads up front, so a
+//! wide WARM set costs about as much as its slowest file rather than the sum.
+
+use attentive_repo::FileSymbols;
+use attentive_telemetry::{StateError, Versioned};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::path::{Path, PathBuf};
+use std::time::UNIX_EPOCH;
+
+/// Total cached content, in bytes
+const MAX_CACHE_BYTES: usize = 2 * 1024 * 1024;
+
+/// Files larger than this are read but not cached
+const MAX_ENTRY_BYTES: u64 = 256 * 1024;
+
+/// Digests kept; the least recently used go first
+const MAX_DIGESTS: usize = 32;
+
+/// Outlines kept; the least recently used go first
+const MAX_TOCS: usize = 256;
+
+/// Threads `prefetch` reads on
+const PREFETCH_THREADS: usize = 4;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedFile {
+    /// Modification time, ns since epoch
+    mtime: u64,
+    size: u64,
+    content: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedDigest {
+    /// `None` when no line of the file fits the budget
+    digest: Option<String>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedToc {
+    mtime: u64,
+    size: u64,
+    entries: Vec<String>,
+    /// Imports for the dependency hints, without the symbols themselves;
+    /// `None` for files in no supported language
+    #[serde(default)]
+    imports: Option<FileSymbols>,
+}
+
+/// Value of `tick` when each entry was last used, by the entry's key
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct Usage {
+    tick: u64,
+    files: HashMap<String, u64>,
+    digests: HashMap<String, u64>,
+    tocs: HashMap<String, u64>,
+}
+
+impl Versioned for Usage {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "content cache usage";
+}
+
+/// Key of the least recently used entry in `entries`
+fn least_recent<V>(entries: &HashMap<String, V>, used: &HashMap<String, u64>) -> Option<String> {
+    entries
+        .keys()
+        .min_by_key(|key| used.get(*key).copied().unwrap_or(0))
+        .cloned()
+}
+
+/// A file as `prefetch` found it
+#[derive(Debug, Clone)]
+struct Prefetched {
+    stamp: Option<(u64, u64)>,
+    /// Read when the cached copy was missing or stale; taken by `read`
+    content: Option<String>,
+}
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub(crate) struct ContentCache {
+    files: HashMap<String, CachedFile>,
+    /// `<content hash>:<max chars>` -> digest
+    #[serde(default)]
+    digests: HashMap<String, CachedDigest>,
+    /// Path -> headings and signatures
+    #[serde(default)]
+    tocs: HashMap<String, CachedToc>,
+    #[serde(skip)]
+    usage: Usage,
+    /// Entries were added, replaced or evicted since loading
+    #[serde(skip)]
+    dirty: bool,
+    /// An entry was used since loading
+    #[serde(skip)]
+    used: bool,
+    #[serde(skip)]
+    prefetched: HashMap<String, Prefetched>,
+    /// Directory relative paths are read from; the working directory when empty
+    #[serde(skip)]
+    root: PathBuf,
+}
+
+impl Versioned for ContentCache {
+    const VERSION: u32 = 2;
+    const KIND: &'static str = "content cache";
+
+    /// Version 2 keeps each outline's imports. Older outlines lack them, so
+    /// they're dropped and rebuilt on next use.
+    fn migrate(from: u32, mut data: serde_json::Value) -> Result<serde_json::Value, StateError> {
+        match from {
+            0 | 1 => {
+                if let Some(cache) = data.as_object_mut() {
+                    cache.remove("tocs");
+                }
+                Ok(data)
+            }
+            _ => Err(StateError::NoMigration {
+                kind: Self::KIND,
+                from,
+            }),
+        }
+    }
+}
+
+/// (mtime ns, size) of `path`, if it is a readable file
+fn stamp(path: &Path) -> Option<(u64, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    let mtime = meta.modified().ok()?.duration_since(UNIX_EPOCH).ok()?;
+    Some((mtime.as_nanos() as u64, meta.len()))
+}
+
+/// `content`'s imports (read from `path`), with its symbols left out
+fn imports_of(path: &str, content: &str) -> Option<FileSymbols> {
+    let mut symbols = attentive_repo::extract_symbols(content, path)?;
+    symbols.symbols.clear();
+    Some(symbols)
+}
+
+/// Where the usage of the cache at `path` is kept
+fn usage_path(path: &Path) -> PathBuf {
+    path.with_extension("lru.json")
+}
+
+/// The state at `path`, or the default if it is missing or unreadable
+fn load_or_default<T: Versioned + Default + Clone + Send + 'static>(path: &Path) -> T {
+    if !path.exists() {
+        return T::default();
+    }
+    super::serve::read_cached(path, |c| Ok(attentive_telemetry::from_versioned_str(c)?))
+        .unwrap_or_default()
+}
+
+fn store<T: Versioned + Clone + Send + 'static>(path: &Path, value: &T) -> anyhow::Result<()> {
+    attentive_telemetry::write_versioned_compact(path, value)?;
+    super::serve::store_cached(path, value);
+    Ok(())
+}
+
+impl ContentCache {
+    /// The cache at `path`, or an empty one if it is missing or unreadable
+    pub(crate) fn load(path: &Path) -> Self {
+        Self {
+            usage: load_or_default(&usage_path(path)),
+            ..load_or_default(path)
+        }
+    }
+
+    /// Read relative paths from `root` (the repo root canonical keys are
+    /// relative to) rather than the working directory
+    pub(crate) fn in_root(mut self, root: &Path) -> Self {
+        self.root = root.to_path_buf();
+        self
+    }
+
+    /// Write the contents if entries changed, and their usage if any was used
+    pub(crate) fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if self.dirty {
+            let contents = Self {
+                files: self.files.clone(),
+                digests: self.digests.clone(),
+                tocs: self.tocs.clone(),
+                ..Self::default()
+            };
+            store(path, &contents)?;
+        }
+        if self.used || self.dirty {
+            let mut usage = self.usage.clone();
+            usage.files.retain(|key, _| self.files.contains_key(key));
+            usage
+                .digests
+                .retain(|key, _| self.digests.contains_key(key));
+            usage.tocs.retain(|key, _| self.tocs.contains_key(key));
+            store(&usage_path(path), &usage)?;
+        }
+        Ok(())
+    }
+
+    /// Advance the clock for an entry being used
+    fn next_tick(&mut self) -> u64 {
+        self.used = true;
+        self.usage.tick += 1;
+        self.usage.tick
+    }
+
+    /// Stat `paths` and read those not cached, on up to `PREFETCH_THREADS`
+    /// threads, so the `read`s that follow don't touch the disk one by one
+    pub(crate) fn prefetch<'a>(&mut self, paths: impl IntoIterator<Item = &'a String>) {
+        let paths: Vec<&String> = paths.into_iter().collect();
+        self.prefetched.clear();
+        if paths.is_empty() {
+            return;
+        }
+        let files = &self.files;
+        let tocs = &self.tocs;
+        let root = &self.root;
+        let fetch = |path: &String| {
+            let file = root.join(path);
+            let stamp = stamp(&file);
+            // An unchanged outline is all a WARM file needs
+            let fresh = files
+                .get(path.as_str())
+                .is_some_and(|cached| stamp == Some((cached.mtime, cached.size)))
+                || tocs
+                    .get(path.as_str())
+                    .is_some_and(|cached| stamp == Some((cached.mtime, cached.size)));
+            let content = match stamp {
+                Some(_) if !fresh => std::fs::read_to_string(&file).ok(),
+                _ => None,
+            };
+            (path.to_string(), Prefetched { stamp, content })
+        };
+        let fetched: Vec<(String, Prefetched)> = std::thread::scope(|scope| {
+            let workers: Vec<_> = paths
+                .chunks(paths.len().div_ceil(PREFETCH_THREADS))
+                .map(|chunk| {
+                    scope.spawn(move || chunk.iter().map(|p| fetch(p)).collect::<Vec<_>>())
+                })
+                .collect();
+            workers
+                .into_iter()
+                .flat_map(|worker| worker.join().unwrap_or_default())
+                .collect()
+        });
+        self.prefetched.extend(fetched);
+    }
+
+    /// (mtime ns, size) of `path`, as prefetched or from disk
+    fn current_stamp(&self, path: &str) -> Option<(u64, u64)> {
+        match self.prefetched.get(path) {
+            Some(prefetched) => prefetched.stamp,
+            None => stamp(&self.root.join(path)),
+        }
+    }
+
+    /// Contents of `path`, from the cache while the file is unchanged on disk
+    pub(crate) fn read(&mut self, path: &str) -> Option<String> {
+        let Some((mtime, size)) = self.current_stamp(path) else {
+            let removed = self.files.remove(path).is_some() | self.tocs.remove(path).is_some();
+            self.dirty |= removed;
+            return None;
+        };
+        let tick = self.next_tick();
+        self.usage.files.insert(path.to_string(), tick);
+        if let Some(cached) = self.files.get(path)
+            && cached.mtime == mtime
+            && cached.size == size
+        {
+            return Some(cached.content.clone());
+        }
+
+        let prefetched = self
+            .prefetched
+            .get_mut(path)
+            .and_then(|prefetched| prefetched.content.take());
+        let content = match prefetched {
+            Some(content) => content,
+            None => std::fs::read_to_string(self.root.join(path)).ok()?,
+        };
+        if size <= MAX_ENTRY_BYTES {
+            let cached = CachedFile {
+                mtime,
+                size,
+                content: content.clone(),
+            };
+            self.files.insert(path.to_string(), cached);
+            self.dirty = true;
+            self.evict();
+        } else {
+            self.dirty |= self.files.remove(path).is_some();
+        }
+        Some(content)
+    }
+
+    /// Digest of `content` (read from `path`) in about `max_chars`, built on
+    /// first use for that content and budget
+    pub(crate) fn digest(&mut self, path: &str, content: &str, max_chars: usize) -> Option<String> {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        let key = format!("{:016x}:{}", hasher.finish(), max_chars);
+        let tick = self.next_tick();
+        self.usage.digests.insert(key.clone(), tick);
+        if let Some(cached) = self.digests.get(&key) {
+            return cached.digest.clone();
+        }
+
+        let edited = attentive_repo::git_changed_lines(&self.root.join(path).to_string_lossy());
+        let digest = attentive_repo::digest_source(content, path, max_chars, &edited);
+        self.digests.insert(
+            key,
+            CachedDigest {
+                digest: digest.clone(),
+            },
+        );
+        self.dirty = true;
+        if self.digests.len() > MAX_DIGESTS
+            && let Some(oldest) = least_recent(&self.digests, &self.usage.digests)
+        {
+            self.digests.remove(&oldest);
+            self.usage.digests.remove(&oldest);
+        }
+        digest
+    }
+
+    /// Headings and signatures of the file at `path`, with its imports,
+    /// read and extracted again only once the file changes on disk. Only the
+    /// outline is kept, not the content, as with WARM and COLD files.
+    pub(crate) fn outline(&mut self, path: &str) -> Option<(Vec<String>, Option<FileSymbols>)> {
+        let stamp = self.current_stamp(path);
+        if let Some(cached) = self.tocs.get(path)
+            && stamp == Some((cached.mtime, cached.size))
+        {
+            let outline = (cached.entries.clone(), cached.imports.clone());
+            let tick = self.next_tick();
+            self.usage.tocs.insert(path.to_string(), tick);
+            return Some(outline);
+        }
+        let Some((mtime, size)) = stamp else {
+            self.dirty |= self.tocs.remove(path).is_some();
+            return None;
+        };
+        let prefetched = self
+            .prefetched
+            .get_mut(path)
+            .and_then(|prefetched| prefetched.content.take());
+        let content = match (prefetched, self.files.get(path)) {
+            (Some(content), _) => content,
+            (None, Some(cached)) if (cached.mtime, cached.size) == (mtime, size) => {
+                cached.content.clone()
+            }
+            _ => std::fs::read_to_string(self.root.join(path)).ok()?,
+        };
+        let entries = self.toc(path, &content);
+        let imports = match self.tocs.get(path) {
+            Some(cached) => cached.imports.clone(),
+            None => imports_of(path, &content),
+        };
+        Some((entries, imports))
+    }
+
+    /// Headings and signatures of `content` (read from `path`), extracted
+    /// again only once the file changes on disk
+    pub(crate) fn toc(&mut self, path: &str, content: &str) -> Vec<String> {
+        let stamp = self.current_stamp(path);
+        let tick = self.next_tick();
+        self.usage.tocs.insert(path.to_string(), tick);
+        if let Some(cached) = self.tocs.get(path)
+            && stamp == Some((cached.mtime, cached.size))
+        {
+            return cached.entries.clone();
+        }
+
+        let entries: Vec<String> = super::hooks::extract_toc_entries(content)
+            .into_iter()
+            .map(str::to_string)
+            .collect();
+        let Some((mtime, size)) = stamp else {
+            self.dirty |= self.tocs.remove(path).is_some();
+            return entries;
+        };
+        self.tocs.insert(
+            path.to_string(),
+            CachedToc {
+                mtime,
+                size,
+                entries: entries.clone(),
+                imports: imports_of(path, content),
+            },
+        );
+        self.dirty = true;
+        if self.tocs.len() > MAX_TOCS
+            && let Some(oldest) = least_recent(&self.tocs, &self.usage.tocs)
+        {
+            self.tocs.remove(&oldest);
+            self.usage.tocs.remove(&oldest);
+        }
+        entries
+    }
+
+    /// Drop least recently used entries until the cache fits its cap
+    fn evict(&mut self) {
+        let mut total: usize = self.files.values().map(|f| f.content.len()).sum();
+        if total <= MAX_CACHE_BYTES {
+            return;
+        }
+        let mut by_age: Vec<(u64, String)> = self
+            .files
+            .keys()
+            .map(|path| {
+                let used = self.usage.files.get(path).copied().unwrap_or(0);
+                (used, path.clone())
+            })
+            .collect();
+        by_age.sort();
+        for (_, path) in by_age {
+            if total <= MAX_CACHE_BYTES {
+                break;
+            }
+            if let Some(evicted) = self.files.remove(&path) {
+                self.usage.files.remove(&path);
+                total -= evicted.content.len();
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_reuses_content_until_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        let path = file.to_str().unwrap();
+        std::fs::write(&file, "fn a() {}").unwrap();
+
+        let mut cache = ContentCache::default();
+        assert_eq!(cache.read(path).as_deref(), Some("fn a() {}"));
+        // Same stamp: the cached copy is served even if disk content differs
+        cache.files.get_mut(path).unwrap().content = "cached".to_string();
+        assert_eq!(cache.read(path).as_deref(), Some("cached"));
+
+        std::fs::write(&file, "fn a() { changed() }").unwrap();
+        assert_eq!(cache.read(path).as_deref(), Some("fn a() { changed() }"));
+
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(cache.read(path), None);
+        assert!(cache.files.is_empty());
+    }
+
+    #[test]
+    fn test_toc_is_reused_until_file_changes() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        let path = file.to_str().unwrap();
+        std::fs::write(&file, "pub fn a() {}\nlet x = 1;\n").unwrap();
+
+        let mut cache = ContentCache::default();
+        let content = cache.read(path).unwrap();
+        assert_eq!(cache.toc(path, &content), vec!["pub fn a() {}"]);
+        cache.tocs.get_mut(path).unwrap().entries = vec!["cached".to_string()];
+        assert_eq!(cache.toc(path, &content), vec!["cached"]);
+
+        std::fs::write(&file, "pub fn a() {}\npub fn b() {}\n").unwrap();
+        let content = cache.read(path).unwrap();
+        assert_eq!(
+            cache.toc(path, &content),
+            vec!["pub fn a() {}", "pub fn b() {}"]
+        );
+
+        std::fs::remove_file(&file).unwrap();
+        assert_eq!(cache.read(path), None);
+        assert!(cache.tocs.is_empty());
+    }
+
+    #[test]
+    fn test_unchanged_outline_skips_reading_the_file() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        let path = file.to_str().unwrap();
+        std::fs::write(&file, "use crate::router;\npub fn a() {}\n").unwrap();
+
+        let mut cache = ContentCache::default();
+        let (entries, imports) = cache.outline(path).unwrap();
+        assert_eq!(entries, vec!["pub fn a() {}"]);
+        let imports = imports.unwrap();
+        assert_eq!(imports.imports, vec!["crate::router;"]);
+        assert!(imports.symbols.is_empty());
+
+        // Neither prefetch nor outline reads the file while it's unchanged
+        assert!(cache.files.is_empty(), "outlines don't keep content");
+        cache.prefetch([&path.to_string()]);
+        assert!(cache.prefetched[path].content.is_none());
+        assert_eq!(cache.outline(path).unwrap().0, entries);
+        assert!(cache.files.is_empty());
+
+        std::fs::write(&file, "pub fn b() {}\n").unwrap();
+        cache.prefetched.clear();
+        let (entries, imports) = cache.outline(path).unwrap();
+        assert_eq!(entries, vec!["pub fn b() {}"]);
+        assert!(imports.unwrap().imports.is_empty());
+    }
+
+    #[test]
+    fn test_prefetch_reads_files_for_later_reads() {
+        let dir = tempfile::tempdir().unwrap();
+        let paths: Vec<String> = (0..10)
+            .map(|i| {
+                let file = dir.path().join(format!("f{}.rs", i));
+                std::fs::write(&file, format!("fn f{}() {{}}", i)).unwrap();
+                file.to_str().unwrap().to_string()
+            })
+            .collect();
+        let missing = dir.path().join("missing.rs").to_str().unwrap().to_string();
+
+        let mut cache = ContentCache::default();
+        cache.read(&paths[0]);
+        cache.prefetch(paths.iter().chain([&missing]));
+        assert_eq!(cache.prefetched.len(), 11);
+        // Fresh in the cache, so not read again
+        assert!(cache.prefetched[&paths[0]].content.is_none());
+        assert!(cache.prefetched[&paths[1]].content.is_some());
+        assert!(cache.prefetched[&missing].stamp.is_none());
+
+        for (i, path) in paths.iter().enumerate() {
+            assert_eq!(cache.read(path), Some(format!("fn f{}() {{}}", i)));
+        }
+        assert_eq!(cache.read(&missing), None);
+        assert!(cache.prefetched.values().all(|p| p.content.is_none()));
+    }
+
+    #[test]
+    fn test_digest_is_cached_per_content_and_budget() {
+        let content: String = (0..200)
+            .map(|i| format!("fn f{i}() {{\n    {i}\n}}\n"))
+            .collect();
+        let mut cache = ContentCache::default();
+        let digest = cache.digest("/nonexistent/a.rs", &content, 500).unwrap();
+        assert!(digest.contains("[digest:"));
+        assert_eq!(cache.digests.len(), 1);
+
+        let cached = Some("cached".to_string());
+        cache.digests.values_mut().next().unwrap().digest = cached.clone();
+        assert_eq!(cache.digest("/nonexistent/a.rs", &content, 500), cached);
+        assert_ne!(cache.digest("/nonexistent/a.rs", &content, 800), cached);
+
+        for budget in 0..MAX_DIGESTS {
+            cache.digest("/nonexistent/a.rs", &content, 1000 + budget);
+        }
+        assert_eq!(cache.digests.len(), MAX_DIGESTS);
+    }
+
+    #[test]
+    fn test_persists_and_evicts_least_recently_used() {
+        let dir = tempfile::tempdir().unwrap();
+        let big = "x".repeat(MAX_ENTRY_BYTES as usize);
+        let paths: Vec<String> = (0..10)
+            .map(|i| {
+                let file = dir.path().join(format!("f{}.txt", i));
+                std::fs::write(&file, &big).unwrap();
+                file.to_str().unwrap().to_string()
+            })
+            .collect();
+
+        let mut cache = ContentCache::default();
+        cache.read(&paths[0]);
+        for path in &paths[1..] {
+            cache.read(path);
+            // Keep the first file in use
+            cache.read(&paths[0]);
+        }
+        let cached = cache.files.values().map(|f| f.content.len()).sum::<usize>();
+        assert!(cached <= MAX_CACHE_BYTES);
+        assert!(cache.files.contains_key(&paths[0]));
+        assert!(!cache.files.contains_key(&paths[1]));
+        assert!(cache.files.contains_key(&paths[9]));
+
+        let cache_path = dir.path().join("content_cache.json");
+        cache.save(&cache_path).unwrap();
+        let loaded = ContentCache::load(&cache_path);
+        assert_eq!(loaded.files.len(), cache.files.len());
+        assert_eq!(loaded.usage.files, cache.usage.files);
+        assert!(!loaded.dirty);
+    }
+
+    #[test]
+    fn test_hits_rewrite_only_usage() {
+        let dir = tempfile::tempdir().unwrap();
+        let file = dir.path().join("a.rs");
+        let path = file.to_str().unwrap();
+        std::fs::write(&file, "pub fn a() {}\n").unwrap();
+        let cache_path = dir.path().join("content_cache.json");
+
+        let mut cache = ContentCache::default();
+        let content = cache.read(path).unwrap();
+        cache.toc(path, &content);
+        assert!(cache.dirty);
+        cache.save(&cache_path).unwrap();
+        let written = std::fs::read_to_string(&cache_path).unwrap();
+        assert!(!written.contains('\n') && !written.contains("last_used"));
+
+        // Hits move the usage clock but leave the contents alone
+        let mut cache = ContentCache::load(&cache_path);
+        let before = cache.usage.files[path];
+        std::fs::write(&cache_path, "not rewritten").unwrap();
+        cache.read(path);
+        cache.toc(path, &content);
+        assert!(!cache.dirty && cache.used);
+        cache.save(&cache_path).unwrap();
+        assert_eq!(
+            std::fs::read_to_string(&cache_path).unwrap(),
+            "not rewritten"
+        );
+        let usage: Usage = attentive_telemetry::read_versioned(&usage_path(&cache_path)).unwrap();
+        assert!(usage.files[path] > before);
+    }
+}
@@ -0,0 +1,299 @@
+//! Dedupe pass over the assembled prompt context
+//!
+//! Sections are built independently, so the same text can reach the context
+//! twice: a memory repeating a WARM file's summary, a plugin listing HOT files
+//! by name. Before the context is emitted, lines already shown are dropped
+//! from later sections and a list naming every HOT file collapses to one item.
+//! HOT file content is never edited.
+//!
+//! Lines in a file's section only count as repeats of that file's earlier
+//! sections, since two files can share a signature without sharing a meaning.
+//! Sections about no file in particular are checked against everything above.
+
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+/// Lines shorter than this (trimmed) are too generic to call repeats
+const MIN_DEDUPE_LINE_CHARS: usize = 16;
+
+/// Fewest HOT files a list must name before it's collapsed, as one item
+/// saves nothing
+const MIN_COLLAPSED_ITEMS: usize = 2;
+
+/// `line` as compared for repeats: trimmed, without a list marker
+fn line_key(line: &str) -> &str {
+    let line = line.trim();
+    line.strip_prefix("- ")
+        .or_else(|| line.strip_prefix("* "))
+        .unwrap_or(line)
+}
+
+/// One section of the assembled context
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct ContextPart {
+    pub text: String,
+    /// File the section shows, for HOT and WARM sections
+    pub file: Option<String>,
+    /// Whether the section holds a HOT file's content
+    pub hot: bool,
+}
+
+impl ContextPart {
+    pub fn new(text: String) -> Self {
+        Self {
+            text,
+            file: None,
+            hot: false,
+        }
+    }
+
+    pub fn for_file(text: String, file: &str, hot: bool) -> Self {
+        Self {
+            text,
+            file: Some(file.to_string()),
+            hot,
+        }
+    }
+}
+
+/// Non-empty parts' text joined with `separator`
+pub(crate) fn join_parts(parts: &[ContextPart], separator: &str) -> String {
+    parts
+        .iter()
+        .filter(|p| !p.text.is_empty())
+        .map(|p| p.text.as_str())
+        .collect::<Vec<_>>()
+        .join(separator)
+}
+
+/// Drop repeated lines and collapse HOT file lists across `parts`, in order.
+/// A section left with nothing but its first line is emptied. Returns the
+/// estimated tokens saved.
+pub(crate) fn dedupe(parts: &mut [ContextPart]) -> usize {
+    let hot_files: Vec<String> = parts
+        .iter()
+        .filter(|p| p.hot)
+        .filter_map(|p| p.file.clone())
+        .collect();
+    let mut seen: HashSet<String> = HashSet::new();
+    let mut seen_by_file: HashMap<String, HashSet<String>> = HashMap::new();
+    let mut saved = 0;
+
+    for part in parts.iter_mut() {
+        let before = attentive_telemetry::estimate_tokens(&part.text);
+        if !part.hot {
+            let file_seen = part.file.as_ref().map(|f| seen_by_file.remove(f));
+            let mut scope = match file_seen {
+                Some(file_seen) => file_seen.unwrap_or_default(),
+                None => seen.clone(),
+            };
+            let mut lines: Vec<&str> = Vec::new();
+            let mut dropped = false;
+            for (i, line) in part.text.lines().enumerate() {
+                let key = line_key(line);
+                let repeat =
+                    i > 0 && key.len() >= MIN_DEDUPE_LINE_CHARS && !scope.insert(key.to_string());
+                if repeat {
+                    dropped = true;
+                } else {
+                    lines.push(line);
+                }
+            }
+            let mut text = if dropped && lines.len() <= 1 {
+                String::new()
+            } else {
+                lines.join("\n")
+            };
+            if part.file.is_none() {
+                text = collapse_hot_list(&text, &hot_files);
+            }
+            if let Some(file) = &part.file {
+                seen_by_file.insert(file.clone(), scope);
+            }
+            part.text = text;
+        }
+        for line in part.text.lines().skip(1) {
+            let key = line_key(line);
+            if key.len() < MIN_DEDUPE_LINE_CHARS {
+                continue;
+            }
+            seen.insert(key.to_string());
+            if let Some(file) = &part.file {
+                seen_by_file
+                    .entry(file.clone())
+                    .or_default()
+                    .insert(key.to_string());
+            }
+        }
+        saved += before.saturating_sub(attentive_telemetry::estimate_tokens(&part.text));
+    }
+    saved
+}
+
+/// The file a list item like "- `router.rs`" or "- src/router.rs" names
+fn list_item_file(line: &str) -> Option<&str> {
+    let item = line.trim().strip_prefix("- ")?.trim();
+    let item = item
+        .strip_prefix('`')
+        .and_then(|i| i.strip_suffix('`'))
+        .unwrap_or(item);
+    (!item.is_empty() && !item.contains(char::is_whitespace)).then_some(item)
+}
+
+/// The one HOT file `name` (a path or bare file name) can refer to
+fn hot_match<'a>(name: &str, hot_files: &'a [String]) -> Option<&'a str> {
+    let mut matches = hot_files.iter().filter(|hot| {
+        *hot == name
+            || hot.ends_with(&format!("/{}", name))
+            || Path::new(hot).file_name().and_then(|n| n.to_str()) == Some(name)
+    });
+    let only = matches.next()?;
+    matches.next().is_none().then_some(only.as_str())
+}
+
+/// `text` with list items naming every HOT file replaced by one item. Lists
+/// that leave any HOT file out are kept, since they say something about it.
+fn collapse_hot_list(text: &str, hot_files: &[String]) -> String {
+    if hot_files.len() < MIN_COLLAPSED_ITEMS {
+        return text.to_string();
+    }
+    let items: Vec<(usize, &str)> = text
+        .lines()
+        .enumerate()
+        .filter_map(|(i, line)| Some((i, hot_match(list_item_file(line)?, hot_files)?)))
+        .collect();
+    let named: HashSet<&str> = items.iter().map(|(_, file)| *file).collect();
+    if named.len() < hot_files.len() {
+        return text.to_string();
+    }
+    let first = items[0].0;
+    let collapsed: HashSet<usize> = items.iter().map(|(i, _)| *i).collect();
+    text.lines()
+        .enumerate()
+        .filter_map(|(i, line)| {
+            if i == first {
+                Some(format!("- all {} HOT files above", hot_files.len()))
+            } else if collapsed.contains(&i) {
+                None
+            } else {
+                Some(line.to_string())
+            }
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hot(file: &str, content: &str) -> ContextPart {
+        ContextPart::for_file(format!("[HOT] {}\n{}", file, content), file, true)
+    }
+
+    #[test]
+    fn test_dedupe_drops_lines_shown_above() {
+        let summary = "Reworked decay so pinned files never drop below WARM";
+        let mut parts = vec![
+            ContextPart::for_file(
+                format!("[WARM] src/router.rs (summary)\n{}", summary),
+                "src/router.rs",
+                false,
+            ),
+            ContextPart::new(format!(
+                "## Project memory\n- {}\n- Config loads project files last",
+                summary
+            )),
+            ContextPart::new(format!("## Notes\n- {}", summary)),
+        ];
+        let saved = dedupe(&mut parts);
+        assert_eq!(
+            parts[1].text,
+            "## Project memory\n- Config loads project files last"
+        );
+        // Nothing left but the header
+        assert_eq!(parts[2].text, "");
+        assert!(saved > 0);
+        assert_eq!(
+            join_parts(&parts, "\n\n"),
+            format!(
+                "[WARM] src/router.rs (summary)\n{}\n\n## Project memory\n- Config loads project files last",
+                summary
+            )
+        );
+    }
+
+    #[test]
+    fn test_dedupe_keeps_hot_content_and_other_files_lines() {
+        let signature = "pub fn new(config: Config) -> Self {";
+        let mut parts = vec![
+            hot(
+                "src/router.rs",
+                &format!("{}\n    todo!()\n}}\n{}", signature, signature),
+            ),
+            ContextPart::for_file(
+                format!(
+                    "[WARM] src/plugin.rs (TOC)\n{}\npub fn name(&self) -> &str",
+                    signature
+                ),
+                "src/plugin.rs",
+                false,
+            ),
+            ContextPart::for_file(
+                format!(
+                    "[WARM] src/router.rs (TOC)\n{}\npub fn update_attention(&mut self)",
+                    signature
+                ),
+                "src/router.rs",
+                false,
+            ),
+        ];
+        let original = parts.clone();
+        dedupe(&mut parts);
+        assert_eq!(parts[0], original[0]);
+        assert_eq!(parts[1], original[1]);
+        assert_eq!(
+            parts[2].text,
+            "[WARM] src/router.rs (TOC)\npub fn update_attention(&mut self)"
+        );
+    }
+
+    #[test]
+    fn test_collapse_hot_file_list() {
+        let list = "**VerifyFirst: files verified (safe to edit):**\n- `router.rs`\n- `config.rs`";
+        let mut parts = vec![
+            hot("src/router.rs", "fn route() {}"),
+            hot("src/config.rs", "fn load() {}"),
+            ContextPart::new(format!("{}\n- `main.rs`", list)),
+        ];
+        dedupe(&mut parts);
+        assert_eq!(
+            parts[2].text,
+            "**VerifyFirst: files verified (safe to edit):**\n- all 2 HOT files above\n- `main.rs`"
+        );
+
+        // A list missing a HOT file, or one HOT file, says more as it is
+        let mut parts = vec![
+            hot("src/router.rs", "fn route() {}"),
+            hot("src/config.rs", "fn load() {}"),
+            hot("src/lib.rs", "mod router;"),
+            ContextPart::new(list.to_string()),
+        ];
+        dedupe(&mut parts);
+        assert_eq!(parts[3].text, list);
+        assert_eq!(
+            collapse_hot_list("- `a.rs`", &["a.rs".to_string()]),
+            "- `a.rs`"
+        );
+    }
+
+    #[test]
+    fn test_hot_match_needs_one_file() {
+        let hot = ["src/a/mod.rs".to_string(), "src/b/mod.rs".to_string()];
+        assert_eq!(hot_match("mod.rs", &hot), None);
+        assert_eq!(hot_match("b/mod.rs", &hot), Some("src/b/mod.rs"));
+        assert_eq!(list_item_file("- `src/a.rs`"), Some("src/a.rs"));
+        assert_eq!(list_item_file("- ... and 3 more"), None);
+        assert_eq!(list_item_file("plain text"), None);
+    }
+}
@@ -85,23 +85,26 @@ fn check_files(paths: Option<&Paths>) -> Vec<(String, String)> {
     };
 
     for (name, path) in files_to_check {
-        let status = if path.exists() {
-            match std::fs::read_to_string(&path) {
-                Ok(content) => {
-                    if name.ends_with(".jsonl") {
-                        let lines = content.lines().count();
-                        format!("OK  ({} lines)", lines)
-                    } else {
-                        match serde_json::from_str::<serde_json::Value>(&content) {
-                            Ok(_) => "OK ".to_string(),
-                            Err(e) => format!("ERR (invalid JSON: {})", e),
-                        }
-                    }
-                }
+        let status = if !path.exists() {
+            "MISS".to_string()
+        } else if name.ends_with(".jsonl") {
+            match attentive_telemetry::check_jsonl(&path) {
+                Ok(h) if h.corrupt > 0 => format!(
+                    "WARN ({} lines, {} corrupt; run `attentive repair`)",
+                    h.valid + h.corrupt,
+                    h.corrupt
+                ),
+                Ok(h) => format!("OK  ({} lines)", h.valid),
                 Err(e) => format!("ERR (read error: {})", e),
             }
         } else {
-            "MISS".to_string()
+            match std::fs::read_to_string(&path) {
+                Ok(content) => match serde_json::from_str::<serde_json::Value>(&content) {
+                    Ok(_) => "OK ".to_string(),
+                    Err(e) => format!("ERR (invalid JSON: {})", e),
+                },
+                Err(e) => format!("ERR (read error: {})", e),
+            }
         };
         checks.push((name, status));
     }
@@ -0,0 +1,272 @@
+//! Inline routing directives in prompts
+//!
+//! `@attentive pin router.rs`, `@attentive forget docs/` or `@attentive focus
+//! src/auth/**` in a prompt steer routing without leaving the conversation.
+//! Directives are stripped before the prompt is routed, and confirmed at the
+//! top of the injected context. Pins and focus last for the session; `forget`
+//! drops matching files from attention state once.
+
+use super::hooks::{read_session_key, write_session_key};
+use attentive_core::{AttentionState, Config, PathPatterns};
+use attentive_telemetry::Paths;
+use serde::{Deserialize, Serialize};
+
+/// Marks a directive in a prompt
+const PREFIX: &str = "@attentive";
+
+/// Session-state key holding the session's pins and focus
+const DIRECTIVES_KEY: &str = "directives";
+
+#[derive(Debug, Clone, PartialEq)]
+enum Directive {
+    Pin(String),
+    Unpin(String),
+    Forget(String),
+    Focus(String),
+    Unfocus,
+}
+
+/// Pins and focus set by directives, kept until the session ends
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SessionDirectives {
+    pinned: Vec<String>,
+    focus: Vec<String>,
+}
+
+/// Start and end of the next word of `line` at or after `from`
+fn next_word(line: &str, from: usize) -> Option<(usize, usize)> {
+    let start = from + line[from..].find(|c: char| !c.is_whitespace())?;
+    let end = line[start..]
+        .find(char::is_whitespace)
+        .map_or(line.len(), |i| start + i);
+    Some((start, end))
+}
+
+/// `prompt` without its directives, and each directive (or why it's invalid)
+fn parse(prompt: &str) -> (String, Vec<Result<Directive, String>>) {
+    let mut stripped = Vec::new();
+    let mut directives = Vec::new();
+    for line in prompt.lines() {
+        let mut kept = String::new();
+        let mut rest = 0;
+        while let Some(at) = line[rest..].find(PREFIX).map(|i| rest + i) {
+            let after = at + PREFIX.len();
+            let attached = line[..at]
+                .chars()
+                .next_back()
+                .is_some_and(|c| !c.is_whitespace())
+                || line[after..]
+                    .chars()
+                    .next()
+                    .is_some_and(|c| !c.is_whitespace());
+            if attached {
+                kept.push_str(&line[rest..after]);
+                rest = after;
+                continue;
+            }
+            kept.push_str(&line[rest..at]);
+            let Some((verb_start, verb_end)) = next_word(line, after) else {
+                directives.push(Err(format!("`{}` needs an action", PREFIX)));
+                rest = line.len();
+                break;
+            };
+            let verb = &line[verb_start..verb_end];
+            rest = verb_end;
+            let mut argument = |make: fn(String) -> Directive| match next_word(line, verb_end) {
+                Some((start, end)) => {
+                    rest = end;
+                    Ok(make(line[start..end].to_string()))
+                }
+                None => Err(format!("`{} {}` needs a path or glob", PREFIX, verb)),
+            };
+            directives.push(match verb {
+                "pin" => argument(Directive::Pin),
+                "unpin" => argument(Directive::Unpin),
+                "forget" => argument(Directive::Forget),
+                "focus" => argument(Directive::Focus),
+                "unfocus" => Ok(Directive::Unfocus),
+                _ => Err(format!(
+                    "unknown directive `{} {}` (use pin, unpin, forget, focus or unfocus)",
+                    PREFIX, verb
+                )),
+            });
+        }
+        kept.push_str(&line[rest..]);
+        let kept = kept.split_whitespace().collect::<Vec<_>>().join(" ");
+        if !kept.is_empty() || !line.contains(PREFIX) {
+            stripped.push(kept);
+        }
+    }
+    (stripped.join("\n").trim().to_string(), directives)
+}
+
+/// Whether `pattern` names a single file rather than a directory or glob
+fn is_literal(pattern: &str) -> bool {
+    !pattern.ends_with('/') && !pattern.contains(['*', '?', '[', '{'])
+}
+
+/// Strip directives from `prompt` and apply them: `forget` to `state`, pins
+/// and focus to the session (saved only with `persist`). The session's pins
+/// and focus are then layered onto `config`. Returns the prompt to route and a
+/// confirmation block for the context, empty when there were no directives.
+pub(crate) fn apply(
+    paths: &Paths,
+    prompt: &str,
+    config: &mut Config,
+    state: &mut AttentionState,
+    persist: bool,
+) -> (String, String) {
+    let mut session: SessionDirectives =
+        read_session_key(paths, DIRECTIVES_KEY).unwrap_or_default();
+    let (stripped, directives) = if prompt.contains(PREFIX) {
+        parse(prompt)
+    } else {
+        (String::new(), Vec::new())
+    };
+    if directives.is_empty() {
+        config.pinned_files.extend(session.pinned);
+        config.focus_files.extend(session.focus);
+        return (prompt.to_string(), String::new());
+    }
+
+    let canonicalizer = paths.canonicalizer().ok();
+    let canon = |pattern: String| match &canonicalizer {
+        Some(c) if is_literal(&pattern) => c.canonicalize(&pattern),
+        _ => pattern,
+    };
+    let mut confirmations = Vec::new();
+    for directive in directives {
+        let confirmation = match directive {
+            Ok(Directive::Pin(pattern)) => {
+                let pattern = canon(pattern);
+                // The pinned floor only lifts files the router already scores
+                if is_literal(&pattern) {
+                    state.scores.entry(pattern.clone()).or_insert(0.0);
+                }
+                let line = format!("Pinned `{}` for this session", pattern);
+                if !session.pinned.contains(&pattern) {
+                    session.pinned.push(pattern);
+                }
+                line
+            }
+            Ok(Directive::Unpin(pattern)) => {
+                let pattern = canon(pattern);
+                let before = session.pinned.len();
+                session.pinned.retain(|p| *p != pattern);
+                if session.pinned.len() < before {
+                    format!("Unpinned `{}`", pattern)
+                } else {
+                    format!("`{}` was not pinned this session", pattern)
+                }
+            }
+            Ok(Directive::Forget(pattern)) => {
+                let pattern = canon(pattern);
+                let matcher = PathPatterns::new(std::slice::from_ref(&pattern));
+                let before = state.scores.len();
+                state.scores.retain(|path, _| !matcher.matches(path));
+                state
+                    .consecutive_turns
+                    .retain(|path, _| !matcher.matches(path));
+                state
+                    .recently_edited
+                    .retain(|path, _| !matcher.matches(path));
+                session.pinned.retain(|p| !matcher.matches(p));
+                format!(
+                    "Forgot {} file(s) matching `{}`",
+                    before - state.scores.len(),
+                    pattern
+                )
+            }
+            Ok(Directive::Focus(pattern)) => {
+                let pattern = canon(pattern);
+                let line = format!(
+                    "Focused on `{}` for this session; other files are demoted",
+                    pattern
+                );
+                if !session.focus.contains(&pattern) {
+                    session.focus.push(pattern);
+                }
+                line
+            }
+            Ok(Directive::Unfocus) => {
+                session.focus.clear();
+                "Focus cleared".to_string()
+            }
+            Err(error) => format!("Ignored {}", error),
+        };
+        confirmations.push(format!("- {}", confirmation));
+    }
+
+    if persist && let Err(e) = write_session_key(paths, DIRECTIVES_KEY, &session) {
+        tracing::warn!("failed to save directives: {}", e);
+    }
+    config.pinned_files.extend(session.pinned);
+    config.focus_files.extend(session.focus);
+    let block = format!("## Attentive directives\n{}", confirmations.join("\n"));
+    (stripped, block)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_telemetry::TestEnv;
+
+    #[test]
+    fn test_parse_strips_directives() {
+        let (prompt, directives) = parse(
+            "@attentive pin router.rs\nfix the bug @attentive focus src/auth/** in login\n@attentive unfocus",
+        );
+        assert_eq!(prompt, "fix the bug in login");
+        assert_eq!(
+            directives,
+            vec![
+                Ok(Directive::Pin("router.rs".to_string())),
+                Ok(Directive::Focus("src/auth/**".to_string())),
+                Ok(Directive::Unfocus),
+            ]
+        );
+
+        let (prompt, directives) =
+            parse("mail me@attentive.dev or me@attentive now\n@attentive frob x\n@attentive pin");
+        assert_eq!(prompt, "mail me@attentive.dev or me@attentive now\nx");
+        assert!(
+            directives[0]
+                .as_ref()
+                .unwrap_err()
+                .contains("unknown directive")
+        );
+        assert!(directives[1].as_ref().unwrap_err().contains("needs a path"));
+    }
+
+    #[test]
+    fn test_apply_persists_pins_and_forgets_files() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        std::fs::create_dir_all(paths.project_dir().unwrap()).unwrap();
+        let mut state = AttentionState::new();
+        state.scores.insert("docs/a.md".to_string(), 0.9);
+        state.scores.insert("docs/b.md".to_string(), 0.5);
+        state.scores.insert("src/lib.rs".to_string(), 0.5);
+
+        let mut config = Config::new();
+        let (prompt, block) = apply(
+            &paths,
+            "@attentive forget docs/ @attentive pin src/ refactor this",
+            &mut config,
+            &mut state,
+            true,
+        );
+        assert_eq!(prompt, "refactor this");
+        assert!(block.contains("- Forgot 2 file(s) matching `docs/`"));
+        assert!(block.contains("- Pinned `src/` for this session"));
+        assert_eq!(state.scores.len(), 1);
+        assert_eq!(config.pinned_files, vec!["src/"]);
+
+        // Later prompts keep the session's pins without repeating them
+        let mut config = Config::new();
+        let (prompt, block) = apply(&paths, "next step", &mut config, &mut state, true);
+        assert_eq!(prompt, "next step");
+        assert!(block.is_empty());
+        assert_eq!(config.pinned_files, vec!["src/"]);
+    }
+}
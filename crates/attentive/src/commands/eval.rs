@@ -0,0 +1,303 @@
+//! Routing quality against labeled relevance judgments
+//!
+//! A suite lists prompts with the files each should bring in. Every case is
+//! routed from the project's saved attention state (nothing is persisted) and
+//! scored on precision@k, recall and reciprocal rank. Each run is appended to
+//! the project's eval history so routing changes can be compared.
+
+use attentive_telemetry::{EvalRecord, PathCanonicalizer, Paths, append_jsonl, read_jsonl};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::path::Path;
+
+use super::hooks::{load_attention_state, load_config, load_learner, run_prompt_pipeline};
+use super::suite::load_suite_file;
+
+/// Suite used when none is given, at the repo root
+pub(crate) const EVAL_SUITE_FILE: &str = ".attentive-eval.yaml";
+
+#[derive(Debug, Clone, Serialize)]
+struct CaseScore {
+    prompt: String,
+    /// Injected files in injection order (HOT, then WARM)
+    ranked: Vec<String>,
+    /// Expected files that were not injected
+    missed: Vec<String>,
+    precision_at_k: f64,
+    recall: f64,
+    reciprocal_rank: f64,
+}
+
+#[derive(Debug, Serialize)]
+struct EvalReport<'a> {
+    #[serde(flatten)]
+    record: &'a EvalRecord,
+    results: &'a [CaseScore],
+}
+
+/// Score one case's ranking against its expected files
+fn score_case(
+    prompt: &str,
+    ranked: &[String],
+    expected: &[String],
+    k: usize,
+    canonicalizer: &PathCanonicalizer,
+) -> CaseScore {
+    let relevant: HashSet<String> = expected.iter().map(|f| canonicalizer.key(f)).collect();
+    let keys: Vec<String> = ranked.iter().map(|f| canonicalizer.key(f)).collect();
+    let hits_at_k = keys
+        .iter()
+        .take(k)
+        .filter(|f| relevant.contains(*f))
+        .count();
+    let injected: HashSet<&String> = keys.iter().collect();
+    let hits = relevant.iter().filter(|f| injected.contains(f)).count();
+    let first_hit = keys.iter().position(|f| relevant.contains(f));
+    CaseScore {
+        prompt: prompt.to_string(),
+        ranked: ranked.to_vec(),
+        missed: expected
+            .iter()
+            .filter(|f| !injected.contains(&canonicalizer.key(f)))
+            .cloned()
+            .collect(),
+        precision_at_k: hits_at_k as f64 / k.max(1) as f64,
+        recall: if relevant.is_empty() {
+            0.0
+        } else {
+            hits as f64 / relevant.len() as f64
+        },
+        reciprocal_rank: first_hit.map_or(0.0, |i| 1.0 / (i + 1) as f64),
+    }
+}
+
+/// Averages over cases
+fn summarize(suite: String, k: usize, scores: &[CaseScore]) -> EvalRecord {
+    let mean = |f: fn(&CaseScore) -> f64| {
+        if scores.is_empty() {
+            0.0
+        } else {
+            scores.iter().map(f).sum::<f64>() / scores.len() as f64
+        }
+    };
+    EvalRecord {
+        timestamp: attentive_telemetry::now(),
+        version: env!("CARGO_PKG_VERSION").to_string(),
+        suite,
+        cases: scores.len(),
+        k,
+        precision_at_k: mean(|s| s.precision_at_k),
+        recall: mean(|s| s.recall),
+        mrr: mean(|s| s.reciprocal_rank),
+    }
+}
+
+/// Latest earlier run of the same suite and k
+fn previous_run<'a>(history: &'a [EvalRecord], record: &EvalRecord) -> Option<&'a EvalRecord> {
+    history
+        .iter()
+        .rev()
+        .find(|r| r.suite == record.suite && r.k == record.k)
+}
+
+fn format_report(
+    record: &EvalRecord,
+    scores: &[CaseScore],
+    previous: Option<&EvalRecord>,
+) -> String {
+    let title = format!("Routing Eval: {}", record.suite);
+    let mut lines = vec![title.clone(), "=".repeat(title.chars().count())];
+    for s in scores {
+        let mut prompt: String = s.prompt.chars().take(50).collect();
+        if s.prompt.chars().count() > 50 {
+            prompt.push('…');
+        }
+        lines.push(format!(
+            "  P@{} {:>5.1}%  R {:>5.1}%  RR {:.2}  {}",
+            record.k,
+            s.precision_at_k * 100.0,
+            s.recall * 100.0,
+            s.reciprocal_rank,
+            prompt
+        ));
+        if !s.missed.is_empty() {
+            lines.push(format!("      missed: {}", s.missed.join(", ")));
+        }
+    }
+
+    let delta = |now: f64, before: Option<f64>, percent: bool| match before {
+        Some(before) if percent => format!(" ({:+.1} pts)", (now - before) * 100.0),
+        Some(before) => format!(" ({:+.3})", now - before),
+        None => String::new(),
+    };
+    lines.push(String::new());
+    lines.push(format!("Cases: {}", record.cases));
+    lines.push(format!(
+        "Precision@{}: {:.1}%{}",
+        record.k,
+        record.precision_at_k * 100.0,
+        delta(
+            record.precision_at_k,
+            previous.map(|p| p.precision_at_k),
+            true
+        )
+    ));
+    lines.push(format!(
+        "Recall: {:.1}%{}",
+        record.recall * 100.0,
+        delta(record.recall, previous.map(|p| p.recall), true)
+    ));
+    lines.push(format!(
+        "MRR: {:.3}{}",
+        record.mrr,
+        delta(record.mrr, previous.map(|p| p.mrr), false)
+    ));
+    if let Some(previous) = previous {
+        lines.push(format!(
+            "Compared with the run of {} (v{})",
+            previous.timestamp.format("%Y-%m-%d %H:%M"),
+            previous.version
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Route every case in `file` (default: `.attentive-eval.yaml` at the repo
+/// root), report precision@k, recall and MRR, and record the run
+pub fn run(file: Option<&str>, k: usize, json: bool) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    attentive_plugins::base::set_read_only(true);
+
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let suite_path = match file {
+        Some(file) => Path::new(file).to_path_buf(),
+        None => canonicalizer.root().join(EVAL_SUITE_FILE),
+    };
+    if !suite_path.exists() {
+        anyhow::bail!(
+            "No eval suite at {} (YAML: cases: [{{prompt, expected: [files]}}])",
+            suite_path.display()
+        );
+    }
+    let (name, cases) = load_suite_file(&suite_path)?;
+    if cases.is_empty() {
+        println!("No eval cases found");
+        return Ok(());
+    }
+
+    let config = load_config(&paths.home_claude);
+    let learner = load_learner(&paths.learned_state_path()?)
+        .map(|l| l.with_half_life(config.learner_half_life));
+    let base_state = load_attention_state(&paths)?;
+    let oracle = attentive_learn::Oracle::new();
+
+    // Cases are independent: each routes from the same saved state
+    let mut scores = Vec::new();
+    for case in &cases {
+        let mut state = base_state.clone();
+        let mut case_config = config.clone();
+        case_config.apply_task_profile(oracle.classify_task(&case.prompt).as_str());
+        let ranked = match run_prompt_pipeline(
+            &paths,
+            case.prompt.clone(),
+            &mut state,
+            case_config,
+            learner.as_ref(),
+            None,
+        )? {
+            Some(pipeline) => {
+                let mut files = pipeline.hot_files;
+                files.extend(pipeline.warm_files);
+                files
+            }
+            None => Vec::new(),
+        };
+        scores.push(score_case(
+            &case.prompt,
+            &ranked,
+            &case.expected,
+            k,
+            &canonicalizer,
+        ));
+    }
+
+    let record = summarize(name, k, &scores);
+    let history_path = paths.eval_history_path()?;
+    let history: Vec<EvalRecord> = read_jsonl(&history_path)?;
+    if json {
+        let report = EvalReport {
+            record: &record,
+            results: &scores,
+        };
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!(
+            "{}",
+            format_report(&record, &scores, previous_run(&history, &record))
+        );
+    }
+    append_jsonl(&history_path, &record)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn files(names: &[&str]) -> Vec<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn test_score_case_ranking_metrics() {
+        let canon = PathCanonicalizer::new("/repo");
+        let ranked = files(&["src/x.rs", "/repo/src/a.rs", "src/y.rs", "src/b.rs"]);
+        let score = score_case(
+            "p",
+            &ranked,
+            &files(&["src/a.rs", "src/b.rs", "src/c.rs"]),
+            2,
+            &canon,
+        );
+        assert_eq!(score.precision_at_k, 0.5);
+        assert!((score.recall - 2.0 / 3.0).abs() < 1e-9);
+        assert_eq!(score.reciprocal_rank, 0.5);
+        assert_eq!(score.missed, vec!["src/c.rs"]);
+
+        let miss = score_case("p", &[], &files(&["a.rs"]), 5, &canon);
+        assert_eq!(miss.reciprocal_rank, 0.0);
+        assert_eq!(miss.recall, 0.0);
+    }
+
+    #[test]
+    fn test_report_compares_with_previous_run() {
+        let canon = PathCanonicalizer::new("/repo");
+        let scores = vec![
+            score_case("a", &files(&["a.rs"]), &files(&["a.rs"]), 1, &canon),
+            score_case("b", &files(&["x.rs"]), &files(&["b.rs"]), 1, &canon),
+        ];
+        let record = summarize("routing".to_string(), 1, &scores);
+        assert_eq!(record.precision_at_k, 0.5);
+        assert_eq!(record.mrr, 0.5);
+
+        let earlier = EvalRecord {
+            precision_at_k: 0.25,
+            mrr: 0.75,
+            ..record.clone()
+        };
+        let other_k = EvalRecord {
+            k: 5,
+            ..record.clone()
+        };
+        let history = vec![earlier, other_k];
+        let previous = previous_run(&history, &record);
+        assert_eq!(previous.unwrap().precision_at_k, 0.25);
+
+        let report = format_report(&record, &scores, previous);
+        assert!(report.starts_with("Routing Eval: routing\n====================="));
+        assert!(report.contains("      missed: b.rs"));
+        assert!(report.contains("Precision@1: 50.0% (+25.0 pts)"));
+        assert!(report.contains("MRR: 0.500 (-0.250)"));
+        assert!(!format_report(&record, &scores, None).contains("Compared with"));
+    }
+}
@@ -0,0 +1,294 @@
+//! `export heatmap`: where the router spends its context budget
+//!
+//! Joins the current attention scores with how often each file was injected
+//! and used over the recorded turns, and renders the result as JSON or as a
+//! self-contained HTML treemap: area is injection count, color is efficiency
+//! (used / injected).
+
+use attentive_core::AttentionState;
+use attentive_telemetry::{PathCanonicalizer, Paths, TurnRecord, read_jsonl};
+use chrono::{DateTime, Duration, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::fmt::Write as _;
+use std::path::Path;
+
+/// One file in the heatmap
+#[derive(Debug, Clone, PartialEq, Serialize)]
+struct HeatmapFile {
+    path: String,
+    /// Current attention score (0 if the file has none)
+    score: f64,
+    /// Turns in the range that injected the file
+    injections: usize,
+    /// ...and of those, turns that also used it
+    used: usize,
+    /// `used / injections`, or None when never injected
+    efficiency: Option<f64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct Heatmap {
+    project: String,
+    /// Only turns at or after this time were counted
+    since: Option<DateTime<Utc>>,
+    turns: usize,
+    files: Vec<HeatmapFile>,
+}
+
+/// Heatmap of the files in `state` or injected by `turns` since `since`
+fn build_heatmap(
+    project: &str,
+    state: &AttentionState,
+    turns: &[TurnRecord],
+    since: Option<DateTime<Utc>>,
+) -> Heatmap {
+    let turns: Vec<&TurnRecord> = turns
+        .iter()
+        .filter(|t| since.is_none_or(|since| t.timestamp >= since))
+        .collect();
+    let mut counts: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for path in state.scores.keys() {
+        counts.entry(path).or_default();
+    }
+    for turn in &turns {
+        for path in &turn.files_injected {
+            let entry = counts.entry(path).or_default();
+            entry.0 += 1;
+            entry.1 += usize::from(turn.files_used.contains(path));
+        }
+    }
+    let files = counts
+        .into_iter()
+        .map(|(path, (injections, used))| HeatmapFile {
+            path: path.to_string(),
+            score: state.scores.get(path).copied().unwrap_or(0.0),
+            injections,
+            used,
+            efficiency: (injections > 0).then(|| used as f64 / injections as f64),
+        })
+        .collect();
+    Heatmap {
+        project: project.to_string(),
+        since,
+        turns: turns.len(),
+        files,
+    }
+}
+
+/// A directory or file in the treemap, weighted by injections
+#[derive(Debug, Default)]
+struct TreeNode<'a> {
+    children: BTreeMap<&'a str, TreeNode<'a>>,
+    file: Option<&'a HeatmapFile>,
+    weight: f64,
+}
+
+impl<'a> TreeNode<'a> {
+    fn insert(&mut self, file: &'a HeatmapFile) {
+        // Files never injected still get a sliver so they stay visible
+        let weight = file.injections.max(1) as f64;
+        let mut node = self;
+        node.weight += weight;
+        for part in file.path.split('/').filter(|p| !p.is_empty()) {
+            node = node.children.entry(part).or_default();
+            node.weight += weight;
+        }
+        node.file = Some(file);
+    }
+}
+
+/// Red (0) through yellow to green (1); gray when never injected
+fn efficiency_color(efficiency: Option<f64>) -> String {
+    match efficiency {
+        Some(e) => format!("hsl({:.0}, 70%, 45%)", e.clamp(0.0, 1.0) * 120.0),
+        None => "hsl(0, 0%, 60%)".to_string(),
+    }
+}
+
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+/// Lay out `node` in the rectangle (percent of the parent box), splitting
+/// children along alternating axes in proportion to their weight
+fn render_node(out: &mut String, name: &str, node: &TreeNode, rect: [f64; 4], vertical: bool) {
+    let [x, y, w, h] = rect;
+    let style = format!(
+        "left:{:.3}%;top:{:.3}%;width:{:.3}%;height:{:.3}%",
+        x, y, w, h
+    );
+    if let Some(file) = node.file.filter(|_| node.children.is_empty()) {
+        let efficiency = file.efficiency.map_or("never injected".to_string(), |e| {
+            format!("{:.0}% used", e * 100.0)
+        });
+        let _ = write!(
+            out,
+            "<div class=\"file\" style=\"{};background:{}\" title=\"{}&#10;score {:.2}, injected {}x, {}\">{}</div>",
+            style,
+            efficiency_color(file.efficiency),
+            escape_html(&file.path),
+            file.score,
+            file.injections,
+            efficiency,
+            escape_html(name)
+        );
+        return;
+    }
+
+    let _ = write!(
+        out,
+        "<div class=\"dir\" style=\"{}\"><span>{}</span><div class=\"inner\">",
+        style,
+        escape_html(name)
+    );
+    let mut offset = 0.0;
+    for (child_name, child) in &node.children {
+        let share = child.weight / node.weight * 100.0;
+        let child_rect = if vertical {
+            [0.0, offset, 100.0, share]
+        } else {
+            [offset, 0.0, share, 100.0]
+        };
+        offset += share;
+        render_node(out, child_name, child, child_rect, !vertical);
+    }
+    out.push_str("</div></div>");
+}
+
+fn render_html(heatmap: &Heatmap) -> String {
+    let mut root = TreeNode::default();
+    for file in &heatmap.files {
+        root.insert(file);
+    }
+    let mut body = String::new();
+    if root.children.is_empty() {
+        body.push_str("<p>No attention state or injected files yet.</p>");
+    } else {
+        render_node(&mut body, "", &root, [0.0, 0.0, 100.0, 100.0], false);
+    }
+    let range = heatmap.since.map_or("all recorded turns".to_string(), |s| {
+        format!("turns since {}", s.format("%Y-%m-%d"))
+    });
+    format!(
+        r#"<!DOCTYPE html>
+<html>
+<head>
+<meta charset="utf-8">
+<title>attentive heatmap: {project}</title>
+<style>
+body {{ font: 13px sans-serif; margin: 16px; }}
+#map {{ position: relative; width: 100%; height: 80vh; }}
+.dir, .file {{ position: absolute; box-sizing: border-box; overflow: hidden; }}
+.dir {{ border: 1px solid #555; }}
+.dir > span {{ display: block; height: 16px; padding: 0 3px; background: #eee; white-space: nowrap; }}
+.dir > .inner {{ position: absolute; top: 16px; left: 0; right: 0; bottom: 0; }}
+.file {{ border: 1px solid #fff; color: #fff; padding: 2px; white-space: nowrap; }}
+</style>
+</head>
+<body>
+<h1>{project}</h1>
+<p>{turns} turns ({range}), {files} files. Area: times injected. Color: share of injections the file was used (red 0% to green 100%; gray never injected).</p>
+<div id="map">{body}</div>
+</body>
+</html>
+"#,
+        project = escape_html(&heatmap.project),
+        turns = heatmap.turns,
+        range = range,
+        files = heatmap.files.len(),
+        body = body
+    )
+}
+
+pub fn run_heatmap(format: &str, days: Option<i64>, output: Option<&str>) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let root = canonicalizer.root();
+    let state = super::hooks::load_attention_state(&paths)?;
+    let turns: Vec<TurnRecord> = read_jsonl(&paths.turns_file())?
+        .into_iter()
+        .filter(|t: &TurnRecord| Path::new(&t.project).starts_with(root))
+        .collect();
+    let since = days.map(|d| attentive_telemetry::now() - Duration::days(d));
+    let heatmap = build_heatmap(&root.to_string_lossy(), &state, &turns, since);
+
+    let rendered = match format {
+        "json" => serde_json::to_string_pretty(&heatmap)?,
+        _ => render_html(&heatmap),
+    };
+    match output {
+        Some(file) => {
+            attentive_telemetry::atomic_write(Path::new(file), rendered.as_bytes())?;
+            println!("Wrote heatmap of {} files to {}", heatmap.files.len(), file);
+        }
+        None => println!("{}", rendered),
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(days_ago: i64, injected: &[&str], used: &[&str]) -> TurnRecord {
+        let strings = |files: &[&str]| files.iter().map(|f| f.to_string()).collect();
+        TurnRecord {
+            turn_id: "t".to_string(),
+            session_id: "s".to_string(),
+            project: "/repo".to_string(),
+            timestamp: Utc::now() - Duration::days(days_ago),
+            injected_tokens: 100,
+            used_tokens: 50,
+            waste_ratio: 0.5,
+            files_injected: strings(injected),
+            files_used: strings(used),
+            injection_chars: 400,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_build_heatmap_counts_range() {
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.9);
+        state.scores.insert("docs/guide.md".to_string(), 0.3);
+        let turns = [
+            turn(30, &["src/old.rs"], &["src/old.rs"]),
+            turn(2, &["src/router.rs", "src/config.rs"], &["src/router.rs"]),
+            turn(1, &["src/router.rs", "src/config.rs"], &[]),
+        ];
+        let since = Some(Utc::now() - Duration::days(7));
+        let heatmap = build_heatmap("/repo", &state, &turns, since);
+        assert_eq!(heatmap.turns, 2);
+        let paths: Vec<&str> = heatmap.files.iter().map(|f| f.path.as_str()).collect();
+        assert_eq!(paths, ["docs/guide.md", "src/config.rs", "src/router.rs"]);
+        let router = &heatmap.files[2];
+        assert_eq!((router.injections, router.used), (2, 1));
+        assert_eq!(router.efficiency, Some(0.5));
+        assert_eq!(heatmap.files[0].efficiency, None);
+        assert_eq!(heatmap.files[1].score, 0.0);
+    }
+
+    #[test]
+    fn test_render_html_treemap() {
+        let mut state = AttentionState::new();
+        state.scores.insert("docs/a<b>.md".to_string(), 0.3);
+        let turns = [
+            turn(1, &["src/router.rs"], &["src/router.rs"]),
+            turn(1, &["src/router.rs", "src/lib.rs"], &["src/router.rs"]),
+        ];
+        let html = render_html(&build_heatmap("/repo", &state, &turns, None));
+        assert!(html.starts_with("<!DOCTYPE html>"));
+        assert!(html.contains("2 turns (all recorded turns), 3 files"));
+        assert!(html.contains("a&lt;b&gt;.md"));
+        // src holds 3 of the 4 weight units: router.rs twice, lib.rs once
+        assert!(html.contains("left:25.000%;top:0.000%;width:75.000%;height:100.000%"));
+        assert!(html.contains("background:hsl(120, 70%, 45%)"));
+        assert!(html.contains("background:hsl(0, 70%, 45%)"));
+        assert!(html.contains("never injected"));
+    }
+}
@@ -1,16 +1,11 @@
-use attentive_learn::Learner;
+use super::hooks::load_learner;
 
 pub fn run() -> anyhow::Result<()> {
     let paths = attentive_telemetry::Paths::new()?;
-    let state_path = paths.home_claude.join("learned_state.json");
-
-    if !state_path.exists() {
+    let Some(learner) = load_learner(&paths.learned_state_path()?) else {
         println!("No learned state found. Run attentive ingest first.");
         return Ok(());
-    }
-
-    let content = std::fs::read_to_string(&state_path)?;
-    let learner: Learner = serde_json::from_str(&content)?;
+    };
     let coactivation = learner.get_learned_coactivation();
 
     println!("Co-activation Graph");
@@ -30,7 +25,13 @@ pub fn run() -> anyhow::Result<()> {
                 (rel.clone(), file.clone())
             };
             if pairs_shown.insert(pair.clone()) {
-                println!("  {} <-> {}", pair.0, pair.1);
+                let strength = learner.coactivation_strength(&pair.0, &pair.1);
+                println!(
+                    "  {} <-> {} ({:.2})",
+                    pair.0,
+                    pair.1,
+                    strength.unwrap_or(0.0)
+                );
             }
         }
     }
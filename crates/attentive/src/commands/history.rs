@@ -1,37 +1,18 @@
-use attentive_telemetry::{Paths, TurnRecord, read_jsonl};
+use attentive_telemetry::{Expr, Paths, TurnQuery, TurnRecord, parse_since, read_jsonl};
 
-#[derive(Default)]
-struct HistoryFilter {
-    file: Option<String>,
-    hours: Option<u64>,
-    limit: Option<usize>,
-}
-
-fn filter_turns<'a>(turns: &'a [TurnRecord], filter: &HistoryFilter) -> Vec<&'a TurnRecord> {
-    let cutoff = filter
-        .hours
-        .map(|h| chrono::Utc::now() - chrono::Duration::hours(h as i64));
+/// Turns listed when no `--limit` is given
+const DEFAULT_LIMIT: usize = 20;
 
-    turns
-        .iter()
-        .filter(|t| {
-            if let Some(ref cutoff) = cutoff
-                && t.timestamp < *cutoff
-            {
-                return false;
-            }
-            if let Some(ref file) = filter.file
-                && !t.files_injected.contains(file)
-                && !t.files_used.contains(file)
-            {
-                return false;
-            }
-            true
-        })
-        .collect()
+/// Query from the `--since` and `--where` flags shared by history and report
+pub fn parse_query(since: Option<&str>, filter: Option<&str>) -> anyhow::Result<TurnQuery> {
+    Ok(TurnQuery {
+        since: since.map(parse_since).transpose()?,
+        filter: filter.map(Expr::parse).transpose()?,
+        ..Default::default()
+    })
 }
 
-fn compute_stats(turns: &[TurnRecord]) -> String {
+fn compute_stats(turns: &[&TurnRecord]) -> String {
     if turns.is_empty() {
         return "No turns to analyze.".to_string();
     }
@@ -52,7 +33,28 @@ fn compute_stats(turns: &[TurnRecord]) -> String {
     )
 }
 
-pub fn run(stats: bool) -> anyhow::Result<()> {
+/// Plugin signals as ` | plugin key=value ...`, empty when none were recorded
+fn format_extensions(turn: &TurnRecord) -> String {
+    turn.extensions
+        .iter()
+        .map(|(plugin, value)| match value.as_object() {
+            Some(fields) => {
+                let pairs: Vec<String> = fields
+                    .iter()
+                    .filter(|(_, v)| !v.is_null())
+                    .map(|(k, v)| match v.as_str() {
+                        Some(s) => format!("{}={}", k, s),
+                        None => format!("{}={}", k, v),
+                    })
+                    .collect();
+                format!(" | {} {}", plugin, pairs.join(" "))
+            }
+            None => format!(" | {} {}", plugin, value),
+        })
+        .collect()
+}
+
+pub fn run(stats: bool, mut query: TurnQuery) -> anyhow::Result<()> {
     let paths = Paths::new()?;
     let turns: Vec<TurnRecord> = read_jsonl(&paths.turns_file())?;
 
@@ -62,32 +64,34 @@ pub fn run(stats: bool) -> anyhow::Result<()> {
     }
 
     if stats {
-        println!("{}", compute_stats(&turns));
+        println!("{}", compute_stats(&query.apply(&turns)));
         return Ok(());
     }
 
-    let filter = HistoryFilter {
-        limit: Some(20),
-        ..Default::default()
-    };
-
-    let filtered = filter_turns(&turns, &filter);
-    let display_turns: Vec<_> = filtered
-        .into_iter()
-        .rev()
-        .take(filter.limit.unwrap_or(20))
-        .collect();
+    query.limit.get_or_insert(DEFAULT_LIMIT);
+    let display_turns = query.apply(&turns);
+    if display_turns.is_empty() {
+        println!("No turns match");
+        return Ok(());
+    }
 
     println!("Recent Turns (last {})", display_turns.len());
     println!("======================");
-    for turn in &display_turns {
+    for turn in display_turns.iter().rev() {
+        let conflicts = if turn.config_conflicts.is_empty() {
+            String::new()
+        } else {
+            format!(" | config conflicts: {}", turn.config_conflicts.join(", "))
+        };
         println!(
-            "  {} | injected:{} used:{} waste:{:.0}% conf:{:.0}%",
+            "  {} | injected:{} used:{} waste:{:.0}% conf:{:.0}%{}{}",
             turn.timestamp.format("%Y-%m-%d %H:%M"),
             turn.injected_tokens,
             turn.used_tokens,
             turn.waste_ratio * 100.0,
             turn.context_confidence.unwrap_or(0.0) * 100.0,
+            format_extensions(turn),
+            conflicts,
         );
     }
     Ok(())
@@ -110,9 +114,9 @@ mod tests {
                 waste_ratio: 0.4,
                 files_injected: vec!["a.rs".to_string()],
                 files_used: vec!["a.rs".to_string()],
-                was_notification: false,
                 injection_chars: 4000,
                 context_confidence: Some(0.8),
+                ..Default::default()
             },
             TurnRecord {
                 turn_id: "t2".to_string(),
@@ -124,9 +128,9 @@ mod tests {
                 waste_ratio: 0.1,
                 files_injected: vec!["b.rs".to_string()],
                 files_used: vec!["b.rs".to_string()],
-                was_notification: false,
                 injection_chars: 8000,
                 context_confidence: Some(0.95),
+                ..Default::default()
             },
         ]
     }
@@ -134,13 +138,11 @@ mod tests {
     #[test]
     fn test_filter_by_file() {
         let turns = sample_turns();
-        let filtered = filter_turns(
-            &turns,
-            &HistoryFilter {
-                file: Some("a.rs".to_string()),
-                ..Default::default()
-            },
-        );
+        let filtered = TurnQuery {
+            file: Some("a.rs".to_string()),
+            ..Default::default()
+        }
+        .apply(&turns);
         assert_eq!(filtered.len(), 1);
         assert_eq!(filtered[0].turn_id, "t1");
     }
@@ -148,18 +150,44 @@ mod tests {
     #[test]
     fn test_stats_mode() {
         let turns = sample_turns();
-        let stats = compute_stats(&turns);
+        let stats = compute_stats(&turns.iter().collect::<Vec<_>>());
         assert!(stats.contains("turns"));
         assert!(stats.contains("waste"));
     }
 
+    #[test]
+    fn test_format_extensions() {
+        let mut turns = sample_turns();
+        assert_eq!(format_extensions(&turns[0]), "");
+        turns[0].extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({ "loop_count": 3, "loop_file": "a.rs" }),
+        );
+        assert_eq!(
+            format_extensions(&turns[0]),
+            " | loopbreaker loop_count=3 loop_file=a.rs"
+        );
+    }
+
     #[test]
     fn test_no_filter_returns_all() {
         let turns = sample_turns();
-        let filtered = filter_turns(&turns, &HistoryFilter::default());
+        let filtered = TurnQuery::default().apply(&turns);
         assert_eq!(filtered.len(), 2);
     }
 
+    #[test]
+    fn test_parse_query_flags() {
+        let turns = sample_turns();
+        let query = parse_query(Some("1h"), Some("waste < 0.2 && files_used ~ \"b\"")).unwrap();
+        let filtered = query.apply(&turns);
+        assert_eq!(filtered.len(), 1);
+        assert_eq!(filtered[0].turn_id, "t2");
+
+        assert!(parse_query(Some("yesterday"), None).is_err());
+        assert!(parse_query(None, Some("waste >")).is_err());
+    }
+
     #[test]
     fn test_history_stats_output() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -174,16 +202,16 @@ mod tests {
             waste_ratio: 0.6,
             files_injected: vec!["a.rs".to_string()],
             files_used: vec!["a.rs".to_string()],
-            was_notification: false,
             injection_chars: 4000,
             context_confidence: Some(0.5),
+            ..Default::default()
         };
         let json = serde_json::to_string(&turn).unwrap();
         std::fs::write(&turns_path, format!("{}\n", json)).unwrap();
 
         let turns: Vec<attentive_telemetry::TurnRecord> =
             attentive_telemetry::read_jsonl(&turns_path).unwrap_or_default();
-        let stats = compute_stats(&turns);
+        let stats = compute_stats(&turns.iter().collect::<Vec<_>>());
         assert!(stats.contains("Total turns"));
         assert!(stats.contains("Avg waste"));
     }
@@ -0,0 +1,297 @@
+//! `benchmark --hook`: end-to-end prompt hook latency with regression tracking
+//!
+//! Each scenario builds a scratch home and repo with seeded attention and
+//! learned state, then runs `attentive hook:user-prompt-submit` as a
+//! subprocess, the way Claude Code does. Results are appended to
+//! `~/.claude/telemetry/hook_bench.jsonl` and compared against earlier runs.
+
+use super::hooks::save_learner;
+use attentive_core::AttentionState;
+use attentive_learn::Learner;
+use attentive_telemetry::{HookBenchRecord, Paths, append_jsonl, read_jsonl, write_versioned};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+use std::time::{Duration, Instant};
+
+struct Scenario {
+    name: &'static str,
+    repo_files: usize,
+    state_entries: usize,
+}
+
+const SCENARIOS: &[Scenario] = &[
+    Scenario {
+        name: "small",
+        repo_files: 100,
+        state_entries: 50,
+    },
+    Scenario {
+        name: "medium",
+        repo_files: 1000,
+        state_entries: 500,
+    },
+    Scenario {
+        name: "large",
+        repo_files: 5000,
+        state_entries: 2000,
+    },
+];
+
+/// Earlier runs whose median p50 is the baseline for a new run
+const BASELINE_RUNS: usize = 5;
+
+fn source_file(i: usize) -> String {
+    let mut src = format!("//! Module {i}\n\nuse std::collections::HashMap;\n\n");
+    for f in 0..6 {
+        src.push_str(&format!(
+            "pub fn handler_{i}_{f}(input: &str) -> HashMap<String, usize> {{\n    \
+             let mut counts = HashMap::new();\n    \
+             for word in input.split_whitespace() {{\n        \
+             *counts.entry(word.to_string()).or_insert(0) += 1;\n    \
+             }}\n    counts\n}}\n\n"
+        ));
+    }
+    src
+}
+
+fn repo_file(repo: &Path, i: usize) -> PathBuf {
+    repo.join(format!("src/mod_{}/file_{}.rs", i / 100, i))
+}
+
+/// Create a scratch repo under `root` and a home whose attention and learned
+/// state cover `scenario.state_entries` of its files. Returns (home, repo).
+fn setup(root: &Path, scenario: &Scenario) -> anyhow::Result<(PathBuf, PathBuf)> {
+    let home = root.join("home");
+    std::fs::create_dir_all(home.join(".claude"))?;
+    std::fs::create_dir_all(root.join("repo"))?;
+    // The hook keys project state by the canonical working directory
+    let repo = root.join("repo").canonicalize()?;
+    for i in 0..scenario.repo_files {
+        let path = repo_file(&repo, i);
+        std::fs::create_dir_all(path.parent().unwrap())?;
+        std::fs::write(path, source_file(i))?;
+    }
+
+    let mut state = AttentionState::new();
+    let mut learner = Learner::new();
+    for i in 0..scenario.state_entries.min(scenario.repo_files) {
+        let path = repo_file(&repo, i).to_string_lossy().to_string();
+        let score = match i % 10 {
+            0 => 0.9,
+            1..=3 => 0.5,
+            _ => 0.1,
+        };
+        if i % 10 == 0 {
+            learner.observe_turn(
+                &format!("fix handler in file {}", i),
+                std::slice::from_ref(&path),
+            );
+        }
+        state.scores.insert(path, score);
+    }
+
+    let project_dir = Paths::with_root(&home).project_dir_for(&repo);
+    write_versioned(&project_dir.join("attn_state.json"), &state)?;
+    save_learner(&project_dir.join("learned_state.json"), &mut learner)?;
+    Ok((home, repo))
+}
+
+/// Wall time of one `hook:user-prompt-submit` run, process start to exit
+fn time_hook(exe: &Path, home: &Path, repo: &Path, prompt: &str) -> anyhow::Result<Duration> {
+    let input = serde_json::json!({ "prompt": prompt }).to_string();
+    let start = Instant::now();
+    let mut child = Command::new(exe)
+        .arg("hook:user-prompt-submit")
+        .env("HOME", home)
+        .current_dir(repo)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let Some(mut stdin) = child.stdin.take() {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let status = child.wait()?;
+    let elapsed = start.elapsed();
+    if !status.success() {
+        anyhow::bail!("hook exited with {}", status);
+    }
+    Ok(elapsed)
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[f64], p: f64) -> f64 {
+    if sorted.is_empty() {
+        return 0.0;
+    }
+    let idx = ((sorted.len() - 1) as f64 * p).round() as usize;
+    sorted[idx]
+}
+
+/// Median p50 of the last few comparable runs of the same scenario
+fn baseline(history: &[HookBenchRecord], record: &HookBenchRecord) -> Option<f64> {
+    let mut previous: Vec<f64> = history
+        .iter()
+        .filter(|r| {
+            r.scenario == record.scenario
+                && r.repo_files == record.repo_files
+                && r.state_entries == record.state_entries
+                && r.debug_build == record.debug_build
+        })
+        .map(|r| r.p50_ms)
+        .collect();
+    let skip = previous.len().saturating_sub(BASELINE_RUNS);
+    let mut previous = previous.split_off(skip);
+    if previous.is_empty() {
+        return None;
+    }
+    previous.sort_by(|a, b| a.total_cmp(b));
+    Some(percentile(&previous, 0.5))
+}
+
+/// Percent change of `current` over `baseline`
+fn change_pct(current: f64, baseline: f64) -> f64 {
+    if baseline <= 0.0 {
+        return 0.0;
+    }
+    (current / baseline - 1.0) * 100.0
+}
+
+fn format_row(record: &HookBenchRecord, baseline: Option<f64>, threshold_pct: f64) -> String {
+    let mut row = format!(
+        "  {:<7} {:>5} files {:>5} entries  p50 {:>7.1}ms  p95 {:>7.1}ms",
+        record.scenario, record.repo_files, record.state_entries, record.p50_ms, record.p95_ms
+    );
+    if let Some(base) = baseline {
+        let change = change_pct(record.p50_ms, base);
+        row.push_str(&format!("  (baseline {:.1}ms, {:+.0}%)", base, change));
+        if change > threshold_pct {
+            row.push_str(&format!(
+                "  REGRESSION over {:.0}% threshold",
+                threshold_pct
+            ));
+        }
+    }
+    row
+}
+
+/// Measure the prompt hook across repo and state sizes, record the results
+/// and fail if any scenario's p50 regressed more than `threshold_pct`
+pub fn run(iterations: usize, threshold_pct: f64) -> anyhow::Result<()> {
+    let exe = std::env::current_exe()?;
+    let history_path = Paths::new()?.hook_bench_file();
+    let history: Vec<HookBenchRecord> = read_jsonl(&history_path)?;
+    let debug_build = cfg!(debug_assertions);
+    let iterations = iterations.max(1);
+
+    let title = format!(
+        "Prompt Hook Latency ({} build, {} runs per scenario)",
+        if debug_build { "debug" } else { "release" },
+        iterations
+    );
+    let mut lines = vec![title.clone(), "=".repeat(title.chars().count())];
+    let mut regressions = 0;
+
+    let scratch = std::env::temp_dir().join(format!("attentive-hookbench-{}", std::process::id()));
+    for scenario in SCENARIOS {
+        let _ = std::fs::remove_dir_all(&scratch);
+        let (home, repo) = setup(&scratch, scenario)?;
+
+        // First run warms the page cache and isn't counted
+        let prompt = "fix the handler in file_1.rs";
+        time_hook(&exe, &home, &repo, prompt)?;
+        let mut samples = Vec::with_capacity(iterations);
+        for _ in 0..iterations {
+            samples.push(time_hook(&exe, &home, &repo, prompt)?.as_secs_f64() * 1000.0);
+        }
+        samples.sort_by(|a, b| a.total_cmp(b));
+
+        let record = HookBenchRecord {
+            timestamp: attentive_telemetry::now(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            debug_build,
+            scenario: scenario.name.to_string(),
+            repo_files: scenario.repo_files,
+            state_entries: scenario.state_entries,
+            p50_ms: percentile(&samples, 0.5),
+            p95_ms: percentile(&samples, 0.95),
+        };
+        let base = baseline(&history, &record);
+        if base.is_some_and(|b| change_pct(record.p50_ms, b) > threshold_pct) {
+            regressions += 1;
+        }
+        lines.push(format_row(&record, base, threshold_pct));
+        append_jsonl(&history_path, &record)?;
+    }
+    let _ = std::fs::remove_dir_all(&scratch);
+
+    println!("{}", lines.join("\n"));
+    if regressions > 0 {
+        anyhow::bail!(
+            "prompt hook latency regressed in {} scenario(s)",
+            regressions
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(scenario: &str, p50_ms: f64) -> HookBenchRecord {
+        HookBenchRecord {
+            timestamp: attentive_telemetry::now(),
+            version: "0.1.0".to_string(),
+            debug_build: false,
+            scenario: scenario.to_string(),
+            repo_files: 100,
+            state_entries: 50,
+            p50_ms,
+            p95_ms: p50_ms * 1.5,
+        }
+    }
+
+    #[test]
+    fn test_baseline_is_median_of_recent_comparable_runs() {
+        let mut history: Vec<HookBenchRecord> = [100.0, 10.0, 12.0, 11.0, 50.0, 13.0]
+            .map(|ms| record("small", ms))
+            .into();
+        history.push(record("large", 500.0));
+        let mut debug = record("small", 900.0);
+        debug.debug_build = true;
+        history.push(debug);
+
+        // The oldest run (100ms) falls outside the last five
+        assert_eq!(baseline(&history, &record("small", 0.0)), Some(12.0));
+        assert_eq!(baseline(&history, &record("medium", 0.0)), None);
+    }
+
+    #[test]
+    fn test_format_row_flags_regressions() {
+        let row = format_row(&record("small", 15.0), Some(10.0), 20.0);
+        assert!(row.contains("(baseline 10.0ms, +50%)"));
+        assert!(row.contains("REGRESSION over 20% threshold"));
+
+        let row = format_row(&record("small", 11.0), Some(10.0), 20.0);
+        assert!(!row.contains("REGRESSION"));
+        assert!(!format_row(&record("small", 11.0), None, 20.0).contains("baseline"));
+    }
+
+    #[test]
+    fn test_setup_seeds_readable_state() {
+        let dir = tempfile::tempdir().unwrap();
+        let scenario = Scenario {
+            name: "tiny",
+            repo_files: 12,
+            state_entries: 20,
+        };
+        let (home, repo) = setup(dir.path(), &scenario).unwrap();
+        let project_dir = Paths::with_root(&home).project_dir_for(&repo);
+        let state: AttentionState =
+            attentive_telemetry::read_versioned(&project_dir.join("attn_state.json")).unwrap();
+        assert_eq!(state.scores.len(), 12);
+        assert!(repo_file(&repo, 11).exists());
+    }
+}
@@ -1,71 +1,213 @@
-use attentive_core::{AttentionState, Config, Router};
+use super::compaction::{COMPACTION_BRIEF_KEY, CompactionBrief};
+use super::content_cache::ContentCache;
+use super::dedupe::{ContextPart, dedupe, join_parts};
+use attentive_core::{
+    AttentionState, Config, ConfigConflict, ConflictChecker, ContextTemplates, DashboardVars,
+    PathScope, Provenance, Redactor, Router, RouterMetrics, SectionVars, StaleObservations,
+    WastedFile, render_template,
+};
+use attentive_learn::{Learner, Oracle, Synonyms, TaskType, WORD_INDEX_FILE, WordIndex};
 use attentive_plugins::PluginRegistry;
-use attentive_telemetry::Paths;
+use attentive_repo::FileSymbols;
+use attentive_telemetry::{PathCanonicalizer, Paths, PluginBudgetUsage};
 use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::io::{self, Read, Write};
 use std::path::Path;
 
-fn load_config(home_claude: &Path) -> Config {
-    let config_path = home_claude.join("attentive.json");
-    if !config_path.exists() {
-        return Config::new();
+/// Project config committed at the repo root; its keys override attentive.json
+pub(crate) const PROJECT_CONFIG_FILE: &str = ".attentive.json";
+
+pub(crate) fn load_config(home_claude: &Path) -> Config {
+    load_config_in(home_claude, PathCanonicalizer::for_current_dir().ok())
+}
+
+/// `load_config` for the project `paths` resolves state for
+pub(crate) fn load_project_config(paths: &Paths) -> Config {
+    load_config_in(&paths.home_claude, paths.canonicalizer().ok())
+}
+
+fn load_config_in(home_claude: &Path, canonicalizer: Option<PathCanonicalizer>) -> Config {
+    // The state server keeps each project's config in memory and swaps it
+    // when the files change
+    if let Some(config) = canonicalizer
+        .as_ref()
+        .and_then(|c| super::serve::watched_config(home_claude, c.root()))
+    {
+        return config;
+    }
+    let project_root = canonicalizer.as_ref().map(|c| c.root());
+    let mut config = load_config_layered(home_claude, project_root);
+    if let Some(canonicalizer) = &canonicalizer {
+        config.canonicalize_paths(canonicalizer);
     }
+    config
+}
+
+/// Which files may enter attention state: repo files plus `external_files`
+pub(crate) fn path_scope(paths: &Paths, config: &Config) -> PathScope {
+    config.path_scope(paths.home_claude.parent().unwrap_or(&paths.home_claude))
+}
 
-    let content = match std::fs::read_to_string(&config_path) {
-        Ok(c) => c,
-        Err(_) => return Config::new(),
+/// attentive.json with the project's .attentive.json (if any) layered on top
+fn load_config_layered(home_claude: &Path, project_root: Option<&Path>) -> Config {
+    let config_path = home_claude.join("attentive.json");
+    let project_path = project_root
+        .map(|root| root.join(PROJECT_CONFIG_FILE))
+        .filter(|p| p.exists());
+    let parsed = match &project_path {
+        Some(project_path) => layer_config_files(&config_path, project_path),
+        None if config_path.exists() => {
+            super::serve::read_cached(&config_path, |c| Ok(Config::from_json(c)?))
+        }
+        None => return Config::new(),
     };
 
-    #[derive(Deserialize)]
-    struct ConfigFile {
-        #[serde(default)]
-        co_activation: std::collections::HashMap<String, Vec<String>>,
-        #[serde(default)]
-        pinned_files: Vec<String>,
-        #[serde(default)]
-        demoted_files: Vec<String>,
+    match parsed {
+        Ok(config) if config.has_errors() => {
+            eprintln!(
+                "[attentive] attentive.json has invalid values (see `attentive config check`); using defaults"
+            );
+            Config::new()
+        }
+        Ok(config) => config,
+        Err(_) => Config::new(),
     }
+}
+
+fn read_config_object(path: &Path) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    super::serve::read_cached(path, |c| match serde_json::from_str(c)? {
+        serde_json::Value::Object(map) => Ok(map),
+        _ => anyhow::bail!("{} is not a JSON object", path.display()),
+    })
+}
+
+/// Parse `global` (if present) and `project`, project keys replacing global ones
+fn layer_config_files(global: &Path, project: &Path) -> anyhow::Result<Config> {
+    let mut merged = if global.exists() {
+        read_config_object(global)?
+    } else {
+        serde_json::Map::new()
+    };
+    merged.extend(read_config_object(project)?);
+    Ok(Config::from_json(
+        &serde_json::Value::Object(merged).to_string(),
+    )?)
+}
 
-    match serde_json::from_str::<ConfigFile>(&content) {
-        Ok(cf) => {
-            let mut config = Config::new();
-            config.co_activation = cf.co_activation;
-            config.pinned_files = cf.pinned_files;
-            config.demoted_files = cf.demoted_files;
-            config
+/// attentive.json and `project_root`'s .attentive.json merged into one
+/// object (project keys win); missing files contribute nothing
+pub(crate) fn layered_config_json(
+    home_claude: &Path,
+    project_root: &Path,
+) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    let mut merged = serde_json::Map::new();
+    for path in [
+        home_claude.join("attentive.json"),
+        project_root.join(PROJECT_CONFIG_FILE),
+    ] {
+        if path.exists() {
+            merged.extend(read_config_object(&path)?);
         }
-        Err(_) => Config::new(),
     }
+    Ok(merged)
 }
 
-fn load_learner(state_path: &Path) -> Option<attentive_learn::Learner> {
+/// The learner with all of its word tables, for commands that update it or
+/// route many prompts
+pub(crate) fn load_learner(state_path: &Path) -> Option<Learner> {
+    try_load_learner(state_path).ok().flatten()
+}
+
+/// `load_learner`, telling a missing learner apart from one that fails to load
+pub(crate) fn try_load_learner(state_path: &Path) -> anyhow::Result<Option<Learner>> {
+    if !state_path.exists() {
+        return Ok(None);
+    }
+    super::serve::read_cached(state_path, |c| {
+        let mut learner: Learner = attentive_telemetry::from_versioned_str(c)?;
+        // State files from before the word index keep the tables inline
+        if !learner.has_word_tables()
+            && let Some(index) = word_index(state_path)
+        {
+            learner.set_word_tables(index.load_all()?);
+        }
+        Ok(learner)
+    })
+    .map(Some)
+}
+
+/// The learner with only the word-table rows `prompt` is scored on, those of
+/// its `synonyms` included. The state server already holds the full learner
+/// in memory, so it uses that.
+fn load_learner_for_prompt(state_path: &Path, prompt: &str, synonyms: Synonyms) -> Option<Learner> {
+    if super::serve::is_serving() {
+        return load_learner(state_path).map(|l| l.with_synonyms(synonyms));
+    }
     if !state_path.exists() {
         return None;
     }
-    let content = std::fs::read_to_string(state_path).ok()?;
-    serde_json::from_str(&content).ok()
+    let mut learner: Learner = attentive_telemetry::read_versioned(state_path).ok()?;
+    if !learner.has_word_tables()
+        && let Some(index) = word_index(state_path)
+    {
+        let prompt = synonyms.expand_text(prompt);
+        learner.set_word_tables(index.load_for_prompt(&prompt).ok()?);
+    }
+    Some(learner.with_synonyms(synonyms))
 }
 
-const MAX_TOTAL_CHARS: usize = 20000;
+/// The word index beside `state_path`, if one has been written
+fn word_index(state_path: &Path) -> Option<WordIndex> {
+    let path = state_path.with_file_name(WORD_INDEX_FILE);
+    if !path.exists() {
+        return None;
+    }
+    WordIndex::open(&path).ok()
+}
 
-fn read_file_content(path: &str, max_chars: usize) -> String {
-    match std::fs::read_to_string(path) {
-        Ok(content) => {
-            if content.len() > max_chars {
-                format!(
-                    "{}...\n[truncated at {} chars]",
-                    &content[..max_chars],
-                    max_chars
-                )
-            } else {
-                content
-            }
-        }
-        Err(_) => format!("[error reading {}]", path),
+/// Write `learner` to `state_path`, with its word tables in the word index
+/// beside it
+pub(crate) fn save_learner(state_path: &Path, learner: &mut Learner) -> anyhow::Result<()> {
+    let tables = learner.take_word_tables();
+    let saved = WordIndex::open(&state_path.with_file_name(WORD_INDEX_FILE))
+        .and_then(|index| index.store(&tables))
+        .and_then(|()| Ok(attentive_telemetry::write_versioned(state_path, learner)?));
+    learner.set_word_tables(tables);
+    saved
+}
+
+/// Classification confidence needed before a task type's usual files are warmed
+const MIN_PREFETCH_CONFIDENCE: f64 = 0.6;
+
+/// Files more than this many times over their budget are digested rather
+/// than truncated, as truncation would drop most of them
+const DIGEST_OVER_BUDGET: usize = 2;
+
+/// Content of `path` fitted to `max_chars`, with its view: `full` (possibly
+/// truncated) or `digest`
+fn read_file_content(
+    cache: &mut ContentCache,
+    path: &str,
+    max_chars: usize,
+) -> (String, &'static str) {
+    let Some(content) = cache.read(path) else {
+        return (format!("[error reading {}]", path), "full");
+    };
+    if content.len() > max_chars * DIGEST_OVER_BUDGET
+        && let Some(digest) = cache.digest(path, &content, max_chars)
+    {
+        return (digest, "digest");
     }
+    (
+        attentive_repo::truncate_source(&content, path, max_chars),
+        "full",
+    )
 }
 
-fn extract_toc(content: &str) -> String {
+/// Headings and signatures of `content`, in file order
+pub(crate) fn extract_toc_entries(content: &str) -> Vec<&str> {
     let mut toc_lines = Vec::new();
     for line in content.lines() {
         let trimmed = line.trim();
@@ -79,49 +221,487 @@ fn extract_toc(content: &str) -> String {
             || trimmed.starts_with("pub trait ");
 
         if is_heading || is_signature {
-            toc_lines.push(trimmed.to_string());
+            toc_lines.push(trimmed);
         }
     }
-    toc_lines.join("\n")
+    toc_lines
 }
 
+/// Token cap on one WARM file's TOC
+const WARM_TOC_MAX_TOKENS: usize = 250;
+
+/// Lowercased words of `text`, with identifiers also split at `_` and camelCase
+fn toc_terms(text: &str) -> Vec<String> {
+    let mut terms = Vec::new();
+    for word in text.split(|c: char| !c.is_alphanumeric() && c != '_') {
+        if word.is_empty() {
+            continue;
+        }
+        terms.push(word.to_lowercase());
+        let mut part = String::new();
+        let mut prev_lower = false;
+        for c in word.chars() {
+            if (c == '_' || (c.is_uppercase() && prev_lower)) && !part.is_empty() {
+                terms.push(std::mem::take(&mut part));
+            }
+            if c != '_' {
+                part.extend(c.to_lowercase());
+            }
+            prev_lower = c.is_lowercase() || c.is_ascii_digit();
+        }
+        if !part.is_empty() && part.len() < word.len() {
+            terms.push(part);
+        }
+    }
+    terms
+}
+
+/// Keep the TOC entries that best match `prompt`: at most `max_entries`
+/// within `WARM_TOC_MAX_TOKENS`, shown in file order with a count of the rest
+fn pack_toc(entries: &[&str], prompt: &str, max_entries: usize) -> String {
+    let prompt_terms: std::collections::HashSet<String> = toc_terms(prompt)
+        .into_iter()
+        .filter(|t| t.len() >= 3)
+        .collect();
+    let mut ranked: Vec<(usize, usize)> = entries
+        .iter()
+        .enumerate()
+        .map(|(i, entry)| {
+            let mut terms = toc_terms(entry);
+            terms.sort();
+            terms.dedup();
+            let overlap = terms.iter().filter(|t| prompt_terms.contains(*t)).count();
+            (i, overlap)
+        })
+        .collect();
+    // Best match first; ties keep file order
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then(a.0.cmp(&b.0)));
+
+    let mut kept = Vec::new();
+    let mut tokens = 0;
+    for (i, _) in ranked {
+        if kept.len() == max_entries {
+            break;
+        }
+        let cost = attentive_telemetry::estimate_tokens(entries[i]).max(1);
+        if tokens + cost > WARM_TOC_MAX_TOKENS && !kept.is_empty() {
+            continue;
+        }
+        kept.push(i);
+        tokens += cost;
+    }
+    kept.sort_unstable();
+
+    let mut lines: Vec<String> = kept.iter().map(|&i| entries[i].to_string()).collect();
+    let omitted = entries.len() - kept.len();
+    if omitted > 0 {
+        lines.push(format!("(+{} more symbols)", omitted));
+    }
+    lines.join("\n")
+}
+
+/// Fingerprint of each HOT/WARM section injected on a turn, keyed by path
+pub(crate) type InjectedSet = BTreeMap<String, u64>;
+
+/// Estimated tokens injected per context source
+pub(crate) type ContextSources = BTreeMap<String, usize>;
+
+const SOURCE_HOT: &str = "hot";
+const SOURCE_WARM: &str = "warm";
+const SOURCE_OBSERVATION: &str = "observation";
+const SOURCE_MEMORY: &str = "memory";
+const SOURCE_COLD_INDEX: &str = "cold_index";
+const SOURCE_DASHBOARD: &str = "dashboard";
+const SOURCE_LIKELY_NEXT: &str = "likely_next";
+const SOURCE_COMPACTION_BRIEF: &str = "compaction_brief";
+
+fn add_source(sources: &mut ContextSources, source: &str, text: &str) {
+    *sources.entry(source.to_string()).or_default() += attentive_telemetry::estimate_tokens(text);
+}
+
+/// Session-state key holding the dashboard's size until the next Stop records it
+const DASHBOARD_TOKENS_KEY: &str = "dashboard_tokens";
+
+/// Session-state key holding the previous turn's injected set (delta mode)
+const LAST_INJECTED_KEY: &str = "last_injected";
+
+fn section_fingerprint(tier: &str, body: &str) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    tier.hash(&mut hasher);
+    body.hash(&mut hasher);
+    hasher.finish()
+}
+
+/// Ends a section shortened to fit the context budget
+const TRUNCATED_MARKER: &str = "\n[... truncated to fit the context budget]";
+
+/// `section` cut down to at most `remaining` bytes, ending on a line break
+/// where possible. `None` if not even its first line fits.
+fn fit_section(section: String, remaining: usize) -> Option<String> {
+    if section.len() <= remaining {
+        return Some(section);
+    }
+    let mut cut = remaining.saturating_sub(TRUNCATED_MARKER.len());
+    while !section.is_char_boundary(cut) {
+        cut -= 1;
+    }
+    let first_line = section.find('\n').unwrap_or(section.len());
+    if cut < first_line {
+        return None;
+    }
+    let cut = section[..cut]
+        .rfind('\n')
+        .filter(|&i| i > first_line)
+        .unwrap_or(cut);
+    Some(format!("{}{}", &section[..cut], TRUNCATED_MARKER))
+}
+
+/// HOT/WARM context as built by `build_tiered_context`
+struct TieredContext {
+    /// HOT and WARM sections, then the unchanged-files line
+    parts: Vec<ContextPart>,
+    /// Fingerprints of every section the text covers
+    injected: InjectedSet,
+    /// Estimated tokens spent on each tier
+    sources: ContextSources,
+    /// Sections truncated or dropped to stay within the budget
+    overflow: usize,
+    /// Secrets replaced in file contents and summaries
+    redactions: usize,
+}
+
+/// Optional inputs to `build_tiered_context`
+#[derive(Default)]
+struct ContextExtras<'a> {
+    /// WARM file -> summary of a stored observation, used instead of its outline
+    observations: HashMap<String, String>,
+    /// WARM file -> numbered lines where it matched the prompt in the search
+    /// index, shown above its outline
+    snippets: HashMap<String, String>,
+    /// Reasons the router recorded per file, tagged onto section headers
+    provenance: Option<&'a RouterMetrics>,
+    /// Last turn's injected set (delta mode)
+    previous: Option<&'a InjectedSet>,
+    /// Attention scores after routing, for section templates
+    scores: Option<&'a HashMap<String, f64>>,
+    /// User layouts replacing the built-in section format
+    templates: Option<&'a ContextTemplates>,
+    /// Redacts secrets from file contents and summaries before they're used
+    redactor: Option<&'a Redactor>,
+    /// "Project memory" section, fitted into what the file sections leave
+    memory: Option<String>,
+}
+
+/// Build HOT/WARM sections; WARM outlines are ranked against `prompt`. A WARM
+/// file with an observation summary gets that instead of its outline when it
+/// fits, as it says more per token, and one with a search snippet gets the
+/// snippet above its outline. With provenance, each header is tagged
+/// with the reasons the router recorded for that file. With a previous
+/// injected set (delta mode), sections identical to last turn's are listed by
+/// name instead of re-sent. A configured HOT or WARM template replaces the
+/// built-in section layout. With a redactor, secrets are replaced in every
+/// file's content and summary first.
+///
+/// The text never exceeds `max_total_chars`: a section that crosses the limit
+/// is shortened to fit, or dropped if not even its header line fits. Files are
+/// read through `cache`, all of them prefetched together.
 fn build_tiered_context(
+    cache: &mut ContentCache,
     hot_files: &[String],
     warm_files: &[String],
+    prompt: &str,
     max_total_chars: usize,
-) -> String {
+    max_toc_entries: usize,
+    extras: &ContextExtras,
+) -> TieredContext {
+    let ContextExtras {
+        observations,
+        snippets,
+        provenance,
+        previous,
+        scores,
+        templates,
+        redactor,
+        memory,
+    } = extras;
+    let mut redactions = 0;
+    let mut redact = |text: String| match redactor {
+        Some(redactor) => redactor.redact_into(text, &mut redactions),
+        None => text,
+    };
+    let reasons = |path: &str| {
+        provenance
+            .and_then(|m| attentive_core::format_provenance(m.reasons(path)))
+            .unwrap_or_default()
+    };
+    let tags = |path: &str| {
+        let reasons = reasons(path);
+        if reasons.is_empty() {
+            reasons
+        } else {
+            format!(" [{}]", reasons)
+        }
+    };
+    // The user's template for the tier, else the built-in header and body
+    let render = |tier: &str, view: &str, path: &str, body: &str, builtin: String| {
+        let template = templates.and_then(|t| match tier {
+            "HOT" => t.hot.as_ref(),
+            _ => t.warm.as_ref(),
+        });
+        let Some(template) = template else {
+            return builtin;
+        };
+        let vars = SectionVars {
+            path,
+            tier,
+            score: scores.and_then(|s| s.get(path)).copied().unwrap_or(0.0),
+            provenance: &reasons(path),
+            view,
+            content: body,
+        };
+        render_template(template, &vars).unwrap_or_else(|e| {
+            tracing::warn!("{} template failed, using the built-in layout: {}", tier, e);
+            builtin
+        })
+    };
+    let unchanged_since =
+        |path: &str, fingerprint: u64| previous.is_some_and(|p| p.get(path) == Some(&fingerprint));
     let mut parts = Vec::new();
+    let mut injected = InjectedSet::new();
+    let mut unchanged = Vec::new();
+    let mut sources = ContextSources::new();
     let mut chars_used = 0;
+    let mut overflow = 0;
+    // Parts are joined with blank lines
+    let separator = |parts: &[ContextPart]| if parts.is_empty() { 0 } else { 2 };
     let per_hot_budget = if !hot_files.is_empty() {
         (max_total_chars * 70 / 100) / hot_files.len()
     } else {
         0
     };
 
+    // Map imports among injected files so WARM entries can say why they're present
+    let mut mapper = attentive_repo::RepoMapper::new();
+    cache.prefetch(hot_files.iter().chain(warm_files));
+    let mut hot_contents = Vec::new();
     for path in hot_files {
-        if chars_used >= max_total_chars {
-            break;
+        let (content, view) = read_file_content(cache, path, per_hot_budget);
+        let content = redact(content);
+        mapper.add_file(path, &content);
+        hot_contents.push((content, view));
+    }
+    // Only the outline of a WARM file is injected, so only it is redacted.
+    // Its imports come with it, so an unchanged file isn't read.
+    let warm_contents: Vec<Option<(Option<FileSymbols>, Vec<String>)>> = warm_files
+        .iter()
+        .map(|path| {
+            let (toc, imports) = cache.outline(path)?;
+            Some((imports, toc.into_iter().map(&mut redact).collect()))
+        })
+        .collect();
+    let observations: HashMap<&String, String> = observations
+        .iter()
+        .map(|(path, summary)| (path, redact(summary.clone())))
+        .collect();
+    let snippets: HashMap<&String, String> = snippets
+        .iter()
+        .map(|(path, snippet)| (path, redact(snippet.clone())))
+        .collect();
+    for (path, content) in warm_files.iter().zip(&warm_contents) {
+        if let Some((Some(imports), _)) = content {
+            mapper.add_symbols(path, imports.clone());
         }
-        let content = read_file_content(path, per_hot_budget);
-        let section = format!("[HOT] {}\n{}", path, content);
-        chars_used += section.len();
-        parts.push(section);
     }
 
-    for path in warm_files {
-        if chars_used >= max_total_chars {
-            break;
+    for (path, (content, view)) in hot_files.iter().zip(hot_contents) {
+        let fingerprint = section_fingerprint("HOT", &content);
+        if unchanged_since(path, fingerprint) {
+            injected.insert(path.clone(), fingerprint);
+            unchanged.push(path.as_str());
+            continue;
         }
-        let content = match std::fs::read_to_string(path) {
-            Ok(c) => extract_toc(&c),
-            Err(_) => format!("[error reading {}]", path),
+        let label = if view == "digest" { " (digest)" } else { "" };
+        let builtin = format!("[HOT] {}{}{}\n{}", path, label, tags(path), content);
+        let section = render("HOT", view, path, &content, builtin);
+        let room = max_total_chars.saturating_sub(chars_used + separator(&parts));
+        if section.len() > room {
+            overflow += 1;
+        }
+        let Some(section) = fit_section(section, room) else {
+            continue;
         };
-        let section = format!("[WARM] {} (TOC)\n{}", path, content);
-        chars_used += section.len();
-        parts.push(section);
+        chars_used += separator(&parts) + section.len();
+        add_source(&mut sources, SOURCE_HOT, &section);
+        parts.push(ContextPart::for_file(section, path, true));
+        injected.insert(path.clone(), fingerprint);
     }
 
-    parts.join("\n\n")
+    for (path, content) in warm_files.iter().zip(&warm_contents) {
+        let hint = format_dependency_hint(path, &mapper, hot_files).map(&mut redact);
+        let with_hint = |text: String| match &hint {
+            Some(hint) => format!("{}\n{}", hint, text),
+            None => text,
+        };
+        let room = max_total_chars.saturating_sub(chars_used + separator(&parts));
+
+        // Observation summary when it fits, else the outline
+        let summary = observations.get(path).map(|summary| {
+            let body = with_hint(summary.clone());
+            let builtin = format!("[WARM] {} (summary){}\n{}", path, tags(path), body);
+            let section = render("WARM", "summary", path, &body, builtin);
+            (body, section)
+        });
+        let (body, section, source) = match summary {
+            Some((body, section)) if section.len() <= room => (body, section, SOURCE_OBSERVATION),
+            _ => {
+                let body = with_hint(match content {
+                    Some((_, entries)) => {
+                        let entries: Vec<&str> = entries.iter().map(String::as_str).collect();
+                        let toc = pack_toc(&entries, prompt, max_toc_entries);
+                        match snippets.get(path) {
+                            Some(snippet) => format!("matched:\n{}\n{}", snippet, toc),
+                            None => toc,
+                        }
+                    }
+                    None => format!("[error reading {}]", path),
+                });
+                let builtin = format!("[WARM] {} (TOC){}\n{}", path, tags(path), body);
+                let section = render("WARM", "toc", path, &body, builtin);
+                (body, section, SOURCE_WARM)
+            }
+        };
+        let fingerprint = section_fingerprint("WARM", &body);
+        if unchanged_since(path, fingerprint) {
+            injected.insert(path.clone(), fingerprint);
+            unchanged.push(path.as_str());
+            continue;
+        }
+        if section.len() > room {
+            overflow += 1;
+        }
+        let Some(section) = fit_section(section, room) else {
+            continue;
+        };
+        chars_used += separator(&parts) + section.len();
+        add_source(&mut sources, source, &section);
+        parts.push(ContextPart::for_file(section, path, false));
+        injected.insert(path.clone(), fingerprint);
+    }
+
+    if !unchanged.is_empty() {
+        let line = format!("unchanged since last turn: {}", unchanged.join(", "));
+        let room = max_total_chars.saturating_sub(chars_used + separator(&parts));
+        if line.len() > room {
+            overflow += 1;
+        }
+        parts.extend(fit_section(line, room).map(ContextPart::new));
+    }
+    if let Some(memory) = memory {
+        let room = max_total_chars.saturating_sub(chars_used + separator(&parts));
+        if memory.len() > room {
+            overflow += 1;
+        }
+        if let Some(memory) = fit_section(memory.clone(), room) {
+            add_source(&mut sources, SOURCE_MEMORY, &memory);
+            parts.push(ContextPart::new(memory));
+        }
+    }
+    TieredContext {
+        parts,
+        injected,
+        sources,
+        overflow,
+        redactions,
+    }
+}
+
+/// Value stored under `key` in this session's state
+pub(crate) fn read_session_key<T: serde::de::DeserializeOwned>(
+    paths: &Paths,
+    key: &str,
+) -> Option<T> {
+    let content = std::fs::read_to_string(paths.session_state_path().ok()?).ok()?;
+    let mut session: serde_json::Value = serde_json::from_str(&content).ok()?;
+    serde_json::from_value(session.get_mut(key)?.take()).ok()
+}
+
+/// Store `value` under `key` in this session's state, keeping other keys
+pub(crate) fn write_session_key(
+    paths: &Paths,
+    key: &str,
+    value: &impl Serialize,
+) -> anyhow::Result<()> {
+    let path = paths.session_state_path()?;
+    let mut session = std::fs::read_to_string(&path)
+        .ok()
+        .and_then(|c| serde_json::from_str::<serde_json::Value>(&c).ok())
+        .filter(serde_json::Value::is_object)
+        .unwrap_or_else(|| serde_json::json!({}));
+    session[key] = serde_json::to_value(value)?;
+    let json = serde_json::to_string_pretty(&session)?;
+    attentive_telemetry::atomic_write(&path, json.as_bytes())?;
+    Ok(())
+}
+
+/// Fingerprints of what was injected on the previous turn of this session
+pub(crate) fn load_last_injected(paths: &Paths) -> InjectedSet {
+    read_session_key(paths, LAST_INJECTED_KEY).unwrap_or_default()
+}
+
+/// Record this turn's injected set in the session state
+fn save_last_injected(paths: &Paths, injected: &InjectedSet) -> anyhow::Result<()> {
+    write_session_key(paths, LAST_INJECTED_KEY, injected)
+}
+
+/// Remove `key` from this session's state, returning its value
+pub(crate) fn take_session_key<T: serde::de::DeserializeOwned>(
+    paths: &Paths,
+    key: &str,
+) -> Option<T> {
+    let path = paths.session_state_path().ok()?;
+    let mut session: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+    let value = session.as_object_mut()?.remove(key)?;
+    let json = serde_json::to_string_pretty(&session).ok()?;
+    attentive_telemetry::atomic_write(&path, json.as_bytes()).ok()?;
+    serde_json::from_value(value).ok()
+}
+
+/// Size of the session-start dashboard, if no turn has been charged for it yet
+fn take_dashboard_tokens(paths: &Paths) -> Option<usize> {
+    take_session_key::<u64>(paths, DASHBOARD_TOKENS_KEY)
+        .filter(|&t| t > 0)
+        .map(|t| t as usize)
+}
+
+/// Most imports listed in a WARM dependency hint
+const MAX_HINT_IMPORTS: usize = 5;
+
+/// One line of graph context for a WARM file: HOT files importing it, files it imports
+fn format_dependency_hint(
+    path: &str,
+    mapper: &attentive_repo::RepoMapper,
+    hot_files: &[String],
+) -> Option<String> {
+    let importers: Vec<String> = mapper
+        .imported_by(path)
+        .into_iter()
+        .filter(|f| hot_files.contains(f))
+        .collect();
+    let imports = mapper.imports_of(path);
+
+    let mut parts = Vec::new();
+    if !importers.is_empty() {
+        parts.push(format!("imported by HOT: {}", importers.join(", ")));
+    }
+    if !imports.is_empty() {
+        let mut listed = imports[..imports.len().min(MAX_HINT_IMPORTS)].join(", ");
+        if imports.len() > MAX_HINT_IMPORTS {
+            listed.push_str(&format!(" (+{} more)", imports.len() - MAX_HINT_IMPORTS));
+        }
+        parts.push(format!("imports: {}", listed));
+    }
+    (!parts.is_empty()).then(|| format!("↳ {}", parts.join("; ")))
 }
 
 fn detect_project_switch(session_state_path: &Path, current_project: &str) -> bool {
@@ -150,9 +730,11 @@ fn detect_project_switch(session_state_path: &Path, current_project: &str) -> bo
     switched
 }
 
+/// Session-start summary of recent turns, laid out by `template` if given
 fn build_dashboard(
     turns: &[attentive_telemetry::TurnRecord],
-    _learner: Option<&attentive_learn::Learner>,
+    _learner: Option<&Learner>,
+    template: Option<&str>,
 ) -> String {
     if turns.is_empty() {
         return String::new();
@@ -166,8 +748,10 @@ fn build_dashboard(
         .filter(|t| t.waste_ratio >= 0.0)
         .map(|t| t.waste_ratio)
         .collect();
+    let mut waste = None;
     if !waste_ratios.is_empty() {
         let avg_waste = waste_ratios.iter().sum::<f64>() / waste_ratios.len() as f64;
+        waste = Some(avg_waste * 100.0);
         let notif_count = turns.iter().filter(|t| t.was_notification).count();
         let notif_pct = notif_count as f64 / turns.len() as f64 * 100.0;
         lines.push(format!(
@@ -196,155 +780,1137 @@ fn build_dashboard(
         .map(|(f, &inj)| (*f, inj, *file_used.get(f).unwrap_or(&0)))
         .collect();
     waste_sorted.sort_by_key(|x| std::cmp::Reverse(x.1 as i64 - x.2 as i64));
-    if !waste_sorted.is_empty() {
-        let top3: Vec<String> = waste_sorted
+    let top_waste: Vec<WastedFile> = waste_sorted
+        .iter()
+        .take(3)
+        .map(|&(f, injected, used)| WastedFile {
+            path: f.to_string(),
+            injected,
+            used,
+        })
+        .collect();
+    if !top_waste.is_empty() {
+        let top3: Vec<String> = top_waste
             .iter()
-            .take(3)
-            .map(|(f, inj, used)| {
-                let name = Path::new(f)
+            .map(|w| {
+                let name = Path::new(&w.path)
                     .file_stem()
                     .and_then(|s| s.to_str())
-                    .unwrap_or(f);
-                format!("{}({}i/{}u)", name, inj, used)
+                    .unwrap_or(&w.path);
+                format!("{}({}i/{}u)", name, w.injected, w.used)
             })
             .collect();
         lines.push(format!("Top waste: {}", top3.join(", ")));
     }
 
-    lines.join("\n")
+    let builtin = lines.join("\n");
+    let Some(template) = template else {
+        return builtin;
+    };
+    let vars = DashboardVars {
+        content: &builtin,
+        turns: turns.len(),
+        waste,
+        top_waste: &top_waste,
+    };
+    render_template(template, &vars).unwrap_or_else(|e| {
+        tracing::warn!(
+            "dashboard template failed, using the built-in layout: {}",
+            e
+        );
+        builtin
+    })
 }
 
 #[derive(Debug, Deserialize)]
 struct PromptInput {
     prompt: String,
+    /// Active model, when the client reports it
+    #[serde(default)]
+    model: Option<String>,
+}
+
+/// Apply the `model_profiles` preset for the active model, returning the
+/// profile name. The model comes from `model` in the config, then the hook
+/// payload, then the last model recorded in `~/.claude/stats-cache.json`.
+pub(crate) fn apply_model_profile(
+    paths: &Paths,
+    config: &mut Config,
+    payload_model: Option<&str>,
+) -> Option<String> {
+    let model = config
+        .model
+        .clone()
+        .or_else(|| payload_model.map(str::to_string))
+        .or_else(|| stats_cache_model(&paths.home_claude))?;
+    config.apply_model_profile(&model)
+}
+
+fn stats_cache_model(home_claude: &Path) -> Option<String> {
+    let raw = std::fs::read_to_string(home_claude.join("stats-cache.json")).ok()?;
+    let value: serde_json::Value = serde_json::from_str(&raw).ok()?;
+    value.get("model")?.as_str().map(str::to_string)
 }
 
+/// Hook output. `prefix` is the stable slot (plugin policies) meant to sit
+/// ahead of everything else so it stays cached; `context` is the per-prompt
+/// block with file content.
 #[derive(Debug, Serialize)]
 struct PromptOutput {
+    #[serde(skip_serializing_if = "String::is_empty")]
+    prefix: String,
     context: String,
     metadata: serde_json::Value,
 }
 
-pub fn hook_user_prompt_submit() -> anyhow::Result<()> {
-    // 1. Read JSON from stdin
-    let mut input_str = String::new();
-    io::stdin().read_to_string(&mut input_str)?;
+/// Cap on cold index entries (highest last score first)
+const MAX_COLD_ENTRIES: usize = 50;
 
-    let input: PromptInput = serde_json::from_str(&input_str)?;
+/// First few outline entries of `path`, from `cache` while it's unchanged
+fn summarize_symbols(cache: &mut ContentCache, path: &str) -> String {
+    let entries = cache.outline(path).map(|(toc, _)| toc).unwrap_or_default();
+    let summary = entries[..entries.len().min(3)].join("; ");
+    match summary.char_indices().nth(80) {
+        Some((idx, _)) => format!("{}...", &summary[..idx]),
+        None => summary,
+    }
+}
 
-    // 2. Load or create attention state
-    let paths = Paths::new()?;
-    let project_dir = paths.project_dir()?;
-    std::fs::create_dir_all(&project_dir)?;
+fn build_cold_entries(
+    cache: &mut ContentCache,
+    cold_files: &[String],
+    state: &AttentionState,
+) -> Vec<attentive_plugins::recall::ColdEntry> {
+    let mut entries: Vec<_> = cold_files
+        .iter()
+        .map(|path| attentive_plugins::recall::ColdEntry {
+            path: path.clone(),
+            summary: String::new(),
+            score: state.scores.get(path).copied().unwrap_or(0.0),
+        })
+        .collect();
+    entries.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+    entries.truncate(MAX_COLD_ENTRIES);
+    for entry in &mut entries {
+        entry.summary = summarize_symbols(cache, &entry.path);
+    }
+    entries
+}
 
-    let state_path = paths.attn_state_path()?;
-    let mut state = if state_path.exists() {
-        let content = std::fs::read_to_string(&state_path)?;
-        serde_json::from_str(&content)?
-    } else {
-        AttentionState::new()
+/// Score given to caller files queued by the xref plugin (WARM)
+const XREF_CALLER_SCORE: f64 = 0.5;
+
+/// Score given to search index matches when routing found no HOT files (WARM)
+const SEARCH_FALLBACK_SCORE: f64 = 0.5;
+
+/// Most search index matches seeded per prompt
+const SEARCH_FALLBACK_RESULTS: usize = 3;
+
+/// Files from the project's search index (built by `init --project`) that
+/// match `prompt` or its learned synonyms, as attention keys, each with the
+/// lines it matched on. Empty when there is no index.
+fn search_fallback(
+    index_path: &Path,
+    prompt: &str,
+    synonyms: &Synonyms,
+    canonicalizer: &PathCanonicalizer,
+) -> Vec<(String, Option<String>)> {
+    if !index_path.exists() {
+        return Vec::new();
+    }
+    let Ok(index) = attentive_index::SearchIndex::new(index_path) else {
+        return Vec::new();
     };
+    let index = index.with_expansions(synonyms.term_map());
+    index
+        .query_lexical(prompt, SEARCH_FALLBACK_RESULTS)
+        .into_iter()
+        .filter(|(_, score)| *score > 0.0)
+        .map(|(path, _)| {
+            let snippet = index.snippet(&path, prompt).ok().flatten();
+            (
+                canonicalizer.canonicalize(&path),
+                snippet.map(|s| s.numbered()),
+            )
+        })
+        .collect()
+}
 
-    // 3. Create router with loaded config
-    let config = load_config(&paths.home_claude);
-    let router = Router::new(config);
+/// Result of running the prompt-submit pipeline for one prompt
+pub(crate) struct PromptPipeline {
+    /// Stable plugin text for the prefix slot, kept apart from `context` so it
+    /// is byte-identical across turns
+    pub prefix: String,
+    pub context: String,
+    pub hot_files: Vec<String>,
+    pub warm_files: Vec<String>,
+    pub cold_entries: Vec<attentive_plugins::recall::ColdEntry>,
+    pub metrics: RouterMetrics,
+    /// Context budget multiplier from BurnRate (1.0 = unthrottled)
+    pub budget_scale: f64,
+    /// Fingerprints of the HOT/WARM sections covered by `context`
+    pub injected: InjectedSet,
+    /// Estimated tokens each source contributed to `context`
+    pub sources: ContextSources,
+    /// HOT/WARM sections truncated or dropped to stay within the budget
+    pub overflow: usize,
+    /// Secrets redacted from the HOT/WARM sections
+    pub redactions: usize,
+    /// File contents read this turn, saved for the next one by the caller
+    pub content_cache: ContentCache,
+    /// Files listed as likely needed in the next turns
+    pub likely_next: Vec<String>,
+    /// HOT/WARM files boosted by the editor's open files or focused buffer
+    pub editor_files: Vec<String>,
+    /// How each budgeted plugin's addition fared against `plugin_budgets`
+    pub plugin_budgets: BTreeMap<String, PluginBudgetUsage>,
+    /// What each plugin's post-hook appended to `context`
+    pub plugin_messages: Vec<(String, String)>,
+    /// HOT/WARM files whose pinned or demoted rule the learner contradicts
+    pub conflicts: Vec<ConfigConflict>,
+    /// Estimated tokens the dedupe pass removed from `context`
+    pub deduped_tokens: usize,
+}
 
-    // 4. Initialize plugins
+impl PromptPipeline {
+    /// `prefix` ahead of `context`, for whatever has no prefix slot
+    pub fn full_context(&self) -> String {
+        with_prefix(&self.prefix, &self.context)
+    }
+}
+
+/// `prefix` and `context` as one block, the prefix first
+fn with_prefix(prefix: &str, context: &str) -> String {
+    [prefix, context]
+        .into_iter()
+        .filter(|part| !part.is_empty())
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Plugins' stable prefix text on its own, for outputs that skip the pipeline
+fn plugin_prefix(paths: &Paths, config: &Config) -> String {
     let mut registry = PluginRegistry::new();
+    registry.set_toggles(config.plugin_toggles.clone());
+    let registered = paths
+        .canonicalizer()
+        .map_err(anyhow::Error::from)
+        .and_then(|canonicalizer| {
+            register_builtin_plugins(&mut registry, paths, &canonicalizer, config)
+        });
+    if registered.is_err() {
+        return String::new();
+    }
+    registry
+        .on_prompt_prefix_by_plugin(&HashMap::new())
+        .into_iter()
+        .map(|(_, prefix)| prefix)
+        .collect::<Vec<_>>()
+        .join("\n\n")
+}
+
+/// Most memories injected per prompt
+const MAX_MEMORIES: usize = 5;
+
+/// Project memories relevant to the prompt (full-text match) or to HOT files
+fn select_memories(
+    db: &attentive_compress::ObservationDb,
+    project: &str,
+    prompt: &str,
+    hot_files: &[String],
+    canonicalizer: &PathCanonicalizer,
+) -> anyhow::Result<Vec<attentive_compress::CompressedObservation>> {
+    let mut selected = db.search_memories(project, prompt, MAX_MEMORIES)?;
+    let hot: std::collections::HashSet<String> =
+        hot_files.iter().map(|f| canonicalizer.key(f)).collect();
+    for memory in db.memories(project)? {
+        if selected.len() >= MAX_MEMORIES {
+            break;
+        }
+        let overlaps = memory
+            .related_files
+            .iter()
+            .any(|f| hot.contains(&canonicalizer.key(f)));
+        if overlaps && !selected.iter().any(|m| m.id == memory.id) {
+            selected.push(memory);
+        }
+    }
+    Ok(selected)
+}
+
+fn format_memory_section(memories: &[attentive_compress::CompressedObservation]) -> String {
+    let mut lines = vec!["## Project memory".to_string()];
+    for memory in memories {
+        if memory.related_files.is_empty() {
+            lines.push(format!("- {}", memory.semantic_summary));
+        } else {
+            lines.push(format!(
+                "- {} ({})",
+                memory.semantic_summary,
+                memory.related_files.join(", ")
+            ));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Drop or annotate (per `policy`) the observations whose related files
+/// changed since they were made, resolving paths against `root`
+fn flag_stale(
+    observations: &mut Vec<attentive_compress::CompressedObservation>,
+    root: &Path,
+    policy: StaleObservations,
+) {
+    observations.retain_mut(|observation| {
+        let changed = observation.changed_files(root);
+        if changed.is_empty() {
+            return true;
+        }
+        match policy {
+            StaleObservations::Exclude => false,
+            StaleObservations::Annotate => {
+                observation.semantic_summary.push_str(&format!(
+                    " (may be outdated: {} changed since)",
+                    changed.join(", ")
+                ));
+                true
+            }
+        }
+    });
+}
+
+/// Summary of the newest observation of each WARM file, keyed by path.
+/// Stale observations are skipped whatever `stale_observations` says, so the
+/// file keeps its outline: those with file hashes when a related file
+/// changed, older ones when made before the file's last modification.
+fn load_warm_observations(
+    paths: &Paths,
+    warm_files: &[String],
+    redactor: &Redactor,
+) -> HashMap<String, String> {
+    let db_path = paths.observations_db_path();
+    if warm_files.is_empty() || !db_path.exists() {
+        return HashMap::new();
+    }
+    let Ok(db) = attentive_compress::ObservationDb::new(&db_path) else {
+        return HashMap::new();
+    };
+    let db = db.with_redactor(redactor.clone());
+    warm_files
+        .iter()
+        .filter_map(|path| {
+            let observation = db.latest_for_file(path).ok()??;
+            let fresh = if observation.file_hashes.is_empty() {
+                let modified: chrono::DateTime<chrono::Utc> =
+                    std::fs::metadata(path).ok()?.modified().ok()?.into();
+                observation.timestamp >= modified
+            } else {
+                observation.changed_files(Path::new("")).is_empty()
+            };
+            fresh.then(|| (path.clone(), observation.semantic_summary))
+        })
+        .collect()
+}
+
+/// "Project memory" section from notes stored with `attentive remember`. The
+/// database is only opened for projects `remember` has marked.
+fn build_memory_section(
+    paths: &Paths,
+    canonicalizer: &PathCanonicalizer,
+    prompt: &str,
+    hot_files: &[String],
+    stale: StaleObservations,
+    redactor: &Redactor,
+) -> Option<String> {
+    if !paths.memory_marker_path(canonicalizer.root()).exists() {
+        return None;
+    }
+    let db = attentive_compress::ObservationDb::new(&paths.observations_db_path())
+        .ok()?
+        .with_redactor(redactor.clone());
+    let project = canonicalizer.root().to_string_lossy().to_string();
+    let mut memories = select_memories(&db, &project, prompt, hot_files, canonicalizer).ok()?;
+    flag_stale(&mut memories, canonicalizer.root(), stale);
+    (!memories.is_empty()).then(|| format_memory_section(&memories))
+}
+
+/// Most files listed as likely needed next
+const MAX_LIKELY_NEXT: usize = 5;
+
+/// Files the learner expects to follow the HOT set within the next turns,
+/// skipping WARM files (already outlined) and files no longer under `root`
+fn predict_likely_next(
+    learner: &Learner,
+    root: &Path,
+    hot_files: &[String],
+    warm_files: &[String],
+) -> Vec<String> {
+    learner
+        .predict_next(hot_files, MAX_LIKELY_NEXT + warm_files.len())
+        .into_iter()
+        .map(|(path, _)| path)
+        .filter(|path| !warm_files.contains(path) && root.join(path).is_file())
+        .take(MAX_LIKELY_NEXT)
+        .collect()
+}
+
+/// Paths only, so Claude can Read them up front if the task heads there
+fn format_likely_next(paths: &[String]) -> String {
+    format!("Likely needed next: {}", paths.join(", "))
+}
+
+/// Scale a size limit by `scale`, never below 1
+fn scale_limit(limit: usize, scale: f64) -> usize {
+    ((limit as f64 * scale).round() as usize).max(1)
+}
+
+/// Register the built-in plugins, pointing those that read project files at
+/// the project `paths` resolves rather than the working directory
+pub(crate) fn register_builtin_plugins(
+    registry: &mut PluginRegistry,
+    paths: &Paths,
+    canonicalizer: &PathCanonicalizer,
+    config: &Config,
+) -> anyhow::Result<()> {
+    let root = canonicalizer.root().to_path_buf();
     registry.register(Box::new(attentive_plugins::BurnRatePlugin::new()));
+    registry.register(Box::new(
+        attentive_plugins::GuardrailPlugin::new().with_repo_root(root.clone()),
+    ));
     registry.register(Box::new(attentive_plugins::LoopBreakerPlugin::new()));
     registry.register(Box::new(attentive_plugins::VerifyFirstPlugin::new()));
+    registry.register(Box::new(
+        attentive_plugins::RecallPlugin::new()
+            .with_cold_index(paths.cold_index_path()?)
+            .with_canonicalizer(canonicalizer.clone()),
+    ));
+    registry.register(Box::new(attentive_plugins::XrefPlugin::new().with_repo(
+        root,
+        config.exclude_patterns(),
+        paths.xref_index_path()?,
+    )));
+    Ok(())
+}
 
-    // 5. Run plugin pre-hooks
-    let session_state = std::collections::HashMap::new();
-    let (prompt, should_continue) = registry.on_prompt_pre(input.prompt, &session_state);
+/// Run decay, boosts, learned floors and plugins against `state`.
+///
+/// Returns `None` when a plugin pre-hook stops the prompt. Persisting `state`
+/// is left to the caller so dry runs can discard it. `previous_injected` is
+/// last turn's injected set, used in delta mode.
+pub(crate) fn run_prompt_pipeline(
+    paths: &Paths,
+    prompt: String,
+    state: &mut AttentionState,
+    config: Config,
+    learner: Option<&Learner>,
+    previous_injected: Option<&InjectedSet>,
+) -> anyhow::Result<Option<PromptPipeline>> {
+    // 1. Initialize plugins and collect shared session state
+    let canonicalizer = paths.canonicalizer()?;
+    let mut registry = PluginRegistry::new();
+    registry.set_toggles(config.plugin_toggles.clone());
+    registry.set_budgets(config.plugin_budgets.clone());
+    register_builtin_plugins(&mut registry, paths, &canonicalizer, &config)?;
+
+    let mut session_state = std::collections::HashMap::new();
+    registry.share_session_state(&mut session_state);
+
+    // 2. Shrink the context budget when BurnRate reports the rate limit is near
+    let budget_scale = session_state
+        .get(attentive_plugins::burnrate::CONTEXT_SCALE_KEY)
+        .and_then(|v| v.as_f64())
+        .unwrap_or(1.0)
+        .clamp(attentive_plugins::burnrate::MIN_CONTEXT_SCALE, 1.0);
+    let mut config = config;
+    let scope = path_scope(paths, &config);
+    config.max_hot_files = scale_limit(config.max_hot_files, budget_scale);
+    let provenance_tags = config.provenance_tags;
+    let stale_observations = config.stale_observations;
+    let templates = config.templates.clone();
+    let redactor = config.redactor();
+    let max_total_chars = scale_limit(
+        config.max_context_chars,
+        budget_scale * config.context_scale,
+    );
+    let max_toc_entries = config.max_toc_entries;
+    let active_files =
+        super::active_files::load(&paths.active_files_path()?, &canonicalizer, &scope);
+    let conflict_checker = ConflictChecker::new(&config);
+    let mut keyword_rules = super::learn::load_keyword_rules(&paths.keyword_rules_path()?);
+    keyword_rules.retain_strong_keywords(|file| scope.allows(file));
+    let router = Router::new(config)
+        .with_active_files(active_files.files)
+        .with_active_buffer(active_files.active)
+        .with_keyword_rules(keyword_rules);
+
+    // 3. Run plugin pre-hooks
+    let (prompt, should_continue) = registry.on_prompt_pre(prompt, &session_state);
 
     if !should_continue {
-        return Ok(());
+        return Ok(None);
     }
 
-    // 6. Run router (decay + learner boost), then enforce learned floors
-    let learned_state_path = paths.learned_state_path()?;
-    let learner = load_learner(&learned_state_path);
+    // Soft pins follow CLAUDE.md / AGENTS.md edits made mid-session
+    state.instruction_refs.refresh(&canonicalizer);
 
-    let _activated = router.update_attention(&mut state, &prompt, learner.as_ref());
+    // 4. Run router (decay + learner boost + plugin score hooks), then
+    // enforce learned floors
+    let mut metrics = RouterMetrics::new();
+    let _activated = router.update_attention_adjusted(
+        state,
+        &prompt,
+        learner,
+        &mut metrics,
+        &mut |scores, prompt| registry.on_scores(scores, prompt),
+    );
 
     // Enforce floors for learned files — warmup files stay HOT, frequent files stay WARM
-    if let Some(l) = &learner {
-        for file in l.get_warmup() {
+    if let Some(l) = learner {
+        for file in l.get_warmup().into_iter().filter(|f| scope.allows(f)) {
+            metrics.note(&file, Provenance::LastSession);
             let score = state.scores.entry(file).or_insert(0.0);
             *score = score.max(0.8);
         }
-        for (file, _freq) in l.top_files_by_frequency(20) {
+        for (file, _freq) in l
+            .top_files_by_frequency(20)
+            .into_iter()
+            .filter(|(f, _)| scope.allows(f))
+        {
+            metrics.note(&file, Provenance::Frequent);
             let score = state.scores.entry(file).or_insert(0.0);
             *score = score.max(0.4);
         }
     }
 
-    let (hot_files, warm_files, _cold_files) = router.build_context_output(&state);
+    // Warm the files this project's prompts of the same task type usually need
+    let oracle: Option<Oracle> = paths
+        .oracle_state_path()
+        .ok()
+        .filter(|p| p.exists())
+        .and_then(|p| {
+            super::serve::read_cached(&p, |c| Ok(attentive_telemetry::from_versioned_str(c)?)).ok()
+        });
+    if let Some(oracle) = oracle {
+        let (task_type, confidence) = oracle.classify_with_confidence(&prompt);
+        if confidence >= MIN_PREFETCH_CONFIDENCE {
+            for file in oracle.prefetch_files(task_type) {
+                if !scope.allows(&file) {
+                    continue;
+                }
+                metrics.note(
+                    &file,
+                    Provenance::TaskPrefetch {
+                        task: task_type.as_str().to_string(),
+                    },
+                );
+                let score = state.scores.entry(file).or_insert(0.0);
+                *score = score.max(0.4);
+            }
+        }
+    }
 
-    // 7. Build context string (HOT: full content, WARM: TOC, COLD: evicted)
-    let context_output = build_tiered_context(&hot_files, &warm_files, MAX_TOTAL_CHARS);
+    // Re-heat files recalled from the cold index, or named in `@recall` as
+    // typed
+    let recall = attentive_plugins::RecallPlugin::new();
+    if registry.allows(&recall) {
+        let pending = canonicalizer.canonicalize_all(&recall.take_pending());
+        for file in scope.retain(pending) {
+            metrics.note(&file, Provenance::Recalled);
+            let score = state.scores.entry(file).or_insert(0.0);
+            *score = score.max(1.0);
+        }
+    }
 
-    // 8. Run plugin post-hooks
-    let additional_context = registry.on_prompt_post(&prompt, &context_output, &session_state);
+    // Pull in callers of symbols modified last turn as WARM
+    let xref = attentive_plugins::XrefPlugin::new();
+    if registry.allows(&xref) {
+        for file in xref.take_pending() {
+            metrics.note(&file, Provenance::Caller);
+            let score = state.scores.entry(file).or_insert(0.0);
+            *score = score.max(XREF_CALLER_SCORE);
+        }
+    }
 
-    // 9. Save state
-    let state_json = serde_json::to_string_pretty(&state)?;
-    attentive_telemetry::atomic_write(&state_path, state_json.as_bytes())?;
+    // Cold start or unfamiliar prompt: fall back to the search index for
+    // provisional WARM entries, which later turns heat or decay as usual
+    let mut search_snippets = HashMap::new();
+    if state.get_hot_files().is_empty() {
+        let synonyms = learner.map(Learner::synonyms).cloned().unwrap_or_default();
+        let index_path = paths.search_index_path()?;
+        for (file, snippet) in search_fallback(&index_path, &prompt, &synonyms, &canonicalizer) {
+            metrics.note(&file, Provenance::Searched);
+            if let Some(snippet) = snippet {
+                search_snippets.insert(file.clone(), snippet);
+            }
+            let score = state.scores.entry(file).or_insert(0.0);
+            *score = score.max(SEARCH_FALLBACK_SCORE);
+        }
+    }
 
-    // 10. Write output to stdout
-    let output = PromptOutput {
-        context: if additional_context.is_empty() {
-            context_output
-        } else {
-            format!("{}\n{}", context_output, additional_context)
+    let (hot_files, warm_files, cold_files) =
+        router.build_context_output_with_metrics(state, &mut metrics);
+
+    // 5. Build context string (HOT: full content, WARM: TOC, COLD: pointer to index)
+    let mut content_cache =
+        ContentCache::load(&paths.content_cache_path()?).in_root(canonicalizer.root());
+    let TieredContext {
+        mut parts,
+        injected,
+        mut sources,
+        overflow,
+        redactions,
+    } = build_tiered_context(
+        &mut content_cache,
+        &hot_files,
+        &warm_files,
+        &prompt,
+        max_total_chars,
+        max_toc_entries,
+        &ContextExtras {
+            observations: load_warm_observations(paths, &warm_files, &redactor),
+            snippets: search_snippets,
+            provenance: provenance_tags.then_some(&metrics),
+            previous: previous_injected,
+            scores: Some(&state.scores),
+            templates: Some(&templates),
+            redactor: Some(&redactor).filter(|r| !r.is_empty()),
+            memory: build_memory_section(
+                paths,
+                &canonicalizer,
+                &prompt,
+                &hot_files,
+                stale_observations,
+                &redactor,
+            ),
         },
+    );
+    let likely_next = learner
+        .map(|l| predict_likely_next(l, canonicalizer.root(), &hot_files, &warm_files))
+        .unwrap_or_default();
+    if !likely_next.is_empty() {
+        let line = format_likely_next(&likely_next);
+        add_source(&mut sources, SOURCE_LIKELY_NEXT, &line);
+        parts.push(ContextPart::new(line));
+    }
+    let cold_entries = build_cold_entries(&mut content_cache, &cold_files, state);
+    let pointer =
+        attentive_plugins::recall::format_cold_pointer(&cold_entries, &paths.cold_index_path()?);
+    if !pointer.is_empty() {
+        add_source(&mut sources, SOURCE_COLD_INDEX, &pointer);
+        parts.push(ContextPart::new(pointer));
+    }
+    let context_output = join_parts(&parts, "\n\n");
+
+    // 6. Run plugin prefix and post-hooks
+    let prefixes = registry.on_prompt_prefix_by_plugin(&session_state);
+    for (plugin, prefix) in &prefixes {
+        add_source(&mut sources, &format!("plugin:{}", plugin), prefix);
+    }
+    let prefix = prefixes
+        .into_iter()
+        .map(|(_, prefix)| prefix)
+        .collect::<Vec<_>>()
+        .join("\n\n");
+    let additions = registry.on_prompt_post_by_plugin(&prompt, &context_output, &session_state);
+    for (plugin, addition) in &additions {
+        add_source(&mut sources, &format!("plugin:{}", plugin), addition);
+    }
+    let plugin_budgets = registry.budget_usage().clone();
+
+    // 7. Drop what the sections repeat of each other, plugin additions included
+    let sections = parts.len();
+    parts.extend(
+        additions
+            .iter()
+            .map(|(_, addition)| ContextPart::new(addition.clone())),
+    );
+    let deduped_tokens = dedupe(&mut parts);
+    let context_output = join_parts(&parts[..sections], "\n\n");
+    let additional_context = join_parts(&parts[sections..], "\n");
+    let context = if additional_context.is_empty() {
+        context_output
+    } else {
+        format!("{}\n{}", context_output, additional_context)
+    };
+    let editor_files = hot_files
+        .iter()
+        .chain(&warm_files)
+        .filter(|file| {
+            metrics
+                .reasons(file)
+                .iter()
+                .any(|r| matches!(r, Provenance::Editor | Provenance::ActiveBuffer))
+        })
+        .cloned()
+        .collect();
+    // A demoted file counts as boosted by what the learner added this turn
+    let conflicts = match learner {
+        Some(learner) => hot_files
+            .iter()
+            .chain(&warm_files)
+            .filter_map(|file| {
+                let boost = metrics
+                    .reasons(file)
+                    .iter()
+                    .find_map(|r| match r {
+                        Provenance::LearnedAffinity(affinity) => Some(*affinity),
+                        _ => None,
+                    })
+                    .unwrap_or(0.0);
+                conflict_checker.check(file, learner, boost)
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    Ok(Some(PromptPipeline {
+        prefix,
+        context,
+        hot_files,
+        warm_files,
+        cold_entries,
+        metrics,
+        budget_scale,
+        injected,
+        sources,
+        overflow,
+        redactions,
+        content_cache,
+        likely_next,
+        editor_files,
+        plugin_budgets,
+        plugin_messages: additions,
+        conflicts,
+        deduped_tokens,
+    }))
+}
+
+/// Load the project's attention state, or a fresh one if none exists yet
+pub(crate) fn load_attention_state(paths: &Paths) -> anyhow::Result<AttentionState> {
+    let state_path = paths.attn_state_path()?;
+    if state_path.exists() {
+        super::serve::read_cached(&state_path, |c| {
+            Ok(attentive_telemetry::from_versioned_str(c)?)
+        })
+    } else {
+        Ok(AttentionState::new())
+    }
+}
+
+/// Context emitted when routing misses the deadline: last turn's tiers, names only
+fn build_deadline_fallback(state: &AttentionState, deadline_ms: u64) -> String {
+    let mut hot = state.get_hot_files();
+    let mut warm = state.get_warm_files();
+    hot.sort();
+    warm.sort();
+    let mut lines = vec![format!(
+        "[attentive] Context routing exceeded the {}ms deadline; showing last known tiers.",
+        deadline_ms
+    )];
+    if !hot.is_empty() {
+        lines.push(format!("HOT: {}", hot.join(", ")));
+    }
+    if !warm.is_empty() {
+        lines.push(format!("WARM: {}", warm.join(", ")));
+    }
+    lines.join("\n")
+}
+
+/// What a hook prints, captured so the state server can relay it
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct HookOutput {
+    pub stdout: String,
+    pub stderr: Vec<String>,
+}
+
+impl HookOutput {
+    fn emit(&self) -> anyhow::Result<()> {
+        io::stdout().write_all(self.stdout.as_bytes())?;
+        io::stdout().flush()?;
+        for line in &self.stderr {
+            eprintln!("{}", line);
+        }
+        Ok(())
+    }
+}
+
+fn prompt_output(output: &PromptOutput) -> anyhow::Result<HookOutput> {
+    Ok(HookOutput {
+        stdout: serde_json::to_string(output)?,
+        stderr: Vec::new(),
+    })
+}
+
+fn read_stdin() -> anyhow::Result<String> {
+    let mut input = String::new();
+    io::stdin().read_to_string(&mut input)?;
+    Ok(input)
+}
+
+pub fn hook_user_prompt_submit() -> anyhow::Result<()> {
+    let input = read_stdin()?;
+    if let Some(output) = super::serve::proxy(super::serve::Hook::UserPromptSubmit, &input) {
+        return output.emit();
+    }
+    user_prompt_submit(Paths::new()?, &input, false, |output| output.emit())
+}
+
+/// Run the prompt hook on its stdin JSON for the project `paths` resolves.
+/// `emit` receives the output as soon as it's ready; state is persisted
+/// afterwards. A pipeline that blows the deadline still runs to the end and
+/// saves its state after the fallback is emitted, so attention state keeps
+/// up with the plugin state it saved. Loads that blow it are abandoned when
+/// the process exits, unless `finish_late` (the state server) waits them out.
+pub(crate) fn user_prompt_submit(
+    paths: Paths,
+    input: &str,
+    finish_late: bool,
+    emit: impl FnOnce(HookOutput) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    let runtime = tokio::runtime::Builder::new_multi_thread()
+        .enable_all()
+        .build()?;
+    let result = runtime.block_on(user_prompt_submit_async(paths, input, emit));
+    if !finish_late {
+        // Don't wait on loads that blew the deadline
+        runtime.shutdown_background();
+    }
+    result
+}
+
+async fn user_prompt_submit_async(
+    paths: Paths,
+    input: &str,
+    emit: impl FnOnce(HookOutput) -> anyhow::Result<()>,
+) -> anyhow::Result<()> {
+    use tokio::task::spawn_blocking;
+
+    let started = std::time::Instant::now();
+
+    // 1. Parse the hook's stdin JSON
+    let input: PromptInput = serde_json::from_str(input)?;
+
+    let project_dir = paths.project_dir()?;
+    std::fs::create_dir_all(&project_dir)?;
+
+    // 2. Config is small and sets the deadline; state and learner load concurrently
+    let mut config = load_project_config(&paths);
+    let deadline_ms = config.hook_deadline_ms;
+    let deadline = started + std::time::Duration::from_millis(deadline_ms);
+
+    // A branch checked out mid-session swaps in that branch's state first
+    if let Err(e) = super::branches::switch(&paths, config.branch_state) {
+        tracing::warn!("switching branch attention state failed: {}", e);
+    }
+
+    let state_paths = paths.clone();
+    let learner_path = paths.learned_state_path()?;
+    let synonyms_path = paths.synonyms_path()?;
+    let half_life = config.learner_half_life;
+    let learner_prompt = input.prompt.clone();
+    let loads = async {
+        tokio::join!(
+            spawn_blocking(move || load_attention_state(&state_paths)),
+            spawn_blocking(move || {
+                let synonyms = Synonyms::load(&synonyms_path);
+                load_learner_for_prompt(&learner_path, &learner_prompt, synonyms)
+                    .map(|l| l.with_half_life(half_life))
+            }),
+        )
+    };
+    let (mut state, learner) = match tokio::time::timeout_at(deadline.into(), loads).await {
+        Ok((state, learner)) => (state??, learner?),
+        Err(_) => {
+            let fallback = build_deadline_fallback(&AttentionState::new(), deadline_ms);
+            return emit(prompt_output(&PromptOutput {
+                prefix: String::new(),
+                context: with_prefix(&plugin_prefix(&paths, &config), &fallback),
+                metadata: serde_json::json!({ "deadline_exceeded": true }),
+            })?);
+        }
+    };
+
+    // 3. Apply inline `@attentive` directives, which are not routed themselves
+    let (prompt, directives) =
+        super::directives::apply(&paths, &input.prompt, &mut config, &mut state, true);
+
+    // 4. Route, build context and run plugins within what's left of the deadline
+    let prompt_tokens = attentive_telemetry::estimate_tokens(&prompt);
+    let task_type = attentive_learn::Oracle::new().classify_task(&prompt);
+    apply_model_profile(&paths, &mut config, input.model.as_deref());
+    config.apply_task_profile(task_type.as_str());
+    let previous_state = state.clone();
+    let fallback_config = config.clone();
+    let pipeline_paths = paths.clone();
+    let delta_context = config.delta_context;
+    let mut pipeline = spawn_blocking(move || {
+        let mut state = state;
+        let previous_injected = delta_context.then(|| load_last_injected(&pipeline_paths));
+        run_prompt_pipeline(
+            &pipeline_paths,
+            prompt,
+            &mut state,
+            config,
+            learner.as_ref(),
+            previous_injected.as_ref(),
+        )
+        .map(|result| (result, state))
+    });
+    let (result, state) = match tokio::time::timeout_at(deadline.into(), &mut pipeline).await {
+        Ok(joined) => joined??,
+        Err(_) => {
+            let fallback = build_deadline_fallback(&previous_state, deadline_ms);
+            emit(prompt_output(&PromptOutput {
+                prefix: String::new(),
+                context: with_prefix(&plugin_prefix(&paths, &fallback_config), &fallback),
+                metadata: serde_json::json!({ "deadline_exceeded": true }),
+            })?)?;
+            // Plugins have saved their state for this turn, or will; the
+            // routed state goes with it even though its context came too late
+            let (result, state) = pipeline.await??;
+            if let Some(result) = result {
+                spawn_blocking(move || {
+                    save_pipeline_state(&paths, &state, &result.cold_entries, &result.content_cache)
+                })
+                .await??;
+            }
+            return Ok(());
+        }
+    };
+    let Some(mut result) = result else {
+        return emit(HookOutput::default());
+    };
+    if !directives.is_empty() {
+        result.context = format!("{}\n\n{}", directives, result.context);
+    }
+    // Once after compaction, remind Claude of the working set it lost, and
+    // of the policies that went with the prefix
+    let brief: Option<CompactionBrief> = take_session_key(&paths, COMPACTION_BRIEF_KEY);
+    if let Some(brief) = brief {
+        let brief = brief.format();
+        add_source(&mut result.sources, SOURCE_COMPACTION_BRIEF, &brief);
+        result.context = format!("{}\n\n{}", brief, result.context);
+        result.context = result.full_context();
+        result.prefix.clear();
+    }
+
+    // Sizes are recorded per turn so the Stop hook can calibrate token counts
+    let mut injected_files = result.hot_files.clone();
+    injected_files.extend(result.warm_files.iter().cloned());
+    let injection = attentive_telemetry::InjectionRecord {
+        turn: state.turn_count,
+        timestamp: attentive_telemetry::now(),
+        chars: result.prefix.len() + result.context.len(),
+        estimated_tokens: attentive_telemetry::estimate_tokens(&result.prefix)
+            + attentive_telemetry::estimate_tokens(&result.context),
+        prompt_tokens,
+        files: injected_files,
+        sources: result.sources,
+        overflow: result.overflow,
+        redactions: result.redactions,
+        deduped_tokens: result.deduped_tokens,
+        task_type: Some(task_type.as_str().to_string()),
+        predicted: result.likely_next.clone(),
+        editor_files: result.editor_files.clone(),
+        plugin_budgets: result.plugin_budgets.clone(),
+        conflicts: result.conflicts.iter().map(|c| c.path.clone()).collect(),
+    };
+
+    // 5. Write output to stdout first so Claude isn't kept waiting on disk
+    emit(prompt_output(&PromptOutput {
+        prefix: result.prefix,
+        context: result.context,
         metadata: serde_json::json!({
-            "hot_count": hot_files.len(),
-            "warm_count": warm_files.len(),
+            "hot_count": result.hot_files.len(),
+            "warm_count": result.warm_files.len(),
+            "router_metrics": result.metrics,
+            "budget_scale": result.budget_scale,
+            "injected_chars": injection.chars,
+            "injected_tokens": injection.estimated_tokens,
+            "budget_overflow": result.overflow,
+            "redactions": result.redactions,
+            "deduped_tokens": result.deduped_tokens,
+            "plugin_budgets": result.plugin_budgets,
         }),
-    };
+    })?)?;
+
+    // 6. Persist state and the cold index for recall in the background
+    let cold_entries = result.cold_entries;
+    let injected = result.injected;
+    let content_cache = result.content_cache;
+    spawn_blocking(move || -> anyhow::Result<()> {
+        save_pipeline_state(&paths, &state, &cold_entries, &content_cache)?;
+        attentive_telemetry::record_injection(&paths.injections_path()?, injection)?;
+        if delta_context {
+            save_last_injected(&paths, &injected)?;
+        }
+        Ok(())
+    })
+    .await??;
 
-    let output_json = serde_json::to_string(&output)?;
-    io::stdout().write_all(output_json.as_bytes())?;
-    io::stdout().flush()?;
+    Ok(())
+}
 
+/// Persist what routing a prompt changed: attention state, the cold index
+/// for recall and the content cache
+fn save_pipeline_state(
+    paths: &Paths,
+    state: &AttentionState,
+    cold_entries: &[attentive_plugins::recall::ColdEntry],
+    content_cache: &ContentCache,
+) -> anyhow::Result<()> {
+    let state_path = paths.attn_state_path()?;
+    attentive_telemetry::write_versioned(&state_path, state)?;
+    super::serve::store_cached(&state_path, state);
+    attentive_plugins::recall::write_cold_index(&paths.cold_index_path()?, cold_entries)?;
+    content_cache.save(&paths.content_cache_path()?)?;
     Ok(())
 }
 
 pub fn hook_session_start() -> anyhow::Result<()> {
-    let paths = Paths::new()?;
+    if let Some(output) = super::serve::proxy(super::serve::Hook::SessionStart, "") {
+        return output.emit();
+    }
+    session_start(&Paths::new()?)?.emit()
+}
+
+/// Most files seeded from git history on a project's first session
+const GIT_SEED_FILES: usize = 8;
+
+/// Lowest and highest seeded score, given to the least and most active files.
+/// After the first prompt's decay (0.7 by default) they're still WARM.
+const GIT_SEED_SCORES: (f64, f64) = (0.375, 0.5);
+
+/// Seed `state` with the most active files of `ranked` (from
+/// `git_recent_files`, repo-relative) that are `scannable` and in `scope`.
+/// Returns how many were seeded.
+fn seed_from_git(
+    state: &mut AttentionState,
+    ranked: &[(String, f64)],
+    scannable: &std::collections::HashSet<String>,
+    scope: &PathScope,
+) -> usize {
+    let (low, high) = GIT_SEED_SCORES;
+    let seeds: Vec<&(String, f64)> = ranked
+        .iter()
+        .filter(|(file, _)| scannable.contains(file) && scope.allows(file))
+        .take(GIT_SEED_FILES)
+        .collect();
+    for (file, activity) in &seeds {
+        state
+            .scores
+            .insert(file.clone(), low + (high - low) * activity.clamp(0.0, 1.0));
+    }
+    seeds.len()
+}
+
+pub(crate) fn session_start(paths: &Paths) -> anyhow::Result<HookOutput> {
+    let mut output = HookOutput::default();
     let project_dir = paths.project_dir()?;
     std::fs::create_dir_all(&project_dir)?;
 
     // 1. Detect project switch (legacy — less relevant with project-scoped state)
-    let cwd = std::env::current_dir()?.to_string_lossy().to_lowercase();
+    let cwd = paths.cwd()?.to_string_lossy().to_lowercase();
     let session_state_path = paths.session_state_path()?;
 
     if detect_project_switch(&session_state_path, &cwd) {
         // Reset attention state
         let attn_path = paths.attn_state_path()?;
         if attn_path.exists()
-            && let Ok(content) = std::fs::read_to_string(&attn_path)
-            && let Ok(mut state) = serde_json::from_str::<AttentionState>(&content)
+            && let Ok(mut state) = attentive_telemetry::read_versioned::<AttentionState>(&attn_path)
         {
             for score in state.scores.values_mut() {
                 *score = 0.0;
             }
             state.turn_count = 0;
-            if let Ok(json) = serde_json::to_string_pretty(&state) {
-                let _ = attentive_telemetry::atomic_write(&attn_path, json.as_bytes());
+            let _ = attentive_telemetry::write_versioned(&attn_path, &state);
+        }
+        output
+            .stderr
+            .push("[attentive] Project switch detected, attention reset".to_string());
+    }
+
+    // 1a. Swap in the checked-out branch's attention state
+    match super::branches::switch(paths, load_project_config(paths).branch_state) {
+        Ok(Some(message)) => output.stderr.push(format!("[attentive] {}", message)),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("switching branch attention state failed: {}", e),
+    }
+
+    // 1b. Migrate absolute or unnormalized paths to canonical repo-relative
+    // keys, carry learned state across files renamed since HEAD last moved,
+    // then drop files outside the project
+    if let Ok(canonicalizer) = paths.canonicalizer() {
+        let _ = super::remap::canonicalize_learned_state(paths, &canonicalizer);
+        if let Ok(moved) = super::remap::apply_git_renames(paths, &canonicalizer)
+            && moved > 0
+        {
+            output.stderr.push(format!(
+                "[attentive] Remapped {} entries for renamed files",
+                moved
+            ));
+        }
+        let config = load_project_config(paths);
+        let scope = path_scope(paths, &config);
+        if let Ok(pruned) = super::remap::prune_foreign_state(paths, &scope)
+            && pruned > 0
+        {
+            output.stderr.push(format!(
+                "[attentive] Dropped {} file(s) outside the project from learned state",
+                pruned
+            ));
+        }
+
+        // 1c. Soft-pin files referenced in CLAUDE.md / AGENTS.md
+        if let Ok(mut state) = load_attention_state(paths)
+            && state.instruction_refs.refresh(&canonicalizer)
+            && attentive_telemetry::write_versioned(&paths.attn_state_path()?, &state).is_ok()
+            && !state.instruction_refs.files.is_empty()
+        {
+            output.stderr.push(format!(
+                "[attentive] Keeping {} file(s) referenced in project instructions warm",
+                state.instruction_refs.files.len()
+            ));
+        }
+
+        // 1d. Brand-new project (no attention state or learner yet): seed
+        // WARM candidates from the files git shows changing most, most recently
+        if !paths.learned_state_path()?.exists()
+            && let Ok(mut state) = load_attention_state(paths)
+            && state.scores.is_empty()
+        {
+            let recent = attentive_repo::git_recent_files(
+                canonicalizer.root(),
+                attentive_repo::RECENCY_LOOKBACK_COMMITS,
+            );
+            let scannable: std::collections::HashSet<String> = if recent.is_empty() {
+                Default::default()
+            } else {
+                attentive_repo::repo_files(canonicalizer.root(), &config.exclude_patterns())
+                    .into_iter()
+                    .collect()
+            };
+            let seeded = seed_from_git(&mut state, &recent, &scannable, &scope);
+            if seeded > 0
+                && attentive_telemetry::write_versioned(&paths.attn_state_path()?, &state).is_ok()
+            {
+                output.stderr.push(format!(
+                    "[attentive] New project: seeded {} recently changed file(s) from git history",
+                    seeded
+                ));
             }
         }
-        eprintln!("[attentive] Project switch detected, attention reset");
     }
 
     // 2. Initialize plugins
+    let config = load_project_config(paths);
     let mut registry = PluginRegistry::new();
-    registry.register(Box::new(attentive_plugins::BurnRatePlugin::new()));
-    registry.register(Box::new(attentive_plugins::LoopBreakerPlugin::new()));
-    registry.register(Box::new(attentive_plugins::VerifyFirstPlugin::new()));
+    registry.set_toggles(config.plugin_toggles.clone());
+    register_builtin_plugins(&mut registry, paths, &paths.canonicalizer()?, &config)?;
 
     let session_state = std::collections::HashMap::new();
     let messages = registry.on_session_start(&session_state);
@@ -353,39 +1919,136 @@ pub fn hook_session_start() -> anyhow::Result<()> {
     let turns: Vec<attentive_telemetry::TurnRecord> =
         attentive_telemetry::read_jsonl(&paths.turns_file()).unwrap_or_default();
     let recent: Vec<_> = turns.into_iter().rev().take(100).collect();
-    let dashboard = build_dashboard(&recent, None);
+    let config = load_config(&paths.home_claude);
+    let dashboard = build_dashboard(&recent, None, config.templates.dashboard.as_deref());
     if !dashboard.is_empty() {
-        println!("{}", dashboard);
+        output.stdout = format!("{}\n", dashboard);
     }
 
     // 4. Write session state
     let session_state_file = paths.session_state_path()?;
     let session_data = serde_json::json!({
         "session_id": uuid_simple(),
-        "started_at": chrono::Utc::now().to_rfc3339(),
+        "started_at": attentive_telemetry::now().to_rfc3339(),
         "plugin_messages": messages,
+        DASHBOARD_TOKENS_KEY: attentive_telemetry::estimate_tokens(&dashboard),
     });
 
     let json = serde_json::to_string_pretty(&session_data)?;
     attentive_telemetry::atomic_write(&session_state_file, json.as_bytes())?;
 
     // 5. Output plugin messages to stderr
-    for msg in &messages {
-        eprintln!("{}", msg);
+    output.stderr.extend(messages);
+
+    Ok(output)
+}
+
+pub fn hook_pre_tool_use() -> anyhow::Result<()> {
+    pre_tool_use(&read_stdin()?)?.emit()
+}
+
+/// PreToolUse hook: when a plugin flags the pending tool call, ask the user
+/// to confirm it, with the plugins' warnings as the reason
+pub(crate) fn pre_tool_use(input: &str) -> anyhow::Result<HookOutput> {
+    // Input: {tool_name, tool_input: {...}, session_id, cwd, ...}
+    let input: serde_json::Value = serde_json::from_str(input)?;
+    let tool = input
+        .get("tool_name")
+        .and_then(|v| v.as_str())
+        .unwrap_or("");
+    let empty = serde_json::json!({});
+    let tool_call =
+        attentive_plugins::ToolCall::from_input(tool, input.get("tool_input").unwrap_or(&empty));
+
+    let mut registry = PluginRegistry::new();
+    registry.set_toggles(load_config(&Paths::new()?.home_claude).plugin_toggles);
+    registry.register(Box::new(attentive_plugins::GuardrailPlugin::new()));
+    let warnings = registry.on_pre_tool_use(&tool_call);
+    if warnings.is_empty() {
+        return Ok(HookOutput::default());
     }
 
-    Ok(())
+    let output = serde_json::json!({
+        "hookSpecificOutput": {
+            "hookEventName": "PreToolUse",
+            "permissionDecision": "ask",
+            "permissionDecisionReason": warnings.join("\n"),
+        }
+    });
+    Ok(HookOutput {
+        stdout: serde_json::to_string(&output)?,
+        stderr: Vec::new(),
+    })
+}
+
+pub fn hook_pre_compact() -> anyhow::Result<()> {
+    pre_compact(&read_stdin()?)?.emit()
+}
+
+/// PreCompact hook: save the working set as a brief for the next prompt, and
+/// forget what was injected so delta mode sends full sections again
+pub(crate) fn pre_compact(input: &str) -> anyhow::Result<HookOutput> {
+    // Input: {session_id, transcript_path, trigger: "manual" | "auto", ...}
+    let input: serde_json::Value =
+        serde_json::from_str(input).unwrap_or_else(|_| serde_json::json!({}));
+    let session_id = input
+        .get("session_id")
+        .and_then(|v| v.as_str())
+        .unwrap_or("default");
+
+    let paths = Paths::new()?;
+    std::fs::create_dir_all(paths.project_dir()?)?;
+    let state = load_attention_state(&paths)?;
+    let config = load_config(&paths.home_claude);
+    let db_path = paths.observations_db_path();
+    let observations = if db_path.exists() {
+        let mut observations = attentive_compress::ObservationDb::new(&db_path)?
+            .with_redactor(config.redactor())
+            .session(session_id)?;
+        flag_stale(
+            &mut observations,
+            &std::env::current_dir()?,
+            config.stale_observations,
+        );
+        observations
+            .into_iter()
+            .map(|o| o.semantic_summary)
+            .collect()
+    } else {
+        Vec::new()
+    };
+    let router = Router::new(config);
+    let brief = CompactionBrief::new(&router, &state, observations);
+
+    write_session_key(&paths, LAST_INJECTED_KEY, &InjectedSet::default())?;
+    if brief.is_empty() {
+        return Ok(HookOutput::default());
+    }
+    write_session_key(&paths, COMPACTION_BRIEF_KEY, &brief)?;
+    Ok(HookOutput {
+        stdout: String::new(),
+        stderr: vec![format!(
+            "[attentive] Saved {} HOT and {} WARM file(s) to re-brief after compaction",
+            brief.hot.len(),
+            brief.warm.len()
+        )],
+    })
 }
 
 pub fn hook_stop() -> anyhow::Result<()> {
-    use attentive_telemetry::{TurnRecord, append_jsonl};
+    let input = read_stdin()?;
+    if let Some(output) = super::serve::proxy(super::serve::Hook::Stop, &input) {
+        return output.emit();
+    }
+    stop(&Paths::new()?, &input)?.emit()
+}
 
-    // 1. Read Stop hook input: {session_id, transcript_path, cwd, ...}
-    let mut input_str = String::new();
-    io::stdin().read_to_string(&mut input_str)?;
+pub(crate) fn stop(paths: &Paths, input: &str) -> anyhow::Result<HookOutput> {
+    use attentive_telemetry::{TurnRecord, append_jsonl};
 
+    // 1. Parse Stop hook input: {session_id, transcript_path, cwd, ...}
     let input: serde_json::Value =
-        serde_json::from_str(&input_str).unwrap_or_else(|_| serde_json::json!({}));
+        serde_json::from_str(input).unwrap_or_else(|_| serde_json::json!({}));
     let transcript_path = input
         .get("transcript_path")
         .and_then(|v| v.as_str())
@@ -395,81 +2058,201 @@ pub fn hook_stop() -> anyhow::Result<()> {
         .and_then(|v| v.as_str())
         .unwrap_or("default");
 
-    // 2. Extract tool calls from the last assistant turn in transcript
-    let tool_calls = extract_tool_calls_from_transcript(transcript_path);
+    // 2. Tool calls and the files the final turn touched, from the transcript
+    let transcript = parse_stop_transcript(transcript_path, &paths.cwd()?);
+    let planning_turn = transcript.is_planning_only();
+    let StopTranscript {
+        tool_calls,
+        last_turn_start,
+        prompt,
+        files_used,
+        reply,
+    } = transcript;
 
     // 3. Initialize plugins and run on_stop
+    let config = load_project_config(paths);
+    let canonicalizer = paths.canonicalizer()?;
     let mut registry = PluginRegistry::new();
-    registry.register(Box::new(attentive_plugins::BurnRatePlugin::new()));
-    registry.register(Box::new(attentive_plugins::LoopBreakerPlugin::new()));
-    registry.register(Box::new(attentive_plugins::VerifyFirstPlugin::new()));
+    registry.set_toggles(config.plugin_toggles.clone());
+    register_builtin_plugins(&mut registry, paths, &canonicalizer, &config)?;
 
     let session_state = std::collections::HashMap::new();
-    let messages = registry.on_stop(&tool_calls, &session_state);
-
-    for msg in &messages {
-        eprintln!("{}", msg);
-    }
+    let messages = registry.on_stop(&tool_calls[last_turn_start..], &session_state);
 
     // 4. Estimate tokens from attention state
-    let paths = Paths::new()?;
     std::fs::create_dir_all(paths.telemetry_dir())?;
     let project_dir = paths.project_dir()?;
     std::fs::create_dir_all(&project_dir)?;
 
-    let state_path = paths.attn_state_path()?;
-    let state = if state_path.exists() {
-        std::fs::read_to_string(&state_path)
-            .ok()
-            .and_then(|c| serde_json::from_str::<AttentionState>(&c).ok())
-    } else {
-        None
-    };
+    let mut state = load_attention_state(paths).ok();
 
-    let files_used = extract_files_from_tool_calls(&tool_calls);
+    // Compare injected and used files in one canonical repo-relative form,
+    // leaving out files outside the project
+    let scope = path_scope(paths, &config);
+    let files_used = scope.retain(canonicalizer.canonicalize_all(&files_used));
+    let session_files =
+        scope.retain(canonicalizer.canonicalize_all(&extract_files_from_tool_calls(&tool_calls)));
 
     let files_injected = if let Some(ref state) = state {
         let mut injected = state.get_hot_files();
         injected.extend(state.get_warm_files());
-        injected
+        canonicalizer.canonicalize_all(&injected)
     } else {
         Vec::new()
     };
 
-    // Hit rate: fraction of injected files that Claude actually touched
-    let hit_rate = compute_hit_rate(&files_injected, &files_used);
-    let injected_tokens = files_injected.len() * 500;
-    let used_tokens = (hit_rate * injected_tokens as f64) as usize;
+    // Files edited this turn are kept at least WARM while the task continues,
+    // and a turn spent only planning spares the next prompt's decay
+    let files_edited = scope.retain(
+        canonicalizer.canonicalize_all(&extract_edited_files(&tool_calls[last_turn_start..])),
+    );
+    if let Some(state) = state.as_mut()
+        && (!files_edited.is_empty() || state.planning_turn != planning_turn)
+    {
+        state.mark_edited(&files_edited);
+        state.planning_turn = planning_turn;
+        let state_path = paths.attn_state_path()?;
+        attentive_telemetry::write_versioned(&state_path, state)?;
+        super::serve::store_cached(&state_path, state);
+    }
 
-    let record = TurnRecord {
+    // Hit rate: fraction of injected files that Claude actually touched or
+    // named in its reply
+    let files_referenced = find_referenced_files(&reply, &files_injected);
+    let mut files_attributed = files_used.clone();
+    files_attributed.extend(files_referenced.iter().cloned());
+    let hit_rate = compute_hit_rate(&files_injected, &files_attributed, &canonicalizer);
+
+    // Injected size as recorded at prompt time: the transcript's reported
+    // usage when it has it, else the estimate calibrated by earlier turns
+    let injection = state.as_ref().and_then(|s| {
+        attentive_telemetry::find_injection(&paths.injections_path().ok()?, s.turn_count)
+    });
+    let (injected_tokens, injection_chars, budget_overflow, mut context_sources) = match &injection
+    {
+        Some(injection) => {
+            let calibration_path = paths.token_calibration_path()?;
+            let mut calibration = attentive_telemetry::TokenCalibration::load(&calibration_path);
+            let observed = attentive_telemetry::last_prompt_usage(Path::new(transcript_path))
+                .map(|tokens| tokens.saturating_sub(injection.prompt_tokens));
+            if let Some(observed) = observed
+                && calibration.observe(injection.estimated_tokens, observed)
+            {
+                let _ = calibration.save(&calibration_path);
+            }
+            let tokens = |estimate: usize| match observed {
+                Some(observed) if injection.estimated_tokens > 0 => {
+                    estimate * observed / injection.estimated_tokens
+                }
+                _ => calibration.calibrate(estimate),
+            };
+            let sources = injection
+                .sources
+                .iter()
+                .map(|(source, &estimate)| (source.clone(), tokens(estimate)))
+                .collect();
+            (
+                tokens(injection.estimated_tokens),
+                injection.chars,
+                injection.overflow,
+                sources,
+            )
+        }
+        None => (0, 0, 0, ContextSources::new()),
+    };
+    if let Some(tokens) = take_dashboard_tokens(paths) {
+        context_sources.insert(SOURCE_DASHBOARD.to_string(), tokens);
+    }
+    let used_tokens = (hit_rate * injected_tokens as f64) as usize;
+    let task_type = injection.as_ref().and_then(|i| i.task_type.clone());
+    let files_predicted = injection
+        .as_ref()
+        .map(|i| canonicalizer.canonicalize_all(&i.predicted))
+        .unwrap_or_default();
+    let files_from_editor = injection
+        .as_ref()
+        .map(|i| canonicalizer.canonicalize_all(&i.editor_files))
+        .unwrap_or_default();
+    let plugin_budgets = injection
+        .as_ref()
+        .map(|i| i.plugin_budgets.clone())
+        .unwrap_or_default();
+    let config_conflicts = injection
+        .as_ref()
+        .map(|i| canonicalizer.canonicalize_all(&i.conflicts))
+        .unwrap_or_default();
+    let turn_tokens = attentive_telemetry::last_turn_usage(Path::new(transcript_path));
+
+    let mut record = TurnRecord {
         turn_id: uuid_simple(),
         session_id: session_id.to_string(),
-        project: std::env::current_dir()?.to_string_lossy().to_string(),
-        timestamp: chrono::Utc::now(),
+        project: paths.cwd()?.to_string_lossy().to_string(),
+        timestamp: attentive_telemetry::now(),
         injected_tokens,
         used_tokens,
         waste_ratio: 1.0 - hit_rate,
         files_injected,
         files_used: files_used.clone(),
         was_notification: false,
-        injection_chars: injected_tokens * 4,
+        injection_chars,
         context_confidence: Some(hit_rate),
+        extensions: Default::default(),
+        context_sources,
+        budget_overflow,
+        redactions: injection.as_ref().map_or(0, |i| i.redactions),
+        deduped_tokens: injection.as_ref().map_or(0, |i| i.deduped_tokens),
+        task_type,
+        turn_tokens,
+        files_predicted,
+        files_referenced,
+        files_from_editor,
+        plugin_budgets,
+        config_conflicts,
     };
+    registry.on_turn_record(&mut record);
     append_jsonl(&paths.turns_file(), &record)?;
 
-    // Train learner with files_used and update warmup for next session
+    // Average cost per task type, from what turns actually spent, and the
+    // files each task type uses. An unreadable (e.g. newer) oracle file is
+    // left alone.
+    let oracle_path = paths.oracle_state_path()?;
+    let oracle: Option<Oracle> = if oracle_path.exists() {
+        attentive_telemetry::read_versioned(&oracle_path).ok()
+    } else {
+        Some(Oracle::new())
+    };
+    if let (Some(mut oracle), Some(task_type)) = (
+        oracle,
+        record.task_type.as_deref().and_then(TaskType::from_name),
+    ) {
+        if let Some(tokens) = record.turn_tokens {
+            oracle.record_cost(task_type, tokens);
+        }
+        oracle.record_files(task_type, &record.files_used);
+        attentive_telemetry::write_versioned(&oracle_path, &oracle)?;
+    }
+
+    // Train learner with files_used, crediting them by how this turn's edits
+    // went, and update warmup for next session
     let learned_state_path = paths.learned_state_path()?;
     if let Some(mut learner) = load_learner(&learned_state_path) {
-        learner.observe_turn("", &files_used);
-        if !files_used.is_empty() {
-            learner.save_session(&files_used);
+        let outcome = attentive_learn::TurnOutcome {
+            edited: files_edited,
+            tests_run: tests_ran_after_edits(&tool_calls[last_turn_start..]),
+        };
+        learner.observe_turn_with_outcome(&prompt, &files_used, &outcome);
+        if !session_files.is_empty() {
+            learner.save_session(&session_files);
         }
-        if let Ok(json) = serde_json::to_string(&learner) {
-            let _ = attentive_telemetry::atomic_write(&learned_state_path, json.as_bytes());
+        if save_learner(&learned_state_path, &mut learner).is_ok() {
+            super::serve::store_cached(&learned_state_path, &learner);
         }
     }
 
-    Ok(())
+    Ok(HookOutput {
+        stdout: String::new(),
+        stderr: messages,
+    })
 }
 
 fn uuid_simple() -> String {
@@ -481,83 +2264,124 @@ fn uuid_simple() -> String {
     format!("turn_{:x}", nanos)
 }
 
-fn extract_tool_calls_from_transcript(transcript_path: &str) -> Vec<attentive_plugins::ToolCall> {
-    use std::io::{BufRead, BufReader};
+/// What the Stop hook reads from the session transcript
+#[derive(Debug, Default)]
+struct StopTranscript {
+    /// Tool calls from every assistant turn, for plugins that track history
+    tool_calls: Vec<attentive_plugins::ToolCall>,
+    /// Where in `tool_calls` the calls made since the last typed prompt begin
+    last_turn_start: usize,
+    /// The last typed prompt
+    prompt: String,
+    /// Existing files the final turn touched, found the way ingest finds them
+    files_used: Vec<String>,
+    /// Text of the assistant's messages since the last typed prompt
+    reply: String,
+}
 
-    if transcript_path.is_empty() {
-        return Vec::new();
+impl StopTranscript {
+    /// Whether the final turn only answered in text: no tool calls and no
+    /// files touched
+    fn is_planning_only(&self) -> bool {
+        self.tool_calls.len() == self.last_turn_start
+            && self.files_used.is_empty()
+            && !self.reply.trim().is_empty()
     }
+}
 
-    let file = match std::fs::File::open(transcript_path) {
-        Ok(f) => f,
-        Err(_) => return Vec::new(),
-    };
+fn parse_stop_transcript(transcript_path: &str, cwd: &Path) -> StopTranscript {
+    use std::io::{BufRead, BufReader};
 
-    // Collect tool_use entries from ALL assistant turns in the transcript
-    let mut all_tool_calls = Vec::new();
-    let reader = BufReader::new(file);
+    let mut parsed = StopTranscript::default();
+    let Ok(file) = std::fs::File::open(transcript_path) else {
+        return parsed;
+    };
 
-    for line in reader.lines() {
-        let line = match line {
-            Ok(l) => l,
-            Err(_) => continue,
-        };
-        let turn: serde_json::Value = match serde_json::from_str(&line) {
-            Ok(v) => v,
-            Err(_) => continue,
+    let mut last_turn_files = Vec::new();
+    for line in BufReader::new(file).lines().map_while(Result::ok) {
+        let Ok(turn) = serde_json::from_str::<serde_json::Value>(&line) else {
+            continue;
         };
-
+        // Tool results arrive as user entries too; only typed prompts start a turn
+        if attentive_telemetry::is_user_prompt(&turn) {
+            parsed.last_turn_start = parsed.tool_calls.len();
+            parsed.prompt = super::ingest::extract_prompt_from_turn(&turn);
+            parsed.reply.clear();
+            last_turn_files.clear();
+            continue;
+        }
         if turn.get("type").and_then(|t| t.as_str()) != Some("assistant") {
             continue;
         }
 
-        if let Some(content) = turn.pointer("/message/content").and_then(|c| c.as_array()) {
-            for item in content {
-                if item.get("type").and_then(|t| t.as_str()) != Some("tool_use") {
+        last_turn_files.extend(super::ingest::extract_files_from_session_turn(&turn));
+        let Some(content) = turn.pointer("/message/content").and_then(|c| c.as_array()) else {
+            continue;
+        };
+        for item in content {
+            match item.get("type").and_then(|t| t.as_str()) {
+                Some("tool_use") => {}
+                Some("text") => {
+                    let text = item.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                    parsed.reply.push_str(text);
+                    parsed.reply.push('\n');
                     continue;
                 }
-                let input = match item.get("input") {
-                    Some(i) => i,
-                    None => continue,
-                };
-                let tool = item
-                    .get("name")
-                    .and_then(|n| n.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                let target = input
-                    .get("file_path")
-                    .or_else(|| input.get("path"))
-                    .or_else(|| input.get("notebook_path"))
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-
-                let content = input
-                    .get("content")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let old_string = input
-                    .get("old_string")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-                let command = input
-                    .get("command")
-                    .and_then(|v| v.as_str())
-                    .map(|s| s.to_string());
-
-                all_tool_calls.push(attentive_plugins::ToolCall {
-                    tool,
-                    target,
-                    content,
-                    old_string,
-                    command,
-                });
+                _ => continue,
             }
+            let Some(input) = item.get("input") else {
+                continue;
+            };
+            let tool = item.get("name").and_then(|n| n.as_str()).unwrap_or("");
+            parsed
+                .tool_calls
+                .push(attentive_plugins::ToolCall::from_input(tool, input));
         }
     }
 
-    all_tool_calls
+    // Grep and Bash also name directories and files that don't exist
+    last_turn_files.sort();
+    last_turn_files.dedup();
+    parsed.files_used = last_turn_files
+        .into_iter()
+        .filter(|f| cwd.join(f).is_file())
+        .collect();
+    parsed
+}
+
+/// Targets of file-modifying tool calls
+fn extract_edited_files(tool_calls: &[attentive_plugins::ToolCall]) -> Vec<String> {
+    tool_calls
+        .iter()
+        .filter(|tc| tc.kind().is_write())
+        .filter_map(|tc| tc.target.clone())
+        .filter(|target| !target.is_empty())
+        .collect()
+}
+
+/// Commands (or their first word) that run a test suite
+const TEST_COMMANDS: &[&str] = &[
+    "test", "pytest", "jest", "vitest", "rspec", "nextest", "tox",
+];
+
+/// Whether a Bash call after the last edit in `tool_calls` ran tests (e.g.
+/// `cargo test`, `npm test`, `pytest -x`)
+fn tests_ran_after_edits(tool_calls: &[attentive_plugins::ToolCall]) -> bool {
+    let Some(last_edit) = tool_calls
+        .iter()
+        .rposition(|tc| !extract_edited_files(std::slice::from_ref(tc)).is_empty())
+    else {
+        return false;
+    };
+    tool_calls[last_edit + 1..]
+        .iter()
+        .filter(|tc| tc.kind() == attentive_plugins::ToolKind::Bash)
+        .filter_map(|tc| tc.command.as_deref())
+        .any(|command| {
+            command
+                .split_whitespace()
+                .any(|word| TEST_COMMANDS.contains(&word.rsplit('/').next().unwrap_or(word)))
+        })
 }
 
 fn extract_files_from_tool_calls(tool_calls: &[attentive_plugins::ToolCall]) -> Vec<String> {
@@ -574,12 +2398,47 @@ fn extract_files_from_tool_calls(tool_calls: &[attentive_plugins::ToolCall]) ->
     files.into_iter().collect()
 }
 
-fn compute_hit_rate(files_injected: &[String], files_used: &[String]) -> f64 {
+/// Injected files that `reply` names by path, or by a file name no other
+/// injected file shares. A name only counts as a whole word: `lib.rs` is not
+/// a mention of `b.rs`.
+fn find_referenced_files(reply: &str, files_injected: &[String]) -> Vec<String> {
+    let file_name = |path: &str| path.rsplit('/').next().unwrap_or(path).to_string();
+    let mut name_counts: HashMap<String, usize> = HashMap::new();
+    for path in files_injected {
+        *name_counts.entry(file_name(path)).or_default() += 1;
+    }
+    let mentions = |needle: &str| {
+        reply.match_indices(needle).any(|(at, _)| {
+            let before = reply[..at].chars().next_back();
+            let after = reply[at + needle.len()..].chars().next();
+            !before.is_some_and(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.'))
+                && !after.is_some_and(|c| c.is_alphanumeric() || c == '_')
+        })
+    };
+    files_injected
+        .iter()
+        .filter(|path| {
+            let name = file_name(path);
+            mentions(path) || (name.contains('.') && name_counts[&name] == 1 && mentions(&name))
+        })
+        .cloned()
+        .collect()
+}
+
+fn compute_hit_rate(
+    files_injected: &[String],
+    files_used: &[String],
+    canonicalizer: &PathCanonicalizer,
+) -> f64 {
     if files_injected.is_empty() {
         return 0.0;
     }
-    let used_set: std::collections::HashSet<&String> = files_used.iter().collect();
-    let injected_set: std::collections::HashSet<&String> = files_injected.iter().collect();
+    let used_set: std::collections::HashSet<String> =
+        files_used.iter().map(|f| canonicalizer.key(f)).collect();
+    let injected_set: std::collections::HashSet<String> = files_injected
+        .iter()
+        .map(|f| canonicalizer.key(f))
+        .collect();
     injected_set
         .iter()
         .filter(|f| used_set.contains(*f))
@@ -590,14 +2449,18 @@ fn compute_hit_rate(files_injected: &[String], files_used: &[String]) -> f64 {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serial_test::serial;
+
+    impl TieredContext {
+        fn text(&self) -> String {
+            join_parts(&self.parts, "\n\n")
+        }
+    }
+    use attentive_telemetry::TestEnv;
 
     #[test]
-    #[serial]
     fn test_hook_session_start() {
-        // Ensure home .claude directory exists for the test
-        let paths = Paths::new().unwrap();
-        std::fs::create_dir_all(&paths.home_claude).unwrap();
+        let env = TestEnv::new();
+        let paths = env.paths();
 
         let result = hook_session_start();
         if let Err(e) = &result {
@@ -618,14 +2481,79 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_session_start_resolves_the_given_project() {
+        let env = TestEnv::new();
+        let project = tempfile::TempDir::new().unwrap();
+        let paths = env.paths().in_dir(project.path());
+
+        session_start(&paths).unwrap();
+        assert!(
+            paths
+                .project_dir_for(project.path())
+                .join("session_state.json")
+                .exists()
+        );
+        assert!(!env.paths().session_state_path().unwrap().exists());
+    }
+
+    #[test]
+    fn test_pre_tool_use_asks_before_destructive_commands() {
+        let _env = TestEnv::new();
+
+        let input = r#"{"tool_name":"Bash","tool_input":{"command":"git push --force"}}"#;
+        let output: serde_json::Value =
+            serde_json::from_str(&pre_tool_use(input).unwrap().stdout).unwrap();
+        let decision = &output["hookSpecificOutput"];
+        assert_eq!(decision["permissionDecision"], "ask");
+        assert!(
+            decision["permissionDecisionReason"]
+                .as_str()
+                .unwrap()
+                .contains("force push")
+        );
+
+        let input = r#"{"tool_name":"Bash","tool_input":{"command":"cargo test"}}"#;
+        assert!(pre_tool_use(input).unwrap().stdout.is_empty());
+    }
+
+    #[test]
+    fn test_pre_compact_saves_brief_for_next_prompt() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        std::fs::create_dir_all(paths.project_dir().unwrap()).unwrap();
+        assert!(pre_compact("{}").unwrap().stderr.is_empty());
+
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.9);
+        attentive_telemetry::write_versioned(&paths.attn_state_path().unwrap(), &state).unwrap();
+        save_last_injected(&paths, &[("hot".to_string(), 1)].into()).unwrap();
+
+        let output = pre_compact(r#"{"session_id": "s1", "trigger": "auto"}"#).unwrap();
+        assert!(output.stderr[0].contains("Saved 1 HOT and 0 WARM"));
+        assert!(load_last_injected(&paths).is_empty());
+        let brief: CompactionBrief = take_session_key(&paths, COMPACTION_BRIEF_KEY).unwrap();
+        assert_eq!(brief.hot, ["src/router.rs"]);
+        assert!(take_session_key::<CompactionBrief>(&paths, COMPACTION_BRIEF_KEY).is_none());
+    }
+
     #[test]
     fn test_hit_rate() {
         let injected = vec!["a.rs".to_string(), "b.rs".to_string(), "c.rs".to_string()];
         let used = vec!["a.rs".to_string(), "c.rs".to_string()];
-        let rate = compute_hit_rate(&injected, &used);
+        let canon = PathCanonicalizer::new("/repo");
+        let rate = compute_hit_rate(&injected, &used, &canon);
         assert!((rate - 2.0 / 3.0).abs() < 0.01);
 
-        assert!((compute_hit_rate(&[], &used) - 0.0).abs() < f64::EPSILON);
+        assert!((compute_hit_rate(&[], &used, &canon) - 0.0).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_hit_rate_mixed_absolute_and_relative() {
+        let injected = vec!["src/a.rs".to_string(), "./src/b.rs".to_string()];
+        let used = vec!["/repo/src/a.rs".to_string(), "/repo/src/b.rs".to_string()];
+        let canon = PathCanonicalizer::new("/repo");
+        assert!((compute_hit_rate(&injected, &used, &canon) - 1.0).abs() < f64::EPSILON);
     }
 
     #[test]
@@ -653,6 +2581,34 @@ mod tests {
         assert_eq!(config.demoted_files, vec!["old.md"]);
     }
 
+    #[test]
+    fn test_project_config_overrides_global_keys() {
+        let home = tempfile::TempDir::new().unwrap();
+        let repo = tempfile::TempDir::new().unwrap();
+        std::fs::write(
+            home.path().join("attentive.json"),
+            r#"{"pinned_files": ["global.md"], "max_hot_files": 7}"#,
+        )
+        .unwrap();
+
+        let config = load_config_layered(home.path(), Some(repo.path()));
+        assert_eq!(config.pinned_files, ["global.md"]);
+
+        std::fs::write(
+            repo.path().join(PROJECT_CONFIG_FILE),
+            r#"{"pinned_files": ["src/lib.rs"]}"#,
+        )
+        .unwrap();
+        let config = load_config_layered(home.path(), Some(repo.path()));
+        assert_eq!(config.pinned_files, ["src/lib.rs"]);
+        assert_eq!(config.max_hot_files, 7);
+
+        std::fs::remove_file(home.path().join("attentive.json")).unwrap();
+        let config = load_config_layered(home.path(), Some(repo.path()));
+        assert_eq!(config.pinned_files, ["src/lib.rs"]);
+        assert_eq!(config.max_hot_files, Config::new().max_hot_files);
+    }
+
     #[test]
     fn test_load_config_missing_file_returns_default() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -665,7 +2621,7 @@ mod tests {
     #[test]
     fn test_load_learner_from_state() {
         let temp = tempfile::TempDir::new().unwrap();
-        let mut learner = attentive_learn::Learner::new();
+        let mut learner = Learner::new();
         for _ in 0..30 {
             learner.observe_turn("router config", &["router.rs".to_string()]);
         }
@@ -680,6 +2636,32 @@ mod tests {
         assert_eq!(format!("{:?}", loaded_learner.maturity()), "Active");
     }
 
+    #[test]
+    fn test_save_learner_moves_word_tables_to_index() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let state_path = temp.path().join("learned_state.json");
+        let mut learner = Learner::new();
+        for _ in 0..30 {
+            learner.observe_turn("router config", &["router.rs".to_string()]);
+            learner.observe_turn("schema docs", &["README.md".to_string()]);
+        }
+        save_learner(&state_path, &mut learner).unwrap();
+        assert!(learner.has_word_tables());
+        assert!(temp.path().join(WORD_INDEX_FILE).exists());
+        let json = std::fs::read_to_string(&state_path).unwrap();
+        assert!(!json.contains("word_file_counts"));
+
+        let full = load_learner(&state_path).unwrap();
+        assert_eq!(full.total_associations(), learner.total_associations());
+        let partial = load_learner_for_prompt(&state_path, "the router", Synonyms::new()).unwrap();
+        assert_eq!(partial.total_associations(), 1);
+        // Synonyms of the prompt's words are loaded with them
+        let synonyms: Synonyms = serde_json::from_str(r#"{"router": {"schema": 0.8}}"#).unwrap();
+        let partial = load_learner_for_prompt(&state_path, "the router", synonyms).unwrap();
+        assert_eq!(partial.total_associations(), 2);
+        assert!(!partial.synonyms().is_empty());
+    }
+
     #[test]
     fn test_detect_project_switch() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -700,7 +2682,7 @@ mod tests {
 
     #[test]
     fn test_build_dashboard_empty() {
-        let dashboard = build_dashboard(&[], None);
+        let dashboard = build_dashboard(&[], None, None);
         assert!(dashboard.is_empty()); // No data = no dashboard
     }
 
@@ -716,13 +2698,22 @@ mod tests {
             waste_ratio: 0.4,
             files_injected: vec!["a.rs".to_string()],
             files_used: vec!["a.rs".to_string()],
-            was_notification: false,
             injection_chars: 4000,
             context_confidence: Some(0.8),
+            ..Default::default()
         }];
-        let dashboard = build_dashboard(&turns, None);
+        let dashboard = build_dashboard(&turns, None, None);
         assert!(dashboard.contains("attentive"));
         assert!(dashboard.contains("Waste"));
+
+        let templated = build_dashboard(
+            &turns,
+            None,
+            Some(
+                "{{ turns }} turns{% for f in top_waste %}, {{ f.path }} {{ f.used }}/{{ f.injected }}{% endfor %}",
+            ),
+        );
+        assert_eq!(templated, "1 turns, a.rs 1/1");
     }
 
     #[test]
@@ -740,6 +2731,7 @@ mod tests {
                 content: None,
                 old_string: None,
                 command: None,
+                ..Default::default()
             },
             attentive_plugins::ToolCall {
                 tool: "Edit".to_string(),
@@ -747,6 +2739,7 @@ mod tests {
                 content: Some("new content".to_string()),
                 old_string: Some("old content".to_string()),
                 command: None,
+                ..Default::default()
             },
             attentive_plugins::ToolCall {
                 tool: "Bash".to_string(),
@@ -754,6 +2747,7 @@ mod tests {
                 content: None,
                 old_string: None,
                 command: Some("cargo test".to_string()),
+                ..Default::default()
             },
             attentive_plugins::ToolCall {
                 tool: "Read".to_string(),
@@ -761,6 +2755,7 @@ mod tests {
                 content: None,
                 old_string: None,
                 command: None,
+                ..Default::default()
             },
         ];
 
@@ -770,18 +2765,145 @@ mod tests {
         assert_eq!(files_used.len(), 2); // fake path and Bash (no target) excluded
     }
 
+    #[test]
+    fn test_parse_stop_transcript_final_turn() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = |name: &str| {
+            let path = temp.path().join(name);
+            std::fs::write(&path, "").unwrap();
+            path.to_string_lossy().to_string()
+        };
+        let (old, read, listed) = (file("old.rs"), file("read.rs"), file("listed.rs"));
+        let tool_use = |name: &str, input: serde_json::Value| {
+            serde_json::json!({"type": "assistant", "message": {"content": [
+                {"type": "tool_use", "name": name, "input": input}
+            ]}})
+        };
+        let entries = [
+            serde_json::json!({"type": "user", "message": {"content": "first"}}),
+            tool_use("Read", serde_json::json!({"file_path": old})),
+            serde_json::json!({"type": "assistant", "message": {"content": [
+                {"type": "text", "text": "Read old.rs"}
+            ]}}),
+            // Typed prompts may also arrive as text blocks
+            serde_json::json!({"type": "user", "message": {"content": [{"type": "text", "text": "second"}]}}),
+            tool_use("Read", serde_json::json!({"file_path": read})),
+            serde_json::json!({"type": "user", "message": {"content": [{"type": "tool_result", "content": "ok"}]}}),
+            tool_use(
+                "Bash",
+                serde_json::json!({"command": format!("wc -l {} /no/such.rs", listed)}),
+            ),
+            tool_use(
+                "Grep",
+                serde_json::json!({"pattern": "fn", "path": temp.path()}),
+            ),
+            serde_json::json!({"type": "assistant", "message": {"content": [
+                {"type": "text", "text": "The bug is in read.rs"}
+            ]}}),
+        ];
+        let transcript = temp.path().join("session.jsonl");
+        let lines: Vec<String> = entries.iter().map(|e| e.to_string()).collect();
+        std::fs::write(&transcript, lines.join("\n")).unwrap();
+
+        let parsed = parse_stop_transcript(transcript.to_str().unwrap(), temp.path());
+        assert_eq!(parsed.tool_calls.len(), 4);
+        assert_eq!(parsed.last_turn_start, 1);
+        assert_eq!(parsed.prompt, "second");
+        let mut expected = vec![listed, read];
+        expected.sort();
+        assert_eq!(parsed.files_used, expected);
+        assert_eq!(parsed.reply, "The bug is in read.rs\n");
+
+        assert!(parse_stop_transcript("", temp.path()).tool_calls.is_empty());
+    }
+
+    #[test]
+    fn test_planning_only_turn() {
+        let planned = StopTranscript {
+            tool_calls: vec![attentive_plugins::ToolCall::from_input(
+                "Read",
+                &serde_json::json!({"file_path": "src/lib.rs"}),
+            )],
+            last_turn_start: 1,
+            reply: "First I'd split the parser from the lexer".to_string(),
+            ..Default::default()
+        };
+        assert!(planned.is_planning_only());
+
+        let worked = StopTranscript {
+            last_turn_start: 0,
+            ..planned
+        };
+        assert!(!worked.is_planning_only());
+        // Nothing to go on, e.g. no transcript
+        assert!(!StopTranscript::default().is_planning_only());
+    }
+
+    #[test]
+    fn test_find_referenced_files() {
+        let injected: Vec<String> = [
+            "src/router.rs",
+            "src/lib.rs",
+            "tests/lib.rs",
+            "docs/config.md",
+            "Makefile",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect();
+        let reply = "The scoring lives in `router.rs:42`, called from src/lib.rs. \
+                     See also myconfig.md and the Makefile targets.";
+        assert_eq!(
+            find_referenced_files(reply, &injected),
+            vec!["src/router.rs", "src/lib.rs", "Makefile"]
+        );
+        // A name shared by two injected files needs its path
+        assert!(find_referenced_files("lib.rs is fine", &injected).is_empty());
+        assert_eq!(
+            find_referenced_files("Edited /repo/docs/config.md.", &injected),
+            vec!["docs/config.md"]
+        );
+    }
+
+    #[test]
+    fn test_tests_ran_after_edits() {
+        let call =
+            |tool: &str, target: Option<&str>, command: Option<&str>| attentive_plugins::ToolCall {
+                tool: tool.to_string(),
+                target: target.map(String::from),
+                content: None,
+                old_string: None,
+                command: command.map(String::from),
+                ..Default::default()
+            };
+        let edit = call("Edit", Some("/repo/src/lib.rs"), None);
+        let cargo_test = call("Bash", None, Some("cargo test -p core"));
+        let pytest = call("Bash", None, Some("./venv/bin/pytest -x"));
+        let ls = call("Bash", None, Some("ls tests/"));
+
+        assert!(tests_ran_after_edits(&[edit.clone(), cargo_test.clone()]));
+        assert!(tests_ran_after_edits(&[edit.clone(), ls.clone(), pytest]));
+        // Tests before the edit don't vouch for it; no edit, nothing to vouch for
+        assert!(!tests_ran_after_edits(&[
+            cargo_test.clone(),
+            edit.clone(),
+            ls
+        ]));
+        assert!(!tests_ran_after_edits(&[cargo_test]));
+    }
+
     #[test]
     fn test_hit_rate_partial() {
         let injected = vec!["a.rs".to_string(), "b.rs".to_string(), "c.rs".to_string()];
         let used = vec!["a.rs".to_string(), "b.rs".to_string()];
-        let rate = compute_hit_rate(&injected, &used);
+        let rate = compute_hit_rate(&injected, &used, &PathCanonicalizer::new("/repo"));
         assert!(rate > 0.5);
         assert!(rate < 1.0);
     }
 
     #[test]
     fn test_hit_rate_empty() {
-        let rate = compute_hit_rate(&[], &[]);
+        let rate = compute_hit_rate(&[], &[], &PathCanonicalizer::new("/repo"));
         assert!((rate - 0.0).abs() < f64::EPSILON);
     }
 
@@ -795,7 +2917,12 @@ mod tests {
         )
         .unwrap();
 
-        let content = read_file_content(file_path.to_str().unwrap(), 10000);
+        let (content, view) = read_file_content(
+            &mut ContentCache::default(),
+            file_path.to_str().unwrap(),
+            10000,
+        );
+        assert_eq!(view, "full");
         assert!(content.contains("# Title"));
         assert!(content.contains("Some content"));
     }
@@ -803,12 +2930,35 @@ mod tests {
     #[test]
     fn test_build_warm_toc() {
         let content = "# Main Title\nParagraph text here.\n## Section One\nDetails.\n### Subsection\nMore details.\nfn foo() {\n}\ndef bar():\n    pass";
-        let toc = extract_toc(content);
+        let toc = extract_toc_entries(content).join("\n");
         assert!(toc.contains("Main Title"));
         assert!(toc.contains("Section One"));
         assert!(toc.contains("Subsection"));
     }
 
+    #[test]
+    fn test_pack_toc_keeps_entries_relevant_to_prompt() {
+        let content: String = (0..30)
+            .map(|i| format!("pub fn helper_{}() {{}}\n", i))
+            .chain(["pub fn parseConfigFile() {}\n".to_string()])
+            .collect();
+        let toc = pack_toc(
+            &extract_toc_entries(&content),
+            "why does config parsing fail?",
+            12,
+        );
+        let lines: Vec<&str> = toc.lines().collect();
+
+        assert_eq!(lines.len(), 12 + 1);
+        assert!(toc.contains("pub fn parseConfigFile()"));
+        assert!(toc.contains("pub fn helper_0()"));
+        assert!(!toc.contains("pub fn helper_29()"));
+        assert_eq!(lines.last(), Some(&"(+19 more symbols)"));
+
+        let small = pack_toc(&["# Title", "## Usage"], "anything", 12);
+        assert_eq!(small, "# Title\n## Usage");
+    }
+
     #[test]
     fn test_build_context_with_content() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -824,13 +2974,418 @@ mod tests {
         let hot_files = vec![hot_file.to_str().unwrap().to_string()];
         let warm_files = vec![warm_file.to_str().unwrap().to_string()];
 
-        let context = build_tiered_context(&hot_files, &warm_files, 20000);
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot_files,
+            &warm_files,
+            "",
+            20000,
+            12,
+            &ContextExtras::default(),
+        )
+        .text();
         assert!(context.contains("[HOT]"));
         assert!(context.contains("Important content here"));
         assert!(context.contains("[WARM]"));
         assert!(context.contains("Section A"));
     }
 
+    #[test]
+    fn test_select_memories_by_prompt_and_hot_files() {
+        use attentive_compress::{CompressedObservation, ObservationDb};
+
+        let temp = tempfile::TempDir::new().unwrap();
+        let db = ObservationDb::new(&temp.path().join("obs.db")).unwrap();
+        let by_text =
+            CompressedObservation::memory("/repo", "Decay is tuned per file type", vec![]);
+        let by_file = CompressedObservation::memory(
+            "/repo",
+            "Never block on disk in hooks",
+            vec!["src/hooks.rs".to_string()],
+        );
+        db.insert(&by_text).unwrap();
+        db.insert(&by_file).unwrap();
+
+        let canon = PathCanonicalizer::new("/repo");
+        let hot = vec!["/repo/src/hooks.rs".to_string()];
+        let selected = select_memories(&db, "/repo", "adjust decay", &hot, &canon).unwrap();
+        let ids: Vec<&str> = selected.iter().map(|m| m.id.as_str()).collect();
+        assert_eq!(ids, vec![by_text.id.as_str(), by_file.id.as_str()]);
+
+        let section = format_memory_section(&selected);
+        assert!(section.starts_with("## Project memory\n- Decay is tuned per file type"));
+        assert!(section.contains("- Never block on disk in hooks (src/hooks.rs)"));
+
+        assert!(
+            select_memories(&db, "/repo", "unrelated", &[], &canon)
+                .unwrap()
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_memory_section_needs_marker_and_fits_budget() {
+        use attentive_compress::{CompressedObservation, ObservationDb};
+
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let repo = tempfile::TempDir::new().unwrap();
+        let root = repo.path().to_string_lossy().to_string();
+        let canon = PathCanonicalizer::new(repo.path());
+        ObservationDb::new(&paths.observations_db_path())
+            .unwrap()
+            .insert(&CompressedObservation::memory(
+                &root,
+                "Decay is tuned per file type",
+                vec![],
+            ))
+            .unwrap();
+        let section = || {
+            build_memory_section(
+                &paths,
+                &canon,
+                "adjust decay",
+                &[],
+                StaleObservations::Annotate,
+                &Redactor::default(),
+            )
+        };
+        assert_eq!(section(), None, "unmarked projects skip the database");
+        super::super::remember::mark_memories(&paths, repo.path()).unwrap();
+        let memory = section().unwrap();
+        assert!(memory.contains("Decay is tuned per file type"));
+
+        let hot = repo.path().join("hot.md");
+        std::fs::write(&hot, "x".repeat(200)).unwrap();
+        let build = |max_total_chars| {
+            build_tiered_context(
+                &mut ContentCache::default(),
+                &[hot.to_string_lossy().to_string()],
+                &[],
+                "",
+                max_total_chars,
+                12,
+                &ContextExtras {
+                    memory: Some(memory.clone()),
+                    ..Default::default()
+                },
+            )
+        };
+        let roomy = build(20000);
+        assert!(roomy.text().ends_with(&memory));
+        assert!(roomy.sources.contains_key(SOURCE_MEMORY));
+        let limit = roomy.text().len() - 10;
+        let tight = build(limit);
+        assert!(tight.text().len() <= limit);
+        assert_eq!(tight.overflow, 1);
+    }
+
+    #[test]
+    fn test_flag_stale_annotates_or_excludes_changed_memories() {
+        use attentive_compress::CompressedObservation;
+
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::write(temp.path().join("hooks.rs"), "fn hook() {}").unwrap();
+        let memory = |note: &str| {
+            CompressedObservation::memory("/repo", note, vec!["hooks.rs".to_string()])
+                .with_file_hashes(temp.path())
+        };
+        let mut memories = vec![memory("Hooks never block"), memory("Hooks log to stderr")];
+        flag_stale(&mut memories, temp.path(), StaleObservations::Annotate);
+        assert_eq!(memories[0].semantic_summary, "Hooks never block");
+
+        std::fs::write(temp.path().join("hooks.rs"), "fn hook() { block() }").unwrap();
+        let mut annotated = memories.clone();
+        flag_stale(&mut annotated, temp.path(), StaleObservations::Annotate);
+        assert_eq!(
+            annotated[0].semantic_summary,
+            "Hooks never block (may be outdated: hooks.rs changed since)"
+        );
+        flag_stale(&mut memories, temp.path(), StaleObservations::Exclude);
+        assert!(memories.is_empty());
+    }
+
+    #[test]
+    fn test_provenance_tags_in_headers() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let hot = vec![temp.path().join("a.md").to_string_lossy().to_string()];
+        let warm = vec![temp.path().join("b.md").to_string_lossy().to_string()];
+        std::fs::write(&hot[0], "# A").unwrap();
+        std::fs::write(&warm[0], "# B").unwrap();
+
+        let mut metrics = RouterMetrics::new();
+        metrics.note(&hot[0], Provenance::Pinned);
+        metrics.note(&hot[0], Provenance::Mentioned);
+        metrics.note(&warm[0], Provenance::LearnedAffinity(0.7));
+
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                provenance: Some(&metrics),
+                ..Default::default()
+            },
+        )
+        .text();
+        assert!(context.contains(&format!("[HOT] {} [pinned, mentioned in prompt]\n", hot[0])));
+        assert!(context.contains(&format!(
+            "[WARM] {} (TOC) [learned affinity 0.7]\n",
+            warm[0]
+        )));
+
+        let untagged = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras::default(),
+        )
+        .text();
+        assert!(untagged.contains(&format!("[HOT] {}\n", hot[0])));
+    }
+
+    #[test]
+    fn test_section_templates_replace_builtin_layout() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let hot = vec![temp.path().join("a.rs").to_string_lossy().to_string()];
+        let warm = vec![temp.path().join("b.md").to_string_lossy().to_string()];
+        std::fs::write(&hot[0], "fn a() {}").unwrap();
+        std::fs::write(&warm[0], "# B\n## Beta section").unwrap();
+        let scores: HashMap<String, f64> = [(hot[0].clone(), 0.9)].into();
+        let templates = ContextTemplates {
+            hot: Some(
+                "<file path=\"{{ path }}\" score=\"{{ score }}\">\n{{ content }}\n</file>"
+                    .to_string(),
+            ),
+            warm: Some(
+                "<outline path=\"{{ path }}\" view=\"{{ view }}\">\n{{ content }}".to_string(),
+            ),
+            dashboard: None,
+        };
+
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                scores: Some(&scores),
+                templates: Some(&templates),
+                ..Default::default()
+            },
+        )
+        .text();
+        assert!(context.contains(&format!(
+            "<file path=\"{}\" score=\"0.9\">\nfn a() {{}}\n</file>",
+            hot[0]
+        )));
+        assert!(context.contains(&format!("<outline path=\"{}\" view=\"toc\">\n", warm[0])));
+        assert!(context.contains("Beta section"));
+        assert!(!context.contains("[HOT]"));
+
+        // A template that fails to render falls back to the built-in layout
+        let broken = ContextTemplates {
+            hot: Some("{{ path | no_such_filter }}".to_string()),
+            ..Default::default()
+        };
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &[],
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                templates: Some(&broken),
+                ..Default::default()
+            },
+        )
+        .text();
+        assert!(context.starts_with(&format!("[HOT] {}\n", hot[0])));
+    }
+
+    #[test]
+    fn test_warm_observation_summary_replaces_toc() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let warm = vec![
+            temp.path().join("a.md").to_string_lossy().to_string(),
+            temp.path().join("b.md").to_string_lossy().to_string(),
+        ];
+        std::fs::write(&warm[0], "# A\n## Alpha section").unwrap();
+        std::fs::write(&warm[1], "# B\n## Beta section").unwrap();
+        let observations: HashMap<String, String> =
+            [(warm[0].clone(), "Parses alpha records".to_string())].into();
+
+        let tiered = build_tiered_context(
+            &mut ContentCache::default(),
+            &[],
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                observations,
+                ..Default::default()
+            },
+        );
+        assert!(tiered.text().contains(&format!(
+            "[WARM] {} (summary)\nParses alpha records",
+            warm[0]
+        )));
+        assert!(!tiered.text().contains("Alpha section"));
+        assert!(tiered.text().contains(&format!("[WARM] {} (TOC)", warm[1])));
+        assert!(tiered.sources.contains_key(SOURCE_OBSERVATION));
+
+        // Falls back to the outline when the summary doesn't fit
+        let long: HashMap<String, String> = [(warm[0].clone(), "x".repeat(500))].into();
+        let tight = build_tiered_context(
+            &mut ContentCache::default(),
+            &[],
+            &warm[..1],
+            "",
+            200,
+            12,
+            &ContextExtras {
+                observations: long,
+                ..Default::default()
+            },
+        );
+        assert!(tight.text().contains("(TOC)"));
+
+        // Search snippets go above the outline
+        let snippets: HashMap<String, String> =
+            [(warm[1].clone(), "2  ## Beta section".to_string())].into();
+        let searched = build_tiered_context(
+            &mut ContentCache::default(),
+            &[],
+            &warm[1..],
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                snippets,
+                ..Default::default()
+            },
+        );
+        assert!(searched.text().contains(&format!(
+            "[WARM] {} (TOC)\nmatched:\n2  ## Beta section\n",
+            warm[1]
+        )));
+    }
+
+    #[test]
+    fn test_warm_dependency_hint() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let dir = temp.path().to_str().unwrap();
+        let hot = vec![format!("{}/app.py", dir)];
+        let warm = vec![format!("{}/store.py", dir)];
+        std::fs::write(&hot[0], "from store import save\ndef run(): pass").unwrap();
+        std::fs::write(&warm[0], "import json\ndef save(): pass").unwrap();
+
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras::default(),
+        )
+        .text();
+        assert!(context.contains(&format!(
+            "[WARM] {} (TOC)\n↳ imported by HOT: {}",
+            warm[0], hot[0]
+        )));
+    }
+
+    #[test]
+    fn test_delta_context_skips_unchanged_sections() {
+        let env = TestEnv::new();
+        let dir = env.home().to_str().unwrap().to_string();
+        let hot = vec![format!("{}/a.md", dir), format!("{}/b.md", dir)];
+        let warm = vec![format!("{}/c.md", dir)];
+        std::fs::write(&hot[0], "# A\nalpha").unwrap();
+        std::fs::write(&hot[1], "# B\nbeta").unwrap();
+        std::fs::write(&warm[0], "# C").unwrap();
+
+        let first = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras::default(),
+        );
+        let injected = first.injected.clone();
+        assert_eq!(injected.len(), 3);
+        assert!(!first.text().contains("unchanged since last turn"));
+
+        save_last_injected(&env.paths(), &injected).unwrap();
+        let previous = load_last_injected(&env.paths());
+        assert_eq!(previous, injected);
+
+        std::fs::write(&hot[1], "# B\nbeta, edited").unwrap();
+        let second = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras {
+                previous: Some(&previous),
+                ..Default::default()
+            },
+        )
+        .text();
+        assert!(!second.contains("alpha"));
+        assert!(second.contains("beta, edited"));
+        assert!(second.ends_with(&format!(
+            "unchanged since last turn: {}, {}",
+            hot[0], warm[0]
+        )));
+    }
+
+    #[test]
+    fn test_predict_likely_next_skips_warm_and_missing() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = |name: &str| dir.path().join(name).to_string_lossy().to_string();
+        for name in ["lib.rs", "next.rs", "warm.rs"] {
+            std::fs::write(path(name), "").unwrap();
+        }
+        let mut learner = Learner::new();
+        for _ in 0..10 {
+            learner.observe_turn("start work", &[path("lib.rs")]);
+            learner.observe_turn("follow up", &[path("next.rs"), path("warm.rs")]);
+            learner.observe_turn("cleanup step", &[path("deleted.rs")]);
+        }
+
+        let predicted =
+            predict_likely_next(&learner, dir.path(), &[path("lib.rs")], &[path("warm.rs")]);
+        assert_eq!(predicted, [path("next.rs")]);
+        assert_eq!(
+            format_likely_next(&predicted),
+            format!("Likely needed next: {}", path("next.rs"))
+        );
+    }
+
+    #[test]
+    fn test_scale_limit() {
+        let max_context_chars = Config::new().max_context_chars;
+        assert_eq!(scale_limit(max_context_chars, 1.0), max_context_chars);
+        assert_eq!(scale_limit(10, 0.25), 3);
+        assert_eq!(scale_limit(2, 0.25), 1);
+    }
+
     #[test]
     fn test_max_chars_respected() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -838,7 +3393,371 @@ mod tests {
         let big_content = "x".repeat(50000);
         std::fs::write(&big_file, &big_content).unwrap();
 
-        let content = read_file_content(big_file.to_str().unwrap(), 1000);
+        let (content, _) = read_file_content(
+            &mut ContentCache::default(),
+            big_file.to_str().unwrap(),
+            1000,
+        );
         assert!(content.len() <= 1100); // Allow small overhead for truncation marker
     }
+
+    #[test]
+    fn test_far_oversized_file_is_digested() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let big_file = temp.path().join("big.rs");
+        let source: String = (0..300)
+            .map(|i| {
+                format!(
+                    "pub fn item_{i}() {{\n{}}}\n",
+                    format!("    body({i});\n").repeat(5)
+                )
+            })
+            .collect();
+        std::fs::write(&big_file, &source).unwrap();
+        let path = big_file.to_str().unwrap();
+
+        let mut cache = ContentCache::default();
+        let (content, view) = read_file_content(&mut cache, path, 10_000);
+        assert_eq!(view, "digest");
+        assert!(content.contains("pub fn item_299()"));
+        assert!(!content.contains("body(150)"));
+
+        // Within twice the budget it is truncated as before
+        let (content, view) = read_file_content(&mut cache, path, source.len() / 2 + 1);
+        assert_eq!(view, "full");
+        assert!(content.contains("body(0)"));
+    }
+
+    #[test]
+    fn test_tiered_context_hard_cap() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = |name: &str, content: String| {
+            let path = temp.path().join(name);
+            std::fs::write(&path, content).unwrap();
+            path.to_str().unwrap().to_string()
+        };
+        let hot = vec![file("a.md", "alpha line\n".repeat(200))];
+        let warm: Vec<String> = (0..3)
+            .map(|i| file(&format!("w{}.md", i), "# Heading\n".repeat(100)))
+            .collect();
+
+        let context = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            1000,
+            12,
+            &ContextExtras::default(),
+        );
+        assert!(context.text().len() <= 1000, "{}", context.text().len());
+        assert!(context.text().contains(&format!("[WARM] {} ", warm[0])));
+        // Too little room is left for even the headers of the other two
+        assert!(!context.text().contains(&warm[1]));
+        assert_eq!(context.overflow, 2);
+
+        let roomy = build_tiered_context(
+            &mut ContentCache::default(),
+            &hot,
+            &warm,
+            "",
+            20000,
+            12,
+            &ContextExtras::default(),
+        );
+        assert_eq!(roomy.overflow, 0);
+    }
+
+    #[test]
+    fn test_fit_section() {
+        assert_eq!(
+            fit_section("[HOT] a\nbody".to_string(), 100).unwrap(),
+            "[HOT] a\nbody"
+        );
+        let section = format!("[HOT] a\n{}", "line\n".repeat(50));
+        let fitted = fit_section(section, 80).unwrap();
+        assert!(fitted.len() <= 80);
+        assert!(fitted.starts_with("[HOT] a\nline\n"));
+        assert!(fitted.ends_with(&format!("line{}", TRUNCATED_MARKER)));
+        assert_eq!(
+            fit_section("[HOT] a long header\nbody".to_string(), 10),
+            None
+        );
+        // Never cuts inside a multi-byte character
+        let fitted = fit_section(format!("h\n{}", "é".repeat(100)), 60).unwrap();
+        assert!(fitted.len() <= 60);
+    }
+
+    #[test]
+    fn test_search_fallback() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let index_path = temp.path().join("search_index.db");
+        let none = Synonyms::new();
+        let canon = PathCanonicalizer::new("/repo");
+        assert!(search_fallback(&index_path, "grammar parser", &none, &canon).is_empty());
+
+        let document = |path: &str, content: &str| attentive_index::Document {
+            path: path.to_string(),
+            content: content.to_string(),
+            mtime: 1.0,
+            doc_type: "code".to_string(),
+        };
+        attentive_index::SearchIndex::new(&index_path)
+            .unwrap()
+            .build(vec![
+                document(
+                    "src/parser.rs",
+                    "fn parse_grammar() { /* grammar rules */ }",
+                ),
+                document("src/net.rs", "fn open_socket() {}"),
+            ])
+            .unwrap();
+        let found = search_fallback(
+            &index_path,
+            "why does the grammar parser hang?",
+            &none,
+            &canon,
+        );
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, "src/parser.rs");
+        assert_eq!(
+            found[0].1.as_deref(),
+            Some("1  fn parse_grammar() { /* grammar rules */ }")
+        );
+        assert!(search_fallback(&index_path, "unrelated words", &none, &canon).is_empty());
+        let synonyms: Synonyms = serde_json::from_str(r#"{"network": {"socket": 0.7}}"#).unwrap();
+        let found = search_fallback(&index_path, "network hangs", &synonyms, &canon);
+        assert_eq!(found[0].0, "src/net.rs");
+    }
+
+    #[test]
+    fn test_seed_from_git() {
+        let ranked: Vec<(String, f64)> = [
+            ("Cargo.lock", 1.0),
+            ("src/router.rs", 1.0),
+            ("src/lib.rs", 0.5),
+            ("src/old.rs", 0.4),
+        ]
+        .iter()
+        .map(|(f, s)| (f.to_string(), *s))
+        .collect();
+        let scannable = ["src/router.rs", "src/lib.rs"]
+            .iter()
+            .map(|f| f.to_string())
+            .collect();
+        let scope = PathScope::new(&[], Path::new("/home/user"));
+
+        let mut state = AttentionState::new();
+        assert_eq!(seed_from_git(&mut state, &ranked, &scannable, &scope), 2);
+        assert_eq!(state.scores.len(), 2);
+        assert_eq!(state.scores["src/router.rs"], GIT_SEED_SCORES.1);
+        let lib = state.scores["src/lib.rs"];
+        assert!(lib > GIT_SEED_SCORES.0 && lib < GIT_SEED_SCORES.1);
+        // Seeds start WARM, not HOT
+        assert_eq!(state.get_hot_files(), Vec::<String>::new());
+        assert_eq!(state.get_warm_files().len(), 2);
+    }
+
+    #[test]
+    fn test_build_cold_entries_sorted_with_summary() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let file = temp.path().join("old.rs");
+        std::fs::write(&file, "pub fn alpha() {}\npub struct Beta;\n").unwrap();
+        let file = file.to_str().unwrap().to_string();
+
+        let mut state = AttentionState::new();
+        state.scores.insert(file.clone(), 0.2);
+        state.scores.insert("missing.rs".to_string(), 0.05);
+
+        let mut cache = ContentCache::default().in_root(temp.path());
+        let cold = ["missing.rs".to_string(), file.clone()];
+        let entries = build_cold_entries(&mut cache, &cold, &state);
+        assert_eq!(entries.len(), 2);
+        assert_eq!(entries[0].path, file);
+        assert_eq!(entries[0].summary, "pub fn alpha() {}; pub struct Beta;");
+        assert!(entries[1].summary.is_empty());
+    }
+
+    #[test]
+    fn test_replayed_pipeline_carries_plugin_policy() {
+        let env = TestEnv::new();
+        let repo = tempfile::TempDir::new().unwrap();
+        let paths = env.paths().in_dir(repo.path());
+        let pipeline = run_prompt_pipeline(
+            &paths,
+            "fix the router".to_string(),
+            &mut AttentionState::new(),
+            Config::new(),
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert!(pipeline.prefix.contains("## VerifyFirst Policy"));
+        assert!(!pipeline.context.contains("## VerifyFirst Policy"));
+        let replayed = pipeline.full_context();
+        assert!(replayed.starts_with(&pipeline.prefix));
+        assert!(replayed.ends_with(&pipeline.context));
+        assert_eq!(plugin_prefix(&paths, &Config::new()), pipeline.prefix);
+    }
+
+    #[test]
+    fn test_keyword_rules_route_the_prompt() {
+        let env = TestEnv::new();
+        let repo = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/decay.rs"), "pub fn decay() {}\n").unwrap();
+        let paths = env.paths().in_dir(repo.path());
+        let mut rules = attentive_learn::Predictor::new();
+        rules.set_strong_keyword("falloff", "src/decay.rs");
+        super::super::learn::save_keyword_rules(&paths.keyword_rules_path().unwrap(), &rules)
+            .unwrap();
+
+        let mut state = AttentionState::new();
+        run_prompt_pipeline(
+            &paths,
+            "tweak the falloff".to_string(),
+            &mut state,
+            Config::new(),
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(state.get_hot_files(), ["src/decay.rs"]);
+    }
+
+    #[test]
+    fn test_recalled_paths_are_canonical_and_scoped() {
+        let env = TestEnv::new();
+        let repo = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(repo.path().join("src")).unwrap();
+        std::fs::write(repo.path().join("src/old.rs"), "pub fn old() {}\n").unwrap();
+        let paths = env.paths().in_dir(repo.path());
+
+        let mut state = AttentionState::new();
+        run_prompt_pipeline(
+            &paths,
+            "@recall ./src/old.rs and @recall /etc/hosts".to_string(),
+            &mut state,
+            Config::new(),
+            None,
+            None,
+        )
+        .unwrap()
+        .unwrap();
+        assert_eq!(state.get_hot_files(), ["src/old.rs"]);
+        assert!(!state.scores.contains_key("/etc/hosts"));
+    }
+
+    /// Append a typed prompt and an assistant reply making `tools` calls to
+    /// the transcript at `path`, then run the Stop hook on it
+    fn stop_after_turn(paths: &Paths, path: &Path, tools: &[serde_json::Value]) -> HookOutput {
+        use std::io::Write;
+        let mut content: Vec<serde_json::Value> =
+            vec![serde_json::json!({"type": "text", "text": "Done."})];
+        content.extend(tools.iter().cloned());
+        let lines = [
+            serde_json::json!({"type": "user", "message": {"content": "keep going"}}),
+            serde_json::json!({"type": "assistant", "message": {"content": content}}),
+        ];
+        let mut file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .unwrap();
+        for line in lines {
+            writeln!(file, "{}", line).unwrap();
+        }
+        let input = serde_json::json!({"transcript_path": path, "session_id": "s1"});
+        stop(paths, &input.to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_stop_counts_commands_once_across_cumulative_transcripts() {
+        let env = TestEnv::new();
+        let repo = tempfile::TempDir::new().unwrap();
+        let paths = env.paths().in_dir(repo.path());
+        let transcript = repo.path().join("session.jsonl");
+        let bash = |command: &str| serde_json::json!({"type": "tool_use", "name": "Bash", "input": {"command": command}});
+        let command_loop = |output: &HookOutput| {
+            output
+                .stderr
+                .iter()
+                .find(|line| line.contains("without an edit"))
+                .cloned()
+        };
+
+        // One `ls`, then two turns without tools: no loop
+        assert_eq!(
+            command_loop(&stop_after_turn(&paths, &transcript, &[bash("ls")])),
+            None
+        );
+        for _ in 0..2 {
+            assert_eq!(
+                command_loop(&stop_after_turn(&paths, &transcript, &[])),
+                None
+            );
+        }
+
+        // A test rerun once per turn is counted once per turn
+        for _ in 0..2 {
+            let output = stop_after_turn(&paths, &transcript, &[bash("cargo test")]);
+            assert_eq!(command_loop(&output), None);
+        }
+        let output = stop_after_turn(&paths, &transcript, &[bash("cargo test")]);
+        assert_eq!(
+            command_loop(&output).as_deref(),
+            Some("[LoopBreaker] Ran `cargo test` 3 times without an edit")
+        );
+    }
+
+    #[test]
+    fn test_stop_escalates_file_loops_by_this_turns_attempts() {
+        let env = TestEnv::new();
+        let repo = tempfile::TempDir::new().unwrap();
+        let paths = env.paths().in_dir(repo.path());
+        let transcript = repo.path().join("session.jsonl");
+        let edit = serde_json::json!({"type": "tool_use", "name": "Edit", "input": {
+            "file_path": repo.path().join("src/lib.rs"),
+            "old_string": "fn parse()",
+            "new_string": "fn parse() -> Option<()>",
+        }});
+        let file_loop = |output: &HookOutput| {
+            output
+                .stderr
+                .iter()
+                .find(|line| line.contains("similar attempts"))
+                .cloned()
+        };
+
+        for _ in 0..2 {
+            let output = stop_after_turn(&paths, &transcript, std::slice::from_ref(&edit));
+            assert_eq!(file_loop(&output), None);
+        }
+        let output = stop_after_turn(&paths, &transcript, std::slice::from_ref(&edit));
+        assert_eq!(
+            file_loop(&output).as_deref(),
+            Some("[LoopBreaker] Detected 3 similar attempts on lib.rs")
+        );
+        let output = stop_after_turn(&paths, &transcript, std::slice::from_ref(&edit));
+        assert_eq!(
+            file_loop(&output).as_deref(),
+            Some("[LoopBreaker] Detected 4 similar attempts on lib.rs (escalation 1/3)")
+        );
+    }
+
+    #[test]
+    fn test_build_deadline_fallback_lists_last_tiers() {
+        let mut state = AttentionState::new();
+        state.scores.insert("hot.rs".to_string(), 0.9);
+        state.scores.insert("warm.rs".to_string(), 0.5);
+        state.scores.insert("cold.rs".to_string(), 0.1);
+
+        let context = build_deadline_fallback(&state, 300);
+        assert!(context.contains("300ms deadline"));
+        assert!(context.contains("HOT: hot.rs"));
+        assert!(context.contains("WARM: warm.rs"));
+        assert!(!context.contains("cold.rs"));
+    }
 }
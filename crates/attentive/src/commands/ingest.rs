@@ -1,9 +1,12 @@
-use attentive_learn::Learner;
-use attentive_telemetry::Paths;
-use std::collections::HashSet;
+use super::hooks::{load_learner, save_learner};
+use attentive_learn::{Learner, Predictor, Synonyms};
+use attentive_telemetry::{PathCanonicalizer, Paths, Versioned};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+use std::hash::{DefaultHasher, Hash, Hasher};
 use std::path::{Path, PathBuf};
 
-fn extract_files_from_session_turn(turn: &serde_json::Value) -> Vec<String> {
+pub(crate) fn extract_files_from_session_turn(turn: &serde_json::Value) -> Vec<String> {
     let mut files = HashSet::new();
     if let Some(content) = turn.pointer("/message/content").and_then(|c| c.as_array()) {
         for item in content {
@@ -23,11 +26,7 @@ fn extract_files_from_session_turn(turn: &serde_json::Value) -> Vec<String> {
             }
             // Bash — extract file paths from command
             if let Some(cmd) = input.get("command").and_then(|v| v.as_str()) {
-                for token in cmd.split_whitespace() {
-                    if token.contains('/') && !token.starts_with('-') && !token.contains("://") {
-                        files.insert(token.to_string());
-                    }
-                }
+                files.extend(extract_paths_from_command(cmd));
             }
             // NotebookEdit — notebook_path
             if let Some(p) = input.get("notebook_path").and_then(|v| v.as_str()) {
@@ -38,7 +37,15 @@ fn extract_files_from_session_turn(turn: &serde_json::Value) -> Vec<String> {
     files.into_iter().collect()
 }
 
-fn extract_prompt_from_turn(turn: &serde_json::Value) -> String {
+/// Path-like tokens in a shell command (contain `/`, not flags or URLs)
+pub(crate) fn extract_paths_from_command(cmd: &str) -> Vec<String> {
+    cmd.split_whitespace()
+        .filter(|token| token.contains('/') && !token.starts_with('-') && !token.contains("://"))
+        .map(|token| token.to_string())
+        .collect()
+}
+
+pub(crate) fn extract_prompt_from_turn(turn: &serde_json::Value) -> String {
     let content = match turn.pointer("/message/content") {
         Some(c) => c,
         None => return String::new(),
@@ -58,10 +65,16 @@ fn extract_prompt_from_turn(turn: &serde_json::Value) -> String {
     String::new()
 }
 
-type PromptFilePairs = Vec<(String, Vec<String>)>;
+pub(crate) type PromptFilePairs = Vec<(String, Vec<String>)>;
 
+#[cfg(test)]
 fn parse_session_jsonl(path: &Path) -> anyhow::Result<(PromptFilePairs, usize)> {
     let content = std::fs::read_to_string(path)?;
+    Ok(parse_claude_transcript(&content))
+}
+
+/// Parse Claude Code session JSONL content into prompt→files pairs
+pub(crate) fn parse_claude_transcript(content: &str) -> (PromptFilePairs, usize) {
     let mut pairs = Vec::new();
     let mut current_prompt = String::new();
     let mut total = 0;
@@ -90,10 +103,10 @@ fn parse_session_jsonl(path: &Path) -> anyhow::Result<(PromptFilePairs, usize)>
         }
     }
 
-    Ok((pairs, total))
+    (pairs, total)
 }
 
-fn discover_session_files(project_dir: &Path) -> Vec<PathBuf> {
+pub(crate) fn discover_session_files(project_dir: &Path) -> Vec<PathBuf> {
     if !project_dir.exists() {
         return Vec::new();
     }
@@ -114,20 +127,168 @@ fn discover_session_files(project_dir: &Path) -> Vec<PathBuf> {
     files
 }
 
-fn load_existing_learner(path: &Path) -> Learner {
-    if !path.exists() {
-        return Learner::new();
+/// How much of one session file has been ingested
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+struct IngestedFile {
+    /// Length of the content ingested so far
+    bytes: usize,
+    /// Hash of those bytes, telling appended sessions from rewritten ones
+    hash: String,
+    /// Prompt-file pairs that content produced
+    pairs: usize,
+}
+
+/// Session files already fed to the learner, keyed by absolute path
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub(crate) struct IngestManifest {
+    files: BTreeMap<String, IngestedFile>,
+}
+
+impl Versioned for IngestManifest {
+    const VERSION: u32 = 1;
+    const KIND: &'static str = "ingest manifest";
+}
+
+fn content_hash(content: &str) -> String {
+    let mut hasher = DefaultHasher::new();
+    content.hash(&mut hasher);
+    format!("{:x}", hasher.finish())
+}
+
+impl IngestManifest {
+    /// Pairs of `content` already ingested from `key`. Sessions only grow, so
+    /// when the ingested prefix is unchanged its pairs come first; a file that
+    /// was rewritten starts over.
+    fn ingested_pairs(&self, key: &str, content: &str) -> usize {
+        let Some(entry) = self.files.get(key) else {
+            return 0;
+        };
+        match content.get(..entry.bytes) {
+            Some(prefix) if content_hash(prefix) == entry.hash => entry.pairs,
+            _ => 0,
+        }
+    }
+
+    fn record(&mut self, key: &str, content: &str, pairs: usize) {
+        let entry = IngestedFile {
+            bytes: content.len(),
+            hash: content_hash(content),
+            pairs,
+        };
+        self.files.insert(key.to_string(), entry);
     }
-    std::fs::read_to_string(path)
+}
+
+fn manifest_key(path: &Path) -> String {
+    std::fs::canonicalize(path)
+        .unwrap_or_else(|_| path.to_path_buf())
+        .to_string_lossy()
+        .to_string()
+}
+
+/// Terms a file is known by, for mining synonyms: the words of its path and
+/// the names it declares
+fn file_terms(root: &Path, file: &str) -> Vec<String> {
+    let mut terms = Synonyms::terms(file);
+    let symbols = std::fs::read_to_string(root.join(file))
         .ok()
-        .and_then(|c| serde_json::from_str(&c).ok())
-        .unwrap_or_default()
+        .and_then(|content| attentive_repo::extract_symbols(&content, file));
+    for symbol in symbols.iter().flat_map(|s| &s.symbols) {
+        terms.extend(Synonyms::terms(&symbol.name));
+    }
+    terms
+}
+
+/// Learned state built from scratch out of every session file
+pub(crate) struct Relearned {
+    pub learner: Learner,
+    pub predictor: Predictor,
+    pub synonyms: Synonyms,
+    manifest: IngestManifest,
+    /// Session files that held at least one prompt-file pair
+    pub sessions: usize,
+    pub pairs: usize,
+}
+
+impl Relearned {
+    /// Replace the learner, keyword rules, synonyms and ingest manifest with
+    /// the rebuilt ones
+    pub fn save(&mut self, paths: &Paths) -> anyhow::Result<()> {
+        save_learner(&paths.learned_state_path()?, &mut self.learner)?;
+        attentive_telemetry::write_versioned(&paths.ingest_manifest_path()?, &self.manifest)?;
+        super::learn::save_keyword_rules(&paths.keyword_rules_path()?, &self.predictor)?;
+        self.synonyms.save(&paths.synonyms_path()?)
+    }
+}
+
+/// Learner, keyword rules and synonyms from `session_files` alone, ignoring
+/// what was learned before. `on_session` is called as each file is read.
+pub(crate) fn relearn(
+    paths: &Paths,
+    session_files: &[PathBuf],
+    mut on_session: impl FnMut(&Path),
+) -> anyhow::Result<Relearned> {
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let scope = super::hooks::path_scope(paths, &super::hooks::load_config(&paths.home_claude));
+    let mut relearned = Relearned {
+        learner: Learner::new(),
+        predictor: Predictor::new(),
+        synonyms: Synonyms::new(),
+        manifest: IngestManifest::default(),
+        sessions: 0,
+        pairs: 0,
+    };
+    let mut corpus: PromptFilePairs = Vec::new();
+    let mut last_session_files: HashSet<String> = HashSet::new();
+
+    for path in session_files {
+        on_session(path);
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
+        };
+        let (pairs, _, _) = super::transcript::parse_transcript_content(path, &content);
+        relearned
+            .manifest
+            .record(&manifest_key(path), &content, pairs.len());
+        if pairs.is_empty() {
+            continue;
+        }
+        let pairs: PromptFilePairs = pairs
+            .into_iter()
+            .map(|(prompt, files)| (prompt, scope.retain(canonicalizer.canonicalize_all(&files))))
+            .collect();
+        last_session_files = pairs.iter().flat_map(|(_, files)| files.clone()).collect();
+        for (prompt, files) in &pairs {
+            relearned.learner.observe_turn(prompt, files);
+        }
+        relearned.sessions += 1;
+        relearned.pairs += pairs.len();
+        corpus.extend(pairs);
+    }
+
+    if relearned.pairs > 0 {
+        let last: Vec<String> = last_session_files.into_iter().collect();
+        relearned.learner.save_session(&last);
+    }
+    relearned.predictor.mine_strong_keywords(&corpus);
+    relearned
+        .synonyms
+        .mine(&corpus, |file| file_terms(canonicalizer.root(), file));
+    Ok(relearned)
+}
+
+fn load_existing_learner(path: &Path) -> Learner {
+    load_learner(path).unwrap_or_default()
 }
 
-pub fn run(file: Option<&str>) -> anyhow::Result<()> {
+/// Feed session transcripts to the learner. Content already ingested is
+/// skipped unless `force`; `reset` starts from an empty learner and re-reads
+/// everything.
+pub fn run(file: Option<&str>, force: bool, reset: bool) -> anyhow::Result<()> {
     let paths = Paths::new()?;
     let project_dir = paths.project_dir()?;
     let learned_state_path = paths.learned_state_path()?;
+    let manifest_path = paths.ingest_manifest_path()?;
     std::fs::create_dir_all(&project_dir)?;
 
     let session_files: Vec<PathBuf> = if let Some(f) = file {
@@ -142,22 +303,74 @@ pub fn run(file: Option<&str>) -> anyhow::Result<()> {
         files
     };
 
-    let mut learner = load_existing_learner(&learned_state_path);
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let renamed = super::remap::apply_git_renames(&paths, &canonicalizer)?;
+    if renamed > 0 {
+        println!("Remapped {} entries for renamed files", renamed);
+    }
+
+    let (mut learner, mut manifest) = if reset {
+        println!("Resetting learner; re-ingesting every session");
+        (Learner::new(), IngestManifest::default())
+    } else {
+        let manifest = if manifest_path.exists() {
+            attentive_telemetry::read_versioned(&manifest_path)?
+        } else {
+            IngestManifest::default()
+        };
+        (load_existing_learner(&learned_state_path), manifest)
+    };
+    learner.canonicalize_files(&canonicalizer);
+    let scope = super::hooks::path_scope(&paths, &super::hooks::load_config(&paths.home_claude));
+    learner.prune_files(|f| scope.allows(f));
     let initial_maturity = learner.maturity();
 
+    let keyword_rules_path = paths.keyword_rules_path()?;
+    let synonyms_path = paths.synonyms_path()?;
+    let (mut predictor, mut synonyms) = if reset {
+        (Predictor::new(), Synonyms::new())
+    } else {
+        (
+            super::learn::load_keyword_rules(&keyword_rules_path),
+            Synonyms::load(&synonyms_path),
+        )
+    };
+    // Every session's pairs, already ingested or not: rules and synonyms
+    // mined from the new pairs alone would rest on a handful of prompts
+    let mut corpus: PromptFilePairs = Vec::new();
+
     let mut total_pairs = 0;
     let mut total_files_processed = 0;
-    let mut per_session_info: Vec<(String, usize, usize)> = Vec::new();
+    let mut per_session_info: Vec<(String, usize, usize, usize)> = Vec::new();
     let mut last_session_files: Vec<String> = Vec::new();
+    let mut already_ingested = 0;
 
     for path in &session_files {
-        let (pairs, total_turns) = match parse_session_jsonl(path) {
-            Ok(result) => result,
-            Err(_) => continue,
+        let Ok(content) = std::fs::read_to_string(path) else {
+            continue;
         };
-        if pairs.is_empty() {
+        let (pairs, total_turns, format) =
+            super::transcript::parse_transcript_content(path, &content);
+        let key = manifest_key(path);
+        let skip = if force {
+            0
+        } else {
+            manifest.ingested_pairs(&key, &content).min(pairs.len())
+        };
+        manifest.record(&key, &content, pairs.len());
+        let mut pairs: PromptFilePairs = pairs
+            .into_iter()
+            .map(|(prompt, files)| (prompt, scope.retain(canonicalizer.canonicalize_all(&files))))
+            .collect();
+        let new_pairs = pairs.split_off(skip);
+        corpus.extend(pairs);
+        if new_pairs.is_empty() {
+            if skip > 0 {
+                already_ingested += 1;
+            }
             continue;
         }
+        let pairs = new_pairs;
 
         let filename = path
             .file_name()
@@ -165,7 +378,12 @@ pub fn run(file: Option<&str>) -> anyhow::Result<()> {
             .unwrap_or("unknown")
             .to_string();
 
-        per_session_info.push((filename, pairs.len(), total_turns));
+        per_session_info.push((
+            format!("{} [{}]", filename, format),
+            pairs.len(),
+            total_turns,
+            skip,
+        ));
 
         // Collect unique files from this session for warm-start
         let mut session_files_set = std::collections::HashSet::new();
@@ -181,20 +399,48 @@ pub fn run(file: Option<&str>) -> anyhow::Result<()> {
         for (prompt, files) in &pairs {
             learner.observe_turn(prompt, files);
         }
+        corpus.extend(pairs);
     }
 
     if total_pairs == 0 {
-        println!("No prompt-file pairs found");
+        if already_ingested > 0 {
+            println!(
+                "No new prompt-file pairs ({} sessions already ingested; --force re-ingests them)",
+                already_ingested
+            );
+        } else {
+            println!("No prompt-file pairs found");
+        }
+        if reset {
+            save_learner(&learned_state_path, &mut learner)?;
+            super::learn::save_keyword_rules(&keyword_rules_path, &predictor)?;
+            synonyms.save(&synonyms_path)?;
+        }
+        attentive_telemetry::write_versioned(&manifest_path, &manifest)?;
         return Ok(());
     }
 
     learner.save_session(&last_session_files);
-    let json = serde_json::to_string_pretty(&learner)?;
-    attentive_telemetry::atomic_write(&learned_state_path, json.as_bytes())?;
+    save_learner(&learned_state_path, &mut learner)?;
+    // Only once the learner holds the pairs is their content marked as ingested
+    attentive_telemetry::write_versioned(&manifest_path, &manifest)?;
+
+    let mined_rules = predictor.mine_strong_keywords(&corpus);
+    super::learn::save_keyword_rules(&keyword_rules_path, &predictor)?;
+
+    let mined_synonyms = synonyms.mine(&corpus, |file| file_terms(canonicalizer.root(), file));
+    synonyms.save(&synonyms_path)?;
 
     // Print per-session details
-    for (filename, pairs, turns) in &per_session_info {
-        println!("  {}: {} pairs from {} turns", filename, pairs, turns);
+    for (filename, pairs, turns, skipped) in &per_session_info {
+        if *skipped > 0 {
+            println!(
+                "  {}: {} new pairs from {} turns ({} already ingested)",
+                filename, pairs, turns, skipped
+            );
+        } else {
+            println!("  {}: {} pairs from {} turns", filename, pairs, turns);
+        }
     }
     println!();
 
@@ -202,6 +448,12 @@ pub fn run(file: Option<&str>) -> anyhow::Result<()> {
         "Ingested {} pairs from {} sessions",
         total_pairs, total_files_processed
     );
+    if already_ingested > 0 {
+        println!(
+            "Skipped {} sessions with nothing new (--force re-ingests them)",
+            already_ingested
+        );
+    }
 
     // Print top files learned
     let top_files = learner.top_files_by_frequency(10);
@@ -215,6 +467,16 @@ pub fn run(file: Option<&str>) -> anyhow::Result<()> {
     // Print association count
     let associations = learner.total_associations();
     println!("Associations: {} word→file mappings", associations);
+    println!(
+        "Keyword rules: {} mined, {} total (see `attentive learn rules`)",
+        mined_rules,
+        predictor.strong_keywords().len()
+    );
+    println!(
+        "Synonyms: {} mined, {} total (see `attentive learn synonyms`)",
+        mined_synonyms,
+        synonyms.len()
+    );
 
     println!(
         "Maturity: {:?} -> {:?}",
@@ -291,6 +553,23 @@ mod tests {
         assert!(pairs.is_empty());
     }
 
+    #[test]
+    fn test_file_terms_include_declared_names() {
+        let temp = tempfile::TempDir::new().unwrap();
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        std::fs::write(
+            temp.path().join("src/login.rs"),
+            "pub fn create_session() {}\n",
+        )
+        .unwrap();
+
+        let terms = file_terms(temp.path(), "src/login.rs");
+        assert!(terms.iter().any(|t| t == "login"));
+        assert!(terms.iter().any(|t| t == "session"));
+        // Unreadable files still have their path
+        assert!(file_terms(temp.path(), "src/gone.rs").contains(&"gone".to_string()));
+    }
+
     #[test]
     fn test_discover_session_files() {
         let temp = tempfile::TempDir::new().unwrap();
@@ -324,6 +603,148 @@ mod tests {
         assert!(!roundtrip.is_empty());
     }
 
+    #[test]
+    fn test_manifest_skips_ingested_prefix() {
+        let mut manifest = IngestManifest::default();
+        let content = "line one\nline two\n";
+        manifest.record("s.jsonl", content, 2);
+
+        assert_eq!(manifest.ingested_pairs("s.jsonl", content), 2);
+        assert_eq!(
+            manifest.ingested_pairs("s.jsonl", &format!("{}line three\n", content)),
+            2
+        );
+        // Rewritten or truncated sessions are ingested from the start
+        assert_eq!(
+            manifest.ingested_pairs("s.jsonl", "line ONE\nline two\n"),
+            0
+        );
+        assert_eq!(manifest.ingested_pairs("s.jsonl", "line one\n"), 0);
+        assert_eq!(manifest.ingested_pairs("other.jsonl", content), 0);
+    }
+
+    #[test]
+    fn test_run_twice_does_not_double_count() {
+        let env = attentive_telemetry::TestEnv::new();
+        let session = env.paths().home_claude.join("session.jsonl");
+        let turn = |prompt: &str, file: &str| {
+            [
+                serde_json::json!({"type": "user", "message": {"content": prompt}}),
+                serde_json::json!({"type": "assistant", "message": {"content": [
+                    {"type": "tool_use", "name": "Read", "input": {"file_path": file}}
+                ]}}),
+            ]
+            .map(|l| format!("{}\n", l))
+            .concat()
+        };
+        std::fs::write(&session, turn("fix the router", "src/router.rs")).unwrap();
+        let file = session.to_str().unwrap();
+        let frequency = || {
+            let learner = load_existing_learner(&env.paths().learned_state_path().unwrap());
+            learner.top_files_by_frequency(5)
+        };
+
+        run(Some(file), false, false).unwrap();
+        let first = frequency();
+        assert_eq!(first.len(), 1);
+        run(Some(file), false, false).unwrap();
+        assert_eq!(frequency(), first);
+
+        // Appended turns are picked up on their own
+        let mut content = std::fs::read_to_string(&session).unwrap();
+        content.push_str(&turn("tune the config", "src/config.rs"));
+        std::fs::write(&session, content).unwrap();
+        run(Some(file), false, false).unwrap();
+        assert_eq!(frequency().len(), 2);
+        assert_eq!(frequency()[0].1, first[0].1);
+
+        // --force counts everything again; --reset starts over
+        run(Some(file), true, false).unwrap();
+        assert_eq!(frequency()[0].1, first[0].1 * 2);
+        run(Some(file), false, true).unwrap();
+        assert_eq!(frequency()[0].1, first[0].1);
+    }
+
+    #[test]
+    fn test_keyword_rules_mined_over_every_session() {
+        let env = attentive_telemetry::TestEnv::new();
+        let session = env.paths().home_claude.join("session.jsonl");
+        let turns = |n: usize| {
+            [
+                serde_json::json!({"type": "user", "message": {"content": "fix the router"}}),
+                serde_json::json!({"type": "assistant", "message": {"content": [
+                    {"type": "tool_use", "name": "Read", "input": {"file_path": "src/router.rs"}}
+                ]}}),
+            ]
+            .map(|l| format!("{}\n", l))
+            .concat()
+            .repeat(n)
+        };
+        let file = session.to_str().unwrap();
+        let rules_path = env.paths().keyword_rules_path().unwrap();
+        let rule = || {
+            super::super::learn::load_keyword_rules(&rules_path)
+                .strong_keywords()
+                .get("router")
+                .cloned()
+        };
+
+        // Neither batch reaches the minimum occurrences on its own
+        std::fs::write(&session, turns(3)).unwrap();
+        run(Some(file), false, false).unwrap();
+        assert_eq!(rule(), None);
+        std::fs::write(&session, turns(5)).unwrap();
+        run(Some(file), false, false).unwrap();
+        let router = rule().expect("mined from both batches");
+        assert!(router.ends_with("src/router.rs"), "{}", router);
+
+        // --reset drops rules whose prompts are gone
+        std::fs::write(&session, turns(1)).unwrap();
+        run(Some(file), false, true).unwrap();
+        assert_eq!(rule(), None);
+    }
+
+    #[test]
+    fn test_synonyms_mined_over_every_session() {
+        let env = attentive_telemetry::TestEnv::new();
+        let session = env.paths().home_claude.join("session.jsonl");
+        let turn = |prompt: &str, file: &str| {
+            [
+                serde_json::json!({"type": "user", "message": {"content": prompt}}),
+                serde_json::json!({"type": "assistant", "message": {"content": [
+                    {"type": "tool_use", "name": "Read", "input": {"file_path": file}}
+                ]}}),
+            ]
+            .map(|l| format!("{}\n", l))
+            .concat()
+        };
+        let auth = turn("fix auth", "src/login.rs");
+        let config = turn("tune config", "src/config.rs");
+        let file = session.to_str().unwrap();
+        let synonyms_path = env.paths().synonyms_path().unwrap();
+        let expands_auth = || {
+            Synonyms::load(&synonyms_path)
+                .expand(&["auth".to_string()])
+                .iter()
+                .any(|(term, _)| term == "login")
+        };
+
+        // Two auth turns are below the minimum; the third completes it
+        let mut content = [auth.as_str(), &config, &auth, &config].concat();
+        std::fs::write(&session, &content).unwrap();
+        run(Some(file), false, false).unwrap();
+        assert!(!expands_auth());
+        content.push_str(&auth);
+        std::fs::write(&session, &content).unwrap();
+        run(Some(file), false, false).unwrap();
+        assert!(expands_auth());
+
+        // --reset starts the synonyms over along with the learner
+        std::fs::write(&session, &config).unwrap();
+        run(Some(file), false, true).unwrap();
+        assert!(!expands_auth());
+    }
+
     #[test]
     fn test_load_existing_learner_invalid_json_returns_new() {
         let temp = tempfile::TempDir::new().unwrap();
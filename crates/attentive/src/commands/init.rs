@@ -1,5 +1,17 @@
-use attentive_telemetry::Paths;
+use super::hooks::PROJECT_CONFIG_FILE;
+use crate::error::ErrorKind;
+use attentive_index::{Document, SearchIndex};
+use attentive_repo::RepoMapper;
+use attentive_telemetry::{PathCanonicalizer, Paths};
 use serde_json::Value;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+/// Files suggested as pins in a new .attentive.json
+const SUGGESTED_PINS: usize = 5;
+
+/// Non-source files worth putting in the search index
+const DOC_EXTENSIONS: &[&str] = &["md", "rst", "txt"];
 
 pub fn run() -> anyhow::Result<()> {
     let paths = Paths::new()?;
@@ -25,15 +37,17 @@ pub fn run() -> anyhow::Result<()> {
         settings["hooks"] = serde_json::json!({});
     }
 
-    // Add attentive hooks for each event
+    // Add attentive hooks for each event (event, tool matcher, command)
     let hooks_to_add = vec![
-        ("UserPromptSubmit", "attentive hook:user-prompt-submit"),
-        ("SessionStart", "attentive hook:session-start"),
-        ("Stop", "attentive hook:stop"),
+        ("UserPromptSubmit", "", "attentive hook:user-prompt-submit"),
+        ("SessionStart", "", "attentive hook:session-start"),
+        ("Stop", "", "attentive hook:stop"),
+        ("PreToolUse", "Bash", "attentive hook:pre-tool-use"),
+        ("PreCompact", "", "attentive hook:pre-compact"),
     ];
 
-    for (event_name, command) in hooks_to_add {
-        add_hook_if_missing(&mut settings, event_name, command)?;
+    for (event_name, matcher, command) in hooks_to_add {
+        add_hook_if_missing(&mut settings, event_name, matcher, command)?;
     }
 
     // Write back settings.json
@@ -45,13 +59,163 @@ pub fn run() -> anyhow::Result<()> {
     println!("  - UserPromptSubmit");
     println!("  - SessionStart");
     println!("  - Stop");
+    println!("  - PreToolUse (Bash)");
+    println!("  - PreCompact");
+
+    Ok(())
+}
+
+/// What `init --project` set up in a repo
+#[derive(Debug)]
+struct ProjectSetup {
+    /// Whether .attentive.json was written (an existing one is left alone)
+    config_created: bool,
+    pins: Vec<String>,
+    indexed: usize,
+}
+
+/// Most depended-on source files, by PageRank over the import graph
+fn suggest_pins(mapper: &RepoMapper) -> Vec<String> {
+    let mut ranked: Vec<(String, f64)> = mapper
+        .page_rank()
+        .into_iter()
+        .filter(|(path, _)| !mapper.imported_by(path).is_empty())
+        .collect();
+    ranked.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    ranked
+        .into_iter()
+        .take(SUGGESTED_PINS)
+        .map(|(path, _)| path)
+        .collect()
+}
+
+/// Search index documents for the code and doc files among `files`
+/// (relative to `root`), with the repo map built along the way. `on_file` is
+/// called as each file is read.
+pub(crate) fn collect_documents(
+    root: &Path,
+    files: Vec<String>,
+    mut on_file: impl FnMut(&str),
+) -> (RepoMapper, Vec<Document>) {
+    let mut mapper = RepoMapper::new();
+    let mut documents = Vec::new();
+    for rel in files {
+        on_file(&rel);
+        let path = root.join(&rel);
+        let Ok(content) = std::fs::read_to_string(&path) else {
+            continue;
+        };
+        mapper.add_file(&rel, &content);
+        let doc_type = if mapper.get_symbols(&rel).is_some() {
+            "code"
+        } else if path
+            .extension()
+            .and_then(|e| e.to_str())
+            .is_some_and(|e| DOC_EXTENSIONS.contains(&e))
+        {
+            "doc"
+        } else {
+            continue;
+        };
+        let mtime = std::fs::metadata(&path)
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+            .map_or(0.0, |d| d.as_secs_f64());
+        documents.push(Document {
+            path: rel,
+            content,
+            mtime,
+            doc_type: doc_type.to_string(),
+        });
+    }
+    (mapper, documents)
+}
+
+/// Write .attentive.json with suggested pins and build the search index over
+/// the files not ignored or matched by `excludes`
+fn setup_project(
+    root: &Path,
+    index_path: &Path,
+    excludes: &[String],
+) -> anyhow::Result<ProjectSetup> {
+    let files = attentive_repo::repo_files(root, excludes);
+    let (mapper, documents) = collect_documents(root, files, |_| {});
+    let pins = suggest_pins(&mapper);
+    let config_path = root.join(PROJECT_CONFIG_FILE);
+    let config_created = !config_path.exists();
+    if config_created {
+        let template = serde_json::json!({
+            "pinned_files": pins,
+            "demoted_files": [],
+        });
+        let json = format!("{}\n", serde_json::to_string_pretty(&template)?);
+        attentive_telemetry::atomic_write(&config_path, json.as_bytes())
+            .map_err(|e| ErrorKind::Config.wrap(e))?;
+    }
+
+    let indexed = documents.len();
+    SearchIndex::new(index_path)
+        .and_then(|mut index| index.build(documents))
+        .map_err(|e| ErrorKind::Index.wrap(e))?;
+    Ok(ProjectSetup {
+        config_created,
+        pins,
+        indexed,
+    })
+}
+
+fn hooks_installed(paths: &Paths) -> bool {
+    std::fs::read_to_string(paths.home_claude.join("settings.json"))
+        .is_ok_and(|c| c.contains("attentive hook:user-prompt-submit"))
+}
+
+/// Bootstrap the current repo: committed .attentive.json, search index and,
+/// with `ingest`, the learner from this project's Claude Code transcripts
+pub fn run_project(ingest: bool) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let root = PathCanonicalizer::for_current_dir()?.root().to_path_buf();
+    std::fs::create_dir_all(paths.project_dir()?)?;
+    let config = super::hooks::load_config(&paths.home_claude);
+    let setup = setup_project(
+        &root,
+        &paths.search_index_path()?,
+        &config.exclude_patterns(),
+    )?;
+
+    if ingest {
+        super::ingest::run(None, false, false)?;
+        println!();
+    }
 
+    if setup.config_created {
+        println!(
+            "✓ Created {} with {} suggested pin(s) (commit it to share them)",
+            PROJECT_CONFIG_FILE,
+            setup.pins.len()
+        );
+        for pin in &setup.pins {
+            println!("    {}", pin);
+        }
+    } else {
+        println!("✓ {} already exists; left unchanged", PROJECT_CONFIG_FILE);
+    }
+    println!("✓ Indexed {} files for search", setup.indexed);
+    if ingest {
+        println!("✓ Ingested Claude Code transcripts for this project");
+    } else {
+        println!("- Skipped transcript ingestion (rerun with --ingest, or `attentive ingest`)");
+    }
+    if !hooks_installed(&paths) {
+        println!("\nHooks aren't installed yet; run `attentive init` to add them.");
+    }
     Ok(())
 }
 
 fn add_hook_if_missing(
     settings: &mut Value,
     event_name: &str,
+    matcher: &str,
     command: &str,
 ) -> anyhow::Result<()> {
     let hooks = settings
@@ -87,7 +251,7 @@ fn add_hook_if_missing(
     if !already_exists {
         // Add new hook group
         let new_group = serde_json::json!({
-            "matcher": "",
+            "matcher": matcher,
             "hooks": [
                 {
                     "type": "command",
@@ -104,26 +268,16 @@ fn add_hook_if_missing(
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serial_test::serial;
+    use attentive_telemetry::TestEnv;
     use std::fs;
-    use tempfile::TempDir;
 
     #[test]
-    #[serial]
     fn test_init_creates_hooks_in_global_settings() {
-        let original_home = std::env::var("HOME").unwrap();
-        let temp = TempDir::new().unwrap();
-        let claude_dir = temp.path().join(".claude");
-        fs::create_dir_all(&claude_dir).unwrap();
-
-        // Override HOME for this test
-        unsafe { std::env::set_var("HOME", temp.path()) };
+        let env = TestEnv::new();
+        let claude_dir = env.paths().home_claude;
 
         let result = run();
 
-        // Restore HOME
-        unsafe { std::env::set_var("HOME", &original_home) };
-
         assert!(result.is_ok());
         assert!(claude_dir.join("settings.json").exists());
 
@@ -132,15 +286,72 @@ mod tests {
         assert!(settings_content.contains("SessionStart"));
         assert!(settings_content.contains("Stop"));
         assert!(settings_content.contains("attentive hook:user-prompt-submit"));
+
+        let settings: Value = serde_json::from_str(&settings_content).unwrap();
+        let pre_tool_use = &settings["hooks"]["PreToolUse"][0];
+        assert_eq!(pre_tool_use["matcher"], "Bash");
+        assert_eq!(
+            pre_tool_use["hooks"][0]["command"],
+            "attentive hook:pre-tool-use"
+        );
+        assert_eq!(
+            settings["hooks"]["PreCompact"][0]["hooks"][0]["command"],
+            "attentive hook:pre-compact"
+        );
+    }
+
+    #[test]
+    fn test_setup_project_writes_config_and_index() {
+        let repo = tempfile::tempdir().unwrap();
+        let root = repo.path();
+        let files = [
+            ("src/config.rs", "pub struct Config;"),
+            (
+                "src/router.rs",
+                "use crate::config::Config;\npub fn route() {}",
+            ),
+            (
+                "src/main.rs",
+                "use crate::config::Config;\nuse crate::router::route;",
+            ),
+            ("README.md", "# Project"),
+            ("logo.svg", "<svg/>"),
+        ];
+        for (path, content) in files {
+            std::fs::create_dir_all(root.join(path).parent().unwrap()).unwrap();
+            std::fs::write(root.join(path), content).unwrap();
+        }
+        let index_path = root.join("index/search.db");
+
+        let setup = setup_project(root, &index_path, &[]).unwrap();
+        assert!(setup.config_created);
+        assert_eq!(setup.pins, ["src/config.rs", "src/router.rs"]);
+        assert_eq!(setup.indexed, 4);
+        let config = attentive_core::Config::from_json(
+            &fs::read_to_string(root.join(PROJECT_CONFIG_FILE)).unwrap(),
+        )
+        .unwrap();
+        assert_eq!(config.pinned_files, setup.pins);
+        let index = SearchIndex::new(&index_path).unwrap();
+        assert_eq!(index.query("project", 5).unwrap()[0].0, "README.md");
+
+        // An existing project config is never overwritten
+        fs::write(root.join(PROJECT_CONFIG_FILE), "{}").unwrap();
+        assert!(
+            !setup_project(root, &index_path, &[])
+                .unwrap()
+                .config_created
+        );
+        assert_eq!(
+            fs::read_to_string(root.join(PROJECT_CONFIG_FILE)).unwrap(),
+            "{}"
+        );
     }
 
     #[test]
-    #[serial]
     fn test_init_preserves_existing_hooks() {
-        let original_home = std::env::var("HOME").unwrap();
-        let temp = TempDir::new().unwrap();
-        let claude_dir = temp.path().join(".claude");
-        fs::create_dir_all(&claude_dir).unwrap();
+        let env = TestEnv::new();
+        let claude_dir = env.paths().home_claude;
 
         // Create existing settings with custom hooks
         let existing_settings = serde_json::json!({
@@ -164,9 +375,7 @@ mod tests {
         )
         .unwrap();
 
-        unsafe { std::env::set_var("HOME", temp.path()) };
         let result = run();
-        unsafe { std::env::set_var("HOME", &original_home) };
 
         assert!(result.is_ok());
 
@@ -184,6 +393,7 @@ mod tests {
         add_hook_if_missing(
             &mut settings,
             "UserPromptSubmit",
+            "",
             "attentive hook:user-prompt-submit",
         )
         .unwrap();
@@ -217,6 +427,7 @@ mod tests {
         add_hook_if_missing(
             &mut settings,
             "UserPromptSubmit",
+            "",
             "attentive hook:user-prompt-submit",
         )
         .unwrap();
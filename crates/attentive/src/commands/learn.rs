@@ -0,0 +1,285 @@
+use super::hooks::PROJECT_CONFIG_FILE;
+use attentive_core::Config;
+use attentive_learn::{Learner, Predictor, Synonyms};
+use attentive_telemetry::{PathCanonicalizer, Paths};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+/// Learned co-activation pairs listed in the report
+pub(crate) const REPORT_COACTIVATION_PAIRS: usize = 5;
+
+/// A learned co-activation pair that config doesn't have yet
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CoactivationSuggestion {
+    pub a: String,
+    pub b: String,
+    /// Jaccard similarity of the turns each was active in
+    pub jaccard: f64,
+    /// Turns both were active in
+    pub together: usize,
+}
+
+/// Up to `limit` learned co-activation pairs missing from `config` (in either
+/// direction), strongest first
+pub(crate) fn coactivation_suggestions(
+    learner: &Learner,
+    config: &Config,
+    limit: usize,
+) -> Vec<CoactivationSuggestion> {
+    let configured = |from: &str, to: &str| {
+        config
+            .co_activation
+            .get(from)
+            .is_some_and(|related| related.iter().any(|f| f == to))
+    };
+    let mut suggestions: Vec<CoactivationSuggestion> = learner
+        .get_learned_coactivation()
+        .into_iter()
+        .flat_map(|(a, related)| related.into_iter().map(move |b| (a.clone(), b)))
+        .filter(|(a, b)| a < b && !configured(a, b) && !configured(b, a))
+        .filter_map(|(a, b)| {
+            let jaccard = learner.coactivation_strength(&a, &b)?;
+            let together = learner.co_occurrences(&a, &b);
+            Some(CoactivationSuggestion {
+                a,
+                b,
+                jaccard,
+                together,
+            })
+        })
+        .collect();
+    suggestions.sort_by(|x, y| {
+        y.jaccard
+            .total_cmp(&x.jaccard)
+            .then(y.together.cmp(&x.together))
+            .then_with(|| (&x.a, &x.b).cmp(&(&y.a, &y.b)))
+    });
+    suggestions.truncate(limit);
+    suggestions
+}
+
+/// Suggested pairs, one per line, and the command that accepts them all
+pub(crate) fn format_coactivation(suggestions: &[CoactivationSuggestion]) -> String {
+    let mut lines: Vec<String> = suggestions
+        .iter()
+        .map(|s| {
+            format!(
+                "  {} <-> {}  jaccard {:.2}, together {} turns",
+                s.a, s.b, s.jaccard, s.together
+            )
+        })
+        .collect();
+    let pairs: Vec<String> = suggestions
+        .iter()
+        .map(|s| format!("{}={}", s.a, s.b))
+        .collect();
+    lines.push(format!(
+        "Accept with: attentive learn coactivate {}",
+        pairs.join(" ")
+    ));
+    lines.join("\n")
+}
+
+/// Load persisted keyword→file rules into a predictor
+pub(crate) fn load_keyword_rules(path: &Path) -> Predictor {
+    let mut predictor = Predictor::new();
+    let rules: BTreeMap<String, String> = std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+        .unwrap_or_default();
+    for (keyword, file) in &rules {
+        predictor.set_strong_keyword(keyword, file);
+    }
+    predictor
+}
+
+/// Persist a predictor's keyword rules as a sorted, hand-editable JSON object
+pub(crate) fn save_keyword_rules(path: &Path, predictor: &Predictor) -> anyhow::Result<()> {
+    let rules: BTreeMap<&String, &String> = predictor.strong_keywords().iter().collect();
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&rules)?;
+    attentive_telemetry::atomic_write(path, json.as_bytes())?;
+    Ok(())
+}
+
+fn format_rules(predictor: &Predictor) -> String {
+    let rules: BTreeMap<&String, &String> = predictor.strong_keywords().iter().collect();
+    if rules.is_empty() {
+        return "No keyword rules. Run `attentive ingest` to mine them.".to_string();
+    }
+    let width = rules.keys().map(|k| k.len()).max().unwrap_or(0);
+    let mut lines = vec![format!("Keyword Rules ({})", rules.len())];
+    for (keyword, file) in rules {
+        lines.push(format!("  {:<width$}  -> {}", keyword, file, width = width));
+    }
+    lines.join("\n")
+}
+
+fn format_synonyms(synonyms: &Synonyms) -> String {
+    if synonyms.is_empty() {
+        return "No synonyms. Run `attentive ingest` to mine them.".to_string();
+    }
+    let entries = synonyms.entries();
+    let width = entries.iter().map(|(w, _)| w.len()).max().unwrap_or(0);
+    let mut lines = vec![format!("Synonyms ({})", entries.len())];
+    for (word, terms) in entries {
+        let terms: Vec<String> = terms
+            .iter()
+            .map(|(term, strength)| format!("{} ({:.2})", term, strength))
+            .collect();
+        lines.push(format!(
+            "  {:<width$}  -> {}",
+            word,
+            terms.join(", "),
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
+
+pub fn run_synonyms() -> anyhow::Result<()> {
+    let path = Paths::new()?.synonyms_path()?;
+    println!("{}", format_synonyms(&Synonyms::load(&path)));
+    println!("\nEdit {} to adjust synonyms by hand.", path.display());
+    Ok(())
+}
+
+pub fn run_rules_list() -> anyhow::Result<()> {
+    let path = Paths::new()?.keyword_rules_path()?;
+    println!("{}", format_rules(&load_keyword_rules(&path)));
+    println!("\nEdit {} to adjust rules by hand.", path.display());
+    Ok(())
+}
+
+pub fn run_rules_set(keyword: &str, file: &str) -> anyhow::Result<()> {
+    let path = Paths::new()?.keyword_rules_path()?;
+    let mut predictor = load_keyword_rules(&path);
+    predictor.set_strong_keyword(keyword, file);
+    save_keyword_rules(&path, &predictor)?;
+    println!("Rule set: {} -> {}", keyword.to_lowercase(), file);
+    Ok(())
+}
+
+pub fn run_rules_remove(keyword: &str) -> anyhow::Result<()> {
+    let path = Paths::new()?.keyword_rules_path()?;
+    let mut predictor = load_keyword_rules(&path);
+    match predictor.remove_strong_keyword(keyword) {
+        Some(file) => {
+            save_keyword_rules(&path, &predictor)?;
+            println!("Rule removed: {} -> {}", keyword.to_lowercase(), file);
+        }
+        None => println!("No rule for '{}'", keyword),
+    }
+    Ok(())
+}
+
+/// Add `a=b` pairs to the project's .attentive.json as co-activations
+pub fn run_coactivate(pairs: &[String]) -> anyhow::Result<()> {
+    let pairs: Vec<(String, String)> = pairs
+        .iter()
+        .map(|pair| match pair.split_once('=') {
+            Some((a, b)) if !a.is_empty() && !b.is_empty() => Ok((a.to_string(), b.to_string())),
+            _ => anyhow::bail!("expected FILE=FILE, got '{}'", pair),
+        })
+        .collect::<anyhow::Result<_>>()?;
+    let paths = Paths::new()?;
+    let project_config = PathCanonicalizer::for_current_dir()?
+        .root()
+        .join(PROJECT_CONFIG_FILE);
+    super::tune::apply_co_activations(
+        &project_config,
+        &paths.home_claude.join("attentive.json"),
+        &pairs,
+    )?;
+    for (a, b) in &pairs {
+        println!("Co-activation added: {} <-> {}", a, b);
+    }
+    println!("Saved to {}", project_config.display());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_keyword_rules_roundtrip() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("keyword_rules.json");
+
+        let mut predictor = Predictor::new();
+        predictor.set_strong_keyword("tokenizer", "src/tokens.rs");
+        predictor.set_strong_keyword("bm25", "src/bm25.rs");
+        save_keyword_rules(&path, &predictor).unwrap();
+
+        let loaded = load_keyword_rules(&path);
+        assert_eq!(loaded.strong_keywords().len(), 2);
+        assert_eq!(
+            loaded.strong_keywords().get("bm25"),
+            Some(&"src/bm25.rs".to_string())
+        );
+    }
+
+    #[test]
+    fn test_format_synonyms() {
+        let synonyms: Synonyms = serde_json::from_str(
+            r#"{"auth": {"login": 0.75, "session": 0.5}, "db": {"sqlite": 0.6}}"#,
+        )
+        .unwrap();
+        assert_eq!(
+            format_synonyms(&synonyms),
+            "Synonyms (2)\n  auth  -> login (0.75), session (0.50)\n  db    -> sqlite (0.60)"
+        );
+        assert!(format_synonyms(&Synonyms::new()).starts_with("No synonyms."));
+    }
+
+    #[test]
+    fn test_load_keyword_rules_missing_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let loaded = load_keyword_rules(&temp.path().join("missing.json"));
+        assert!(loaded.strong_keywords().is_empty());
+    }
+
+    #[test]
+    fn test_coactivation_suggestions_skip_configured_pairs() {
+        let mut learner = Learner::new();
+        for _ in 0..6 {
+            learner.observe_turn("router work", &["router.rs".into(), "config.rs".into()]);
+            learner.observe_turn("index work", &["index.rs".into(), "bm25.rs".into()]);
+        }
+        learner.observe_turn("index work", &["index.rs".into()]);
+
+        let suggestions = coactivation_suggestions(&learner, &Config::new(), 5);
+        assert_eq!(suggestions.len(), 2);
+        assert_eq!(
+            (suggestions[0].a.as_str(), suggestions[0].b.as_str()),
+            ("config.rs", "router.rs")
+        );
+        assert_eq!(suggestions[0].jaccard, 1.0);
+        assert_eq!(suggestions[0].together, 6);
+        assert_eq!(suggestions[1].b, "index.rs");
+        assert!(format_coactivation(&suggestions).ends_with(
+            "Accept with: attentive learn coactivate config.rs=router.rs bm25.rs=index.rs"
+        ));
+
+        let mut config = Config::new();
+        config
+            .co_activation
+            .insert("router.rs".to_string(), vec!["config.rs".to_string()]);
+        let suggestions = coactivation_suggestions(&learner, &config, 5);
+        assert_eq!(suggestions.len(), 1);
+        assert_eq!(suggestions[0].a, "bm25.rs");
+    }
+
+    #[test]
+    fn test_format_rules_sorted() {
+        let mut predictor = Predictor::new();
+        predictor.set_strong_keyword("zeta", "z.rs");
+        predictor.set_strong_keyword("alpha", "a.rs");
+        let out = format_rules(&predictor);
+        assert!(out.starts_with("Keyword Rules (2)"));
+        assert!(out.find("alpha").unwrap() < out.find("zeta").unwrap());
+    }
+}
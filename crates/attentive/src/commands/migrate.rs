@@ -0,0 +1,151 @@
+use attentive_core::AttentionState;
+use attentive_learn::{Learner, Oracle};
+use attentive_plugins::PluginRegistry;
+use attentive_telemetry::{Paths, Versioned, migrate_file};
+
+use super::hooks::{load_learner, save_learner};
+use super::ingest::IngestManifest;
+
+/// One line of the migration report
+fn describe(name: &str, to: u32, outcome: &anyhow::Result<Option<u32>>) -> String {
+    match outcome {
+        Ok(Some(from)) => format!("  {}: migrated v{} -> v{}", name, from, to),
+        Ok(None) => format!("  {}: up to date", name),
+        Err(e) => format!("  {}: {}", name, e),
+    }
+}
+
+fn migrate<T: Versioned>(path: &std::path::Path) -> anyhow::Result<Option<u32>> {
+    Ok(migrate_file::<T>(path)?)
+}
+
+/// Upgrade the project's attention and learned state and every plugin's state
+/// file to the current schema versions. Files from a newer attentive are left
+/// untouched and reported as errors.
+pub fn run() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let mut lines = vec!["State Migration".to_string(), "===============".to_string()];
+    let mut failures = 0;
+
+    let project = [
+        (
+            "attn_state.json",
+            AttentionState::VERSION,
+            migrate::<AttentionState>(&paths.attn_state_path()?),
+        ),
+        (
+            "learned_state.json",
+            Learner::VERSION,
+            migrate::<Learner>(&paths.learned_state_path()?),
+        ),
+        (
+            "oracle_state.json",
+            Oracle::VERSION,
+            migrate::<Oracle>(&paths.oracle_state_path()?),
+        ),
+        (
+            "ingest_manifest.json",
+            IngestManifest::VERSION,
+            migrate::<IngestManifest>(&paths.ingest_manifest_path()?),
+        ),
+    ];
+    for (name, version, outcome) in &project {
+        failures += usize::from(outcome.is_err());
+        lines.push(describe(name, *version, outcome));
+    }
+
+    // Files outside the project recorded before paths were scoped
+    let config = super::hooks::load_config(&paths.home_claude);
+    let scope = super::hooks::path_scope(&paths, &config);
+    match super::remap::prune_foreign_state(&paths, &scope) {
+        Ok(pruned) => lines.push(format!("  files outside the project: {} dropped", pruned)),
+        Err(e) => {
+            failures += 1;
+            lines.push(format!("  files outside the project: {}", e));
+        }
+    }
+
+    // Word-file associations the learner would drop in its periodic cleanup
+    let learned_state_path = paths.learned_state_path()?;
+    if let Some(mut learner) = load_learner(&learned_state_path) {
+        let pruned = learner.prune_associations();
+        if pruned > 0
+            && let Err(e) = save_learner(&learned_state_path, &mut learner)
+        {
+            failures += 1;
+            lines.push(format!("  weak word-file associations: {}", e));
+        } else {
+            lines.push(format!("  weak word-file associations: {} dropped", pruned));
+        }
+    }
+
+    // Memories stored before prompts looked for a marker first
+    let db_path = paths.observations_db_path();
+    if db_path.exists() {
+        let marked = attentive_compress::ObservationDb::new(&db_path)
+            .map(|db| db.with_redactor(config.redactor()))
+            .and_then(|db| db.memory_projects())
+            .and_then(|projects| {
+                for project in &projects {
+                    super::remember::mark_memories(&paths, std::path::Path::new(project))?;
+                }
+                Ok(projects.len())
+            });
+        match marked {
+            Ok(count) => lines.push(format!("  projects with memories: {} marked", count)),
+            Err(e) => {
+                failures += 1;
+                lines.push(format!("  projects with memories: {}", e));
+            }
+        }
+    }
+
+    let mut registry = PluginRegistry::new();
+    registry.register(Box::new(attentive_plugins::BurnRatePlugin::new()));
+    registry.register(Box::new(attentive_plugins::GuardrailPlugin::new()));
+    registry.register(Box::new(attentive_plugins::LoopBreakerPlugin::new()));
+    registry.register(Box::new(attentive_plugins::VerifyFirstPlugin::new()));
+    registry.register(Box::new(attentive_plugins::RecallPlugin::new()));
+    registry.register(Box::new(attentive_plugins::XrefPlugin::new()));
+    for (name, outcome) in registry.migrate_states() {
+        failures += usize::from(outcome.is_err());
+        // Every plugin state is at schema version 1 so far
+        lines.push(describe(&format!("{}_state.json", name), 1, &outcome));
+    }
+
+    println!("{}", lines.join("\n"));
+    if failures > 0 {
+        anyhow::bail!("{} state file(s) could not be migrated", failures);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_telemetry::TestEnv;
+
+    #[test]
+    fn test_migrate_wraps_legacy_state_and_keeps_future_versions() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let attn_path = paths.attn_state_path().unwrap();
+        std::fs::create_dir_all(attn_path.parent().unwrap()).unwrap();
+        std::fs::write(
+            &attn_path,
+            r#"{"scores": {"a.rs": 0.9}, "consecutive_turns": {}, "turn_count": 4}"#,
+        )
+        .unwrap();
+
+        run().unwrap();
+        let json = std::fs::read_to_string(&attn_path).unwrap();
+        assert_eq!(attentive_telemetry::stored_version(&json).unwrap(), 1);
+        let state: AttentionState = attentive_telemetry::from_versioned_str(&json).unwrap();
+        assert_eq!(state.turn_count, 4);
+
+        let future = r#"{"version": 99, "data": {}}"#;
+        std::fs::write(&attn_path, future).unwrap();
+        assert!(run().is_err());
+        assert_eq!(std::fs::read_to_string(&attn_path).unwrap(), future);
+    }
+}
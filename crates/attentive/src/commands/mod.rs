@@ -1,12 +1,40 @@
+pub mod active_files;
 pub mod benchmark;
+pub mod branches;
+pub mod compaction;
+pub mod completions;
 pub mod compress;
+pub mod config;
+pub mod content_cache;
+pub mod dedupe;
 pub mod diagnostic;
+pub mod directives;
+pub mod eval;
+pub mod export;
 pub mod graph;
 pub mod history;
+pub mod hook_bench;
 pub mod hooks;
 pub mod ingest;
 pub mod init;
+pub mod learn;
+pub mod migrate;
 pub mod plugins;
+pub mod preview;
+pub mod profile;
+pub mod rebuild;
+pub mod remap;
+pub mod remember;
+pub mod repair;
+pub mod repl;
 pub mod report;
+pub mod search;
+pub mod serve;
+pub mod stash;
 pub mod status;
+pub mod suite;
+pub mod sync;
+pub mod transcript;
+pub mod tune;
 pub mod version;
+pub mod xref;
@@ -1,4 +1,7 @@
-use attentive_plugins::{BurnRatePlugin, LoopBreakerPlugin, Plugin, VerifyFirstPlugin};
+use attentive_plugins::{
+    BurnRatePlugin, GuardrailPlugin, LoopBreakerPlugin, Plugin, RecallPlugin, VerifyFirstPlugin,
+    XrefPlugin,
+};
 use std::path::Path;
 
 #[cfg(test)]
@@ -46,8 +49,11 @@ fn set_plugin_enabled(config_path: &Path, name: &str, enabled: bool) -> anyhow::
 pub fn run_list() -> anyhow::Result<()> {
     let plugins: Vec<Box<dyn Plugin>> = vec![
         Box::new(BurnRatePlugin::new()),
+        Box::new(GuardrailPlugin::new()),
         Box::new(LoopBreakerPlugin::new()),
         Box::new(VerifyFirstPlugin::new()),
+        Box::new(RecallPlugin::new()),
+        Box::new(XrefPlugin::new()),
     ];
 
     println!("Registered Plugins");
@@ -91,13 +97,12 @@ pub fn run() -> anyhow::Result<()> {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use serial_test::serial;
+    use attentive_telemetry::TestEnv;
 
     #[test]
-    #[serial]
     fn test_plugins_list() {
-        let paths = attentive_telemetry::Paths::new().unwrap();
-        std::fs::create_dir_all(paths.home_claude.join("plugins")).unwrap();
+        let env = TestEnv::new();
+        std::fs::create_dir_all(env.paths().home_claude.join("plugins")).unwrap();
 
         let result = run();
         assert!(result.is_ok());
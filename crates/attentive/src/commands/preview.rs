@@ -0,0 +1,222 @@
+use attentive_telemetry::{Paths, estimate_tokens};
+
+use super::hooks::{
+    PromptPipeline, apply_model_profile, load_attention_state, load_config, load_last_injected,
+    load_learner, run_prompt_pipeline,
+};
+
+/// Run the prompt-submit pipeline without persisting any state and print what
+/// would be injected.
+pub fn run(prompt: &str) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+
+    // Plugins persist their own state on post-hooks; keep them read-only too
+    attentive_plugins::base::set_read_only(true);
+
+    let mut state = load_attention_state(&paths)?;
+    let mut config = load_config(&paths.home_claude);
+    let (prompt, directives) =
+        super::directives::apply(&paths, prompt, &mut config, &mut state, false);
+    apply_model_profile(&paths, &mut config, None);
+    config.apply_task_profile(
+        attentive_learn::Oracle::new()
+            .classify_task(&prompt)
+            .as_str(),
+    );
+    let learner = load_learner(&paths.learned_state_path()?)
+        .map(|l| l.with_half_life(config.learner_half_life));
+    let previous_injected = config.delta_context.then(|| load_last_injected(&paths));
+    let result = run_prompt_pipeline(
+        &paths,
+        prompt,
+        &mut state,
+        config,
+        learner.as_ref(),
+        previous_injected.as_ref(),
+    )?;
+
+    match result {
+        Some(mut pipeline) => {
+            if !directives.is_empty() {
+                pipeline.context = format!("{}\n\n{}", directives, pipeline.context);
+            }
+            println!("{}", format_preview(&pipeline))
+        }
+        None => println!("Prompt blocked by a plugin pre-hook; nothing would be injected."),
+    }
+    Ok(())
+}
+
+fn format_preview(pipeline: &PromptPipeline) -> String {
+    let mut lines = vec!["# Context Preview (dry run)".to_string(), String::new()];
+
+    lines.push(format!("HOT ({}):", pipeline.hot_files.len()));
+    for f in &pipeline.hot_files {
+        lines.push(format!("  {}", f));
+    }
+    lines.push(format!("WARM ({}):", pipeline.warm_files.len()));
+    for f in &pipeline.warm_files {
+        lines.push(format!("  {}", f));
+    }
+    lines.push(format!("COLD (indexed): {}", pipeline.cold_entries.len()));
+    if !pipeline.likely_next.is_empty() {
+        lines.push(format!("Likely next: {}", pipeline.likely_next.join(", ")));
+    }
+    for conflict in &pipeline.conflicts {
+        lines.push(format!(
+            "Config conflict: {} is {}; to settle it, {}",
+            conflict.path, conflict, conflict.resolution
+        ));
+    }
+
+    lines.push(String::new());
+    lines.push(format!(
+        "Estimated tokens: {} ({} chars)",
+        estimate_tokens(&pipeline.prefix) + estimate_tokens(&pipeline.context),
+        pipeline.prefix.len() + pipeline.context.len()
+    ));
+    if !pipeline.sources.is_empty() {
+        let sources: Vec<String> = pipeline
+            .sources
+            .iter()
+            .map(|(source, tokens)| format!("{} {}", source, tokens))
+            .collect();
+        lines.push(format!("By source: {}", sources.join(", ")));
+    }
+    let phases: Vec<String> = pipeline
+        .metrics
+        .phases
+        .iter()
+        .map(|p| format!("{} {}µs", p.phase, p.micros))
+        .collect();
+    lines.push(format!(
+        "Routing: {}µs over {} scores ({})",
+        pipeline.metrics.total().as_micros(),
+        pipeline.metrics.score_count,
+        phases.join(", ")
+    ));
+    if pipeline.metrics.budget_exceeded() {
+        lines.push(format!(
+            "Latency budget exceeded, skipped: {}",
+            pipeline.metrics.skipped.join(", ")
+        ));
+    }
+    if pipeline.overflow > 0 {
+        lines.push(format!(
+            "Context budget overflow: {} section(s) shortened or dropped",
+            pipeline.overflow
+        ));
+    }
+    let trimmed: Vec<String> = pipeline
+        .plugin_budgets
+        .iter()
+        .filter(|(_, usage)| usage.truncated())
+        .map(|(plugin, usage)| {
+            format!(
+                "{} {} -> {} of {} tokens",
+                plugin, usage.requested, usage.used, usage.budget
+            )
+        })
+        .collect();
+    if !trimmed.is_empty() {
+        lines.push(format!("Plugin budgets trimmed: {}", trimmed.join(", ")));
+    }
+    if pipeline.budget_scale < 1.0 {
+        lines.push(format!(
+            "Context budget throttled to {:.0}% (BurnRate)",
+            pipeline.budget_scale * 100.0
+        ));
+    }
+    lines.push(String::new());
+    if !pipeline.prefix.is_empty() {
+        lines.push("--- prefix (stable) ---".to_string());
+        lines.push(pipeline.prefix.clone());
+    }
+    lines.push("--- context ---".to_string());
+    lines.push(pipeline.context.clone());
+
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_preview_lists_tiers() {
+        let pipeline = PromptPipeline {
+            prefix: "## VerifyFirst Policy".to_string(),
+            context: "[HOT] src/main.rs\nfn main() {}".to_string(),
+            hot_files: vec!["src/main.rs".to_string()],
+            warm_files: vec!["src/lib.rs".to_string(), "README.md".to_string()],
+            cold_entries: vec![],
+            metrics: Default::default(),
+            budget_scale: 1.0,
+            injected: Default::default(),
+            sources: [
+                ("hot".to_string(), 6),
+                ("plugin:verifyfirst".to_string(), 40),
+            ]
+            .into(),
+            overflow: 2,
+            redactions: 0,
+            deduped_tokens: 0,
+            content_cache: Default::default(),
+            likely_next: vec!["src/config.rs".to_string()],
+            editor_files: vec![],
+            plugin_budgets: [(
+                "verifyfirst".to_string(),
+                attentive_telemetry::PluginBudgetUsage {
+                    budget: 40,
+                    requested: 95,
+                    used: 38,
+                },
+            )]
+            .into(),
+            plugin_messages: vec![],
+            conflicts: vec![],
+        };
+        let out = format_preview(&pipeline);
+        assert!(out.contains("HOT (1):\n  src/main.rs"));
+        assert!(out.contains("Plugin budgets trimmed: verifyfirst 95 -> 38 of 40 tokens"));
+        assert!(out.contains("By source: hot 6, plugin:verifyfirst 40"));
+        assert!(out.contains("Context budget overflow: 2 section(s)"));
+        assert!(out.contains("COLD (indexed): 0\nLikely next: src/config.rs"));
+        assert!(out.contains("WARM (2):\n  src/lib.rs\n  README.md"));
+        assert!(out.contains("Estimated tokens:"));
+        assert!(out.contains("--- prefix (stable) ---\n## VerifyFirst Policy\n--- context ---"));
+        assert!(out.ends_with("[HOT] src/main.rs\nfn main() {}"));
+    }
+
+    #[test]
+    fn test_format_preview_empty() {
+        let pipeline = PromptPipeline {
+            prefix: String::new(),
+            context: String::new(),
+            hot_files: vec![],
+            warm_files: vec![],
+            cold_entries: vec![],
+            metrics: Default::default(),
+            budget_scale: 1.0,
+            injected: Default::default(),
+            sources: Default::default(),
+            overflow: 0,
+            redactions: 0,
+            deduped_tokens: 0,
+            content_cache: Default::default(),
+            likely_next: vec![],
+            editor_files: vec![],
+            plugin_budgets: Default::default(),
+            plugin_messages: vec![],
+            conflicts: vec![],
+        };
+        let out = format_preview(&pipeline);
+        assert!(out.contains("HOT (0):"));
+        assert!(!out.contains("Plugin budgets"));
+        assert!(!out.contains("By source"));
+        assert!(!out.contains("overflow"));
+        assert!(!out.contains("Likely next"));
+        assert!(out.contains("Estimated tokens: 0 (0 chars)"));
+        assert!(!out.contains("prefix (stable)"));
+    }
+}
@@ -0,0 +1,138 @@
+//! `profile`: list config profiles and pick the one in use
+//!
+//! The selection is stored as the `profile` key of `~/.claude/attentive.json`;
+//! a project `.attentive.json` that sets `profile` still takes precedence.
+
+use attentive_core::Config;
+use attentive_telemetry::Paths;
+use std::path::Path;
+
+/// Profiles with their settings, the selected one marked with `*`
+fn format_profiles(config: &Config) -> String {
+    let mut lines = vec![format!("Profiles ({})", config.profiles.len())];
+    let width = config.profiles.keys().map(|n| n.len()).max().unwrap_or(0);
+    for (name, profile) in &config.profiles {
+        let marker = if config.profile.as_ref() == Some(name) {
+            "*"
+        } else {
+            " "
+        };
+        lines.push(format!(
+            "{} {:width$}  {}",
+            marker,
+            name,
+            profile.describe(),
+            width = width
+        ));
+    }
+    if !config.task_profiles.is_empty() {
+        lines.push(String::new());
+        lines.push("By task type:".to_string());
+        for (task_type, name) in &config.task_profiles {
+            lines.push(format!("  {} -> {}", task_type, name));
+        }
+    }
+    if !config.model_profiles.is_empty() {
+        lines.push(String::new());
+        lines.push("By model:".to_string());
+        for (fragment, name) in &config.model_profiles {
+            lines.push(format!("  *{}* -> {}", fragment, name));
+        }
+    }
+    lines.join("\n")
+}
+
+/// Set (or with `None`, remove) the `profile` key of the config at `path`,
+/// keeping its other keys
+fn set_profile(path: &Path, name: Option<&str>) -> anyhow::Result<()> {
+    let mut map = if path.exists() {
+        match serde_json::from_str(&std::fs::read_to_string(path)?)? {
+            serde_json::Value::Object(map) => map,
+            _ => anyhow::bail!("{} is not a JSON object", path.display()),
+        }
+    } else {
+        serde_json::Map::new()
+    };
+    match name {
+        Some(name) => {
+            map.insert("profile".to_string(), name.into());
+        }
+        None if map.remove("profile").is_none() => return Ok(()),
+        None => {}
+    }
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let json = serde_json::to_string_pretty(&serde_json::Value::Object(map))?;
+    attentive_telemetry::atomic_write(path, format!("{}\n", json).as_bytes())?;
+    Ok(())
+}
+
+pub fn run_list() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    println!(
+        "{}",
+        format_profiles(&super::hooks::load_config(&paths.home_claude))
+    );
+    Ok(())
+}
+
+pub fn run_use(name: &str) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config = super::hooks::load_config(&paths.home_claude);
+    let Some(profile) = config.profiles.get(name) else {
+        let known: Vec<&str> = config.profiles.keys().map(|n| n.as_str()).collect();
+        anyhow::bail!("no profile named '{}' (known: {})", name, known.join(", "));
+    };
+    set_profile(&paths.home_claude.join("attentive.json"), Some(name))?;
+    println!("Using profile '{}': {}", name, profile.describe());
+    Ok(())
+}
+
+pub fn run_clear() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    set_profile(&paths.home_claude.join("attentive.json"), None)?;
+    println!("No profile selected; attentive.json settings apply as written");
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_set_profile_keeps_other_keys() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("attentive.json");
+        std::fs::write(&path, r#"{"max_hot_files": 4}"#).unwrap();
+
+        set_profile(&path, Some("economy")).unwrap();
+        let config = Config::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config.profile.as_deref(), Some("economy"));
+        // economy sets max_hot_files itself
+        assert_eq!(config.max_hot_files, 1);
+
+        set_profile(&path, None).unwrap();
+        let config = Config::from_json(&std::fs::read_to_string(&path).unwrap()).unwrap();
+        assert_eq!(config.profile, None);
+        assert_eq!(config.max_hot_files, 4);
+
+        let missing = dir.path().join("none/attentive.json");
+        set_profile(&missing, None).unwrap();
+        assert!(!missing.exists());
+    }
+
+    #[test]
+    fn test_format_profiles_marks_selected() {
+        let config = Config::from_json(
+            r#"{"profile": "focused", "task_profiles": {"exploration": "exploratory"}}"#,
+        )
+        .unwrap();
+        let text = format_profiles(&config);
+        assert!(text.contains("* focused       hot_threshold=0.7"));
+        assert!(text.contains("  economy       hot_threshold=0.9"));
+        assert!(text.contains("xref=off"));
+        assert!(text.contains("  exploration -> exploratory"));
+        assert!(text.contains("  *haiku* -> compact"));
+    }
+}
@@ -0,0 +1,228 @@
+//! Regenerate derived state from the project's transcripts and files
+//!
+//! The learner, keyword rules, synonyms and search index are all derived:
+//! from session transcripts and the repo's contents. After a schema change, a
+//! corrupt file or improved extraction logic they can be rebuilt from scratch
+//! rather than patched. Stored observations include user memories that exist
+//! nowhere else, so only their full-text index is rebuilt. Attention state and
+//! turn history aren't derived and are left alone.
+
+use crate::error::ErrorKind;
+use attentive_index::SearchIndex;
+use attentive_telemetry::{PathCanonicalizer, Paths};
+use indicatif::{ProgressBar, ProgressStyle};
+use std::path::{Path, PathBuf};
+
+/// What a rebuild wrote, or would write in a dry run
+#[derive(Debug, Default, PartialEq)]
+struct RebuildReport {
+    /// Session files found for the project
+    session_files: usize,
+    /// Session files holding at least one prompt-file pair
+    sessions: usize,
+    pairs: usize,
+    associations: usize,
+    keyword_rules: usize,
+    synonyms: usize,
+    /// Repo files looked at for the search index
+    repo_files: usize,
+    indexed: usize,
+    /// Stored observations, if there's an observation database
+    observations: Option<usize>,
+}
+
+/// Bar counting `len` items, labelled `prefix`; hidden when stderr isn't a
+/// terminal
+fn progress_bar(len: usize, prefix: &'static str) -> ProgressBar {
+    let bar = ProgressBar::new(len as u64).with_prefix(prefix);
+    if let Ok(style) =
+        ProgressStyle::with_template("{prefix:>12} [{bar:30}] {pos}/{len} {wide_msg}")
+    {
+        bar.set_style(style.progress_chars("=> "));
+    }
+    bar
+}
+
+/// The project's session files, oldest first, so the last one read is the
+/// session the learner warm-starts from
+fn session_files_by_age(project_dir: &Path) -> Vec<PathBuf> {
+    let mut files: Vec<(std::time::SystemTime, PathBuf)> =
+        super::ingest::discover_session_files(project_dir)
+            .into_iter()
+            .map(|path| {
+                let modified = std::fs::metadata(&path)
+                    .and_then(|m| m.modified())
+                    .unwrap_or(std::time::UNIX_EPOCH);
+                (modified, path)
+            })
+            .collect();
+    files.sort();
+    files.into_iter().map(|(_, path)| path).collect()
+}
+
+/// Rebuild every piece of derived state for the repo at `root`; with
+/// `dry_run`, everything is computed but nothing written
+fn rebuild(paths: &Paths, root: &Path, dry_run: bool) -> anyhow::Result<RebuildReport> {
+    let project_dir = paths.project_dir()?;
+    let session_files = session_files_by_age(&project_dir);
+    let mut report = RebuildReport {
+        session_files: session_files.len(),
+        ..Default::default()
+    };
+
+    let bar = progress_bar(session_files.len(), "transcripts");
+    let mut relearned = super::ingest::relearn(paths, &session_files, |path| {
+        bar.set_message(
+            path.file_name()
+                .map(|n| n.to_string_lossy().to_string())
+                .unwrap_or_default(),
+        );
+        bar.inc(1);
+    })?;
+    bar.finish_and_clear();
+    report.sessions = relearned.sessions;
+    report.pairs = relearned.pairs;
+    report.associations = relearned.learner.total_associations();
+    report.keyword_rules = relearned.predictor.strong_keywords().len();
+    report.synonyms = relearned.synonyms.len();
+    // Learned state may come from transcripts ingested by path, so it's only
+    // replaced when there are sessions to replace it with
+    if !dry_run && !session_files.is_empty() {
+        relearned.save(paths)?;
+    }
+
+    let config = super::hooks::load_config(&paths.home_claude);
+    let files = attentive_repo::repo_files(root, &config.exclude_patterns());
+    report.repo_files = files.len();
+    let bar = progress_bar(files.len(), "repo files");
+    let (_, documents) = super::init::collect_documents(root, files, |rel| {
+        bar.set_message(rel.to_string());
+        bar.inc(1);
+    });
+    bar.finish_and_clear();
+    report.indexed = documents.len();
+    if !dry_run {
+        std::fs::create_dir_all(&project_dir)?;
+        let index_path = paths.search_index_path()?;
+        let mut index = match SearchIndex::new(&index_path) {
+            Ok(index) => index,
+            Err(e) => {
+                tracing::warn!("Recreating unreadable search index: {}", e);
+                std::fs::remove_file(&index_path)?;
+                SearchIndex::new(&index_path).map_err(|e| ErrorKind::Index.wrap(e))?
+            }
+        };
+        index
+            .build(documents)
+            .map_err(|e| ErrorKind::Index.wrap(e))?;
+    }
+
+    let db_path = paths.observations_db_path();
+    if db_path.exists() {
+        let db = attentive_compress::ObservationDb::new(&db_path)?.with_redactor(config.redactor());
+        report.observations = Some(db.count()?);
+        if !dry_run {
+            db.rebuild_search_index()?;
+        }
+    }
+    Ok(report)
+}
+
+fn format_report(report: &RebuildReport, dry_run: bool) -> String {
+    let mut lines = vec![if dry_run {
+        "Rebuild (dry run, nothing written)".to_string()
+    } else {
+        "Rebuild".to_string()
+    }];
+    lines.push(format!(
+        "  transcripts: {} pairs from {} of {} sessions",
+        report.pairs, report.sessions, report.session_files
+    ));
+    lines.push(format!(
+        "  learner: {} word→file associations",
+        report.associations
+    ));
+    lines.push(format!("  keyword rules: {}", report.keyword_rules));
+    lines.push(format!("  synonyms: {} words", report.synonyms));
+    lines.push(format!(
+        "  search index: {} of {} repo files",
+        report.indexed, report.repo_files
+    ));
+    match report.observations {
+        Some(count) => lines.push(format!("  observation search: {} observations", count)),
+        None => lines.push("  observation search: no observations stored".to_string()),
+    }
+    lines.join("\n")
+}
+
+/// Regenerate the learner, keyword rules, synonyms, search index and
+/// observation search from scratch
+pub fn run(dry_run: bool) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let root = PathCanonicalizer::for_current_dir()?.root().to_path_buf();
+    let report = rebuild(&paths, &root, dry_run)?;
+    println!("{}", format_report(&report, dry_run));
+    if report.session_files == 0 {
+        println!(
+            "\nNo session files found in {}; learned state left unchanged",
+            paths.project_dir()?.display()
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_telemetry::TestEnv;
+
+    fn session(prompt: &str, file: &str) -> String {
+        [
+            serde_json::json!({"type": "user", "message": {"content": prompt}}),
+            serde_json::json!({"type": "assistant", "message": {"content": [
+                {"type": "tool_use", "name": "Read", "input": {"file_path": file}}
+            ]}}),
+        ]
+        .map(|l| format!("{}\n", l))
+        .concat()
+    }
+
+    #[test]
+    fn test_rebuild_replaces_learned_state() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let project_dir = paths.project_dir().unwrap();
+        std::fs::create_dir_all(&project_dir).unwrap();
+        std::fs::write(
+            project_dir.join("one.jsonl"),
+            session("fix the router", "src/router.rs"),
+        )
+        .unwrap();
+        let repo = tempfile::TempDir::new().unwrap();
+        std::fs::write(repo.path().join("lib.rs"), "pub fn route() {}\n").unwrap();
+        std::fs::write(repo.path().join("notes.md"), "# Notes\n").unwrap();
+
+        // Learned before, from a session that is gone
+        let learned_path = paths.learned_state_path().unwrap();
+        let mut stale = attentive_learn::Learner::new();
+        stale.observe_turn("old prompt", &["src/old.rs".to_string()]);
+        super::super::hooks::save_learner(&learned_path, &mut stale).unwrap();
+        let before = std::fs::read_to_string(&learned_path).unwrap();
+
+        let report = rebuild(&paths, repo.path(), true).unwrap();
+        assert_eq!((report.session_files, report.sessions), (1, 1));
+        assert_eq!(report.indexed, 2);
+        assert_eq!(std::fs::read_to_string(&learned_path).unwrap(), before);
+        assert!(!paths.search_index_path().unwrap().exists());
+
+        assert_eq!(rebuild(&paths, repo.path(), false).unwrap(), report);
+        let learner = super::super::hooks::load_learner(&learned_path).unwrap();
+        assert_eq!(
+            learner.top_files_by_frequency(5),
+            [("src/router.rs".to_string(), 1)]
+        );
+        let index = SearchIndex::new(paths.search_index_path().unwrap()).unwrap();
+        assert_eq!(index.query_lexical("route", 5).len(), 1);
+        assert!(format_report(&report, false).contains("1 pairs from 1 of 1 sessions"));
+    }
+}
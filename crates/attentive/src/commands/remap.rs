@@ -0,0 +1,319 @@
+use super::hooks::{PROJECT_CONFIG_FILE, load_learner, save_learner};
+use attentive_core::{AttentionState, PathScope};
+use attentive_repo::{RENAME_LOOKBACK_COMMITS, Rename, detect_git_renames};
+use attentive_telemetry::{PathCanonicalizer, Paths, read_versioned, remap_path, write_versioned};
+use std::path::Path;
+
+/// Rewrite pinned/demoted entries and co-activation keys/values in a config
+fn remap_config_value(config: &mut serde_json::Value, old: &str, new: &str) -> usize {
+    let mut moved = 0;
+    for key in ["pinned_files", "demoted_files"] {
+        if let Some(list) = config.get_mut(key).and_then(|v| v.as_array_mut()) {
+            for entry in list.iter_mut() {
+                if let Some(target) = entry.as_str().and_then(|p| remap_path(p, old, new)) {
+                    *entry = serde_json::Value::String(target);
+                    moved += 1;
+                }
+            }
+        }
+    }
+
+    if let Some(map) = config
+        .get_mut("co_activation")
+        .and_then(|v| v.as_object_mut())
+    {
+        let keys: Vec<String> = map.keys().cloned().collect();
+        for key in keys {
+            if let Some(target) = remap_path(&key, old, new)
+                && let Some(value) = map.remove(&key)
+            {
+                map.insert(target, value);
+                moved += 1;
+            }
+        }
+        for related in map.values_mut() {
+            if let Some(list) = related.as_array_mut() {
+                for entry in list.iter_mut() {
+                    if let Some(target) = entry.as_str().and_then(|p| remap_path(p, old, new)) {
+                        *entry = serde_json::Value::String(target);
+                        moved += 1;
+                    }
+                }
+            } else if let Some(weighted) = related.as_object_mut() {
+                // {file: strength} form
+                let keys: Vec<String> = weighted.keys().cloned().collect();
+                for key in keys {
+                    if let Some(target) = remap_path(&key, old, new)
+                        && let Some(strength) = weighted.remove(&key)
+                    {
+                        weighted.insert(target, strength);
+                        moved += 1;
+                    }
+                }
+            }
+        }
+    }
+    moved
+}
+
+fn read_json<T: serde::de::DeserializeOwned>(path: &Path) -> Option<T> {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|c| serde_json::from_str(&c).ok())
+}
+
+fn write_json<T: serde::Serialize>(path: &Path, value: &T) -> anyhow::Result<()> {
+    let json = serde_json::to_string_pretty(value)?;
+    attentive_telemetry::atomic_write(path, json.as_bytes())?;
+    Ok(())
+}
+
+/// Transfer learner stats and attention scores across renames. Both are
+/// brought into canonical repo-relative form first, so a rename only moves
+/// the file it names. Returns the number of entries moved.
+pub(crate) fn apply_renames(
+    paths: &Paths,
+    canonicalizer: &PathCanonicalizer,
+    renames: &[Rename],
+) -> anyhow::Result<usize> {
+    if renames.is_empty() {
+        return Ok(0);
+    }
+
+    let learned_state_path = paths.learned_state_path()?;
+    let attn_state_path = paths.attn_state_path()?;
+
+    let mut learner = load_learner(&learned_state_path);
+    let mut state: Option<AttentionState> = read_versioned(&attn_state_path).ok();
+    let learner_canonicalized = learner
+        .as_mut()
+        .map_or(0, |l| l.canonicalize_files(canonicalizer));
+    let state_canonicalized = state
+        .as_mut()
+        .map_or(0, |s| s.canonicalize_files(canonicalizer));
+
+    let (mut learner_moved, mut state_moved) = (0, 0);
+    for rename in renames {
+        let (from, to) = (
+            canonicalizer.canonicalize(&rename.from),
+            canonicalizer.canonicalize(&rename.to),
+        );
+        if let Some(l) = learner.as_mut() {
+            learner_moved += l.remap_file(&from, &to);
+        }
+        if let Some(s) = state.as_mut() {
+            state_moved += s.remap_file(&from, &to);
+        }
+    }
+
+    if let Some(l) = &mut learner
+        && learner_moved + learner_canonicalized > 0
+    {
+        save_learner(&learned_state_path, l)?;
+    }
+    if let Some(s) = &state
+        && state_moved + state_canonicalized > 0
+    {
+        write_versioned(&attn_state_path, s)?;
+    }
+
+    Ok(learner_moved + state_moved)
+}
+
+/// Rewrite pinned, demoted and co-activation entries in the repo's
+/// .attentive.json. The global attentive.json is shared by every project, so
+/// one repo's renames are never applied to it.
+fn apply_project_config_renames(
+    canonicalizer: &PathCanonicalizer,
+    renames: &[Rename],
+) -> anyhow::Result<usize> {
+    let config_path = canonicalizer.root().join(PROJECT_CONFIG_FILE);
+    let Some(mut config) = read_json::<serde_json::Value>(&config_path) else {
+        return Ok(0);
+    };
+    let moved: usize = renames
+        .iter()
+        .map(|rename| {
+            remap_config_value(
+                &mut config,
+                &canonicalizer.canonicalize(&rename.from),
+                &canonicalizer.canonicalize(&rename.to),
+            )
+        })
+        .sum();
+    if moved > 0 {
+        write_json(&config_path, &config)?;
+    }
+    Ok(moved)
+}
+
+/// Detect renames from recent git history and apply them. The history is
+/// only scanned when HEAD has moved since the last scan.
+pub(crate) fn apply_git_renames(
+    paths: &Paths,
+    canonicalizer: &PathCanonicalizer,
+) -> anyhow::Result<usize> {
+    let Some(head) = attentive_repo::git_head(canonicalizer.root()).and_then(|h| h.commit) else {
+        return Ok(0);
+    };
+    let scan_path = paths.rename_scan_path()?;
+    if std::fs::read_to_string(&scan_path).is_ok_and(|last| last.trim() == head) {
+        return Ok(0);
+    }
+    let moved = apply_renames(
+        paths,
+        canonicalizer,
+        &detect_git_renames(canonicalizer.root(), RENAME_LOOKBACK_COMMITS),
+    )?;
+    if let Some(parent) = scan_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    attentive_telemetry::atomic_write(&scan_path, head.as_bytes())?;
+    Ok(moved)
+}
+
+/// Rewrite learner and attention state keys into canonical repo-relative form
+pub(crate) fn canonicalize_learned_state(
+    paths: &Paths,
+    canonicalizer: &PathCanonicalizer,
+) -> anyhow::Result<usize> {
+    let learned_state_path = paths.learned_state_path()?;
+    let attn_state_path = paths.attn_state_path()?;
+    let mut changed = 0;
+
+    if let Some(mut learner) = load_learner(&learned_state_path) {
+        let moved = learner.canonicalize_files(canonicalizer);
+        if moved > 0 {
+            save_learner(&learned_state_path, &mut learner)?;
+        }
+        changed += moved;
+    }
+    if let Ok(mut state) = read_versioned::<AttentionState>(&attn_state_path) {
+        let moved = state.canonicalize_files(canonicalizer);
+        if moved > 0 {
+            write_versioned(&attn_state_path, &state)?;
+        }
+        changed += moved;
+    }
+    Ok(changed)
+}
+
+/// Drop learner and attention state for files `scope` doesn't allow, e.g.
+/// recorded before out-of-project paths were filtered
+pub(crate) fn prune_foreign_state(paths: &Paths, scope: &PathScope) -> anyhow::Result<usize> {
+    let learned_state_path = paths.learned_state_path()?;
+    let attn_state_path = paths.attn_state_path()?;
+    let mut pruned = 0;
+
+    if let Some(mut learner) = load_learner(&learned_state_path) {
+        let removed = learner.prune_files(|f| scope.allows(f));
+        if removed > 0 {
+            save_learner(&learned_state_path, &mut learner)?;
+        }
+        pruned += removed;
+    }
+    if let Ok(mut state) = read_versioned::<AttentionState>(&attn_state_path) {
+        let removed = state.prune_files(|f| scope.allows(f));
+        if removed > 0 {
+            write_versioned(&attn_state_path, &state)?;
+        }
+        pruned += removed;
+    }
+    Ok(pruned)
+}
+
+pub fn run(from: Option<&str>, to: Option<&str>) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let renames = match (from, to) {
+        (Some(from), Some(to)) => vec![Rename {
+            from: from.to_string(),
+            to: to.to_string(),
+        }],
+        _ => detect_git_renames(canonicalizer.root(), RENAME_LOOKBACK_COMMITS),
+    };
+
+    if renames.is_empty() {
+        println!("No renames to apply");
+        return Ok(());
+    }
+    for rename in &renames {
+        println!("  {} -> {}", rename.from, rename.to);
+    }
+    let moved = apply_renames(&paths, &canonicalizer, &renames)?;
+    let config_moved = apply_project_config_renames(&canonicalizer, &renames)?;
+    println!(
+        "Remapped {} entries across {} renames",
+        moved + config_moved,
+        renames.len()
+    );
+    if config_moved > 0 {
+        println!(
+            "Updated {} entries in {}",
+            config_moved, PROJECT_CONFIG_FILE
+        );
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_remap_config_value() {
+        let mut config = serde_json::json!({
+            "pinned_files": ["src/old.rs", "docs/**"],
+            "demoted_files": ["/repo/src/old.rs"],
+            "co_activation": {
+                "src/old.rs": ["src/lib.rs"],
+                "src/main.rs": ["src/old.rs"],
+                "src/lib.rs": {"src/old.rs": 0.5}
+            },
+            "latency_budget_ms": 20
+        });
+
+        let moved = remap_config_value(&mut config, "src/old.rs", "src/new.rs");
+        assert_eq!(moved, 4);
+        assert_eq!(config["pinned_files"][0], "src/new.rs");
+        assert_eq!(config["pinned_files"][1], "docs/**");
+        // Only the canonical key is renamed
+        assert_eq!(config["demoted_files"][0], "/repo/src/old.rs");
+        assert!(config["co_activation"].get("src/new.rs").is_some());
+        assert_eq!(config["co_activation"]["src/main.rs"][0], "src/new.rs");
+        assert_eq!(config["co_activation"]["src/lib.rs"]["src/new.rs"], 0.5);
+        assert_eq!(config["latency_budget_ms"], 20);
+    }
+
+    #[test]
+    fn test_apply_renames_moves_only_the_named_file() {
+        let env = attentive_telemetry::TestEnv::new();
+        let paths = env.paths();
+        std::fs::create_dir_all(paths.project_dir().unwrap()).unwrap();
+        let canonicalizer = PathCanonicalizer::new("/repo");
+        let mut state = AttentionState::new();
+        state.scores.insert("/repo/README.md".to_string(), 0.9);
+        state.scores.insert("crates/foo/README.md".to_string(), 0.4);
+        write_versioned(&paths.attn_state_path().unwrap(), &state).unwrap();
+        let global = paths.home_claude.join("attentive.json");
+        let config = r#"{"pinned_files": ["README.md"]}"#;
+        std::fs::write(&global, config).unwrap();
+
+        let renames = [Rename {
+            from: "README.md".to_string(),
+            to: "docs/README.md".to_string(),
+        }];
+        assert_eq!(apply_renames(&paths, &canonicalizer, &renames).unwrap(), 1);
+        let state: AttentionState = read_versioned(&paths.attn_state_path().unwrap()).unwrap();
+        assert_eq!(state.scores["docs/README.md"], 0.9);
+        assert_eq!(state.scores["crates/foo/README.md"], 0.4);
+        assert!(!state.scores.contains_key("README.md"));
+        assert_eq!(std::fs::read_to_string(&global).unwrap(), config);
+    }
+
+    #[test]
+    fn test_remap_config_value_no_match() {
+        let mut config = serde_json::json!({"pinned_files": ["a.rs"]});
+        assert_eq!(remap_config_value(&mut config, "b.rs", "c.rs"), 0);
+    }
+}
@@ -0,0 +1,42 @@
+use attentive_compress::{CompressedObservation, ObservationDb};
+use attentive_telemetry::{PathCanonicalizer, Paths};
+
+/// Store a user-authored note as a project memory, optionally tied to files
+pub fn run(note: &str, files: &[String]) -> anyhow::Result<()> {
+    let note = note.trim();
+    if note.is_empty() {
+        anyhow::bail!("Memory note is empty");
+    }
+
+    let paths = Paths::new()?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let project = canonicalizer.root().to_string_lossy().to_string();
+    let files = canonicalizer.canonicalize_all(files);
+
+    let redactor = super::hooks::load_config(&paths.home_claude).redactor();
+    let db = ObservationDb::new(&paths.observations_db_path())?.with_redactor(redactor);
+    let memory =
+        CompressedObservation::memory(&project, note, files).with_file_hashes(canonicalizer.root());
+    let redactions = db.insert(&memory)?;
+    mark_memories(&paths, canonicalizer.root())?;
+
+    println!("Remembered {}", memory.id);
+    if redactions > 0 {
+        println!("  redacted {} secret(s)", redactions);
+    }
+    if !memory.related_files.is_empty() {
+        println!("  files: {}", memory.related_files.join(", "));
+    }
+    Ok(())
+}
+
+/// Note that the project rooted at `root` has memories for the prompt hook
+/// to look up
+pub(crate) fn mark_memories(paths: &Paths, root: &std::path::Path) -> anyhow::Result<()> {
+    let marker = paths.memory_marker_path(root);
+    if let Some(dir) = marker.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    std::fs::write(marker, "")?;
+    Ok(())
+}
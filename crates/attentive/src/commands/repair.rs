@@ -0,0 +1,68 @@
+//! `repair`: drop corrupt lines from the telemetry logs
+//!
+//! An interrupted write can leave a torn line in `turns.jsonl`. Readers skip
+//! such lines already; this rewrites the file without them so the warning
+//! goes away, keeping the dropped lines in `<file>.corrupt`.
+
+use attentive_telemetry::{JsonlHealth, Paths, corrupt_path, repair_jsonl};
+use std::path::Path;
+
+/// One line of the repair report
+fn describe(name: &str, path: &Path, health: &std::io::Result<JsonlHealth>) -> String {
+    match health {
+        Ok(JsonlHealth {
+            valid: 0,
+            corrupt: 0,
+        }) => format!("  {}: empty", name),
+        Ok(JsonlHealth { valid, corrupt: 0 }) => format!("  {}: ok ({} records)", name, valid),
+        Ok(JsonlHealth { valid, corrupt }) => format!(
+            "  {}: dropped {} corrupt line(s), kept {} (saved to {})",
+            name,
+            corrupt,
+            valid,
+            corrupt_path(path).display()
+        ),
+        Err(e) => format!("  {}: {}", name, e),
+    }
+}
+
+pub fn run() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let mut lines = vec![
+        "Telemetry Repair".to_string(),
+        "================".to_string(),
+    ];
+    let mut failures = 0;
+    for (name, path) in [
+        ("turns.jsonl", paths.turns_file()),
+        ("hook_bench.jsonl", paths.hook_bench_file()),
+    ] {
+        let health = repair_jsonl(&path);
+        failures += usize::from(health.is_err());
+        lines.push(describe(name, &path, &health));
+    }
+    println!("{}", lines.join("\n"));
+    if failures > 0 {
+        anyhow::bail!("{} file(s) could not be repaired", failures);
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_describe() {
+        let path = Path::new("/t/turns.jsonl");
+        let health = |valid, corrupt| Ok(JsonlHealth { valid, corrupt });
+        assert_eq!(
+            describe("turns.jsonl", path, &health(3, 0)),
+            "  turns.jsonl: ok (3 records)"
+        );
+        assert_eq!(
+            describe("turns.jsonl", path, &health(3, 2)),
+            "  turns.jsonl: dropped 2 corrupt line(s), kept 3 (saved to /t/turns.jsonl.corrupt)"
+        );
+    }
+}
@@ -0,0 +1,250 @@
+//! `repl`: route prompts interactively against an in-memory copy of state
+//!
+//! Each prompt runs the prompt pipeline like `preview` does, but attention
+//! state carries over from one prompt to the next, so decay, streaks and
+//! co-activation play out as they would across a session. Nothing is written
+//! to disk.
+
+use super::hooks::{
+    PromptPipeline, apply_model_profile, load_attention_state, load_config, load_last_injected,
+    load_learner, run_prompt_pipeline,
+};
+use attentive_core::RouterMetrics;
+use attentive_telemetry::{Paths, estimate_tokens};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+
+/// Context lines shown after each prompt; `:context` prints it all
+const CONTEXT_PREVIEW_LINES: usize = 20;
+/// Files followed through the score trace
+const TRACE_FILES: usize = 10;
+/// Lines shown per plugin message
+const PLUGIN_MESSAGE_LINES: usize = 3;
+
+const HELP: &str = "Type a prompt to route it. Commands:
+  :context  print the last prompt's full context
+  :reset    reload attention state from disk
+  :help     show this help
+  :quit     exit (or Ctrl-D)";
+
+pub fn run() -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+
+    // Plugins persist their own state on post-hooks; keep them read-only too
+    attentive_plugins::base::set_read_only(true);
+    attentive_core::set_score_tracing(true);
+
+    let base_config = load_config(&paths.home_claude);
+    let learner = load_learner(&paths.learned_state_path()?)
+        .map(|l| l.with_half_life(base_config.learner_half_life));
+    let mut state = load_attention_state(&paths)?;
+    let mut previous_injected = load_last_injected(&paths);
+    let mut last_context = String::new();
+
+    println!(
+        "attentive repl: {} files scored, nothing is saved. :help for commands.",
+        state.scores.len()
+    );
+    let mut stdin = std::io::stdin().lock();
+    loop {
+        print!("> ");
+        std::io::stdout().flush()?;
+        let mut line = String::new();
+        if stdin.read_line(&mut line)? == 0 {
+            break;
+        }
+        match line.trim() {
+            "" => {}
+            ":quit" | ":q" => break,
+            ":help" => println!("{}", HELP),
+            ":context" => println!("{}", last_context),
+            ":reset" => {
+                state = load_attention_state(&paths)?;
+                previous_injected = load_last_injected(&paths);
+                println!("State reloaded ({} files)", state.scores.len());
+            }
+            prompt => {
+                let mut config = base_config.clone();
+                let (prompt, directives) =
+                    super::directives::apply(&paths, prompt, &mut config, &mut state, false);
+                apply_model_profile(&paths, &mut config, None);
+                config.apply_task_profile(
+                    attentive_learn::Oracle::new()
+                        .classify_task(&prompt)
+                        .as_str(),
+                );
+                let delta = config.delta_context;
+                let result = run_prompt_pipeline(
+                    &paths,
+                    prompt,
+                    &mut state,
+                    config,
+                    learner.as_ref(),
+                    delta.then_some(&previous_injected),
+                )?;
+                let Some(mut pipeline) = result else {
+                    println!("Blocked by a plugin pre-hook; nothing would be injected.");
+                    continue;
+                };
+                if !directives.is_empty() {
+                    pipeline.context = format!("{}\n\n{}", directives, pipeline.context);
+                }
+                println!("{}", format_turn(&pipeline, &state.scores));
+                last_context = pipeline.full_context();
+                previous_injected = pipeline.injected;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Tiers, plugin messages, score trace and the start of the context for one
+/// prompt. `scores` are the final ones, after the learned floors.
+fn format_turn(pipeline: &PromptPipeline, scores: &HashMap<String, f64>) -> String {
+    let tier = |files: &[String]| {
+        let listed: Vec<String> = files
+            .iter()
+            .map(|f| format!("{} ({:.2})", f, scores.get(f).copied().unwrap_or(0.0)))
+            .collect();
+        if listed.is_empty() {
+            "-".to_string()
+        } else {
+            listed.join(", ")
+        }
+    };
+    let mut lines = vec![
+        format!("HOT:  {}", tier(&pipeline.hot_files)),
+        format!("WARM: {}", tier(&pipeline.warm_files)),
+    ];
+    if !pipeline.likely_next.is_empty() {
+        lines.push(format!("Likely next: {}", pipeline.likely_next.join(", ")));
+    }
+    for conflict in &pipeline.conflicts {
+        lines.push(format!(
+            "Config conflict: {} is {}; to settle it, {}",
+            conflict.path, conflict, conflict.resolution
+        ));
+    }
+
+    if !pipeline.plugin_messages.is_empty() {
+        lines.push("Plugins:".to_string());
+        for (plugin, message) in &pipeline.plugin_messages {
+            for text in message.lines().take(PLUGIN_MESSAGE_LINES) {
+                lines.push(format!("  [{}] {}", plugin, text));
+            }
+        }
+    }
+
+    let traced: Vec<&String> = pipeline
+        .hot_files
+        .iter()
+        .chain(&pipeline.warm_files)
+        .collect();
+    let trace = format_trace(&pipeline.metrics, scores, &traced);
+    if !trace.is_empty() {
+        lines.push("Score trace:".to_string());
+        lines.push(trace);
+    }
+
+    let context = pipeline.full_context();
+    let total = context.lines().count();
+    lines.push(format!(
+        "Context: {} tokens, {} lines{}",
+        estimate_tokens(&context),
+        total,
+        if total > CONTEXT_PREVIEW_LINES {
+            format!(" (first {}; :context for all)", CONTEXT_PREVIEW_LINES)
+        } else {
+            String::new()
+        }
+    ));
+    lines.extend(
+        context
+            .lines()
+            .take(CONTEXT_PREVIEW_LINES)
+            .map(|line| format!("  {}", line)),
+    );
+    lines.join("\n")
+}
+
+/// Table of each file's score after every traced router phase, ending with
+/// its final score. Lists `files` first, then the highest-scored others, up
+/// to `TRACE_FILES`. Empty when nothing was traced or scored.
+fn format_trace(
+    metrics: &RouterMetrics,
+    scores: &HashMap<String, f64>,
+    files: &[&String],
+) -> String {
+    if metrics.score_trace.is_empty() {
+        return String::new();
+    }
+    let mut rest: Vec<(&String, f64)> = scores
+        .iter()
+        .filter(|(f, _)| !files.contains(f))
+        .map(|(f, &s)| (f, s))
+        .collect();
+    rest.sort_by(|a, b| b.1.total_cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    let rows: Vec<&String> = files
+        .iter()
+        .copied()
+        .chain(rest.into_iter().map(|(f, _)| f))
+        .take(TRACE_FILES)
+        .collect();
+    if rows.is_empty() {
+        return String::new();
+    }
+
+    let mut columns: Vec<(&str, &HashMap<String, f64>)> = metrics
+        .score_trace
+        .iter()
+        .map(|(phase, snapshot)| (phase.as_str(), snapshot))
+        .collect();
+    columns.push(("final", scores));
+    let width = rows.iter().map(|f| f.len()).max().unwrap_or(0);
+
+    let mut header = format!("  {:<width$}", "file", width = width);
+    for (phase, _) in &columns {
+        header.push_str(&format!("  {:>w$}", phase, w = phase.len().max(4)));
+    }
+    let mut lines = vec![header];
+    for file in rows {
+        let mut row = format!("  {:<width$}", file, width = width);
+        for (phase, snapshot) in &columns {
+            let value = snapshot
+                .get(file.as_str())
+                .map_or("-".to_string(), |s| format!("{:.2}", s));
+            row.push_str(&format!("  {:>w$}", value, w = phase.len().max(4)));
+        }
+        lines.push(row);
+    }
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_trace_follows_files_through_phases() {
+        let mut metrics = RouterMetrics::new();
+        let start = HashMap::from([("a.rs".to_string(), 0.5), ("b.rs".to_string(), 0.9)]);
+        let decay = HashMap::from([("a.rs".to_string(), 0.35), ("b.rs".to_string(), 0.63)]);
+        metrics.score_trace = vec![("start".to_string(), start), ("decay".to_string(), decay)];
+        let scores = HashMap::from([
+            ("a.rs".to_string(), 1.0),
+            ("b.rs".to_string(), 0.63),
+            ("new.rs".to_string(), 0.3),
+        ]);
+        let hot = "a.rs".to_string();
+
+        let trace = format_trace(&metrics, &scores, &[&hot]);
+        assert_eq!(
+            trace,
+            "  file    start  decay  final\n\
+             \x20 a.rs     0.50   0.35   1.00\n\
+             \x20 b.rs     0.90   0.63   0.63\n\
+             \x20 new.rs      -      -   0.30"
+        );
+        assert_eq!(format_trace(&RouterMetrics::new(), &scores, &[]), "");
+    }
+}
@@ -1,14 +1,483 @@
-use attentive_telemetry::{Paths, TurnRecord, read_jsonl};
-use std::collections::HashMap;
+use super::hooks::{load_config, load_learner};
+use super::learn::{REPORT_COACTIVATION_PAIRS, coactivation_suggestions, format_coactivation};
+use attentive_compress::{CompressedObservation, ObservationDb};
+use attentive_learn::{LOOKAHEAD_TURNS, Oracle, TaskType};
+use attentive_telemetry::{Paths, TurnQuery, TurnRecord, estimate_tokens, read_jsonl};
+use chrono::{DateTime, Duration, Utc};
+use std::collections::{BTreeMap, HashMap};
+use std::path::Path;
 
-pub fn run() -> anyhow::Result<()> {
+/// Files injected at least this often across the team qualify for demotion review
+const MIN_DEMOTION_INJECTIONS: usize = 5;
+/// ...when fewer than this fraction of those injections were used
+const MAX_DEMOTION_EFFICIENCY: f64 = 0.2;
+
+/// Trailing turns of a session whose files seed the next session's warm start
+const WARM_START_TURNS: usize = 3;
+/// Files listed per section of the session report
+const SESSION_TOP_FILES: usize = 5;
+
+/// Days of history whose task mix drives the weekly forecast
+const FORECAST_WINDOW_DAYS: i64 = 28;
+/// Days counted as "recent" when comparing a task type's average cost
+const RECENT_COST_DAYS: i64 = 7;
+/// Turns needed on each side of the comparison before alerting
+const MIN_COST_SAMPLES: usize = 3;
+/// Recent average this much above the earlier one raises an alert
+const COST_GROWTH_ALERT: f64 = 0.5;
+
+/// Turns recorded on this machine that match `query`
+fn query_turns(query: &TurnQuery) -> anyhow::Result<Vec<TurnRecord>> {
     let paths = Paths::new()?;
     let turns: Vec<TurnRecord> = read_jsonl(&paths.turns_file())?;
+    Ok(query.apply(&turns).into_iter().cloned().collect())
+}
+
+pub fn run(query: &TurnQuery) -> anyhow::Result<()> {
+    let turns = query_turns(query)?;
     let report = build_report(&turns);
     println!("{}", report);
+
+    // Learned state is per project, so this section covers the current one
+    let paths = Paths::new()?;
+    if let Some(learner) = load_learner(&paths.learned_state_path()?) {
+        let config = load_config(&paths.home_claude);
+        let suggestions = coactivation_suggestions(&learner, &config, REPORT_COACTIVATION_PAIRS);
+        if !suggestions.is_empty() {
+            println!(
+                "\nCo-activation Suggestions\n-------------------------\n{}",
+                format_coactivation(&suggestions)
+            );
+        }
+    }
+    Ok(())
+}
+
+/// Write this machine's turns with project paths and identifying text removed
+pub fn run_export(file: &str, query: &TurnQuery) -> anyhow::Result<()> {
+    let turns = query_turns(query)?;
+    let lines: Vec<String> = turns
+        .iter()
+        .map(|t| serde_json::to_string(&anonymize(t)))
+        .collect::<Result<_, _>>()?;
+    let mut content = lines.join("\n");
+    content.push('\n');
+    attentive_telemetry::atomic_write(Path::new(file), content.as_bytes())?;
+    println!("Exported {} turns to {}", turns.len(), file);
+    Ok(())
+}
+
+/// Team report over every exported `.jsonl` in `dir` (one file per developer)
+pub fn run_merge(dir: &str) -> anyhow::Result<()> {
+    let mut developers = Vec::new();
+    let mut entries: Vec<_> = std::fs::read_dir(dir)?
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| p.extension().is_some_and(|e| e == "jsonl"))
+        .collect();
+    entries.sort();
+    for path in entries {
+        let name = path
+            .file_stem()
+            .map(|s| s.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let turns: Vec<TurnRecord> = read_jsonl(&path)?;
+        developers.push((name, turns));
+    }
+    println!("{}", build_team_report(&developers));
     Ok(())
 }
 
+/// Narrative report for one session, matched by id or unique id prefix
+pub fn run_session(id: &str) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let turns: Vec<TurnRecord> = read_jsonl(&paths.turns_file())?;
+    let session_id = resolve_session(&turns, id)?;
+    let session: Vec<TurnRecord> = turns
+        .into_iter()
+        .filter(|t| t.session_id == session_id)
+        .collect();
+
+    let db_path = paths.observations_db_path();
+    let observations = if db_path.exists() {
+        ObservationDb::new(&db_path)?
+            .with_redactor(load_config(&paths.home_claude).redactor())
+            .session(&session_id)?
+    } else {
+        Vec::new()
+    };
+
+    let project = Path::new(&session[0].project).to_path_buf();
+    let file_tokens = |file: &str| {
+        std::fs::read_to_string(project.join(file))
+            .ok()
+            .map(|content| estimate_tokens(&content))
+    };
+    println!(
+        "{}",
+        build_session_report(&session_id, &session, &observations, file_tokens)
+    );
+    Ok(())
+}
+
+/// The session `id` names exactly, or the only one it is a prefix of
+fn resolve_session(turns: &[TurnRecord], id: &str) -> anyhow::Result<String> {
+    if turns.iter().any(|t| t.session_id == id) {
+        return Ok(id.to_string());
+    }
+    let mut matches: Vec<&str> = turns
+        .iter()
+        .map(|t| t.session_id.as_str())
+        .filter(|s| s.starts_with(id))
+        .collect();
+    matches.sort_unstable();
+    matches.dedup();
+    match matches.as_slice() {
+        [] => anyhow::bail!("No turns recorded for session {}", id),
+        [only] => Ok(only.to_string()),
+        many => anyhow::bail!("Session prefix {} is ambiguous: {}", id, many.join(", ")),
+    }
+}
+
+/// `(item, count)` pairs, most frequent first, ties by name
+fn ranked<'a>(items: impl Iterator<Item = &'a str>) -> Vec<(&'a str, usize)> {
+    let mut counts: HashMap<&str, usize> = HashMap::new();
+    for item in items {
+        *counts.entry(item).or_default() += 1;
+    }
+    let mut ranked: Vec<_> = counts.into_iter().collect();
+    ranked.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+    ranked
+}
+
+/// Sum of a numeric plugin field over the session's turns
+fn extension_sum(turns: &[TurnRecord], plugin: &str, field: &str) -> u64 {
+    turns
+        .iter()
+        .filter_map(|t| t.extensions.get(plugin)?.get(field)?.as_u64())
+        .sum()
+}
+
+/// What one session worked on, where its attention went, what the routing
+/// saved, what went wrong and where the next session should pick up.
+/// `file_tokens` sizes a file as it is now; the baseline is every injected
+/// file included in full on each turn it was injected.
+fn build_session_report(
+    session_id: &str,
+    turns: &[TurnRecord],
+    observations: &[CompressedObservation],
+    file_tokens: impl Fn(&str) -> Option<usize>,
+) -> String {
+    if turns.is_empty() {
+        return format!("No turns recorded for session {}.", session_id);
+    }
+
+    let first = turns.iter().map(|t| t.timestamp).min().unwrap_or_default();
+    let last = turns.iter().map(|t| t.timestamp).max().unwrap_or_default();
+    let mut sections = vec![format!(
+        "Session Report: {}\n================{}\n\
+         Project: {}\n{} turns over {} min ({} – {})",
+        session_id,
+        "=".repeat(session_id.chars().count()),
+        turns[0].project,
+        turns.len(),
+        (last - first).num_minutes(),
+        first.format("%Y-%m-%d %H:%M"),
+        last.format("%H:%M")
+    )];
+
+    // What was worked on
+    let mut worked_on = Vec::new();
+    let tasks = ranked(turns.iter().filter_map(|t| t.task_type.as_deref()));
+    if !tasks.is_empty() {
+        let mix: Vec<String> = tasks
+            .iter()
+            .map(|(task, n)| format!("{} ×{}", task, n))
+            .collect();
+        worked_on.push(format!("Mostly {}: {}", tasks[0].0, mix.join(", ")));
+    }
+    let concepts = ranked(
+        observations
+            .iter()
+            .flat_map(|o| o.concepts.iter().map(String::as_str)),
+    );
+    if !concepts.is_empty() {
+        let top: Vec<&str> = concepts
+            .iter()
+            .take(SESSION_TOP_FILES)
+            .map(|(c, _)| *c)
+            .collect();
+        worked_on.push(format!("Concepts: {}", top.join(", ")));
+    }
+    for obs in observations.iter().rev().take(SESSION_TOP_FILES).rev() {
+        worked_on.push(format!("  - {}", obs.semantic_summary));
+    }
+    if !worked_on.is_empty() {
+        sections.push(format!("\nWorked On\n---------\n{}", worked_on.join("\n")));
+    }
+
+    // Files that dominated attention
+    let used = ranked(
+        turns
+            .iter()
+            .flat_map(|t| t.files_used.iter().map(String::as_str)),
+    );
+    let injected = ranked(
+        turns
+            .iter()
+            .flat_map(|t| t.files_injected.iter().map(String::as_str)),
+    );
+    let attention: Vec<String> = used
+        .iter()
+        .take(SESSION_TOP_FILES)
+        .map(|(f, n)| {
+            let inj = injected.iter().find(|(i, _)| i == f).map_or(0, |(_, n)| *n);
+            format!(
+                "  {} — used in {}/{} turns, injected {}",
+                f,
+                n,
+                turns.len(),
+                inj
+            )
+        })
+        .collect();
+    if !attention.is_empty() {
+        sections.push(format!("\nAttention\n---------\n{}", attention.join("\n")));
+    }
+
+    // Savings against including every injected file in full
+    let total_injected: usize = turns.iter().map(|t| t.injected_tokens).sum();
+    let total_used: usize = turns.iter().map(|t| t.used_tokens).sum();
+    let baseline: usize = turns
+        .iter()
+        .flat_map(|t| &t.files_injected)
+        .filter_map(|f| file_tokens(f))
+        .sum();
+    let mut savings = vec![format!(
+        "Injected: {} tokens, used: {} (waste {:.1}%)",
+        total_injected,
+        total_used,
+        waste_of(turns) * 100.0
+    )];
+    if baseline > 0 {
+        let saved = baseline.saturating_sub(total_injected);
+        savings.push(format!(
+            "Baseline (injected files in full): {} tokens, saved {} ({:.0}%)",
+            baseline,
+            saved,
+            saved as f64 / baseline as f64 * 100.0
+        ));
+    }
+    let raw: i64 = observations.iter().map(|o| o.raw_tokens).sum();
+    let compressed: i64 = observations.iter().map(|o| o.compressed_tokens).sum();
+    if raw > 0 {
+        savings.push(format!(
+            "Observations: {} compressed from {} to {} tokens",
+            observations.len(),
+            raw,
+            compressed
+        ));
+    }
+    sections.push(format!("\nSavings\n-------\n{}", savings.join("\n")));
+
+    // Loops and violations
+    let mut problems = Vec::new();
+    let loop_files = ranked(
+        turns
+            .iter()
+            .filter_map(|t| t.extensions.get("loopbreaker")?.get("loop_file")?.as_str()),
+    );
+    for (file, n) in &loop_files {
+        problems.push(format!("Loop on {} ({} turns)", file, n));
+    }
+    let violations = extension_sum(turns, "verifyfirst", "violations");
+    if violations > 0 {
+        problems.push(format!("Edits without reading first: {}", violations));
+    }
+    let destructive = extension_sum(turns, "guardrail", "destructive_commands");
+    if destructive > 0 {
+        problems.push(format!("Destructive commands: {}", destructive));
+    }
+    if problems.is_empty() {
+        problems.push("No loops or violations".to_string());
+    }
+    sections.push(format!(
+        "\nLoops & Violations\n------------------\n{}",
+        problems.join("\n")
+    ));
+
+    // Warm start: files the session ended on, unless it was looping on them
+    let tail = &turns[turns.len().saturating_sub(WARM_START_TURNS)..];
+    let mut warm_start: Vec<&str> = Vec::new();
+    for f in tail.iter().rev().flat_map(|t| &t.files_used) {
+        if !warm_start.contains(&f.as_str()) && !loop_files.iter().any(|(l, _)| l == f) {
+            warm_start.push(f);
+        }
+    }
+    warm_start.truncate(SESSION_TOP_FILES);
+    let next = if warm_start.is_empty() {
+        "No files were used at the end of this session".to_string()
+    } else {
+        format!(
+            "Start from {}; mention them in the first prompt (or `remember` them) \
+             to warm the router",
+            warm_start.join(", ")
+        )
+    };
+    sections.push(format!("\nNext Session\n------------\n{}", next));
+
+    sections.join("\n")
+}
+
+/// Make `path` relative to `project`; other absolute paths keep only their file name
+fn strip_path(path: &str, project: &str) -> String {
+    let relative = Path::new(path)
+        .strip_prefix(project)
+        .ok()
+        .filter(|p| !p.as_os_str().is_empty());
+    match relative {
+        Some(rel) => rel.to_string_lossy().to_string(),
+        None if Path::new(path).is_absolute() => Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        None => path.to_string(),
+    }
+}
+
+fn strip_value(value: &serde_json::Value, project: &str) -> serde_json::Value {
+    match value {
+        serde_json::Value::String(s) => serde_json::Value::String(strip_path(s, project)),
+        serde_json::Value::Array(items) => {
+            serde_json::Value::Array(items.iter().map(|v| strip_value(v, project)).collect())
+        }
+        serde_json::Value::Object(fields) => serde_json::Value::Object(
+            fields
+                .iter()
+                .map(|(k, v)| (k.clone(), strip_value(v, project)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// Copy of `turn` safe to share: project reduced to its directory name and
+/// every path made project-relative
+fn anonymize(turn: &TurnRecord) -> TurnRecord {
+    let project = turn.project.as_str();
+    let strip_all = |files: &[String]| -> Vec<String> {
+        files.iter().map(|f| strip_path(f, project)).collect()
+    };
+    TurnRecord {
+        project: Path::new(project)
+            .file_name()
+            .map(|n| n.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        files_injected: strip_all(&turn.files_injected),
+        files_used: strip_all(&turn.files_used),
+        files_predicted: strip_all(&turn.files_predicted),
+        files_referenced: strip_all(&turn.files_referenced),
+        files_from_editor: strip_all(&turn.files_from_editor),
+        extensions: turn
+            .extensions
+            .iter()
+            .map(|(k, v)| (k.clone(), strip_value(v, project)))
+            .collect(),
+        ..turn.clone()
+    }
+}
+
+/// Files injected often but rarely used: (file, injected, used), most injected first
+fn demotion_candidates(turns: &[TurnRecord]) -> Vec<(String, usize, usize)> {
+    let mut counts: HashMap<&str, (usize, usize)> = HashMap::new();
+    for t in turns {
+        for f in &t.files_injected {
+            counts.entry(f.as_str()).or_default().0 += 1;
+        }
+        for f in &t.files_used {
+            if let Some(entry) = counts.get_mut(f.as_str()) {
+                entry.1 += 1;
+            }
+        }
+    }
+    let mut candidates: Vec<(String, usize, usize)> = counts
+        .into_iter()
+        .filter(|&(_, (injected, used))| {
+            injected >= MIN_DEMOTION_INJECTIONS
+                && (used as f64 / injected as f64) < MAX_DEMOTION_EFFICIENCY
+        })
+        .map(|(f, (injected, used))| (f.to_string(), injected, used))
+        .collect();
+    candidates.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(&b.0)));
+    candidates
+}
+
+fn waste_of(turns: &[TurnRecord]) -> f64 {
+    let injected: usize = turns.iter().map(|t| t.injected_tokens).sum();
+    let used: usize = turns.iter().map(|t| t.used_tokens).sum();
+    if injected > 0 {
+        1.0 - used as f64 / injected as f64
+    } else {
+        0.0
+    }
+}
+
+fn build_team_report(developers: &[(String, Vec<TurnRecord>)]) -> String {
+    let all: Vec<TurnRecord> = developers
+        .iter()
+        .flat_map(|(_, turns)| turns.iter().cloned())
+        .collect();
+    if all.is_empty() {
+        return "No exported turns found.".to_string();
+    }
+
+    let mut sections = vec![format!(
+        "Team Report\n===========\nDevelopers: {}\nTotal turns: {}",
+        developers.len(),
+        all.len()
+    )];
+
+    let mut lines = Vec::new();
+    for (name, turns) in developers {
+        let confidences: Vec<f64> = turns.iter().filter_map(|t| t.context_confidence).collect();
+        let hit = if confidences.is_empty() {
+            "n/a".to_string()
+        } else {
+            format!(
+                "{:.0}%",
+                confidences.iter().sum::<f64>() / confidences.len() as f64 * 100.0
+            )
+        };
+        lines.push(format!(
+            "  {} — turns:{} waste:{:.1}% hit rate:{}",
+            name,
+            turns.len(),
+            waste_of(turns) * 100.0,
+            hit
+        ));
+    }
+    sections.push(format!(
+        "\nRouting Effectiveness\n---------------------\n{}",
+        lines.join("\n")
+    ));
+
+    let candidates = demotion_candidates(&all);
+    if !candidates.is_empty() {
+        let lines: Vec<String> = candidates
+            .iter()
+            .take(10)
+            .map(|(f, injected, used)| format!("  {} — injected:{} used:{}", f, injected, used))
+            .collect();
+        sections.push(format!(
+            "\nTop Demotion Candidates\n-----------------------\n{}",
+            lines.join("\n")
+        ));
+    }
+
+    sections.push(format!("\n{}", build_report(&all)));
+    sections.join("\n")
+}
+
 fn build_report(turns: &[TurnRecord]) -> String {
     if turns.is_empty() {
         return "No turns recorded yet.".to_string();
@@ -43,7 +512,7 @@ fn build_report(turns: &[TurnRecord]) -> String {
         sorted[sorted.len() / 2]
     };
     let notif_count = turns.iter().filter(|t| t.was_notification).count();
-    sections.push(format!(
+    let mut waste = format!(
         "\nWaste Analysis\n--------------\n\
          Mean waste: {:.1}% | Median: {:.1}%\n\
          Notification turns: {}/{} ({:.0}%)",
@@ -52,21 +521,97 @@ fn build_report(turns: &[TurnRecord]) -> String {
         notif_count,
         turns.len(),
         notif_count as f64 / turns.len() as f64 * 100.0
-    ));
+    );
+    let overflow_turns = turns.iter().filter(|t| t.budget_overflow > 0).count();
+    if overflow_turns > 0 {
+        let overflow_sections: usize = turns.iter().map(|t| t.budget_overflow).sum();
+        waste.push_str(&format!(
+            "\nBudget overflows: {}/{} turns ({} sections shortened or dropped)",
+            overflow_turns,
+            turns.len(),
+            overflow_sections
+        ));
+    }
+    let mut trims: BTreeMap<&str, usize> = BTreeMap::new();
+    for turn in turns {
+        for (plugin, usage) in &turn.plugin_budgets {
+            if usage.truncated() {
+                *trims.entry(plugin).or_insert(0) += 1;
+            }
+        }
+    }
+    if !trims.is_empty() {
+        let trims: Vec<String> = trims
+            .iter()
+            .map(|(plugin, count)| format!("{} {}", plugin, count))
+            .collect();
+        waste.push_str(&format!(
+            "\nPlugin budget trims (turns): {}",
+            trims.join(", ")
+        ));
+    }
+    sections.push(waste);
 
     // Section 3: Confidence
+    let mut confidence = Vec::new();
     let confidences: Vec<f64> = turns.iter().filter_map(|t| t.context_confidence).collect();
     if !confidences.is_empty() {
         let avg_conf = confidences.iter().sum::<f64>() / confidences.len() as f64;
-        sections.push(format!(
-            "\nConfidence\n----------\n\
-             Average context confidence: {:.1}% ({} turns with data)",
+        confidence.push(format!(
+            "Average context confidence: {:.1}% ({} turns with data)",
             avg_conf * 100.0,
             confidences.len()
         ));
     }
+    let (predictions_used, predictions) = prediction_accuracy(turns);
+    if predictions > 0 {
+        confidence.push(format!(
+            "Likely-next predictions used: {}/{} ({:.0}%)",
+            predictions_used,
+            predictions,
+            predictions_used as f64 / predictions as f64 * 100.0
+        ));
+    }
+    if let Some(impact) = editor_impact(turns) {
+        let percent = |used: usize, total: usize| used as f64 / total.max(1) as f64 * 100.0;
+        confidence.push(format!(
+            "Open-file signals: {} turns, a boosted file used in {} ({:.0}%)",
+            impact.turns,
+            impact.turns_used,
+            percent(impact.turns_used, impact.turns)
+        ));
+        confidence.push(format!(
+            "Editor-boosted files used: {:.0}% vs {:.0}% of other injected files",
+            percent(impact.boosted.0, impact.boosted.1),
+            percent(impact.other.0, impact.other.1)
+        ));
+    }
+    if !confidence.is_empty() {
+        sections.push(format!(
+            "\nConfidence\n----------\n{}",
+            confidence.join("\n")
+        ));
+    }
+
+    // Section 4: Plugin signals
+    let signals = build_plugin_signals(turns);
+    if !signals.is_empty() {
+        sections.push(format!("\nPlugin Signals\n--------------\n{}", signals));
+    }
+
+    // Section 5: Context sources
+    let sources = build_source_breakdown(turns);
+    if !sources.is_empty() {
+        sections.push(format!("\nContext Sources\n---------------\n{}", sources));
+    }
+
+    // Section 6: Cost forecast by task mix
+    let forecast = build_cost_forecast(turns);
+    if !forecast.is_empty() {
+        sections.push(format!("\nCost Forecast\n-------------\n{}", forecast));
+    }
 
-    // Section 4: File Leaderboard
+    // Section 7: File Leaderboard
     let leaderboard = build_file_leaderboard(turns);
     if !leaderboard.is_empty() {
         sections.push(format!(
@@ -78,6 +623,267 @@ fn build_report(turns: &[TurnRecord]) -> String {
     sections.join("\n")
 }
 
+/// Likely-next predictions whose file was used on the turn they were made or
+/// one of the `LOOKAHEAD_TURNS` after it in the same session: (used, total)
+fn prediction_accuracy(turns: &[TurnRecord]) -> (usize, usize) {
+    let mut sessions: HashMap<&str, Vec<&TurnRecord>> = HashMap::new();
+    for turn in turns {
+        sessions.entry(&turn.session_id).or_default().push(turn);
+    }
+    let mut used = 0;
+    let mut total = 0;
+    for session in sessions.values() {
+        for (i, turn) in session.iter().enumerate() {
+            let window = &session[i..session.len().min(i + 1 + LOOKAHEAD_TURNS)];
+            for file in &turn.files_predicted {
+                total += 1;
+                used += usize::from(window.iter().any(|t| t.files_used.contains(file)));
+            }
+        }
+    }
+    (used, total)
+}
+
+/// How injected files boosted by the editor signal fared against the rest
+#[derive(Debug, PartialEq)]
+struct EditorImpact {
+    /// Turns where the signal boosted an injected file
+    turns: usize,
+    /// ...and where at least one of those files was used
+    turns_used: usize,
+    /// (used, injected) over the boosted files
+    boosted: (usize, usize),
+    /// (used, injected) over the other files injected on those turns
+    other: (usize, usize),
+}
+
+/// `None` when no turn had an editor signal
+fn editor_impact(turns: &[TurnRecord]) -> Option<EditorImpact> {
+    let mut impact = EditorImpact {
+        turns: 0,
+        turns_used: 0,
+        boosted: (0, 0),
+        other: (0, 0),
+    };
+    for turn in turns.iter().filter(|t| !t.files_from_editor.is_empty()) {
+        let used =
+            |file: &String| turn.files_used.contains(file) || turn.files_referenced.contains(file);
+        let mut any_used = false;
+        for file in &turn.files_injected {
+            let counts = if turn.files_from_editor.contains(file) {
+                any_used |= used(file);
+                &mut impact.boosted
+            } else {
+                &mut impact.other
+            };
+            counts.0 += usize::from(used(file));
+            counts.1 += 1;
+        }
+        impact.turns += 1;
+        impact.turns_used += usize::from(any_used);
+    }
+    (impact.turns > 0).then_some(impact)
+}
+
+/// Per plugin field: turns reporting it and the sum of its numeric values
+fn build_plugin_signals(turns: &[TurnRecord]) -> String {
+    let mut fields: BTreeMap<String, (usize, f64)> = BTreeMap::new();
+    for t in turns {
+        for (plugin, value) in &t.extensions {
+            let Some(object) = value.as_object() else {
+                continue;
+            };
+            for (field, v) in object {
+                let numeric = v
+                    .as_f64()
+                    .or_else(|| v.as_bool().map(|b| if b { 1.0 } else { 0.0 }));
+                if let Some(n) = numeric {
+                    let entry = fields.entry(format!("{}.{}", plugin, field)).or_default();
+                    entry.0 += 1;
+                    entry.1 += n;
+                }
+            }
+        }
+    }
+
+    fields
+        .iter()
+        .map(|(field, (count, sum))| {
+            format!(
+                "  {} — turns:{} total:{} avg:{:.1}",
+                field,
+                count,
+                sum,
+                sum / *count as f64
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+/// Pearson correlation of paired samples, `None` without enough spread
+fn correlation(pairs: &[(f64, f64)]) -> Option<f64> {
+    if pairs.len() < 3 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let mean_x = pairs.iter().map(|p| p.0).sum::<f64>() / n;
+    let mean_y = pairs.iter().map(|p| p.1).sum::<f64>() / n;
+    let (mut cov, mut var_x, mut var_y) = (0.0, 0.0, 0.0);
+    for (x, y) in pairs {
+        cov += (x - mean_x) * (y - mean_y);
+        var_x += (x - mean_x).powi(2);
+        var_y += (y - mean_y).powi(2);
+    }
+    if var_x < 1e-9 || var_y < 1e-9 {
+        return None;
+    }
+    Some(cov / (var_x * var_y).sqrt())
+}
+
+/// Per context source, largest first: tokens, share of all source tokens,
+/// turns it appeared in, and how its size tracks the turn's hit rate
+fn build_source_breakdown(turns: &[TurnRecord]) -> String {
+    let mut totals: BTreeMap<&str, (usize, usize)> = BTreeMap::new();
+    for t in turns {
+        for (source, &tokens) in &t.context_sources {
+            let entry = totals.entry(source.as_str()).or_default();
+            entry.0 += tokens;
+            entry.1 += 1;
+        }
+    }
+    let grand_total: usize = totals.values().map(|(tokens, _)| tokens).sum();
+    if grand_total == 0 {
+        return String::new();
+    }
+
+    let mut sources: Vec<_> = totals.into_iter().collect();
+    sources.sort_by_key(|(_, (tokens, _))| std::cmp::Reverse(*tokens));
+    sources
+        .iter()
+        .map(|(source, (tokens, count))| {
+            let pairs: Vec<(f64, f64)> = turns
+                .iter()
+                .filter(|t| !t.context_sources.is_empty())
+                .filter_map(|t| {
+                    let size = t.context_sources.get(*source).copied().unwrap_or(0);
+                    Some((size as f64, t.context_confidence?))
+                })
+                .collect();
+            let usage = correlation(&pairs)
+                .map(|r| format!("{:+.2}", r))
+                .unwrap_or_else(|| "n/a".to_string());
+            format!(
+                "  {} — tokens:{} share:{:.0}% turns:{} usage-corr:{}",
+                source,
+                tokens,
+                *tokens as f64 / grand_total as f64 * 100.0,
+                count,
+                usage
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_tokens(tokens: f64) -> String {
+    if tokens >= 1_000_000.0 {
+        format!("{:.1}M", tokens / 1_000_000.0)
+    } else if tokens >= 1_000.0 {
+        format!("{:.1}k", tokens / 1_000.0)
+    } else {
+        format!("{:.0}", tokens)
+    }
+}
+
+/// Average turn cost per task type (from an `Oracle` fed the recorded
+/// turns), a weekly spend forecast from the recent task mix, and alerts for
+/// task types whose cost grew over the last week. Times are relative to the
+/// newest turn.
+fn build_cost_forecast(turns: &[TurnRecord]) -> String {
+    let costed: Vec<(DateTime<Utc>, TaskType, usize)> = turns
+        .iter()
+        .filter_map(|t| {
+            let task_type = TaskType::from_name(t.task_type.as_deref()?)?;
+            Some((t.timestamp, task_type, t.turn_tokens?))
+        })
+        .collect();
+    let Some(latest) = costed.iter().map(|(at, _, _)| *at).max() else {
+        return String::new();
+    };
+    let window_start = latest - Duration::days(FORECAST_WINDOW_DAYS);
+    let recent_start = latest - Duration::days(RECENT_COST_DAYS);
+
+    let (mut all, mut window, mut recent, mut earlier) =
+        (Oracle::new(), Oracle::new(), Oracle::new(), Oracle::new());
+    let mut earliest_in_window = latest;
+    for &(at, task_type, tokens) in &costed {
+        all.record_cost(task_type, tokens);
+        if at > window_start {
+            window.record_cost(task_type, tokens);
+            earliest_in_window = earliest_in_window.min(at);
+        }
+        if at > recent_start {
+            recent.record_cost(task_type, tokens);
+        } else {
+            earlier.record_cost(task_type, tokens);
+        }
+    }
+    // At least a week, so a single day of turns isn't extrapolated sevenfold
+    let weeks = ((latest - earliest_in_window).num_hours() as f64 / (24.0 * 7.0)).max(1.0);
+
+    let mut lines = Vec::new();
+    let mut alerts = Vec::new();
+    let mut weekly_total = 0.0;
+    for task_type in TaskType::ALL {
+        let Some(average) = all.estimate_cost(task_type) else {
+            continue;
+        };
+        // The recent average, once there's enough of it, reflects current cost best
+        let current = match recent.estimate_cost(task_type) {
+            Some(cost) if recent.sample_count(task_type) >= MIN_COST_SAMPLES => cost,
+            _ => window.estimate_cost(task_type).unwrap_or(average),
+        };
+        let per_week = window.sample_count(task_type) as f64 / weeks;
+        let weekly = per_week * current as f64;
+        weekly_total += weekly;
+        lines.push(format!(
+            "  {} — turns:{} avg:{} ~{:.0}/week forecast:{}/week",
+            task_type.as_str(),
+            all.sample_count(task_type),
+            format_tokens(average as f64),
+            per_week,
+            format_tokens(weekly)
+        ));
+
+        if recent.sample_count(task_type) >= MIN_COST_SAMPLES
+            && earlier.sample_count(task_type) >= MIN_COST_SAMPLES
+            && let (Some(now), Some(before)) = (
+                recent.estimate_cost(task_type),
+                earlier.estimate_cost(task_type),
+            )
+            && before > 0
+            && now as f64 > before as f64 * (1.0 + COST_GROWTH_ALERT)
+        {
+            alerts.push(format!(
+                "ALERT: {} turns average {} over the last {} days, up {:.0}% from {}",
+                task_type.as_str(),
+                format_tokens(now as f64),
+                RECENT_COST_DAYS,
+                (now as f64 / before as f64 - 1.0) * 100.0,
+                format_tokens(before as f64)
+            ));
+        }
+    }
+    lines.push(format!(
+        "Forecast: ~{} tokens/week at the task mix of the last {} days",
+        format_tokens(weekly_total),
+        FORECAST_WINDOW_DAYS
+    ));
+    lines.extend(alerts);
+    lines.join("\n")
+}
+
 fn build_file_leaderboard(turns: &[TurnRecord]) -> String {
     let mut injected_count: HashMap<&str, usize> = HashMap::new();
     let mut used_count: HashMap<&str, usize> = HashMap::new();
@@ -143,9 +949,9 @@ mod tests {
                 waste_ratio: 0.4,
                 files_injected: vec!["a.rs".to_string(), "b.rs".to_string()],
                 files_used: vec!["a.rs".to_string()],
-                was_notification: false,
                 injection_chars: 4000,
                 context_confidence: Some(0.8),
+                ..Default::default()
             },
             TurnRecord {
                 turn_id: "t2".to_string(),
@@ -157,9 +963,9 @@ mod tests {
                 waste_ratio: 0.1,
                 files_injected: vec!["a.rs".to_string(), "c.rs".to_string()],
                 files_used: vec!["a.rs".to_string(), "c.rs".to_string()],
-                was_notification: false,
                 injection_chars: 8000,
                 context_confidence: Some(0.95),
+                ..Default::default()
             },
         ]
     }
@@ -174,12 +980,311 @@ mod tests {
         assert!(report.contains("Confidence"));
     }
 
+    #[test]
+    fn test_prediction_accuracy_looks_ahead_within_session() {
+        let mut turns = Vec::new();
+        for (i, (session, predicted, used)) in [
+            ("s1", vec!["b.rs", "c.rs"], vec!["a.rs"]),
+            ("s2", vec![], vec!["c.rs"]),
+            ("s1", vec![], vec!["x.rs"]),
+            ("s1", vec!["d.rs"], vec!["b.rs", "d.rs"]),
+            ("s1", vec![], vec!["c.rs"]),
+        ]
+        .into_iter()
+        .enumerate()
+        {
+            let strings = |v: Vec<&str>| v.into_iter().map(String::from).collect();
+            turns.push(TurnRecord {
+                turn_id: format!("t{}", i),
+                session_id: session.to_string(),
+                files_predicted: strings(predicted),
+                files_used: strings(used),
+                ..sample_turns().remove(0)
+            });
+        }
+        // b.rs is used 2 turns later; c.rs only in another session or too late
+        assert_eq!(prediction_accuracy(&turns), (2, 3));
+        assert!(build_report(&turns).contains("Likely-next predictions used: 2/3 (67%)"));
+        assert_eq!(prediction_accuracy(&sample_turns()), (0, 0));
+    }
+
+    #[test]
+    fn test_editor_impact() {
+        assert_eq!(editor_impact(&sample_turns()), None);
+        let strings = |v: &[&str]| v.iter().map(|s| s.to_string()).collect::<Vec<_>>();
+        let turn = |injected: &[&str], editor: &[&str], used: &[&str]| TurnRecord {
+            files_injected: strings(injected),
+            files_from_editor: strings(editor),
+            files_used: strings(used),
+            ..sample_turns().remove(0)
+        };
+        let turns = vec![
+            turn(&["a.rs", "b.rs", "c.rs"], &["a.rs"], &["a.rs"]),
+            turn(&["a.rs", "d.rs"], &["a.rs"], &["d.rs"]),
+            turn(&["x.rs"], &[], &["x.rs"]),
+        ];
+        assert_eq!(
+            editor_impact(&turns),
+            Some(EditorImpact {
+                turns: 2,
+                turns_used: 1,
+                boosted: (1, 2),
+                other: (1, 3),
+            })
+        );
+        let report = build_report(&turns);
+        assert!(report.contains("Open-file signals: 2 turns, a boosted file used in 1 (50%)"));
+        assert!(report.contains("Editor-boosted files used: 50% vs 33% of other injected files"));
+    }
+
+    fn costed_turn(days_ago: i64, task_type: &str, tokens: usize) -> TurnRecord {
+        TurnRecord {
+            timestamp: Utc::now() - Duration::days(days_ago),
+            task_type: Some(task_type.to_string()),
+            turn_tokens: Some(tokens),
+            ..sample_turns().remove(0)
+        }
+    }
+
+    #[test]
+    fn test_cost_forecast_by_task_mix() {
+        assert!(build_cost_forecast(&sample_turns()).is_empty());
+
+        // Two weeks of history: 2 bug fixes and 1 feature a week
+        let mut turns = Vec::new();
+        for week in 0..2 {
+            turns.push(costed_turn(week * 7 + 1, "bug_fix", 10_000));
+            turns.push(costed_turn(week * 7 + 2, "bug_fix", 10_000));
+            turns.push(costed_turn(week * 7 + 3, "feature", 40_000));
+        }
+        turns.push(costed_turn(0, "feature", 40_000));
+        let forecast = build_cost_forecast(&turns);
+        assert!(
+            forecast.contains("bug_fix — turns:4 avg:10.0k"),
+            "{}",
+            forecast
+        );
+        assert!(
+            forecast.contains("feature — turns:3 avg:40.0k"),
+            "{}",
+            forecast
+        );
+        assert!(!forecast.contains("ALERT"));
+        // Turns span 10 days: 4/1.43 × 10k + 3/1.43 × 40k = 112k a week
+        assert!(
+            forecast.contains("~3/week forecast:28.0k/week"),
+            "{}",
+            forecast
+        );
+        assert!(
+            forecast.contains("Forecast: ~112.0k tokens/week"),
+            "{}",
+            forecast
+        );
+    }
+
+    #[test]
+    fn test_cost_forecast_alerts_on_growth() {
+        let mut turns: Vec<TurnRecord> = (10..13)
+            .map(|days| costed_turn(days, "refactor", 10_000))
+            .collect();
+        turns.extend((0..3).map(|days| costed_turn(days, "refactor", 25_000)));
+        turns.extend((0..3).map(|days| costed_turn(days, "review", 5_000)));
+        let forecast = build_cost_forecast(&turns);
+        assert!(
+            forecast.contains(
+                "ALERT: refactor turns average 25.0k over the last 7 days, up 150% from 10.0k"
+            ),
+            "{}",
+            forecast
+        );
+        assert!(!forecast.contains("ALERT: review"));
+        assert!(build_report(&turns).contains("Cost Forecast"));
+    }
+
+    #[test]
+    fn test_build_report_budget_overflows() {
+        let mut turns = sample_turns();
+        assert!(!build_report(&turns).contains("Budget overflows"));
+        turns[1].budget_overflow = 3;
+        assert!(build_report(&turns).contains("Budget overflows: 1/2 turns (3 sections"));
+
+        assert!(!build_report(&turns).contains("Plugin budget trims"));
+        let usage = |used| attentive_telemetry::PluginBudgetUsage {
+            budget: 40,
+            requested: 90,
+            used,
+        };
+        turns[0]
+            .plugin_budgets
+            .insert("verifyfirst".to_string(), usage(38));
+        turns[1]
+            .plugin_budgets
+            .insert("verifyfirst".to_string(), usage(0));
+        turns[1]
+            .plugin_budgets
+            .insert("loopbreaker".to_string(), usage(90));
+        let report = build_report(&turns);
+        assert!(report.contains("Plugin budget trims (turns): verifyfirst 2"));
+        assert!(!report.contains("loopbreaker"));
+    }
+
+    #[test]
+    fn test_session_report_narrative() {
+        let mut turns = sample_turns();
+        turns[0].task_type = Some("bug_fix".to_string());
+        turns[1].task_type = Some("bug_fix".to_string());
+        turns[0].extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({ "loop_file": "b.rs", "loop_count": 3 }),
+        );
+        turns[1].extensions.insert(
+            "verifyfirst".to_string(),
+            serde_json::json!({ "violations": 2, "session_violations": 2 }),
+        );
+        let observation = CompressedObservation {
+            id: "o1".to_string(),
+            session_id: "s1".to_string(),
+            timestamp: Utc::now(),
+            tool_name: "Read".to_string(),
+            observation_type: "discovery".to_string(),
+            concepts: vec!["parser".to_string()],
+            raw_tokens: 500,
+            compressed_tokens: 50,
+            semantic_summary: "Parser drops trailing commas".to_string(),
+            key_facts: vec![],
+            related_files: vec!["a.rs".to_string()],
+            raw_content_hash: "h".to_string(),
+            file_hashes: Default::default(),
+            project: None,
+        };
+
+        let report = build_session_report("s1", &turns, &[observation], |_| Some(2000));
+        assert!(report.contains("Session Report: s1\n=================="));
+        assert!(report.contains("Mostly bug_fix: bug_fix ×2"));
+        assert!(report.contains("Concepts: parser\n  - Parser drops trailing commas"));
+        assert!(report.contains("  a.rs — used in 2/2 turns, injected 2"));
+        assert!(
+            report.contains("Baseline (injected files in full): 8000 tokens, saved 5000 (62%)")
+        );
+        assert!(report.contains("Observations: 1 compressed from 500 to 50 tokens"));
+        assert!(report.contains("Loop on b.rs (1 turns)\nEdits without reading first: 2"));
+        assert!(report.contains("Start from a.rs, c.rs;"));
+    }
+
+    #[test]
+    fn test_resolve_session_prefix() {
+        let mut turns = sample_turns();
+        turns[1].session_id = "s12".to_string();
+        assert_eq!(resolve_session(&turns, "s1").unwrap(), "s1");
+        assert_eq!(resolve_session(&turns, "s12").unwrap(), "s12");
+        turns[0].session_id = "s11".to_string();
+        assert!(resolve_session(&turns, "s1").is_err());
+        assert!(resolve_session(&turns, "x").is_err());
+    }
+
     #[test]
     fn test_build_report_empty() {
         let report = build_report(&[]);
         assert!(report.contains("No turns"));
     }
 
+    #[test]
+    fn test_plugin_signals_section() {
+        let mut turns = sample_turns();
+        turns[0].extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({ "loop_count": 3, "loop_file": "a.rs" }),
+        );
+        turns[1].extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({ "loop_count": 5 }),
+        );
+
+        let signals = build_plugin_signals(&turns);
+        assert_eq!(
+            signals,
+            "  loopbreaker.loop_count — turns:2 total:8 avg:4.0"
+        );
+        assert!(build_report(&turns).contains("Plugin Signals"));
+        assert!(!build_report(&sample_turns()).contains("Plugin Signals"));
+    }
+
+    #[test]
+    fn test_source_breakdown_section() {
+        let mut turns = sample_turns();
+        turns.push(TurnRecord {
+            turn_id: "t3".to_string(),
+            context_confidence: Some(0.2),
+            ..turns[0].clone()
+        });
+        // Hit rates are 0.8, 0.95 and 0.2; HOT size rises and falls with them
+        let sizes = [(300, 100), (600, 100), (100, 100)];
+        for (turn, (hot, plugin)) in turns.iter_mut().zip(sizes) {
+            turn.context_sources = [
+                ("hot".to_string(), hot),
+                ("plugin:verifyfirst".to_string(), plugin),
+            ]
+            .into();
+        }
+
+        let breakdown = build_source_breakdown(&turns);
+        let lines: Vec<&str> = breakdown.lines().collect();
+        assert_eq!(lines.len(), 2);
+        assert!(lines[0].starts_with("  hot — tokens:1000 share:77% turns:3 usage-corr:+0.9"));
+        // A constant-size source has no measurable relationship with usage
+        assert!(lines[1].ends_with("usage-corr:n/a"));
+        assert!(build_report(&turns).contains("Context Sources"));
+        assert!(!build_report(&sample_turns()).contains("Context Sources"));
+    }
+
+    #[test]
+    fn test_anonymize_strips_paths() {
+        let mut turn = sample_turns().remove(0);
+        turn.project = "/home/alice/work/app".to_string();
+        turn.files_injected = vec![
+            "/home/alice/work/app/src/a.rs".to_string(),
+            "src/b.rs".to_string(),
+            "/home/alice/.claude/notes.md".to_string(),
+        ];
+        turn.extensions.insert(
+            "loopbreaker".to_string(),
+            serde_json::json!({ "loop_file": "/home/alice/work/app/src/a.rs", "loop_count": 3 }),
+        );
+
+        let shared = anonymize(&turn);
+        assert_eq!(shared.project, "app");
+        assert_eq!(
+            shared.files_injected,
+            vec!["src/a.rs", "src/b.rs", "notes.md"]
+        );
+        assert_eq!(shared.extensions["loopbreaker"]["loop_file"], "src/a.rs");
+        assert_eq!(shared.extensions["loopbreaker"]["loop_count"], 3);
+        assert!(!serde_json::to_string(&shared).unwrap().contains("alice"));
+    }
+
+    #[test]
+    fn test_team_report_demotion_candidates() {
+        let mut noisy = sample_turns().remove(0);
+        noisy.files_injected = vec!["gen/schema.rs".to_string(), "a.rs".to_string()];
+        noisy.files_used = vec!["a.rs".to_string()];
+        let developers = vec![
+            ("alice".to_string(), vec![noisy.clone(); 3]),
+            ("bob".to_string(), vec![noisy; 3]),
+        ];
+
+        let pooled: Vec<TurnRecord> = developers.iter().flat_map(|(_, t)| t.clone()).collect();
+        assert_eq!(
+            demotion_candidates(&pooled),
+            vec![("gen/schema.rs".to_string(), 6, 0)]
+        );
+        let report = build_team_report(&developers);
+        assert!(report.contains("Developers: 2"));
+        assert!(report.contains("  alice — turns:3"));
+        assert!(report.contains("  gen/schema.rs — injected:6 used:0"));
+        assert!(report.contains("Token Usage Report"));
+    }
+
     #[test]
     fn test_file_leaderboard_sorted() {
         let turns = sample_turns();
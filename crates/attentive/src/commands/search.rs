@@ -0,0 +1,112 @@
+//! `search`: query the project's search index, optionally filtered by
+//! document type, path and age
+
+use attentive_core::FusionStrategy;
+use attentive_index::{DocFilter, SearchIndex, Snippet};
+use attentive_learn::Synonyms;
+use attentive_telemetry::Paths;
+use chrono::Duration;
+use std::collections::HashMap;
+
+/// Parse an age like `30m`, `12h`, `7d` or `2w`
+fn parse_age(age: &str) -> anyhow::Result<Duration> {
+    let invalid = || anyhow::anyhow!("invalid age '{}' (expected e.g. 12h, 7d, 2w)", age);
+    let (split, _) = age.char_indices().last().ok_or_else(invalid)?;
+    let (count, unit) = age.split_at(split);
+    let count: i64 = count.parse().map_err(|_| invalid())?;
+    match unit {
+        "m" => Ok(Duration::minutes(count)),
+        "h" => Ok(Duration::hours(count)),
+        "d" => Ok(Duration::days(count)),
+        "w" => Ok(Duration::weeks(count)),
+        _ => Err(invalid()),
+    }
+}
+
+/// One line per result, each followed by its snippet (indented) if any
+fn format_results(results: &[(String, f64)], snippets: &HashMap<String, Snippet>) -> String {
+    if results.is_empty() {
+        return "No matches".to_string();
+    }
+    let width = results.iter().map(|(p, _)| p.len()).max().unwrap_or(0);
+    let mut lines = Vec::new();
+    for (path, score) in results {
+        lines.push(format!("{:width$}  {:.3}", path, score, width = width));
+        if let Some(snippet) = snippets.get(path) {
+            lines.extend(snippet.numbered().lines().map(|l| format!("    {}", l)));
+        }
+    }
+    lines.join("\n")
+}
+
+pub fn run(
+    query: &str,
+    types: &[String],
+    path_globs: &[String],
+    since: Option<&str>,
+    limit: usize,
+    fusion: Option<&str>,
+) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let index_path = paths.search_index_path()?;
+    if !index_path.exists() {
+        println!("No search index for this project. Run attentive init --project first.");
+        return Ok(());
+    }
+    let filter = DocFilter {
+        types: types.to_vec(),
+        path_globs: path_globs.to_vec(),
+        modified_after: since
+            .map(parse_age)
+            .transpose()?
+            .map(|age| attentive_telemetry::now() - age),
+    };
+    let mut search_fusion = super::hooks::load_config(&paths.home_claude).search_fusion;
+    if let Some(name) = fusion {
+        search_fusion.strategy = FusionStrategy::from_name(name)
+            .ok_or_else(|| anyhow::anyhow!("unknown fusion strategy '{}'", name))?;
+    }
+    let synonyms = Synonyms::load(&paths.synonyms_path()?);
+    let index = SearchIndex::new(index_path)?
+        .with_fusion(search_fusion)
+        .with_expansions(synonyms.term_map());
+    let results = index.query_filtered(query, limit, &filter)?;
+    let mut snippets = HashMap::new();
+    for (path, _) in &results {
+        if let Some(snippet) = index.snippet(path, query)? {
+            snippets.insert(path.clone(), snippet);
+        }
+    }
+    println!("{}", format_results(&results, &snippets));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_age() {
+        assert_eq!(parse_age("7d").unwrap(), Duration::days(7));
+        assert_eq!(parse_age("12h").unwrap(), Duration::hours(12));
+        assert_eq!(parse_age("2w").unwrap(), Duration::weeks(2));
+        assert!(parse_age("7").is_err());
+        assert!(parse_age("d").is_err());
+        assert!(parse_age("").is_err());
+        assert!(parse_age("7é").is_err());
+        let results = [("src/a.rs".to_string(), 1.5), ("b.md".to_string(), 0.25)];
+        assert_eq!(
+            format_results(&results, &HashMap::new()),
+            "src/a.rs  1.500\nb.md      0.250"
+        );
+        let snippet = Snippet {
+            symbol: Some("route".to_string()),
+            line: 9,
+            text: "/// Routes\npub fn route() {".to_string(),
+        };
+        assert_eq!(
+            format_results(&results, &[("src/a.rs".to_string(), snippet)].into()),
+            "src/a.rs  1.500\n     9  /// Routes\n    10  pub fn route() {\nb.md      0.250"
+        );
+    }
+}
@@ -0,0 +1,464 @@
+//! Local state server: keeps parsed config, learner and attention state in
+//! memory so hook invocations skip re-reading JSON from disk.
+//!
+//! Hooks check for the socket and proxy their stdin through it; without a
+//! running server (or on any proxy error) they run directly as before.
+//!
+//! Config is watched: when attentive.json or a project's .attentive.json
+//! changes, the server re-validates it and swaps it in whole, logging what
+//! changed. An invalid edit leaves the previous config in use.
+
+use attentive_core::{Config, Severity};
+use attentive_telemetry::{PathCanonicalizer, Paths};
+use serde::{Deserialize, Serialize};
+use std::any::Any;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, SystemTime};
+
+use super::hooks::HookOutput;
+
+/// How long a proxied hook waits on the server before running directly
+#[cfg(unix)]
+const PROXY_TIMEOUT_SECS: u64 = 30;
+
+/// Parsed file contents, valid while the file's mtime and length are unchanged
+struct CacheEntry {
+    stamp: (SystemTime, u64),
+    value: Box<dyn Any + Send>,
+}
+
+/// Only populated inside `attentive serve`; one-shot commands read from disk
+static CACHE: OnceLock<Mutex<HashMap<PathBuf, CacheEntry>>> = OnceLock::new();
+
+fn file_stamp(path: &Path) -> Option<(SystemTime, u64)> {
+    let meta = std::fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Whether this process is the state server, holding parsed state in memory
+pub(crate) fn is_serving() -> bool {
+    CACHE.get().is_some()
+}
+
+/// Read and parse `path`, reusing the server's in-memory copy while the file
+/// is unchanged on disk
+pub(crate) fn read_cached<T: Clone + Send + 'static>(
+    path: &Path,
+    parse: impl FnOnce(&str) -> anyhow::Result<T>,
+) -> anyhow::Result<T> {
+    let Some(cache) = CACHE.get() else {
+        return parse(&std::fs::read_to_string(path)?);
+    };
+
+    let stamp = file_stamp(path);
+    if let Some(stamp) = stamp
+        && let Some(entry) = cache.lock().unwrap().get(path)
+        && entry.stamp == stamp
+        && let Some(value) = entry.value.downcast_ref::<T>()
+    {
+        return Ok(value.clone());
+    }
+
+    let value = parse(&std::fs::read_to_string(path)?)?;
+    if let Some(stamp) = stamp {
+        let entry = CacheEntry {
+            stamp,
+            value: Box::new(value.clone()),
+        };
+        cache.lock().unwrap().insert(path.to_path_buf(), entry);
+    }
+    Ok(value)
+}
+
+/// Record a value just written to `path` so the next read skips parsing it
+pub(crate) fn store_cached<T: Clone + Send + 'static>(path: &Path, value: &T) {
+    let Some(cache) = CACHE.get() else {
+        return;
+    };
+    if let Some(stamp) = file_stamp(path) {
+        let entry = CacheEntry {
+            stamp,
+            value: Box::new(value.clone()),
+        };
+        cache.lock().unwrap().insert(path.to_path_buf(), entry);
+    }
+}
+
+/// How often the server checks config files for edits
+const CONFIG_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Longest value shown in a config reload log line
+const MAX_DIFF_VALUE_CHARS: usize = 60;
+
+type JsonObject = serde_json::Map<String, serde_json::Value>;
+
+/// The config the server routes one project with
+struct WatchedConfig {
+    /// Stamps of attentive.json and .attentive.json when last loaded
+    stamps: [Option<(SystemTime, u64)>; 2],
+    /// The merged JSON it was parsed from, for describing changes
+    json: JsonObject,
+    config: Config,
+}
+
+/// Config per (home .claude dir, project root); only populated inside
+/// `attentive serve`
+static CONFIGS: OnceLock<Mutex<HashMap<(PathBuf, PathBuf), WatchedConfig>>> = OnceLock::new();
+
+fn config_stamps(home_claude: &Path, root: &Path) -> [Option<(SystemTime, u64)>; 2] {
+    [
+        file_stamp(&home_claude.join("attentive.json")),
+        file_stamp(&root.join(super::hooks::PROJECT_CONFIG_FILE)),
+    ]
+}
+
+/// Parse and validate the layered config for the project at `root`
+fn load_watched(home_claude: &Path, root: &Path) -> anyhow::Result<WatchedConfig> {
+    let stamps = config_stamps(home_claude, root);
+    let json = super::hooks::layered_config_json(home_claude, root)?;
+    let mut config = Config::from_json(&serde_json::Value::Object(json.clone()).to_string())?;
+    let errors: Vec<String> = config
+        .validate()
+        .into_iter()
+        .filter(|i| i.severity == Severity::Error)
+        .map(|i| format!("{}: {}", i.field, i.message))
+        .collect();
+    if !errors.is_empty() {
+        anyhow::bail!("invalid values ({})", errors.join("; "));
+    }
+    config.canonicalize_paths(&PathCanonicalizer::new(root));
+    Ok(WatchedConfig {
+        stamps,
+        json,
+        config,
+    })
+}
+
+/// The server's config for the project at `root`, loaded on first use. `None`
+/// outside `attentive serve`, or when the config can't be loaded yet.
+pub(crate) fn watched_config(home_claude: &Path, root: &Path) -> Option<Config> {
+    let mut configs = CONFIGS.get()?.lock().unwrap();
+    let key = (home_claude.to_path_buf(), root.to_path_buf());
+    if let Some(watched) = configs.get(&key) {
+        return Some(watched.config.clone());
+    }
+    let watched = load_watched(home_claude, root).ok()?;
+    let config = watched.config.clone();
+    configs.insert(key, watched);
+    Some(config)
+}
+
+fn short_json(value: &serde_json::Value) -> String {
+    let text = value.to_string();
+    match text.char_indices().nth(MAX_DIFF_VALUE_CHARS) {
+        Some((idx, _)) => format!("{}...", &text[..idx]),
+        None => text,
+    }
+}
+
+/// Keys added (`+`), removed (`-`) and changed between two config objects
+fn describe_config_diff(old: &JsonObject, new: &JsonObject) -> String {
+    let mut keys: Vec<&String> = old.keys().chain(new.keys()).collect();
+    keys.sort();
+    keys.dedup();
+    let changes: Vec<String> = keys
+        .into_iter()
+        .filter_map(|key| match (old.get(key), new.get(key)) {
+            (Some(before), Some(after)) if before != after => Some(format!(
+                "{}: {} -> {}",
+                key,
+                short_json(before),
+                short_json(after)
+            )),
+            (None, Some(after)) => Some(format!("+{} = {}", key, short_json(after))),
+            (Some(_), None) => Some(format!("-{}", key)),
+            _ => None,
+        })
+        .collect();
+    if changes.is_empty() {
+        "no changes".to_string()
+    } else {
+        changes.join(", ")
+    }
+}
+
+/// Reload each watched config whose files changed. Returns log lines.
+fn reload_changed_configs() -> Vec<String> {
+    let Some(configs) = CONFIGS.get() else {
+        return Vec::new();
+    };
+    let mut log = Vec::new();
+    for ((home_claude, root), watched) in configs.lock().unwrap().iter_mut() {
+        let stamps = config_stamps(home_claude, root);
+        if stamps == watched.stamps {
+            continue;
+        }
+        match load_watched(home_claude, root) {
+            Ok(fresh) => {
+                log.push(format!(
+                    "Reloaded config for {}: {}",
+                    root.display(),
+                    describe_config_diff(&watched.json, &fresh.json)
+                ));
+                *watched = fresh;
+            }
+            Err(e) => {
+                log.push(format!(
+                    "Config for {} not reloaded, keeping the previous one: {:#}",
+                    root.display(),
+                    e
+                ));
+                // Don't report the same broken edit every poll
+                watched.stamps = stamps;
+            }
+        }
+    }
+    log
+}
+
+/// Hook entry points the server can run
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub(crate) enum Hook {
+    UserPromptSubmit,
+    SessionStart,
+    Stop,
+}
+
+/// One line of JSON sent by a hook to the server
+#[derive(Debug, Serialize, Deserialize)]
+struct HookRequest {
+    hook: Hook,
+    cwd: PathBuf,
+    input: String,
+}
+
+/// One line of JSON sent back; `error` set when the hook failed server-side
+#[derive(Debug, Serialize, Deserialize)]
+struct HookResponse {
+    #[serde(flatten)]
+    output: HookOutput,
+    error: Option<String>,
+}
+
+/// Run `hook` through the state server, or `None` when no server is reachable
+#[cfg(unix)]
+pub(crate) fn proxy(hook: Hook, input: &str) -> Option<HookOutput> {
+    use std::io::{BufRead, BufReader, Write};
+    use std::os::unix::net::UnixStream;
+    use std::time::Duration;
+
+    let socket = Paths::new().ok()?.server_socket_path();
+    if !socket.exists() {
+        return None;
+    }
+    let mut stream = UnixStream::connect(&socket).ok()?;
+    stream
+        .set_read_timeout(Some(Duration::from_secs(PROXY_TIMEOUT_SECS)))
+        .ok()?;
+
+    let request = HookRequest {
+        hook,
+        cwd: std::env::current_dir().ok()?,
+        input: input.to_string(),
+    };
+    let mut line = serde_json::to_string(&request).ok()?;
+    line.push('\n');
+    stream.write_all(line.as_bytes()).ok()?;
+
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply).ok()?;
+    let response: HookResponse = serde_json::from_str(&reply).ok()?;
+    match response.error {
+        Some(err) => {
+            eprintln!("[attentive] state server: {}", err);
+            None
+        }
+        None => Some(response.output),
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn proxy(_hook: Hook, _input: &str) -> Option<HookOutput> {
+    None
+}
+
+/// Serve one connection: run the requested hook in the client's directory
+#[cfg(unix)]
+fn handle(stream: std::os::unix::net::UnixStream) -> anyhow::Result<()> {
+    use std::io::{BufRead, BufReader, Write};
+
+    let mut line = String::new();
+    BufReader::new(&stream).read_line(&mut line)?;
+    let request: HookRequest = serde_json::from_str(&line)?;
+
+    let send = |output: HookOutput, error: Option<String>| -> anyhow::Result<()> {
+        let mut reply = serde_json::to_string(&HookResponse { output, error })?;
+        reply.push('\n');
+        (&stream).write_all(reply.as_bytes())?;
+        Ok(())
+    };
+
+    // Project state is resolved for the client's directory, never the server's
+    let paths = match Paths::new() {
+        Ok(paths) => paths.in_dir(&request.cwd),
+        Err(e) => return send(HookOutput::default(), Some(e.to_string())),
+    };
+
+    // The prompt hook replies as soon as its output is ready and persists
+    // after, finishing a pipeline that missed its deadline before the next
+    // connection is served
+    let mut replied = false;
+    let result = match request.hook {
+        Hook::UserPromptSubmit => {
+            super::hooks::user_prompt_submit(paths, &request.input, true, |output| {
+                replied = true;
+                send(output, None)
+            })
+        }
+        Hook::SessionStart => super::hooks::session_start(&paths).and_then(|o| send(o, None)),
+        Hook::Stop => super::hooks::stop(&paths, &request.input).and_then(|o| send(o, None)),
+    };
+    match result {
+        Err(e) if !replied => send(HookOutput::default(), Some(e.to_string())),
+        other => other,
+    }
+}
+
+#[cfg(unix)]
+pub fn run() -> anyhow::Result<()> {
+    use std::os::unix::net::{UnixListener, UnixStream};
+
+    let socket = Paths::new()?.server_socket_path();
+    if socket.exists() {
+        if UnixStream::connect(&socket).is_ok() {
+            anyhow::bail!("State server already running at {}", socket.display());
+        }
+        // Left behind by a server that didn't shut down cleanly
+        std::fs::remove_file(&socket)?;
+    }
+    if let Some(parent) = socket.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+
+    let listener = UnixListener::bind(&socket)?;
+    CACHE.get_or_init(Default::default);
+    CONFIGS.get_or_init(Default::default);
+    println!("Serving attentive state on {}", socket.display());
+
+    std::thread::spawn(|| {
+        loop {
+            std::thread::sleep(CONFIG_POLL_INTERVAL);
+            for line in reload_changed_configs() {
+                println!("[attentive] {}", line);
+            }
+        }
+    });
+
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if let Err(e) = handle(stream) {
+                    eprintln!("[attentive] state server: {:#}", e);
+                }
+            }
+            Err(e) => eprintln!("[attentive] state server: {}", e),
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub fn run() -> anyhow::Result<()> {
+    anyhow::bail!("`attentive serve` needs unix domain sockets, which this platform lacks")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_cached_reuses_until_file_changes() {
+        CACHE.get_or_init(Default::default);
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("state.json");
+        std::fs::write(&path, "1").unwrap();
+
+        let parse = |c: &str| Ok(c.trim().parse::<u32>()?);
+        assert_eq!(read_cached(&path, parse).unwrap(), 1);
+        // Served from memory: the parser isn't consulted again
+        let cached: u32 = read_cached(&path, |_| anyhow::bail!("re-parsed")).unwrap();
+        assert_eq!(cached, 1);
+
+        std::fs::write(&path, "22").unwrap();
+        assert_eq!(read_cached(&path, parse).unwrap(), 22);
+    }
+
+    #[test]
+    fn test_config_reload_swaps_valid_edits_only() {
+        CONFIGS.get_or_init(Default::default);
+        let temp = tempfile::TempDir::new().unwrap();
+        let home_claude = temp.path().join(".claude");
+        let root = temp.path().join("repo");
+        std::fs::create_dir_all(&home_claude).unwrap();
+        std::fs::create_dir_all(&root).unwrap();
+        std::fs::write(
+            home_claude.join("attentive.json"),
+            r#"{"max_hot_files": 4}"#,
+        )
+        .unwrap();
+        let project = root.join(".attentive.json");
+        std::fs::write(&project, r#"{"pinned_files": ["a.rs"]}"#).unwrap();
+
+        let config = watched_config(&home_claude, &root).unwrap();
+        assert_eq!(config.max_hot_files, 4);
+        assert_eq!(config.pinned_files, ["a.rs"]);
+
+        std::fs::write(
+            &project,
+            r#"{"max_hot_files": 6, "co_activation": {"a.rs": ["b.rs"]}}"#,
+        )
+        .unwrap();
+        let log = reload_changed_configs();
+        let line = log
+            .iter()
+            .find(|l| l.contains(&*root.to_string_lossy()))
+            .unwrap();
+        assert!(line.ends_with(
+            r#"+co_activation = {"a.rs":["b.rs"]}, max_hot_files: 4 -> 6, -pinned_files"#
+        ));
+        let config = watched_config(&home_claude, &root).unwrap();
+        assert_eq!(config.max_hot_files, 6);
+        assert_eq!(config.co_activation["a.rs"], ["b.rs"]);
+
+        // An invalid edit is reported once and the last good config stays
+        std::fs::write(&project, r#"{"max_hot_files": 6, "hot_threshold": 7.5}"#).unwrap();
+        let log = reload_changed_configs();
+        let line = log
+            .iter()
+            .find(|l| l.contains(&*root.to_string_lossy()))
+            .unwrap();
+        assert!(line.contains("not reloaded") && line.contains("hot_threshold"));
+        assert_eq!(
+            watched_config(&home_claude, &root).unwrap().max_hot_files,
+            6
+        );
+        let log = reload_changed_configs();
+        assert!(!log.iter().any(|l| l.contains(&*root.to_string_lossy())));
+    }
+
+    #[test]
+    fn test_request_round_trip() {
+        let request = HookRequest {
+            hook: Hook::UserPromptSubmit,
+            cwd: PathBuf::from("/repo"),
+            input: "{}".to_string(),
+        };
+        let json = serde_json::to_string(&request).unwrap();
+        assert!(json.contains("\"user_prompt_submit\""));
+        let back: HookRequest = serde_json::from_str(&json).unwrap();
+        assert_eq!(back.hook, Hook::UserPromptSubmit);
+    }
+}
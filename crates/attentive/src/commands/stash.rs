@@ -0,0 +1,237 @@
+//! `stash`: park a task's attention state and bring it back later
+//!
+//! Each stash is a directory under the project's `stash/` holding a copy of
+//! `attn_state.json` and, when saved with `--plugins`, the plugin state files.
+
+use attentive_core::AttentionState;
+use attentive_telemetry::{Paths, read_versioned, write_versioned};
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+const STATE_FILE: &str = "attn_state.json";
+const PLUGINS_DIR: &str = "plugins";
+
+/// Stash names become directory names, so keep them to a safe alphabet
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    let valid = !name.is_empty()
+        && !name.starts_with('.')
+        && name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || "._-".contains(c));
+    if !valid {
+        anyhow::bail!(
+            "invalid stash name '{}': use letters, digits, '.', '_' or '-'",
+            name
+        );
+    }
+    Ok(())
+}
+
+fn stash_path(paths: &Paths, name: &str) -> anyhow::Result<PathBuf> {
+    validate_name(name)?;
+    Ok(paths.stash_dir()?.join(name))
+}
+
+/// Plugin `*_state.json` files in `dir`
+fn plugin_state_files(dir: &Path) -> Vec<PathBuf> {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<PathBuf> = entries
+        .flatten()
+        .map(|e| e.path())
+        .filter(|p| {
+            p.is_file()
+                && p.file_name()
+                    .and_then(|n| n.to_str())
+                    .is_some_and(|n| n.ends_with("_state.json"))
+        })
+        .collect();
+    files.sort();
+    files
+}
+
+fn copy_files(files: &[PathBuf], to: &Path) -> anyhow::Result<()> {
+    std::fs::create_dir_all(to)?;
+    for file in files {
+        if let Some(name) = file.file_name() {
+            std::fs::copy(file, to.join(name))?;
+        }
+    }
+    Ok(())
+}
+
+/// Snapshot the current attention state (and plugin state with `plugins`)
+fn save(paths: &Paths, name: &str, plugins: bool) -> anyhow::Result<String> {
+    let dir = stash_path(paths, name)?;
+    if dir.exists() {
+        anyhow::bail!(
+            "stash '{}' already exists; pop it or pick another name",
+            name
+        );
+    }
+    let state = super::hooks::load_attention_state(paths)?;
+    std::fs::create_dir_all(&dir)?;
+    write_versioned(&dir.join(STATE_FILE), &state)?;
+    if plugins {
+        let files = plugin_state_files(&paths.home_claude.join(PLUGINS_DIR));
+        copy_files(&files, &dir.join(PLUGINS_DIR))?;
+    }
+    Ok(format!(
+        "Stashed attention state as '{}' ({} HOT, {} WARM{})",
+        name,
+        state.get_hot_files().len(),
+        state.get_warm_files().len(),
+        if plugins { ", with plugin state" } else { "" }
+    ))
+}
+
+/// Replace the current attention state with the stash and delete it
+fn pop(paths: &Paths, name: &str) -> anyhow::Result<String> {
+    let dir = stash_path(paths, name)?;
+    if !dir.exists() {
+        anyhow::bail!("no stash named '{}' (see `attentive stash list`)", name);
+    }
+    let state: AttentionState = read_versioned(&dir.join(STATE_FILE))?;
+    let state_path = paths.attn_state_path()?;
+    if let Some(parent) = state_path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    write_versioned(&state_path, &state)?;
+
+    let plugin_files = plugin_state_files(&dir.join(PLUGINS_DIR));
+    copy_files(&plugin_files, &paths.home_claude.join(PLUGINS_DIR))?;
+    std::fs::remove_dir_all(&dir)?;
+    Ok(format!(
+        "Restored '{}' ({} HOT, {} WARM{}); the previous attention state was replaced",
+        name,
+        state.get_hot_files().len(),
+        state.get_warm_files().len(),
+        if plugin_files.is_empty() {
+            ""
+        } else {
+            ", with plugin state"
+        }
+    ))
+}
+
+fn list(paths: &Paths) -> anyhow::Result<String> {
+    let Ok(entries) = std::fs::read_dir(paths.stash_dir()?) else {
+        return Ok("No stashes. Save one with `attentive stash save <name>`.".to_string());
+    };
+    let mut stashes: Vec<(SystemTime, String)> = entries
+        .flatten()
+        .filter(|e| e.path().join(STATE_FILE).exists())
+        .map(|e| {
+            let saved = std::fs::metadata(e.path().join(STATE_FILE))
+                .and_then(|m| m.modified())
+                .unwrap_or(SystemTime::UNIX_EPOCH);
+            (saved, e.file_name().to_string_lossy().to_string())
+        })
+        .collect();
+    if stashes.is_empty() {
+        return Ok("No stashes. Save one with `attentive stash save <name>`.".to_string());
+    }
+    // Newest first
+    stashes.sort_by(|a, b| b.cmp(a));
+
+    let mut lines = vec![format!("Stashes ({})", stashes.len())];
+    for (saved, name) in stashes {
+        let dir = paths.stash_dir()?.join(&name);
+        let summary = match read_versioned::<AttentionState>(&dir.join(STATE_FILE)) {
+            Ok(state) => format!(
+                "{} HOT, {} WARM, turn {}",
+                state.get_hot_files().len(),
+                state.get_warm_files().len(),
+                state.turn_count
+            ),
+            Err(e) => format!("unreadable: {}", e),
+        };
+        let saved: chrono::DateTime<chrono::Local> = saved.into();
+        let plugins = if dir.join(PLUGINS_DIR).exists() {
+            " +plugins"
+        } else {
+            ""
+        };
+        lines.push(format!(
+            "  {}  {}  ({}){}",
+            name,
+            saved.format("%Y-%m-%d %H:%M"),
+            summary,
+            plugins
+        ));
+    }
+    Ok(lines.join("\n"))
+}
+
+pub fn run_save(name: &str, plugins: bool) -> anyhow::Result<()> {
+    println!("{}", save(&Paths::new()?, name, plugins)?);
+    Ok(())
+}
+
+pub fn run_pop(name: &str) -> anyhow::Result<()> {
+    println!("{}", pop(&Paths::new()?, name)?);
+    Ok(())
+}
+
+pub fn run_list() -> anyhow::Result<()> {
+    println!("{}", list(&Paths::new()?)?);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_telemetry::TestEnv;
+
+    fn state_with(file: &str, score: f64) -> AttentionState {
+        let mut state = AttentionState::new();
+        state.scores.insert(file.to_string(), score);
+        state
+    }
+
+    #[test]
+    fn test_save_and_pop_round_trip() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        let state_path = paths.attn_state_path().unwrap();
+        std::fs::create_dir_all(state_path.parent().unwrap()).unwrap();
+        write_versioned(&state_path, &state_with("src/bug.rs", 0.9)).unwrap();
+        let plugin_state = paths.home_claude.join("plugins/loopbreaker_state.json");
+        std::fs::create_dir_all(plugin_state.parent().unwrap()).unwrap();
+        std::fs::write(&plugin_state, "{\"bug\": true}").unwrap();
+
+        let saved = save(&paths, "bugfix", true).unwrap();
+        assert!(
+            saved.contains("1 HOT, 0 WARM, with plugin state"),
+            "{}",
+            saved
+        );
+        assert!(save(&paths, "bugfix", false).is_err(), "names are unique");
+        assert!(list(&paths).unwrap().contains("bugfix"));
+
+        // Work on something else
+        write_versioned(&state_path, &state_with("src/feature.rs", 0.5)).unwrap();
+        std::fs::write(&plugin_state, "{\"bug\": false}").unwrap();
+
+        pop(&paths, "bugfix").unwrap();
+        let restored: AttentionState = read_versioned(&state_path).unwrap();
+        assert_eq!(restored.scores, state_with("src/bug.rs", 0.9).scores);
+        assert_eq!(
+            std::fs::read_to_string(&plugin_state).unwrap(),
+            "{\"bug\": true}"
+        );
+        assert!(pop(&paths, "bugfix").is_err(), "pop removes the stash");
+        assert!(list(&paths).unwrap().starts_with("No stashes"));
+    }
+
+    #[test]
+    fn test_stash_names_are_validated() {
+        let env = TestEnv::new();
+        let paths = env.paths();
+        for name in ["", "../escape", ".hidden", "a/b"] {
+            assert!(save(&paths, name, false).is_err(), "{:?}", name);
+        }
+        assert!(save(&paths, "feature-2.x_a", false).is_ok());
+    }
+}
@@ -1,17 +1,243 @@
-use std::collections::HashSet;
+use std::collections::{HashMap, HashSet};
 use std::io::{BufRead, BufReader};
+use std::path::Path;
 
-use attentive_core::AttentionState;
-use attentive_telemetry::Paths;
+use attentive_core::{AttentionState, Tier};
+use attentive_repo::RepoMapper;
+use attentive_telemetry::{PathCanonicalizer, Paths};
+use serde::Serialize;
 
-pub fn run(session: Option<&str>) -> anyhow::Result<()> {
+/// Format version of `status --export-context`, bumped on incompatible changes
+pub const CONTEXT_EXPORT_VERSION: u32 = 1;
+
+/// One HOT or WARM file in the exported routing decision
+#[derive(Debug, Serialize)]
+struct ExportedFile {
+    path: String,
+    tier: Tier,
+    score: f64,
+    /// Consecutive turns at HOT or WARM
+    streak: usize,
+}
+
+/// Where external tools report active files, and what the router does with them
+#[derive(Debug, Serialize)]
+struct ActiveFilesExport {
+    path: String,
+    boost: f64,
+    /// Boost for the focused file
+    active_boost: f64,
+    /// Files currently listed there
+    files: Vec<String>,
+    active: Option<String>,
+}
+
+/// `status --export-context` output
+#[derive(Debug, Serialize)]
+struct ContextExport {
+    version: u32,
+    /// Repo root that relative paths are under
+    project: String,
+    /// Prompts routed so far
+    turn: usize,
+    /// HOT then WARM files, by score
+    files: Vec<ExportedFile>,
+    active_files: ActiveFilesExport,
+}
+
+/// The routing decision held in `state`
+fn build_context_export(
+    state: &AttentionState,
+    root: &Path,
+    active_files: ActiveFilesExport,
+) -> ContextExport {
+    let mut files: Vec<ExportedFile> = state
+        .scores
+        .iter()
+        .filter_map(|(path, &score)| {
+            let tier = Tier::from_score(score);
+            (tier != Tier::Cold).then(|| ExportedFile {
+                path: path.clone(),
+                tier,
+                score,
+                streak: state.consecutive_turns.get(path).copied().unwrap_or(0),
+            })
+        })
+        .collect();
+    files.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    ContextExport {
+        version: CONTEXT_EXPORT_VERSION,
+        project: root.to_string_lossy().into_owned(),
+        turn: state.turn_count,
+        files,
+        active_files,
+    }
+}
+
+/// Print the current routing decision as JSON for external tools
+fn run_export_context(paths: &Paths) -> anyhow::Result<()> {
+    let state = super::hooks::load_attention_state(paths)?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let config = super::hooks::load_config(&paths.home_claude);
+    let scope = super::hooks::path_scope(paths, &config);
+    let active_path = paths.active_files_path()?;
+    let active = super::active_files::load(&active_path, &canonicalizer, &scope);
+    let active_files = ActiveFilesExport {
+        path: active_path.to_string_lossy().into_owned(),
+        boost: config.active_file_boost,
+        active_boost: config.active_buffer_boost,
+        files: active.files,
+        active: active.active,
+    };
+    let export = build_context_export(&state, canonicalizer.root(), active_files);
+    println!("{}", serde_json::to_string_pretty(&export)?);
+    Ok(())
+}
+
+/// What `status --files` shows for one HOT or WARM file
+#[derive(Debug, Clone, PartialEq)]
+struct FileStats {
+    path: String,
+    tier: Tier,
+    score: f64,
+    /// None for files the symbol extractor doesn't parse
+    language: Option<String>,
+    symbols: usize,
+    lines: usize,
+    tokens: usize,
+    /// Share of the repo's files ranked at or below this one, 0-100
+    rank_percentile: Option<f64>,
+    /// Consecutive turns at HOT
+    streak: usize,
+}
+
+/// PageRank percentile of every file in `mapper`
+fn rank_percentiles(mapper: &RepoMapper) -> HashMap<String, f64> {
+    let ranks = mapper.page_rank();
+    let mut sorted: Vec<f64> = ranks.values().copied().collect();
+    sorted.sort_by(f64::total_cmp);
+    ranks
+        .into_iter()
+        .map(|(path, rank)| {
+            let at_or_below = sorted.partition_point(|r| *r <= rank);
+            (path, at_or_below as f64 * 100.0 / sorted.len() as f64)
+        })
+        .collect()
+}
+
+/// Stats for the HOT and WARM files of `state`, HOT first, by score.
+/// Relative paths are read from `root`; `mapper` covers the repo.
+fn collect_file_stats(state: &AttentionState, root: &Path, mapper: &RepoMapper) -> Vec<FileStats> {
+    let percentiles = rank_percentiles(mapper);
+    let mut stats: Vec<FileStats> = state
+        .scores
+        .iter()
+        .filter_map(|(path, &score)| {
+            let tier = state.get_tier(path)?;
+            if tier == Tier::Cold {
+                return None;
+            }
+            let content = std::fs::read_to_string(root.join(path)).unwrap_or_default();
+            let symbols = mapper.get_symbols(path);
+            Some(FileStats {
+                path: path.clone(),
+                tier,
+                score,
+                language: symbols.map(|s| s.language.clone()),
+                symbols: symbols.map_or(0, |s| s.symbols.len()),
+                lines: content.lines().count(),
+                tokens: attentive_telemetry::estimate_tokens(&content),
+                rank_percentile: percentiles.get(path).copied(),
+                streak: state.consecutive_turns.get(path).copied().unwrap_or(0),
+            })
+        })
+        .collect();
+    stats.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    stats
+}
+
+fn format_file_stats(stats: &[FileStats]) -> String {
+    if stats.is_empty() {
+        return "No HOT or WARM files".to_string();
+    }
+    let width = stats.iter().map(|s| s.path.len()).max().unwrap_or(0);
+    let mut lines = vec![format!(
+        "{:<width$}  {:<4}  {:>5}  {:<10}  {:>7}  {:>8}  {:>6}  {:>4}  {:>6}",
+        "file",
+        "tier",
+        "score",
+        "language",
+        "symbols",
+        "sym/100L",
+        "tokens",
+        "rank",
+        "streak",
+        width = width
+    )];
+    for s in stats {
+        let tier = match s.tier {
+            Tier::Hot => "HOT",
+            _ => "WARM",
+        };
+        let density = if s.lines > 0 && s.language.is_some() {
+            format!("{:.1}", s.symbols as f64 * 100.0 / s.lines as f64)
+        } else {
+            "-".to_string()
+        };
+        let rank = s
+            .rank_percentile
+            .map_or("-".to_string(), |p| format!("p{:.0}", p));
+        lines.push(format!(
+            "{:<width$}  {:<4}  {:>5.2}  {:<10}  {:>7}  {:>8}  {:>6}  {:>4}  {:>6}",
+            s.path,
+            tier,
+            s.score,
+            s.language.as_deref().unwrap_or("-"),
+            s.symbols,
+            density,
+            s.tokens,
+            rank,
+            s.streak,
+            width = width
+        ));
+    }
+    lines.join("\n")
+}
+
+/// Per-file table for the HOT and WARM files of the current project
+fn run_files(paths: &Paths) -> anyhow::Result<()> {
+    let state = super::hooks::load_attention_state(paths)?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let root = canonicalizer.root();
+    let config = super::hooks::load_config(&paths.home_claude);
+    let mapper = RepoMapper::load_cached(root, &config.exclude_patterns())?;
+    println!(
+        "{}",
+        format_file_stats(&collect_file_stats(&state, root, &mapper))
+    );
+    Ok(())
+}
+
+pub fn run(session: Option<&str>, files: bool, export_context: bool) -> anyhow::Result<()> {
     let paths = Paths::new()?;
+    if files {
+        return run_files(&paths);
+    }
+    if export_context {
+        return run_export_context(&paths);
+    }
     let state_path = paths.attn_state_path()?;
 
     let state: Option<AttentionState> = if state_path.exists() {
-        std::fs::read_to_string(&state_path)
-            .ok()
-            .and_then(|c| serde_json::from_str(&c).ok())
+        attentive_telemetry::read_versioned(&state_path).ok()
     } else {
         None
     };
@@ -108,6 +334,98 @@ fn compute_hit_rate_from_transcript(
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_file_stats() {
+        let dir = tempfile::tempdir().unwrap();
+        let root = dir.path();
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::write(
+            root.join("src/router.rs"),
+            "pub fn route() {}\npub struct Router;\n",
+        )
+        .unwrap();
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "use crate::router::route;\npub fn run() {}\n",
+        )
+        .unwrap();
+        std::fs::write(root.join("NOTES.md"), "notes\n").unwrap();
+        let mut mapper = RepoMapper::new();
+        for rel in ["src/router.rs", "src/lib.rs", "NOTES.md"] {
+            mapper.add_file(rel, &std::fs::read_to_string(root.join(rel)).unwrap());
+        }
+
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.9);
+        state.scores.insert("NOTES.md".to_string(), 0.3);
+        state.scores.insert("src/lib.rs".to_string(), 0.1);
+        state
+            .consecutive_turns
+            .insert("src/router.rs".to_string(), 4);
+
+        let stats = collect_file_stats(&state, root, &mapper);
+        assert_eq!(stats.len(), 2);
+        let router = &stats[0];
+        assert_eq!(router.tier, Tier::Hot);
+        assert_eq!(router.language.as_deref(), Some("rust"));
+        assert_eq!((router.symbols, router.lines, router.streak), (2, 2, 4));
+        // Imported by lib.rs, so it outranks it
+        assert_eq!(router.rank_percentile, Some(100.0));
+        assert_eq!(stats[1].language, None);
+        assert_eq!(stats[1].rank_percentile, None);
+
+        let table = format_file_stats(&stats);
+        assert!(table.contains("src/router.rs  HOT    0.90  rust"));
+        assert!(table.contains("p100"));
+        assert!(
+            table
+                .lines()
+                .nth(2)
+                .unwrap()
+                .starts_with("NOTES.md       WARM")
+        );
+    }
+
+    #[test]
+    fn test_context_export() {
+        let mut state = AttentionState::new();
+        state.scores.insert("src/router.rs".to_string(), 0.9);
+        state.scores.insert("README.md".to_string(), 0.3);
+        state.scores.insert("src/lib.rs".to_string(), 0.1);
+        state
+            .consecutive_turns
+            .insert("src/router.rs".to_string(), 2);
+        state.turn_count = 7;
+        let active_files = ActiveFilesExport {
+            path: "/state/active_files.json".to_string(),
+            boost: 0.5,
+            active_boost: 0.8,
+            files: vec!["src/lib.rs".to_string()],
+            active: None,
+        };
+
+        let export = build_context_export(&state, Path::new("/repo"), active_files);
+        assert_eq!(
+            serde_json::to_value(&export).unwrap(),
+            serde_json::json!({
+                "version": CONTEXT_EXPORT_VERSION,
+                "project": "/repo",
+                "turn": 7,
+                "files": [
+                    {"path": "src/router.rs", "tier": "HOT", "score": 0.9, "streak": 2},
+                    {"path": "README.md", "tier": "WARM", "score": 0.3, "streak": 0},
+                ],
+                "active_files": {
+                    "path": "/state/active_files.json",
+                    "boost": 0.5,
+                    "active_boost": 0.8,
+                    "files": ["src/lib.rs"],
+                    "active": null,
+                },
+            })
+        );
+    }
+
     #[test]
     fn test_hit_rate_empty() {
         let injected = HashSet::new();
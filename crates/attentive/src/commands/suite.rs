@@ -0,0 +1,530 @@
+use attentive_core::AttentionState;
+use attentive_index::SearchIndex;
+use attentive_telemetry::{PathCanonicalizer, Paths, estimate_tokens};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::path::Path;
+
+use super::hooks::{load_config, load_learner, run_prompt_pipeline};
+
+/// Most recent prompts replayed when the suite comes from ingested sessions
+const MAX_SESSION_CASES: usize = 200;
+
+/// A recorded prompt and the files it actually needed
+#[derive(Debug, Clone, Deserialize)]
+pub(crate) struct SuiteCase {
+    pub prompt: String,
+    pub expected: Vec<String>,
+}
+
+/// YAML suite file: `name` plus a list of `{prompt, expected}` cases
+#[derive(Debug, Deserialize)]
+struct SuiteFile {
+    #[serde(default)]
+    name: Option<String>,
+    cases: Vec<SuiteCase>,
+}
+
+/// What a simple router would have injected, to compare the pipeline with
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Baseline {
+    /// Top BM25 matches for the prompt from the project's search index
+    Bm25,
+    /// Files used in the most recent earlier cases
+    RecentFiles,
+    /// Nothing at all
+    None,
+}
+
+impl Baseline {
+    fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "bm25" => Some(Baseline::Bm25),
+            "recent-files" => Some(Baseline::RecentFiles),
+            "none" => Some(Baseline::None),
+            _ => None,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Baseline::Bm25 => "bm25",
+            Baseline::RecentFiles => "recent-files",
+            Baseline::None => "none",
+        }
+    }
+}
+
+/// Replays cases through a baseline. Each picks up to `limit` files (the
+/// HOT and WARM limits together) and injects them whole.
+struct BaselineRunner {
+    baseline: Baseline,
+    index: Option<SearchIndex>,
+    /// Files used in earlier cases, most recent first
+    recent: Vec<String>,
+    limit: usize,
+}
+
+impl BaselineRunner {
+    fn new(baseline: Baseline, index: Option<SearchIndex>, limit: usize) -> Self {
+        Self {
+            baseline,
+            index,
+            recent: Vec::new(),
+            limit,
+        }
+    }
+
+    fn inject(&self, prompt: &str) -> Vec<String> {
+        match self.baseline {
+            Baseline::Bm25 => self
+                .index
+                .as_ref()
+                .map(|index| index.query_lexical(prompt, self.limit))
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, score)| *score > 0.0)
+                .map(|(path, _)| path)
+                .collect(),
+            Baseline::RecentFiles => self.recent.iter().take(self.limit).cloned().collect(),
+            Baseline::None => Vec::new(),
+        }
+    }
+
+    /// Note the files a case actually used
+    fn observe(&mut self, used: &[String]) {
+        for file in used.iter().rev() {
+            self.recent.retain(|f| f != file);
+            self.recent.insert(0, file.clone());
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+struct CaseResult {
+    prompt: String,
+    injected: usize,
+    expected: usize,
+    hits: usize,
+    precision: f64,
+    recall: f64,
+    /// Estimated tokens injected
+    tokens: usize,
+}
+
+#[derive(Debug, Serialize)]
+struct SuiteReport {
+    name: String,
+    cases: usize,
+    /// Micro-averaged over all cases
+    precision: f64,
+    recall: f64,
+    f1: f64,
+    /// Share of cases where at least one injected file was used
+    hit_rate: f64,
+    tokens: usize,
+    results: Vec<CaseResult>,
+    /// The same cases through the `--against` baseline
+    #[serde(skip_serializing_if = "Option::is_none")]
+    against: Option<Box<SuiteReport>>,
+}
+
+fn ratio(num: usize, den: usize) -> f64 {
+    if den == 0 {
+        0.0
+    } else {
+        num as f64 / den as f64
+    }
+}
+
+/// Score one case: precision of injected files, recall of expected files
+fn score_case(
+    prompt: &str,
+    injected: &[String],
+    expected: &[String],
+    tokens: usize,
+    canonicalizer: &PathCanonicalizer,
+) -> CaseResult {
+    let injected: HashSet<String> = injected.iter().map(|f| canonicalizer.key(f)).collect();
+    let expected: HashSet<String> = expected.iter().map(|f| canonicalizer.key(f)).collect();
+    let hits = injected.intersection(&expected).count();
+    CaseResult {
+        prompt: prompt.to_string(),
+        injected: injected.len(),
+        expected: expected.len(),
+        hits,
+        precision: ratio(hits, injected.len()),
+        recall: ratio(hits, expected.len()),
+        tokens,
+    }
+}
+
+fn summarize(name: String, results: Vec<CaseResult>) -> SuiteReport {
+    let hits: usize = results.iter().map(|r| r.hits).sum();
+    let precision = ratio(hits, results.iter().map(|r| r.injected).sum());
+    let recall = ratio(hits, results.iter().map(|r| r.expected).sum());
+    let f1 = if precision + recall > 0.0 {
+        2.0 * precision * recall / (precision + recall)
+    } else {
+        0.0
+    };
+    SuiteReport {
+        name,
+        cases: results.len(),
+        precision,
+        recall,
+        f1,
+        hit_rate: ratio(results.iter().filter(|r| r.hits > 0).count(), results.len()),
+        tokens: results.iter().map(|r| r.tokens).sum(),
+        results,
+        against: None,
+    }
+}
+
+/// The pipeline's totals beside a baseline's, and how they differ
+fn format_comparison(report: &SuiteReport, baseline: &SuiteReport) -> String {
+    let width = baseline.name.len().max(9);
+    let row = |label: &str, ours: String, theirs: String| {
+        format!(
+            "  {:<10} {:>9}  {:>width$}",
+            label,
+            ours,
+            theirs,
+            width = width
+        )
+    };
+    let pct = |v: f64| format!("{:.1}%", v * 100.0);
+    let mut lines = vec![
+        format!("Against {}", baseline.name),
+        row("", "attentive".to_string(), baseline.name.clone()),
+        row("Precision", pct(report.precision), pct(baseline.precision)),
+        row("Recall", pct(report.recall), pct(baseline.recall)),
+        row(
+            "F1",
+            format!("{:.3}", report.f1),
+            format!("{:.3}", baseline.f1),
+        ),
+        row("Hit rate", pct(report.hit_rate), pct(baseline.hit_rate)),
+        row(
+            "Tokens",
+            report.tokens.to_string(),
+            baseline.tokens.to_string(),
+        ),
+    ];
+    let tokens = if baseline.tokens == 0 {
+        format!("{} more tokens", report.tokens)
+    } else {
+        let change = (report.tokens as f64 / baseline.tokens as f64 - 1.0) * 100.0;
+        format!("{:+.1}% tokens", change)
+    };
+    lines.push(format!(
+        "Attentive: {:+.1} points hit rate, {:+.1} points recall, {}",
+        (report.hit_rate - baseline.hit_rate) * 100.0,
+        (report.recall - baseline.recall) * 100.0,
+        tokens
+    ));
+    lines.join("\n")
+}
+
+fn format_report(report: &SuiteReport) -> String {
+    let mut lines = vec![
+        format!("Routing Suite: {}", report.name),
+        "=".repeat(15 + report.name.len()),
+    ];
+    for r in &report.results {
+        let mut prompt: String = r.prompt.chars().take(50).collect();
+        if r.prompt.chars().count() > 50 {
+            prompt.push('…');
+        }
+        lines.push(format!(
+            "  P {:>5.1}%  R {:>5.1}%  {}/{} hit  {}",
+            r.precision * 100.0,
+            r.recall * 100.0,
+            r.hits,
+            r.expected,
+            prompt
+        ));
+    }
+    lines.push(String::new());
+    lines.push(format!("Cases: {}", report.cases));
+    lines.push(format!("Precision: {:.1}%", report.precision * 100.0));
+    lines.push(format!("Recall: {:.1}%", report.recall * 100.0));
+    lines.push(format!("F1: {:.3}", report.f1));
+    lines.push(format!("Hit rate: {:.1}%", report.hit_rate * 100.0));
+    lines.push(format!("Tokens: {}", report.tokens));
+    if let Some(baseline) = &report.against {
+        lines.push(String::new());
+        lines.push(format_comparison(report, baseline));
+    }
+    lines.join("\n")
+}
+
+pub(crate) fn load_suite_file(path: &Path) -> anyhow::Result<(String, Vec<SuiteCase>)> {
+    let content = std::fs::read_to_string(path)?;
+    let suite: SuiteFile = serde_yaml::from_str(&content)?;
+    let name = suite.name.unwrap_or_else(|| path.display().to_string());
+    Ok((name, suite.cases))
+}
+
+/// Prompt/file pairs from the project's ingestable sessions, oldest first
+fn load_session_cases(paths: &Paths) -> anyhow::Result<Vec<SuiteCase>> {
+    let mut sessions = super::ingest::discover_session_files(&paths.project_dir()?);
+    sessions.sort_by_key(|p| std::fs::metadata(p).and_then(|m| m.modified()).ok());
+
+    let mut cases: Vec<SuiteCase> = Vec::new();
+    for session in &sessions {
+        if let Ok((pairs, _, _)) = super::transcript::parse_transcript(session) {
+            cases.extend(
+                pairs
+                    .into_iter()
+                    .filter(|(_, files)| !files.is_empty())
+                    .map(|(prompt, expected)| SuiteCase { prompt, expected }),
+            );
+        }
+    }
+    let skip = cases.len().saturating_sub(MAX_SESSION_CASES);
+    Ok(cases.split_off(skip))
+}
+
+/// Estimated tokens of `files` injected whole; unreadable files count nothing
+fn file_tokens(files: &[String], root: &Path, cache: &mut HashMap<String, usize>) -> usize {
+    files
+        .iter()
+        .map(|file| {
+            *cache.entry(file.clone()).or_insert_with(|| {
+                std::fs::read_to_string(root.join(file))
+                    .map(|content| estimate_tokens(&content))
+                    .unwrap_or(0)
+            })
+        })
+        .sum()
+}
+
+/// Replay recorded prompts through the routing pipeline (nothing is persisted)
+/// and report precision/recall of injected vs. actually-used files, next to
+/// those of the `against` baseline if given.
+pub fn run(suite: Option<&str>, json: bool, against: Option<&str>) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    attentive_plugins::base::set_read_only(true);
+    let baseline = against
+        .map(|name| {
+            Baseline::from_name(name).ok_or_else(|| anyhow::anyhow!("unknown baseline '{}'", name))
+        })
+        .transpose()?;
+
+    let (name, cases) = match suite {
+        Some(file) => load_suite_file(Path::new(file))?,
+        None => ("ingested sessions".to_string(), load_session_cases(&paths)?),
+    };
+    if cases.is_empty() {
+        println!("No suite cases found");
+        return Ok(());
+    }
+
+    let config = load_config(&paths.home_claude);
+    let learner = load_learner(&paths.learned_state_path()?)
+        .map(|l| l.with_half_life(config.learner_half_life));
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let oracle = attentive_learn::Oracle::new();
+    let mut runner = match baseline {
+        Some(baseline) => {
+            let index = if baseline == Baseline::Bm25 {
+                let index_path = paths.search_index_path()?;
+                if !index_path.exists() {
+                    anyhow::bail!(
+                        "No search index for this project. Run attentive init --project first."
+                    );
+                }
+                Some(SearchIndex::new(index_path)?)
+            } else {
+                None
+            };
+            let limit = config.max_hot_files + config.max_warm_files;
+            Some(BaselineRunner::new(baseline, index, limit))
+        }
+        None => None,
+    };
+    let mut token_cache = HashMap::new();
+
+    // Cases run in order against one evolving state, like a session
+    let mut state = AttentionState::new();
+    let mut results = Vec::new();
+    let mut baseline_results = Vec::new();
+    for case in &cases {
+        let mut case_config = config.clone();
+        case_config.apply_task_profile(oracle.classify_task(&case.prompt).as_str());
+        let (injected, tokens) = match run_prompt_pipeline(
+            &paths,
+            case.prompt.clone(),
+            &mut state,
+            case_config,
+            learner.as_ref(),
+            None,
+        )? {
+            Some(pipeline) => {
+                let tokens = estimate_tokens(&pipeline.full_context());
+                let mut files = pipeline.hot_files;
+                files.extend(pipeline.warm_files);
+                (files, tokens)
+            }
+            None => (Vec::new(), 0),
+        };
+        results.push(score_case(
+            &case.prompt,
+            &injected,
+            &case.expected,
+            tokens,
+            &canonicalizer,
+        ));
+
+        if let Some(runner) = runner.as_mut() {
+            let injected = runner.inject(&case.prompt);
+            let tokens = file_tokens(&injected, canonicalizer.root(), &mut token_cache);
+            baseline_results.push(score_case(
+                &case.prompt,
+                &injected,
+                &case.expected,
+                tokens,
+                &canonicalizer,
+            ));
+            runner.observe(&case.expected);
+        }
+    }
+
+    let mut report = summarize(name, results);
+    if let Some(baseline) = baseline {
+        let baseline_report = summarize(baseline.name().to_string(), baseline_results);
+        report.against = Some(Box::new(baseline_report));
+    }
+    if json {
+        println!("{}", serde_json::to_string_pretty(&report)?);
+    } else {
+        println!("{}", format_report(&report));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_case() {
+        let canon = PathCanonicalizer::new("/repo");
+        let injected = vec!["src/a.rs".to_string(), "/repo/src/b.rs".to_string()];
+        let expected = vec!["/repo/src/a.rs".to_string(), "src/c.rs".to_string()];
+        let result = score_case("p", &injected, &expected, 40, &canon);
+        assert_eq!(result.hits, 1);
+        assert!((result.precision - 0.5).abs() < f64::EPSILON);
+        assert!((result.recall - 0.5).abs() < f64::EPSILON);
+    }
+
+    #[test]
+    fn test_summarize_micro_average() {
+        let canon = PathCanonicalizer::new("/repo");
+        let results = vec![
+            score_case(
+                "a",
+                &["x.rs".to_string()],
+                &["x.rs".to_string()],
+                10,
+                &canon,
+            ),
+            score_case(
+                "b",
+                &["y.rs".to_string(), "z.rs".to_string()],
+                &["w.rs".to_string()],
+                20,
+                &canon,
+            ),
+        ];
+        let report = summarize("t".to_string(), results);
+        assert!((report.precision - 1.0 / 3.0).abs() < 1e-9);
+        assert!((report.recall - 0.5).abs() < 1e-9);
+        assert!((report.hit_rate - 0.5).abs() < 1e-9);
+        assert_eq!(report.tokens, 30);
+        assert!(format_report(&report).contains("Cases: 2"));
+    }
+
+    #[test]
+    fn test_recent_files_baseline() {
+        let mut runner = BaselineRunner::new(Baseline::RecentFiles, None, 2);
+        assert!(runner.inject("anything").is_empty());
+        runner.observe(&["a.rs".to_string(), "b.rs".to_string()]);
+        runner.observe(&["c.rs".to_string(), "a.rs".to_string()]);
+        assert_eq!(runner.inject("anything"), ["c.rs", "a.rs"]);
+
+        let mut none = BaselineRunner::new(Baseline::None, None, 2);
+        none.observe(&["a.rs".to_string()]);
+        assert!(none.inject("anything").is_empty());
+    }
+
+    #[test]
+    fn test_bm25_baseline_and_file_tokens() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let mut index = SearchIndex::new(temp.path().join("index.db")).unwrap();
+        let doc = |path: &str, content: &str| attentive_index::Document {
+            path: path.to_string(),
+            content: content.to_string(),
+            mtime: 1.0,
+            doc_type: "code".to_string(),
+        };
+        index
+            .build(vec![
+                doc("src/decay.rs", "fn apply_decay() { scores }"),
+                doc("src/net.rs", "fn open_socket() {}"),
+            ])
+            .unwrap();
+        let runner = BaselineRunner::new(Baseline::Bm25, Some(index), 5);
+        assert_eq!(runner.inject("tune the decay"), ["src/decay.rs"]);
+
+        std::fs::create_dir_all(temp.path().join("src")).unwrap();
+        std::fs::write(temp.path().join("src/decay.rs"), "x".repeat(400)).unwrap();
+        let mut cache = HashMap::new();
+        let files = ["src/decay.rs".to_string(), "src/gone.rs".to_string()];
+        assert_eq!(
+            file_tokens(&files, temp.path(), &mut cache),
+            estimate_tokens(&"x".repeat(400))
+        );
+    }
+
+    #[test]
+    fn test_format_comparison() {
+        let canon = PathCanonicalizer::new("/repo");
+        let x = ["x.rs".to_string()];
+        let mut report = summarize("t".to_string(), vec![score_case("a", &x, &x, 100, &canon)]);
+        let baseline = summarize(
+            "bm25".to_string(),
+            vec![score_case("a", &["y.rs".to_string()], &x, 400, &canon)],
+        );
+        let comparison = format_comparison(&report, &baseline);
+        assert!(comparison.starts_with("Against bm25"));
+        assert!(comparison.contains("  Hit rate      100.0%       0.0%"));
+        assert!(
+            comparison.ends_with(
+                "Attentive: +100.0 points hit rate, +100.0 points recall, -75.0% tokens"
+            )
+        );
+
+        report.against = Some(Box::new(baseline));
+        assert!(format_report(&report).contains("\nAgainst bm25\n"));
+        let json = serde_json::to_value(&report).unwrap();
+        assert_eq!(json["against"]["tokens"], 400);
+    }
+
+    #[test]
+    fn test_load_suite_file() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("suite.yaml");
+        std::fs::write(
+            &path,
+            "name: routing\ncases:\n  - prompt: fix decay\n    expected: [src/router.rs]\n",
+        )
+        .unwrap();
+
+        let (name, cases) = load_suite_file(&path).unwrap();
+        assert_eq!(name, "routing");
+        assert_eq!(cases.len(), 1);
+        assert_eq!(cases[0].expected, vec!["src/router.rs"]);
+    }
+}
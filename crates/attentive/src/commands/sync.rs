@@ -0,0 +1,542 @@
+//! `sync`: carry a project's learned state between machines
+//!
+//! `push` and `pull` exchange the learner (`learned_state.json` with its word
+//! index), `attn_state.json` and the observation database with the backend
+//! configured under `sync`: a branch of a git repository or an S3 bucket,
+//! driven through the `git` and `aws` CLIs so their usual credentials apply.
+//!
+//! The remote holds one directory per project, named after the repo's origin
+//! URL so clones on different machines meet, plus the shared observations.
+//! Learner and attention state are merged into the other side's, keeping the
+//! larger of each count and score, so syncing the same state twice changes
+//! nothing; `--force` overwrites the other side instead. Observations are
+//! merged, each side gaining the ones it lacks.
+
+use super::hooks::{load_config, save_learner, try_load_learner};
+use crate::error::ErrorKind;
+use attentive_compress::ObservationDb;
+use attentive_core::{AttentionState, SyncBackend};
+use attentive_telemetry::{Paths, read_versioned, write_versioned};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const LEARNER_FILE: &str = "learned_state.json";
+const ATTENTION_FILE: &str = "attn_state.json";
+const OBSERVATIONS_FILE: &str = "observations.db";
+
+/// Where one side of a sync keeps its state
+struct StateDirs {
+    /// Holds the learner and attention state
+    project: PathBuf,
+    observations: PathBuf,
+}
+
+/// What happened to learner or attention state, with the turns the target
+/// has seen afterwards
+#[derive(Debug, PartialEq)]
+enum Outcome {
+    /// The source had none
+    Absent,
+    /// The source's copy replaced the target's, which was missing or forced
+    Copied { turns: usize },
+    /// The source's copy was merged into the target's
+    Merged { turns: usize },
+}
+
+#[derive(Debug)]
+struct SyncReport {
+    learner: Outcome,
+    attention: Outcome,
+    observations_added: usize,
+}
+
+/// Merge `from`'s learner and attention state into `to`'s (or copy them
+/// over, with `force`), and add `from`'s missing observations to `to`
+fn transfer(from: &StateDirs, to: &StateDirs, force: bool) -> anyhow::Result<SyncReport> {
+    std::fs::create_dir_all(&to.project)?;
+
+    let source = try_load_learner(&from.project.join(LEARNER_FILE))?;
+    let target = try_load_learner(&to.project.join(LEARNER_FILE))?;
+    let learner = match (source, target) {
+        (None, _) => Outcome::Absent,
+        (Some(source), Some(mut target)) if !force => {
+            target.merge(&source);
+            save_learner(&to.project.join(LEARNER_FILE), &mut target)?;
+            Outcome::Merged {
+                turns: target.turn_count(),
+            }
+        }
+        (Some(mut source), _) => {
+            save_learner(&to.project.join(LEARNER_FILE), &mut source)?;
+            Outcome::Copied {
+                turns: source.turn_count(),
+            }
+        }
+    };
+
+    let read_attention = |dir: &Path| -> anyhow::Result<Option<AttentionState>> {
+        let path = dir.join(ATTENTION_FILE);
+        Ok(if path.exists() {
+            Some(read_versioned(&path)?)
+        } else {
+            None
+        })
+    };
+    let attention = match (read_attention(&from.project)?, read_attention(&to.project)?) {
+        (None, _) => Outcome::Absent,
+        (Some(source), Some(mut target)) if !force => {
+            target.merge(&source);
+            write_versioned(&to.project.join(ATTENTION_FILE), &target)?;
+            Outcome::Merged {
+                turns: target.turn_count,
+            }
+        }
+        (Some(source), _) => {
+            write_versioned(&to.project.join(ATTENTION_FILE), &source)?;
+            Outcome::Copied {
+                turns: source.turn_count,
+            }
+        }
+    };
+
+    let observations_added = if from.observations.exists() {
+        ObservationDb::new(&to.observations)?.merge_from(&from.observations)?
+    } else {
+        0
+    };
+
+    Ok(SyncReport {
+        learner,
+        attention,
+        observations_added,
+    })
+}
+
+/// Name the project's state goes under on the remote: its origin URL when it
+/// has one (`git@github.com:me/app.git` -> `github.com-me-app`), else the
+/// name of its local state directory
+fn project_key(root: &Path, project_dir: &Path) -> String {
+    let origin = Command::new("git")
+        .arg("-C")
+        .arg(root)
+        .args(["remote", "get-url", "origin"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .map(|output| String::from_utf8_lossy(&output.stdout).trim().to_string())
+        .filter(|url| !url.is_empty());
+    match origin {
+        Some(url) => key_from_url(&url),
+        None => project_dir
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+    }
+}
+
+fn key_from_url(url: &str) -> String {
+    let url = url.trim_end_matches('/').trim_end_matches(".git");
+    let url = url.split_once("://").map_or(url, |(_, rest)| rest);
+    let url = url.split_once('@').map_or(url, |(_, rest)| rest);
+    url.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || "._-".contains(c) {
+                c
+            } else {
+                '-'
+            }
+        })
+        .collect::<String>()
+        .trim_matches('-')
+        .to_string()
+}
+
+/// A `--project` name, which becomes a directory on the remote, so it must
+/// be a single plain path component
+fn check_project_key(key: &str) -> anyhow::Result<&str> {
+    if key.is_empty()
+        || key.contains(['/', '\\'])
+        || key.contains("..")
+        || key == "."
+        || key == OBSERVATIONS_FILE
+    {
+        return Err(ErrorKind::Config.wrap(anyhow::anyhow!(
+            "invalid --project {:?}: expected a name without path separators or \"..\"",
+            key
+        )));
+    }
+    Ok(key)
+}
+
+/// Run `program` with `args`, failing with its stderr when it exits non-zero
+fn run_tool(program: &str, dir: Option<&Path>, args: &[&str]) -> anyhow::Result<String> {
+    let mut command = Command::new(program);
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command
+        .args(args)
+        .output()
+        .map_err(|e| anyhow::anyhow!("could not run {}: {}", program, e))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{} {} failed: {}",
+            program,
+            args.first().copied().unwrap_or_default(),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// `s3://bucket/prefix` and the `aws` flags shared by every call
+fn s3_location(bucket: &str, prefix: &str, endpoint_url: &Option<String>) -> (String, Vec<String>) {
+    let prefix = prefix.trim_matches('/');
+    let url = if prefix.is_empty() {
+        format!("s3://{}", bucket)
+    } else {
+        format!("s3://{}/{}", bucket, prefix)
+    };
+    let flags = match endpoint_url {
+        Some(endpoint) => vec!["--endpoint-url".to_string(), endpoint.clone()],
+        None => Vec::new(),
+    };
+    (url, flags)
+}
+
+/// Copy the remote's files for `key` and its observations into `dir`, which
+/// must not exist yet. A remote never synced to leaves `dir` empty.
+fn fetch(backend: &SyncBackend, dir: &Path, key: &str) -> anyhow::Result<()> {
+    let dir_arg = dir.to_string_lossy();
+    match backend {
+        SyncBackend::Git { remote, branch } => {
+            let heads = run_tool("git", None, &["ls-remote", "--heads", remote, branch])?;
+            if heads.trim().is_empty() {
+                run_tool("git", None, &["init", "-q", &dir_arg])?;
+                run_tool("git", Some(dir), &["checkout", "-q", "--orphan", branch])?;
+                run_tool("git", Some(dir), &["remote", "add", "origin", remote])?;
+            } else {
+                run_tool(
+                    "git",
+                    None,
+                    &[
+                        "clone", "-q", "--depth", "1", "--branch", branch, remote, &dir_arg,
+                    ],
+                )?;
+            }
+        }
+        SyncBackend::S3 {
+            bucket,
+            prefix,
+            endpoint_url,
+        } => {
+            std::fs::create_dir_all(dir)?;
+            let (url, flags) = s3_location(bucket, prefix, endpoint_url);
+            let include = format!("{}/*", key);
+            let mut args = vec!["s3", "sync", &url, &dir_arg, "--only-show-errors"];
+            args.extend(["--exclude", "*", "--include", &include]);
+            args.extend(["--include", OBSERVATIONS_FILE]);
+            args.extend(flags.iter().map(String::as_str));
+            run_tool("aws", None, &args)?;
+        }
+    }
+    Ok(())
+}
+
+/// Replace the remote's files for `key` and its observations with those in
+/// `dir`, as left by `fetch` and updated since
+fn publish(backend: &SyncBackend, dir: &Path, key: &str) -> anyhow::Result<()> {
+    match backend {
+        SyncBackend::Git { branch, .. } => {
+            run_tool("git", Some(dir), &["add", "-A"])?;
+            if run_tool("git", Some(dir), &["status", "--porcelain"])?
+                .trim()
+                .is_empty()
+            {
+                return Ok(());
+            }
+            // State commits need no real author; keep the user's if git has one
+            let mut args = Vec::new();
+            if run_tool("git", Some(dir), &["config", "user.email"]).is_err() {
+                args.extend([
+                    "-c",
+                    "user.name=attentive",
+                    "-c",
+                    "user.email=attentive@localhost",
+                ]);
+            }
+            let message = format!("Sync {}", key);
+            args.extend(["commit", "-q", "-m", &message]);
+            run_tool("git", Some(dir), &args)?;
+            let refspec = format!("HEAD:refs/heads/{}", branch);
+            run_tool("git", Some(dir), &["push", "-q", "origin", &refspec])?;
+        }
+        SyncBackend::S3 {
+            bucket,
+            prefix,
+            endpoint_url,
+        } => {
+            let (url, flags) = s3_location(bucket, prefix, endpoint_url);
+            let dir_arg = dir.to_string_lossy();
+            let include = format!("{}/*", key);
+            let mut args = vec!["s3", "sync", &dir_arg, &url, "--only-show-errors"];
+            args.extend(["--exclude", "*", "--include", &include]);
+            args.extend(["--include", OBSERVATIONS_FILE]);
+            args.extend(flags.iter().map(String::as_str));
+            run_tool("aws", None, &args)?;
+        }
+    }
+    Ok(())
+}
+
+fn describe(backend: &SyncBackend) -> String {
+    match backend {
+        SyncBackend::Git { remote, branch } => format!("{} ({})", remote, branch),
+        SyncBackend::S3 { bucket, prefix, .. } => s3_location(bucket, prefix, &None).0,
+    }
+}
+
+fn format_outcome(what: &str, outcome: &Outcome, pushing: bool) -> String {
+    let (action, verb, target) = if pushing {
+        ("push", "pushed", "remote")
+    } else {
+        ("pull", "pulled", "local")
+    };
+    match outcome {
+        Outcome::Absent => format!("  {}: nothing to {}", what, action),
+        Outcome::Copied { turns } => format!("  {}: {} ({} turns)", what, verb, turns),
+        Outcome::Merged { turns } => {
+            format!("  {}: merged into {} ({} turns)", what, target, turns)
+        }
+    }
+}
+
+fn format_report(report: &SyncReport, pushing: bool) -> String {
+    let side = if pushing { "to the remote" } else { "locally" };
+    [
+        format_outcome("learner", &report.learner, pushing),
+        format_outcome("attention state", &report.attention, pushing),
+        format!(
+            "  observations: {} added {}",
+            report.observations_added, side
+        ),
+    ]
+    .join("\n")
+}
+
+/// Push (`pushing`) or pull the current project's state
+pub fn run(pushing: bool, force: bool, project: Option<&str>) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let config = load_config(&paths.home_claude);
+    let Some(backend) = config.sync else {
+        return Err(ErrorKind::Config.wrap(anyhow::anyhow!(
+            "no sync backend configured; set \"sync\" in attentive.json, e.g. \
+             {{\"backend\": \"git\", \"remote\": \"git@github.com:me/attentive-state.git\"}}"
+        )));
+    };
+    let local = StateDirs {
+        project: paths.project_dir()?,
+        observations: paths.observations_db_path(),
+    };
+    let key = match project {
+        Some(key) => check_project_key(key)?.to_string(),
+        None => project_key(&std::env::current_dir()?, &local.project),
+    };
+
+    let scratch = paths.home_claude.join("sync");
+    if scratch.exists() {
+        std::fs::remove_dir_all(&scratch)?;
+    }
+    let result = (|| {
+        fetch(&backend, &scratch, &key).map_err(|e| ErrorKind::Network.wrap(e))?;
+        let remote = StateDirs {
+            project: scratch.join(&key),
+            observations: scratch.join(OBSERVATIONS_FILE),
+        };
+        let report = if pushing {
+            let report = transfer(&local, &remote, force)?;
+            publish(&backend, &scratch, &key).map_err(|e| ErrorKind::Network.wrap(e))?;
+            report
+        } else {
+            transfer(&remote, &local, force)?
+        };
+        anyhow::Ok(report)
+    })();
+    let _ = std::fs::remove_dir_all(&scratch);
+    let report = result?;
+
+    println!(
+        "{} {} {} {}",
+        if pushing { "Pushed" } else { "Pulled" },
+        key,
+        if pushing { "to" } else { "from" },
+        describe(&backend)
+    );
+    println!("{}", format_report(&report, pushing));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use attentive_learn::{Learner, WORD_INDEX_FILE};
+
+    fn learner_with_turns(turns: usize) -> Learner {
+        let mut learner = Learner::new();
+        for _ in 0..turns {
+            learner.observe_turn("router decay", &["src/router.rs".to_string()]);
+        }
+        learner
+    }
+
+    fn dirs(root: &Path) -> StateDirs {
+        StateDirs {
+            project: root.join("project"),
+            observations: root.join(OBSERVATIONS_FILE),
+        }
+    }
+
+    #[test]
+    fn test_transfer_merges_into_the_other_side() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let (laptop, desktop) = (dirs(&tmp.path().join("a")), dirs(&tmp.path().join("b")));
+        std::fs::create_dir_all(&laptop.project).unwrap();
+        save_learner(
+            &laptop.project.join(LEARNER_FILE),
+            &mut learner_with_turns(30),
+        )
+        .unwrap();
+        let state = AttentionState {
+            turn_count: 12,
+            ..AttentionState::new()
+        };
+        write_versioned(&laptop.project.join(ATTENTION_FILE), &state).unwrap();
+
+        let report = transfer(&laptop, &desktop, false).unwrap();
+        assert_eq!(report.learner, Outcome::Copied { turns: 30 });
+        assert_eq!(report.attention, Outcome::Copied { turns: 12 });
+        let copied = try_load_learner(&desktop.project.join(LEARNER_FILE))
+            .unwrap()
+            .unwrap();
+        assert_eq!(copied.turn_count(), 30);
+        assert!(copied.has_word_tables());
+        assert!(desktop.project.join(WORD_INDEX_FILE).exists());
+
+        // The desktop learns about other files; both sides' files survive
+        let mut desktop_learner = Learner::new();
+        for _ in 0..40 {
+            desktop_learner.observe_turn("parser tokens", &["src/parser.rs".to_string()]);
+        }
+        save_learner(&desktop.project.join(LEARNER_FILE), &mut desktop_learner).unwrap();
+        let mut desktop_state = AttentionState::new();
+        desktop_state
+            .scores
+            .insert("src/parser.rs".to_string(), 0.9);
+        write_versioned(&desktop.project.join(ATTENTION_FILE), &desktop_state).unwrap();
+        for _ in 0..2 {
+            let report = transfer(&laptop, &desktop, false).unwrap();
+            assert_eq!(report.learner, Outcome::Merged { turns: 40 });
+            assert_eq!(report.attention, Outcome::Merged { turns: 12 });
+            let merged = try_load_learner(&desktop.project.join(LEARNER_FILE))
+                .unwrap()
+                .unwrap();
+            assert_eq!(merged.file_usage("src/router.rs"), 30);
+            assert_eq!(merged.file_usage("src/parser.rs"), 40);
+        }
+        let merged: AttentionState = read_versioned(&desktop.project.join(ATTENTION_FILE)).unwrap();
+        assert!(merged.scores.contains_key("src/parser.rs"));
+
+        let report = transfer(&laptop, &desktop, true).unwrap();
+        assert_eq!(report.learner, Outcome::Copied { turns: 30 });
+        let forced = try_load_learner(&desktop.project.join(LEARNER_FILE))
+            .unwrap()
+            .unwrap();
+        assert_eq!(forced.file_usage("src/parser.rs"), 0);
+
+        let report = transfer(&dirs(&tmp.path().join("empty")), &desktop, false).unwrap();
+        assert_eq!(report.learner, Outcome::Absent);
+        assert!(format_report(&report, true).contains("learner: nothing to push"));
+    }
+
+    #[test]
+    fn test_check_project_key() {
+        assert_eq!(
+            check_project_key("github.com-me-app").unwrap(),
+            "github.com-me-app"
+        );
+        for key in [
+            "",
+            ".",
+            "..",
+            "../other",
+            "a/b",
+            "a\\b",
+            "up..",
+            OBSERVATIONS_FILE,
+        ] {
+            assert!(check_project_key(key).is_err(), "{:?}", key);
+        }
+    }
+
+    #[test]
+    fn test_key_from_url() {
+        assert_eq!(
+            key_from_url("git@github.com:me/app.git"),
+            "github.com-me-app"
+        );
+        assert_eq!(
+            key_from_url("https://github.com/me/app"),
+            "github.com-me-app"
+        );
+        assert_eq!(
+            key_from_url("ssh://git@host:22/team/app.git/"),
+            "host-22-team-app"
+        );
+    }
+
+    #[test]
+    fn test_git_backend_roundtrip() {
+        let tmp = tempfile::TempDir::new().unwrap();
+        let bare = tmp.path().join("state.git");
+        if run_tool(
+            "git",
+            None,
+            &["init", "-q", "--bare", &bare.to_string_lossy()],
+        )
+        .is_err()
+        {
+            return;
+        }
+        let backend = SyncBackend::Git {
+            remote: bare.to_string_lossy().into_owned(),
+            branch: "attentive-sync".to_string(),
+        };
+        let laptop = dirs(&tmp.path().join("laptop"));
+        std::fs::create_dir_all(&laptop.project).unwrap();
+        save_learner(
+            &laptop.project.join(LEARNER_FILE),
+            &mut learner_with_turns(30),
+        )
+        .unwrap();
+
+        let scratch = tmp.path().join("push");
+        fetch(&backend, &scratch, "app").unwrap();
+        let remote = StateDirs {
+            project: scratch.join("app"),
+            observations: scratch.join(OBSERVATIONS_FILE),
+        };
+        transfer(&laptop, &remote, false).unwrap();
+        publish(&backend, &scratch, "app").unwrap();
+
+        let scratch = tmp.path().join("pull");
+        fetch(&backend, &scratch, "app").unwrap();
+        let remote = StateDirs {
+            project: scratch.join("app"),
+            observations: scratch.join(OBSERVATIONS_FILE),
+        };
+        let desktop = dirs(&tmp.path().join("desktop"));
+        let report = transfer(&remote, &desktop, false).unwrap();
+        assert_eq!(report.learner, Outcome::Copied { turns: 30 });
+        assert_eq!(report.attention, Outcome::Absent);
+    }
+}
@@ -0,0 +1,416 @@
+//! Transcript adapters for ingesting history from other coding agents
+
+use super::ingest::{PromptFilePairs, extract_paths_from_command, parse_claude_transcript};
+use std::collections::HashSet;
+use std::path::Path;
+
+/// A transcript format that can be turned into prompt→files training pairs
+pub(crate) trait TranscriptParser {
+    /// Short format name shown in ingest output
+    fn name(&self) -> &'static str;
+
+    /// Whether this parser understands the file, judged by name and shape
+    fn detect(&self, path: &Path, content: &str) -> bool;
+
+    /// Parse into (prompt, files) pairs plus the number of records seen
+    fn parse(&self, content: &str) -> (PromptFilePairs, usize);
+}
+
+/// Claude Code session JSONL (`{"type": "user"|"assistant", "message": ...}`)
+pub(crate) struct ClaudeCodeParser;
+
+/// Cursor chat export JSON (`{"tabs": [{"bubbles": [...]}]}`)
+pub(crate) struct CursorParser;
+
+/// Aider `.aider.chat.history.md`
+pub(crate) struct AiderParser;
+
+/// OpenAI-style JSONL: chat messages with tool calls, or Codex CLI rollouts
+pub(crate) struct OpenAiParser;
+
+/// Parsers in detection order; Claude Code is the fallback
+fn parsers() -> [&'static dyn TranscriptParser; 4] {
+    [
+        &AiderParser,
+        &CursorParser,
+        &OpenAiParser,
+        &ClaudeCodeParser,
+    ]
+}
+
+/// Pick the parser for a transcript by file name and content shape
+pub(crate) fn detect_parser(path: &Path, content: &str) -> &'static dyn TranscriptParser {
+    parsers()
+        .into_iter()
+        .find(|p| p.detect(path, content))
+        .unwrap_or(&ClaudeCodeParser)
+}
+
+/// Read a transcript of any supported format
+pub(crate) fn parse_transcript(
+    path: &Path,
+) -> anyhow::Result<(PromptFilePairs, usize, &'static str)> {
+    let content = std::fs::read_to_string(path)?;
+    Ok(parse_transcript_content(path, &content))
+}
+
+/// Parse already-read transcript `content`; `path` only helps detection
+pub(crate) fn parse_transcript_content(
+    path: &Path,
+    content: &str,
+) -> (PromptFilePairs, usize, &'static str) {
+    let parser = detect_parser(path, content);
+    let (pairs, total) = parser.parse(content);
+    (pairs, total, parser.name())
+}
+
+fn first_json_line(content: &str) -> Option<serde_json::Value> {
+    content
+        .lines()
+        .filter(|l| !l.trim().is_empty())
+        .find_map(|l| serde_json::from_str(l).ok())
+}
+
+/// Collect path-like values for well-known keys anywhere in a JSON value
+fn collect_json_paths(value: &serde_json::Value, keys: &[&str], out: &mut HashSet<String>) {
+    match value {
+        serde_json::Value::Object(map) => {
+            for (k, v) in map {
+                if keys.contains(&k.as_str())
+                    && let Some(s) = v.as_str()
+                    && !s.is_empty()
+                {
+                    out.insert(s.to_string());
+                } else {
+                    collect_json_paths(v, keys, out);
+                }
+            }
+        }
+        serde_json::Value::Array(items) => {
+            for item in items {
+                collect_json_paths(item, keys, out);
+            }
+        }
+        _ => {}
+    }
+}
+
+impl TranscriptParser for ClaudeCodeParser {
+    fn name(&self) -> &'static str {
+        "claude-code"
+    }
+
+    fn detect(&self, _path: &Path, content: &str) -> bool {
+        first_json_line(content).is_some_and(|v| v.get("message").is_some())
+    }
+
+    fn parse(&self, content: &str) -> (PromptFilePairs, usize) {
+        parse_claude_transcript(content)
+    }
+}
+
+const CURSOR_PATH_KEYS: &[&str] = &["fsPath", "filePath", "relativeWorkspacePath"];
+
+impl TranscriptParser for CursorParser {
+    fn name(&self) -> &'static str {
+        "cursor"
+    }
+
+    fn detect(&self, _path: &Path, content: &str) -> bool {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+            return false;
+        };
+        let tabs = value.get("tabs").unwrap_or(&value);
+        tabs.as_array()
+            .is_some_and(|t| t.iter().any(|tab| tab.get("bubbles").is_some()))
+    }
+
+    fn parse(&self, content: &str) -> (PromptFilePairs, usize) {
+        let Ok(value) = serde_json::from_str::<serde_json::Value>(content) else {
+            return (Vec::new(), 0);
+        };
+        let tabs = value
+            .get("tabs")
+            .unwrap_or(&value)
+            .as_array()
+            .cloned()
+            .unwrap_or_default();
+
+        let mut pairs = Vec::new();
+        let mut total = 0;
+        for tab in &tabs {
+            let Some(bubbles) = tab.get("bubbles").and_then(|b| b.as_array()) else {
+                continue;
+            };
+            let mut current_prompt = String::new();
+            let mut files = HashSet::new();
+            for bubble in bubbles {
+                total += 1;
+                let text = bubble.get("text").and_then(|t| t.as_str()).unwrap_or("");
+                match bubble.get("type").and_then(|t| t.as_str()) {
+                    Some("user") => {
+                        if !current_prompt.is_empty() && !files.is_empty() {
+                            pairs.push((current_prompt.clone(), files.drain().collect()));
+                        }
+                        files.clear();
+                        current_prompt = text.to_string();
+                        // Files the user attached as context count toward this turn
+                        collect_json_paths(bubble, CURSOR_PATH_KEYS, &mut files);
+                    }
+                    Some("ai") | Some("assistant") => {
+                        collect_json_paths(bubble, CURSOR_PATH_KEYS, &mut files);
+                    }
+                    _ => {}
+                }
+            }
+            if !current_prompt.is_empty() && !files.is_empty() {
+                pairs.push((current_prompt, files.into_iter().collect()));
+            }
+        }
+        (pairs, total)
+    }
+}
+
+impl TranscriptParser for AiderParser {
+    fn name(&self) -> &'static str {
+        "aider"
+    }
+
+    fn detect(&self, path: &Path, content: &str) -> bool {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or("");
+        name.ends_with(".aider.chat.history.md") || content.starts_with("# aider chat started")
+    }
+
+    fn parse(&self, content: &str) -> (PromptFilePairs, usize) {
+        let mut pairs = Vec::new();
+        let mut total = 0;
+        let mut current_prompt = String::new();
+        let mut files: HashSet<String> = HashSet::new();
+
+        // Files added before the first prompt carry over to it
+        let mut flush = |prompt: &str, files: &mut HashSet<String>| {
+            if prompt.is_empty() {
+                return;
+            }
+            if !files.is_empty() {
+                pairs.push((prompt.to_string(), files.drain().collect()));
+            }
+        };
+
+        let mut in_prompt = false;
+        for line in content.lines() {
+            if let Some(prompt) = line.strip_prefix("#### ") {
+                // Consecutive #### lines are one multi-line prompt
+                if in_prompt {
+                    current_prompt.push('\n');
+                    current_prompt.push_str(prompt);
+                } else {
+                    total += 1;
+                    flush(&current_prompt, &mut files);
+                    current_prompt = prompt.to_string();
+                    in_prompt = true;
+                }
+                continue;
+            }
+            in_prompt = false;
+            let note = line.trim_start_matches('>').trim();
+            if let Some(path) = note.strip_prefix("Applied edit to ") {
+                files.insert(path.trim().to_string());
+            } else if let Some(rest) = note.strip_prefix("Added ")
+                && let Some(path) = rest.strip_suffix(" to the chat.")
+            {
+                files.insert(path.trim().to_string());
+            }
+        }
+        flush(&current_prompt, &mut files);
+        (pairs, total)
+    }
+}
+
+const OPENAI_PATH_KEYS: &[&str] = &["path", "file_path", "filename", "file"];
+
+impl OpenAiParser {
+    /// Files referenced by a tool/function call's JSON-encoded arguments
+    fn files_from_arguments(arguments: &serde_json::Value, files: &mut HashSet<String>) {
+        let parsed = match arguments.as_str() {
+            Some(s) => serde_json::from_str(s).unwrap_or(serde_json::Value::Null),
+            None => arguments.clone(),
+        };
+        collect_json_paths(&parsed, OPENAI_PATH_KEYS, files);
+        match parsed.get("command") {
+            Some(serde_json::Value::String(cmd)) => files.extend(extract_paths_from_command(cmd)),
+            Some(serde_json::Value::Array(argv)) => {
+                let cmd: Vec<&str> = argv.iter().filter_map(|a| a.as_str()).collect();
+                files.extend(extract_paths_from_command(&cmd.join(" ")));
+            }
+            _ => {}
+        }
+    }
+
+    fn message_text(message: &serde_json::Value) -> String {
+        match message.get("content") {
+            Some(serde_json::Value::String(s)) => s.clone(),
+            Some(serde_json::Value::Array(parts)) => parts
+                .iter()
+                .filter_map(|p| p.get("text").and_then(|t| t.as_str()))
+                .collect::<Vec<_>>()
+                .join("\n"),
+            _ => String::new(),
+        }
+    }
+}
+
+impl TranscriptParser for OpenAiParser {
+    fn name(&self) -> &'static str {
+        "openai"
+    }
+
+    fn detect(&self, _path: &Path, content: &str) -> bool {
+        first_json_line(content).is_some_and(|v| {
+            v.get("message").is_none()
+                && (v.get("role").is_some()
+                    || v.get("messages").is_some()
+                    || v.get("type").and_then(|t| t.as_str()) == Some("function_call"))
+        })
+    }
+
+    fn parse(&self, content: &str) -> (PromptFilePairs, usize) {
+        // Flatten both one-message-per-line and {"messages": [...]} layouts
+        let mut messages = Vec::new();
+        for line in content.lines() {
+            let Ok(value) = serde_json::from_str::<serde_json::Value>(line) else {
+                continue;
+            };
+            match value.get("messages").and_then(|m| m.as_array()) {
+                Some(batch) => messages.extend(batch.iter().cloned()),
+                None => messages.push(value),
+            }
+        }
+
+        let mut pairs = Vec::new();
+        let mut current_prompt = String::new();
+        let mut files = HashSet::new();
+        for message in &messages {
+            let role = message.get("role").and_then(|r| r.as_str());
+            let kind = message.get("type").and_then(|t| t.as_str());
+            if role == Some("user") {
+                if !current_prompt.is_empty() && !files.is_empty() {
+                    pairs.push((current_prompt.clone(), files.drain().collect()));
+                }
+                files.clear();
+                current_prompt = Self::message_text(message);
+            } else if kind == Some("function_call") {
+                if let Some(args) = message.get("arguments") {
+                    Self::files_from_arguments(args, &mut files);
+                }
+            } else if let Some(calls) = message.get("tool_calls").and_then(|c| c.as_array()) {
+                for call in calls {
+                    if let Some(args) = call.pointer("/function/arguments") {
+                        Self::files_from_arguments(args, &mut files);
+                    }
+                }
+            }
+        }
+        if !current_prompt.is_empty() && !files.is_empty() {
+            pairs.push((current_prompt, files.into_iter().collect()));
+        }
+        (pairs, messages.len())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_claude_code() {
+        let content = r#"{"type":"user","message":{"content":"fix router"}}"#;
+        let parser = detect_parser(Path::new("abc.jsonl"), content);
+        assert_eq!(parser.name(), "claude-code");
+    }
+
+    #[test]
+    fn test_parse_cursor_export() {
+        let content = serde_json::json!({
+            "tabs": [{
+                "chatTitle": "Router work",
+                "bubbles": [
+                    {"type": "user", "text": "why is decay slow",
+                     "context": {"fileSelections": [{"uri": {"fsPath": "/repo/src/router.rs"}}]}},
+                    {"type": "ai", "text": "Look here",
+                     "codeBlocks": [{"uri": {"fsPath": "/repo/src/config.rs"}}]},
+                    {"type": "user", "text": "thanks"}
+                ]
+            }]
+        })
+        .to_string();
+
+        let parser = detect_parser(Path::new("cursor.json"), &content);
+        assert_eq!(parser.name(), "cursor");
+        let (pairs, total) = parser.parse(&content);
+        assert_eq!(total, 3);
+        assert_eq!(pairs.len(), 1);
+        assert_eq!(pairs[0].0, "why is decay slow");
+        assert!(pairs[0].1.contains(&"/repo/src/router.rs".to_string()));
+        assert!(pairs[0].1.contains(&"/repo/src/config.rs".to_string()));
+    }
+
+    #[test]
+    fn test_parse_aider_history() {
+        let content = "# aider chat started at 2025-01-01 10:00:00\n\n\
+            > Added src/router.rs to the chat.\n\n\
+            #### make decay configurable\n\n\
+            Here is the change.\n\n\
+            > Applied edit to src/config.rs\n\n\
+            #### now update the docs\n\
+            > Applied edit to README.md\n";
+
+        let parser = detect_parser(Path::new(".aider.chat.history.md"), content);
+        assert_eq!(parser.name(), "aider");
+        let (pairs, total) = parser.parse(content);
+        assert_eq!(total, 2);
+        assert_eq!(pairs.len(), 2);
+        assert_eq!(pairs[0].0, "make decay configurable");
+        let mut first = pairs[0].1.clone();
+        first.sort();
+        assert_eq!(first, vec!["src/config.rs", "src/router.rs"]);
+        assert_eq!(pairs[1].1, vec!["README.md".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_openai_chat_jsonl() {
+        let lines = [
+            serde_json::json!({"role": "user", "content": "refactor the tokenizer"}),
+            serde_json::json!({"role": "assistant", "tool_calls": [{
+                "function": {"name": "read_file", "arguments": "{\"path\": \"src/tokens.rs\"}"}
+            }]}),
+        ];
+        let content: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let content = content.join("\n");
+
+        let parser = detect_parser(Path::new("chat.jsonl"), &content);
+        assert_eq!(parser.name(), "openai");
+        let (pairs, total) = parser.parse(&content);
+        assert_eq!(total, 2);
+        assert_eq!(pairs[0].0, "refactor the tokenizer");
+        assert_eq!(pairs[0].1, vec!["src/tokens.rs".to_string()]);
+    }
+
+    #[test]
+    fn test_parse_codex_rollout() {
+        let lines = [
+            serde_json::json!({"type": "message", "role": "user",
+                "content": [{"type": "input_text", "text": "run the bench"}]}),
+            serde_json::json!({"type": "function_call", "name": "shell",
+                "arguments": "{\"command\": [\"cat\", \"benches/router_update.rs\"]}"}),
+        ];
+        let content: Vec<String> = lines.iter().map(|l| l.to_string()).collect();
+        let content = content.join("\n");
+
+        let parser = detect_parser(Path::new("rollout.jsonl"), &content);
+        assert_eq!(parser.name(), "openai");
+        let (pairs, _) = parser.parse(&content);
+        assert_eq!(pairs[0].0, "run the bench");
+        assert_eq!(pairs[0].1, vec!["benches/router_update.rs".to_string()]);
+    }
+}
@@ -0,0 +1,426 @@
+//! `tune`: config changes suggested by how injected files were used
+//!
+//! Files injected on many turns but almost never used are proposed for
+//! `demoted_files`. Files Claude keeps reaching for without them being
+//! injected are proposed for `co_activation` with the file they usually
+//! accompany, or for `pinned_files` when there is none. Accepted suggestions
+//! are written to the project's `.attentive.json`.
+
+use super::hooks::PROJECT_CONFIG_FILE;
+use attentive_core::{Config, Router};
+use attentive_telemetry::{PathCanonicalizer, Paths, TurnRecord, read_jsonl};
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{BufRead, Write};
+use std::path::Path;
+
+/// Injected files used on at most this share of their turns are demotable
+const MAX_DEMOTE_USAGE: f64 = 0.05;
+/// A missed file must be used without being injected on at least this share
+/// of the turns that used it
+const MIN_MISSED_SHARE: f64 = 0.5;
+/// ...and go along with one injected file this often to co-activate with it
+const MIN_PARTNER_SHARE: f64 = 0.6;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Suggestion {
+    /// Injected on `injected` turns, used on `used` of them
+    Demote {
+        file: String,
+        injected: usize,
+        used: usize,
+    },
+    /// `file` was used without being injected on `missed` turns, `together`
+    /// of which injected and used `with`
+    CoActivate {
+        with: String,
+        file: String,
+        missed: usize,
+        together: usize,
+    },
+    /// Used without being injected on `missed` of `used` turns
+    Pin {
+        file: String,
+        missed: usize,
+        used: usize,
+    },
+}
+
+impl Suggestion {
+    fn describe(&self) -> String {
+        match self {
+            Suggestion::Demote {
+                file,
+                injected,
+                used,
+            } => format!(
+                "demote {} (injected {} turns, used {})",
+                file, injected, used
+            ),
+            Suggestion::CoActivate {
+                with,
+                file,
+                missed,
+                together,
+            } => format!(
+                "co-activate {} with {} (missed {} turns, {} of them alongside it)",
+                file, with, missed, together
+            ),
+            Suggestion::Pin { file, missed, used } => format!(
+                "pin {} (used {} turns, not injected on {})",
+                file, used, missed
+            ),
+        }
+    }
+
+    /// Add the suggestion to the config object `map`. Keys the project file
+    /// doesn't set yet start from `inherited` (the global config), since the
+    /// project value replaces the global one.
+    fn apply(
+        &self,
+        map: &mut serde_json::Map<String, serde_json::Value>,
+        inherited: &serde_json::Map<String, serde_json::Value>,
+    ) {
+        let mut entry = |key: &str, empty: serde_json::Value| {
+            map.entry(key)
+                .or_insert_with(|| inherited.get(key).cloned().unwrap_or(empty))
+                .clone()
+        };
+        let push = |list: serde_json::Value, file: &str| {
+            let mut list = match list {
+                serde_json::Value::Array(list) => list,
+                _ => Vec::new(),
+            };
+            if !list.iter().any(|v| v == file) {
+                list.push(file.into());
+            }
+            serde_json::Value::Array(list)
+        };
+        match self {
+            Suggestion::Demote { file, .. } => {
+                let list = push(entry("demoted_files", serde_json::json!([])), file);
+                map.insert("demoted_files".to_string(), list);
+            }
+            Suggestion::Pin { file, .. } => {
+                let list = push(entry("pinned_files", serde_json::json!([])), file);
+                map.insert("pinned_files".to_string(), list);
+            }
+            Suggestion::CoActivate { with, file, .. } => {
+                let mut graph = match entry("co_activation", serde_json::json!({})) {
+                    serde_json::Value::Object(graph) => graph,
+                    _ => serde_json::Map::new(),
+                };
+                let related = match graph.remove(with) {
+                    // {file: strength} form
+                    Some(serde_json::Value::Object(mut weighted)) => {
+                        weighted.entry(file.as_str()).or_insert(1.0.into());
+                        serde_json::Value::Object(weighted)
+                    }
+                    other => push(other.unwrap_or_default(), file),
+                };
+                graph.insert(with.clone(), related);
+                map.insert(
+                    "co_activation".to_string(),
+                    serde_json::Value::Object(graph),
+                );
+            }
+        }
+    }
+}
+
+/// Suggestions from `turns` not already covered by `config`. A file needs at
+/// least `min_turns` turns of evidence before anything is suggested for it.
+fn suggest(turns: &[TurnRecord], config: &Config, min_turns: usize) -> Vec<Suggestion> {
+    #[derive(Default)]
+    struct FileUse {
+        injected: usize,
+        injected_used: usize,
+        used: usize,
+        /// Used without being injected
+        missed: usize,
+        /// Injected-and-used file -> missed turns it was part of
+        partners: BTreeMap<String, usize>,
+    }
+
+    let mut files: BTreeMap<&str, FileUse> = BTreeMap::new();
+    for turn in turns {
+        let injected: BTreeSet<&str> = turn.files_injected.iter().map(|f| f.as_str()).collect();
+        let used: BTreeSet<&str> = turn.files_used.iter().map(|f| f.as_str()).collect();
+        for &file in &injected {
+            let stats = files.entry(file).or_default();
+            stats.injected += 1;
+            stats.injected_used += usize::from(used.contains(file));
+        }
+        for &file in &used {
+            let stats = files.entry(file).or_default();
+            stats.used += 1;
+            if !injected.contains(file) {
+                stats.missed += 1;
+                for &partner in used.intersection(&injected) {
+                    *stats.partners.entry(partner.to_string()).or_default() += 1;
+                }
+            }
+        }
+    }
+
+    let router = Router::new(config.clone());
+    let mut demotions = Vec::new();
+    let mut promotions = Vec::new();
+    for (file, stats) in files {
+        if stats.injected >= min_turns
+            && (stats.injected_used as f64 / stats.injected as f64) <= MAX_DEMOTE_USAGE
+            && !router.is_demoted(file)
+            && !router.is_pinned(file)
+        {
+            demotions.push(Suggestion::Demote {
+                file: file.to_string(),
+                injected: stats.injected,
+                used: stats.injected_used,
+            });
+        }
+
+        if stats.used < min_turns
+            || (stats.missed as f64 / stats.used as f64) < MIN_MISSED_SHARE
+            || router.is_pinned(file)
+        {
+            continue;
+        }
+        let partner = stats
+            .partners
+            .iter()
+            .filter(|&(_, &together)| together as f64 / stats.missed as f64 >= MIN_PARTNER_SHARE)
+            .max_by_key(|&(with, &together)| (together, std::cmp::Reverse(with)));
+        match partner {
+            Some((with, _))
+                if config
+                    .co_activation
+                    .get(with)
+                    .is_some_and(|r| r.iter().any(|f| f == file)) => {}
+            Some((with, &together)) => promotions.push(Suggestion::CoActivate {
+                with: with.clone(),
+                file: file.to_string(),
+                missed: stats.missed,
+                together,
+            }),
+            None => promotions.push(Suggestion::Pin {
+                file: file.to_string(),
+                missed: stats.missed,
+                used: stats.used,
+            }),
+        }
+    }
+    demotions.extend(promotions);
+    demotions
+}
+
+fn read_object(path: &Path) -> anyhow::Result<serde_json::Map<String, serde_json::Value>> {
+    if !path.exists() {
+        return Ok(serde_json::Map::new());
+    }
+    match serde_json::from_str(&std::fs::read_to_string(path)?)? {
+        serde_json::Value::Object(map) => Ok(map),
+        _ => anyhow::bail!("{} is not a JSON object", path.display()),
+    }
+}
+
+/// Write `accepted` into the project config at `project`, keeping its other keys
+fn apply_suggestions(project: &Path, global: &Path, accepted: &[Suggestion]) -> anyhow::Result<()> {
+    let mut map = read_object(project)?;
+    let inherited = read_object(global)?;
+    for suggestion in accepted {
+        suggestion.apply(&mut map, &inherited);
+    }
+    let json = serde_json::to_string_pretty(&serde_json::Value::Object(map))?;
+    Config::from_json(&json)?;
+    attentive_telemetry::atomic_write(project, format!("{}\n", json).as_bytes())?;
+    Ok(())
+}
+
+/// Write `pairs` into the project config at `project` as co-activations in
+/// both directions
+pub(crate) fn apply_co_activations(
+    project: &Path,
+    global: &Path,
+    pairs: &[(String, String)],
+) -> anyhow::Result<()> {
+    let accepted: Vec<Suggestion> = pairs
+        .iter()
+        .flat_map(|(a, b)| [(a, b), (b, a)])
+        .map(|(with, file)| Suggestion::CoActivate {
+            with: with.clone(),
+            file: file.clone(),
+            missed: 0,
+            together: 0,
+        })
+        .collect();
+    apply_suggestions(project, global, &accepted)
+}
+
+/// Ask on stderr/stdin; anything but y/yes (including EOF) declines
+fn confirm(question: &str) -> bool {
+    eprint!("{} [y/N] ", question);
+    let _ = std::io::stderr().flush();
+    let mut answer = String::new();
+    if std::io::stdin().lock().read_line(&mut answer).is_err() {
+        return false;
+    }
+    matches!(answer.trim().to_lowercase().as_str(), "y" | "yes")
+}
+
+pub fn run(min_turns: usize, yes: bool) -> anyhow::Result<()> {
+    let paths = Paths::new()?;
+    let canonicalizer = PathCanonicalizer::for_current_dir()?;
+    let root = canonicalizer.root();
+    let turns: Vec<TurnRecord> = read_jsonl(&paths.turns_file())?
+        .into_iter()
+        .filter(|t: &TurnRecord| Path::new(&t.project).starts_with(root))
+        .collect();
+    let config = super::hooks::load_config(&paths.home_claude);
+    let suggestions = suggest(&turns, &config, min_turns);
+    if suggestions.is_empty() {
+        println!(
+            "No suggestions from {} turns (files need {} turns of evidence)",
+            turns.len(),
+            min_turns
+        );
+        return Ok(());
+    }
+
+    println!("Suggestions from {} turns:", turns.len());
+    let mut accepted = Vec::new();
+    for suggestion in suggestions {
+        let description = suggestion.describe();
+        if yes {
+            println!("  {}", description);
+            accepted.push(suggestion);
+        } else if confirm(&format!("  {}?", description)) {
+            accepted.push(suggestion);
+        }
+    }
+    if accepted.is_empty() {
+        println!("Nothing applied");
+        return Ok(());
+    }
+
+    let project_config = root.join(PROJECT_CONFIG_FILE);
+    apply_suggestions(
+        &project_config,
+        &paths.home_claude.join("attentive.json"),
+        &accepted,
+    )?;
+    println!(
+        "Applied {} suggestion(s) to {}",
+        accepted.len(),
+        project_config.display()
+    );
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn turn(injected: &[&str], used: &[&str]) -> TurnRecord {
+        let strings = |files: &[&str]| files.iter().map(|f| f.to_string()).collect();
+        TurnRecord {
+            turn_id: "t".to_string(),
+            session_id: "s".to_string(),
+            project: "/repo".to_string(),
+            timestamp: chrono::Utc::now(),
+            injected_tokens: 100,
+            used_tokens: 50,
+            waste_ratio: 0.5,
+            files_injected: strings(injected),
+            files_used: strings(used),
+            injection_chars: 400,
+            ..Default::default()
+        }
+    }
+
+    #[test]
+    fn test_suggest_demotions_and_promotions() {
+        let mut turns = Vec::new();
+        for _ in 0..10 {
+            // CHANGELOG.md is always injected and never read; schema.rs is
+            // read whenever router.rs is, without being injected
+            turns.push(turn(
+                &["src/router.rs", "CHANGELOG.md", "src/old.rs"],
+                &["src/router.rs", "src/schema.rs"],
+            ));
+            // Makefile is read without any injected file alongside it
+            turns.push(turn(&["docs/guide.md"], &["Makefile"]));
+        }
+        turns.push(turn(&["src/old.rs"], &["src/old.rs"]));
+
+        let config = Config::from_json(r#"{"demoted_files": ["src/old.rs"]}"#).unwrap();
+        let suggestions = suggest(&turns, &config, 10);
+        assert_eq!(
+            suggestions,
+            [
+                Suggestion::Demote {
+                    file: "CHANGELOG.md".to_string(),
+                    injected: 10,
+                    used: 0
+                },
+                Suggestion::Demote {
+                    file: "docs/guide.md".to_string(),
+                    injected: 10,
+                    used: 0
+                },
+                Suggestion::Pin {
+                    file: "Makefile".to_string(),
+                    missed: 10,
+                    used: 10
+                },
+                Suggestion::CoActivate {
+                    with: "src/router.rs".to_string(),
+                    file: "src/schema.rs".to_string(),
+                    missed: 10,
+                    together: 10
+                },
+            ]
+        );
+        // Not enough evidence yet
+        assert!(suggest(&turns, &config, 11).is_empty());
+    }
+
+    #[test]
+    fn test_apply_suggestions_keeps_inherited_entries() {
+        let dir = tempfile::tempdir().unwrap();
+        let project = dir.path().join(".attentive.json");
+        let global = dir.path().join("attentive.json");
+        std::fs::write(&project, r#"{"co_activation": {"a.rs": {"b.rs": 0.5}}}"#).unwrap();
+        std::fs::write(
+            &global,
+            r#"{"demoted_files": ["vendor/"], "pinned_files": ["x.rs"]}"#,
+        )
+        .unwrap();
+
+        let accepted = [
+            Suggestion::Demote {
+                file: "CHANGELOG.md".to_string(),
+                injected: 10,
+                used: 0,
+            },
+            Suggestion::CoActivate {
+                with: "a.rs".to_string(),
+                file: "c.rs".to_string(),
+                missed: 5,
+                together: 5,
+            },
+            Suggestion::CoActivate {
+                with: "d.rs".to_string(),
+                file: "e.rs".to_string(),
+                missed: 5,
+                together: 5,
+            },
+        ];
+        apply_suggestions(&project, &global, &accepted).unwrap();
+        let config = Config::from_json(&std::fs::read_to_string(&project).unwrap()).unwrap();
+        assert_eq!(config.demoted_files, ["vendor/", "CHANGELOG.md"]);
+        // Not touched, so still inherited from the global file at load time
+        assert!(config.pinned_files.is_empty());
+        assert_eq!(config.co_activation_weight("a.rs", "b.rs"), 0.5);
+        assert_eq!(config.co_activation["a.rs"], ["b.rs", "c.rs"]);
+        assert_eq!(config.co_activation["d.rs"], ["e.rs"]);
+    }
+}
@@ -0,0 +1,50 @@
+use attentive_repo::{XrefHit, XrefIndex};
+
+fn format_hits(symbol: &str, hits: &[XrefHit]) -> String {
+    if hits.is_empty() {
+        return format!("No references to {}", symbol);
+    }
+    let mut lines = vec![symbol.to_string()];
+    for hit in hits {
+        let kind = if hit.defines { "def" } else { "ref" };
+        lines.push(format!("  {}  {}", kind, hit.path));
+    }
+    lines.push(format!("\n{} files", hits.len()));
+    lines.join("\n")
+}
+
+/// List files in the current repo that define or reference `symbol`
+pub fn run(symbol: &str) -> anyhow::Result<()> {
+    let root = attentive_telemetry::PathCanonicalizer::for_current_dir()?
+        .root()
+        .to_path_buf();
+    let config = super::hooks::load_config(&attentive_telemetry::Paths::new()?.home_claude);
+    let index = XrefIndex::load_cached(&root, &config.exclude_patterns())?;
+    println!("{}", format_hits(symbol, &index.lookup(symbol)));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_format_hits() {
+        let hits = vec![
+            XrefHit {
+                path: "src/router.rs".to_string(),
+                defines: true,
+            },
+            XrefHit {
+                path: "src/hooks.rs".to_string(),
+                defines: false,
+            },
+        ];
+        let out = format_hits("route", &hits);
+        assert_eq!(
+            out,
+            "route\n  def  src/router.rs\n  ref  src/hooks.rs\n\n2 files"
+        );
+        assert_eq!(format_hits("nope", &[]), "No references to nope");
+    }
+}
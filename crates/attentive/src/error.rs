@@ -0,0 +1,185 @@
+//! Error taxonomy and exit codes
+//!
+//! Commands return `anyhow` errors. `main` files each one under the family of
+//! the command that failed, unless the error already carries a `CliError` or a
+//! cause that says otherwise, and exits with that family's code so scripts can
+//! tell a bad config from a corrupt state file without parsing messages.
+
+use attentive_telemetry::StateError;
+use std::io::ErrorKind as IoErrorKind;
+
+/// What kind of failure ended a command
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    /// attentive.json is unreadable or has invalid values
+    Config,
+    /// Session state, telemetry or learned data can't be read or written
+    State,
+    /// The repo index or symbol graph can't be built or searched
+    Index,
+    /// A hook failed; its stdout is still valid (empty) hook output
+    Hook,
+    /// The state server or a compression backend can't be reached
+    Network,
+    /// Anything else
+    Other,
+}
+
+impl ErrorKind {
+    /// Process exit code. 2 is left to clap's usage errors (and means "block"
+    /// to Claude Code hooks), so no family uses it.
+    pub fn exit_code(self) -> u8 {
+        match self {
+            ErrorKind::Other => 1,
+            ErrorKind::Config => 3,
+            ErrorKind::State => 4,
+            ErrorKind::Index => 5,
+            ErrorKind::Hook => 6,
+            ErrorKind::Network => 7,
+        }
+    }
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ErrorKind::Config => "config",
+            ErrorKind::State => "state",
+            ErrorKind::Index => "index",
+            ErrorKind::Hook => "hook",
+            ErrorKind::Network => "network",
+            ErrorKind::Other => "other",
+        }
+    }
+
+    /// Tag `error` with this kind; commands return this when they know better
+    /// than their family
+    pub fn wrap(self, error: impl Into<anyhow::Error>) -> anyhow::Error {
+        CliError {
+            kind: self,
+            error: error.into(),
+        }
+        .into()
+    }
+}
+
+/// A command's error with the kind it was filed under
+#[derive(Debug, thiserror::Error)]
+#[error("{error:#}")]
+pub struct CliError {
+    pub kind: ErrorKind,
+    pub error: anyhow::Error,
+}
+
+impl CliError {
+    /// File `error` from a command of `family`. A `CliError` in its chain
+    /// wins, then causes that only one family produces.
+    pub fn classify(family: ErrorKind, error: anyhow::Error) -> Self {
+        if let Some(tagged) = error.downcast_ref::<CliError>() {
+            return Self {
+                kind: tagged.kind,
+                error,
+            };
+        }
+        let kind = error
+            .chain()
+            .find_map(|cause| {
+                if cause.is::<StateError>() {
+                    return Some(ErrorKind::State);
+                }
+                let io = cause.downcast_ref::<std::io::Error>()?;
+                matches!(
+                    io.kind(),
+                    IoErrorKind::ConnectionRefused
+                        | IoErrorKind::ConnectionReset
+                        | IoErrorKind::ConnectionAborted
+                        | IoErrorKind::NotConnected
+                        | IoErrorKind::TimedOut
+                )
+                .then_some(ErrorKind::Network)
+            })
+            .unwrap_or(family);
+        Self { kind, error }
+    }
+
+    /// `{"error": {"kind", "code", "message"}}`, printed to stderr with `--json`
+    pub fn to_json(&self) -> serde_json::Value {
+        serde_json::json!({
+            "error": {
+                "kind": self.kind.name(),
+                "code": self.kind.exit_code(),
+                "message": format!("{:#}", self.error),
+            }
+        })
+    }
+
+    /// Print the error to stderr; stdout is never touched, so a failed hook
+    /// leaves valid empty output behind
+    pub fn report(&self, json: bool) {
+        if json {
+            eprintln!("{}", self.to_json());
+        } else {
+            eprintln!("Error: {:?}", self.error);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use anyhow::Context;
+
+    #[test]
+    fn test_classify_uses_family_then_chain() {
+        let plain = CliError::classify(ErrorKind::Index, anyhow::anyhow!("no index"));
+        assert_eq!(plain.kind, ErrorKind::Index);
+        assert_eq!(plain.kind.exit_code(), 5);
+
+        let state = StateError::NoMigration {
+            kind: "attention state",
+            from: 7,
+        };
+        let err = anyhow::Error::from(state).context("loading state");
+        assert_eq!(
+            CliError::classify(ErrorKind::Hook, err).kind,
+            ErrorKind::State
+        );
+
+        let refused = std::io::Error::from(IoErrorKind::ConnectionRefused);
+        assert_eq!(
+            CliError::classify(ErrorKind::Other, refused.into()).kind,
+            ErrorKind::Network
+        );
+
+        let tagged = Err::<(), _>(ErrorKind::Config.wrap(anyhow::anyhow!("bad threshold")))
+            .context("checking config")
+            .unwrap_err();
+        let err = CliError::classify(ErrorKind::State, tagged);
+        assert_eq!(err.kind, ErrorKind::Config);
+        assert_eq!(
+            err.to_json(),
+            serde_json::json!({
+                "error": {
+                    "kind": "config",
+                    "code": 3,
+                    "message": "checking config: bad threshold",
+                }
+            })
+        );
+    }
+
+    #[test]
+    fn test_exit_codes_are_distinct() {
+        let kinds = [
+            ErrorKind::Config,
+            ErrorKind::State,
+            ErrorKind::Index,
+            ErrorKind::Hook,
+            ErrorKind::Network,
+            ErrorKind::Other,
+        ];
+        let mut codes: Vec<u8> = kinds.iter().map(|k| k.exit_code()).collect();
+        codes.sort();
+        codes.dedup();
+        assert_eq!(codes.len(), kinds.len());
+        assert!(!codes.contains(&0) && !codes.contains(&2));
+    }
+}
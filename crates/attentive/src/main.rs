@@ -1,12 +1,19 @@
 mod cli;
 mod commands;
+mod error;
 
 use clap::Parser;
-use cli::{Cli, Commands, PluginAction};
+use cli::{
+    Cli, Commands, CompressAction, ConfigAction, ExportAction, LearnAction, PluginAction,
+    ProfileAction, RuleAction, StashAction, SyncAction,
+};
+use error::CliError;
+use std::process::ExitCode;
 
-fn main() -> anyhow::Result<()> {
-    // Initialize tracing
+fn main() -> ExitCode {
+    // Initialize tracing; stdout is reserved for hook output
     tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
         .with_env_filter(
             tracing_subscriber::EnvFilter::from_default_env()
                 .add_directive(tracing::Level::INFO.into()),
@@ -14,25 +21,171 @@ fn main() -> anyhow::Result<()> {
         .init();
 
     let cli = Cli::parse();
+    let family = cli.command.error_kind();
+    let json = cli.command.json();
+    match run(cli.command) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(error) => {
+            let error = CliError::classify(family, error);
+            error.report(json);
+            ExitCode::from(error.kind.exit_code())
+        }
+    }
+}
 
-    match cli.command {
-        Commands::Init => commands::init::run(),
-        Commands::Ingest { file } => commands::ingest::run(file.as_deref()),
-        Commands::Status { session } => commands::status::run(session.as_deref()),
+fn run(command: Commands) -> anyhow::Result<()> {
+    match command {
+        Commands::Init { project, ingest } => {
+            if project {
+                commands::init::run_project(ingest)
+            } else {
+                commands::init::run()
+            }
+        }
+        Commands::Ingest { file, force, reset } => {
+            commands::ingest::run(file.as_deref(), force, reset)
+        }
+        Commands::Status {
+            session,
+            files,
+            export_context,
+        } => commands::status::run(session.as_deref(), files, export_context),
         Commands::Version => commands::version::run(),
+        Commands::Preview { prompt } => commands::preview::run(&prompt),
+        Commands::Repl => commands::repl::run(),
         Commands::HookUserPromptSubmit => commands::hooks::hook_user_prompt_submit(),
         Commands::HookSessionStart => commands::hooks::hook_session_start(),
         Commands::HookStop => commands::hooks::hook_stop(),
-        Commands::Report => commands::report::run(),
+        Commands::HookPreToolUse => commands::hooks::hook_pre_tool_use(),
+        Commands::HookPreCompact => commands::hooks::hook_pre_compact(),
+        Commands::Report {
+            export,
+            merge,
+            session,
+            since,
+            filter,
+        } => {
+            let query = commands::history::parse_query(since.as_deref(), filter.as_deref())?;
+            match (export, merge, session) {
+                (Some(file), _, _) => commands::report::run_export(&file, &query),
+                (None, Some(dir), _) => commands::report::run_merge(&dir),
+                (None, None, Some(id)) => commands::report::run_session(&id),
+                (None, None, None) => commands::report::run(&query),
+            }
+        }
         Commands::Diagnostic => commands::diagnostic::run(),
-        Commands::Benchmark => commands::benchmark::run(),
-        Commands::Compress => commands::compress::run(),
+        Commands::Benchmark {
+            suite,
+            json,
+            against,
+            hook,
+            iterations,
+            threshold,
+        } => match suite {
+            Some(file) => commands::suite::run(file.as_deref(), json, against.as_deref()),
+            None if hook => commands::hook_bench::run(iterations, threshold),
+            None => commands::benchmark::run(),
+        },
+        Commands::Eval { file, k, json } => commands::eval::run(file.as_deref(), k, json),
+        Commands::Compress { action } => match action {
+            None => commands::compress::run(),
+            Some(CompressAction::Query { text, limit, json }) => {
+                commands::compress::run_query(&text, limit, json)
+            }
+            Some(CompressAction::Show { id, json }) => commands::compress::run_show(&id, json),
+            Some(CompressAction::Timeline { id, days, json }) => {
+                commands::compress::run_timeline(&id, days, json)
+            }
+        },
         Commands::Graph => commands::graph::run(),
-        Commands::History { stats } => commands::history::run(stats),
+        Commands::History {
+            stats,
+            file,
+            since,
+            min_waste,
+            session,
+            filter,
+            limit,
+        } => {
+            let mut query = commands::history::parse_query(since.as_deref(), filter.as_deref())?;
+            query.file = file;
+            query.min_waste = min_waste;
+            query.session = session;
+            query.limit = limit;
+            commands::history::run(stats, query)
+        }
         Commands::Plugins { action } => match action {
             Some(PluginAction::List) | None => commands::plugins::run_list(),
             Some(PluginAction::Enable { name }) => commands::plugins::run_enable(&name),
             Some(PluginAction::Disable { name }) => commands::plugins::run_disable(&name),
         },
+        Commands::Remap { from, to } => commands::remap::run(from.as_deref(), to.as_deref()),
+        Commands::Migrate => commands::migrate::run(),
+        Commands::Repair => commands::repair::run(),
+        Commands::Rebuild { dry_run } => commands::rebuild::run(dry_run),
+        Commands::Learn { action } => match action {
+            LearnAction::Rules { action } => match action {
+                Some(RuleAction::List) | None => commands::learn::run_rules_list(),
+                Some(RuleAction::Set { keyword, file }) => {
+                    commands::learn::run_rules_set(&keyword, &file)
+                }
+                Some(RuleAction::Remove { keyword }) => commands::learn::run_rules_remove(&keyword),
+            },
+            LearnAction::Coactivate { pairs } => commands::learn::run_coactivate(&pairs),
+            LearnAction::Synonyms => commands::learn::run_synonyms(),
+        },
+        Commands::Remember { note, files } => commands::remember::run(&note, &files),
+        Commands::Search {
+            query,
+            types,
+            paths,
+            since,
+            limit,
+            fusion,
+        } => commands::search::run(
+            &query,
+            &types,
+            &paths,
+            since.as_deref(),
+            limit,
+            fusion.as_deref(),
+        ),
+        Commands::Tune { min_turns, yes } => commands::tune::run(min_turns, yes),
+        Commands::Xref { symbol } => commands::xref::run(&symbol),
+        Commands::Config { action } => match action {
+            ConfigAction::Check => commands::config::run_check(),
+            ConfigAction::Schema => commands::config::run_schema(),
+            ConfigAction::Edit => commands::config::run_edit(),
+            ConfigAction::ShowDecay => commands::config::run_show_decay(),
+            ConfigAction::ShowDefaults => commands::config::run_show_defaults(),
+            ConfigAction::Conflicts => commands::config::run_conflicts(),
+        },
+        Commands::Serve => commands::serve::run(),
+        Commands::Export { action } => match action {
+            ExportAction::Heatmap {
+                format,
+                days,
+                output,
+            } => commands::export::run_heatmap(&format, days, output.as_deref()),
+        },
+        Commands::Profile { action } => match action {
+            ProfileAction::Use { name } => commands::profile::run_use(&name),
+            ProfileAction::List => commands::profile::run_list(),
+            ProfileAction::Clear => commands::profile::run_clear(),
+        },
+        Commands::Stash { action } => match action {
+            StashAction::Save { name, plugins } => commands::stash::run_save(&name, plugins),
+            StashAction::Pop { name } => commands::stash::run_pop(&name),
+            StashAction::List => commands::stash::run_list(),
+        },
+        Commands::Sync { action } => match action {
+            SyncAction::Push { force, project } => {
+                commands::sync::run(true, force, project.as_deref())
+            }
+            SyncAction::Pull { force, project } => {
+                commands::sync::run(false, force, project.as_deref())
+            }
+        },
+        Commands::Completions { shell } => commands::completions::run(shell),
     }
 }
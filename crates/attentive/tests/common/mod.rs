@@ -1,4 +1,4 @@
-use attentive_core::{AttentionState, Config};
+use attentive_core::{AttentionState, Config, InstructionRefs};
 use std::collections::HashMap;
 
 pub fn sample_config() -> Config {
@@ -13,8 +13,44 @@ pub fn sample_config() -> Config {
         pinned_floor_boost: 0.5,
         demoted_penalty: 0.3,
         co_activation: HashMap::new(),
+        co_activation_weights: HashMap::new(),
         pinned_files: vec![],
         demoted_files: vec![],
+        focus_files: vec![],
+        exclude_files: vec![],
+        default_file_rules: true,
+        external_files: vec![],
+        latency_budget_ms: 50,
+        hook_deadline_ms: 300,
+        provenance_tags: false,
+        edit_floor_turns: 3,
+        planning_decay_scale: 0.0,
+        delta_context: false,
+        pair_tests: true,
+        activate_mentioned: false,
+        instruction_floor: 0.3,
+        active_file_boost: 0.5,
+        active_buffer_boost: 0.8,
+        score_normalization: Default::default(),
+        learner_half_life: 500,
+        context_scale: 1.0,
+        profiles: attentive_core::builtin_profiles(),
+        profile: None,
+        task_profiles: Default::default(),
+        plugin_toggles: Default::default(),
+        plugin_budgets: Default::default(),
+        compression: Config::new().compression,
+        sync: None,
+        branch_state: Default::default(),
+        search_fusion: Default::default(),
+        redact_secrets: true,
+        redact_patterns: vec![],
+        stale_observations: Default::default(),
+        templates: Default::default(),
+        max_context_chars: 20_000,
+        max_toc_entries: 12,
+        model: None,
+        model_profiles: attentive_core::builtin_model_profiles(),
     }
 }
 
@@ -23,5 +59,8 @@ pub fn sample_state() -> AttentionState {
         scores: HashMap::new(),
         consecutive_turns: HashMap::new(),
         turn_count: 0,
+        recently_edited: HashMap::new(),
+        instruction_refs: InstructionRefs::default(),
+        planning_turn: false,
     }
 }